@@ -0,0 +1,694 @@
+// Reading and writing tar archives in the POSIX ustar format, with pax
+// extended headers for names, link targets and numbers that do not fit.
+// Both directions stream: member data passes through in blocks and is
+// never held in memory whole.
+
+use std::io::{self, Read, Write};
+
+pub const BLOCK_SIZE: usize = 512;
+
+// Archives are padded to a whole record of this many blocks, as tar does
+const RECORD_BLOCKS: u64 = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    File,
+    HardLink,
+    Symlink,
+    CharDevice,
+    BlockDevice,
+    Directory,
+    Fifo,
+    Other(u8),
+}
+
+impl Kind {
+    fn flag(self) -> u8 {
+        match self {
+            Kind::File => b'0',
+            Kind::HardLink => b'1',
+            Kind::Symlink => b'2',
+            Kind::CharDevice => b'3',
+            Kind::BlockDevice => b'4',
+            Kind::Directory => b'5',
+            Kind::Fifo => b'6',
+            Kind::Other(flag) => flag,
+        }
+    }
+
+    fn from_flag(flag: u8) -> Kind {
+        match flag {
+            // Old archives mark regular files with NUL, and '7' is a
+            // contiguous file, which is an ordinary file everywhere else
+            b'0' | b'\0' | b'7' => Kind::File,
+            b'1' => Kind::HardLink,
+            b'2' => Kind::Symlink,
+            b'3' => Kind::CharDevice,
+            b'4' => Kind::BlockDevice,
+            b'5' => Kind::Directory,
+            b'6' => Kind::Fifo,
+            flag => Kind::Other(flag),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Header {
+    pub name: String,
+    pub kind: Kind,
+    // Permission bits, including set-id and sticky
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    // Bytes of data following the header; 0 for anything but files
+    pub size: u64,
+    pub mtime: i64,
+    // Target of a symbolic or hard link
+    pub link_name: String,
+    pub user_name: String,
+    pub group_name: String,
+    pub device_major: u32,
+    pub device_minor: u32,
+}
+
+impl Header {
+    pub fn new(name: &str, kind: Kind) -> Self {
+        Header {
+            name: name.to_string(),
+            kind,
+            mode: 0o644,
+            uid: 0,
+            gid: 0,
+            size: 0,
+            mtime: 0,
+            link_name: String::new(),
+            user_name: String::new(),
+            group_name: String::new(),
+            device_major: 0,
+            device_minor: 0,
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// Field offsets and widths in a ustar header block
+const NAME: (usize, usize) = (0, 100);
+const MODE: (usize, usize) = (100, 8);
+const UID: (usize, usize) = (108, 8);
+const GID: (usize, usize) = (116, 8);
+const SIZE: (usize, usize) = (124, 12);
+const MTIME: (usize, usize) = (136, 12);
+const CHECKSUM: (usize, usize) = (148, 8);
+const TYPE_FLAG: usize = 156;
+const LINK_NAME: (usize, usize) = (157, 100);
+const MAGIC: (usize, usize) = (257, 8);
+const USER_NAME: (usize, usize) = (265, 32);
+const GROUP_NAME: (usize, usize) = (297, 32);
+const DEVICE_MAJOR: (usize, usize) = (329, 8);
+const DEVICE_MINOR: (usize, usize) = (337, 8);
+const PREFIX: (usize, usize) = (345, 155);
+
+const USTAR_MAGIC: &[u8; 8] = b"ustar\x0000";
+
+fn field(block: &[u8; BLOCK_SIZE], (offset, width): (usize, usize)) -> &[u8] {
+    &block[offset..offset + width]
+}
+
+// Text up to the first NUL
+fn text_field(block: &[u8; BLOCK_SIZE], place: (usize, usize)) -> String {
+    let bytes = field(block, place);
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+// Octal digits padded with spaces or NULs, or the base-256 form GNU tar
+// uses for numbers too big for octal
+fn number_field(block: &[u8; BLOCK_SIZE], place: (usize, usize)) -> io::Result<u64> {
+    let bytes = field(block, place);
+    if bytes[0] & 0x80 != 0 {
+        let mut value: u64 = (bytes[0] & 0x7f) as u64;
+        for &byte in &bytes[1..] {
+            value = value
+                .checked_mul(256)
+                .ok_or_else(|| invalid("number out of range in archive header"))?
+                | byte as u64;
+        }
+        return Ok(value);
+    }
+    let text = String::from_utf8_lossy(bytes);
+    let digits = text.trim_matches(|c| c == ' ' || c == '\0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8).map_err(|_| invalid("invalid number in archive header"))
+}
+
+fn checksum(block: &[u8; BLOCK_SIZE]) -> u64 {
+    let (offset, width) = CHECKSUM;
+    block
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            if (offset..offset + width).contains(&i) {
+                b' ' as u64
+            } else {
+                b as u64
+            }
+        })
+        .sum()
+}
+
+// Writes text that is known to fit
+fn put_text(block: &mut [u8; BLOCK_SIZE], (offset, width): (usize, usize), text: &str) {
+    let bytes = &text.as_bytes()[..text.len().min(width)];
+    block[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+// Writes a zero-padded octal number with a NUL after it; false if it needs
+// more digits than the field has
+fn put_number(block: &mut [u8; BLOCK_SIZE], (offset, width): (usize, usize), value: u64) -> bool {
+    let digits = format!("{:0width$o}", value, width = width - 1);
+    if digits.len() > width - 1 {
+        return false;
+    }
+    block[offset..offset + width - 1].copy_from_slice(digits.as_bytes());
+    block[offset + width - 1] = 0;
+    true
+}
+
+// Splits a long name at a slash into a prefix of up to 155 bytes and a
+// name of up to 100, as ustar allows
+fn split_name(name: &str) -> Option<(&str, &str)> {
+    if name.len() <= NAME.1 {
+        return Some(("", name));
+    }
+    name.char_indices()
+        .filter(|&(i, c)| c == '/' && i > 0 && i <= PREFIX.1)
+        .map(|(i, _)| (&name[..i], &name[i + 1..]))
+        .find(|(_, rest)| !rest.is_empty() && rest.len() <= NAME.1)
+}
+
+// One "LENGTH KEY=VALUE\n" record, where LENGTH counts itself
+fn pax_record(key: &str, value: &str) -> String {
+    let base = key.len() + value.len() + 3;
+    let mut length = base + base.to_string().len();
+    if length.to_string().len() != base.to_string().len() {
+        length = base + length.to_string().len();
+    }
+    format!("{} {}={}\n", length, key, value)
+}
+
+pub struct Writer<W: Write> {
+    inner: W,
+    blocks: u64,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Writer { inner, blocks: 0 }
+    }
+
+    fn write_block(&mut self, block: &[u8; BLOCK_SIZE]) -> io::Result<()> {
+        self.inner.write_all(block)?;
+        self.blocks += 1;
+        Ok(())
+    }
+
+    // Zeros up to the next block boundary after `written` bytes of data
+    fn pad(&mut self, written: u64) -> io::Result<()> {
+        let partial = (written % BLOCK_SIZE as u64) as usize;
+        if partial > 0 {
+            self.inner.write_all(&[0; BLOCK_SIZE][partial..])?;
+        }
+        self.blocks += written.div_ceil(BLOCK_SIZE as u64);
+        Ok(())
+    }
+
+    // Writes the header, then exactly header.size bytes taken from data
+    pub fn append(&mut self, header: &Header, data: &mut impl Read) -> io::Result<()> {
+        let mut block = [0u8; BLOCK_SIZE];
+        let mut pax = String::new();
+
+        match split_name(&header.name) {
+            Some((prefix, name)) => {
+                put_text(&mut block, PREFIX, prefix);
+                put_text(&mut block, NAME, name);
+            }
+            None => {
+                pax.push_str(&pax_record("path", &header.name));
+                put_text(&mut block, NAME, &header.name);
+            }
+        }
+        if header.link_name.len() > LINK_NAME.1 {
+            pax.push_str(&pax_record("linkpath", &header.link_name));
+        }
+        put_text(&mut block, LINK_NAME, &header.link_name);
+
+        put_number(&mut block, MODE, header.mode as u64 & 0o7777);
+        for (place, key, value) in [
+            (UID, "uid", header.uid as u64),
+            (GID, "gid", header.gid as u64),
+            (SIZE, "size", header.size),
+        ] {
+            if !put_number(&mut block, place, value) {
+                pax.push_str(&pax_record(key, &value.to_string()));
+            }
+        }
+        if header.mtime < 0 || !put_number(&mut block, MTIME, header.mtime as u64) {
+            pax.push_str(&pax_record("mtime", &header.mtime.to_string()));
+        }
+        put_number(&mut block, DEVICE_MAJOR, header.device_major as u64);
+        put_number(&mut block, DEVICE_MINOR, header.device_minor as u64);
+        for (place, key, value) in [
+            (USER_NAME, "uname", &header.user_name),
+            (GROUP_NAME, "gname", &header.group_name),
+        ] {
+            if value.len() > place.1 {
+                pax.push_str(&pax_record(key, value));
+            } else {
+                put_text(&mut block, place, value);
+            }
+        }
+        block[TYPE_FLAG] = header.kind.flag();
+        block[MAGIC.0..MAGIC.0 + MAGIC.1].copy_from_slice(USTAR_MAGIC);
+
+        if !pax.is_empty() {
+            self.write_pax(&header.name, pax.as_bytes())?;
+        }
+        let sum = checksum(&block);
+        block[CHECKSUM.0..CHECKSUM.0 + CHECKSUM.1]
+            .copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+        self.write_block(&block)?;
+
+        let copied = io::copy(&mut data.take(header.size), &mut self.inner)?;
+        if copied < header.size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "File shrank while being archived",
+            ));
+        }
+        self.pad(copied)
+    }
+
+    // An extended header carrying the records for the member that follows
+    fn write_pax(&mut self, name: &str, records: &[u8]) -> io::Result<()> {
+        let base = name
+            .rsplit('/')
+            .find(|part| !part.is_empty())
+            .unwrap_or(name);
+        let mut header = Header::new(&format!("PaxHeaders/{}", base), Kind::Other(b'x'));
+        header.name.truncate(NAME.1);
+        while !header.name.is_char_boundary(header.name.len()) {
+            header.name.pop();
+        }
+        header.size = records.len() as u64;
+        self.append(&header, &mut &records[..])
+    }
+
+    // Ends the archive with two zero blocks and pads it to a whole record
+    pub fn finish(mut self) -> io::Result<W> {
+        let zeros = [0u8; BLOCK_SIZE];
+        self.write_block(&zeros)?;
+        self.write_block(&zeros)?;
+        while !self.blocks.is_multiple_of(RECORD_BLOCKS) {
+            self.write_block(&zeros)?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+// Reads members one at a time: next_header moves to the next member and
+// the Reader itself then reads that member's data
+pub struct Reader<R: Read> {
+    inner: R,
+    // Data bytes of the current member not yet read, and the padding after
+    remaining: u64,
+    padding: u64,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Reader {
+            inner,
+            remaining: 0,
+            padding: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    // Fills the block; false if the input ended before any of it
+    fn read_block(&mut self, block: &mut [u8; BLOCK_SIZE]) -> io::Result<bool> {
+        let mut filled = 0;
+        while filled < BLOCK_SIZE {
+            match self.inner.read(&mut block[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Unexpected EOF in archive",
+                    ))
+                }
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(true)
+    }
+
+    // Skips whatever is left of the current member
+    fn skip_rest(&mut self) -> io::Result<()> {
+        let skip = self.remaining + self.padding;
+        let skipped = io::copy(&mut (&mut self.inner).take(skip), &mut io::sink())?;
+        if skipped < skip {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Unexpected EOF in archive",
+            ));
+        }
+        self.remaining = 0;
+        self.padding = 0;
+        Ok(())
+    }
+
+    fn start_data(&mut self, size: u64) {
+        self.remaining = size;
+        self.padding = size.next_multiple_of(BLOCK_SIZE as u64) - size;
+    }
+
+    fn read_extension(&mut self, size: u64) -> io::Result<Vec<u8>> {
+        self.start_data(size);
+        let mut data = Vec::new();
+        self.read_to_end(&mut data)?;
+        self.skip_rest()?;
+        Ok(data)
+    }
+
+    // Moves to the next member, skipping any data of the current one that
+    // was not read; None at the end of the archive
+    pub fn next_header(&mut self) -> io::Result<Option<Header>> {
+        self.skip_rest()?;
+        let mut pax: Vec<(String, String)> = Vec::new();
+        let mut long_name = None;
+        let mut long_link = None;
+        let mut block = [0u8; BLOCK_SIZE];
+
+        loop {
+            if !self.read_block(&mut block)? || block.iter().all(|&b| b == 0) {
+                return Ok(None);
+            }
+            if number_field(&block, CHECKSUM)? != checksum(&block) {
+                return Err(invalid("This does not look like a tar archive"));
+            }
+
+            let size = number_field(&block, SIZE)?;
+            match block[TYPE_FLAG] {
+                b'x' => {
+                    let data = self.read_extension(size)?;
+                    pax.extend(parse_pax(&data)?);
+                }
+                // Global pax headers and GNU volume labels carry nothing
+                // the members need
+                b'g' | b'V' => {
+                    self.read_extension(size)?;
+                }
+                b'L' => long_name = Some(extension_text(&self.read_extension(size)?)),
+                b'K' => long_link = Some(extension_text(&self.read_extension(size)?)),
+                _ => break,
+            }
+        }
+
+        let mut header = Header::new("", Kind::from_flag(block[TYPE_FLAG]));
+        header.name = text_field(&block, NAME);
+        if field(&block, MAGIC) == USTAR_MAGIC {
+            let prefix = text_field(&block, PREFIX);
+            if !prefix.is_empty() {
+                header.name = format!("{}/{}", prefix, header.name);
+            }
+        }
+        header.mode = number_field(&block, MODE)? as u32;
+        header.uid = number_field(&block, UID)? as u32;
+        header.gid = number_field(&block, GID)? as u32;
+        header.size = number_field(&block, SIZE)?;
+        header.mtime = number_field(&block, MTIME)? as i64;
+        header.link_name = text_field(&block, LINK_NAME);
+        header.user_name = text_field(&block, USER_NAME);
+        header.group_name = text_field(&block, GROUP_NAME);
+        header.device_major = number_field(&block, DEVICE_MAJOR)? as u32;
+        header.device_minor = number_field(&block, DEVICE_MINOR)? as u32;
+        if let Some(name) = long_name {
+            header.name = name;
+        }
+        if let Some(link) = long_link {
+            header.link_name = link;
+        }
+
+        for (key, value) in pax {
+            let number = || {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| invalid("invalid pax record"))
+            };
+            match key.as_str() {
+                "path" => header.name = value,
+                "linkpath" => header.link_name = value,
+                "uname" => header.user_name = value,
+                "gname" => header.group_name = value,
+                "uid" => header.uid = number()? as u32,
+                "gid" => header.gid = number()? as u32,
+                "size" => header.size = number()?,
+                "mtime" => {
+                    // Fractions of a second are dropped
+                    let whole = value.split('.').next().unwrap_or("");
+                    header.mtime = whole.parse().map_err(|_| invalid("invalid pax record"))?;
+                }
+                _ => {}
+            }
+        }
+
+        // Links and directories carry no data whatever their size says
+        let has_data = !matches!(
+            header.kind,
+            Kind::HardLink | Kind::Symlink | Kind::Directory | Kind::Fifo
+        );
+        self.start_data(if has_data { header.size } else { 0 });
+        Ok(Some(header))
+    }
+}
+
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        let want = buffer.len().min(self.remaining as usize);
+        let read = self.inner.read(&mut buffer[..want])?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Unexpected EOF in archive",
+            ));
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+// GNU long names are NUL-terminated
+fn extension_text(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+fn parse_pax(mut data: &[u8]) -> io::Result<Vec<(String, String)>> {
+    let mut records = Vec::new();
+    while !data.is_empty() {
+        let space = data
+            .iter()
+            .position(|&b| b == b' ')
+            .ok_or_else(|| invalid("invalid pax record"))?;
+        let length: usize = std::str::from_utf8(&data[..space])
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .filter(|&length| length > space + 1 && length <= data.len())
+            .ok_or_else(|| invalid("invalid pax record"))?;
+        let record = &data[space + 1..length];
+        let record = record.strip_suffix(b"\n").unwrap_or(record);
+        let equals = record
+            .iter()
+            .position(|&b| b == b'=')
+            .ok_or_else(|| invalid("invalid pax record"))?;
+        records.push((
+            String::from_utf8_lossy(&record[..equals]).into_owned(),
+            String::from_utf8_lossy(&record[equals + 1..]).into_owned(),
+        ));
+        data = &data[length..];
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive(members: &[(Header, &[u8])]) -> Vec<u8> {
+        let mut writer = Writer::new(Vec::new());
+        for (header, data) in members {
+            writer.append(header, &mut &data[..]).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn members(data: &[u8]) -> Vec<(Header, Vec<u8>)> {
+        let mut reader = Reader::new(data);
+        let mut members = Vec::new();
+        while let Some(header) = reader.next_header().unwrap() {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).unwrap();
+            members.push((header, data));
+        }
+        members
+    }
+
+    fn file(name: &str, data: &[u8]) -> Header {
+        let mut header = Header::new(name, Kind::File);
+        header.size = data.len() as u64;
+        header
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut directory = Header::new("dir/", Kind::Directory);
+        directory.mode = 0o2755;
+        directory.user_name = String::from("alice");
+        directory.group_name = String::from("staff");
+        directory.uid = 1000;
+        directory.gid = 50;
+        directory.mtime = 1_700_000_000;
+        let mut link = Header::new("dir/link", Kind::Symlink);
+        link.link_name = String::from("a.txt");
+        let mut device = Header::new("null", Kind::CharDevice);
+        device.device_major = 1;
+        device.device_minor = 3;
+        let big = vec![7u8; 1500];
+
+        let data = archive(&[
+            (directory, b""),
+            (file("dir/a.txt", b"hello\n"), b"hello\n"),
+            (link, b""),
+            (file("dir/big", &big), &big),
+            (device, b""),
+        ]);
+        assert_eq!(data.len() % (BLOCK_SIZE * RECORD_BLOCKS as usize), 0);
+
+        let read = members(&data);
+        assert_eq!(read.len(), 5);
+        let (directory, _) = &read[0];
+        assert_eq!(directory.name, "dir/");
+        assert_eq!(directory.kind, Kind::Directory);
+        assert_eq!(
+            (directory.mode, directory.uid, directory.gid),
+            (0o2755, 1000, 50)
+        );
+        assert_eq!(
+            (directory.user_name.as_str(), directory.group_name.as_str()),
+            ("alice", "staff")
+        );
+        assert_eq!(directory.mtime, 1_700_000_000);
+        assert_eq!(read[1].0.name, "dir/a.txt");
+        assert_eq!(read[1].1, b"hello\n");
+        assert_eq!(read[2].0.kind, Kind::Symlink);
+        assert_eq!(read[2].0.link_name, "a.txt");
+        assert_eq!(read[3].1, big);
+        assert_eq!((read[4].0.device_major, read[4].0.device_minor), (1, 3));
+    }
+
+    #[test]
+    fn long_names_and_numbers() {
+        // Split into prefix and name, and too long even for that
+        let split = format!("{}/{}", "d".repeat(120), "f".repeat(90));
+        let long = "x".repeat(300);
+        let mut link = Header::new("link", Kind::HardLink);
+        link.link_name = "t".repeat(150);
+        let mut numbers = file("numbers", b"");
+        numbers.uid = 0o7777777 + 1;
+        numbers.mtime = -86400;
+
+        let data = archive(&[
+            (file(&split, b"a"), b"a"),
+            (file(&long, b"b"), b"b"),
+            (link.clone(), b""),
+            (numbers, b""),
+        ]);
+        let read = members(&data);
+        assert_eq!(read.len(), 4);
+        assert_eq!(read[0].0.name, split);
+        assert_eq!(read[1].0.name, long);
+        assert_eq!(read[1].1, b"b");
+        assert_eq!(read[2].0.link_name, link.link_name);
+        assert_eq!(read[3].0.uid, 0o7777777 + 1);
+        assert_eq!(read[3].0.mtime, -86400);
+    }
+
+    #[test]
+    fn skips_unread_data() {
+        let data = archive(&[(file("a", b"12345"), b"12345"), (file("b", b"6"), b"6")]);
+        let mut reader = Reader::new(&data[..]);
+        assert_eq!(reader.next_header().unwrap().unwrap().name, "a");
+        assert_eq!(reader.next_header().unwrap().unwrap().name, "b");
+        assert!(reader.next_header().unwrap().is_none());
+    }
+
+    #[test]
+    fn pax_records() {
+        assert_eq!(pax_record("path", "abc"), "12 path=abc\n");
+        // The length itself pushes the record to the next number of digits
+        let record = pax_record("k", &"v".repeat(94));
+        assert_eq!(record.len(), 101);
+        assert!(record.starts_with("101 "));
+        assert_eq!(
+            parse_pax(b"12 path=abc\n8 uid=7\n").unwrap(),
+            vec![
+                (String::from("path"), String::from("abc")),
+                (String::from("uid"), String::from("7"))
+            ]
+        );
+        assert!(parse_pax(b"99 path=abc\n").is_err());
+        assert!(parse_pax(b"12 pathabc\n").is_err());
+    }
+
+    #[test]
+    fn numbers_in_headers() {
+        let mut block = [0u8; BLOCK_SIZE];
+        assert!(put_number(&mut block, UID, 0o7777777));
+        assert!(!put_number(&mut block, UID, 0o7777777 + 1));
+        assert_eq!(number_field(&block, UID).unwrap(), 0o7777777);
+
+        // Octal padded with spaces, and GNU's base-256
+        block[SIZE.0..SIZE.0 + SIZE.1].copy_from_slice(b"     17 \0\0\0\0");
+        assert_eq!(number_field(&block, SIZE).unwrap(), 0o17);
+        block[SIZE.0..SIZE.0 + SIZE.1].copy_from_slice(&[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0]);
+        assert_eq!(number_field(&block, SIZE).unwrap(), 256);
+        block[SIZE.0..SIZE.0 + SIZE.1].copy_from_slice(b"12x45678901\0");
+        assert!(number_field(&block, SIZE).is_err());
+    }
+
+    #[test]
+    fn rejects_damaged_archives() {
+        let mut data = archive(&[(file("a", b"12345"), b"12345")]);
+        data[0] = b'b';
+        assert!(Reader::new(&data[..]).next_header().is_err());
+
+        let data = archive(&[(file("a", b"12345"), b"12345")]);
+        let mut reader = Reader::new(&data[..BLOCK_SIZE + 2]);
+        reader.next_header().unwrap();
+        assert!(reader.read_to_end(&mut Vec::new()).is_err());
+    }
+}
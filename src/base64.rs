@@ -0,0 +1,297 @@
+use cmds_lite::error;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Input is read in blocks of this many bytes, a multiple of three so that
+// only the last block can end in a partial group
+const BLOCK_SIZE: usize = 3 * 1024 * 32;
+
+pub struct Base64Options {
+    pub decode: bool,
+    // -w: encoded line length; 0 means one unbroken line
+    pub wrap: usize,
+    // -i: skip characters outside the alphabet when decoding
+    pub ignore_garbage: bool,
+}
+
+impl Default for Base64Options {
+    fn default() -> Self {
+        Base64Options {
+            decode: false,
+            wrap: 76,
+            ignore_garbage: false,
+        }
+    }
+}
+
+// Bad input is reported only after the data decoded before it has been
+// written
+pub enum Failure {
+    Read(io::Error),
+    Write(io::Error),
+    InvalidInput,
+    Other(String),
+}
+
+const fn decode_table() -> [u8; 256] {
+    let mut table = [0xff; 256];
+    let mut i = 0;
+    while i < ALPHABET.len() {
+        table[ALPHABET[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+const DECODE: [u8; 256] = decode_table();
+
+fn value(symbol: u8) -> Option<u8> {
+    match DECODE[symbol as usize] {
+        0xff => None,
+        v => Some(v),
+    }
+}
+
+// Encodes whole groups of three bytes, or a final partial group with padding
+fn encode_block(block: &[u8], encoded: &mut Vec<u8>) {
+    for group in block.chunks(3) {
+        let b = [
+            group[0],
+            *group.get(1).unwrap_or(&0),
+            *group.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if i <= group.len() {
+                encoded.push(ALPHABET[(n >> shift & 0x3f) as usize]);
+            } else {
+                encoded.push(b'=');
+            }
+        }
+    }
+}
+
+struct Base64Command<'a> {
+    options: &'a Base64Options,
+    // Encoded characters on the current output line
+    column: usize,
+}
+
+impl<'a> Base64Command<'a> {
+    fn write_wrapped(&mut self, mut encoded: &[u8], out: &mut impl Write) -> io::Result<()> {
+        if self.options.wrap == 0 {
+            return out.write_all(encoded);
+        }
+        while !encoded.is_empty() {
+            let take = (self.options.wrap - self.column).min(encoded.len());
+            out.write_all(&encoded[..take])?;
+            self.column += take;
+            encoded = &encoded[take..];
+            if self.column == self.options.wrap {
+                out.write_all(b"\n")?;
+                self.column = 0;
+            }
+        }
+        Ok(())
+    }
+
+    fn encode(&mut self, input: &mut impl Read, out: &mut impl Write) -> Result<(), Failure> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        let mut encoded = Vec::with_capacity(BLOCK_SIZE / 3 * 4);
+        loop {
+            let read = fill(input, &mut block).map_err(Failure::Read)?;
+            encoded.clear();
+            encode_block(&block[..read], &mut encoded);
+            self.write_wrapped(&encoded, out).map_err(Failure::Write)?;
+            if read < block.len() {
+                break;
+            }
+        }
+        if self.column > 0 {
+            out.write_all(b"\n").map_err(Failure::Write)?;
+        }
+        Ok(())
+    }
+
+    // Decodes four characters at a time; newlines are always skipped. A
+    // group that is cut short still yields the bytes it fully holds.
+    fn decode(&mut self, input: &mut impl Read, out: &mut impl Write) -> Result<(), Failure> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        let mut decoded = Vec::with_capacity(BLOCK_SIZE);
+        let mut quad = Vec::with_capacity(4);
+        loop {
+            let read = fill(input, &mut block).map_err(Failure::Read)?;
+            decoded.clear();
+            let mut valid = true;
+            for &symbol in &block[..read] {
+                if symbol == b'\n'
+                    || (self.options.ignore_garbage && symbol != b'=' && value(symbol).is_none())
+                {
+                    continue;
+                }
+                quad.push(symbol);
+                if quad.len() == 4 {
+                    valid = decode_quad(&quad, &mut decoded);
+                    quad.clear();
+                    if !valid {
+                        break;
+                    }
+                }
+            }
+            if valid && read < block.len() && !quad.is_empty() {
+                valid = decode_quad(&quad, &mut decoded);
+            }
+            out.write_all(&decoded).map_err(Failure::Write)?;
+            if !valid {
+                out.flush().map_err(Failure::Write)?;
+                return Err(Failure::InvalidInput);
+            }
+            if read < block.len() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn run(&mut self, input: &mut impl Read, out: &mut impl Write) -> Result<(), Failure> {
+        if self.options.decode {
+            self.decode(input, out)?;
+        } else {
+            self.encode(input, out)?;
+        }
+        out.flush().map_err(Failure::Write)
+    }
+}
+
+// Decodes one group, which is only complete with four characters; false
+// if it is not valid, after decoding the bytes it does hold
+fn decode_quad(quad: &[u8], decoded: &mut Vec<u8>) -> bool {
+    let mut values = [0u8; 4];
+    let mut symbols: usize = 0;
+    for (i, &symbol) in quad.iter().enumerate() {
+        match value(symbol) {
+            Some(v) => {
+                values[i] = v;
+                symbols += 1;
+            }
+            None => break,
+        }
+    }
+    let n = (values[0] as u32) << 18
+        | (values[1] as u32) << 12
+        | (values[2] as u32) << 6
+        | values[3] as u32;
+    let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+    // Two symbols make one byte, three make two and four make three
+    let whole = symbols.saturating_sub(1);
+    decoded.extend_from_slice(&bytes[..whole]);
+
+    match (quad.len(), symbols) {
+        (4, 4) => true,
+        (4, 3) => quad[3] == b'=',
+        (4, 2) => quad[2] == b'=' && quad[3] == b'=',
+        _ => false,
+    }
+}
+
+// Reads until the buffer is full or the input ends
+fn fill(input: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match input.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("base64: {}", message);
+    std::process::exit(1);
+}
+
+pub fn run(file: &str, options: &Base64Options) -> Result<(), Failure> {
+    let mut command = Base64Command { options, column: 0 };
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if file == "-" {
+        command.run(&mut io::stdin().lock(), &mut out)
+    } else {
+        let mut input = File::open(file)
+            .map_err(|e| Failure::Other(format!("{}: {}", file, error::message(&e))))?;
+        command.run(&mut input, &mut out)
+    }
+}
+
+fn parse_wrap(value: &str) -> usize {
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("invalid wrap size: '{}'", value)))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = Base64Options::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--decode" => options.decode = true,
+            "--ignore-garbage" => options.ignore_garbage = true,
+            _ if arg.starts_with("--wrap=") => options.wrap = parse_wrap(&arg["--wrap=".len()..]),
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'd' => options.decode = true,
+                        'i' => options.ignore_garbage = true,
+                        'w' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail("option requires an argument -- 'w'");
+                            };
+                            options.wrap = parse_wrap(&value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if files.len() > 1 {
+        fail(&format!("extra operand '{}'", files[1]));
+    }
+    let file = files.first().map_or("-", |f| f.as_str());
+
+    match run(file, &options) {
+        Ok(()) => {}
+        Err(Failure::Read(e)) => fail(&format!("read error: {}", error::message(&e))),
+        Err(Failure::Write(e)) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(1),
+        Err(Failure::Write(e)) => fail(&format!("write error: {}", error::message(&e))),
+        Err(Failure::InvalidInput) => fail("invalid input"),
+        Err(Failure::Other(message)) => fail(&message),
+    }
+}
@@ -0,0 +1,108 @@
+use cmds_lite::error;
+use cmds_lite::pathname;
+use std::env;
+use std::io::{self, Write};
+
+#[derive(Default)]
+pub struct BasenameOptions {
+    pub suffix: Option<String>,
+    // Separate outputs with NUL instead of newline
+    pub zero: bool,
+}
+
+pub fn run(names: &[String], options: &BasenameOptions) -> io::Result<()> {
+    let terminator = if options.zero { b'\0' } else { b'\n' };
+    let mut stdout = io::stdout().lock();
+    for name in names {
+        let mut base = pathname::base_name(name);
+        if let Some(suffix) = &options.suffix {
+            base = pathname::strip_suffix(base, suffix);
+        }
+        stdout.write_all(base.as_bytes())?;
+        stdout.write_all(&[terminator])?;
+    }
+    stdout.flush()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = BasenameOptions::default();
+    let mut names = Vec::new();
+    let mut multiple = false;
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            names.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--multiple" => multiple = true,
+            "--zero" => options.zero = true,
+            _ if arg.starts_with("--suffix=") => {
+                options.suffix = Some(arg["--suffix=".len()..].to_string());
+                multiple = true;
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("basename: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'a' => multiple = true,
+                        'z' => options.zero = true,
+                        's' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + flag.len_utf8()..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                eprintln!("basename: option requires an argument -- 's'");
+                                std::process::exit(1);
+                            };
+                            // -s implies -a
+                            options.suffix = Some(value);
+                            multiple = true;
+                            break;
+                        }
+                        _ => {
+                            eprintln!("basename: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if names.is_empty() {
+        eprintln!("basename: missing operand");
+        std::process::exit(1);
+    }
+
+    // Without -a the second operand is a suffix to remove
+    if !multiple {
+        if names.len() > 2 {
+            eprintln!("basename: extra operand '{}'", names[2]);
+            std::process::exit(1);
+        }
+        if names.len() == 2 {
+            options.suffix = names.pop();
+        }
+    }
+
+    if let Err(e) = run(&names, &options) {
+        eprintln!("basename: write error: {}", error::message(&e));
+        std::process::exit(1);
+    }
+}
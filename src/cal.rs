@@ -0,0 +1,250 @@
+use cmds_lite::color::{Palette, When};
+use cmds_lite::datetime::{self, Timestamp};
+use cmds_lite::error;
+use std::env;
+use std::io::{self, BufWriter, Write};
+
+// Width of one month: seven two-digit days with a space between them
+const MONTH_WIDTH: usize = 20;
+const GAP: &str = "  ";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Span {
+    // The month alone
+    One,
+    // -3: the months before and after as well
+    Three,
+    // -y: the whole year
+    Year,
+}
+
+pub struct CalOptions {
+    pub span: Span,
+    // -m: weeks start on Monday instead of Sunday
+    pub monday_first: bool,
+    pub color: When,
+}
+
+impl Default for CalOptions {
+    fn default() -> Self {
+        CalOptions {
+            span: Span::One,
+            monday_first: false,
+            color: When::Auto,
+        }
+    }
+}
+
+// Renders months, highlighting the day `today` when colors are on
+struct Calendar<'a> {
+    options: &'a CalOptions,
+    today: Option<(i64, u32, u32)>,
+    palette: Option<Palette>,
+}
+
+fn center(text: &str, width: usize) -> String {
+    let left = width.saturating_sub(text.len()) / 2;
+    format!("{:left$}{:<rest$}", "", text, rest = width - left)
+}
+
+impl<'a> Calendar<'a> {
+    // The lines of one month, each MONTH_WIDTH columns wide: its title, the
+    // weekday names and six weeks, blank where the month has no days
+    fn month(&self, year: i64, month: u32, with_year: bool) -> Vec<String> {
+        let name = datetime::MONTHS[month as usize - 1];
+        let title = if with_year {
+            format!("{} {}", name, year)
+        } else {
+            name.to_string()
+        };
+        let first_weekday = if self.options.monday_first { 1 } else { 0 };
+        let names: Vec<&str> = (0..7)
+            .map(|i| &datetime::WEEKDAYS[(i + first_weekday) % 7][..2])
+            .collect();
+        let mut lines = vec![center(&title, MONTH_WIDTH), names.join(" ")];
+
+        let first = datetime::days_from_civil(year, month, 1);
+        // Blank cells before the 1st: 1970-01-01 was a Thursday
+        let lead = ((first + 4).rem_euclid(7) as usize + 7 - first_weekday) % 7;
+        let days = datetime::days_in_month(year, month) as usize;
+        for week in 0..6 {
+            let cells: Vec<String> = (0..7)
+                .map(|column| {
+                    let cell: usize = week * 7 + column;
+                    if cell < lead || cell - lead >= days {
+                        return String::from("  ");
+                    }
+                    let day = cell - lead + 1;
+                    let cell = format!("{:2}", day);
+                    match &self.palette {
+                        Some(palette) if self.today == Some((year, month, day as u32)) => {
+                            format!("{}{}{}", palette.start("today"), cell, palette.end())
+                        }
+                        _ => cell,
+                    }
+                })
+                .collect();
+            lines.push(cells.join(" "));
+        }
+        lines
+    }
+
+    // Months side by side
+    fn row(&self, months: &[(i64, u32)], with_year: bool, out: &mut impl Write) -> io::Result<()> {
+        let blocks: Vec<Vec<String>> = months
+            .iter()
+            .map(|&(year, month)| self.month(year, month, with_year))
+            .collect();
+        for line in 0..blocks[0].len() {
+            let joined: Vec<&str> = blocks.iter().map(|block| block[line].as_str()).collect();
+            writeln!(out, "{}", joined.join(GAP).trim_end())?;
+        }
+        Ok(())
+    }
+
+    fn print(&self, year: i64, month: u32, out: &mut impl Write) -> io::Result<()> {
+        match self.options.span {
+            Span::One => self.row(&[(year, month)], true, out),
+            Span::Three => {
+                let total = year * 12 + month as i64 - 1;
+                let months: Vec<(i64, u32)> = (total - 1..=total + 1)
+                    .map(|m| (m.div_euclid(12), m.rem_euclid(12) as u32 + 1))
+                    .collect();
+                self.row(&months, true, out)
+            }
+            Span::Year => {
+                let width = 3 * MONTH_WIDTH + 2 * GAP.len();
+                writeln!(out, "{}", center(&year.to_string(), width).trim_end())?;
+                for quarter in 0..4 {
+                    let months: Vec<(i64, u32)> =
+                        (1..=3).map(|i| (year, quarter * 3 + i)).collect();
+                    self.row(&months, false, out)?;
+                    if quarter < 3 {
+                        writeln!(out)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("cal: {}", message);
+    std::process::exit(1);
+}
+
+fn parse_year(text: &str) -> i64 {
+    match text.parse::<i64>() {
+        Ok(year) if (1..=9999).contains(&year) => year,
+        _ => fail(&format!("illegal year value: use 1-9999: '{}'", text)),
+    }
+}
+
+// A month as a number or a name, which may be abbreviated
+fn parse_month(text: &str) -> u32 {
+    if let Ok(month) = text.parse::<u32>() {
+        if (1..=12).contains(&month) {
+            return month;
+        }
+    } else if text.len() >= 3 {
+        let lower = text.to_ascii_lowercase();
+        if let Some(index) = datetime::MONTHS
+            .iter()
+            .position(|name| name.to_ascii_lowercase().starts_with(&lower))
+        {
+            return index as u32 + 1;
+        }
+    }
+    fail(&format!("illegal month value: use 1-12: '{}'", text))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = CalOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--one" => options.span = Span::One,
+            "--three" => options.span = Span::Three,
+            "--year" => options.span = Span::Year,
+            "--monday" => options.monday_first = true,
+            "--sunday" => options.monday_first = false,
+            "--color" => options.color = When::Auto,
+            _ if arg.starts_with("--color=") => {
+                let value = &arg["--color=".len()..];
+                options.color = When::parse(value)
+                    .unwrap_or_else(|| fail(&format!("unsupported color mode: '{}'", value)))
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        '1' => options.span = Span::One,
+                        '3' => options.span = Span::Three,
+                        'y' => options.span = Span::Year,
+                        'm' => options.monday_first = true,
+                        's' => options.monday_first = false,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    let now = Timestamp::now();
+    let local =
+        datetime::local_time(now.secs).unwrap_or_else(|| fail("cannot determine the current date"));
+    let mut today = Some((local.year, local.month, local.day));
+
+    // [[DAY] MONTH] YEAR, where a year alone means the whole year
+    let (year, month) = match operands.len() {
+        0 => (local.year, local.month),
+        1 => {
+            if options.span == Span::One {
+                options.span = Span::Year;
+            }
+            (parse_year(&operands[0]), 1)
+        }
+        2 => (parse_year(&operands[1]), parse_month(&operands[0])),
+        3 => {
+            let year = parse_year(&operands[2]);
+            let month = parse_month(&operands[1]);
+            let day = match operands[0].parse::<u32>() {
+                Ok(day) if day >= 1 && day <= datetime::days_in_month(year, month) => day,
+                _ => fail(&format!("illegal day value: '{}'", operands[0])),
+            };
+            today = Some((year, month, day));
+            (year, month)
+        }
+        _ => fail(&format!("extra operand '{}'", operands[3])),
+    };
+
+    // Today is shown in reverse video
+    let calendar = Calendar {
+        options: &options,
+        today,
+        palette: options
+            .color
+            .enabled()
+            .then(|| Palette::new("rs=0:today=7")),
+    };
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let result = calendar
+        .print(year, month, &mut out)
+        .and_then(|_| out.flush());
+    if let Err(e) = result {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            fail(&format!("write error: {}", error::message(&e)));
+        }
+    }
+}
@@ -1,8 +1,10 @@
+use cmds_lite::numbering::{LineNumbering, Style};
 use std::env;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 
+#[derive(Default)]
 pub struct CatOptions {
     pub number_lines: bool,
     pub number_nonblank_lines: bool,
@@ -11,18 +13,6 @@ pub struct CatOptions {
     pub squeeze_blank: bool,
 }
 
-impl Default for CatOptions {
-    fn default() -> Self {
-        CatOptions {
-            number_lines: false,
-            number_nonblank_lines: false,
-            show_ends: false,
-            show_tabs: false,
-            squeeze_blank: false,
-        }
-    }
-}
-
 trait FileReader {
     fn read_file(&self, path: &Path, options: &CatOptions) -> io::Result<()>;
 }
@@ -32,7 +22,13 @@ trait StdinReader {
 }
 
 trait LineProcessor {
-    fn process_line(&self, line: &str, line_number: &mut usize, options: &CatOptions);
+    fn process_line(
+        &self,
+        line: &[u8],
+        numbering: &mut LineNumbering,
+        options: &CatOptions,
+        out: &mut dyn Write,
+    ) -> io::Result<()>;
 }
 
 struct StandardFileReader;
@@ -40,16 +36,7 @@ struct StandardFileReader;
 impl FileReader for StandardFileReader {
     fn read_file(&self, path: &Path, options: &CatOptions) -> io::Result<()> {
         let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let processor = StandardLineProcessor;
-        let mut line_number = 1;
-
-        for line_result in reader.lines() {
-            let line = line_result?;
-            processor.process_line(&line, &mut line_number, options);
-        }
-
-        Ok(())
+        process_lines(BufReader::new(file), options)
     }
 }
 
@@ -58,59 +45,93 @@ struct StandardStdinReader;
 impl StdinReader for StandardStdinReader {
     fn read_stdin(&self, options: &CatOptions) -> io::Result<()> {
         let stdin = io::stdin();
-        let reader = BufReader::new(stdin);
-        let processor = StandardLineProcessor;
-        let mut line_number = 1;
-
-        for line_result in reader.lines() {
-            let line = line_result?;
-            processor.process_line(&line, &mut line_number, options);
-        }
+        process_lines(stdin.lock(), options)
+    }
+}
 
-        Ok(())
+// Reads lines as bytes with their terminators, so that what is written
+// ends each line the way the input did, including a last line with no
+// newline at all
+fn process_lines(mut reader: impl BufRead, options: &CatOptions) -> io::Result<()> {
+    let processor = StandardLineProcessor;
+    let mut numbering = LineNumbering::new(1, 1, 6, "\t");
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut line = Vec::new();
+
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        processor.process_line(&line, &mut numbering, options, &mut out)?;
+        line.clear();
     }
+
+    out.flush()
 }
 
 struct StandardLineProcessor;
 
 impl LineProcessor for StandardLineProcessor {
-    fn process_line(&self, line: &str, line_number: &mut usize, options: &CatOptions) {
-        let is_blank = line.trim().is_empty();
-        
-        // Skip blank lines if number_nonblank_lines is true and the line is blank
-        if options.number_nonblank_lines && is_blank {
-            println!("{}", format_line(line, None, options));
-        } else if options.number_lines || (options.number_nonblank_lines && !is_blank) {
-            println!("{}", format_line(line, Some(*line_number), options));
-            *line_number += 1;
+    fn process_line(
+        &self,
+        line: &[u8],
+        numbering: &mut LineNumbering,
+        options: &CatOptions,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        // -b numbers the lines that are not empty, -n all of them
+        let style = if options.number_nonblank_lines {
+            Style::NonEmpty
+        } else if options.number_lines {
+            Style::All
+        } else {
+            Style::Nothing
+        };
+
+        let content = line.strip_suffix(b"\n").unwrap_or(line);
+        if style.numbers(content) {
+            out.write_all(&format_line(line, Some(numbering.number()), options))
         } else {
-            println!("{}", format_line(line, None, options));
+            out.write_all(&format_line(line, None, options))
         }
     }
 }
 
-fn format_line(line: &str, line_number: Option<usize>, options: &CatOptions) -> String {
-    let mut result = String::new();
+// The line with its number, markers and original terminator. Like GNU
+// cat, -E puts the $ before a \r\n ending, showing the \r as ^M, and
+// leaves a last line without a newline unmarked.
+fn format_line(line: &[u8], number: Option<String>, options: &CatOptions) -> Vec<u8> {
+    let mut result = Vec::new();
     
     // Add line number if specified
-    if let Some(num) = line_number {
-        result.push_str(&format!("{:6}\t", num));
+    if let Some(number) = number {
+        result.extend_from_slice(number.as_bytes());
     }
     
-    // Process line content
-    let mut processed_line = line.to_string();
+    let (content, terminator) = match line.strip_suffix(b"\n") {
+        Some(content) => (content, &b"\n"[..]),
+        None => (line, &b""[..]),
+    };
+    let (content, carriage_return) = match content.strip_suffix(b"\r") {
+        Some(content) if options.show_ends && !terminator.is_empty() => (content, true),
+        _ => (content, false),
+    };
     
     // Replace tabs with visible representation if show_tabs is enabled
-    if options.show_tabs {
-        processed_line = processed_line.replace('\t', "^I");
+    for &byte in content {
+        if options.show_tabs && byte == b'\t' {
+            result.extend_from_slice(b"^I");
+        } else {
+            result.push(byte);
+        }
     }
     
-    result.push_str(&processed_line);
-    
     // Add $ at the end of line if show_ends is enabled
-    if options.show_ends {
-        result.push('$');
+    if carriage_return {
+        result.extend_from_slice(b"^M");
+    }
+    if options.show_ends && !terminator.is_empty() {
+        result.push(b'$');
     }
+    result.extend_from_slice(terminator);
     
     result
 }
@@ -0,0 +1,380 @@
+// The command line shared by md5sum, sha1sum and sha256sum, which differ
+// only in the digest they compute.
+
+use crate::error;
+use crate::hash::{self, Algorithm};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+#[derive(Default)]
+pub struct ChecksumOptions {
+    // -b: mark files as read in binary mode with a '*'
+    pub binary: bool,
+    // --tag: BSD-style "ALGORITHM (FILE) = HASH" lines
+    pub tag: bool,
+    // -z: end lines with NUL and leave file names unescaped
+    pub zero: bool,
+    // -c: read sums from the files and check them instead
+    pub check: bool,
+    // --quiet: do not print OK for files that match
+    pub quiet: bool,
+    // --status: print nothing and report only through the exit status
+    pub status: bool,
+    // -w: warn about improperly formatted checksum lines
+    pub warn: bool,
+    // --strict: fail if any checksum line is improperly formatted
+    pub strict: bool,
+}
+
+// What checking one list of sums found
+#[derive(Default)]
+struct Tally {
+    failed: usize,
+    unreadable: usize,
+    malformed: usize,
+    // Lines that could be checked, whatever the result
+    checked: usize,
+}
+
+struct ChecksumCommand<'a> {
+    program: &'a str,
+    algorithm: Algorithm,
+    options: &'a ChecksumOptions,
+}
+
+impl<'a> ChecksumCommand<'a> {
+    fn hash_file(&self, file: &str) -> io::Result<Vec<u8>> {
+        if file == "-" {
+            self.algorithm.hash_reader(&mut io::stdin().lock())
+        } else {
+            self.algorithm.hash_reader(&mut File::open(file)?)
+        }
+    }
+
+    fn write_sum(&self, file: &str, digest: &[u8], out: &mut impl Write) -> io::Result<()> {
+        // Names holding a backslash or line break are escaped, and the
+        // line starts with a backslash to say so
+        let (escaped, name) = if self.options.zero {
+            (false, file.to_string())
+        } else {
+            escape_name(file)
+        };
+        if escaped {
+            out.write_all(b"\\")?;
+        }
+        let hex = hash::to_hex(digest);
+        if self.options.tag {
+            write!(out, "{} ({}) = {}", self.algorithm.name(), name, hex)?;
+        } else {
+            let mode = if self.options.binary { '*' } else { ' ' };
+            write!(out, "{} {}{}", hex, mode, name)?;
+        }
+        out.write_all(if self.options.zero { b"\0" } else { b"\n" })
+    }
+
+    fn run(&self, files: &[String]) -> io::Result<bool> {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        let mut ok = true;
+        for file in files {
+            match self.hash_file(file) {
+                Ok(digest) => self.write_sum(file, &digest, &mut out)?,
+                Err(e) => {
+                    out.flush()?;
+                    eprintln!("{}: {}: {}", self.program, file, error::message(&e));
+                    ok = false;
+                }
+            }
+        }
+        out.flush()?;
+        Ok(ok)
+    }
+
+    // Splits a line of a checksum list into the expected digest and the
+    // file name, in either the plain or the --tag format
+    fn parse_line(&self, line: &str) -> Option<(Vec<u8>, String)> {
+        let line = line.trim_start();
+        let (escaped, line) = match line.strip_prefix('\\') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (hex, name) = match line.strip_prefix(self.algorithm.name()) {
+            Some(rest) if rest.starts_with(" (") => {
+                let (name, hex) = rest[2..].rsplit_once(") = ")?;
+                (hex, name)
+            }
+            _ => {
+                let hex = line.get(..self.algorithm.size() * 2)?;
+                let rest = &line[hex.len()..];
+                let name = rest
+                    .strip_prefix("  ")
+                    .or_else(|| rest.strip_prefix(" *"))?;
+                (hex, name)
+            }
+        };
+        let digest = hash::from_hex(hex).filter(|d| d.len() == self.algorithm.size())?;
+        if name.is_empty() {
+            return None;
+        }
+        let name = if escaped {
+            unescape_name(name)?
+        } else {
+            name.to_string()
+        };
+        Some((digest, name))
+    }
+
+    fn check_list(&self, list: &str, out: &mut impl Write) -> io::Result<Tally> {
+        let mut tally = Tally::default();
+        let mut reader: Box<dyn BufRead> = if list == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            match File::open(list) {
+                Ok(f) => Box::new(BufReader::new(f)),
+                Err(e) => {
+                    out.flush()?;
+                    eprintln!("{}: {}: {}", self.program, list, error::message(&e));
+                    tally.unreadable += 1;
+                    return Ok(tally);
+                }
+            }
+        };
+
+        let mut buffer = Vec::new();
+        let mut number = 0;
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                break;
+            }
+            number += 1;
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches('\n').trim_end_matches('\r');
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            let Some((expected, file)) = self.parse_line(line) else {
+                tally.malformed += 1;
+                if self.options.warn {
+                    out.flush()?;
+                    eprintln!(
+                        "{}: {}: {}: improperly formatted {} checksum line",
+                        self.program,
+                        list,
+                        number,
+                        self.algorithm.name()
+                    );
+                }
+                continue;
+            };
+            tally.checked += 1;
+
+            let (escaped, name) = escape_name(&file);
+            let prefix = if escaped { "\\" } else { "" };
+            match self.hash_file(&file) {
+                Ok(digest) if digest == expected => {
+                    if !self.options.quiet && !self.options.status {
+                        writeln!(out, "{}{}: OK", prefix, name)?;
+                    }
+                }
+                Ok(_) => {
+                    tally.failed += 1;
+                    if !self.options.status {
+                        writeln!(out, "{}{}: FAILED", prefix, name)?;
+                    }
+                }
+                Err(e) => {
+                    tally.unreadable += 1;
+                    out.flush()?;
+                    eprintln!("{}: {}: {}", self.program, file, error::message(&e));
+                    if !self.options.status {
+                        writeln!(out, "{}{}: FAILED open or read", prefix, name)?;
+                    }
+                }
+            }
+        }
+
+        if tally.checked == 0 {
+            out.flush()?;
+            eprintln!(
+                "{}: {}: no properly formatted {} checksum lines found",
+                self.program,
+                list,
+                self.algorithm.name()
+            );
+        }
+        Ok(tally)
+    }
+
+    fn check(&self, lists: &[String]) -> io::Result<bool> {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        let mut ok = true;
+        for list in lists {
+            let tally = self.check_list(list, &mut out)?;
+            out.flush()?;
+            if !self.options.status {
+                // A list with nothing to check was already reported
+                let malformed = if tally.checked == 0 {
+                    0
+                } else {
+                    tally.malformed
+                };
+                let warnings = [
+                    (malformed, "line is", "lines are", "improperly formatted"),
+                    (
+                        tally.unreadable,
+                        "listed file",
+                        "listed files",
+                        "could not be read",
+                    ),
+                    (
+                        tally.failed,
+                        "computed checksum",
+                        "computed checksums",
+                        "did NOT match",
+                    ),
+                ];
+                for (count, one, many, what) in warnings {
+                    if count > 0 {
+                        let noun = if count == 1 { one } else { many };
+                        eprintln!("{}: WARNING: {} {} {}", self.program, count, noun, what);
+                    }
+                }
+            }
+            if tally.checked == 0
+                || tally.failed > 0
+                || tally.unreadable > 0
+                || (self.options.strict && tally.malformed > 0)
+            {
+                ok = false;
+            }
+        }
+        Ok(ok)
+    }
+}
+
+fn escape_name(name: &str) -> (bool, String) {
+    if !name.contains(['\\', '\n', '\r']) {
+        return (false, name.to_string());
+    }
+    let escaped = name
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    (true, escaped)
+}
+
+// Undoes escape_name, for names read back from a checksum list
+fn unescape_name(name: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(unescaped)
+}
+
+pub fn run(
+    program: &str,
+    algorithm: Algorithm,
+    files: &[String],
+    options: &ChecksumOptions,
+) -> io::Result<bool> {
+    let command = ChecksumCommand {
+        program,
+        algorithm,
+        options,
+    };
+    if options.check {
+        command.check(files)
+    } else {
+        command.run(files)
+    }
+}
+
+// The whole command: parses the arguments, prints the sums and exits
+pub fn main(program: &str, algorithm: Algorithm) -> ! {
+    let fail = |message: &str| -> ! {
+        eprintln!("{}: {}", program, message);
+        std::process::exit(1);
+    };
+
+    let args: Vec<String> = env::args().collect();
+    let mut options = ChecksumOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--binary" => options.binary = true,
+            "--text" => options.binary = false,
+            "--tag" => options.tag = true,
+            "--zero" => options.zero = true,
+            "--check" => options.check = true,
+            "--quiet" => options.quiet = true,
+            "--status" => options.status = true,
+            "--warn" => options.warn = true,
+            "--strict" => options.strict = true,
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'b' => options.binary = true,
+                        't' => options.binary = false,
+                        'z' => options.zero = true,
+                        'c' => options.check = true,
+                        'w' => options.warn = true,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if options.check && options.tag {
+        fail("the --tag option is meaningless when verifying checksums");
+    }
+    if !options.check {
+        let only_check = [
+            (options.quiet, "--quiet"),
+            (options.status, "--status"),
+            (options.warn, "--warn"),
+            (options.strict, "--strict"),
+        ];
+        if let Some((_, option)) = only_check.iter().find(|(set, _)| *set) {
+            fail(&format!(
+                "the {} option is meaningful only when verifying checksums",
+                option
+            ));
+        }
+    }
+
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    match run(program, algorithm, &files, &options) {
+        Ok(true) => std::process::exit(0),
+        Ok(false) => std::process::exit(1),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(1),
+        Err(e) => fail(&format!("write error: {}", error::message(&e))),
+    }
+}
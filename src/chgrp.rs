@@ -0,0 +1,91 @@
+use cmds_lite::ownership::{Owner, OwnershipCommand, OwnershipOptions, Reporting};
+use cmds_lite::users;
+use std::env;
+
+pub fn run(owner: &Owner, files: &[String], options: &OwnershipOptions) -> bool {
+    let command = OwnershipCommand::new("chgrp", owner, options);
+    command.run(files)
+}
+
+// chgrp takes a bare group, by name or number
+fn parse_owner(group: &str) -> Owner {
+    match users::parse_group(group) {
+        Some(gid) => Owner {
+            gid: Some(gid),
+            group_text: Some(group.to_string()),
+            ..Owner::default()
+        },
+        None => {
+            eprintln!("chgrp: invalid group: '{}'", group);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = OwnershipOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--recursive" => options.recursive = true,
+            "--no-dereference" => options.dereference = false,
+            "--dereference" => options.dereference = true,
+            "--changes" => options.reporting = Reporting::Changes,
+            "--verbose" => options.reporting = Reporting::Verbose,
+            "--silent" | "--quiet" => options.quiet = true,
+            _ if arg.starts_with("--from=") => {
+                let from = match Owner::parse(&arg["--from=".len()..]) {
+                    Ok(from) => from,
+                    Err(message) => {
+                        eprintln!("chgrp: {}", message);
+                        std::process::exit(1);
+                    }
+                };
+                options.from_uid = from.uid;
+                options.from_gid = from.gid;
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("chgrp: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'R' => options.recursive = true,
+                        'h' => options.dereference = false,
+                        'c' => options.reporting = Reporting::Changes,
+                        'v' => options.reporting = Reporting::Verbose,
+                        'f' => options.quiet = true,
+                        _ => {
+                            eprintln!("chgrp: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if operands.is_empty() {
+        eprintln!("chgrp: missing operand");
+        std::process::exit(1);
+    }
+    if operands.len() == 1 {
+        eprintln!("chgrp: missing operand after '{}'", operands[0]);
+        std::process::exit(1);
+    }
+
+    let owner = parse_owner(&operands[0]);
+    if !run(&owner, &operands[1..], &options) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,235 @@
+use cmds_lite::error;
+use cmds_lite::mode::{self, Mode};
+use cmds_lite::walk::{WalkOptions, Walker};
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Reporting {
+    #[default]
+    Silent,
+    // -c: only files whose mode actually changed
+    Changes,
+    // -v: every file processed
+    Verbose,
+}
+
+#[derive(Default)]
+pub struct ChmodOptions {
+    pub recursive: bool,
+    pub reporting: Reporting,
+    // -f: suppress most error messages
+    pub quiet: bool,
+    // Warn when the umask kept a mode written like an option (-w) from
+    // fully applying
+    pub diagnose_surprises: bool,
+}
+
+struct ChmodCommand<'a> {
+    mode: &'a Mode,
+    options: &'a ChmodOptions,
+    umask: u32,
+}
+
+impl<'a> ChmodCommand<'a> {
+    fn new(mode: &'a Mode, options: &'a ChmodOptions) -> Self {
+        ChmodCommand {
+            mode,
+            options,
+            umask: mode::current_umask(),
+        }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let mut ok = true;
+        for file in files {
+            ok &= if self.options.recursive {
+                self.change_tree(file)
+            } else {
+                self.change_operand(Path::new(file))
+            };
+        }
+        ok
+    }
+
+    fn change_operand(&self, path: &Path) -> bool {
+        match fs::metadata(path) {
+            Ok(metadata) => self.change(path, &metadata),
+            Err(e) => {
+                self.report_error(&format!(
+                    "cannot access '{}': {}",
+                    path.display(),
+                    error::message(&e)
+                ));
+                false
+            }
+        }
+    }
+
+    // Symlinks named on the command line are followed; those found in the
+    // tree are skipped, as GNU chmod does
+    fn change_tree(&self, root: &str) -> bool {
+        let walk_options = WalkOptions {
+            follow_root_links: true,
+            ..WalkOptions::default()
+        };
+        let mut ok = true;
+        for item in Walker::new(&[root], walk_options) {
+            match item {
+                Ok(entry) => {
+                    if entry.metadata.file_type().is_symlink() {
+                        if self.options.reporting == Reporting::Verbose {
+                            println!(
+                                "neither symbolic link '{}' nor referent has been changed",
+                                entry.path.display()
+                            );
+                        }
+                        continue;
+                    }
+                    ok &= self.change(&entry.path, &entry.metadata);
+                }
+                Err(e) => {
+                    let message = if e.path == Path::new(root) {
+                        format!("cannot access '{}'", root)
+                    } else {
+                        format!("cannot read directory '{}'", e.path.display())
+                    };
+                    self.report_error(&format!("{}: {}", message, error::message(&e.error)));
+                    ok = false;
+                }
+            }
+        }
+        ok
+    }
+
+    fn change(&self, path: &Path, metadata: &fs::Metadata) -> bool {
+        let old = metadata.permissions().mode() & 0o7777;
+        let new = self.mode.apply(old, metadata.is_dir(), self.umask);
+
+        let result = if new == old {
+            Ok(())
+        } else {
+            fs::set_permissions(path, fs::Permissions::from_mode(new))
+        };
+        if let Err(e) = result {
+            self.report_error(&format!(
+                "changing permissions of '{}': {}",
+                path.display(),
+                error::message(&e)
+            ));
+            return false;
+        }
+
+        match self.options.reporting {
+            Reporting::Silent => {}
+            Reporting::Changes if new == old => {}
+            _ if new == old => println!(
+                "mode of '{}' retained as {:04o} ({})",
+                path.display(),
+                old,
+                mode::symbolic(old)
+            ),
+            _ => println!(
+                "mode of '{}' changed from {:04o} ({}) to {:04o} ({})",
+                path.display(),
+                old,
+                mode::symbolic(old),
+                new,
+                mode::symbolic(new)
+            ),
+        }
+
+        if self.options.diagnose_surprises {
+            let expected = self.mode.apply(old, metadata.is_dir(), 0);
+            if new & !expected != 0 {
+                self.report_error(&format!(
+                    "{}: new permissions are {}, not {}",
+                    path.display(),
+                    mode::symbolic(new),
+                    mode::symbolic(expected)
+                ));
+                return false;
+            }
+        }
+        true
+    }
+
+    fn report_error(&self, message: &str) {
+        if !self.options.quiet {
+            eprintln!("chmod: {}", message);
+        }
+    }
+}
+
+pub fn run(mode: &Mode, files: &[String], options: &ChmodOptions) -> bool {
+    let command = ChmodCommand::new(mode, options);
+    command.run(files)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = ChmodOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--recursive" => options.recursive = true,
+            "--changes" => options.reporting = Reporting::Changes,
+            "--verbose" => options.reporting = Reporting::Verbose,
+            "--silent" | "--quiet" => options.quiet = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("chmod: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            // A mode such as -w or -x+r is a mode, not options
+            _ if arg[1..].starts_with(|c| "rwxXstugoa,+=01234567".contains(c)) => {
+                options.diagnose_surprises = true;
+                operands.push(arg.clone());
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'R' => options.recursive = true,
+                        'c' => options.reporting = Reporting::Changes,
+                        'v' => options.reporting = Reporting::Verbose,
+                        'f' => options.quiet = true,
+                        _ => {
+                            eprintln!("chmod: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if operands.is_empty() {
+        eprintln!("chmod: missing operand");
+        std::process::exit(1);
+    }
+    if operands.len() == 1 {
+        eprintln!("chmod: missing operand after '{}'", operands[0]);
+        std::process::exit(1);
+    }
+
+    let mode = match Mode::parse(&operands[0]) {
+        Ok(mode) => mode,
+        Err(e) => {
+            eprintln!("chmod: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if !run(&mode, &operands[1..], &options) {
+        std::process::exit(1);
+    }
+}
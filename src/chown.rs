@@ -0,0 +1,79 @@
+use cmds_lite::ownership::{Owner, OwnershipCommand, OwnershipOptions, Reporting};
+use std::env;
+
+pub fn run(owner: &Owner, files: &[String], options: &OwnershipOptions) -> bool {
+    let command = OwnershipCommand::new("chown", owner, options);
+    command.run(files)
+}
+
+fn parse_owner(spec: &str) -> Owner {
+    match Owner::parse(spec) {
+        Ok(owner) => owner,
+        Err(message) => {
+            eprintln!("chown: {}", message);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = OwnershipOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--recursive" => options.recursive = true,
+            "--no-dereference" => options.dereference = false,
+            "--dereference" => options.dereference = true,
+            "--changes" => options.reporting = Reporting::Changes,
+            "--verbose" => options.reporting = Reporting::Verbose,
+            "--silent" | "--quiet" => options.quiet = true,
+            _ if arg.starts_with("--from=") => {
+                let from = parse_owner(&arg["--from=".len()..]);
+                options.from_uid = from.uid;
+                options.from_gid = from.gid;
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("chown: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'R' => options.recursive = true,
+                        'h' => options.dereference = false,
+                        'c' => options.reporting = Reporting::Changes,
+                        'v' => options.reporting = Reporting::Verbose,
+                        'f' => options.quiet = true,
+                        _ => {
+                            eprintln!("chown: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if operands.is_empty() {
+        eprintln!("chown: missing operand");
+        std::process::exit(1);
+    }
+    if operands.len() == 1 {
+        eprintln!("chown: missing operand after '{}'", operands[0]);
+        std::process::exit(1);
+    }
+
+    let owner = parse_owner(&operands[0]);
+    if !run(&owner, &operands[1..], &options) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,96 @@
+use cmds_lite::crc::{Algorithm, Crc};
+use cmds_lite::error;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+fn fail(message: &str) -> ! {
+    eprintln!("cksum: {}", message);
+    std::process::exit(1);
+}
+
+fn crc_file(algorithm: Algorithm, file: &str) -> io::Result<(u32, u64)> {
+    if file == "-" {
+        Crc::of_reader(algorithm, &mut io::stdin().lock())
+    } else {
+        Crc::of_reader(algorithm, &mut File::open(file)?)
+    }
+}
+
+// Prints "CRC LENGTH FILE" for each file; standard input read because no
+// files were named is printed without a name
+pub fn run(algorithm: Algorithm, files: &[String]) -> io::Result<bool> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut ok = true;
+    if files.is_empty() {
+        let (crc, length) = crc_file(algorithm, "-")?;
+        writeln!(out, "{} {}", crc, length)?;
+    }
+    for file in files {
+        match crc_file(algorithm, file) {
+            Ok((crc, length)) => writeln!(out, "{} {} {}", crc, length, file)?,
+            Err(e) => {
+                out.flush()?;
+                eprintln!("cksum: {}: {}", file, error::message(&e));
+                ok = false;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(ok)
+}
+
+fn parse_algorithm(name: &str) -> Algorithm {
+    Algorithm::from_name(name).unwrap_or_else(|| {
+        fail(&format!(
+            "invalid argument '{}' for '--algorithm'\nValid arguments are: 'crc', 'crc32', 'crc32b', 'crc32c'",
+            name
+        ))
+    })
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut algorithm = Algorithm::Posix;
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--algorithm" | "-a" => {
+                if i >= args.len() {
+                    fail(if arg == "-a" {
+                        "option requires an argument -- 'a'"
+                    } else {
+                        "option '--algorithm' requires an argument"
+                    });
+                }
+                algorithm = parse_algorithm(&args[i]);
+                i += 1;
+            }
+            _ if arg.starts_with("--algorithm=") => {
+                algorithm = parse_algorithm(&arg["--algorithm=".len()..]);
+            }
+            _ if arg.starts_with("-a") => algorithm = parse_algorithm(&arg[2..]),
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => fail(&format!("invalid option -- '{}'", &arg[1..2])),
+        }
+    }
+
+    match run(algorithm, &files) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(1),
+        Err(e) => fail(&error::message(&e)),
+    }
+}
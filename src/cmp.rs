@@ -0,0 +1,338 @@
+use cmds_lite::error;
+use cmds_lite::size;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+
+const BLOCK_SIZE: usize = 64 * 1024;
+
+#[derive(Default)]
+pub struct CmpOptions {
+    // -s: print nothing, only exit with the result
+    pub silent: bool,
+    // -l: list every differing byte instead of stopping at the first
+    pub verbose: bool,
+    // -b: also show the differing bytes as characters
+    pub print_bytes: bool,
+    // -i: bytes to skip at the start of each file
+    pub skip: (u64, u64),
+    // -n: compare at most this many bytes
+    pub limit: Option<u64>,
+}
+
+// cmp exits 0 when the files are the same, 1 when they differ and 2 for
+// trouble
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Same,
+    Different,
+    Trouble,
+}
+
+struct Input {
+    name: String,
+    reader: BufReader<Box<dyn Read>>,
+    // The size of a regular file, which sets the width of -l offsets
+    size: Option<u64>,
+}
+
+// A byte the way cat -v shows it: ^X for control characters and M- for
+// those with the high bit set
+fn visible(byte: u8) -> String {
+    let (meta, byte) = if byte >= 0x80 {
+        ("M-", byte - 0x80)
+    } else {
+        ("", byte)
+    };
+    match byte {
+        0..=31 => format!("{}^{}", meta, (byte + 64) as char),
+        127 => format!("{}^?", meta),
+        _ => format!("{}{}", meta, byte as char),
+    }
+}
+
+// The next block of an input, with read errors naming it
+fn fill<'r>(reader: &'r mut BufReader<Box<dyn Read>>, name: &str) -> io::Result<&'r [u8]> {
+    reader
+        .fill_buf()
+        .map_err(|e| io::Error::other(format!("{}: {}", name, error::message(&e))))
+}
+
+struct CmpCommand<'a> {
+    options: &'a CmpOptions,
+}
+
+impl<'a> CmpCommand<'a> {
+    fn open(&self, name: &str, skip: u64) -> io::Result<Input> {
+        let (reader, size): (Box<dyn Read>, _) = if name == "-" {
+            (Box::new(io::stdin()), None)
+        } else {
+            let file = File::open(name)?;
+            let metadata = file.metadata()?;
+            let size = metadata.is_file().then_some(metadata.len());
+            (Box::new(file), size)
+        };
+        let mut reader = BufReader::with_capacity(BLOCK_SIZE, reader);
+        io::copy(&mut (&mut reader).take(skip), &mut io::sink())?;
+        Ok(Input {
+            name: name.to_string(),
+            reader,
+            size: size.map(|size| size.saturating_sub(skip)),
+        })
+    }
+
+    // Compares the two inputs a block at a time, reporting differences
+    // and the end of the shorter one
+    fn compare(
+        &self,
+        first: &mut Input,
+        second: &mut Input,
+        out: &mut impl Write,
+    ) -> io::Result<Status> {
+        // -l offsets are as wide as the largest offset that can differ
+        let width = [first.size, second.size, self.options.limit]
+            .into_iter()
+            .flatten()
+            .min()
+            .map_or(19, |size| size.to_string().len());
+        let mut remaining = self.options.limit.unwrap_or(u64::MAX);
+        let mut offset = 0u64;
+        let mut lines = 0u64;
+        let mut last = b'\n';
+        let mut status = Status::Same;
+
+        while remaining > 0 {
+            let a = fill(&mut first.reader, &first.name)?;
+            let b = fill(&mut second.reader, &second.name)?;
+            let count = a.len().min(b.len()).min(remaining as usize);
+            if count == 0 {
+                if a.is_empty() != b.is_empty() {
+                    let shorter = if a.is_empty() {
+                        &first.name
+                    } else {
+                        &second.name
+                    };
+                    // What -l listed comes before the message
+                    out.flush()?;
+                    self.report_eof(shorter, offset, lines, last);
+                    return Ok(Status::Different);
+                }
+                break;
+            }
+            let (a, b) = (&a[..count], &b[..count]);
+
+            if a != b {
+                if self.options.silent {
+                    return Ok(Status::Different);
+                }
+                if !self.options.verbose {
+                    let at = a.iter().zip(b).position(|(x, y)| x != y).unwrap();
+                    lines += a[..at].iter().filter(|&&byte| byte == b'\n').count() as u64;
+                    write!(
+                        out,
+                        "{} {} differ: {} {}, line {}",
+                        first.name,
+                        second.name,
+                        if self.options.print_bytes {
+                            "byte"
+                        } else {
+                            "char"
+                        },
+                        offset + at as u64 + 1,
+                        lines + 1
+                    )?;
+                    if self.options.print_bytes {
+                        write!(
+                            out,
+                            " is {:3o} {} {:3o} {}",
+                            a[at],
+                            visible(a[at]),
+                            b[at],
+                            visible(b[at])
+                        )?;
+                    }
+                    writeln!(out)?;
+                    return Ok(Status::Different);
+                }
+                for (i, (&x, &y)) in a.iter().zip(b).enumerate() {
+                    if x == y {
+                        continue;
+                    }
+                    let position = offset + i as u64 + 1;
+                    if self.options.print_bytes {
+                        writeln!(
+                            out,
+                            "{:>width$} {:3o} {:<4} {:3o} {}",
+                            position,
+                            x,
+                            visible(x),
+                            y,
+                            visible(y)
+                        )?;
+                    } else {
+                        writeln!(out, "{:>width$} {:3o} {:3o}", position, x, y)?;
+                    }
+                }
+                status = Status::Different;
+            }
+
+            lines += a.iter().filter(|&&byte| byte == b'\n').count() as u64;
+            last = a[count - 1];
+            offset += count as u64;
+            remaining -= count as u64;
+            first.reader.consume(count);
+            second.reader.consume(count);
+        }
+        Ok(status)
+    }
+
+    fn report_eof(&self, name: &str, offset: u64, lines: u64, last: u8) {
+        if self.options.silent {
+            return;
+        }
+        if offset == 0 {
+            eprintln!("cmp: EOF on {} which is empty", name);
+        } else if self.options.verbose {
+            eprintln!("cmp: EOF on {} after byte {}", name, offset);
+        } else if last == b'\n' {
+            eprintln!("cmp: EOF on {} after byte {}, line {}", name, offset, lines);
+        } else {
+            eprintln!(
+                "cmp: EOF on {} after byte {}, in line {}",
+                name,
+                offset,
+                lines + 1
+            );
+        }
+    }
+
+    fn run(&self, first: &str, second: &str) -> Status {
+        let open = |name: &str, skip: u64| {
+            self.open(name, skip).map_err(|e| {
+                if !self.options.silent {
+                    eprintln!("cmp: {}: {}", name, error::message(&e));
+                }
+            })
+        };
+        let (Ok(mut first), Ok(mut second)) = (
+            open(first, self.options.skip.0),
+            open(second, self.options.skip.1),
+        ) else {
+            return Status::Trouble;
+        };
+
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        let result = self
+            .compare(&mut first, &mut second, &mut out)
+            .and_then(|status| out.flush().map(|_| status));
+        match result {
+            Ok(status) => status,
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Status::Trouble,
+            Err(e) => {
+                eprintln!("cmp: {}", error::message(&e));
+                Status::Trouble
+            }
+        }
+    }
+}
+
+// cmp exits 2 for trouble, leaving 1 to mean the files differ
+fn fail(message: &str) -> ! {
+    eprintln!("cmp: {}", message);
+    std::process::exit(2);
+}
+
+fn parse_bytes(value: &str, what: &str) -> u64 {
+    size::parse_size(value).unwrap_or_else(|| fail(&format!("invalid {} '{}'", what, value)))
+}
+
+// -i takes SKIP for both files, or SKIP1:SKIP2
+fn parse_skip(value: &str) -> (u64, u64) {
+    match value.split_once(':') {
+        Some((first, second)) => (
+            parse_bytes(first, "--ignore-initial value"),
+            parse_bytes(second, "--ignore-initial value"),
+        ),
+        None => {
+            let skip = parse_bytes(value, "--ignore-initial value");
+            (skip, skip)
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = CmpOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--silent" | "--quiet" => options.silent = true,
+            "--verbose" => options.verbose = true,
+            "--print-bytes" => options.print_bytes = true,
+            _ if arg.starts_with("--ignore-initial=") => {
+                options.skip = parse_skip(&arg["--ignore-initial=".len()..])
+            }
+            _ if arg.starts_with("--bytes=") => {
+                options.limit = Some(parse_bytes(&arg["--bytes=".len()..], "--bytes value"))
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        's' => options.silent = true,
+                        'l' => options.verbose = true,
+                        'b' => options.print_bytes = true,
+                        'i' | 'n' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            if flag == 'i' {
+                                options.skip = parse_skip(&value);
+                            } else {
+                                options.limit = Some(parse_bytes(&value, "--bytes value"));
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if options.silent && options.verbose {
+        fail("options -l and -s are incompatible");
+    }
+    match files.len() {
+        0 => fail("missing operand"),
+        1 => files.push(String::from("-")),
+        2 => {}
+        _ => fail(&format!("extra operand '{}'", files[2])),
+    }
+
+    let command = CmpCommand { options: &options };
+    match command.run(&files[0], &files[1]) {
+        Status::Same => {}
+        Status::Different => std::process::exit(1),
+        Status::Trouble => std::process::exit(2),
+    }
+}
@@ -0,0 +1,188 @@
+use std::cmp::Ordering;
+use std::ffi::{CStr, CString};
+
+// String ordering shared by sort, uniq, comm and join, so that files sorted
+// by one are considered sorted by the others.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Collator {
+    // Use the C library's LC_COLLATE rules instead of plain byte order
+    locale: bool,
+}
+
+impl Collator {
+    // Byte order, as in the C/POSIX locale
+    pub fn bytes() -> Self {
+        Collator { locale: false }
+    }
+
+    // Honors LC_ALL / LC_COLLATE / LANG like the GNU tools do. Anything
+    // other than the C or POSIX locale collates with strcoll.
+    pub fn from_environment() -> Self {
+        let name = unsafe {
+            let empty = b"\0";
+            let result = libc::setlocale(libc::LC_COLLATE, empty.as_ptr() as *const libc::c_char);
+            if result.is_null() {
+                return Collator::bytes();
+            }
+            CStr::from_ptr(result).to_string_lossy().to_string()
+        };
+        Collator {
+            locale: name != "C" && name != "POSIX",
+        }
+    }
+
+    pub fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        if self.locale {
+            if let (Ok(a), Ok(b)) = (CString::new(a), CString::new(b)) {
+                let result = unsafe { libc::strcoll(a.as_ptr(), b.as_ptr()) };
+                return result.cmp(&0);
+            }
+        }
+        a.cmp(b)
+    }
+
+    // Case-insensitive comparison; lowercase letters are folded to
+    // uppercase like GNU sort -f
+    pub fn compare_folded(&self, a: &[u8], b: &[u8]) -> Ordering {
+        let fold = |text: &[u8]| text.to_ascii_uppercase();
+        self.compare(&fold(a), &fold(b))
+    }
+}
+
+pub fn is_blank(byte: u8) -> bool {
+    byte == b' ' || byte == b'\t'
+}
+
+// A number as GNU sort -n reads it: optional blanks, optional '-', digits
+// and an optional fraction. Anything that does not parse counts as zero.
+struct Numeric<'a> {
+    negative: bool,
+    integer: &'a [u8],
+    fraction: &'a [u8],
+}
+
+fn parse_numeric(text: &[u8]) -> Numeric<'_> {
+    let mut i = 0;
+    while i < text.len() && is_blank(text[i]) {
+        i += 1;
+    }
+    let negative = text.get(i) == Some(&b'-');
+    if negative {
+        i += 1;
+    }
+
+    let start = i;
+    while i < text.len() && text[i].is_ascii_digit() {
+        i += 1;
+    }
+    let mut integer = &text[start..i];
+    while integer.first() == Some(&b'0') {
+        integer = &integer[1..];
+    }
+
+    let mut fraction: &[u8] = &[];
+    if text.get(i) == Some(&b'.') {
+        let start = i + 1;
+        let mut end = start;
+        while end < text.len() && text[end].is_ascii_digit() {
+            end += 1;
+        }
+        fraction = &text[start..end];
+        while fraction.last() == Some(&b'0') {
+            fraction = &fraction[..fraction.len() - 1];
+        }
+    }
+
+    Numeric {
+        negative,
+        integer,
+        fraction,
+    }
+}
+
+// Compares the leading numbers of two strings without converting them to
+// floats, so arbitrarily long numbers compare exactly
+pub fn compare_numeric(a: &[u8], b: &[u8]) -> Ordering {
+    let a = parse_numeric(a);
+    let b = parse_numeric(b);
+    let a_zero = a.integer.is_empty() && a.fraction.is_empty();
+    let b_zero = b.integer.is_empty() && b.fraction.is_empty();
+
+    let a_negative = a.negative && !a_zero;
+    let b_negative = b.negative && !b_zero;
+    if a_negative != b_negative {
+        return if a_negative {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        };
+    }
+
+    let magnitude = a
+        .integer
+        .len()
+        .cmp(&b.integer.len())
+        .then_with(|| a.integer.cmp(b.integer))
+        .then_with(|| a.fraction.cmp(b.fraction));
+
+    if a_negative {
+        magnitude.reverse()
+    } else {
+        magnitude
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_order() {
+        for (a, b, expected) in [
+            ("1", "2", Ordering::Less),
+            ("10", "9", Ordering::Greater),
+            ("007", "7", Ordering::Equal),
+            ("1.5", "1.50", Ordering::Equal),
+            ("1.05", "1.5", Ordering::Less),
+            ("-1", "1", Ordering::Less),
+            ("-10", "-9", Ordering::Less),
+            ("-0", "0", Ordering::Equal),
+            ("-0.0", "", Ordering::Equal),
+            ("abc", "0", Ordering::Equal),
+            ("  42", "42", Ordering::Equal),
+            ("\t3x", "3y", Ordering::Equal),
+            (".5", "0.4", Ordering::Greater),
+            (
+                "99999999999999999999999",
+                "99999999999999999999998",
+                Ordering::Greater,
+            ),
+        ] {
+            assert_eq!(
+                compare_numeric(a.as_bytes(), b.as_bytes()),
+                expected,
+                "{} {}",
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn byte_order() {
+        let collator = Collator::bytes();
+        assert_eq!(collator.compare(b"B", b"a"), Ordering::Less);
+        assert_eq!(collator.compare(b"a", b"ab"), Ordering::Less);
+        assert_eq!(collator.compare_folded(b"a", b"B"), Ordering::Less);
+        assert_eq!(collator.compare_folded(b"abc", b"ABC"), Ordering::Equal);
+        // Letters fold to upper case, so they sort before _ as in GNU sort
+        assert_eq!(collator.compare_folded(b"a", b"_"), Ordering::Less);
+    }
+
+    #[test]
+    fn blanks() {
+        assert!(is_blank(b' ') && is_blank(b'\t'));
+        assert!(!is_blank(b'\n') && !is_blank(b'a'));
+    }
+}
@@ -0,0 +1,144 @@
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::Path;
+
+// Terminal colors for commands with a --color option: deciding whether to
+// use them, and a palette of SGR escape sequences keyed by short names in
+// the "key=value:key=value" syntax of LS_COLORS and diff's --palette.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum When {
+    Never,
+    Auto,
+    Always,
+}
+
+impl When {
+    // Parses the argument of --color=WHEN, with GNU's synonyms
+    pub fn parse(text: &str) -> Option<When> {
+        match text {
+            "never" | "no" | "none" => Some(When::Never),
+            "auto" | "tty" | "if-tty" => Some(When::Auto),
+            "always" | "yes" | "force" => Some(When::Always),
+            _ => None,
+        }
+    }
+
+    // Whether to color standard output: for auto, only when it is a
+    // terminal that can show colors
+    pub fn enabled(self) -> bool {
+        match self {
+            When::Never => false,
+            When::Always => true,
+            When::Auto => {
+                io::stdout().is_terminal() && env::var("TERM").is_ok_and(|term| term != "dumb")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Palette {
+    entries: Vec<(String, String)>,
+}
+
+impl Palette {
+    // A palette from its "key=value" list, such as "ad=32:de=31"
+    pub fn new(spec: &str) -> Self {
+        let mut palette = Palette {
+            entries: Vec::new(),
+        };
+        palette.update(spec);
+        palette
+    }
+
+    // Adds or replaces entries; malformed ones are ignored, as the GNU
+    // tools do
+    pub fn update(&mut self, spec: &str) {
+        for entry in spec.split(':') {
+            let Some((key, value)) = entry.split_once('=') else {
+                continue;
+            };
+            match self.entries.iter_mut().find(|(k, _)| k == key) {
+                Some(existing) => existing.1 = value.to_string(),
+                None => self.entries.push((key.to_string(), value.to_string())),
+            }
+        }
+    }
+
+    // The SGR parameters for a key, such as "01;34"
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // The escape sequence that starts text colored as `key`, or an empty
+    // string if it has no color
+    pub fn start(&self, key: &str) -> String {
+        match self.get(key) {
+            Some(value) if !value.is_empty() => format!("\x1b[{}m", value),
+            _ => String::new(),
+        }
+    }
+
+    // The escape sequence that ends colored text
+    pub fn end(&self) -> String {
+        format!("\x1b[{}m", self.get("rs").unwrap_or("0"))
+    }
+
+    // The LS_COLORS key a file is colored by: its type, then for regular files
+    // whether it is executable, then a "*.ext" pattern for its name. Links
+    // whose target is missing are "or".
+    pub fn file_key(&self, name: &str, path: &Path, metadata: &fs::Metadata) -> String {
+        let file_type = metadata.file_type();
+        let key = if file_type.is_dir() {
+            "di"
+        } else if file_type.is_symlink() {
+            if fs::metadata(path).is_ok() {
+                "ln"
+            } else {
+                "or"
+            }
+        } else if file_type.is_fifo() {
+            "pi"
+        } else if file_type.is_socket() {
+            "so"
+        } else if file_type.is_block_device() {
+            "bd"
+        } else if file_type.is_char_device() {
+            "cd"
+        } else if metadata.permissions().mode() & 0o111 != 0 {
+            "ex"
+        } else {
+            // The longest matching pattern wins, so "*.tar.gz" beats "*.gz"
+            let pattern = self
+                .entries
+                .iter()
+                .filter_map(|(key, _)| key.strip_prefix('*').map(|suffix| (key, suffix)))
+                .filter(|(_, suffix)| name.ends_with(suffix))
+                .max_by_key(|(_, suffix)| suffix.len());
+            return match pattern {
+                Some((key, _)) => key.clone(),
+                None => String::from("fi"),
+            };
+        };
+        key.to_string()
+    }
+}
+
+// The colors ls gives file types when $LS_COLORS does not say otherwise
+pub const LS_DEFAULT: &str =
+    "rs=0:di=01;34:ln=01;36:pi=40;33:so=01;35:bd=40;33;01:cd=40;33;01:or=40;31;01:ex=01;32";
+
+// The palette for listing files: the defaults with $LS_COLORS on top
+pub fn ls_palette() -> Palette {
+    let mut palette = Palette::new(LS_DEFAULT);
+    if let Ok(spec) = env::var("LS_COLORS") {
+        palette.update(&spec);
+    }
+    palette
+}
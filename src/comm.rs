@@ -0,0 +1,266 @@
+use cmds_lite::collate::Collator;
+use cmds_lite::error;
+use std::cmp::Ordering;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OrderCheck {
+    // Complain about unsorted input once unpairable lines have been seen
+    Default,
+    // --check-order: unsorted input is fatal
+    Enabled,
+    // --nocheck-order
+    Disabled,
+}
+
+pub struct CommOptions {
+    // -1, -2, -3: leave out lines only in file 1, only in file 2, or in both
+    pub suppress: [bool; 3],
+    // Put before the second column, and twice before the third
+    pub output_delimiter: Vec<u8>,
+    // --total: finish with the number of lines in each column
+    pub total: bool,
+    // -z: lines end in NUL instead of newline
+    pub zero_terminated: bool,
+    pub order_check: OrderCheck,
+}
+
+impl Default for CommOptions {
+    fn default() -> Self {
+        CommOptions {
+            suppress: [false; 3],
+            output_delimiter: b"\t".to_vec(),
+            total: false,
+            zero_terminated: false,
+            order_check: OrderCheck::Default,
+        }
+    }
+}
+
+// Writing fails with the error itself, so a closed pipe can exit quietly
+pub enum Failure {
+    Write(io::Error),
+    Other(String),
+}
+
+struct Input {
+    name: String,
+    reader: Box<dyn BufRead>,
+    // The line read last, to check the order against
+    previous: Option<Vec<u8>>,
+    warned: bool,
+}
+
+struct CommCommand<'a> {
+    options: &'a CommOptions,
+    collator: Collator,
+    inputs: [Input; 2],
+    seen_unpairable: bool,
+    counts: [u64; 3],
+}
+
+impl<'a> CommCommand<'a> {
+    fn terminator(&self) -> u8 {
+        if self.options.zero_terminated {
+            0
+        } else {
+            b'\n'
+        }
+    }
+
+    // Reads the next line of a file, checking that it does not sort
+    // before the previous one
+    fn advance(&mut self, file: usize) -> Result<Option<Vec<u8>>, Failure> {
+        let terminator = self.terminator();
+        let input = &mut self.inputs[file];
+        let mut line = Vec::new();
+        let read = input
+            .reader
+            .read_until(terminator, &mut line)
+            .map_err(|e| Failure::Other(format!("{}: {}", input.name, error::message(&e))))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if line.last() == Some(&terminator) {
+            line.pop();
+        }
+
+        let check = match self.options.order_check {
+            OrderCheck::Enabled => true,
+            OrderCheck::Default => self.seen_unpairable,
+            OrderCheck::Disabled => false,
+        };
+        let previous = self.inputs[file].previous.replace(line.clone());
+        if check && !self.inputs[file].warned {
+            if let Some(previous) = previous {
+                if self.collator.compare(&previous, &line) == Ordering::Greater {
+                    let message = format!("file {} is not in sorted order", file + 1);
+                    if self.options.order_check == OrderCheck::Enabled {
+                        return Err(Failure::Other(message));
+                    }
+                    eprintln!("comm: {}", message);
+                    self.inputs[file].warned = true;
+                }
+            }
+        }
+        Ok(Some(line))
+    }
+
+    // Column 0 holds lines only in file 1, 1 lines only in file 2 and 2
+    // lines in both; each is indented past the columns being shown
+    fn write_column(&mut self, column: usize, line: &[u8], out: &mut impl Write) -> io::Result<()> {
+        self.counts[column] += 1;
+        if self.options.suppress[column] {
+            return Ok(());
+        }
+        let indent = self.options.suppress[..column]
+            .iter()
+            .filter(|&&suppressed| !suppressed)
+            .count();
+        for _ in 0..indent {
+            out.write_all(&self.options.output_delimiter)?;
+        }
+        out.write_all(line)?;
+        out.write_all(&[self.terminator()])
+    }
+
+    fn run(&mut self, out: &mut impl Write) -> Result<(), Failure> {
+        let mut current = [self.advance(0)?, self.advance(1)?];
+        loop {
+            let (column, advance) = match (&current[0], &current[1]) {
+                (None, None) => break,
+                (Some(_), None) => (0, [true, false]),
+                (None, Some(_)) => (1, [false, true]),
+                (Some(first), Some(second)) => match self.collator.compare(first, second) {
+                    Ordering::Less => (0, [true, false]),
+                    Ordering::Greater => (1, [false, true]),
+                    Ordering::Equal => (2, [true, true]),
+                },
+            };
+            if column != 2 {
+                self.seen_unpairable = true;
+            }
+            // Common lines are printed as file 1 has them
+            let file = if column == 1 { 1 } else { 0 };
+            let line = current[file].clone().unwrap_or_default();
+            self.write_column(column, &line, out)
+                .map_err(Failure::Write)?;
+            for file in 0..2 {
+                if advance[file] {
+                    current[file] = self.advance(file)?;
+                }
+            }
+        }
+
+        if self.options.total {
+            let delimiter = &self.options.output_delimiter;
+            for count in self.counts {
+                out.write_all(count.to_string().as_bytes())
+                    .and_then(|_| out.write_all(delimiter))
+                    .map_err(Failure::Write)?;
+            }
+            out.write_all(b"total")
+                .and_then(|_| out.write_all(&[self.terminator()]))
+                .map_err(Failure::Write)?;
+        }
+
+        out.flush().map_err(Failure::Write)?;
+        if self.inputs.iter().any(|input| input.warned) {
+            return Err(Failure::Other(String::from("input is not in sorted order")));
+        }
+        Ok(())
+    }
+}
+
+fn open(name: &str) -> Result<Input, Failure> {
+    let reader: Box<dyn BufRead> = if name == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        let file = File::open(name)
+            .map_err(|e| Failure::Other(format!("{}: {}", name, error::message(&e))))?;
+        Box::new(BufReader::new(file))
+    };
+    Ok(Input {
+        name: name.to_string(),
+        reader,
+        previous: None,
+        warned: false,
+    })
+}
+
+pub fn run(first: &str, second: &str, options: &CommOptions) -> Result<(), Failure> {
+    let mut command = CommCommand {
+        options,
+        collator: Collator::from_environment(),
+        inputs: [open(first)?, open(second)?],
+        seen_unpairable: false,
+        counts: [0; 3],
+    };
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    command.run(&mut out)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("comm: {}", message);
+    std::process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = CommOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--check-order" => options.order_check = OrderCheck::Enabled,
+            "--nocheck-order" => options.order_check = OrderCheck::Disabled,
+            "--total" => options.total = true,
+            "--zero-terminated" => options.zero_terminated = true,
+            // An empty delimiter separates the columns with NUL
+            _ if arg.starts_with("--output-delimiter=") => {
+                let delimiter = &arg["--output-delimiter=".len()..];
+                options.output_delimiter = if delimiter.is_empty() {
+                    vec![0]
+                } else {
+                    delimiter.as_bytes().to_vec()
+                };
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        '1' => options.suppress[0] = true,
+                        '2' => options.suppress[1] = true,
+                        '3' => options.suppress[2] = true,
+                        'z' => options.zero_terminated = true,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    match files.len() {
+        0 => fail("missing operand"),
+        1 => fail(&format!("missing operand after '{}'", files[0])),
+        2 => {}
+        _ => fail(&format!("extra operand '{}'", files[2])),
+    }
+
+    match run(&files[0], &files[1], &options) {
+        Ok(()) => {}
+        Err(Failure::Write(e)) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(1),
+        Err(Failure::Write(e)) => fail(&format!("write error: {}", error::message(&e))),
+        Err(Failure::Other(message)) => fail(&message),
+    }
+}
@@ -0,0 +1,299 @@
+// The command line shared by gzip, gunzip and zcat, which differ only in
+// their defaults: gunzip decompresses, and zcat also writes to standard
+// output.
+
+use crate::error;
+use crate::gz;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, IsTerminal, Read, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+use std::time::SystemTime;
+
+const SUFFIX: &str = ".gz";
+
+pub struct CompressOptions {
+    // -d: decompress instead of compressing
+    pub decompress: bool,
+    // -c: write to standard output and leave the files alone
+    pub stdout: bool,
+    // -k: keep the input files
+    pub keep: bool,
+    // -f: overwrite outputs and read or write terminals
+    pub force: bool,
+    // -1 to -9: compression level, from fastest to smallest
+    pub level: u32,
+}
+
+impl Default for CompressOptions {
+    fn default() -> Self {
+        CompressOptions {
+            decompress: false,
+            stdout: false,
+            keep: false,
+            force: false,
+            level: 6,
+        }
+    }
+}
+
+// gzip exits 2 for warnings, such as files skipped, and 1 for errors;
+// errors win when there are both
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+struct CompressCommand<'a> {
+    program: &'a str,
+    options: &'a CompressOptions,
+    status: Status,
+}
+
+impl<'a> CompressCommand<'a> {
+    fn warn(&mut self, message: &str) {
+        eprintln!("{}: {}", self.program, message);
+        self.status = self.status.max(Status::Warning);
+    }
+
+    fn error(&mut self, message: &str) {
+        eprintln!("{}: {}", self.program, message);
+        self.status = Status::Error;
+    }
+
+    // Copies input to output through the encoder or decoder
+    fn transform(
+        &self,
+        input: impl Read,
+        output: impl Write,
+        name: Option<&str>,
+        mtime: u32,
+    ) -> io::Result<()> {
+        let mut input = BufReader::new(input);
+        let mut output = BufWriter::new(output);
+        if self.options.decompress {
+            let mut decoder = gz::Decoder::new(input)?;
+            io::copy(&mut decoder, &mut output)?;
+            output.flush()
+        } else {
+            let mut encoder = gz::Encoder::new(output, self.options.level, name, mtime)?;
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?.flush()
+        }
+    }
+
+    fn process_stdin(&mut self) {
+        if !self.options.force {
+            if self.options.decompress && io::stdin().is_terminal() {
+                self.error(
+                    "compressed data not read from a terminal. Use -f to force decompression.",
+                );
+                return;
+            }
+            if !self.options.decompress && io::stdout().is_terminal() {
+                self.error(
+                    "compressed data not written to a terminal. Use -f to force compression.",
+                );
+                return;
+            }
+        }
+        let result = self.transform(io::stdin().lock(), io::stdout().lock(), None, 0);
+        self.report(result, "stdin");
+    }
+
+    fn report(&mut self, result: io::Result<()>, name: &str) {
+        match result {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(1),
+            Err(e) if e.to_string() == gz::TRAILING_GARBAGE => {
+                self.warn(&format!("{}: {}", name, e))
+            }
+            Err(e) => self.error(&format!("{}: {}", name, error_message(&e))),
+        }
+    }
+
+    // The name the output of a file goes to, or None to skip it
+    fn output_name(&mut self, file: &str) -> Option<String> {
+        if self.options.decompress {
+            if let Some(stem) = file.strip_suffix(SUFFIX) {
+                if !stem.is_empty() && !stem.ends_with('/') {
+                    return Some(stem.to_string());
+                }
+            }
+            if let Some(stem) = file.strip_suffix(".tgz") {
+                return Some(format!("{}.tar", stem));
+            }
+            if self.options.stdout {
+                return Some(file.to_string());
+            }
+            self.warn(&format!("{}: unknown suffix -- ignored", file));
+            None
+        } else {
+            if file.ends_with(SUFFIX) && !self.options.stdout {
+                self.warn(&format!(
+                    "{} already has {} suffix -- unchanged",
+                    file, SUFFIX
+                ));
+                return None;
+            }
+            Some(format!("{}{}", file, SUFFIX))
+        }
+    }
+
+    fn process_file(&mut self, file: &str) {
+        let metadata = match fs::metadata(file) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                self.error(&format!("{}: {}", file, error_message(&e)));
+                return;
+            }
+        };
+        if metadata.is_dir() {
+            self.warn(&format!("{} is a directory -- ignored", file));
+            return;
+        }
+        let Some(target) = self.output_name(file) else {
+            return;
+        };
+        let input = match File::open(file) {
+            Ok(input) => input,
+            Err(e) => {
+                self.error(&format!("{}: {}", file, error_message(&e)));
+                return;
+            }
+        };
+
+        // The header keeps the original base name and time, as gzip does
+        let name = Path::new(file)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        let mtime = metadata.mtime().clamp(0, u32::MAX as i64) as u32;
+
+        if self.options.stdout {
+            let result = self.transform(input, io::stdout().lock(), name.as_deref(), mtime);
+            self.report(result, file);
+            return;
+        }
+
+        let mut open = OpenOptions::new();
+        open.write(true).mode(0o600);
+        if self.options.force {
+            open.create(true).truncate(true);
+        } else {
+            open.create_new(true);
+        }
+        let output = match open.open(&target) {
+            Ok(output) => output,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                self.warn(&format!("{} already exists; not overwritten", target));
+                return;
+            }
+            Err(e) => {
+                self.error(&format!("{}: {}", target, error_message(&e)));
+                return;
+            }
+        };
+
+        let result = output
+            .try_clone()
+            .and_then(|writer| self.transform(input, writer, name.as_deref(), mtime))
+            .and_then(|_| {
+                output.set_permissions(fs::Permissions::from_mode(metadata.mode() & 0o7777))?;
+                output.set_modified(metadata.modified().unwrap_or(SystemTime::now()))
+            });
+        match result {
+            Ok(()) => {
+                if !self.options.keep {
+                    if let Err(e) = fs::remove_file(file) {
+                        self.error(&format!("{}: {}", file, error_message(&e)));
+                    }
+                }
+            }
+            Err(e) => {
+                // A half-written output is worse than none
+                let _ = fs::remove_file(&target);
+                self.report(Err(e), file);
+            }
+        }
+    }
+
+    fn run(&mut self, files: &[String]) -> Status {
+        for file in files {
+            if file == "-" {
+                self.process_stdin();
+            } else {
+                self.process_file(file);
+            }
+        }
+        self.status
+    }
+}
+
+// A truncated stream is reported in gzip's words
+fn error_message(e: &io::Error) -> String {
+    match e.kind() {
+        io::ErrorKind::UnexpectedEof => String::from("unexpected end of file"),
+        _ => error::message(e),
+    }
+}
+
+// The whole command: parses the arguments, processes the files and exits.
+// `options` holds the defaults the program name implies.
+pub fn main(program: &str, mut options: CompressOptions) -> ! {
+    let fail = |message: &str| -> ! {
+        eprintln!("{}: {}", program, message);
+        std::process::exit(1);
+    };
+
+    let args: Vec<String> = env::args().collect();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--stdout" | "--to-stdout" => options.stdout = true,
+            "--decompress" | "--uncompress" => options.decompress = true,
+            "--keep" => options.keep = true,
+            "--force" => options.force = true,
+            "--fast" => options.level = 1,
+            "--best" => options.level = 9,
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'c' => options.stdout = true,
+                        'd' => options.decompress = true,
+                        'k' => options.keep = true,
+                        'f' => options.force = true,
+                        '1'..='9' => options.level = flag.to_digit(10).unwrap(),
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    let mut command = CompressCommand {
+        program,
+        options: &options,
+        status: Status::Ok,
+    };
+    match command.run(&files) {
+        Status::Ok => std::process::exit(0),
+        Status::Error => std::process::exit(1),
+        Status::Warning => std::process::exit(2),
+    }
+}
@@ -0,0 +1,155 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{FileExt, MetadataExt};
+
+// Copying the contents of one file to another, keeping the holes of sparse
+// files. A hole is a range the file system has no blocks for and reads as
+// zeros; writing those zeros out would give the copy blocks for all of it,
+// so a 100G disk image with 2G of data would take 100G.
+
+const BUFFER_SIZE: usize = 128 * 1024;
+
+// When the copy gets holes, as cp's --sparse takes it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sparse {
+    // Write every byte, so the copy has no holes
+    Never,
+    // Keep the holes the source has
+    #[default]
+    Auto,
+    // Also turn runs of zeros in the data into holes
+    Always,
+}
+
+impl Sparse {
+    pub fn parse(text: &str) -> Option<Sparse> {
+        match text {
+            "never" => Some(Sparse::Never),
+            "auto" => Some(Sparse::Auto),
+            "always" => Some(Sparse::Always),
+            _ => None,
+        }
+    }
+}
+
+// The offset where the next data or hole at or after `offset` starts, or
+// None past the last data
+fn seek_to(file: &File, offset: u64, whence: libc::c_int) -> io::Result<Option<u64>> {
+    let position = unsafe { libc::lseek(file.as_raw_fd(), offset as libc::off_t, whence) };
+    if position < 0 {
+        let e = io::Error::last_os_error();
+        return if e.raw_os_error() == Some(libc::ENXIO) {
+            Ok(None)
+        } else {
+            Err(e)
+        };
+    }
+    Ok(Some(position as u64))
+}
+
+// The ranges of a file that hold data, as start and end offsets. None when
+// the file system cannot tell data from holes.
+fn data_extents(file: &File, size: u64) -> Option<Vec<(u64, u64)>> {
+    let mut extents = Vec::new();
+    let mut offset = 0;
+    while offset < size {
+        let start = match seek_to(file, offset, libc::SEEK_DATA).ok()? {
+            Some(start) if start < size => start,
+            _ => break,
+        };
+        let end = seek_to(file, start, libc::SEEK_HOLE)
+            .ok()?
+            .unwrap_or(size)
+            .min(size);
+        extents.push((start, end));
+        offset = end;
+    }
+    Some(extents)
+}
+
+// Copies the contents of `input` to `output`, which should be empty, and
+// returns how many bytes that was. `progress` is told the total so far
+// after each block, and can stop the copy by returning an error.
+//
+// Holes are only made in a regular file. A regular source whose blocks do
+// not cover its size has holes, and only the ranges with data are copied;
+// with Always, blocks of zeros are skipped over too.
+pub fn copy_data(
+    input: &mut File,
+    output: &mut File,
+    sparse: Sparse,
+    progress: &mut dyn FnMut(u64) -> io::Result<()>,
+) -> io::Result<u64> {
+    let metadata = input.metadata()?;
+    let holes = sparse != Sparse::Never && output.metadata()?.is_file();
+    if !holes || !metadata.is_file() {
+        return copy_stream(input, output, holes && sparse == Sparse::Always, progress);
+    }
+
+    let size = metadata.len();
+    let extents = if metadata.blocks() * 512 < size {
+        data_extents(input, size)
+    } else {
+        None
+    };
+    let extents = extents.unwrap_or_else(|| vec![(0, size)]);
+    let skip_zeros = sparse == Sparse::Always;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut copied = 0;
+    for (start, end) in extents {
+        let mut offset = start;
+        while offset < end {
+            let wanted = (end - offset).min(BUFFER_SIZE as u64) as usize;
+            let read = match input.read_at(&mut buffer[..wanted], offset) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let block = &buffer[..read];
+            if !(skip_zeros && block.iter().all(|&b| b == 0)) {
+                output.write_all_at(block, offset)?;
+            }
+            offset += read as u64;
+            copied += read as u64;
+            progress(copied)?;
+        }
+    }
+    // A hole at the end has no data to write, so the size is set instead
+    output.set_len(size)?;
+    Ok(copied)
+}
+
+// Copies input that cannot be read at offsets, such as a pipe, in order
+fn copy_stream(
+    input: &mut File,
+    output: &mut File,
+    skip_zeros: bool,
+    progress: &mut dyn FnMut(u64) -> io::Result<()>,
+) -> io::Result<u64> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut copied = 0;
+    let mut trailing_hole = false;
+    loop {
+        let read = match input.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let block = &buffer[..read];
+        trailing_hole = skip_zeros && block.iter().all(|&b| b == 0);
+        if trailing_hole {
+            output.seek(SeekFrom::Current(read as i64))?;
+        } else {
+            output.write_all(block)?;
+        }
+        copied += read as u64;
+        progress(copied)?;
+    }
+    if trailing_hole {
+        output.set_len(copied)?;
+    }
+    Ok(copied)
+}
@@ -0,0 +1,468 @@
+use cmds_lite::copy::{self, Sparse};
+use cmds_lite::datetime::Timestamp;
+use cmds_lite::error;
+use cmds_lite::mode;
+use cmds_lite::platform;
+use cmds_lite::walk::{self, WalkOptions, Walker};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::{
+    self as unix_fs, DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt,
+};
+use std::path::{Path, PathBuf};
+
+#[derive(Default)]
+pub struct CpOptions {
+    // -r, -R: copy directories and everything in them; symbolic links
+    // are then copied as links
+    pub recursive: bool,
+    // -p: give the copies the mode, owner and timestamps of the sources
+    pub preserve: bool,
+    // -f: remove a destination that cannot be opened and try again
+    pub force: bool,
+    // -n: leave existing destinations alone
+    pub no_clobber: bool,
+    // -v: report each file copied
+    pub verbose: bool,
+    // -t: copy every source into this directory
+    pub target_directory: Option<String>,
+    // -T: the destination is a file, even when it names a directory
+    pub no_target_directory: bool,
+    // --sparse: when copies get holes
+    pub sparse: Sparse,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("cp: {}", message);
+    std::process::exit(1);
+}
+
+struct CpCommand<'a> {
+    options: &'a CpOptions,
+}
+
+impl<'a> CpCommand<'a> {
+    // Mode, owner and timestamps for -p. The owner can only be given away
+    // by root, so failing to set it is not an error, as with GNU cp.
+    fn preserve(&self, metadata: &fs::Metadata, dest: &Path) -> Result<(), String> {
+        let is_link = metadata.file_type().is_symlink();
+        let _ = platform::change_owner(dest, Some(metadata.uid()), Some(metadata.gid()), !is_link);
+        if !is_link {
+            fs::set_permissions(dest, fs::Permissions::from_mode(metadata.mode() & 0o7777))
+                .map_err(|e| {
+                    format!(
+                        "preserving permissions for '{}': {}",
+                        dest.display(),
+                        error::message(&e)
+                    )
+                })?;
+        }
+        let atime = Timestamp::new(metadata.atime(), metadata.atime_nsec() as u32);
+        let mtime = Timestamp::new(metadata.mtime(), metadata.mtime_nsec() as u32);
+        platform::set_times(dest, atime, mtime, !is_link).map_err(|e| {
+            format!(
+                "preserving times for '{}': {}",
+                dest.display(),
+                error::message(&e)
+            )
+        })
+    }
+
+    // Opens the destination for writing, emptying it if it exists. A new
+    // file gets the permissions of the source, less the umask.
+    fn create(&self, dest: &Path, metadata: &fs::Metadata) -> io::Result<File> {
+        let open = || {
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .mode(metadata.mode() & 0o777)
+                .open(dest)
+        };
+        match open() {
+            Err(e) if self.options.force && e.kind() != io::ErrorKind::NotFound => {
+                fs::remove_file(dest)?;
+                open()
+            }
+            result => result,
+        }
+    }
+
+    fn copy_file(&self, source: &Path, metadata: &fs::Metadata, dest: &Path) -> Result<(), String> {
+        if let Ok(existing) = fs::metadata(dest) {
+            if existing.dev() == metadata.dev() && existing.ino() == metadata.ino() {
+                return Err(format!(
+                    "'{}' and '{}' are the same file",
+                    source.display(),
+                    dest.display()
+                ));
+            }
+            if existing.is_dir() {
+                return Err(format!(
+                    "cannot overwrite directory '{}' with non-directory",
+                    dest.display()
+                ));
+            }
+            if self.options.no_clobber {
+                return Ok(());
+            }
+        }
+
+        let mut input = File::open(source).map_err(|e| {
+            format!(
+                "cannot open '{}' for reading: {}",
+                source.display(),
+                error::message(&e)
+            )
+        })?;
+        let mut output = self.create(dest, metadata).map_err(|e| {
+            format!(
+                "cannot create regular file '{}': {}",
+                dest.display(),
+                error::message(&e)
+            )
+        })?;
+        copy::copy_data(
+            &mut input,
+            &mut output,
+            self.options.sparse,
+            &mut |_| Ok(()),
+        )
+        .map_err(|e| {
+            format!(
+                "error copying '{}' to '{}': {}",
+                source.display(),
+                dest.display(),
+                error::message(&e)
+            )
+        })?;
+        drop(output);
+
+        if self.options.preserve {
+            self.preserve(metadata, dest)?;
+        }
+        self.report(source, dest);
+        Ok(())
+    }
+
+    fn copy_symlink(
+        &self,
+        source: &Path,
+        metadata: &fs::Metadata,
+        dest: &Path,
+    ) -> Result<(), String> {
+        let target = fs::read_link(source).map_err(|e| {
+            format!(
+                "cannot read symbolic link '{}': {}",
+                source.display(),
+                error::message(&e)
+            )
+        })?;
+        let exists = fs::symlink_metadata(dest).is_ok();
+        if exists && self.options.no_clobber {
+            return Ok(());
+        }
+        if exists && self.options.force {
+            let _ = fs::remove_file(dest);
+        }
+        unix_fs::symlink(&target, dest).map_err(|e| {
+            format!(
+                "cannot create symbolic link '{}': {}",
+                dest.display(),
+                error::message(&e)
+            )
+        })?;
+        if self.options.preserve {
+            self.preserve(metadata, dest)?;
+        }
+        self.report(source, dest);
+        Ok(())
+    }
+
+    // Copies a directory and everything in it. The directories are kept
+    // writable while they are filled, and get their final mode and times
+    // once the walk is over, deepest first.
+    fn copy_tree(&self, source: &Path, dest: &Path) -> bool {
+        if let (Ok(from), Ok(to)) = (
+            fs::canonicalize(source),
+            dest.parent().map_or(Ok(PathBuf::new()), fs::canonicalize),
+        ) {
+            let to = to.join(dest.file_name().unwrap_or_default());
+            if to.starts_with(&from) {
+                eprintln!(
+                    "cp: cannot copy a directory, '{}', into itself, '{}'",
+                    source.display(),
+                    dest.display()
+                );
+                return false;
+            }
+        }
+
+        let mut ok = true;
+        let mut directories = Vec::new();
+        for item in Walker::new(&[source], WalkOptions::default()) {
+            let entry = match item {
+                Ok(entry) => entry,
+                Err(e) => {
+                    eprintln!("cp: cannot access {}", e);
+                    ok = false;
+                    continue;
+                }
+            };
+            let relative = entry.path.strip_prefix(source).unwrap_or(&entry.path);
+            let target = if relative.as_os_str().is_empty() {
+                dest.to_path_buf()
+            } else {
+                dest.join(relative)
+            };
+            let file_type = entry.metadata.file_type();
+            let result = if file_type.is_dir() {
+                self.create_directory(&entry.path, &entry.metadata, &target)
+                    .map(|()| directories.push((target, entry.metadata)))
+            } else if file_type.is_symlink() {
+                self.copy_symlink(&entry.path, &entry.metadata, &target)
+            } else if file_type.is_file() {
+                self.copy_file(&entry.path, &entry.metadata, &target)
+            } else {
+                Err(format!(
+                    "cannot copy special file '{}'",
+                    entry.path.display()
+                ))
+            };
+            if let Err(message) = result {
+                eprintln!("cp: {}", message);
+                ok = false;
+            }
+        }
+
+        for (path, metadata) in directories.iter().rev() {
+            let result = if self.options.preserve {
+                self.preserve(metadata, path)
+            } else {
+                let mode = metadata.mode() & 0o777 & !mode::current_umask();
+                fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|e| {
+                    format!(
+                        "setting permissions for '{}': {}",
+                        path.display(),
+                        error::message(&e)
+                    )
+                })
+            };
+            if let Err(message) = result {
+                eprintln!("cp: {}", message);
+                ok = false;
+            }
+        }
+        ok
+    }
+
+    fn create_directory(
+        &self,
+        source: &Path,
+        metadata: &fs::Metadata,
+        dest: &Path,
+    ) -> Result<(), String> {
+        match fs::metadata(dest) {
+            Ok(existing) if existing.is_dir() => return Ok(()),
+            Ok(_) => {
+                return Err(format!(
+                    "cannot overwrite non-directory '{}' with directory '{}'",
+                    dest.display(),
+                    source.display()
+                ))
+            }
+            Err(_) => {}
+        }
+        fs::DirBuilder::new()
+            .mode(metadata.mode() & 0o777 | 0o700)
+            .create(dest)
+            .map_err(|e| {
+                format!(
+                    "cannot create directory '{}': {}",
+                    dest.display(),
+                    error::message(&e)
+                )
+            })?;
+        self.report(source, dest);
+        Ok(())
+    }
+
+    fn report(&self, source: &Path, dest: &Path) {
+        if self.options.verbose {
+            println!("'{}' -> '{}'", source.display(), dest.display());
+        }
+    }
+
+    // Copies one operand. Without -r, symbolic links are followed and
+    // directories are left out.
+    fn copy(&self, source: &str, dest: &Path) -> bool {
+        let path = Path::new(source);
+        let metadata = if self.options.recursive {
+            fs::symlink_metadata(path)
+        } else {
+            fs::metadata(path)
+        };
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                eprintln!(
+                    "cp: cannot stat {}",
+                    walk::WalkError {
+                        path: path.to_path_buf(),
+                        error
+                    }
+                );
+                return false;
+            }
+        };
+
+        let result = if metadata.is_dir() {
+            if !self.options.recursive {
+                Err(format!("-r not specified; omitting directory '{}'", source))
+            } else {
+                return self.copy_tree(path, dest);
+            }
+        } else if metadata.file_type().is_symlink() {
+            self.copy_symlink(path, &metadata, dest)
+        } else {
+            self.copy_file(path, &metadata, dest)
+        };
+        match result {
+            Ok(()) => true,
+            Err(message) => {
+                eprintln!("cp: {}", message);
+                false
+            }
+        }
+    }
+
+    fn copy_into(&self, sources: &[String], dir: &str) -> bool {
+        match fs::metadata(dir) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => {
+                eprintln!("cp: target '{}' is not a directory", dir);
+                return false;
+            }
+            Err(e) => {
+                eprintln!("cp: failed to access '{}': {}", dir, error::message(&e));
+                return false;
+            }
+        }
+
+        let mut ok = true;
+        for source in sources {
+            let name = Path::new(source)
+                .file_name()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(source));
+            ok &= self.copy(source, &Path::new(dir).join(name));
+        }
+        ok
+    }
+
+    fn run(&self, operands: &[String]) -> bool {
+        if let Some(dir) = &self.options.target_directory {
+            return self.copy_into(operands, dir);
+        }
+        let (dest, sources) = operands.split_last().unwrap_or_else(|| unreachable!());
+        if !self.options.no_target_directory && (sources.len() > 1 || Path::new(dest).is_dir()) {
+            return self.copy_into(sources, dest);
+        }
+        self.copy(&sources[0], Path::new(dest))
+    }
+}
+
+fn parse_sparse(text: &str) -> Sparse {
+    Sparse::parse(text)
+        .unwrap_or_else(|| fail(&format!("invalid argument '{}' for '--sparse'", text)))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = CpOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--recursive" => options.recursive = true,
+            "--force" => options.force = true,
+            "--no-clobber" => options.no_clobber = true,
+            "--verbose" => options.verbose = true,
+            "--no-target-directory" => options.no_target_directory = true,
+            _ if arg.starts_with("--sparse=") => {
+                options.sparse = parse_sparse(&arg["--sparse=".len()..])
+            }
+            _ if arg.starts_with("--target-directory=") => {
+                options.target_directory = Some(arg["--target-directory=".len()..].to_string())
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'r' | 'R' => options.recursive = true,
+                        'p' => options.preserve = true,
+                        'f' => {
+                            options.force = true;
+                            options.no_clobber = false;
+                        }
+                        'n' => {
+                            options.no_clobber = true;
+                            options.force = false;
+                        }
+                        'v' => options.verbose = true,
+                        'T' => options.no_target_directory = true,
+                        't' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail("option requires an argument -- 't'");
+                            };
+                            options.target_directory = Some(value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if options.target_directory.is_some() && options.no_target_directory {
+        fail("cannot combine --target-directory (-t) and --no-target-directory (-T)");
+    }
+    if operands.is_empty() {
+        fail("missing file operand");
+    }
+    if options.target_directory.is_none() {
+        if operands.len() == 1 {
+            fail(&format!(
+                "missing destination file operand after '{}'",
+                operands[0]
+            ));
+        }
+        if options.no_target_directory && operands.len() > 2 {
+            fail(&format!("extra operand '{}'", operands[2]));
+        }
+    }
+
+    let command = CpCommand { options: &options };
+    if !command.run(&operands) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,193 @@
+// Cyclic redundancy checks: the POSIX one that cksum prints by default and
+// the reflected CRC-32 variants, all driven by 256-entry lookup tables.
+
+use std::io::{self, Read};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    // POSIX cksum: unreflected, with the input length folded in at the end
+    Posix,
+    // ISO-HDLC CRC-32, as used by gzip and zip
+    Crc32,
+    // Castagnoli CRC-32C, as used by iSCSI and ext4
+    Crc32c,
+}
+
+impl Algorithm {
+    // Parses a --algorithm value
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        match name {
+            "crc" => Some(Algorithm::Posix),
+            "crc32" | "crc32b" => Some(Algorithm::Crc32),
+            "crc32c" => Some(Algorithm::Crc32c),
+            _ => None,
+        }
+    }
+}
+
+const fn forward_table(polynomial: u32) -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                crc << 1 ^ polynomial
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+const fn reflected_table(polynomial: u32) -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                crc >> 1 ^ polynomial
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+const POSIX_TABLE: [u32; 256] = forward_table(0x04c1_1db7);
+const CRC32_TABLE: [u32; 256] = reflected_table(0xedb8_8320);
+const CRC32C_TABLE: [u32; 256] = reflected_table(0x82f6_3b78);
+
+pub struct Crc {
+    algorithm: Algorithm,
+    value: u32,
+    length: u64,
+}
+
+impl Crc {
+    pub fn new(algorithm: Algorithm) -> Self {
+        let value = match algorithm {
+            Algorithm::Posix => 0,
+            Algorithm::Crc32 | Algorithm::Crc32c => !0,
+        };
+        Crc {
+            algorithm,
+            value,
+            length: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+        self.value = match self.algorithm {
+            Algorithm::Posix => posix_update(self.value, data),
+            Algorithm::Crc32 => reflected_update(&CRC32_TABLE, self.value, data),
+            Algorithm::Crc32c => reflected_update(&CRC32C_TABLE, self.value, data),
+        };
+    }
+
+    // Bytes passed to update so far
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    pub fn finish(&self) -> u32 {
+        match self.algorithm {
+            Algorithm::Posix => {
+                // The length goes in low byte first, in as few bytes as it needs
+                let mut value = self.value;
+                let mut length = self.length;
+                while length > 0 {
+                    value = posix_update(value, &[length as u8]);
+                    length >>= 8;
+                }
+                !value
+            }
+            Algorithm::Crc32 | Algorithm::Crc32c => !self.value,
+        }
+    }
+
+    // Runs the whole of a reader through a new CRC, returning it with the
+    // number of bytes read
+    pub fn of_reader(algorithm: Algorithm, reader: &mut impl Read) -> io::Result<(u32, u64)> {
+        let mut crc = Crc::new(algorithm);
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok((crc.finish(), crc.length())),
+                Ok(read) => crc.update(&buffer[..read]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn posix_update(mut value: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        value = value << 8 ^ POSIX_TABLE[((value >> 24) as u8 ^ byte) as usize];
+    }
+    value
+}
+
+fn reflected_update(table: &[u32; 256], mut value: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        value = value >> 8 ^ table[(value as u8 ^ byte) as usize];
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checksum(algorithm: Algorithm, data: &[u8]) -> u32 {
+        let mut crc = Crc::new(algorithm);
+        crc.update(data);
+        crc.finish()
+    }
+
+    #[test]
+    fn check_values() {
+        for (algorithm, empty, digits) in [
+            (Algorithm::Posix, 4_294_967_295, 930_766_865),
+            (Algorithm::Crc32, 0, 0xcbf4_3926),
+            (Algorithm::Crc32c, 0, 0xe306_9283),
+        ] {
+            assert_eq!(checksum(algorithm, b""), empty);
+            assert_eq!(checksum(algorithm, b"123456789"), digits);
+        }
+    }
+
+    #[test]
+    fn updates_in_pieces() {
+        let mut crc = Crc::new(Algorithm::Posix);
+        crc.update(b"1234");
+        crc.update(b"");
+        crc.update(b"56789");
+        assert_eq!(crc.length(), 9);
+        assert_eq!(crc.finish(), 930_766_865);
+
+        let (value, length) = Crc::of_reader(Algorithm::Crc32, &mut &b"123456789"[..]).unwrap();
+        assert_eq!((value, length), (0xcbf4_3926, 9));
+    }
+
+    #[test]
+    fn algorithm_names() {
+        assert!(Algorithm::from_name("crc") == Some(Algorithm::Posix));
+        assert!(Algorithm::from_name("crc32b") == Some(Algorithm::Crc32));
+        assert!(Algorithm::from_name("crc32c") == Some(Algorithm::Crc32c));
+        assert!(Algorithm::from_name("md5").is_none());
+    }
+}
@@ -0,0 +1,300 @@
+use cmds_lite::error;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+// Sorted, non-overlapping 1-based inclusive ranges such as `1,3-5,7-`
+pub struct RangeList {
+    ranges: Vec<(usize, usize)>,
+}
+
+impl RangeList {
+    pub fn parse(text: &str) -> Result<RangeList, String> {
+        let mut ranges = Vec::new();
+        for part in text.split([',', ' ']) {
+            if part.is_empty() {
+                return Err(format!("invalid range list '{}'", text));
+            }
+            let number = |value: &str| {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid byte, character or field list '{}'", text))
+            };
+            let (start, end) = match part.split_once('-') {
+                Some(("", "")) => {
+                    return Err(String::from("invalid range with no endpoint: -"));
+                }
+                Some((start, end)) => {
+                    // Open-ended ranges run from the first or to the last position
+                    let start = if start.is_empty() { 1 } else { number(start)? };
+                    let end = if end.is_empty() {
+                        usize::MAX
+                    } else {
+                        number(end)?
+                    };
+                    (start, end)
+                }
+                None => {
+                    let value = number(part)?;
+                    (value, value)
+                }
+            };
+            if start == 0 || end == 0 {
+                return Err(String::from("fields and positions are numbered from 1"));
+            }
+            if start > end {
+                return Err(String::from("invalid decreasing range"));
+            }
+            ranges.push((start, end));
+        }
+
+        ranges.sort();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        Ok(RangeList { ranges: merged })
+    }
+
+    pub fn contains(&self, position: usize) -> bool {
+        self.ranges
+            .iter()
+            .any(|(start, end)| *start <= position && position <= *end)
+    }
+}
+
+pub enum Selection {
+    Bytes(RangeList),
+    Characters(RangeList),
+    Fields {
+        list: RangeList,
+        delimiter: u8,
+        // -s: drop lines that contain no delimiter at all
+        only_delimited: bool,
+    },
+}
+
+struct CutCommand<'a> {
+    selection: &'a Selection,
+}
+
+impl<'a> CutCommand<'a> {
+    fn new(selection: &'a Selection) -> Self {
+        CutCommand { selection }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let stdout = io::stdout();
+        let mut output = BufWriter::new(stdout.lock());
+        let mut ok = true;
+
+        for file in files {
+            let result = if file == "-" {
+                self.cut(io::stdin().lock(), &mut output)
+            } else {
+                File::open(file).and_then(|f| self.cut(BufReader::new(f), &mut output))
+            };
+            if let Err(e) = result {
+                eprintln!("cut: {}: {}", file, error::message(&e));
+                ok = false;
+            }
+        }
+
+        if let Err(e) = output.flush() {
+            eprintln!("cut: write error: {}", error::message(&e));
+            ok = false;
+        }
+        ok
+    }
+
+    fn cut<R: BufRead, W: Write>(&self, mut input: R, output: &mut W) -> io::Result<()> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+
+            match self.selection {
+                Selection::Bytes(list) => {
+                    for (index, byte) in line.iter().enumerate() {
+                        if list.contains(index + 1) {
+                            output.write_all(&[*byte])?;
+                        }
+                    }
+                }
+                Selection::Characters(list) => match std::str::from_utf8(&line) {
+                    Ok(text) => {
+                        for (index, c) in text.chars().enumerate() {
+                            if list.contains(index + 1) {
+                                write!(output, "{}", c)?;
+                            }
+                        }
+                    }
+                    // Input that is not UTF-8 is cut by bytes
+                    Err(_) => {
+                        for (index, byte) in line.iter().enumerate() {
+                            if list.contains(index + 1) {
+                                output.write_all(&[*byte])?;
+                            }
+                        }
+                    }
+                },
+                Selection::Fields {
+                    list,
+                    delimiter,
+                    only_delimited,
+                } => {
+                    if !line.contains(delimiter) {
+                        if *only_delimited {
+                            continue;
+                        }
+                        output.write_all(&line)?;
+                        output.write_all(b"\n")?;
+                        continue;
+                    }
+                    let mut first = true;
+                    for (index, field) in line.split(|b| b == delimiter).enumerate() {
+                        if list.contains(index + 1) {
+                            if !first {
+                                output.write_all(&[*delimiter])?;
+                            }
+                            output.write_all(field)?;
+                            first = false;
+                        }
+                    }
+                }
+            }
+            output.write_all(b"\n")?;
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("cut: {}", message);
+    std::process::exit(1);
+}
+
+pub fn run(files: &[String], selection: &Selection) -> bool {
+    let command = CutCommand::new(selection);
+    command.run(files)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    // Which list was given (b, c or f) and its text
+    let mut list: Option<(char, String)> = None;
+    let mut delimiter: Option<String> = None;
+    let mut only_delimited = false;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            files.push(arg.clone());
+            continue;
+        }
+
+        let mut set_list = |kind: char, value: String| {
+            if list.is_some() {
+                fail("only one list may be specified");
+            }
+            list = Some((kind, value));
+        };
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--only-delimited" => only_delimited = true,
+            _ if arg.starts_with("--bytes=") => set_list('b', arg["--bytes=".len()..].to_string()),
+            _ if arg.starts_with("--characters=") => {
+                set_list('c', arg["--characters=".len()..].to_string())
+            }
+            _ if arg.starts_with("--fields=") => {
+                set_list('f', arg["--fields=".len()..].to_string())
+            }
+            _ if arg.starts_with("--delimiter=") => {
+                delimiter = Some(arg["--delimiter=".len()..].to_string());
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("cut: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        's' => only_delimited = true,
+                        // Ignored, like GNU cut
+                        'n' => {}
+                        'b' | 'c' | 'f' | 'd' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + flag.len_utf8()..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                eprintln!("cut: option requires an argument -- '{}'", flag);
+                                std::process::exit(1);
+                            };
+                            if flag == 'd' {
+                                delimiter = Some(value);
+                            } else {
+                                set_list(flag, value);
+                            }
+                            break;
+                        }
+                        _ => {
+                            eprintln!("cut: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let Some((kind, text)) = list else {
+        fail("you must specify a list of bytes, characters, or fields");
+    };
+    let ranges = RangeList::parse(&text).unwrap_or_else(|message| fail(&message));
+
+    if kind != 'f' && (delimiter.is_some() || only_delimited) {
+        fail("an input delimiter may be specified only when operating on fields");
+    }
+    let selection = match kind {
+        'b' => Selection::Bytes(ranges),
+        'c' => Selection::Characters(ranges),
+        _ => {
+            let delimiter = match delimiter.as_deref().map(str::as_bytes) {
+                None => b'\t',
+                Some([byte]) => *byte,
+                Some(_) => fail("the delimiter must be a single character"),
+            };
+            Selection::Fields {
+                list: ranges,
+                delimiter,
+                only_delimited,
+            }
+        }
+    };
+
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    if !run(&files, &selection) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,169 @@
+use cmds_lite::datetime::{self, Timestamp};
+use cmds_lite::error;
+use std::env;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+
+// Where the time to print comes from
+pub enum Source {
+    Now,
+    // -d: a date string such as "2024-01-15 12:00" or "2 days ago"
+    Date(String),
+    // -r: the modification time of a file
+    Reference(String),
+}
+
+pub struct DateOptions {
+    pub source: Source,
+    // -u: print Coordinated Universal Time instead of local time
+    pub utc: bool,
+    // +FORMAT, or the format an option such as -I or -R implies
+    pub format: Option<String>,
+}
+
+impl Default for DateOptions {
+    fn default() -> Self {
+        DateOptions {
+            source: Source::Now,
+            utc: false,
+            format: None,
+        }
+    }
+}
+
+// The format date prints in when none is given
+const DEFAULT_FORMAT: &str = "%a %b %e %H:%M:%S %Z %Y";
+
+fn fail(message: &str) -> ! {
+    eprintln!("date: {}", message);
+    std::process::exit(1);
+}
+
+fn run(options: &DateOptions) -> String {
+    // Local time is UTC from here on, so parsing -d strings without a zone
+    // and breaking the time down both use it
+    if options.utc {
+        datetime::use_utc();
+    }
+
+    let stamp = match &options.source {
+        Source::Now => Timestamp::now(),
+        Source::Date(text) => {
+            datetime::parse_date(text).unwrap_or_else(|_| fail(&format!("invalid date '{}'", text)))
+        }
+        Source::Reference(file) => match fs::metadata(file) {
+            Ok(metadata) => Timestamp::new(metadata.mtime(), metadata.mtime_nsec() as u32),
+            Err(e) => fail(&format!("{}: {}", file, error::message(&e))),
+        },
+    };
+    let time = datetime::local_time(stamp.secs)
+        .unwrap_or_else(|| fail(&format!("time {} is out of range", stamp.secs)));
+    let format = options.format.as_deref().unwrap_or(DEFAULT_FORMAT);
+    datetime::format_time(format, stamp, &time)
+}
+
+// The format for -I/--iso-8601 at a precision
+fn iso_format(precision: &str) -> String {
+    let format = match precision {
+        "" | "date" => "%Y-%m-%d",
+        "hours" => "%Y-%m-%dT%H%:z",
+        "minutes" => "%Y-%m-%dT%H:%M%:z",
+        "seconds" => "%Y-%m-%dT%H:%M:%S%:z",
+        "ns" => "%Y-%m-%dT%H:%M:%S,%N%:z",
+        _ => fail(&format!(
+            "invalid argument '{}' for '--iso-8601'",
+            precision
+        )),
+    };
+    format.to_string()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = DateOptions::default();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    let set_source = |options: &mut DateOptions, source: Source| {
+        if !matches!(options.source, Source::Now) {
+            fail("the options to specify dates for printing are mutually exclusive");
+        }
+        options.source = source;
+    };
+    let set_format = |options: &mut DateOptions, format: String| {
+        if options.format.is_some() {
+            fail("multiple output formats specified");
+        }
+        options.format = Some(format);
+    };
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if let Some(format) = arg.strip_prefix('+') {
+            set_format(&mut options, format.to_string());
+            continue;
+        }
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            fail(&format!(
+                "invalid date '{}'; setting the date is not supported",
+                arg
+            ));
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--utc" | "--universal" => options.utc = true,
+            "--iso-8601" => set_format(&mut options, iso_format("")),
+            "--rfc-email" => set_format(&mut options, String::from("%a, %d %b %Y %H:%M:%S %z")),
+            _ if arg.starts_with("--iso-8601=") => {
+                set_format(&mut options, iso_format(&arg["--iso-8601=".len()..]))
+            }
+            _ if arg.starts_with("--date=") => set_source(
+                &mut options,
+                Source::Date(arg["--date=".len()..].to_string()),
+            ),
+            _ if arg.starts_with("--reference=") => set_source(
+                &mut options,
+                Source::Reference(arg["--reference=".len()..].to_string()),
+            ),
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'u' => options.utc = true,
+                        'R' => set_format(&mut options, String::from("%a, %d %b %Y %H:%M:%S %z")),
+                        // -I takes its precision only when attached
+                        'I' => {
+                            set_format(&mut options, iso_format(&arg[pos + 1..]));
+                            break;
+                        }
+                        'd' | 'r' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            let source = if flag == 'd' {
+                                Source::Date(value)
+                            } else {
+                                Source::Reference(value)
+                            };
+                            set_source(&mut options, source);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    println!("{}", run(&options));
+}
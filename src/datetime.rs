@@ -0,0 +1,862 @@
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+// Seconds and nanoseconds relative to the Unix epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp {
+    pub secs: i64,
+    pub nanos: u32,
+}
+
+impl Timestamp {
+    pub fn new(secs: i64, nanos: u32) -> Self {
+        Timestamp { secs, nanos }
+    }
+
+    pub fn now() -> Self {
+        Timestamp::from_system_time(SystemTime::now())
+    }
+
+    pub fn from_system_time(time: SystemTime) -> Self {
+        match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(elapsed) => Timestamp::new(elapsed.as_secs() as i64, elapsed.subsec_nanos()),
+            Err(e) => {
+                // Before the epoch: keep nanos positive by borrowing a second
+                let before = e.duration();
+                let mut secs = -(before.as_secs() as i64);
+                let mut nanos = 0;
+                if before.subsec_nanos() > 0 {
+                    secs -= 1;
+                    nanos = 1_000_000_000 - before.subsec_nanos();
+                }
+                Timestamp::new(secs, nanos)
+            }
+        }
+    }
+
+    pub fn to_system_time(self) -> SystemTime {
+        if self.secs >= 0 {
+            SystemTime::UNIX_EPOCH + Duration::new(self.secs as u64, self.nanos)
+        } else {
+            SystemTime::UNIX_EPOCH - Duration::from_secs(self.secs.unsigned_abs())
+                + Duration::from_nanos(self.nanos as u64)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDateError {
+    text: String,
+}
+
+impl fmt::Display for ParseDateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid date format '{}'", self.text)
+    }
+}
+
+impl std::error::Error for ParseDateError {}
+
+// Days since 1970-01-01 for a proleptic Gregorian date
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+// Inverse of days_from_civil, returning (year, month, day)
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ if is_leap_year(year) => 29,
+        _ => 28,
+    }
+}
+
+pub fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+// Converts a wall-clock time in the local timezone to epoch seconds,
+// letting mktime work out whether daylight saving time applies.
+pub fn local_to_epoch(
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+) -> Option<i64> {
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        tm.tm_year = (year - 1900) as libc::c_int;
+        tm.tm_mon = month as libc::c_int - 1;
+        tm.tm_mday = day as libc::c_int;
+        tm.tm_hour = hour as libc::c_int;
+        tm.tm_min = minute as libc::c_int;
+        tm.tm_sec = second as libc::c_int;
+        tm.tm_isdst = -1;
+        let secs = libc::mktime(&mut tm);
+        if secs == -1 && !(tm.tm_year == 69 && tm.tm_mon == 11 && tm.tm_mday == 31) {
+            return None;
+        }
+        Some(secs as i64)
+    }
+}
+
+// A moment broken down into local wall-clock fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    // Days since Sunday, and since January 1st
+    pub weekday: u32,
+    pub year_day: u32,
+    // Seconds east of UTC, and the zone abbreviation in effect
+    pub utc_offset: i64,
+    pub zone: String,
+}
+
+pub fn local_time(secs: i64) -> Option<LocalTime> {
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        let time = secs as libc::time_t;
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            return None;
+        }
+        let zone = if tm.tm_zone.is_null() {
+            String::new()
+        } else {
+            std::ffi::CStr::from_ptr(tm.tm_zone)
+                .to_string_lossy()
+                .into_owned()
+        };
+        Some(LocalTime {
+            year: tm.tm_year as i64 + 1900,
+            month: tm.tm_mon as u32 + 1,
+            day: tm.tm_mday as u32,
+            hour: tm.tm_hour as u32,
+            minute: tm.tm_min as u32,
+            second: tm.tm_sec as u32,
+            weekday: tm.tm_wday as u32,
+            year_day: tm.tm_yday as u32,
+            utc_offset: tm.tm_gmtoff as i64,
+            zone,
+        })
+    }
+}
+
+// An offset east of UTC as +HHMM
+pub fn format_offset(offset: i64) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.unsigned_abs() / 60;
+    format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+}
+
+fn utc_to_epoch(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> i64 {
+    days_from_civil(year, month, day) * 86400
+        + hour as i64 * 3600
+        + minute as i64 * 60
+        + second as i64
+}
+
+fn valid_fields(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> bool {
+    (1..=12).contains(&month)
+        && day >= 1
+        && day <= days_in_month(year, month)
+        && hour < 24
+        && minute < 60
+        // Allow a leap second like GNU does
+        && second <= 60
+}
+
+fn parse_number(text: &str) -> Option<u32> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+// Parses ".123" style fractions into nanoseconds
+fn parse_fraction(text: &str) -> Option<u32> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut digits: String = text.chars().take(9).collect();
+    while digits.len() < 9 {
+        digits.push('0');
+    }
+    digits.parse().ok()
+}
+
+// Parses a trailing zone such as "Z", "UTC", "+05:30" or "-0800" into an
+// offset east of UTC in seconds.
+fn parse_zone(text: &str) -> Option<i64> {
+    let text = text.trim();
+    match text {
+        "Z" | "z" | "UTC" | "GMT" => return Some(0),
+        _ => {}
+    }
+    let sign = match text.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = text[1..].chars().filter(|c| *c != ':').collect();
+    let (hours, minutes) = match digits.len() {
+        2 => (parse_number(&digits)?, 0),
+        4 => (parse_number(&digits[..2])?, parse_number(&digits[2..])?),
+        _ => return None,
+    };
+    if hours > 24 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours as i64 * 3600 + minutes as i64 * 60))
+}
+
+// Parses the date forms accepted by `touch -d` and `date -d`: an absolute
+// date, relative items such as "2 days ago" counted from now, or an
+// absolute date followed by relative items.
+pub fn parse_date(text: &str) -> Result<Timestamp, ParseDateError> {
+    if let Ok(stamp) = parse_absolute(text) {
+        return Ok(stamp);
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    for split in (0..words.len()).rev() {
+        let base = if split == 0 {
+            Timestamp::now()
+        } else {
+            match parse_absolute(&words[..split].join(" ")) {
+                Ok(base) => base,
+                Err(_) => continue,
+            }
+        };
+        if let Some(stamp) = apply_relative(base, &words[split..]) {
+            return Ok(stamp);
+        }
+    }
+    Err(ParseDateError {
+        text: text.to_string(),
+    })
+}
+
+// Moves a moment by relative items: "[+-]N UNIT", "next UNIT", "last
+// UNIT" or a bare UNIT, each optionally followed by "ago", and the words
+// "yesterday", "today", "tomorrow" and "now". Days, months and years move
+// the local calendar date and keep the time of day. None if any word is
+// not understood.
+fn apply_relative(base: Timestamp, words: &[&str]) -> Option<Timestamp> {
+    let (mut seconds, mut days, mut months) = (0i64, 0i64, 0i64);
+    let mut words = words
+        .iter()
+        .map(|word| word.to_ascii_lowercase())
+        .peekable();
+
+    while let Some(word) = words.next() {
+        let (count, unit) = match word.as_str() {
+            "now" | "today" => continue,
+            "yesterday" => (-1, String::from("day")),
+            "tomorrow" => (1, String::from("day")),
+            "next" => (1, words.next()?),
+            "last" => (-1, words.next()?),
+            "this" => (0, words.next()?),
+            _ => {
+                // A count, possibly with its unit attached as in "3days"
+                let digits = word
+                    .char_indices()
+                    .find(|&(i, c)| !(c.is_ascii_digit() || (i == 0 && matches!(c, '+' | '-'))))
+                    .map_or(word.len(), |(i, _)| i);
+                match &word[..digits] {
+                    "" => (1, word.clone()),
+                    number => {
+                        let count: i64 = number.parse().ok()?;
+                        let unit = if digits < word.len() {
+                            word[digits..].to_string()
+                        } else {
+                            words.next()?
+                        };
+                        (count, unit)
+                    }
+                }
+            }
+        };
+        let count = if words.peek().is_some_and(|word| word == "ago") {
+            words.next();
+            -count
+        } else {
+            count
+        };
+        match unit.strip_suffix('s').unwrap_or(&unit) {
+            "sec" | "second" => seconds += count,
+            "min" | "minute" => seconds += count * 60,
+            "hour" => seconds += count * 3600,
+            "day" => days += count,
+            "week" => days += count * 7,
+            "fortnight" => days += count * 14,
+            "month" => months += count,
+            "year" => months += count * 12,
+            _ => return None,
+        }
+    }
+
+    let mut secs = base.secs;
+    if days != 0 || months != 0 {
+        let local = local_time(base.secs)?;
+        let total = local.year * 12 + local.month as i64 - 1 + months;
+        // A day past the end of the month runs over into the next, as
+        // with GNU date
+        let days = days_from_civil(total.div_euclid(12), total.rem_euclid(12) as u32 + 1, 1)
+            + local.day as i64
+            - 1
+            + days;
+        let (year, month, day) = civil_from_days(days);
+        secs = local_to_epoch(year, month, day, local.hour, local.minute, local.second)?;
+    }
+    Some(Timestamp::new(secs + seconds, base.nanos))
+}
+
+// Parses the absolute date forms: "@SECONDS", "now", "YYYY-MM-DD" and
+// "YYYY-MM-DD[T ]HH:MM[:SS[.frac]]", optionally followed by a zone. Times
+// without a zone are local.
+fn parse_absolute(text: &str) -> Result<Timestamp, ParseDateError> {
+    let error = || ParseDateError {
+        text: text.to_string(),
+    };
+    let trimmed = text.trim();
+
+    if trimmed.eq_ignore_ascii_case("now") {
+        return Ok(Timestamp::now());
+    }
+
+    if let Some(epoch) = trimmed.strip_prefix('@') {
+        let (whole, fraction) = match epoch.split_once('.') {
+            Some((whole, fraction)) => (whole, parse_fraction(fraction).ok_or_else(error)?),
+            None => (epoch, 0),
+        };
+        let (negative, digits) = match whole.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, whole.strip_prefix('+').unwrap_or(whole)),
+        };
+        let secs: i64 = digits.parse().map_err(|_| error())?;
+        return Ok(if negative && fraction > 0 {
+            Timestamp::new(-secs - 1, 1_000_000_000 - fraction)
+        } else if negative {
+            Timestamp::new(-secs, 0)
+        } else {
+            Timestamp::new(secs, fraction)
+        });
+    }
+
+    // Split "date", "time" and "zone" parts
+    let (date_part, rest) = match trimmed.find(['T', 't', ' ']) {
+        Some(index) => (&trimmed[..index], trimmed[index + 1..].trim()),
+        None => (trimmed, ""),
+    };
+
+    let mut fields = date_part.splitn(3, '-');
+    let year = fields.next().and_then(parse_number).ok_or_else(error)? as i64;
+    let month = fields.next().and_then(parse_number).ok_or_else(error)?;
+    let day = fields.next().and_then(parse_number).ok_or_else(error)?;
+
+    let mut hour = 0;
+    let mut minute = 0;
+    let mut second = 0;
+    let mut nanos = 0;
+    let mut zone = None;
+
+    if !rest.is_empty() {
+        // Anything that cannot be part of HH:MM:SS.frac starts the zone
+        let zone_start = rest
+            .char_indices()
+            .find(|(_, c)| !(c.is_ascii_digit() || matches!(c, ':' | '.' | ',')))
+            .map(|(index, _)| index);
+        let (time_part, zone_part) = match zone_start {
+            Some(index) => (&rest[..index], Some(&rest[index..])),
+            None => (rest, None),
+        };
+        if let Some(zone_part) = zone_part {
+            zone = Some(parse_zone(zone_part).ok_or_else(error)?);
+        }
+
+        let (clock, fraction) = match time_part.split_once(['.', ',']) {
+            Some((clock, fraction)) => (clock, Some(fraction)),
+            None => (time_part, None),
+        };
+        let mut parts = clock.split(':');
+        hour = parts.next().and_then(parse_number).ok_or_else(error)?;
+        minute = parts.next().and_then(parse_number).ok_or_else(error)?;
+        if let Some(part) = parts.next() {
+            second = parse_number(part).ok_or_else(error)?;
+        }
+        if parts.next().is_some() {
+            return Err(error());
+        }
+        if let Some(fraction) = fraction {
+            nanos = parse_fraction(fraction).ok_or_else(error)?;
+        }
+    }
+
+    if !valid_fields(year, month, day, hour, minute, second) {
+        return Err(error());
+    }
+
+    let secs = match zone {
+        Some(offset) => utc_to_epoch(year, month, day, hour, minute, second) - offset,
+        None => local_to_epoch(year, month, day, hour, minute, second).ok_or_else(error)?,
+    };
+    Ok(Timestamp::new(secs, nanos))
+}
+
+// Parses the POSIX touch stamp format [[CC]YY]MMDDhhmm[.ss] in local time
+pub fn parse_touch_stamp(text: &str) -> Result<Timestamp, ParseDateError> {
+    let error = || ParseDateError {
+        text: text.to_string(),
+    };
+
+    let (digits, seconds) = match text.split_once('.') {
+        Some((digits, seconds)) if seconds.len() == 2 => {
+            (digits, parse_number(seconds).ok_or_else(error)?)
+        }
+        Some(_) => return Err(error()),
+        None => (text, 0),
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(error());
+    }
+
+    let field = |start: usize| parse_number(&digits[start..start + 2]).ok_or_else(error);
+    let (year, offset) = match digits.len() {
+        8 => {
+            let now = Timestamp::now().secs;
+            let (year, _, _) = civil_from_days(now.div_euclid(86400));
+            (year, 0)
+        }
+        10 => {
+            let yy = field(0)? as i64;
+            (if yy < 69 { 2000 + yy } else { 1900 + yy }, 2)
+        }
+        12 => (field(0)? as i64 * 100 + field(2)? as i64, 4),
+        _ => return Err(error()),
+    };
+    let month = field(offset)?;
+    let day = field(offset + 2)?;
+    let hour = field(offset + 4)?;
+    let minute = field(offset + 6)?;
+
+    if !valid_fields(year, month, day, hour, minute, seconds) {
+        return Err(error());
+    }
+    let secs = local_to_epoch(year, month, day, hour, minute, seconds).ok_or_else(error)?;
+    Ok(Timestamp::new(secs, 0))
+}
+
+// Makes local time UTC for the rest of the process, as `date -u` does
+pub fn use_utc() {
+    extern "C" {
+        fn tzset();
+    }
+    std::env::set_var("TZ", "UTC0");
+    unsafe { tzset() };
+}
+
+// Day and month names in the C locale, from Sunday and from January
+pub const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+pub const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+// The ISO 8601 week-numbering year and week of a date, where weeks start
+// on Monday and week 1 is the one with the year's first Thursday
+fn iso_week(year: i64, year_day: u32, weekday: u32) -> (i64, u32) {
+    let weeks_in = |year: i64| {
+        let january_first = (days_from_civil(year, 1, 1) + 4).rem_euclid(7);
+        if january_first == 4 || (january_first == 3 && is_leap_year(year)) {
+            53
+        } else {
+            52
+        }
+    };
+    let iso_weekday = if weekday == 0 { 7 } else { weekday as i64 };
+    let week = (year_day as i64 + 1 - iso_weekday + 10) / 7;
+    if week < 1 {
+        (year - 1, weeks_in(year - 1))
+    } else if week > weeks_in(year) as i64 {
+        (year + 1, 1)
+    } else {
+        (year, week as u32)
+    }
+}
+
+// What a conversion produces, for the flags that change case and padding
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Number,
+    // Names that # turns upper case, and those it turns lower case
+    Name,
+    LowerName,
+    Text,
+}
+
+// Formats a moment with the strftime conversions GNU date understands, as
+// in the C locale: %Y, %m, %d, %H, %M, %S, %N, %s, %z, %Z, %a, %A, %b,
+// %B and the rest. The flags -, _, 0, ^ and # and a field width may come
+// between the % and the conversion. `time` is `stamp` broken down in the
+// zone to show.
+pub fn format_time(format: &str, stamp: Timestamp, time: &LocalTime) -> String {
+    let mut out = String::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let mut pad = None;
+        let mut upper = false;
+        let mut swap_case = false;
+        while let Some(&flag) = chars.peek() {
+            match flag {
+                '-' | '_' | '0' => pad = Some(flag),
+                '^' => upper = true,
+                '#' => swap_case = true,
+                _ => break,
+            }
+            chars.next();
+        }
+        let mut width = None;
+        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+            width = Some(width.unwrap_or(0) * 10 + digit as usize);
+            chars.next();
+        }
+        let mut colons = 0;
+        while chars.peek() == Some(&':') {
+            colons += 1;
+            chars.next();
+        }
+        let Some(conversion) = chars.next() else {
+            out.push('%');
+            break;
+        };
+
+        // Numbers with their default width and padding
+        let number = |value: i64, default_width: usize, default_pad: char| {
+            let pad = match pad {
+                Some('-') => None,
+                Some('_') => Some(' '),
+                Some('0') => Some('0'),
+                _ => Some(default_pad),
+            };
+            let digits = value.unsigned_abs().to_string();
+            let sign = if value < 0 { "-" } else { "" };
+            let width = width.unwrap_or(default_width);
+            let fill = width.saturating_sub(sign.len() + digits.len());
+            match pad {
+                None => format!("{}{}", sign, digits),
+                Some('0') => format!("{}{}{}", sign, "0".repeat(fill), digits),
+                Some(_) => format!("{}{}{}", " ".repeat(fill), sign, digits),
+            }
+        };
+        let hour12 = if time.hour.is_multiple_of(12) {
+            12
+        } else {
+            time.hour % 12
+        } as i64;
+        let (iso_year, iso_week) = iso_week(time.year, time.year_day, time.weekday);
+        let weekday = WEEKDAYS[time.weekday as usize % 7];
+        let month = MONTHS[(time.month as usize + 11) % 12];
+        let am_pm = if time.hour < 12 { "AM" } else { "PM" };
+
+        let (text, kind) = match conversion {
+            'Y' => (number(time.year, 1, '0'), Kind::Number),
+            'C' => (number(time.year.div_euclid(100), 2, '0'), Kind::Number),
+            'y' => (number(time.year.rem_euclid(100), 2, '0'), Kind::Number),
+            'G' => (number(iso_year, 1, '0'), Kind::Number),
+            'g' => (number(iso_year.rem_euclid(100), 2, '0'), Kind::Number),
+            'm' => (number(time.month as i64, 2, '0'), Kind::Number),
+            'q' => (number((time.month as i64 + 2) / 3, 1, '0'), Kind::Number),
+            'd' => (number(time.day as i64, 2, '0'), Kind::Number),
+            'e' => (number(time.day as i64, 2, ' '), Kind::Number),
+            'j' => (number(time.year_day as i64 + 1, 3, '0'), Kind::Number),
+            'H' => (number(time.hour as i64, 2, '0'), Kind::Number),
+            'k' => (number(time.hour as i64, 2, ' '), Kind::Number),
+            'I' => (number(hour12, 2, '0'), Kind::Number),
+            'l' => (number(hour12, 2, ' '), Kind::Number),
+            'M' => (number(time.minute as i64, 2, '0'), Kind::Number),
+            'S' => (number(time.second as i64, 2, '0'), Kind::Number),
+            's' => (number(stamp.secs, 1, '0'), Kind::Number),
+            'u' => (
+                number(
+                    if time.weekday == 0 {
+                        7
+                    } else {
+                        time.weekday as i64
+                    },
+                    1,
+                    '0',
+                ),
+                Kind::Number,
+            ),
+            'w' => (number(time.weekday as i64, 1, '0'), Kind::Number),
+            'U' => (
+                number((time.year_day as i64 + 7 - time.weekday as i64) / 7, 2, '0'),
+                Kind::Number,
+            ),
+            'W' => {
+                let monday_based = (time.weekday as i64 + 6) % 7;
+                (
+                    number((time.year_day as i64 + 7 - monday_based) / 7, 2, '0'),
+                    Kind::Number,
+                )
+            }
+            'V' => (number(iso_week as i64, 2, '0'), Kind::Number),
+            'N' => {
+                // A width keeps that many digits, so %3N gives milliseconds
+                let digits = format!("{:09}", stamp.nanos);
+                let keep = width.unwrap_or(9).clamp(1, 9);
+                (digits[..keep].to_string(), Kind::Number)
+            }
+            'z' => {
+                let sign = if time.utc_offset < 0 { '-' } else { '+' };
+                let offset = time.utc_offset.unsigned_abs();
+                let (hours, minutes, seconds) = (offset / 3600, offset / 60 % 60, offset % 60);
+                let text = match colons {
+                    0 => format!("{}{:02}{:02}", sign, hours, minutes),
+                    1 => format!("{}{:02}:{:02}", sign, hours, minutes),
+                    2 => format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, seconds),
+                    // %:::z uses only the precision needed
+                    _ if seconds != 0 => {
+                        format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, seconds)
+                    }
+                    _ if minutes != 0 => format!("{}{:02}:{:02}", sign, hours, minutes),
+                    _ => format!("{}{:02}", sign, hours),
+                };
+                (text, Kind::Number)
+            }
+            'a' => (weekday[..3].to_string(), Kind::Name),
+            'A' => (weekday.to_string(), Kind::Name),
+            'b' | 'h' => (month[..3].to_string(), Kind::Name),
+            'B' => (month.to_string(), Kind::Name),
+            'p' => (am_pm.to_string(), Kind::LowerName),
+            'P' => (am_pm.to_ascii_lowercase(), Kind::Text),
+            'Z' => (time.zone.clone(), Kind::LowerName),
+            'c' => (format_time("%a %b %e %H:%M:%S %Y", stamp, time), Kind::Text),
+            'D' | 'x' => (format_time("%m/%d/%y", stamp, time), Kind::Text),
+            'F' => (format_time("%Y-%m-%d", stamp, time), Kind::Text),
+            'r' => (format_time("%I:%M:%S %p", stamp, time), Kind::Text),
+            'R' => (format_time("%H:%M", stamp, time), Kind::Text),
+            'T' | 'X' => (format_time("%H:%M:%S", stamp, time), Kind::Text),
+            'n' => (String::from("\n"), Kind::Text),
+            't' => (String::from("\t"), Kind::Text),
+            '%' => (String::from("%"), Kind::Text),
+            // Anything else is kept as written
+            _ => {
+                out.push('%');
+                out.push(conversion);
+                continue;
+            }
+        };
+
+        // # makes names upper case, except for %p and %Z which it lowers
+        let text = match kind {
+            Kind::Name if swap_case => text.to_ascii_uppercase(),
+            Kind::LowerName if swap_case => text.to_ascii_lowercase(),
+            _ if upper => text.to_ascii_uppercase(),
+            _ => text,
+        };
+        // Numbers are padded already; anything else pads with spaces,
+        // unless - asks for no padding at all
+        if let (Some(width), false) = (width, kind == Kind::Number || pad == Some('-')) {
+            let fill = if pad == Some('0') { '0' } else { ' ' };
+            for _ in text.chars().count()..width {
+                out.push(fill);
+            }
+        }
+        out.push_str(&text);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The fields of `secs` in UTC, built without the C library so the
+    // tests do not depend on the zone they run in
+    fn utc(secs: i64) -> LocalTime {
+        let days = secs.div_euclid(86400);
+        let seconds = secs.rem_euclid(86400) as u32;
+        let (year, month, day) = civil_from_days(days);
+        LocalTime {
+            year,
+            month,
+            day,
+            hour: seconds / 3600,
+            minute: seconds / 60 % 60,
+            second: seconds % 60,
+            weekday: (days + 4).rem_euclid(7) as u32,
+            year_day: (days - days_from_civil(year, 1, 1)) as u32,
+            utc_offset: 0,
+            zone: String::from("UTC"),
+        }
+    }
+
+    fn parsed(text: &str) -> Option<Timestamp> {
+        parse_date(text).ok()
+    }
+
+    #[test]
+    fn civil_dates() {
+        for (year, month, day, days) in [
+            (1970, 1, 1, 0),
+            (1969, 12, 31, -1),
+            (2000, 2, 29, 11016),
+            (2000, 3, 1, 11017),
+            (1900, 3, 1, -25508),
+            (2038, 1, 19, 24855),
+        ] {
+            assert_eq!(days_from_civil(year, month, day), days);
+            assert_eq!(civil_from_days(days), (year, month, day));
+        }
+        assert!(is_leap_year(2000) && is_leap_year(2024));
+        assert!(!is_leap_year(1900) && !is_leap_year(2023));
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2023, 4), 30);
+    }
+
+    #[test]
+    fn absolute_dates() {
+        for (text, expected) in [
+            ("@0", Some(Timestamp::new(0, 0))),
+            ("@1700000000", Some(Timestamp::new(1_700_000_000, 0))),
+            ("@1.5", Some(Timestamp::new(1, 500_000_000))),
+            ("@-1.25", Some(Timestamp::new(-2, 750_000_000))),
+            ("2000-01-01T00:00:00Z", Some(Timestamp::new(946_684_800, 0))),
+            ("2000-01-01 12:30Z", Some(Timestamp::new(946_729_800, 0))),
+            (
+                "2000-01-01T00:00:00.25Z",
+                Some(Timestamp::new(946_684_800, 250_000_000)),
+            ),
+            (
+                "2000-01-01T05:30:00+05:30",
+                Some(Timestamp::new(946_684_800, 0)),
+            ),
+            (
+                "1999-12-31T16:00:00-0800",
+                Some(Timestamp::new(946_684_800, 0)),
+            ),
+            ("2024-02-29T00:00Z", Some(Timestamp::new(1_709_164_800, 0))),
+            ("2023-02-29T00:00Z", None),
+            ("2000-13-01T00:00Z", None),
+            ("2000-01-01T24:00Z", None),
+            ("2000-01-01T00:00+99", None),
+            ("2000-01-01T00:00:00:00Z", None),
+            ("@x", None),
+            ("nonsense", None),
+        ] {
+            assert_eq!(parsed(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn relative_dates() {
+        let base = "2000-01-01T00:00:00Z";
+        let at = |secs: i64| Some(Timestamp::new(946_684_800 + secs, 0));
+        for (items, expected) in [
+            ("", at(0)),
+            ("now", at(0)),
+            ("+90 seconds", at(90)),
+            ("3 min", at(180)),
+            ("1 hour ago", at(-3600)),
+            ("2hours", at(7200)),
+            ("next hour", at(3600)),
+            ("last minute", at(-60)),
+            ("-2 hours ago", at(7200)),
+            ("1 hour 30 min", at(5400)),
+            ("1 parsec", None),
+            ("next", None),
+        ] {
+            let text = format!("{} {}", base, items);
+            assert_eq!(parsed(text.trim()), expected, "{}", items);
+        }
+    }
+
+    #[test]
+    fn formatted_times() {
+        // Thursday 2009-02-13 23:31:30 UTC
+        let stamp = Timestamp::new(1_234_567_890, 123_456_789);
+        let time = utc(stamp.secs);
+        for (format, expected) in [
+            ("%Y-%m-%d %H:%M:%S", "2009-02-13 23:31:30"),
+            ("%F %T", "2009-02-13 23:31:30"),
+            ("%a %A %b %B", "Fri Friday Feb February"),
+            ("%^a %#B %#Z %#p", "FRI FEBRUARY utc pm"),
+            ("%e|%-d|%_m|%05d", "13|13| 2|00013"),
+            ("%I %l %p %P", "11 11 PM pm"),
+            ("%j %U %W %V %G %u %w", "044 06 06 07 2009 5 5"),
+            ("%s.%N %3N", "1234567890.123456789 123"),
+            ("%z %:z %::z %:::z", "+0000 +00:00 +00:00:00 +00"),
+            ("%C%y %q", "2009 1"),
+            ("%10A|%-10B", "    Friday|February"),
+            ("100%% %Q", "100% %Q"),
+        ] {
+            assert_eq!(format_time(format, stamp, &time), expected, "{}", format);
+        }
+    }
+
+    #[test]
+    fn iso_weeks() {
+        for (year, month, day, expected) in [
+            (2005, 1, 1, (2004, 53)),
+            (2007, 12, 31, (2008, 1)),
+            (2008, 12, 29, (2009, 1)),
+            (2010, 1, 3, (2009, 53)),
+            (2020, 12, 31, (2020, 53)),
+        ] {
+            let time = utc(days_from_civil(year, month, day) * 86400);
+            assert_eq!(iso_week(time.year, time.year_day, time.weekday), expected);
+        }
+    }
+}
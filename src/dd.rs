@@ -0,0 +1,478 @@
+use cmds_lite::error;
+use cmds_lite::format::{Flags, Spec, Value};
+use cmds_lite::signal::{self, Signal};
+use cmds_lite::size;
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem::ManuallyDrop;
+use std::os::unix::io::FromRawFd;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    // Statistics at the end
+    Default,
+    // status=none: nothing but errors
+    None,
+    // status=noxfer: the record counts without the transfer line
+    NoTransfer,
+    // status=progress: a transfer line every second as well
+    Progress,
+}
+
+pub struct DdOptions {
+    // if=: read from this file instead of standard input
+    pub input: Option<String>,
+    // of=: write to this file instead of standard output
+    pub output: Option<String>,
+    // ibs=: bytes read at a time
+    pub input_block: usize,
+    // obs=: bytes written at a time
+    pub output_block: usize,
+    // bs=: both, with each block read written out as it is
+    pub block: Option<usize>,
+    // count=: copy only this many input blocks
+    pub count: Option<u64>,
+    // skip=: input blocks to skip first
+    pub skip: u64,
+    // seek=: output blocks to skip first
+    pub seek: u64,
+    // status=: what to report on standard error
+    pub status: Status,
+    // conv=notrunc: leave the output file's size alone
+    pub no_truncate: bool,
+    // conv=sync: pad short input blocks with zeros to the full size
+    pub sync: bool,
+}
+
+impl Default for DdOptions {
+    fn default() -> Self {
+        DdOptions {
+            input: None,
+            output: None,
+            input_block: 512,
+            output_block: 512,
+            block: None,
+            count: None,
+            skip: 0,
+            seek: 0,
+            status: Status::Default,
+            no_truncate: false,
+            sync: false,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("dd: {}", message);
+    std::process::exit(1);
+}
+
+// A size in bytes with two significant figures or so, the way dd shows
+// them: "1.0 kB", "105 MB", "9.8 GiB"
+fn scaled(value: f64, base: f64, units: &[&str]) -> String {
+    let mut value = value;
+    let mut unit = 0;
+    while value >= base && unit + 1 < units.len() {
+        value /= base;
+        unit += 1;
+    }
+    if value < 10.0 && unit > 0 {
+        format!("{:.1} {}", value, units[unit])
+    } else {
+        format!("{:.0} {}", value, units[unit])
+    }
+}
+
+const SI_UNITS: &[&str] = &["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+const IEC_UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+// Counts of whole and partial blocks, and bytes written
+#[derive(Default)]
+struct Statistics {
+    full_in: u64,
+    partial_in: u64,
+    full_out: u64,
+    partial_out: u64,
+    bytes: u64,
+}
+
+impl Statistics {
+    // "N bytes (SI, IEC) copied, T s, R/s"; the time is given whole for
+    // progress lines
+    fn transfer_line(&self, elapsed: Duration, whole_seconds: bool) -> String {
+        let mut line = format!("{} bytes", self.bytes);
+        if self.bytes >= 1000 {
+            line.push_str(&format!(
+                " ({}",
+                scaled(self.bytes as f64, 1000.0, SI_UNITS)
+            ));
+            if self.bytes >= 1024 {
+                line.push_str(&format!(
+                    ", {}",
+                    scaled(self.bytes as f64, 1024.0, IEC_UNITS)
+                ));
+            }
+            line.push(')');
+        }
+        let seconds = elapsed.as_secs_f64();
+        let time = if whole_seconds {
+            format!("{:.0}", seconds)
+        } else {
+            let spec = Spec {
+                flags: Flags::default(),
+                width: None,
+                precision: None,
+                conversion: b'g',
+            };
+            String::from_utf8_lossy(&spec.render(0, None, Value::Float(seconds))).into_owned()
+        };
+        let rate = if seconds > 0.0 {
+            let mut rate = self.bytes as f64 / seconds;
+            let mut unit = 0;
+            while rate >= 1000.0 && unit + 1 < SI_UNITS.len() {
+                rate /= 1000.0;
+                unit += 1;
+            }
+            if rate < 100.0 {
+                format!("{:.1} {}/s", rate, SI_UNITS[unit])
+            } else {
+                format!("{:.0} {}/s", rate, SI_UNITS[unit])
+            }
+        } else {
+            String::from("Infinity B/s")
+        };
+        format!("{} copied, {} s, {}", line, time, rate)
+    }
+}
+
+struct DdCommand<'a> {
+    options: &'a DdOptions,
+    statistics: Statistics,
+    started: Instant,
+    // When the next progress line is due
+    next_progress: Instant,
+    // Whether a progress line is showing, to be ended before the report
+    progress_shown: bool,
+}
+
+impl<'a> DdCommand<'a> {
+    fn input_name(&self) -> &str {
+        self.options.input.as_deref().unwrap_or("standard input")
+    }
+
+    fn output_name(&self) -> &str {
+        self.options.output.as_deref().unwrap_or("standard output")
+    }
+
+    fn input_block(&self) -> usize {
+        self.options.block.unwrap_or(self.options.input_block)
+    }
+
+    fn output_block(&self) -> usize {
+        self.options.block.unwrap_or(self.options.output_block)
+    }
+
+    fn report(&mut self) {
+        let mut stderr = io::stderr().lock();
+        if std::mem::take(&mut self.progress_shown) {
+            let _ = writeln!(stderr);
+        }
+        if self.options.status == Status::None {
+            return;
+        }
+        let stats = &self.statistics;
+        let _ = writeln!(stderr, "{}+{} records in", stats.full_in, stats.partial_in);
+        let _ = writeln!(
+            stderr,
+            "{}+{} records out",
+            stats.full_out, stats.partial_out
+        );
+        if self.options.status != Status::NoTransfer {
+            let line = stats.transfer_line(self.started.elapsed(), false);
+            let _ = writeln!(stderr, "{}", line);
+        }
+    }
+
+    // A progress line in place of the last one, once a second
+    fn progress(&mut self) {
+        let now = Instant::now();
+        if self.options.status != Status::Progress || now < self.next_progress {
+            return;
+        }
+        self.next_progress = now + Duration::from_secs(1);
+        self.progress_shown = true;
+        let line = self.statistics.transfer_line(self.started.elapsed(), true);
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r{}\x1b[K", line);
+        let _ = stderr.flush();
+    }
+
+    // One read, which may come up short on pipes and terminals
+    fn read_block(&self, input: &mut File, buffer: &mut [u8]) -> Result<usize, String> {
+        loop {
+            match input.read(buffer) {
+                Ok(read) => return Ok(read),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    return Err(format!(
+                        "error reading '{}': {}",
+                        self.input_name(),
+                        error::message(&e)
+                    ))
+                }
+            }
+        }
+    }
+
+    fn write_block(&mut self, output: &mut File, block: &[u8]) -> Result<(), String> {
+        output.write_all(block).map_err(|e| {
+            format!(
+                "error writing '{}': {}",
+                self.output_name(),
+                error::message(&e)
+            )
+        })?;
+        if block.len() == self.output_block() {
+            self.statistics.full_out += 1;
+        } else {
+            self.statistics.partial_out += 1;
+        }
+        self.statistics.bytes += block.len() as u64;
+        Ok(())
+    }
+
+    // Moves past the skip= input blocks, by seeking when the input allows
+    // it and by reading them otherwise
+    fn skip_input(&self, input: &mut File) -> Result<(), String> {
+        let bytes = self.options.skip * self.input_block() as u64;
+        if bytes == 0 || input.seek(SeekFrom::Current(bytes as i64)).is_ok() {
+            return Ok(());
+        }
+        let mut buffer = vec![0; self.input_block()];
+        for _ in 0..self.options.skip {
+            if self.read_block(input, &mut buffer)? == 0 {
+                eprintln!(
+                    "dd: '{}': cannot skip to specified offset",
+                    self.input_name()
+                );
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Moves past the seek= output blocks, and cuts the output file off
+    // there unless conv=notrunc
+    fn seek_output(&self, output: &mut File) -> Result<(), String> {
+        let offset = self.options.seek * self.output_block() as u64;
+        let error = |e: io::Error| {
+            format!(
+                "cannot seek in '{}': {}",
+                self.output_name(),
+                error::message(&e)
+            )
+        };
+        if offset > 0 {
+            output.seek(SeekFrom::Start(offset)).map_err(error)?;
+        }
+        let regular = output.metadata().is_ok_and(|metadata| metadata.is_file());
+        if self.options.output.is_some() && !self.options.no_truncate && regular {
+            output.set_len(offset).map_err(|e| {
+                format!(
+                    "failed to truncate to {} bytes in output file '{}': {}",
+                    offset,
+                    self.output_name(),
+                    error::message(&e)
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    fn copy(&mut self, input: &mut File, output: &mut File, usr1: &[Signal]) -> Result<(), String> {
+        let input_block = self.input_block();
+        let output_block = self.output_block();
+        let mut buffer = vec![0; input_block];
+        // Bytes waiting to fill an output block, when ibs and obs differ
+        let mut pending: Vec<u8> = Vec::new();
+
+        while self
+            .options
+            .count
+            .is_none_or(|count| self.statistics.full_in + self.statistics.partial_in < count)
+        {
+            let read = self.read_block(input, &mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            let mut length = read;
+            if read == input_block {
+                self.statistics.full_in += 1;
+            } else {
+                self.statistics.partial_in += 1;
+                if self.options.sync {
+                    buffer[read..].fill(0);
+                    length = input_block;
+                }
+            }
+
+            if self.options.block.is_some() {
+                // With bs= each block read goes out as it is
+                let block = std::mem::take(&mut buffer);
+                let result = self.write_block(output, &block[..length]);
+                buffer = block;
+                result?;
+            } else {
+                pending.extend_from_slice(&buffer[..length]);
+                let whole = pending.len() / output_block * output_block;
+                let blocks: Vec<u8> = pending.drain(..whole).collect();
+                for block in blocks.chunks(output_block) {
+                    self.write_block(output, block)?;
+                }
+            }
+
+            if signal::wait_timeout(usr1, Duration::ZERO).is_ok_and(|signal| signal.is_some()) {
+                self.report();
+            }
+            self.progress();
+        }
+        if !pending.is_empty() {
+            self.write_block(output, &pending)?;
+        }
+        Ok(())
+    }
+
+    fn run(&mut self) -> bool {
+        // SIGUSR1 asks for the statistics so far; it is held back and
+        // looked for between blocks
+        let usr1: Vec<Signal> = if signal::is_ignored(Signal::USR1) {
+            Vec::new()
+        } else {
+            vec![Signal::USR1]
+        };
+        if let Err(e) = signal::block(&usr1) {
+            fail(&e.to_string());
+        }
+
+        let mut input = match &self.options.input {
+            Some(name) => ManuallyDrop::new(File::open(name).unwrap_or_else(|e| {
+                fail(&format!(
+                    "failed to open '{}': {}",
+                    name,
+                    error::message(&e)
+                ))
+            })),
+            None => ManuallyDrop::new(unsafe { File::from_raw_fd(0) }),
+        };
+        let mut output = match &self.options.output {
+            Some(name) => ManuallyDrop::new(
+                OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(name)
+                    .unwrap_or_else(|e| {
+                        fail(&format!(
+                            "failed to open '{}': {}",
+                            name,
+                            error::message(&e)
+                        ))
+                    }),
+            ),
+            None => ManuallyDrop::new(unsafe { File::from_raw_fd(1) }),
+        };
+
+        let result = self
+            .skip_input(&mut input)
+            .and_then(|_| self.seek_output(&mut output))
+            .and_then(|_| self.copy(&mut input, &mut output, &usr1));
+        if let Err(message) = &result {
+            if std::mem::take(&mut self.progress_shown) {
+                eprintln!();
+            }
+            eprintln!("dd: {}", message);
+        }
+        self.report();
+        result.is_ok()
+    }
+}
+
+// A dd number: digits with an optional suffix, c for bytes, w for two
+// bytes, b for 512 and K, M, G... as for other commands, and products
+// such as 2x512
+fn parse_number(value: &str) -> u64 {
+    let product = value.split('x').try_fold(1u64, |total, factor| {
+        let number = match factor.strip_suffix('c') {
+            Some(digits) => size::parse_size(digits),
+            None => match factor.strip_suffix('w') {
+                Some(digits) => size::parse_size(digits).and_then(|n| n.checked_mul(2)),
+                None => size::parse_size(factor),
+            },
+        }?;
+        total.checked_mul(number)
+    });
+    product.unwrap_or_else(|| fail(&format!("invalid number: '{}'", value)))
+}
+
+fn parse_block_size(value: &str) -> usize {
+    match parse_number(value) {
+        0 => fail(&format!("invalid number: '{}'", value)),
+        size => usize::try_from(size)
+            .ok()
+            .filter(|&size| size <= isize::MAX as usize)
+            .unwrap_or_else(|| fail(&format!("invalid number: '{}'", value))),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = DdOptions::default();
+
+    for arg in &args[1..] {
+        let Some((key, value)) = arg.split_once('=') else {
+            fail(&format!("unrecognized operand '{}'", arg));
+        };
+        match key {
+            "if" => options.input = Some(value.to_string()),
+            "of" => options.output = Some(value.to_string()),
+            "bs" => options.block = Some(parse_block_size(value)),
+            "ibs" => options.input_block = parse_block_size(value),
+            "obs" => options.output_block = parse_block_size(value),
+            "count" => options.count = Some(parse_number(value)),
+            "skip" => options.skip = parse_number(value),
+            "seek" => options.seek = parse_number(value),
+            "status" => {
+                options.status = match value {
+                    "none" => Status::None,
+                    "noxfer" => Status::NoTransfer,
+                    "progress" => Status::Progress,
+                    _ => fail(&format!("invalid status level: '{}'", value)),
+                }
+            }
+            "conv" => {
+                for conversion in value.split(',') {
+                    match conversion {
+                        "notrunc" => options.no_truncate = true,
+                        "sync" => options.sync = true,
+                        _ => fail(&format!("invalid conversion: '{}'", conversion)),
+                    }
+                }
+            }
+            _ => fail(&format!("unrecognized operand '{}'", arg)),
+        }
+    }
+
+    let started = Instant::now();
+    let mut command = DdCommand {
+        options: &options,
+        statistics: Statistics::default(),
+        started,
+        next_progress: started + Duration::from_secs(1),
+        progress_shown: false,
+    };
+    if !command.run() {
+        std::process::exit(1);
+    }
+}
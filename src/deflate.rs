@@ -0,0 +1,980 @@
+// DEFLATE (RFC 1951) compression and decompression, both streaming. The
+// encoder finds repeats with hash chains over the 32 KiB window and writes
+// each block as stored, fixed or dynamic Huffman data, whichever is
+// smallest; the decoder walks canonical Huffman codes a bit at a time.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::{self, BufRead, Read, Write};
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+// Input is compressed in chunks of this many bytes, one block each
+const BLOCK_INPUT: usize = 64 * 1024;
+const HASH_BITS: u32 = 15;
+const NO_POSITION: u32 = u32::MAX;
+const END_OF_BLOCK: usize = 256;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// The order code length code lengths are sent in
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn fixed_literal_lengths() -> [u8; 288] {
+    let mut lengths = [8; 288];
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths
+}
+
+const FIXED_DISTANCE_LENGTHS: [u8; 30] = [5; 30];
+
+fn corrupt(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// Index of the length or distance code covering value
+fn code_index(bases: &[u16], value: usize) -> usize {
+    bases
+        .iter()
+        .rposition(|&base| base as usize <= value)
+        .unwrap_or(0)
+}
+
+// Code lengths of an optimal prefix code, no longer than limit: when the
+// tree is too deep the frequencies are flattened and it is built again
+fn huffman_lengths(frequencies: &[u32], limit: u8) -> Vec<u8> {
+    let mut frequencies = frequencies.to_vec();
+    // A lone symbol still gets a one-bit code, with a partner so the code
+    // is complete
+    for symbol in 0..2 {
+        if frequencies.iter().filter(|&&f| f > 0).count() < 2 && frequencies[symbol] == 0 {
+            frequencies[symbol] = 1;
+        }
+    }
+    loop {
+        let lengths = tree_lengths(&frequencies);
+        if lengths.iter().all(|&length| length <= limit) {
+            return lengths;
+        }
+        for frequency in frequencies.iter_mut().filter(|f| **f > 0) {
+            *frequency = (*frequency >> 1).max(1);
+        }
+    }
+}
+
+fn tree_lengths(frequencies: &[u32]) -> Vec<u8> {
+    let mut heap = BinaryHeap::new();
+    for (symbol, &frequency) in frequencies.iter().enumerate() {
+        if frequency > 0 {
+            heap.push(Reverse((frequency as u64, symbol)));
+        }
+    }
+    // Leaves are the symbols themselves; inner nodes are numbered after
+    // them, so a parent always has a higher number than its children
+    let mut parents = vec![0usize; frequencies.len()];
+    while heap.len() > 1 {
+        let Reverse((a, left)) = heap.pop().unwrap();
+        let Reverse((b, right)) = heap.pop().unwrap();
+        let node = parents.len();
+        parents.push(0);
+        parents[left] = node;
+        parents[right] = node;
+        heap.push(Reverse((a + b, node)));
+    }
+    let mut depths = vec![0u8; parents.len()];
+    for node in (frequencies.len()..parents.len().saturating_sub(1)).rev() {
+        depths[node] = depths[parents[node]] + 1;
+    }
+    (0..frequencies.len())
+        .map(|symbol| {
+            if frequencies[symbol] == 0 {
+                0
+            } else {
+                depths[parents[symbol]] + 1
+            }
+        })
+        .collect()
+}
+
+// Canonical codes for the lengths, bit-reversed since DEFLATE sends
+// Huffman codes most significant bit first into a least-significant-first
+// stream
+fn canonical_codes(lengths: &[u8]) -> Vec<u16> {
+    let mut counts = [0u16; 16];
+    for &length in lengths {
+        counts[length as usize] += 1;
+    }
+    counts[0] = 0;
+    let mut next = [0u16; 16];
+    let mut code = 0u16;
+    for bits in 1..16 {
+        code = (code + counts[bits - 1]) << 1;
+        next[bits] = code;
+    }
+    lengths
+        .iter()
+        .map(|&length| {
+            if length == 0 {
+                return 0;
+            }
+            let code = next[length as usize];
+            next[length as usize] += 1;
+            code.reverse_bits() >> (16 - length)
+        })
+        .collect()
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= (value as u64) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn align(&mut self) {
+        if self.count > 0 {
+            self.bytes.push(self.bits as u8);
+            self.bits = 0;
+            self.count = 0;
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Token {
+    Literal(u8),
+    Match { length: usize, distance: usize },
+}
+
+// How hard the encoder looks for repeats at each level
+struct Effort {
+    // Candidates tried per position
+    max_chain: usize,
+    // A match this long is taken without looking further
+    nice_length: usize,
+    // Check whether the next position has a longer match first
+    lazy: bool,
+}
+
+fn effort(level: u32) -> Effort {
+    let (max_chain, nice_length, lazy) = match level {
+        0 | 1 => (4, 8, false),
+        2 => (8, 16, false),
+        3 => (16, 32, false),
+        4 => (16, 16, true),
+        5 => (32, 32, true),
+        6 => (128, 128, true),
+        7 => (256, 128, true),
+        8 => (1024, 258, true),
+        _ => (4096, 258, true),
+    };
+    Effort {
+        max_chain,
+        nice_length,
+        lazy,
+    }
+}
+
+// Hash chains over one chunk of input and the window before it
+struct Matcher<'a> {
+    data: &'a [u8],
+    end: usize,
+    effort: Effort,
+    head: Vec<u32>,
+    previous: Vec<u32>,
+}
+
+impl<'a> Matcher<'a> {
+    fn hash(&self, position: usize) -> usize {
+        let bytes = &self.data[position..position + MIN_MATCH];
+        ((bytes[0] as usize) << 10 ^ (bytes[1] as usize) << 5 ^ bytes[2] as usize)
+            & ((1 << HASH_BITS) - 1)
+    }
+
+    fn insert(&mut self, position: usize) {
+        if position + MIN_MATCH <= self.data.len() {
+            let hash = self.hash(position);
+            self.previous[position] = self.head[hash];
+            self.head[hash] = position as u32;
+        }
+    }
+
+    // The longest earlier repeat of the bytes at position, as (length,
+    // distance), without running past the end of the chunk
+    fn longest(&self, position: usize) -> Option<(usize, usize)> {
+        let max_length = MAX_MATCH.min(self.end - position);
+        if max_length < MIN_MATCH {
+            return None;
+        }
+        let data = self.data;
+        let mut best = (MIN_MATCH - 1, 0);
+        let mut candidate = self.head[self.hash(position)];
+        let mut chain = self.effort.max_chain;
+        while candidate != NO_POSITION && chain > 0 {
+            let start = candidate as usize;
+            let distance = position - start;
+            if distance > WINDOW_SIZE {
+                break;
+            }
+            if data[start + best.0] == data[position + best.0] {
+                let length = data[start..start + max_length]
+                    .iter()
+                    .zip(&data[position..position + max_length])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best.0 {
+                    best = (length, distance);
+                    if length >= self.effort.nice_length || length == max_length {
+                        break;
+                    }
+                }
+            }
+            candidate = self.previous[start];
+            chain -= 1;
+        }
+        // A three-byte repeat far back costs more than the literals
+        if best.0 < MIN_MATCH || (best.0 == MIN_MATCH && best.1 > 4096) {
+            return None;
+        }
+        Some(best)
+    }
+
+    fn tokens(mut self, start: usize) -> Vec<Token> {
+        for position in start.saturating_sub(WINDOW_SIZE)..start {
+            self.insert(position);
+        }
+        let mut tokens = Vec::new();
+        let mut position = start;
+        while position < self.end {
+            let found = self.longest(position);
+            self.insert(position);
+            let Some((length, distance)) = found else {
+                tokens.push(Token::Literal(self.data[position]));
+                position += 1;
+                continue;
+            };
+            if self.effort.lazy && length < self.effort.nice_length && position + 1 < self.end {
+                if let Some((next, _)) = self.longest(position + 1) {
+                    if next > length {
+                        tokens.push(Token::Literal(self.data[position]));
+                        position += 1;
+                        continue;
+                    }
+                }
+            }
+            tokens.push(Token::Match { length, distance });
+            for skipped in position + 1..position + length {
+                self.insert(skipped);
+            }
+            position += length;
+        }
+        tokens
+    }
+}
+
+pub struct Encoder<W: Write> {
+    inner: W,
+    level: u32,
+    // Up to a window of input already compressed, then input waiting
+    data: Vec<u8>,
+    history: usize,
+    bits: BitWriter,
+}
+
+impl<W: Write> Encoder<W> {
+    // Levels run from 1, fastest, to 9, smallest
+    pub fn new(inner: W, level: u32) -> Self {
+        Encoder {
+            inner,
+            level,
+            data: Vec::with_capacity(WINDOW_SIZE + BLOCK_INPUT),
+            history: 0,
+            bits: BitWriter {
+                bytes: Vec::new(),
+                bits: 0,
+                count: 0,
+            },
+        }
+    }
+
+    // Compresses the pending input up to end as one block
+    fn compress(&mut self, end: usize, last: bool) -> io::Result<()> {
+        let matcher = Matcher {
+            data: &self.data,
+            end,
+            effort: effort(self.level),
+            head: vec![NO_POSITION; 1 << HASH_BITS],
+            previous: vec![NO_POSITION; self.data.len()],
+        };
+        let tokens = matcher.tokens(self.history);
+        let raw = &self.data[self.history..end];
+        write_block(&mut self.bits, &tokens, raw, last);
+        self.inner.write_all(&self.bits.bytes)?;
+        self.bits.bytes.clear();
+
+        let keep_from = end.saturating_sub(WINDOW_SIZE);
+        self.data.drain(..keep_from);
+        self.history = end - keep_from;
+        Ok(())
+    }
+
+    // Writes the final block and returns the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.compress(self.data.len(), true)?;
+        self.bits.align();
+        self.inner.write_all(&self.bits.bytes)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.data.extend_from_slice(buffer);
+        while self.data.len() - self.history >= BLOCK_INPUT {
+            self.compress(self.history + BLOCK_INPUT, false)?;
+        }
+        Ok(buffer.len())
+    }
+
+    // Output can only be flushed a block at a time, so this flushes only
+    // what whole blocks have produced
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn write_block(bits: &mut BitWriter, tokens: &[Token], raw: &[u8], last: bool) {
+    let mut literal_counts = [0u32; 286];
+    let mut distance_counts = [0u32; 30];
+    let mut extra_bits: u64 = 0;
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => literal_counts[byte as usize] += 1,
+            Token::Match { length, distance } => {
+                let length_code = code_index(&LENGTH_BASE, length);
+                let distance_code = code_index(&DISTANCE_BASE, distance);
+                literal_counts[257 + length_code] += 1;
+                distance_counts[distance_code] += 1;
+                extra_bits += (LENGTH_EXTRA[length_code] + DISTANCE_EXTRA[distance_code]) as u64;
+            }
+        }
+    }
+    literal_counts[END_OF_BLOCK] = 1;
+
+    let cost = |lengths: &[u8], counts: &[u32]| -> u64 {
+        lengths
+            .iter()
+            .zip(counts)
+            .map(|(&length, &count)| length as u64 * count as u64)
+            .sum()
+    };
+
+    let literal_lengths = huffman_lengths(&literal_counts, 15);
+    let distance_lengths = huffman_lengths(&distance_counts, 15);
+    let header = CodeLengthHeader::new(&literal_lengths, &distance_lengths);
+    let dynamic_size = 3
+        + header.size()
+        + cost(&literal_lengths, &literal_counts)
+        + cost(&distance_lengths, &distance_counts)
+        + extra_bits;
+
+    let fixed_literal = fixed_literal_lengths();
+    let fixed_size = 3
+        + cost(&fixed_literal[..286], &literal_counts)
+        + cost(&FIXED_DISTANCE_LENGTHS, &distance_counts)
+        + extra_bits;
+
+    let stored_chunks = raw.len().div_ceil(0xffff).max(1) as u64;
+    let stored_size = (raw.len() as u64 + 5 * stored_chunks) * 8 + 7;
+
+    if stored_size < dynamic_size.min(fixed_size) {
+        let mut chunks = raw.chunks(0xffff).peekable();
+        if raw.is_empty() {
+            write_stored(bits, &[], last);
+        }
+        while let Some(chunk) = chunks.next() {
+            write_stored(bits, chunk, last && chunks.peek().is_none());
+        }
+    } else if fixed_size <= dynamic_size {
+        bits.write(last as u32, 1);
+        bits.write(1, 2);
+        write_tokens(bits, tokens, &fixed_literal, &FIXED_DISTANCE_LENGTHS);
+    } else {
+        bits.write(last as u32, 1);
+        bits.write(2, 2);
+        header.write(bits);
+        write_tokens(bits, tokens, &literal_lengths, &distance_lengths);
+    }
+}
+
+fn write_stored(bits: &mut BitWriter, chunk: &[u8], last: bool) {
+    bits.write(last as u32, 1);
+    bits.write(0, 2);
+    bits.align();
+    let length = chunk.len() as u16;
+    bits.bytes.extend_from_slice(&length.to_le_bytes());
+    bits.bytes.extend_from_slice(&(!length).to_le_bytes());
+    bits.bytes.extend_from_slice(chunk);
+}
+
+fn write_tokens(
+    bits: &mut BitWriter,
+    tokens: &[Token],
+    literal_lengths: &[u8],
+    distance_lengths: &[u8],
+) {
+    let literal_codes = canonical_codes(literal_lengths);
+    let distance_codes = canonical_codes(distance_lengths);
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => {
+                let symbol = byte as usize;
+                bits.write(literal_codes[symbol] as u32, literal_lengths[symbol] as u32);
+            }
+            Token::Match { length, distance } => {
+                let code = code_index(&LENGTH_BASE, length);
+                let symbol = 257 + code;
+                bits.write(literal_codes[symbol] as u32, literal_lengths[symbol] as u32);
+                bits.write(
+                    (length - LENGTH_BASE[code] as usize) as u32,
+                    LENGTH_EXTRA[code] as u32,
+                );
+                let code = code_index(&DISTANCE_BASE, distance);
+                bits.write(distance_codes[code] as u32, distance_lengths[code] as u32);
+                bits.write(
+                    (distance - DISTANCE_BASE[code] as usize) as u32,
+                    DISTANCE_EXTRA[code] as u32,
+                );
+            }
+        }
+    }
+    bits.write(
+        literal_codes[END_OF_BLOCK] as u32,
+        literal_lengths[END_OF_BLOCK] as u32,
+    );
+}
+
+// The code lengths of a dynamic block, run-length encoded with symbols
+// 16 to 18 and then Huffman coded themselves
+struct CodeLengthHeader {
+    literal_count: usize,
+    distance_count: usize,
+    // Each code length symbol with the value of its extra bits
+    symbols: Vec<(u8, u8)>,
+    lengths: Vec<u8>,
+    // Code length code lengths sent, in CODE_LENGTH_ORDER
+    sent: usize,
+}
+
+impl CodeLengthHeader {
+    fn new(literal_lengths: &[u8], distance_lengths: &[u8]) -> Self {
+        let used = |lengths: &[u8], minimum: usize| {
+            lengths
+                .iter()
+                .rposition(|&length| length > 0)
+                .map_or(minimum, |last| (last + 1).max(minimum))
+        };
+        let literal_count = used(literal_lengths, 257);
+        let distance_count = used(distance_lengths, 1);
+        let all: Vec<u8> = literal_lengths[..literal_count]
+            .iter()
+            .chain(&distance_lengths[..distance_count])
+            .copied()
+            .collect();
+
+        let mut symbols = Vec::new();
+        let mut i = 0;
+        while i < all.len() {
+            let length = all[i];
+            let run = all[i..].iter().take_while(|&&l| l == length).count();
+            if length == 0 && run >= 11 {
+                let take = run.min(138);
+                symbols.push((18, (take - 11) as u8));
+                i += take;
+            } else if length == 0 && run >= 3 {
+                let take = run.min(10);
+                symbols.push((17, (take - 3) as u8));
+                i += take;
+            } else if length != 0 && run >= 4 {
+                // The length itself, then repeats of it
+                symbols.push((length, 0));
+                let take = (run - 1).min(6);
+                symbols.push((16, (take - 3) as u8));
+                i += 1 + take;
+            } else {
+                symbols.push((length, 0));
+                i += 1;
+            }
+        }
+
+        let mut counts = [0u32; 19];
+        for &(symbol, _) in &symbols {
+            counts[symbol as usize] += 1;
+        }
+        let lengths = huffman_lengths(&counts, 7);
+        let sent = CODE_LENGTH_ORDER
+            .iter()
+            .rposition(|&symbol| lengths[symbol] > 0)
+            .map_or(4, |last| (last + 1).max(4));
+        CodeLengthHeader {
+            literal_count,
+            distance_count,
+            symbols,
+            lengths,
+            sent,
+        }
+    }
+
+    fn extra_bits(symbol: u8) -> u32 {
+        match symbol {
+            16 => 2,
+            17 => 3,
+            18 => 7,
+            _ => 0,
+        }
+    }
+
+    // Size in bits, after the three block header bits
+    fn size(&self) -> u64 {
+        let symbols: u64 = self
+            .symbols
+            .iter()
+            .map(|&(symbol, _)| {
+                self.lengths[symbol as usize] as u64 + Self::extra_bits(symbol) as u64
+            })
+            .sum();
+        14 + 3 * self.sent as u64 + symbols
+    }
+
+    fn write(&self, bits: &mut BitWriter) {
+        bits.write((self.literal_count - 257) as u32, 5);
+        bits.write((self.distance_count - 1) as u32, 5);
+        bits.write((self.sent - 4) as u32, 4);
+        for &symbol in &CODE_LENGTH_ORDER[..self.sent] {
+            bits.write(self.lengths[symbol] as u32, 3);
+        }
+        let codes = canonical_codes(&self.lengths);
+        for &(symbol, extra) in &self.symbols {
+            let index = symbol as usize;
+            bits.write(codes[index] as u32, self.lengths[index] as u32);
+            bits.write(extra as u32, Self::extra_bits(symbol));
+        }
+    }
+}
+
+// A canonical Huffman code as the number of codes of each length and the
+// symbols in code order
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Huffman> {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        // More codes of some length than the lengths before leave room for
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err(corrupt("invalid Huffman code lengths"));
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length > 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+}
+
+enum State {
+    // Before a block header, or at the end once the last block is done
+    Header,
+    Stored(usize),
+    Codes(Huffman, Huffman),
+    Done,
+}
+
+// Reads uncompressed data from a DEFLATE stream. Input is taken a byte at
+// a time as bits are needed, so once the stream ends the inner reader is
+// positioned right after it.
+pub struct Decoder<R: BufRead> {
+    inner: R,
+    bits: u32,
+    count: u32,
+    state: State,
+    last_block: bool,
+    // Decoded data: the window behind `position`, then data not yet read
+    output: Vec<u8>,
+    position: usize,
+}
+
+impl<R: BufRead> Decoder<R> {
+    pub fn new(inner: R) -> Self {
+        Decoder {
+            inner,
+            bits: 0,
+            count: 0,
+            state: State::Header,
+            last_block: false,
+            output: Vec::new(),
+            position: 0,
+        }
+    }
+
+    // True once the final block has been read
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    // Starts over on a new stream from the same reader
+    pub fn reset(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+        self.state = State::Header;
+        self.last_block = false;
+        self.output.clear();
+        self.position = 0;
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    fn next_byte(&mut self) -> io::Result<u8> {
+        let byte = match self.inner.fill_buf()? {
+            [] => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "unexpected end of file",
+                ))
+            }
+            [byte, ..] => *byte,
+        };
+        self.inner.consume(1);
+        Ok(byte)
+    }
+
+    fn read_bits(&mut self, count: u32) -> io::Result<u32> {
+        while self.count < count {
+            self.bits |= (self.next_byte()? as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1u64 << count) - 1) as u32;
+        self.bits >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+
+    fn decode(&mut self, code: &Huffman) -> io::Result<usize> {
+        let mut value: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for &count in &code.counts[1..] {
+            value |= self.read_bits(1)? as i32;
+            let count = count as i32;
+            if value - first < count {
+                return Ok(code.symbols[(index + value - first) as usize] as usize);
+            }
+            index += count;
+            first = (first + count) << 1;
+            value <<= 1;
+        }
+        Err(corrupt("invalid Huffman code"))
+    }
+
+    fn read_header(&mut self) -> io::Result<State> {
+        if self.last_block {
+            // Whatever is left of the last byte is padding
+            self.bits = 0;
+            self.count = 0;
+            return Ok(State::Done);
+        }
+        self.last_block = self.read_bits(1)? == 1;
+        match self.read_bits(2)? {
+            0 => {
+                self.bits = 0;
+                self.count = 0;
+                let length = self.read_bits(16)?;
+                let check = self.read_bits(16)?;
+                if length != !check & 0xffff {
+                    return Err(corrupt("invalid stored block lengths"));
+                }
+                Ok(State::Stored(length as usize))
+            }
+            1 => Ok(State::Codes(
+                Huffman::new(&fixed_literal_lengths())?,
+                Huffman::new(&FIXED_DISTANCE_LENGTHS)?,
+            )),
+            2 => self.read_dynamic_codes(),
+            _ => Err(corrupt("invalid block type")),
+        }
+    }
+
+    fn read_dynamic_codes(&mut self) -> io::Result<State> {
+        let literal_count = self.read_bits(5)? as usize + 257;
+        let distance_count = self.read_bits(5)? as usize + 1;
+        let sent = self.read_bits(4)? as usize + 4;
+        if literal_count > 286 || distance_count > 30 {
+            return Err(corrupt("too many length or distance symbols"));
+        }
+        let mut code_lengths = [0u8; 19];
+        for &symbol in &CODE_LENGTH_ORDER[..sent] {
+            code_lengths[symbol] = self.read_bits(3)? as u8;
+        }
+        let code_length_code = Huffman::new(&code_lengths)?;
+
+        let mut lengths = Vec::with_capacity(literal_count + distance_count);
+        while lengths.len() < literal_count + distance_count {
+            let symbol = self.decode(&code_length_code)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    let previous = *lengths
+                        .last()
+                        .ok_or_else(|| corrupt("repeated length with no first length"))?;
+                    (previous, 3 + self.read_bits(2)? as usize)
+                }
+                17 => (0, 3 + self.read_bits(3)? as usize),
+                _ => (0, 11 + self.read_bits(7)? as usize),
+            };
+            if lengths.len() + repeat > literal_count + distance_count {
+                return Err(corrupt("too many code lengths"));
+            }
+            lengths.extend(std::iter::repeat_n(value, repeat));
+        }
+        if lengths[END_OF_BLOCK] == 0 {
+            return Err(corrupt("missing end-of-block code"));
+        }
+        Ok(State::Codes(
+            Huffman::new(&lengths[..literal_count])?,
+            Huffman::new(&lengths[literal_count..])?,
+        ))
+    }
+
+    // Decodes until a window's worth of data is ready or the block ends
+    fn inflate(&mut self) -> io::Result<()> {
+        match std::mem::replace(&mut self.state, State::Header) {
+            State::Header => self.state = self.read_header()?,
+            State::Done => self.state = State::Done,
+            State::Stored(0) => {}
+            State::Stored(remaining) => {
+                let available = self.inner.fill_buf()?;
+                if available.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "unexpected end of file",
+                    ));
+                }
+                let take = available.len().min(remaining);
+                self.output.extend_from_slice(&available[..take]);
+                self.inner.consume(take);
+                if take < remaining {
+                    self.state = State::Stored(remaining - take);
+                }
+            }
+            State::Codes(literals, distances) => {
+                let target = self.output.len() + WINDOW_SIZE;
+                while self.output.len() < target {
+                    let symbol = self.decode(&literals)?;
+                    if symbol < END_OF_BLOCK {
+                        self.output.push(symbol as u8);
+                        continue;
+                    }
+                    if symbol == END_OF_BLOCK {
+                        return Ok(());
+                    }
+                    let code = symbol - 257;
+                    if code >= LENGTH_BASE.len() {
+                        return Err(corrupt("invalid length code"));
+                    }
+                    let length = LENGTH_BASE[code] as usize
+                        + self.read_bits(LENGTH_EXTRA[code] as u32)? as usize;
+                    let code = self.decode(&distances)?;
+                    if code >= DISTANCE_BASE.len() {
+                        return Err(corrupt("invalid distance code"));
+                    }
+                    let distance = DISTANCE_BASE[code] as usize
+                        + self.read_bits(DISTANCE_EXTRA[code] as u32)? as usize;
+                    if distance > self.output.len() {
+                        return Err(corrupt("invalid distance too far back"));
+                    }
+                    // The copy may overlap what it is producing
+                    let start = self.output.len() - distance;
+                    for i in 0..length {
+                        let byte = self.output[start + i];
+                        self.output.push(byte);
+                    }
+                }
+                self.state = State::Codes(literals, distances);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.position < self.output.len() {
+                let take = buffer.len().min(self.output.len() - self.position);
+                buffer[..take].copy_from_slice(&self.output[self.position..self.position + take]);
+                self.position += take;
+                return Ok(take);
+            }
+            if self.is_done() || buffer.is_empty() {
+                return Ok(0);
+            }
+            // Only the window is needed once everything has been read
+            if self.output.len() > 2 * WINDOW_SIZE {
+                let drop = self.output.len() - WINDOW_SIZE;
+                self.output.drain(..drop);
+                self.position -= drop;
+            }
+            self.inflate()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Bytes that do not repeat, from a linear congruential generator
+    fn noise(length: usize) -> Vec<u8> {
+        let mut state = 12345u32;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    fn compress(data: &[u8], level: u32) -> Vec<u8> {
+        let mut encoder = Encoder::new(Vec::new(), level);
+        // Written in uneven pieces, as a caller might
+        for piece in data.chunks(1000) {
+            encoder.write_all(piece).unwrap();
+        }
+        encoder.finish().unwrap()
+    }
+
+    fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decoder = Decoder::new(data);
+        let mut out = Vec::new();
+        decoder.read_to_end(&mut out)?;
+        assert!(decoder.is_done());
+        Ok(out)
+    }
+
+    #[test]
+    fn round_trips() {
+        let text = b"the quick brown fox jumps over the lazy dog. ".repeat(3000);
+        let mut mixed = noise(40_000);
+        mixed.extend_from_slice(&text);
+        mixed.extend(noise(100_000));
+        mixed.extend_from_within(1000..60_000);
+        for data in [
+            Vec::new(),
+            b"a".to_vec(),
+            b"abcabcabcabcabcabc".to_vec(),
+            vec![0; 300_000],
+            text,
+            noise(200_000),
+            mixed,
+        ] {
+            for level in [1, 6, 9] {
+                let compressed = compress(&data, level);
+                assert_eq!(decompress(&compressed).unwrap(), data, "level {}", level);
+            }
+        }
+    }
+
+    #[test]
+    fn compresses() {
+        let repeated = vec![b'x'; 100_000];
+        assert!(compress(&repeated, 6).len() < 1000);
+        // Data that cannot shrink grows by little more than the block
+        // headers
+        let random = noise(100_000);
+        assert!(compress(&random, 9).len() < random.len() + 100);
+    }
+
+    #[test]
+    fn reads_streams_from_other_encoders() {
+        // A stored block, then "hello\n" as gzip -9 writes it with fixed
+        // codes
+        assert_eq!(
+            decompress(&[0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c']).unwrap(),
+            b"abc"
+        );
+        let fixed = [0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0xe7, 0x02, 0x00];
+        assert_eq!(decompress(&fixed).unwrap(), b"hello\n");
+    }
+
+    #[test]
+    fn stops_at_the_end_of_the_stream() {
+        let mut data = compress(b"first", 6);
+        data.extend_from_slice(b"after");
+        let mut decoder = Decoder::new(&data[..]);
+        let mut out = Vec::new();
+        decoder.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"first");
+        let mut rest = Vec::new();
+        decoder.get_mut().read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"after");
+    }
+
+    #[test]
+    fn rejects_bad_streams() {
+        // Truncated, a stored length whose complement is wrong, and the
+        // reserved block type
+        let compressed = compress(&noise(1000), 6);
+        let truncated = &compressed[..compressed.len() / 2];
+        for data in [truncated, &[0x01, 0x03, 0x00, 0x00, 0x00, 1, 2, 3], &[0x07]] {
+            let mut out = Vec::new();
+            assert!(Decoder::new(data).read_to_end(&mut out).is_err());
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use cmds_lite::error;
+use cmds_lite::mounts::{self, FsStats, MountEntry};
+use cmds_lite::size::BlockSize;
+use std::env;
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+#[derive(Default)]
+pub struct DfOptions {
+    pub human_readable: bool,
+    pub inodes: bool,
+    pub all: bool,
+    pub print_type: bool,
+    // --block-size: the unit sizes are shown in unless -h is given
+    pub block_size: BlockSize,
+}
+
+trait UsageColumns {
+    fn headers(&self) -> [String; 4];
+    // Minimum widths GNU df uses, so short tables still line up the same
+    fn min_widths(&self) -> [usize; 4] {
+        [5, 5, 5, 4]
+    }
+    // Total, used, available and use percentage
+    fn values(&self, stats: &FsStats) -> [String; 4];
+}
+
+fn percentage(used: u64, available: u64) -> String {
+    let total = used + available;
+    if total == 0 {
+        return String::from("-");
+    }
+    // Rounded up, like GNU df, so a nearly full filesystem never shows 99%
+    format!("{}%", (used * 100).div_ceil(total))
+}
+
+// Sizes in units of a block size, or scaled to fit like -h shows them
+struct BlockColumns {
+    unit: BlockSize,
+}
+
+impl UsageColumns for BlockColumns {
+    fn headers(&self) -> [String; 4] {
+        let [size, available] = match self.unit {
+            BlockSize::Units { .. } => [self.unit.heading(), String::from("Available")],
+            BlockSize::Human | BlockSize::Si => [String::from("Size"), String::from("Avail")],
+        };
+        [size, String::from("Used"), available, String::from("Use%")]
+    }
+
+    fn values(&self, stats: &FsStats) -> [String; 4] {
+        let unit = stats.fragment_size;
+        let used = stats.blocks.saturating_sub(stats.blocks_free);
+        [
+            self.unit.format(stats.blocks * unit),
+            self.unit.format(used * unit),
+            self.unit.format(stats.blocks_available * unit),
+            percentage(used, stats.blocks_available),
+        ]
+    }
+}
+
+struct InodeColumns;
+
+impl UsageColumns for InodeColumns {
+    fn headers(&self) -> [String; 4] {
+        ["Inodes", "IUsed", "IFree", "IUse%"].map(String::from)
+    }
+
+    fn min_widths(&self) -> [usize; 4] {
+        [5, 5, 5, 5]
+    }
+
+    fn values(&self, stats: &FsStats) -> [String; 4] {
+        let used = stats.files.saturating_sub(stats.files_free);
+        [
+            stats.files.to_string(),
+            used.to_string(),
+            stats.files_free.to_string(),
+            percentage(used, stats.files_free),
+        ]
+    }
+}
+
+struct DfCommand<'a> {
+    columns: Box<dyn UsageColumns>,
+    options: &'a DfOptions,
+}
+
+impl<'a> DfCommand<'a> {
+    fn new(options: &'a DfOptions) -> Self {
+        let columns: Box<dyn UsageColumns> = if options.inodes {
+            Box::new(InodeColumns)
+        } else if options.human_readable {
+            Box::new(BlockColumns {
+                unit: BlockSize::Human,
+            })
+        } else {
+            Box::new(BlockColumns {
+                unit: options.block_size.clone(),
+            })
+        };
+        DfCommand { columns, options }
+    }
+
+    fn run(&self, files: &[String]) -> io::Result<bool> {
+        let mounts = mounts::read_mounts()?;
+        let mut ok = true;
+
+        let selected: Vec<&MountEntry> = if files.is_empty() {
+            mounts.iter().collect()
+        } else {
+            let mut selected = Vec::new();
+            for file in files {
+                match containing_mount(&mounts, Path::new(file)) {
+                    Ok(Some(mount)) => selected.push(mount),
+                    Ok(None) => {
+                        eprintln!("df: {}: can't find mount point", file);
+                        ok = false;
+                    }
+                    Err(e) => {
+                        eprintln!("df: {}: {}", file, error::message(&e));
+                        ok = false;
+                    }
+                }
+            }
+            selected
+        };
+
+        let mut rows = Vec::new();
+        for (index, mount) in selected.iter().enumerate() {
+            // A mount point that was mounted over is only listed once
+            if files.is_empty()
+                && !self.options.all
+                && selected[..index]
+                    .iter()
+                    .any(|later| later.mount_point == mount.mount_point)
+            {
+                continue;
+            }
+
+            let stats = match mounts::statvfs(Path::new(&mount.mount_point)) {
+                Ok(stats) => stats,
+                Err(e) => {
+                    if !files.is_empty() {
+                        eprintln!("df: {}: {}", mount.mount_point, error::message(&e));
+                        ok = false;
+                    }
+                    continue;
+                }
+            };
+
+            // Pseudo filesystems (proc, sysfs, ...) report no blocks and are
+            // hidden unless asked for or named explicitly
+            if stats.blocks == 0 && !self.options.all && files.is_empty() {
+                continue;
+            }
+
+            let mut row = vec![mount.device.clone()];
+            if self.options.print_type {
+                row.push(mount.fs_type.clone());
+            }
+            row.extend(self.columns.values(&stats));
+            row.push(mount.mount_point.clone());
+            rows.push(row);
+        }
+
+        if !rows.is_empty() || files.is_empty() {
+            self.print_table(rows);
+        }
+        Ok(ok)
+    }
+
+    fn print_table(&self, rows: Vec<Vec<String>>) {
+        let mut header = vec![String::from("Filesystem")];
+        if self.options.print_type {
+            header.push(String::from("Type"));
+        }
+        header.extend(self.columns.headers());
+        header.push(String::from("Mounted on"));
+
+        let mut min_widths = vec![14];
+        if self.options.print_type {
+            min_widths.push(4);
+        }
+        min_widths.extend(self.columns.min_widths());
+        min_widths.push(0);
+
+        let columns = header.len();
+        let mut widths: Vec<usize> = header
+            .iter()
+            .zip(&min_widths)
+            .map(|(title, min)| title.len().max(*min))
+            .collect();
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        // Text columns are left-aligned, numbers right-aligned; the last
+        // column is never padded
+        let text_columns = if self.options.print_type { 2 } else { 1 };
+        let format_row = |cells: Vec<&str>| {
+            let mut line = String::new();
+            for (index, cell) in cells.iter().enumerate() {
+                if index > 0 {
+                    line.push(' ');
+                }
+                if index + 1 == columns {
+                    line.push_str(cell);
+                } else if index < text_columns {
+                    line.push_str(&format!("{:<width$}", cell, width = widths[index]));
+                } else {
+                    line.push_str(&format!("{:>width$}", cell, width = widths[index]));
+                }
+            }
+            line
+        };
+
+        println!(
+            "{}",
+            format_row(header.iter().map(|cell| cell.as_str()).collect())
+        );
+        for row in &rows {
+            println!(
+                "{}",
+                format_row(row.iter().map(|cell| cell.as_str()).collect())
+            );
+        }
+    }
+}
+
+// Finds the mount a file lives on by comparing device numbers; later
+// entries win because they are mounted on top of earlier ones
+fn containing_mount<'m>(
+    mounts: &'m [MountEntry],
+    file: &Path,
+) -> io::Result<Option<&'m MountEntry>> {
+    let device = fs::metadata(file)?.dev();
+    Ok(mounts.iter().rev().find(|mount| {
+        fs::metadata(&mount.mount_point)
+            .map(|metadata| metadata.dev() == device)
+            .unwrap_or(false)
+    }))
+}
+
+pub fn run(files: &[String], options: &DfOptions) -> io::Result<bool> {
+    let command = DfCommand::new(options);
+    command.run(files)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = DfOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--human-readable" => options.human_readable = true,
+            "--inodes" => options.inodes = true,
+            "--all" => options.all = true,
+            "--print-type" => options.print_type = true,
+            _ if arg.starts_with("--block-size=") => {
+                let value = &arg["--block-size=".len()..];
+                options.block_size = BlockSize::parse(value).unwrap_or_else(|| {
+                    eprintln!("df: invalid --block-size argument '{}'", value);
+                    std::process::exit(1);
+                });
+                options.human_readable = false;
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("df: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'h' => options.human_readable = true,
+                        'i' => options.inodes = true,
+                        'a' => options.all = true,
+                        'T' => options.print_type = true,
+                        'k' => {
+                            options.human_readable = false;
+                            options.block_size = BlockSize::default();
+                        }
+                        _ => {
+                            eprintln!("df: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    match run(&files, &options) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!(
+                "df: cannot read table of mounted file systems: {}",
+                error::message(&e)
+            );
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,977 @@
+use cmds_lite::collate::Collator;
+use cmds_lite::color::{Palette, When};
+use cmds_lite::datetime::{self, Timestamp};
+use cmds_lite::error;
+use cmds_lite::platform;
+use cmds_lite::tty;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{self, BufWriter, IsTerminal, Read, StdoutLock, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+// GNU diff's colors: bold file headers, cyan line numbers, red for lines
+// taken away and green for lines added
+const DEFAULT_PALETTE: &str = "rs=0:hd=1:ad=32:de=31:ln=36";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Normal,
+    // -u: unified output with this many lines of context
+    Unified(usize),
+    // -y: the files side by side in columns filling this width
+    SideBySide(usize),
+}
+
+pub struct DiffOptions {
+    pub format: Format,
+    // -q: only say whether the files differ
+    pub brief: bool,
+    // -s: say so when the files are the same
+    pub report_identical: bool,
+    // -i: compare lines without regard to case
+    pub ignore_case: bool,
+    // -a: compare files as text even if they look binary
+    pub text: bool,
+    // -r: compare subdirectories found in both directories, recursively
+    pub recursive: bool,
+    // -t: pad side-by-side output with spaces instead of tabs
+    pub expand_tabs: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            format: Format::Normal,
+            brief: false,
+            report_identical: false,
+            ignore_case: false,
+            text: false,
+            recursive: false,
+            expand_tabs: false,
+        }
+    }
+}
+
+// One side of the comparison: its lines, each with its newline if it has
+// one, and a number per line that is equal exactly when the lines compare
+// equal
+struct Side {
+    name: String,
+    data: Vec<u8>,
+    lines: Vec<(usize, usize)>,
+    ids: Vec<u32>,
+    modified: Timestamp,
+}
+
+impl Side {
+    fn line(&self, index: usize) -> &[u8] {
+        let (start, end) = self.lines[index];
+        &self.data[start..end]
+    }
+}
+
+// A run of lines removed from the first file, [old_start, old_end), and
+// lines added from the second, [new_start, new_end); either may be empty
+#[derive(Clone, Copy)]
+struct Change {
+    old_start: usize,
+    old_end: usize,
+    new_start: usize,
+    new_end: usize,
+}
+
+// Myers' O(ND) algorithm in its linear-space form: the middle of an
+// optimal edit path is found by searching from both ends at once, and the
+// halves on either side of it are solved the same way.
+struct Myers<'a> {
+    a: &'a [u32],
+    b: &'a [u32],
+    removed: Vec<bool>,
+    added: Vec<bool>,
+}
+
+impl<'a> Myers<'a> {
+    fn compare(&mut self, mut a_lo: usize, mut a_hi: usize, mut b_lo: usize, mut b_hi: usize) {
+        while a_lo < a_hi && b_lo < b_hi && self.a[a_lo] == self.b[b_lo] {
+            a_lo += 1;
+            b_lo += 1;
+        }
+        while a_lo < a_hi && b_lo < b_hi && self.a[a_hi - 1] == self.b[b_hi - 1] {
+            a_hi -= 1;
+            b_hi -= 1;
+        }
+        if a_lo == a_hi {
+            self.added[b_lo..b_hi].fill(true);
+        } else if b_lo == b_hi {
+            self.removed[a_lo..a_hi].fill(true);
+        } else {
+            let (x, y) = self.split(a_lo, a_hi, b_lo, b_hi);
+            self.compare(a_lo, x, b_lo, y);
+            self.compare(x, a_hi, y, b_hi);
+        }
+    }
+
+    // A point on an optimal path between the two corners with about half
+    // the edits on each side of it. Both ranges are non-empty and differ
+    // at both ends, so the path has at least two edits and each half has
+    // fewer than the whole.
+    fn split(&self, a_lo: usize, a_hi: usize, b_lo: usize, b_hi: usize) -> (usize, usize) {
+        let n = (a_hi - a_lo) as isize;
+        let m = (b_hi - b_lo) as isize;
+        let delta = n - m;
+        let odd = delta % 2 != 0;
+        let max = (n + m + 1) / 2;
+        let offset = max + 1;
+        // Furthest x reached on each diagonal k = x - y, from the start
+        // going forward and from the end going backward
+        let mut forward = vec![0isize; (2 * max + 3) as usize];
+        let mut backward = vec![0isize; (2 * max + 3) as usize];
+        let a = |i: isize| self.a[a_lo + i as usize];
+        let b = |j: isize| self.b[b_lo + j as usize];
+
+        for d in 0..=max {
+            for k in (-d..=d).step_by(2) {
+                let i = (k + offset) as usize;
+                let mut x = if k == -d || (k != d && forward[i - 1] < forward[i + 1]) {
+                    forward[i + 1]
+                } else {
+                    forward[i - 1] + 1
+                };
+                let mut y = x - k;
+                let start = (x, y);
+                while x < n && y < m && a(x) == b(y) {
+                    x += 1;
+                    y += 1;
+                }
+                forward[i] = x;
+                let opposite = delta - k;
+                if odd
+                    && (-(d - 1)..=d - 1).contains(&opposite)
+                    && x + backward[(opposite + offset) as usize] >= n
+                {
+                    return (a_lo + start.0 as usize, b_lo + start.1 as usize);
+                }
+            }
+            for k in (-d..=d).step_by(2) {
+                let i = (k + offset) as usize;
+                let mut x = if k == -d || (k != d && backward[i - 1] < backward[i + 1]) {
+                    backward[i + 1]
+                } else {
+                    backward[i - 1] + 1
+                };
+                let mut y = x - k;
+                let start = (x, y);
+                while x < n && y < m && a(n - 1 - x) == b(m - 1 - y) {
+                    x += 1;
+                    y += 1;
+                }
+                backward[i] = x;
+                let opposite = delta - k;
+                if !odd
+                    && (-d..=d).contains(&opposite)
+                    && x + forward[(opposite + offset) as usize] >= n
+                {
+                    return (a_lo + (n - start.0) as usize, b_lo + (m - start.1) as usize);
+                }
+            }
+        }
+        unreachable!("the forward and backward searches always meet")
+    }
+}
+
+fn changes(old: &Side, new: &Side) -> Vec<Change> {
+    let mut myers = Myers {
+        a: &old.ids,
+        b: &new.ids,
+        removed: vec![false; old.ids.len()],
+        added: vec![false; new.ids.len()],
+    };
+    myers.compare(0, old.ids.len(), 0, new.ids.len());
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.ids.len() || j < new.ids.len() {
+        if i < old.ids.len() && j < new.ids.len() && !myers.removed[i] && !myers.added[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let (old_start, new_start) = (i, j);
+        while i < old.ids.len() && myers.removed[i] {
+            i += 1;
+        }
+        while j < new.ids.len() && myers.added[j] {
+            j += 1;
+        }
+        changes.push(Change {
+            old_start,
+            old_end: i,
+            new_start,
+            new_end: j,
+        });
+    }
+    changes
+}
+
+// GNU diff's time stamp in unified headers
+fn header_time(stamp: Timestamp) -> String {
+    match datetime::local_time(stamp.secs) {
+        Some(t) => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:09} {}",
+            t.year,
+            t.month,
+            t.day,
+            t.hour,
+            t.minute,
+            t.second,
+            stamp.nanos,
+            datetime::format_offset(t.utc_offset)
+        ),
+        None => stamp.secs.to_string(),
+    }
+}
+
+// A line range as normal output shows it: "N" for one line, "N,M" for
+// several, and the line before for an empty range
+fn normal_range(start: usize, end: usize) -> String {
+    match end - start {
+        0 => start.to_string(),
+        1 => end.to_string(),
+        _ => format!("{},{}", start + 1, end),
+    }
+}
+
+// A line range as a unified hunk header shows it: "N" for one line and
+// "N,COUNT" otherwise, where an empty range starts at the line before
+fn unified_range(start: usize, end: usize) -> String {
+    match end - start {
+        0 => format!("{},0", start),
+        1 => end.to_string(),
+        count => format!("{},{}", start + 1, count),
+    }
+}
+
+// diff exits 0 when the files are the same, 1 when they differ and 2 for
+// trouble; across several pairs of files the worst one counts
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Status {
+    Same,
+    Different,
+    Trouble,
+}
+
+// Column layout of side-by-side output, as GNU diff computes it
+struct Columns {
+    // Columns each side's text may take
+    half: usize,
+    // Where the gutter mark goes
+    gutter: usize,
+    // Where the second file's text starts
+    second: usize,
+}
+
+impl Columns {
+    // With tabs expanded the columns need not line up with tab stops
+    fn new(width: usize, expand_tabs: bool) -> Self {
+        let tab = if expand_tabs { 1 } else { TAB_WIDTH };
+        let offset = (width + tab + 3) / (2 * tab) * tab;
+        let half = offset.saturating_sub(3).min(width.saturating_sub(offset));
+        let second = if half > 0 { offset } else { width };
+        Columns {
+            half,
+            gutter: (half + second).saturating_sub(1) / 2,
+            second,
+        }
+    }
+}
+
+const TAB_WIDTH: usize = 8;
+
+struct DiffCommand<'a> {
+    options: &'a DiffOptions,
+    // Lines already seen, by their comparison key
+    ids: HashMap<Vec<u8>, u32>,
+    out: BufWriter<StdoutLock<'static>>,
+    // The colors to use, or None for plain output
+    palette: Option<Palette>,
+    // The options as given, repeated in the "diff" line that introduces
+    // each pair of files compared within directories
+    switches: String,
+    collator: Collator,
+}
+
+impl<'a> DiffCommand<'a> {
+    fn trouble(&mut self, message: &str) -> Status {
+        let _ = self.out.flush();
+        eprintln!("diff: {}", message);
+        Status::Trouble
+    }
+
+    fn read_side(&mut self, name: &str) -> io::Result<Side> {
+        let mut data = Vec::new();
+        let modified = if name == "-" {
+            io::stdin().lock().read_to_end(&mut data)?;
+            Timestamp::now()
+        } else {
+            let metadata = fs::metadata(name)?;
+            if metadata.is_dir() {
+                return Err(io::Error::from(io::ErrorKind::IsADirectory));
+            }
+            data = fs::read(name)?;
+            Timestamp::new(metadata.mtime(), metadata.mtime_nsec() as u32)
+        };
+
+        let mut lines = Vec::new();
+        let mut start = 0;
+        while start < data.len() {
+            let end = data[start..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(data.len(), |i| start + i + 1);
+            lines.push((start, end));
+            start = end;
+        }
+        let ids = lines
+            .iter()
+            .map(|&(start, end)| {
+                let line = &data[start..end];
+                let key = if self.options.ignore_case {
+                    line.to_ascii_lowercase()
+                } else {
+                    line.to_vec()
+                };
+                let next = self.ids.len() as u32;
+                *self.ids.entry(key).or_insert(next)
+            })
+            .collect();
+        Ok(Side {
+            name: name.to_string(),
+            data,
+            lines,
+            ids,
+            modified,
+        })
+    }
+
+    // The escape sequences around text colored as `key`, if coloring
+    fn colors(&self, key: &str) -> (String, String) {
+        match &self.palette {
+            Some(palette) => (palette.start(key), palette.end()),
+            None => (String::new(), String::new()),
+        }
+    }
+
+    // Writes a line of text colored as `key`, followed by a newline
+    fn write_colored(&mut self, key: &str, text: &[u8]) -> io::Result<()> {
+        let (start, end) = self.colors(key);
+        self.out.write_all(start.as_bytes())?;
+        self.out.write_all(text)?;
+        self.out.write_all(end.as_bytes())?;
+        self.out.write_all(b"\n")
+    }
+
+    fn write_line(&mut self, prefix: &str, key: &str, side: &Side, index: usize) -> io::Result<()> {
+        let line = side.line(index);
+        let text = line.strip_suffix(b"\n").unwrap_or(line);
+        if key.is_empty() {
+            self.out.write_all(prefix.as_bytes())?;
+            self.out.write_all(text)?;
+            self.out.write_all(b"\n")?;
+        } else {
+            self.write_colored(key, &[prefix.as_bytes(), text].concat())?;
+        }
+        if !line.ends_with(b"\n") {
+            self.out.write_all(b"\\ No newline at end of file\n")?;
+        }
+        Ok(())
+    }
+
+    fn write_normal(&mut self, old: &Side, new: &Side, changes: &[Change]) -> io::Result<()> {
+        for change in changes {
+            let kind = match (
+                change.old_start == change.old_end,
+                change.new_start == change.new_end,
+            ) {
+                (true, _) => 'a',
+                (_, true) => 'd',
+                _ => 'c',
+            };
+            let header = format!(
+                "{}{}{}",
+                normal_range(change.old_start, change.old_end),
+                kind,
+                normal_range(change.new_start, change.new_end)
+            );
+            self.write_colored("ln", header.as_bytes())?;
+            for i in change.old_start..change.old_end {
+                self.write_line("< ", "de", old, i)?;
+            }
+            if kind == 'c' {
+                writeln!(self.out, "---")?;
+            }
+            for j in change.new_start..change.new_end {
+                self.write_line("> ", "ad", new, j)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_unified(
+        &mut self,
+        old: &Side,
+        new: &Side,
+        changes: &[Change],
+        context: usize,
+    ) -> io::Result<()> {
+        let header = format!("--- {}\t{}", old.name, header_time(old.modified));
+        self.write_colored("hd", header.as_bytes())?;
+        let header = format!("+++ {}\t{}", new.name, header_time(new.modified));
+        self.write_colored("hd", header.as_bytes())?;
+
+        let mut rest = changes;
+        while let Some(first) = rest.first() {
+            // Changes separated by no more than twice the context share a
+            // hunk
+            let mut count = 1;
+            while count < rest.len()
+                && rest[count].old_start - rest[count - 1].old_end <= 2 * context
+            {
+                count += 1;
+            }
+            let (hunk, after) = rest.split_at(count);
+            rest = after;
+            let last = hunk[count - 1];
+
+            let old_start = first.old_start.saturating_sub(context);
+            let new_start = first.new_start - (first.old_start - old_start);
+            let old_end = (last.old_end + context).min(old.lines.len());
+            let new_end = last.new_end + (old_end - last.old_end);
+            let header = format!(
+                "@@ -{} +{} @@",
+                unified_range(old_start, old_end),
+                unified_range(new_start, new_end)
+            );
+            self.write_colored("ln", header.as_bytes())?;
+
+            let mut i = old_start;
+            for change in hunk {
+                for k in i..change.old_start {
+                    self.write_line(" ", "", old, k)?;
+                }
+                for k in change.old_start..change.old_end {
+                    self.write_line("-", "de", old, k)?;
+                }
+                for k in change.new_start..change.new_end {
+                    self.write_line("+", "ad", new, k)?;
+                }
+                i = change.old_end;
+            }
+            for k in i..old_end {
+                self.write_line(" ", "", old, k)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Moves from one column to a later one, with tabs as far as they go
+    // unless tabs are expanded
+    fn pad(&self, row: &mut Vec<u8>, from: usize, to: usize) {
+        let mut column = from;
+        if !self.options.expand_tabs {
+            while (column / TAB_WIDTH + 1) * TAB_WIDTH <= to {
+                row.push(b'\t');
+                column = (column / TAB_WIDTH + 1) * TAB_WIDTH;
+            }
+        }
+        row.resize(row.len() + to.saturating_sub(column), b' ');
+    }
+
+    // Adds a line's text, cut off at the width of a column; tab stops
+    // count from the start of the column. Returns the width used.
+    fn put_text(&self, row: &mut Vec<u8>, line: &[u8], limit: usize) -> usize {
+        let text = line.strip_suffix(b"\n").unwrap_or(line);
+        let mut position = 0;
+        for c in String::from_utf8_lossy(text).chars() {
+            if c == '\t' {
+                let next = (position / TAB_WIDTH + 1) * TAB_WIDTH;
+                if self.options.expand_tabs {
+                    let next = next.min(limit);
+                    row.resize(row.len() + next - position, b' ');
+                    position = next;
+                } else if next < limit {
+                    row.push(b'\t');
+                    position = next;
+                } else {
+                    break;
+                }
+            } else {
+                if position >= limit {
+                    break;
+                }
+                let mut buffer = [0; 4];
+                row.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                position += 1;
+            }
+        }
+        position
+    }
+
+    // One row of side-by-side output: the first file's line, the gutter
+    // mark and the second file's line
+    fn write_row(
+        &mut self,
+        columns: &Columns,
+        left: Option<&[u8]>,
+        mark: u8,
+        right: Option<&[u8]>,
+    ) -> io::Result<()> {
+        let (de_start, de_end) = self.colors("de");
+        let (ad_start, ad_end) = self.colors("ad");
+        let mut row = Vec::new();
+        let mut column = 0;
+        if let Some(line) = left {
+            if mark != b' ' {
+                row.extend_from_slice(de_start.as_bytes());
+            }
+            column = self.put_text(&mut row, line, columns.half);
+            if mark != b' ' {
+                row.extend_from_slice(de_end.as_bytes());
+            }
+        }
+        if mark != b' ' {
+            self.pad(&mut row, column, columns.gutter);
+            row.push(mark);
+            column = columns.gutter + 1;
+        }
+        if let Some(line) = right.filter(|line| *line != b"\n") {
+            self.pad(&mut row, column, columns.second);
+            if mark != b' ' {
+                row.extend_from_slice(ad_start.as_bytes());
+            }
+            self.put_text(&mut row, line, columns.half);
+            if mark != b' ' {
+                row.extend_from_slice(ad_end.as_bytes());
+            }
+        }
+        // Like GNU diff, a last line without a newline is shown without one
+        if right.or(left).is_some_and(|line| line.ends_with(b"\n")) {
+            row.push(b'\n');
+        }
+        self.out.write_all(&row)
+    }
+
+    fn write_side_by_side(
+        &mut self,
+        old: &Side,
+        new: &Side,
+        changes: &[Change],
+        width: usize,
+    ) -> io::Result<()> {
+        let columns = Columns::new(width, self.options.expand_tabs);
+        let (mut i, mut j) = (0, 0);
+        let end = Change {
+            old_start: old.lines.len(),
+            old_end: old.lines.len(),
+            new_start: new.lines.len(),
+            new_end: new.lines.len(),
+        };
+        for change in changes.iter().chain([&end]) {
+            while i < change.old_start {
+                self.write_row(&columns, Some(old.line(i)), b' ', Some(new.line(j)))?;
+                i += 1;
+                j += 1;
+            }
+            // Changed lines pair up; the rest are only on one side
+            while i < change.old_end && j < change.new_end {
+                self.write_row(&columns, Some(old.line(i)), b'|', Some(new.line(j)))?;
+                i += 1;
+                j += 1;
+            }
+            while i < change.old_end {
+                self.write_row(&columns, Some(old.line(i)), b'<', None)?;
+                i += 1;
+            }
+            while j < change.new_end {
+                self.write_row(&columns, None, b'>', Some(new.line(j)))?;
+                j += 1;
+            }
+        }
+        Ok(())
+    }
+
+    // Compares two files. `within` is set when they were found by
+    // comparing directories, so that their names are announced first.
+    fn compare_files(&mut self, first: &str, second: &str, within: bool) -> Status {
+        if first != "-" && second != "-" {
+            if let (Ok(a), Ok(b)) = (fs::metadata(first), fs::metadata(second)) {
+                if a.dev() == b.dev() && a.ino() == b.ino() {
+                    return self.report_same(first, second);
+                }
+            }
+        }
+        let old = match self.read_side(first) {
+            Ok(side) => side,
+            Err(e) => return self.trouble(&format!("{}: {}", first, error::message(&e))),
+        };
+        let new = match self.read_side(second) {
+            Ok(side) => side,
+            Err(e) => return self.trouble(&format!("{}: {}", second, error::message(&e))),
+        };
+
+        // Equal ids mean equal lines, or lines equal but for case with -i
+        if old.ids == new.ids {
+            return self.report_same(first, second);
+        }
+
+        let binary = |side: &Side| side.data[..side.data.len().min(8192)].contains(&0);
+        let result = if self.options.brief || (!self.options.text && (binary(&old) || binary(&new)))
+        {
+            let kind = if self.options.brief {
+                "Files"
+            } else {
+                "Binary files"
+            };
+            writeln!(self.out, "{} {} and {} differ", kind, first, second)
+        } else {
+            let changes = changes(&old, &new);
+            let announce = if within {
+                writeln!(self.out, "diff{} {} {}", self.switches, first, second)
+            } else {
+                Ok(())
+            };
+            announce.and_then(|_| match self.options.format {
+                Format::Normal => self.write_normal(&old, &new, &changes),
+                Format::Unified(context) => self.write_unified(&old, &new, &changes, context),
+                Format::SideBySide(width) => self.write_side_by_side(&old, &new, &changes, width),
+            })
+        };
+        match result {
+            Ok(()) => Status::Different,
+            Err(e) => self.write_failed(&e),
+        }
+    }
+
+    fn write_failed(&mut self, e: &io::Error) -> Status {
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            std::process::exit(2);
+        }
+        self.trouble(&format!("write error: {}", error::message(e)))
+    }
+
+    fn report_same(&mut self, first: &str, second: &str) -> Status {
+        if self.options.report_identical {
+            if let Err(e) = writeln!(self.out, "Files {} and {} are identical", first, second) {
+                return self.write_failed(&e);
+            }
+        }
+        Status::Same
+    }
+
+    // Entry names in a directory, sorted
+    fn entries(&self, dir: &Path) -> io::Result<Vec<OsString>> {
+        let mut names = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<io::Result<Vec<_>>>()?;
+        names.sort_by(|a, b| self.collator.compare(a.as_bytes(), b.as_bytes()));
+        Ok(names)
+    }
+
+    // Compares the files two directories have in common, reporting those
+    // only one has. Subdirectories in both are compared with -r, and only
+    // mentioned otherwise.
+    fn compare_dirs(&mut self, first: &Path, second: &Path) -> Status {
+        let (old, new) = match (self.entries(first), self.entries(second)) {
+            (Ok(old), Ok(new)) => (old, new),
+            (Err(e), _) => {
+                return self.trouble(&format!("{}: {}", first.display(), error::message(&e)))
+            }
+            (_, Err(e)) => {
+                return self.trouble(&format!("{}: {}", second.display(), error::message(&e)))
+            }
+        };
+
+        let mut status = Status::Same;
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            let order = match (old.get(i), new.get(j)) {
+                (Some(a), Some(b)) => self.collator.compare(a.as_bytes(), b.as_bytes()),
+                (Some(_), None) => Ordering::Less,
+                _ => Ordering::Greater,
+            };
+            let result = match order {
+                Ordering::Less => {
+                    i += 1;
+                    self.report_only(first, &old[i - 1])
+                }
+                Ordering::Greater => {
+                    j += 1;
+                    self.report_only(second, &new[j - 1])
+                }
+                Ordering::Equal => {
+                    i += 1;
+                    j += 1;
+                    self.compare_entries(&first.join(&old[i - 1]), &second.join(&new[j - 1]))
+                }
+            };
+            status = status.max(result);
+        }
+        status
+    }
+
+    fn report_only(&mut self, dir: &Path, name: &OsStr) -> Status {
+        match writeln!(
+            self.out,
+            "Only in {}: {}",
+            dir.display(),
+            name.to_string_lossy()
+        ) {
+            Ok(()) => Status::Different,
+            Err(e) => self.write_failed(&e),
+        }
+    }
+
+    fn compare_entries(&mut self, first: &Path, second: &Path) -> Status {
+        let (a, b) = match (fs::metadata(first), fs::metadata(second)) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) => {
+                return self.trouble(&format!("{}: {}", first.display(), error::message(&e)))
+            }
+            (_, Err(e)) => {
+                return self.trouble(&format!("{}: {}", second.display(), error::message(&e)))
+            }
+        };
+        let kind = |metadata: &fs::Metadata| {
+            if metadata.is_dir() {
+                "directory"
+            } else if metadata.len() == 0 {
+                "regular empty file"
+            } else {
+                "regular file"
+            }
+        };
+        let result = match (a.is_dir(), b.is_dir()) {
+            (true, true) if self.options.recursive => return self.compare_dirs(first, second),
+            (true, true) => writeln!(
+                self.out,
+                "Common subdirectories: {} and {}",
+                first.display(),
+                second.display()
+            ),
+            (false, false) => {
+                return self.compare_files(
+                    &first.to_string_lossy(),
+                    &second.to_string_lossy(),
+                    true,
+                )
+            }
+            _ => writeln!(
+                self.out,
+                "File {} is a {} while file {} is a {}",
+                first.display(),
+                kind(&a),
+                second.display(),
+                kind(&b)
+            ),
+        };
+        match result {
+            Ok(()) if a.is_dir() && b.is_dir() => Status::Same,
+            Ok(()) => Status::Different,
+            Err(e) => self.write_failed(&e),
+        }
+    }
+
+    fn run(&mut self, first: &str, second: &str) -> Status {
+        let is_dir = |name: &str| name != "-" && Path::new(name).is_dir();
+        let status = if is_dir(first) && is_dir(second) {
+            self.compare_dirs(Path::new(first), Path::new(second))
+        } else {
+            let (first, second) = resolve_operands(first, second);
+            self.compare_files(&first, &second, false)
+        };
+        match self.out.flush() {
+            Ok(()) => status,
+            Err(e) => self.write_failed(&e),
+        }
+    }
+}
+
+// When one operand is a directory, the file of the same name in it is
+// compared instead
+fn resolve_operands(first: &str, second: &str) -> (String, String) {
+    let is_dir = |name: &str| name != "-" && Path::new(name).is_dir();
+    let inside = |dir: &str, file: &str| {
+        let base = Path::new(file)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Path::new(dir).join(base).to_string_lossy().to_string()
+    };
+    match (is_dir(first), is_dir(second)) {
+        (true, false) if second != "-" => (inside(first, second), second.to_string()),
+        (false, true) if first != "-" => (first.to_string(), inside(second, first)),
+        _ => (first.to_string(), second.to_string()),
+    }
+}
+
+// An argument as the shell would need it, for the "diff" lines of -r
+fn quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./,:+@%^".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+// diff exits 2 for trouble, leaving 1 to mean the files differ
+fn fail(message: &str) -> ! {
+    eprintln!("diff: {}", message);
+    std::process::exit(2);
+}
+
+fn parse_number(value: &str, what: &str) -> usize {
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("invalid {} '{}'", what, value)))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = DiffOptions::default();
+    let mut files = Vec::new();
+    let mut switches = String::new();
+    let mut side_by_side = false;
+    let mut width = None;
+    let mut color = When::Never;
+    let mut palette = Palette::new(DEFAULT_PALETTE);
+    let mut paginate = false;
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+        // Not one of the switches repeated in "diff ..." headers
+        if arg == "--paginate" {
+            paginate = true;
+            continue;
+        }
+        switches.push(' ');
+        switches.push_str(&quote(arg));
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--brief" => options.brief = true,
+            "--report-identical-files" => options.report_identical = true,
+            "--ignore-case" => options.ignore_case = true,
+            "--text" => options.text = true,
+            "--recursive" => options.recursive = true,
+            "--expand-tabs" => options.expand_tabs = true,
+            "--side-by-side" => side_by_side = true,
+            "--normal" => options.format = Format::Normal,
+            "--unified" => options.format = Format::Unified(3),
+            "--color" => color = When::Auto,
+            _ if arg.starts_with("--unified=") => {
+                let value = &arg["--unified=".len()..];
+                options.format = Format::Unified(parse_number(value, "context length"))
+            }
+            _ if arg.starts_with("--width=") => {
+                width = Some(parse_number(&arg["--width=".len()..], "width"))
+            }
+            _ if arg.starts_with("--color=") => {
+                let value = &arg["--color=".len()..];
+                color = When::parse(value)
+                    .unwrap_or_else(|| fail(&format!("invalid argument '{}' for '--color'", value)))
+            }
+            _ if arg.starts_with("--palette=") => palette.update(&arg["--palette=".len()..]),
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'q' => options.brief = true,
+                        's' => options.report_identical = true,
+                        'i' => options.ignore_case = true,
+                        'a' => options.text = true,
+                        'r' => options.recursive = true,
+                        't' => options.expand_tabs = true,
+                        'y' => side_by_side = true,
+                        'u' => options.format = Format::Unified(3),
+                        'U' | 'W' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                switches.push(' ');
+                                switches.push_str(&quote(&args[i - 1]));
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            if flag == 'U' {
+                                options.format =
+                                    Format::Unified(parse_number(&value, "context length"));
+                            } else {
+                                width = Some(parse_number(&value, "width"));
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if side_by_side {
+        if options.format != Format::Normal {
+            fail("conflicting output style options");
+        }
+        // Without -W, side by side fills the terminal, or GNU's 130
+        // columns when not writing to one
+        let width = width.unwrap_or_else(|| {
+            if io::stdout().is_terminal() {
+                platform::terminal_width().unwrap_or(130)
+            } else {
+                130
+            }
+        });
+        options.format = Format::SideBySide(width);
+    }
+
+    match files.len() {
+        0 => fail("missing operand"),
+        1 => fail(&format!("missing operand after '{}'", files[0])),
+        2 => {}
+        _ => fail(&format!("extra operand '{}'", files[2])),
+    }
+
+    // Colors are settled while standard output is still the terminal
+    let palette = color.enabled().then_some(palette);
+    if paginate {
+        tty::paginate();
+    }
+    let mut command = DiffCommand {
+        options: &options,
+        ids: HashMap::new(),
+        out: BufWriter::new(io::stdout().lock()),
+        palette,
+        switches,
+        collator: Collator::from_environment(),
+    };
+    match command.run(&files[0], &files[1]) {
+        Status::Same => std::process::exit(0),
+        Status::Different => std::process::exit(1),
+        Status::Trouble => std::process::exit(2),
+    }
+}
@@ -0,0 +1,64 @@
+use cmds_lite::error;
+use cmds_lite::pathname;
+use std::env;
+use std::io::{self, Write};
+
+#[derive(Default)]
+pub struct DirnameOptions {
+    // Separate outputs with NUL instead of newline
+    pub zero: bool,
+}
+
+pub fn run(names: &[String], options: &DirnameOptions) -> io::Result<()> {
+    let terminator = if options.zero { b'\0' } else { b'\n' };
+    let mut stdout = io::stdout().lock();
+    for name in names {
+        stdout.write_all(pathname::dir_name(name).as_bytes())?;
+        stdout.write_all(&[terminator])?;
+    }
+    stdout.flush()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = DirnameOptions::default();
+    let mut names = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            names.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--zero" => options.zero = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("dirname: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'z' => options.zero = true,
+                        _ => {
+                            eprintln!("dirname: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if names.is_empty() {
+        eprintln!("dirname: missing operand");
+        std::process::exit(1);
+    }
+
+    if let Err(e) = run(&names, &options) {
+        eprintln!("dirname: write error: {}", error::message(&e));
+        std::process::exit(1);
+    }
+}
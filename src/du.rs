@@ -0,0 +1,643 @@
+use cmds_lite::error;
+use cmds_lite::platform;
+use cmds_lite::size::{self, BlockSize};
+use cmds_lite::walk::{self, WalkOptions, Walker};
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{self, BufWriter, StdoutLock, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+#[derive(Default)]
+pub struct DuOptions {
+    pub summarize: bool,
+    pub all: bool,
+    pub grand_total: bool,
+    pub human_readable: bool,
+    // --block-size: the unit sizes are shown in unless -h is given
+    pub block_size: BlockSize,
+    pub max_depth: Option<usize>,
+    pub apparent_size: bool,
+    pub one_file_system: bool,
+    // -t, --threshold: leave out entries smaller than this many bytes, or
+    // when negative, larger than its magnitude
+    pub threshold: i64,
+    // --sort: list entries largest first instead of in walk order
+    pub sort: bool,
+    // Number of scanning threads; 1 walks sequentially
+    pub threads: usize,
+}
+
+trait SizeFormatter {
+    fn format(&self, bytes: u64) -> String;
+}
+
+struct BlockFormatter {
+    unit: BlockSize,
+}
+
+impl SizeFormatter for BlockFormatter {
+    fn format(&self, bytes: u64) -> String {
+        self.unit.format(bytes)
+    }
+}
+
+struct HumanFormatter;
+
+impl SizeFormatter for HumanFormatter {
+    fn format(&self, bytes: u64) -> String {
+        size::human_readable(bytes)
+    }
+}
+
+// Sizing and listing rules shared by the sequential and parallel scanners
+struct Accounting<'a> {
+    options: &'a DuOptions,
+    // (device, inode) of directories and multiply-linked files already
+    // counted
+    seen: Mutex<HashSet<(u64, u64)>>,
+    // With more than one operand they can overlap, so every file is
+    // remembered, not just hard links
+    hash_all: bool,
+}
+
+impl<'a> Accounting<'a> {
+    // None for a directory or file that was already counted, such as a hard
+    // link or an operand inside an earlier one
+    fn size_of(&self, metadata: &fs::Metadata) -> Option<u64> {
+        match self.key(metadata) {
+            Some(key) if !self.claim(key) => None,
+            _ => Some(self.bytes(metadata)),
+        }
+    }
+
+    fn bytes(&self, metadata: &fs::Metadata) -> u64 {
+        if self.options.apparent_size {
+            metadata.len()
+        } else {
+            metadata.blocks() * 512
+        }
+    }
+
+    // The (device, inode) to remember for an entry that could be met again
+    fn key(&self, metadata: &fs::Metadata) -> Option<(u64, u64)> {
+        (self.hash_all || metadata.is_dir() || metadata.nlink() > 1)
+            .then(|| (metadata.dev(), metadata.ino()))
+    }
+
+    // True the first time an entry is met
+    fn claim(&self, key: (u64, u64)) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.insert(key)
+    }
+
+    fn is_listed(&self, depth: usize, is_dir: bool) -> bool {
+        if depth == 0 {
+            return true;
+        }
+        let within_depth = if self.options.summarize {
+            false
+        } else {
+            self.options.max_depth.is_none_or(|max| depth <= max)
+        };
+        within_depth && (is_dir || self.options.all)
+    }
+}
+
+struct DuCommand<'a> {
+    formatter: Box<dyn SizeFormatter>,
+    accounting: Accounting<'a>,
+    // With --sort, the entries held back until everything has been scanned
+    sorted: RefCell<Vec<(u64, String)>>,
+    out: RefCell<BufWriter<StdoutLock<'static>>>,
+}
+
+impl<'a> DuCommand<'a> {
+    fn new(options: &'a DuOptions, hash_all: bool) -> Self {
+        let formatter: Box<dyn SizeFormatter> = if options.human_readable {
+            Box::new(HumanFormatter)
+        } else {
+            Box::new(BlockFormatter {
+                unit: options.block_size.clone(),
+            })
+        };
+        DuCommand {
+            formatter,
+            accounting: Accounting {
+                options,
+                seen: Mutex::new(HashSet::new()),
+                hash_all,
+            },
+            sorted: RefCell::new(Vec::new()),
+            out: RefCell::new(BufWriter::new(io::stdout().lock())),
+        }
+    }
+
+    fn run(&self, paths: &[String]) -> bool {
+        let options = self.accounting.options;
+        let mut ok = true;
+        let mut total = 0;
+        for path in paths {
+            let (bytes, scanned) = if options.threads > 1 {
+                self.scan_parallel(path)
+            } else {
+                self.scan(path)
+            };
+            total += bytes;
+            ok &= scanned;
+        }
+
+        // Equal sizes stay in walk order
+        let mut sorted = self.sorted.take();
+        sorted.sort_by_key(|&(bytes, _)| Reverse(bytes));
+        for (bytes, name) in sorted {
+            self.print(bytes, &name);
+        }
+
+        if options.grand_total {
+            self.print(total, "total");
+        }
+        if let Err(e) = self.out.borrow_mut().flush() {
+            write_failed(&e);
+        }
+        ok
+    }
+
+    // Walks one operand and returns its total size, along with whether
+    // everything under it could be read. Directories come before their
+    // contents so that one already counted can be skipped, and are
+    // reported once everything in them has been added up.
+    fn scan(&self, path: &str) -> (u64, bool) {
+        let walk_options = WalkOptions {
+            same_filesystem: self.accounting.options.one_file_system,
+            ..WalkOptions::default()
+        };
+
+        // The directories currently open, with their running sums
+        let mut open: Vec<(usize, PathBuf, u64)> = Vec::new();
+        let mut ok = true;
+        let mut root_total = 0;
+
+        let mut walker = Walker::new(&[path], walk_options);
+        while let Some(item) = walker.next() {
+            let entry = match item {
+                Ok(entry) => entry,
+                Err(e) => {
+                    eprintln!("du: cannot access {}", e);
+                    ok = false;
+                    continue;
+                }
+            };
+
+            let depth = entry.depth;
+            self.close_to(depth, &mut open, &mut root_total);
+
+            let own = match self.accounting.size_of(&entry.metadata) {
+                Some(bytes) => bytes,
+                None => {
+                    walker.skip_current_dir();
+                    continue;
+                }
+            };
+            let is_dir = entry.is_dir();
+            self.add(&mut open, &mut root_total, depth, own, is_dir, entry.path);
+        }
+        self.close_to(0, &mut open, &mut root_total);
+
+        (root_total, ok)
+    }
+
+    // Opens a directory, or adds a file to the directory holding it
+    fn add(
+        &self,
+        open: &mut Vec<(usize, PathBuf, u64)>,
+        root_total: &mut u64,
+        depth: usize,
+        own: u64,
+        is_dir: bool,
+        path: PathBuf,
+    ) {
+        if is_dir {
+            open.push((depth, path, own));
+            return;
+        }
+        match open.last_mut() {
+            Some((_, _, sum)) => *sum += own,
+            None => *root_total = own,
+        }
+        if self.accounting.is_listed(depth, false) {
+            self.report(own, &path.to_string_lossy());
+        }
+    }
+
+    // Closes the open directories that an entry at this depth is not in
+    fn close_to(&self, depth: usize, open: &mut Vec<(usize, PathBuf, u64)>, root_total: &mut u64) {
+        while open
+            .last()
+            .is_some_and(|&(open_depth, _, _)| open_depth >= depth)
+        {
+            self.close(open, root_total);
+        }
+    }
+
+    // Reports the innermost open directory and adds it to the one holding
+    // it, or to the operand's total if it is the operand
+    fn close(&self, open: &mut Vec<(usize, PathBuf, u64)>, root_total: &mut u64) {
+        let Some((depth, path, total)) = open.pop() else {
+            return;
+        };
+        match open.last_mut() {
+            Some((_, _, sum)) => *sum += total,
+            None => *root_total = total,
+        }
+        if self.accounting.is_listed(depth, true) {
+            self.report(total, &path.to_string_lossy());
+        }
+    }
+
+    // Same result as scan, but subdirectories are read by idle worker
+    // threads. They only collect entries; the entries are then replayed in
+    // walk order, so a file linked from several subtrees is credited to the
+    // first one, whichever thread reached it first.
+    fn scan_parallel(&self, path: &str) -> (u64, bool) {
+        let root = PathBuf::from(path);
+        let metadata = match fs::symlink_metadata(&root) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                eprintln!(
+                    "du: cannot access {}",
+                    walk::WalkError { path: root, error }
+                );
+                return (0, false);
+            }
+        };
+
+        let scanner = ParallelScanner {
+            accounting: &self.accounting,
+            idle_workers: AtomicUsize::new(self.accounting.options.threads - 1),
+            root_device: metadata.dev(),
+        };
+        let subtree = thread::scope(|scope| scanner.scan_node(scope, root, metadata, 0));
+
+        let mut open = Vec::new();
+        let mut root_total = 0;
+        // The depth of a directory counted before, whose contents are passed
+        // over as the sequential walk would not enter it
+        let mut skipped: Option<usize> = None;
+        for visit in subtree.visits {
+            if skipped.is_some_and(|depth| visit.depth > depth) {
+                continue;
+            }
+            skipped = None;
+            self.close_to(visit.depth, &mut open, &mut root_total);
+            if visit.key.is_some_and(|key| !self.accounting.claim(key)) {
+                if visit.is_dir {
+                    skipped = Some(visit.depth);
+                }
+                continue;
+            }
+            self.add(
+                &mut open,
+                &mut root_total,
+                visit.depth,
+                visit.bytes,
+                visit.is_dir,
+                visit.path,
+            );
+        }
+        self.close_to(0, &mut open, &mut root_total);
+        (root_total, subtree.ok)
+    }
+
+    // Lists an entry unless the threshold leaves it out
+    fn report(&self, bytes: u64, name: &str) {
+        let threshold = self.accounting.options.threshold;
+        let excluded = if threshold < 0 {
+            bytes > threshold.unsigned_abs()
+        } else {
+            bytes < threshold as u64
+        };
+        if excluded {
+            return;
+        }
+        if self.accounting.options.sort {
+            self.sorted.borrow_mut().push((bytes, name.to_string()));
+        } else {
+            self.print(bytes, name);
+        }
+    }
+
+    fn print(&self, bytes: u64, name: &str) {
+        let mut out = self.out.borrow_mut();
+        if let Err(e) = writeln!(out, "{}\t{}", self.formatter.format(bytes), name) {
+            write_failed(&e);
+        }
+    }
+}
+
+// An entry met by a worker thread, left uncounted until the replay
+struct Visit {
+    depth: usize,
+    key: Option<(u64, u64)>,
+    // A directory's own size plus that of the files in it that can
+    // neither be met again nor be listed
+    bytes: u64,
+    is_dir: bool,
+    path: PathBuf,
+}
+
+#[derive(Default)]
+struct Subtree {
+    // Entries in the order the sequential walk would meet them
+    visits: Vec<Visit>,
+    ok: bool,
+}
+
+struct ParallelScanner<'a> {
+    accounting: &'a Accounting<'a>,
+    idle_workers: AtomicUsize,
+    root_device: u64,
+}
+
+enum Pending<'scope> {
+    Done(Subtree),
+    Running(thread::ScopedJoinHandle<'scope, Subtree>),
+}
+
+impl<'a> ParallelScanner<'a> {
+    fn try_claim_worker(&self) -> bool {
+        self.idle_workers
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |idle| {
+                idle.checked_sub(1)
+            })
+            .is_ok()
+    }
+
+    fn scan_node<'scope>(
+        &'scope self,
+        scope: &'scope thread::Scope<'scope, '_>,
+        path: PathBuf,
+        metadata: fs::Metadata,
+        depth: usize,
+    ) -> Subtree {
+        let mut subtree = Subtree {
+            ok: true,
+            ..Subtree::default()
+        };
+        subtree.visits.push(Visit {
+            depth,
+            key: self.accounting.key(&metadata),
+            bytes: self.accounting.bytes(&metadata),
+            is_dir: metadata.is_dir(),
+            path: path.clone(),
+        });
+
+        let crosses_device =
+            self.accounting.options.one_file_system && metadata.dev() != self.root_device;
+        if metadata.is_dir() && !crosses_device {
+            let mut pending = Vec::new();
+            match fs::read_dir(&path) {
+                Ok(entries) => {
+                    for entry in entries {
+                        let child = match entry {
+                            Ok(entry) => entry.path(),
+                            Err(error) => {
+                                eprintln!(
+                                    "du: cannot access {}",
+                                    walk::WalkError {
+                                        path: path.clone(),
+                                        error
+                                    }
+                                );
+                                subtree.ok = false;
+                                continue;
+                            }
+                        };
+                        let child_metadata = match fs::symlink_metadata(&child) {
+                            Ok(child_metadata) => child_metadata,
+                            Err(error) => {
+                                eprintln!(
+                                    "du: cannot access {}",
+                                    walk::WalkError { path: child, error }
+                                );
+                                subtree.ok = false;
+                                continue;
+                            }
+                        };
+
+                        let is_dir = child_metadata.is_dir();
+                        if !is_dir
+                            && self.accounting.key(&child_metadata).is_none()
+                            && !self.accounting.is_listed(depth + 1, false)
+                        {
+                            subtree.visits[0].bytes += self.accounting.bytes(&child_metadata);
+                        } else if is_dir && self.try_claim_worker() {
+                            pending.push(Pending::Running(scope.spawn(move || {
+                                let result =
+                                    self.scan_node(scope, child, child_metadata, depth + 1);
+                                self.idle_workers.fetch_add(1, Ordering::AcqRel);
+                                result
+                            })));
+                        } else {
+                            pending.push(Pending::Done(self.scan_node(
+                                scope,
+                                child,
+                                child_metadata,
+                                depth + 1,
+                            )));
+                        }
+                    }
+                }
+                Err(error) => {
+                    eprintln!(
+                        "du: cannot access {}",
+                        walk::WalkError {
+                            path: path.clone(),
+                            error
+                        }
+                    );
+                    subtree.ok = false;
+                }
+            }
+
+            for child in pending {
+                let child = match child {
+                    Pending::Done(child) => child,
+                    Pending::Running(handle) => handle.join().unwrap_or_default(),
+                };
+                subtree.ok &= child.ok;
+                subtree.visits.extend(child.visits);
+            }
+        }
+        subtree
+    }
+}
+
+// Ends the command when the listing cannot be written; a closed pipe, as
+// with du | head, is not worth a message
+fn write_failed(e: &io::Error) -> ! {
+    if e.kind() != io::ErrorKind::BrokenPipe {
+        eprintln!("du: write error: {}", error::message(e));
+    }
+    std::process::exit(1);
+}
+
+pub fn run(paths: &[String], options: &DuOptions) -> bool {
+    let command = DuCommand::new(options, paths.len() > 1);
+    command.run(paths)
+}
+
+fn parse_count(option: &str, value: &str) -> usize {
+    match value.parse() {
+        Ok(count) => count,
+        Err(_) => {
+            eprintln!("du: invalid {} '{}'", option, value);
+            std::process::exit(1);
+        }
+    }
+}
+
+// A size with an optional suffix, such as 100K; a leading '-' makes it an
+// upper bound
+fn parse_threshold(option: &str, value: &str) -> i64 {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+    let bytes = size::parse_size(digits)
+        .and_then(|bytes| i64::try_from(bytes).ok())
+        .unwrap_or_else(|| {
+            eprintln!("du: invalid {} argument '{}'", option, value);
+            std::process::exit(1);
+        });
+    if negative && bytes == 0 {
+        eprintln!("du: invalid --threshold argument '{}'", value);
+        std::process::exit(1);
+    }
+    if negative {
+        -bytes
+    } else {
+        bytes
+    }
+}
+
+fn default_threads() -> usize {
+    platform::available_processors()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = DuOptions {
+        threads: 1,
+        ..DuOptions::default()
+    };
+    let mut paths = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            paths.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--summarize" => options.summarize = true,
+            "--all" => options.all = true,
+            "--total" => options.grand_total = true,
+            "--human-readable" => options.human_readable = true,
+            "--apparent-size" => options.apparent_size = true,
+            "--one-file-system" => options.one_file_system = true,
+            "--parallel" => options.threads = default_threads(),
+            "--sort" => options.sort = true,
+            _ if arg.starts_with("--threshold=") => {
+                options.threshold = parse_threshold("--threshold", &arg["--threshold=".len()..]);
+            }
+            _ if arg.starts_with("--block-size=") => {
+                let value = &arg["--block-size=".len()..];
+                options.block_size = BlockSize::parse(value).unwrap_or_else(|| {
+                    eprintln!("du: invalid --block-size argument '{}'", value);
+                    std::process::exit(1);
+                });
+                options.human_readable = false;
+            }
+            _ if arg.starts_with("--max-depth=") => {
+                options.max_depth =
+                    Some(parse_count("maximum depth", &arg["--max-depth=".len()..]));
+            }
+            _ if arg.starts_with("--threads=") => {
+                let threads = parse_count("thread count", &arg["--threads=".len()..]);
+                options.threads = if threads == 0 {
+                    default_threads()
+                } else {
+                    threads
+                };
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("du: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        's' => options.summarize = true,
+                        'a' => options.all = true,
+                        'c' => options.grand_total = true,
+                        'h' => options.human_readable = true,
+                        'k' => {
+                            options.human_readable = false;
+                            options.block_size = BlockSize::default();
+                        }
+                        'x' => options.one_file_system = true,
+                        'd' | 't' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                eprintln!("du: option requires an argument -- '{}'", flag);
+                                std::process::exit(1);
+                            };
+                            if flag == 'd' {
+                                options.max_depth = Some(parse_count("maximum depth", &value));
+                            } else {
+                                options.threshold = parse_threshold("-t", &value);
+                            }
+                            break;
+                        }
+                        _ => {
+                            eprintln!("du: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if options.summarize && options.all {
+        eprintln!("du: cannot both summarize and show all entries");
+        std::process::exit(1);
+    }
+
+    if paths.is_empty() {
+        paths.push(String::from("."));
+    }
+
+    if !run(&paths, &options) {
+        std::process::exit(1);
+    }
+}
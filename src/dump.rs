@@ -0,0 +1,112 @@
+// Input shared by od and hexdump: the files are read as one stream, in
+// blocks that may span file boundaries.
+
+use crate::size;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read};
+
+pub struct Input {
+    // Files not opened yet; "-" is standard input
+    pending: VecDeque<String>,
+    current: Option<Box<dyn Read>>,
+    current_name: String,
+    // Files that could not be opened or read, for the caller to report
+    errors: Vec<(String, io::Error)>,
+}
+
+impl Input {
+    pub fn new(files: &[String]) -> Self {
+        Input {
+            pending: files.iter().cloned().collect(),
+            current: None,
+            current_name: String::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    // Moves on to the next file that opens; false once none are left
+    fn open_next(&mut self) -> bool {
+        while let Some(name) = self.pending.pop_front() {
+            if name == "-" {
+                self.current = Some(Box::new(io::stdin()));
+            } else {
+                match File::open(&name) {
+                    Ok(file) => self.current = Some(Box::new(file)),
+                    Err(e) => {
+                        self.errors.push((name, e));
+                        continue;
+                    }
+                }
+            }
+            self.current_name = name;
+            return true;
+        }
+        false
+    }
+
+    // Fills as much of the buffer as the remaining files allow and returns
+    // how much was read; less than the whole buffer only at the end
+    pub fn read_block(&mut self, buffer: &mut [u8]) -> usize {
+        let mut filled = 0;
+        while filled < buffer.len() {
+            if self.current.is_none() && !self.open_next() {
+                break;
+            }
+            let Some(reader) = self.current.as_mut() else {
+                break;
+            };
+            match reader.read(&mut buffer[filled..]) {
+                Ok(0) => self.current = None,
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.errors.push((self.current_name.clone(), e));
+                    self.current = None;
+                }
+            }
+        }
+        filled
+    }
+
+    // Discards up to `count` bytes and returns how many there were
+    pub fn skip(&mut self, count: u64) -> u64 {
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut skipped = 0;
+        while skipped < count {
+            let wanted = (count - skipped).min(buffer.len() as u64) as usize;
+            let read = self.read_block(&mut buffer[..wanted]);
+            if read == 0 {
+                break;
+            }
+            skipped += read as u64;
+        }
+        skipped
+    }
+
+    // The files that have failed since the last call
+    pub fn take_errors(&mut self) -> Vec<(String, io::Error)> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+// How a byte appears in the text column of a dump
+pub fn printable(byte: u8) -> char {
+    if (0x20..0x7f).contains(&byte) {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+// Byte counts take a 0x prefix for hex, a leading 0 for octal, or a size
+// suffix
+pub fn parse_count(text: &str) -> Option<u64> {
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        return u64::from_str_radix(hex, 16).ok();
+    }
+    if text.len() > 1 && text.starts_with('0') && text.bytes().all(|b| b.is_ascii_digit()) {
+        return u64::from_str_radix(&text[1..], 8).ok();
+    }
+    size::parse_size(text)
+}
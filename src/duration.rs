@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+// Durations as sleep, timeout and watch accept them: a floating point
+// number of seconds with an optional s, m, h or d suffix.
+
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let (number, multiplier) = match text.as_bytes().last()? {
+        b's' => (&text[..text.len() - 1], 1.0),
+        b'm' => (&text[..text.len() - 1], 60.0),
+        b'h' => (&text[..text.len() - 1], 3600.0),
+        b'd' => (&text[..text.len() - 1], 86400.0),
+        _ => (text, 1.0),
+    };
+    // "inf" sleeps forever, but "nan" means nothing
+    let value: f64 = number.parse().ok()?;
+    if value.is_nan() || value < 0.0 {
+        return None;
+    }
+    let seconds = value * multiplier;
+    // Anything too long to represent is as good as forever
+    Some(Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsed_durations() {
+        for (text, expected) in [
+            ("0", Some(Duration::ZERO)),
+            ("1", Some(Duration::from_secs(1))),
+            ("0.5", Some(Duration::from_millis(500))),
+            ("2s", Some(Duration::from_secs(2))),
+            ("1.5m", Some(Duration::from_secs(90))),
+            ("2h", Some(Duration::from_secs(7200))),
+            ("1d", Some(Duration::from_secs(86400))),
+            ("inf", Some(Duration::MAX)),
+            ("1e300d", Some(Duration::MAX)),
+            ("", None),
+            ("s", None),
+            ("-1", None),
+            ("nan", None),
+            ("1x", None),
+            ("1 s", None),
+        ] {
+            assert_eq!(parse_duration(text), expected, "{}", text);
+        }
+    }
+}
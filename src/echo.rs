@@ -0,0 +1,140 @@
+use cmds_lite::error;
+use std::env;
+use std::io::{self, Write};
+
+#[derive(Default)]
+pub struct EchoOptions {
+    pub no_newline: bool,
+    pub escapes: bool,
+}
+
+struct EchoCommand<'a> {
+    options: &'a EchoOptions,
+}
+
+impl<'a> EchoCommand<'a> {
+    fn new(options: &'a EchoOptions) -> Self {
+        EchoCommand { options }
+    }
+
+    fn run<W: Write>(&self, words: &[String], output: &mut W) -> io::Result<()> {
+        let mut text = Vec::new();
+        let mut stopped = false;
+        for (index, word) in words.iter().enumerate() {
+            if index > 0 {
+                text.push(b' ');
+            }
+            if self.options.escapes {
+                stopped = unescape(word.as_bytes(), &mut text);
+                if stopped {
+                    break;
+                }
+            } else {
+                text.extend_from_slice(word.as_bytes());
+            }
+        }
+        // \c also suppresses the trailing newline
+        if !self.options.no_newline && !stopped {
+            text.push(b'\n');
+        }
+        output.write_all(&text)?;
+        output.flush()
+    }
+}
+
+// Expands backslash escapes like GNU echo -e. Returns true when \c was
+// found, which ends all output.
+fn unescape(word: &[u8], out: &mut Vec<u8>) -> bool {
+    let mut i = 0;
+    while i < word.len() {
+        if word[i] != b'\\' || i + 1 == word.len() {
+            out.push(word[i]);
+            i += 1;
+            continue;
+        }
+
+        let escape = word[i + 1];
+        i += 2;
+        match escape {
+            b'\\' => out.push(b'\\'),
+            b'a' => out.push(0x07),
+            b'b' => out.push(0x08),
+            b'c' => return true,
+            b'e' => out.push(0x1b),
+            b'f' => out.push(0x0c),
+            b'n' => out.push(b'\n'),
+            b'r' => out.push(b'\r'),
+            b't' => out.push(b'\t'),
+            b'v' => out.push(0x0b),
+            b'x' => {
+                // One or two hex digits; without any, `\x` is printed as is
+                let digits = word[i..]
+                    .iter()
+                    .take(2)
+                    .take_while(|b| b.is_ascii_hexdigit())
+                    .count();
+                if digits == 0 {
+                    out.extend_from_slice(b"\\x");
+                } else {
+                    let hex = std::str::from_utf8(&word[i..i + digits]).unwrap_or("0");
+                    out.push(u8::from_str_radix(hex, 16).unwrap_or(0));
+                    i += digits;
+                }
+            }
+            b'0'..=b'7' => {
+                // \0NNN takes up to three digits after the 0, while \NNN
+                // counts the first digit as one of its three
+                let limit = if escape == b'0' { 3 } else { 2 };
+                let mut value = (escape - b'0') as u32;
+                let mut taken = 0;
+                while taken < limit && i < word.len() && (b'0'..=b'7').contains(&word[i]) {
+                    value = value * 8 + (word[i] - b'0') as u32;
+                    i += 1;
+                    taken += 1;
+                }
+                out.push(value as u8);
+            }
+            other => {
+                out.push(b'\\');
+                out.push(other);
+            }
+        }
+    }
+    false
+}
+
+pub fn run(words: &[String], options: &EchoOptions) -> io::Result<()> {
+    let command = EchoCommand::new(options);
+    command.run(words, &mut io::stdout().lock())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = EchoOptions::default();
+    let mut first_word = 1;
+
+    // Like GNU echo, leading arguments are options only if they consist
+    // entirely of n, e and E; anything else, including `--`, is printed
+    for arg in args.iter().skip(1) {
+        let is_option =
+            arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| "neE".contains(c));
+        if !is_option {
+            break;
+        }
+        for flag in arg.chars().skip(1) {
+            match flag {
+                'n' => options.no_newline = true,
+                'e' => options.escapes = true,
+                _ => options.escapes = false,
+            }
+        }
+        first_word += 1;
+    }
+
+    if let Err(e) = run(&args[first_word..], &options) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("echo: write error: {}", error::message(&e));
+        }
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,198 @@
+use cmds_lite::error;
+use cmds_lite::signal::{self, Signal};
+use std::env;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::ExitStatusExt;
+use std::process::Command;
+
+// Like GNU env, failures of env itself exit with 125, so they can be told
+// apart from the command's own status
+const EXIT_FAILURE: i32 = 125;
+const EXIT_CANNOT_INVOKE: i32 = 126;
+const EXIT_NOT_FOUND: i32 = 127;
+
+#[derive(Default)]
+pub struct EnvOptions {
+    pub ignore_environment: bool,
+    pub unset: Vec<String>,
+    // -0: end each printed variable with NUL instead of newline
+    pub null: bool,
+}
+
+struct EnvCommand<'a> {
+    options: &'a EnvOptions,
+}
+
+impl<'a> EnvCommand<'a> {
+    fn new(options: &'a EnvOptions) -> Self {
+        EnvCommand { options }
+    }
+
+    // The environment after -i, -u and the NAME=value assignments, in that
+    // order
+    fn environment(&self, assignments: &[String]) -> Vec<(OsString, OsString)> {
+        let mut variables: Vec<(OsString, OsString)> = if self.options.ignore_environment {
+            Vec::new()
+        } else {
+            env::vars_os().collect()
+        };
+        variables.retain(|(name, _)| {
+            !self
+                .options
+                .unset
+                .iter()
+                .any(|unset| name == unset.as_str())
+        });
+        for assignment in assignments {
+            let (name, value) = assignment.split_once('=').unwrap_or((assignment, ""));
+            variables.retain(|(existing, _)| existing != name);
+            variables.push((OsString::from(name), OsString::from(value)));
+        }
+        variables
+    }
+
+    fn print(&self, variables: &[(OsString, OsString)]) -> io::Result<()> {
+        let terminator = if self.options.null { b'\0' } else { b'\n' };
+        let mut stdout = io::stdout().lock();
+        for (name, value) in variables {
+            stdout.write_all(name.as_bytes())?;
+            stdout.write_all(b"=")?;
+            stdout.write_all(value.as_bytes())?;
+            stdout.write_all(&[terminator])?;
+        }
+        stdout.flush()
+    }
+
+    // Runs the command and returns the status env should exit with
+    fn execute(&self, variables: &[(OsString, OsString)], command: &[String]) -> i32 {
+        let spawned = Command::new(&command[0])
+            .args(&command[1..])
+            .env_clear()
+            .envs(variables.iter().map(|(name, value)| (name, value)))
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                let status = if e.kind() == io::ErrorKind::NotFound {
+                    EXIT_NOT_FOUND
+                } else {
+                    EXIT_CANNOT_INVOKE
+                };
+                eprintln!("env: '{}': {}", command[0], error::message(&e));
+                return status;
+            }
+        };
+
+        match child.wait() {
+            Ok(status) => match (status.code(), status.signal()) {
+                (Some(code), _) => code,
+                // Die the same way the command did
+                (None, Some(number)) => match Signal::from_number(number) {
+                    Some(signal) => signal::exit_by(signal),
+                    None => 128 + number,
+                },
+                (None, None) => EXIT_FAILURE,
+            },
+            Err(e) => {
+                eprintln!("env: {}", error::message(&e));
+                EXIT_FAILURE
+            }
+        }
+    }
+
+    fn run(&self, assignments: &[String], command: &[String]) -> i32 {
+        let variables = self.environment(assignments);
+        if !command.is_empty() {
+            return self.execute(&variables, command);
+        }
+        match self.print(&variables) {
+            Ok(()) => 0,
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => EXIT_FAILURE,
+            Err(e) => {
+                eprintln!("env: write error: {}", error::message(&e));
+                EXIT_FAILURE
+            }
+        }
+    }
+}
+
+pub fn run(assignments: &[String], command: &[String], options: &EnvOptions) -> i32 {
+    let command_runner = EnvCommand::new(options);
+    command_runner.run(assignments, command)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("env: {}", message);
+    std::process::exit(EXIT_FAILURE);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = EnvOptions::default();
+    let mut i = 1;
+
+    // Options come first; the first assignment or command ends them
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--" {
+            i += 1;
+            break;
+        }
+        if !arg.starts_with('-') {
+            break;
+        }
+        i += 1;
+
+        match arg.as_str() {
+            "-" | "--ignore-environment" => options.ignore_environment = true,
+            "--null" => options.null = true,
+            _ if arg.starts_with("--unset=") => {
+                options.unset.push(arg["--unset=".len()..].to_string());
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'i' => options.ignore_environment = true,
+                        '0' => options.null = true,
+                        'u' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail("option requires an argument -- 'u'");
+                            };
+                            options.unset.push(value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    for name in &options.unset {
+        if name.is_empty() || name.contains('=') {
+            fail(&format!("cannot unset '{}': Invalid argument", name));
+        }
+    }
+
+    let mut assignments = Vec::new();
+    while i < args.len() && args[i].contains('=') {
+        assignments.push(args[i].clone());
+        i += 1;
+    }
+    let command = &args[i..];
+    if options.null && !command.is_empty() {
+        fail("cannot specify --null (-0) with command");
+    }
+
+    std::process::exit(run(&assignments, command, &options));
+}
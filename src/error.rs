@@ -0,0 +1,46 @@
+use std::io;
+
+// Error text the way the GNU tools print it: the C library's description
+// of the error, without the " (os error N)" that io::Error adds.
+
+pub fn message(e: &io::Error) -> String {
+    match e.kind() {
+        io::ErrorKind::NotFound => String::from("No such file or directory"),
+        io::ErrorKind::PermissionDenied => String::from("Permission denied"),
+        io::ErrorKind::AlreadyExists => String::from("File exists"),
+        io::ErrorKind::IsADirectory => String::from("Is a directory"),
+        io::ErrorKind::NotADirectory => String::from("Not a directory"),
+        io::ErrorKind::DirectoryNotEmpty => String::from("Directory not empty"),
+        _ => {
+            let text = e.to_string();
+            match text.find(" (os error ") {
+                Some(end) => text[..end].to_string(),
+                None => text,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages() {
+        for (error, expected) in [
+            (libc::ENOENT, "No such file or directory"),
+            (libc::EINVAL, "Invalid argument"),
+            (libc::ELOOP, "Too many levels of symbolic links"),
+            (libc::EBUSY, "Device or resource busy"),
+            (libc::ENOTEMPTY, "Directory not empty"),
+        ] {
+            let e = io::Error::from_raw_os_error(error);
+            assert_eq!(message(&e), expected, "{}", error);
+        }
+        assert_eq!(
+            message(&io::Error::from(io::ErrorKind::NotFound)),
+            "No such file or directory"
+        );
+        assert_eq!(message(&io::Error::other("bad header")), "bad header");
+    }
+}
@@ -0,0 +1,157 @@
+use cmds_lite::error;
+use cmds_lite::tabs::TabStops;
+use cmds_lite::wrap::{self, Count};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+#[derive(Default)]
+pub struct ExpandOptions {
+    // -t: where the tab stops are
+    pub tabs: TabStops,
+    // -i: only expand tabs before the first non-blank of each line
+    pub initial: bool,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("expand: {}", message);
+    std::process::exit(1);
+}
+
+struct ExpandCommand<'a> {
+    options: &'a ExpandOptions,
+}
+
+impl<'a> ExpandCommand<'a> {
+    // Columns are counted by grapheme cluster, so characters of several
+    // bytes, wide characters and combining marks all line up
+    fn expand_line(&self, line: &[u8], output: &mut Vec<u8>) {
+        let mut column = 0;
+        let mut leading = true;
+        for unit in wrap::units(line, Count::Columns) {
+            if unit == b"\t" && (leading || !self.options.initial) {
+                // Past the last stop a tab is a single space
+                let stop = self.options.tabs.next(column).unwrap_or(column + 1);
+                output.resize(output.len() + stop - column, b' ');
+                column = stop;
+                continue;
+            }
+            if unit != b" " && unit != b"\t" {
+                leading = false;
+            }
+            output.extend_from_slice(unit);
+            column = wrap::advance(column, unit, Count::Columns);
+        }
+    }
+
+    fn expand<R: BufRead, W: Write>(&self, mut input: R, output: &mut W) -> io::Result<()> {
+        let mut line = Vec::new();
+        let mut expanded = Vec::new();
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+            expanded.clear();
+            self.expand_line(&line, &mut expanded);
+            output.write_all(&expanded)?;
+        }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let stdout = io::stdout();
+        let mut output = BufWriter::new(stdout.lock());
+        let mut ok = true;
+
+        for file in files {
+            let result = if file == "-" {
+                self.expand(io::stdin().lock(), &mut output)
+            } else {
+                File::open(file).and_then(|f| self.expand(BufReader::new(f), &mut output))
+            };
+            match result {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return ok,
+                Err(e) => {
+                    eprintln!("expand: {}: {}", file, error::message(&e));
+                    ok = false;
+                }
+            }
+        }
+
+        match output.flush() {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                eprintln!("expand: write error: {}", error::message(&e));
+                false
+            }
+            _ => ok,
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = ExpandOptions::default();
+    let mut files = Vec::new();
+    // Every -t adds to one list
+    let mut tab_list: Vec<String> = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--initial" => options.initial = true,
+            _ if arg.starts_with("--tabs=") => tab_list.push(arg["--tabs=".len()..].to_string()),
+            "--tabs" if i < args.len() => {
+                i += 1;
+                tab_list.push(args[i - 1].clone());
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            // The old form of -t, such as -4 or -2,6
+            _ if arg[1..].bytes().all(|b| b.is_ascii_digit() || b == b',') => {
+                tab_list.push(arg[1..].to_string())
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'i' => options.initial = true,
+                        't' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            tab_list.push(value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if !tab_list.is_empty() {
+        options.tabs = TabStops::parse(&tab_list.join(",")).unwrap_or_else(|e| fail(&e));
+    }
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    let command = ExpandCommand { options: &options };
+    if !command.run(&files) {
+        std::process::exit(1);
+    }
+}
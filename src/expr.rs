@@ -0,0 +1,338 @@
+use cmds_lite::collate::Collator;
+use cmds_lite::regex::{Regex, Syntax};
+use std::cmp::Ordering;
+use std::env;
+
+// Like GNU expr, a null or zero result exits with 1, and an expression
+// that is malformed or cannot be computed with 2
+const EXIT_NULL: i32 = 1;
+const EXIT_INVALID: i32 = 2;
+
+#[derive(Clone, Debug)]
+enum Value {
+    Integer(i128),
+    Text(String),
+}
+
+// An integer as expr reads one: an optional minus sign and digits, with
+// nothing else around them
+fn parse_integer(text: &str) -> Option<Result<i128, String>> {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(text.parse().map_err(|_| String::from("integer overflow")))
+}
+
+impl Value {
+    fn to_text(&self) -> String {
+        match self {
+            Value::Integer(number) => number.to_string(),
+            Value::Text(text) => text.clone(),
+        }
+    }
+
+    fn to_integer(&self) -> Result<i128, String> {
+        match self {
+            Value::Integer(number) => Ok(*number),
+            Value::Text(text) => {
+                parse_integer(text).unwrap_or_else(|| Err(String::from("non-integer argument")))
+            }
+        }
+    }
+
+    // Zero or the empty string; a text such as "00" or "-0" counts too
+    fn is_null(&self) -> bool {
+        match self {
+            Value::Integer(number) => *number == 0,
+            Value::Text(text) => {
+                let digits = text.strip_prefix('-').unwrap_or(text);
+                text.is_empty() || (!digits.is_empty() && digits.bytes().all(|b| b == b'0'))
+            }
+        }
+    }
+}
+
+struct ExprCommand<'a> {
+    args: &'a [String],
+    pos: usize,
+    collator: Collator,
+}
+
+impl<'a> ExprCommand<'a> {
+    // Consumes the next argument if it is `token`
+    fn next_is(&mut self, token: &str) -> bool {
+        if self.args.get(self.pos).is_some_and(|arg| arg == token) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn missing_argument(&self) -> String {
+        format!(
+            "syntax error: missing argument after '{}'",
+            self.args[self.pos - 1]
+        )
+    }
+
+    // A single argument taken as it is, whatever it looks like
+    fn token(&mut self) -> Result<Value, String> {
+        match self.args.get(self.pos) {
+            Some(arg) => {
+                self.pos += 1;
+                Ok(Value::Text(arg.clone()))
+            }
+            None => Err(self.missing_argument()),
+        }
+    }
+
+    // A parenthesized expression or a single argument
+    fn primary(&mut self) -> Result<Value, String> {
+        if self.pos >= self.args.len() {
+            return Err(self.missing_argument());
+        }
+        if self.next_is("(") {
+            let value = self.or()?;
+            if !self.next_is(")") {
+                return Err(match self.args.get(self.pos) {
+                    Some(arg) => format!("syntax error: expecting ')' instead of '{}'", arg),
+                    None => format!(
+                        "syntax error: expecting ')' after '{}'",
+                        self.args[self.pos - 1]
+                    ),
+                });
+            }
+            return Ok(value);
+        }
+        if self.args[self.pos] == ")" {
+            return Err(String::from("syntax error: unexpected ')'"));
+        }
+        self.token()
+    }
+
+    // The keywords, and + which makes the next argument a plain string
+    // even when it is a keyword or operator
+    fn function(&mut self) -> Result<Value, String> {
+        if self.next_is("+") {
+            return self.token();
+        }
+        if self.next_is("length") {
+            let text = self.function()?.to_text();
+            return Ok(Value::Integer(text.chars().count() as i128));
+        }
+        if self.next_is("match") {
+            let text = self.function()?;
+            let pattern = self.function()?;
+            return match_pattern(&text, &pattern);
+        }
+        if self.next_is("index") {
+            let text = self.function()?.to_text();
+            let chars = self.function()?.to_text();
+            let position = text
+                .chars()
+                .position(|c| chars.contains(c))
+                .map_or(0, |index| index + 1);
+            return Ok(Value::Integer(position as i128));
+        }
+        if self.next_is("substr") {
+            let text = self.function()?.to_text();
+            let position = self.function()?;
+            let length = self.function()?;
+            // Positions count from 1; anything out of range gives ""
+            let substring = match (
+                parse_integer(&position.to_text()),
+                parse_integer(&length.to_text()),
+            ) {
+                (Some(Ok(position)), Some(Ok(length))) if position >= 1 && length >= 1 => text
+                    .chars()
+                    .skip((position - 1).min(usize::MAX as i128) as usize)
+                    .take(length.min(usize::MAX as i128) as usize)
+                    .collect(),
+                _ => String::new(),
+            };
+            return Ok(Value::Text(substring));
+        }
+        self.primary()
+    }
+
+    fn matches(&mut self) -> Result<Value, String> {
+        let mut value = self.function()?;
+        while self.next_is(":") {
+            let pattern = self.function()?;
+            value = match_pattern(&value, &pattern)?;
+        }
+        Ok(value)
+    }
+
+    fn product(&mut self) -> Result<Value, String> {
+        let mut value = self.matches()?;
+        loop {
+            let operator = if self.next_is("*") {
+                '*'
+            } else if self.next_is("/") {
+                '/'
+            } else if self.next_is("%") {
+                '%'
+            } else {
+                return Ok(value);
+            };
+            let right = self.matches()?.to_integer()?;
+            let left = value.to_integer()?;
+            if operator != '*' && right == 0 {
+                return Err(String::from("division by zero"));
+            }
+            let result = match operator {
+                '*' => left.checked_mul(right),
+                '/' => left.checked_div(right),
+                _ => left.checked_rem(right),
+            };
+            value = Value::Integer(result.ok_or_else(|| String::from("integer overflow"))?);
+        }
+    }
+
+    fn sum(&mut self) -> Result<Value, String> {
+        let mut value = self.product()?;
+        loop {
+            let add = if self.next_is("+") {
+                true
+            } else if self.next_is("-") {
+                false
+            } else {
+                return Ok(value);
+            };
+            let right = self.product()?.to_integer()?;
+            let left = value.to_integer()?;
+            let result = if add {
+                left.checked_add(right)
+            } else {
+                left.checked_sub(right)
+            };
+            value = Value::Integer(result.ok_or_else(|| String::from("integer overflow"))?);
+        }
+    }
+
+    // Integers compare as numbers, anything else as strings in the
+    // locale's order
+    fn comparison(&mut self) -> Result<Value, String> {
+        let mut value = self.sum()?;
+        loop {
+            let operator = ["<", "<=", "=", "==", "!=", ">=", ">"]
+                .into_iter()
+                .find(|operator| self.next_is(operator));
+            let Some(operator) = operator else {
+                return Ok(value);
+            };
+            let right = self.sum()?;
+            let ordering = match (value.to_integer(), right.to_integer()) {
+                (Ok(left), Ok(right)) => left.cmp(&right),
+                _ => self
+                    .collator
+                    .compare(value.to_text().as_bytes(), right.to_text().as_bytes()),
+            };
+            let result = match operator {
+                "<" => ordering == Ordering::Less,
+                "<=" => ordering != Ordering::Greater,
+                "=" | "==" => ordering == Ordering::Equal,
+                "!=" => ordering != Ordering::Equal,
+                ">=" => ordering != Ordering::Less,
+                _ => ordering == Ordering::Greater,
+            };
+            value = Value::Integer(result as i128);
+        }
+    }
+
+    // The left side if it is neither null nor zero and the right side is
+    // not either, otherwise 0
+    fn and(&mut self) -> Result<Value, String> {
+        let mut value = self.comparison()?;
+        while self.next_is("&") {
+            let right = self.comparison()?;
+            if value.is_null() || right.is_null() {
+                value = Value::Integer(0);
+            }
+        }
+        Ok(value)
+    }
+
+    // The left side if it is neither null nor zero, otherwise the right
+    // side if that is not, otherwise 0
+    fn or(&mut self) -> Result<Value, String> {
+        let mut value = self.and()?;
+        while self.next_is("|") {
+            let right = self.and()?;
+            if value.is_null() {
+                value = if right.is_null() {
+                    Value::Integer(0)
+                } else {
+                    right
+                };
+            }
+        }
+        Ok(value)
+    }
+
+    fn run(&mut self) -> Result<Value, String> {
+        let value = self.or()?;
+        if let Some(arg) = self.args.get(self.pos) {
+            return Err(format!("syntax error: unexpected argument '{}'", arg));
+        }
+        Ok(value)
+    }
+}
+
+// STRING : REGEX. The pattern is a basic regular expression anchored at
+// the start of the string. With a \( \) group the result is the text the
+// first group matched, otherwise the number of characters matched.
+fn match_pattern(text: &Value, pattern: &Value) -> Result<Value, String> {
+    let text = text.to_text();
+    let regex = Regex::new(&pattern.to_text(), Syntax::Basic, false)?;
+    let captures = regex
+        .captures_at(text.as_bytes(), 0)
+        .filter(|groups| groups[0].is_some_and(|(start, _)| start == 0));
+    if regex.groups() > 0 {
+        let group = captures
+            .and_then(|groups| groups[1])
+            .map(|(start, end)| String::from_utf8_lossy(&text.as_bytes()[start..end]).to_string());
+        return Ok(Value::Text(group.unwrap_or_default()));
+    }
+    let length = captures.and_then(|groups| groups[0]).map_or(0, |(_, end)| {
+        String::from_utf8_lossy(&text.as_bytes()[..end])
+            .chars()
+            .count()
+    });
+    Ok(Value::Integer(length as i128))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    // Only a leading -- is an option; anything else, even starting with
+    // a dash, is part of the expression
+    let start = if args.get(1).is_some_and(|arg| arg == "--") {
+        2
+    } else {
+        1
+    };
+    if start >= args.len() {
+        eprintln!("expr: missing operand");
+        std::process::exit(EXIT_INVALID);
+    }
+
+    let mut command = ExprCommand {
+        args: &args[start..],
+        pos: 0,
+        collator: Collator::from_environment(),
+    };
+    match command.run() {
+        Ok(value) => {
+            println!("{}", value.to_text());
+            if value.is_null() {
+                std::process::exit(EXIT_NULL);
+            }
+        }
+        Err(message) => {
+            eprintln!("expr: {}", message);
+            std::process::exit(EXIT_INVALID);
+        }
+    }
+}
@@ -0,0 +1,785 @@
+use cmds_lite::error;
+use cmds_lite::glob;
+use cmds_lite::mode::Mode;
+use cmds_lite::platform;
+use cmds_lite::walk::{Entry, WalkOptions, Walker};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+use std::process::Command;
+use std::time::SystemTime;
+
+#[derive(Default)]
+pub struct FindOptions {
+    pub follow_links: bool,
+    pub follow_root_links: bool,
+    pub max_depth: Option<usize>,
+    pub min_depth: usize,
+    // Process directory contents before the directory itself (-depth, -delete)
+    pub depth_first: bool,
+}
+
+trait Predicate {
+    fn evaluate(&mut self, entry: &Entry) -> bool;
+    // Whether this expression produces output, which disables the implicit -print
+    fn is_action(&self) -> bool {
+        false
+    }
+    // Called once the walk is over to run pending batches; returns false if
+    // anything failed in a way that should change find's exit status
+    fn finish(&mut self) -> bool {
+        true
+    }
+}
+
+struct And(Box<dyn Predicate>, Box<dyn Predicate>);
+
+impl Predicate for And {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        self.0.evaluate(entry) && self.1.evaluate(entry)
+    }
+
+    fn is_action(&self) -> bool {
+        self.0.is_action() || self.1.is_action()
+    }
+
+    fn finish(&mut self) -> bool {
+        let left = self.0.finish();
+        let right = self.1.finish();
+        left && right
+    }
+}
+
+struct Or(Box<dyn Predicate>, Box<dyn Predicate>);
+
+impl Predicate for Or {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        self.0.evaluate(entry) || self.1.evaluate(entry)
+    }
+
+    fn is_action(&self) -> bool {
+        self.0.is_action() || self.1.is_action()
+    }
+
+    fn finish(&mut self) -> bool {
+        let left = self.0.finish();
+        let right = self.1.finish();
+        left && right
+    }
+}
+
+struct Not(Box<dyn Predicate>);
+
+impl Predicate for Not {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        !self.0.evaluate(entry)
+    }
+
+    fn is_action(&self) -> bool {
+        self.0.is_action()
+    }
+
+    fn finish(&mut self) -> bool {
+        self.0.finish()
+    }
+}
+
+struct Constant(bool);
+
+impl Predicate for Constant {
+    fn evaluate(&mut self, _entry: &Entry) -> bool {
+        self.0
+    }
+}
+
+struct NameTest {
+    pattern: String,
+    ignore_case: bool,
+}
+
+impl Predicate for NameTest {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        let name = entry.file_name();
+        if self.ignore_case {
+            glob::matches_ignore_case(&self.pattern, &name)
+        } else {
+            glob::matches(&self.pattern, &name)
+        }
+    }
+}
+
+struct TypeTest {
+    types: Vec<char>,
+}
+
+impl Predicate for TypeTest {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        let file_type = entry.metadata.file_type();
+        let actual = if file_type.is_dir() {
+            'd'
+        } else if file_type.is_symlink() {
+            'l'
+        } else if file_type.is_block_device() {
+            'b'
+        } else if file_type.is_char_device() {
+            'c'
+        } else if file_type.is_fifo() {
+            'p'
+        } else if file_type.is_socket() {
+            's'
+        } else {
+            'f'
+        };
+        self.types.contains(&actual)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Less,
+    Equal,
+    Greater,
+}
+
+impl Comparison {
+    fn holds(self, actual: u64, expected: u64) -> bool {
+        match self {
+            Comparison::Less => actual < expected,
+            Comparison::Equal => actual == expected,
+            Comparison::Greater => actual > expected,
+        }
+    }
+}
+
+struct SizeTest {
+    comparison: Comparison,
+    count: u64,
+    unit: u64,
+}
+
+impl Predicate for SizeTest {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        // Sizes are rounded up to whole units, so -size -1M only matches
+        // empty files, like GNU find
+        let size = entry.metadata.len();
+        let units = size.div_ceil(self.unit);
+        self.comparison.holds(units, self.count)
+    }
+}
+
+struct MtimeTest {
+    comparison: Comparison,
+    days: u64,
+    now: i64,
+}
+
+impl Predicate for MtimeTest {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        let age = self.now - entry.metadata.mtime();
+        if age < 0 {
+            return matches!(self.comparison, Comparison::Less);
+        }
+        self.comparison.holds(age as u64 / 86400, self.days)
+    }
+}
+
+struct NewerTest {
+    // Modification time of the reference file, as seconds and nanoseconds
+    reference: (i64, i64),
+}
+
+impl Predicate for NewerTest {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        (entry.metadata.mtime(), entry.metadata.mtime_nsec()) > self.reference
+    }
+}
+
+#[derive(Clone, Copy)]
+enum PermMatch {
+    // MODE: exactly these permission bits
+    Exact,
+    // -MODE: at least all of them
+    All,
+    // /MODE: at least one of them, or anything when there are none
+    Any,
+}
+
+struct PermTest {
+    bits: u32,
+    matching: PermMatch,
+}
+
+impl Predicate for PermTest {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        let mode = entry.metadata.mode() & 0o7777;
+        match self.matching {
+            PermMatch::Exact => mode == self.bits,
+            PermMatch::All => mode & self.bits == self.bits,
+            PermMatch::Any => self.bits == 0 || mode & self.bits != 0,
+        }
+    }
+}
+
+struct EmptyTest;
+
+impl Predicate for EmptyTest {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        if entry.metadata.is_dir() {
+            fs::read_dir(&entry.path).is_ok_and(|mut entries| entries.next().is_none())
+        } else {
+            entry.metadata.is_file() && entry.metadata.len() == 0
+        }
+    }
+}
+
+fn write_path(entry: &Entry, terminator: u8) {
+    let mut out = io::stdout().lock();
+    let written = out
+        .write_all(entry.path.as_os_str().as_bytes())
+        .and_then(|_| out.write_all(&[terminator]));
+    if written.is_err() {
+        // Downstream went away, e.g. piped into head
+        std::process::exit(1);
+    }
+}
+
+struct Print {
+    terminator: u8,
+}
+
+impl Predicate for Print {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        write_path(entry, self.terminator);
+        true
+    }
+
+    fn is_action(&self) -> bool {
+        true
+    }
+}
+
+struct Delete {
+    failed: bool,
+}
+
+impl Predicate for Delete {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        // The starting point "." cannot be removed and is silently kept
+        if entry.depth == 0 && entry.path.as_os_str() == "." {
+            return true;
+        }
+
+        let result = if entry.is_dir() {
+            fs::remove_dir(&entry.path)
+        } else {
+            fs::remove_file(&entry.path)
+        };
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!(
+                    "find: cannot delete '{}': {}",
+                    entry.path.display(),
+                    error::message(&e)
+                );
+                self.failed = true;
+                false
+            }
+        }
+    }
+
+    fn is_action(&self) -> bool {
+        true
+    }
+
+    fn finish(&mut self) -> bool {
+        !self.failed
+    }
+}
+
+// Replaces every "{}" in an -exec argument with the entry path
+fn substitute(arg: &str, path: &Path) -> OsString {
+    let mut bytes = Vec::new();
+    let mut parts = arg.split("{}");
+    if let Some(first) = parts.next() {
+        bytes.extend_from_slice(first.as_bytes());
+    }
+    for part in parts {
+        bytes.extend_from_slice(path.as_os_str().as_bytes());
+        bytes.extend_from_slice(part.as_bytes());
+    }
+    OsString::from_vec(bytes)
+}
+
+// Runs a command, reporting spawn failures; returns whether it exited 0
+fn run_command(program: &OsString, args: &[OsString]) -> bool {
+    // Output written so far (e.g. by -print0) must come before the command's
+    let _ = io::stdout().flush();
+    match Command::new(program).args(args).status() {
+        Ok(status) => status.success(),
+        Err(e) => {
+            eprintln!(
+                "find: '{}': {}",
+                program.to_string_lossy(),
+                error::message(&e)
+            );
+            false
+        }
+    }
+}
+
+// -exec COMMAND ;  runs COMMAND once per entry and is true if it exits 0
+struct Exec {
+    command: Vec<String>,
+}
+
+impl Predicate for Exec {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        let mut args: Vec<OsString> = self
+            .command
+            .iter()
+            .map(|arg| substitute(arg, &entry.path))
+            .collect();
+        let program = args.remove(0);
+        run_command(&program, &args)
+    }
+
+    fn is_action(&self) -> bool {
+        true
+    }
+}
+
+// -exec COMMAND {} +  collects entries and runs COMMAND with as many of them
+// as fit on one command line; always true, failures affect the exit status
+struct ExecBatch {
+    command: Vec<String>,
+    pending: Vec<OsString>,
+    pending_bytes: usize,
+    limit: usize,
+    failed: bool,
+}
+
+impl ExecBatch {
+    fn new(command: Vec<String>) -> Self {
+        ExecBatch {
+            command,
+            pending: Vec::new(),
+            pending_bytes: 0,
+            limit: platform::argument_space(),
+            failed: false,
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let program = OsString::from(&self.command[0]);
+        let mut args: Vec<OsString> = self.command[1..].iter().map(OsString::from).collect();
+        args.append(&mut self.pending);
+        if !run_command(&program, &args) {
+            self.failed = true;
+        }
+        self.pending_bytes = 0;
+    }
+}
+
+impl Predicate for ExecBatch {
+    fn evaluate(&mut self, entry: &Entry) -> bool {
+        let path = entry.path.as_os_str().to_os_string();
+        // Each argument also costs a pointer and a terminating NUL
+        let cost = path.len() + 1 + std::mem::size_of::<usize>();
+        if self.pending_bytes + cost > self.limit {
+            self.flush();
+        }
+        self.pending_bytes += cost;
+        self.pending.push(path);
+        true
+    }
+
+    fn is_action(&self) -> bool {
+        true
+    }
+
+    fn finish(&mut self) -> bool {
+        self.flush();
+        !self.failed
+    }
+}
+
+// Recursive-descent parser for the find expression language:
+//   expr    := and ( (-o | -or) and )*
+//   and     := unary ( [-a | -and] unary )*
+//   unary   := (! | -not) unary | primary
+//   primary := ( expr ) | test | action
+struct ExpressionParser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+    options: &'a mut FindOptions,
+    now: i64,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn new(tokens: &'a [String], options: &'a mut FindOptions) -> Self {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() as i64)
+            .unwrap_or(0);
+        ExpressionParser {
+            tokens,
+            pos: 0,
+            options,
+            now,
+        }
+    }
+
+    fn parse(mut self) -> Result<Box<dyn Predicate>, String> {
+        if self.tokens.is_empty() {
+            return Ok(Box::new(Print { terminator: b'\n' }));
+        }
+
+        let expression = self.parse_or()?;
+        if let Some(token) = self.peek() {
+            return Err(if token == ")" {
+                String::from("invalid expression; you have too many ')'")
+            } else {
+                format!("paths must precede expression: `{}'", token)
+            });
+        }
+
+        if expression.is_action() {
+            Ok(expression)
+        } else {
+            Ok(Box::new(And(expression, Box::new(Print { terminator: b'\n' }))))
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|token| token.as_str())
+    }
+
+    fn next_argument(&mut self, primary: &str) -> Result<String, String> {
+        match self.tokens.get(self.pos) {
+            Some(value) => {
+                self.pos += 1;
+                Ok(value.clone())
+            }
+            None => Err(format!("missing argument to `{}'", primary)),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Box<dyn Predicate>, String> {
+        let mut left = self.parse_and()?;
+        while matches!(self.peek(), Some("-o") | Some("-or")) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = Box::new(Or(left, right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Box<dyn Predicate>, String> {
+        let mut left = self.parse_unary()?;
+        loop {
+            match self.peek() {
+                Some("-a") | Some("-and") => {
+                    self.pos += 1;
+                }
+                // Juxtaposition is an implicit -and
+                Some(token) if token != "-o" && token != "-or" && token != ")" => {}
+                _ => break,
+            }
+            let right = self.parse_unary()?;
+            left = Box::new(And(left, right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Box<dyn Predicate>, String> {
+        match self.peek() {
+            Some("!") | Some("-not") => {
+                self.pos += 1;
+                Ok(Box::new(Not(self.parse_unary()?)))
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Box<dyn Predicate>, String> {
+        let token = match self.peek() {
+            Some(token) => token.to_string(),
+            None => return Err(String::from("invalid expression")),
+        };
+        self.pos += 1;
+
+        match token.as_str() {
+            "(" => {
+                let inner = self.parse_or()?;
+                if self.peek() != Some(")") {
+                    return Err(String::from("invalid expression; I was expecting to find a ')' somewhere but did not see one."));
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            "-name" | "-iname" => {
+                let pattern = self.next_argument(&token)?;
+                Ok(Box::new(NameTest {
+                    pattern,
+                    ignore_case: token == "-iname",
+                }))
+            }
+            "-type" => {
+                let value = self.next_argument(&token)?;
+                let mut types = Vec::new();
+                for part in value.split(',') {
+                    match part {
+                        "f" | "d" | "l" | "b" | "c" | "p" | "s" => {
+                            types.push(part.chars().next().unwrap_or('f'))
+                        }
+                        _ => return Err(format!("Unknown argument to -type: {}", part)),
+                    }
+                }
+                Ok(Box::new(TypeTest { types }))
+            }
+            "-size" => {
+                let value = self.next_argument(&token)?;
+                let (comparison, rest) = split_comparison(&value);
+                let (digits, unit) = match rest.char_indices().last() {
+                    Some((index, c)) if c.is_ascii_alphabetic() => {
+                        let unit = match c {
+                            'c' => 1,
+                            'w' => 2,
+                            'b' => 512,
+                            'k' => 1024,
+                            'M' => 1024 * 1024,
+                            'G' => 1024 * 1024 * 1024,
+                            _ => return Err(format!("invalid -size type `{}'", c)),
+                        };
+                        (&rest[..index], unit)
+                    }
+                    _ => (rest, 512),
+                };
+                let count = digits
+                    .parse()
+                    .map_err(|_| format!("invalid argument `{}' to `-size'", value))?;
+                Ok(Box::new(SizeTest {
+                    comparison,
+                    count,
+                    unit,
+                }))
+            }
+            "-mtime" => {
+                let value = self.next_argument(&token)?;
+                let (comparison, rest) = split_comparison(&value);
+                let days = rest
+                    .parse()
+                    .map_err(|_| format!("invalid argument `{}' to `-mtime'", value))?;
+                Ok(Box::new(MtimeTest {
+                    comparison,
+                    days,
+                    now: self.now,
+                }))
+            }
+            "-newer" => {
+                let file = self.next_argument(&token)?;
+                // The reference file is looked at the way the roots are
+                let metadata = if self.options.follow_links || self.options.follow_root_links {
+                    fs::metadata(&file)
+                } else {
+                    fs::symlink_metadata(&file)
+                };
+                let metadata =
+                    metadata.map_err(|e| format!("'{}': {}", file, error::message(&e)))?;
+                Ok(Box::new(NewerTest {
+                    reference: (metadata.mtime(), metadata.mtime_nsec()),
+                }))
+            }
+            "-perm" => {
+                let value = self.next_argument(&token)?;
+                let (matching, spec) = if let Some(spec) = value.strip_prefix('-') {
+                    (PermMatch::All, spec)
+                } else if let Some(spec) = value.strip_prefix('/') {
+                    (PermMatch::Any, spec)
+                } else {
+                    (PermMatch::Exact, value.as_str())
+                };
+                // Symbolic modes are applied to no permissions at all, with
+                // no umask in the way
+                let bits = Mode::parse(spec)
+                    .map_err(|_| format!("invalid mode `{}'", value))?
+                    .apply(0, false, 0);
+                Ok(Box::new(PermTest { bits, matching }))
+            }
+            "-empty" => Ok(Box::new(EmptyTest)),
+            "-maxdepth" | "-mindepth" => {
+                let value = self.next_argument(&token)?;
+                let depth: usize = value
+                    .parse()
+                    .map_err(|_| format!("invalid argument `{}' to `{}'", value, token))?;
+                // Global options apply to the whole walk and are always true
+                if token == "-maxdepth" {
+                    self.options.max_depth = Some(depth);
+                } else {
+                    self.options.min_depth = depth;
+                }
+                Ok(Box::new(Constant(true)))
+            }
+            "-true" => Ok(Box::new(Constant(true))),
+            "-false" => Ok(Box::new(Constant(false))),
+            "-print" => Ok(Box::new(Print { terminator: b'\n' })),
+            "-print0" => Ok(Box::new(Print { terminator: 0 })),
+            "-depth" => {
+                self.options.depth_first = true;
+                Ok(Box::new(Constant(true)))
+            }
+            "-delete" => {
+                // Deleting only works bottom-up, so -delete implies -depth
+                self.options.depth_first = true;
+                Ok(Box::new(Delete { failed: false }))
+            }
+            "-exec" => self.parse_exec(),
+            ")" => Err(String::from("invalid expression; empty parentheses are not allowed.")),
+            _ if token.starts_with('-') => Err(format!("unknown predicate `{}'", token)),
+            _ => Err(format!("paths must precede expression: `{}'", token)),
+        }
+    }
+}
+
+impl<'a> ExpressionParser<'a> {
+    // The command runs until a ";" or, directly after "{}", a "+"
+    fn parse_exec(&mut self) -> Result<Box<dyn Predicate>, String> {
+        let mut command = Vec::new();
+        loop {
+            let token = match self.tokens.get(self.pos) {
+                Some(token) => token.clone(),
+                None => return Err(String::from("missing argument to `-exec'")),
+            };
+            self.pos += 1;
+
+            if token == ";" {
+                break;
+            }
+            if token == "+" && command.last().is_some_and(|last: &String| last == "{}") {
+                command.pop();
+                if command.is_empty() {
+                    return Err(String::from("missing argument to `-exec'"));
+                }
+                if command.iter().any(|arg| arg.contains("{}")) {
+                    return Err(String::from(
+                        "only one instance of {} is supported with -exec ... +",
+                    ));
+                }
+                return Ok(Box::new(ExecBatch::new(command)));
+            }
+            command.push(token);
+        }
+
+        if command.is_empty() {
+            return Err(String::from("missing argument to `-exec'"));
+        }
+        Ok(Box::new(Exec { command }))
+    }
+}
+
+fn split_comparison(value: &str) -> (Comparison, &str) {
+    if let Some(rest) = value.strip_prefix('+') {
+        (Comparison::Greater, rest)
+    } else if let Some(rest) = value.strip_prefix('-') {
+        (Comparison::Less, rest)
+    } else {
+        (Comparison::Equal, value)
+    }
+}
+
+struct FindCommand<'a> {
+    expression: Box<dyn Predicate>,
+    options: &'a FindOptions,
+}
+
+impl<'a> FindCommand<'a> {
+    fn new(expression: Box<dyn Predicate>, options: &'a FindOptions) -> Self {
+        FindCommand {
+            expression,
+            options,
+        }
+    }
+
+    fn run(&mut self, paths: &[String]) -> bool {
+        let walk_options = WalkOptions {
+            follow_links: self.options.follow_links,
+            follow_root_links: self.options.follow_root_links,
+            max_depth: self.options.max_depth,
+            contents_first: self.options.depth_first,
+            ..WalkOptions::default()
+        };
+
+        let mut ok = true;
+        for item in Walker::new(paths, walk_options) {
+            match item {
+                Ok(entry) => {
+                    if entry.depth >= self.options.min_depth {
+                        self.expression.evaluate(&entry);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("find: {}", e);
+                    ok = false;
+                }
+            }
+        }
+        self.expression.finish() && ok
+    }
+}
+
+pub fn run(paths: &[String], expression: &[String], options: &mut FindOptions) -> Result<bool, String> {
+    let predicate = ExpressionParser::new(expression, options).parse()?;
+    let mut command = FindCommand::new(predicate, options);
+    Ok(command.run(paths))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = FindOptions::default();
+    let mut i = 1;
+
+    // Leading -H/-L/-P options control symlink handling
+    while i < args.len() {
+        match args[i].as_str() {
+            "-L" => options.follow_links = true,
+            "-H" => options.follow_root_links = true,
+            "-P" => {
+                options.follow_links = false;
+                options.follow_root_links = false;
+            }
+            _ => break,
+        }
+        i += 1;
+    }
+
+    // Paths run until the first token that starts an expression
+    let mut paths = Vec::new();
+    while i < args.len() {
+        let arg = &args[i];
+        if (arg.starts_with('-') && arg.len() > 1) || arg == "(" || arg == "!" {
+            break;
+        }
+        paths.push(arg.clone());
+        i += 1;
+    }
+    if paths.is_empty() {
+        paths.push(String::from("."));
+    }
+
+    match run(&paths, &args[i..], &mut options) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(message) => {
+            eprintln!("find: {}", message);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use cmds_lite::error;
+use cmds_lite::wrap::{self, Count, Word};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+pub struct FmtOptions {
+    // -w: the widest a line may be
+    pub width: usize,
+    // -s: split long lines but do not join short ones
+    pub split_only: bool,
+}
+
+impl Default for FmtOptions {
+    fn default() -> Self {
+        FmtOptions {
+            width: 75,
+            split_only: false,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("fmt: {}", message);
+    std::process::exit(1);
+}
+
+// The blanks a line starts with
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+// Whether the word ends a sentence, ignoring closing quotes and brackets
+fn ends_sentence(word: &str) -> bool {
+    word.trim_end_matches(['"', '\'', ')', ']'])
+        .ends_with(['.', '?', '!'])
+}
+
+// The words of a line. A sentence that ends at the end of the line or
+// before two spaces keeps two spaces after it.
+fn words(line: &str) -> Vec<Word<'_>> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut rest = line.trim_start_matches([' ', '\t']);
+    while !rest.is_empty() {
+        let end = rest.find([' ', '\t']).unwrap_or(rest.len());
+        let (text, after) = rest.split_at(end);
+        rest = after.trim_start_matches([' ', '\t']);
+        let gap = after.len() - rest.len();
+        let space = if ends_sentence(text) && (rest.is_empty() || gap >= 2) {
+            2
+        } else {
+            1
+        };
+        words.push(Word { text, space });
+    }
+    words
+}
+
+struct FmtCommand<'a> {
+    options: &'a FmtOptions,
+}
+
+impl<'a> FmtCommand<'a> {
+    // Fills a paragraph: lines with the same indentation and no blank
+    // line between them
+    fn paragraph<W: Write>(&self, lines: &[String], output: &mut W) -> io::Result<()> {
+        let Some(first) = lines.first() else {
+            return Ok(());
+        };
+        let indent = indentation(first);
+        let room = self
+            .options
+            .width
+            .saturating_sub(wrap::width(indent.as_bytes(), Count::Columns));
+        let words: Vec<Word> = lines.iter().flat_map(|line| words(line)).collect();
+        for line in wrap::fill(&words, room) {
+            writeln!(output, "{}{}", indent, line)?;
+        }
+        Ok(())
+    }
+
+    fn fmt<R: BufRead, W: Write>(&self, mut input: R, output: &mut W) -> io::Result<()> {
+        let mut paragraph: Vec<String> = Vec::new();
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            if input.read_until(b'\n', &mut buffer)? == 0 {
+                return self.paragraph(&paragraph, output);
+            }
+            let text = String::from_utf8_lossy(&buffer);
+            let line = text.trim_end_matches(['\n', '\r']);
+
+            if line.trim().is_empty() {
+                self.paragraph(&paragraph, output)?;
+                paragraph.clear();
+                writeln!(output)?;
+                continue;
+            }
+            let joins = !self.options.split_only
+                && paragraph
+                    .first()
+                    .is_some_and(|first| indentation(first) == indentation(line));
+            if !joins {
+                self.paragraph(&paragraph, output)?;
+                paragraph.clear();
+            }
+            paragraph.push(line.to_string());
+        }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let stdout = io::stdout();
+        let mut output = BufWriter::new(stdout.lock());
+        let mut ok = true;
+
+        for file in files {
+            let result = if file == "-" {
+                self.fmt(io::stdin().lock(), &mut output)
+            } else {
+                File::open(file).and_then(|f| self.fmt(BufReader::new(f), &mut output))
+            };
+            match result {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return ok,
+                Err(e) => {
+                    eprintln!("fmt: {}: {}", file, error::message(&e));
+                    ok = false;
+                }
+            }
+        }
+
+        match output.flush() {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                eprintln!("fmt: write error: {}", error::message(&e));
+                false
+            }
+            _ => ok,
+        }
+    }
+}
+
+fn parse_width(value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(width) if width > 0 => width,
+        _ => fail(&format!("invalid width: '{}'", value)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = FmtOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--split-only" => options.split_only = true,
+            _ if arg.starts_with("--width=") => {
+                options.width = parse_width(&arg["--width=".len()..])
+            }
+            "--width" if i < args.len() => {
+                i += 1;
+                options.width = parse_width(&args[i - 1]);
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            // The old form of -w, such as -60
+            _ if arg[1..].bytes().all(|b| b.is_ascii_digit()) => {
+                options.width = parse_width(&arg[1..])
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        's' => options.split_only = true,
+                        'w' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            options.width = parse_width(&value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    let command = FmtCommand { options: &options };
+    if !command.run(&files) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,162 @@
+use cmds_lite::error;
+use cmds_lite::wrap::{self, Count};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+pub struct FoldOptions {
+    // -w: the widest a line may be
+    pub width: usize,
+    // -s: break after the last blank that fits
+    pub spaces: bool,
+    // -b: count bytes instead of columns
+    pub count: Count,
+}
+
+impl Default for FoldOptions {
+    fn default() -> Self {
+        FoldOptions {
+            width: 80,
+            spaces: false,
+            count: Count::Columns,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("fold: {}", message);
+    std::process::exit(1);
+}
+
+struct FoldCommand<'a> {
+    options: &'a FoldOptions,
+}
+
+impl<'a> FoldCommand<'a> {
+    fn fold<R: BufRead, W: Write>(&self, mut input: R, output: &mut W) -> io::Result<()> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+            let ended = line.last() == Some(&b'\n');
+            if ended {
+                line.pop();
+            }
+            let pieces = wrap::fold(
+                &line,
+                self.options.width,
+                self.options.spaces,
+                self.options.count,
+            );
+            output.write_all(&pieces.join(&b'\n'))?;
+            if ended {
+                output.write_all(b"\n")?;
+            }
+        }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let stdout = io::stdout();
+        let mut output = BufWriter::new(stdout.lock());
+        let mut ok = true;
+
+        for file in files {
+            let result = if file == "-" {
+                self.fold(io::stdin().lock(), &mut output)
+            } else {
+                File::open(file).and_then(|f| self.fold(BufReader::new(f), &mut output))
+            };
+            match result {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return ok,
+                Err(e) => {
+                    eprintln!("fold: {}: {}", file, error::message(&e));
+                    ok = false;
+                }
+            }
+        }
+
+        match output.flush() {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                eprintln!("fold: write error: {}", error::message(&e));
+                false
+            }
+            _ => ok,
+        }
+    }
+}
+
+fn parse_width(value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(width) if width > 0 => width,
+        _ => fail(&format!("invalid number of columns: '{}'", value)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = FoldOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--bytes" => options.count = Count::Bytes,
+            "--spaces" => options.spaces = true,
+            _ if arg.starts_with("--width=") => {
+                options.width = parse_width(&arg["--width=".len()..])
+            }
+            "--width" if i < args.len() => {
+                i += 1;
+                options.width = parse_width(&args[i - 1]);
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            // The old form of -w, such as -40
+            _ if arg[1..].bytes().all(|b| b.is_ascii_digit()) => {
+                options.width = parse_width(&arg[1..])
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'b' => options.count = Count::Bytes,
+                        's' => options.spaces = true,
+                        'w' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            options.width = parse_width(&value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    let command = FoldCommand { options: &options };
+    if !command.run(&files) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,552 @@
+// printf-style format strings: parsing into literal text and conversion
+// specifications, and rendering values the way C's printf does. Shared by
+// printf and seq -f.
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Flags {
+    pub left: bool,
+    pub plus: bool,
+    pub space: bool,
+    pub alternate: bool,
+    pub zero: bool,
+}
+
+// A width or precision, given inline or taken from the arguments with `*`
+#[derive(Clone, Copy, Debug)]
+pub enum Count {
+    Fixed(usize),
+    Argument,
+}
+
+#[derive(Clone, Debug)]
+pub struct Spec {
+    pub flags: Flags,
+    pub width: Option<Count>,
+    pub precision: Option<Count>,
+    pub conversion: u8,
+}
+
+#[derive(Debug)]
+pub enum Piece {
+    Text(Vec<u8>),
+    Conversion(Spec),
+    // A \c in the format: stop producing output
+    Stop,
+}
+
+pub enum Value<'a> {
+    Signed(i64),
+    Unsigned(u64),
+    Float(f64),
+    Bytes(&'a [u8]),
+}
+
+// Splits a format into pieces. With `escapes`, backslash sequences in the
+// text are expanded as printf(1) does.
+pub fn parse(format: &[u8], escapes: bool) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut text = Vec::new();
+    let mut i = 0;
+
+    while i < format.len() {
+        match format[i] {
+            b'\\' if escapes => {
+                if format.get(i + 1) == Some(&b'c') {
+                    flush_text(&mut text, &mut pieces);
+                    pieces.push(Piece::Stop);
+                    return Ok(pieces);
+                }
+                i = unescape(format, i, &mut text, false);
+            }
+            b'%' if format.get(i + 1) == Some(&b'%') => {
+                text.push(b'%');
+                i += 2;
+            }
+            b'%' => {
+                let (spec, next) = parse_spec(format, i)?;
+                flush_text(&mut text, &mut pieces);
+                pieces.push(Piece::Conversion(spec));
+                i = next;
+            }
+            byte => {
+                text.push(byte);
+                i += 1;
+            }
+        }
+    }
+    flush_text(&mut text, &mut pieces);
+    Ok(pieces)
+}
+
+fn flush_text(text: &mut Vec<u8>, pieces: &mut Vec<Piece>) {
+    if !text.is_empty() {
+        pieces.push(Piece::Text(std::mem::take(text)));
+    }
+}
+
+fn parse_spec(format: &[u8], start: usize) -> Result<(Spec, usize), String> {
+    let mut i = start + 1;
+    let mut flags = Flags::default();
+    while let Some(byte) = format.get(i) {
+        match byte {
+            b'-' => flags.left = true,
+            b'+' => flags.plus = true,
+            b' ' => flags.space = true,
+            b'#' => flags.alternate = true,
+            b'0' => flags.zero = true,
+            _ => break,
+        }
+        i += 1;
+    }
+
+    let count = |i: &mut usize| {
+        if format.get(*i) == Some(&b'*') {
+            *i += 1;
+            return Some(Count::Argument);
+        }
+        let digits = format[*i..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        let text = std::str::from_utf8(&format[*i..*i + digits]).unwrap_or("");
+        *i += digits;
+        text.parse().ok().map(Count::Fixed)
+    };
+
+    let width = count(&mut i);
+    let precision = if format.get(i) == Some(&b'.') {
+        i += 1;
+        Some(count(&mut i).unwrap_or(Count::Fixed(0)))
+    } else {
+        None
+    };
+
+    // Length modifiers carry no meaning when every value is 64 bits
+    while matches!(
+        format.get(i),
+        Some(b'h' | b'l' | b'L' | b'q' | b'j' | b'z' | b't')
+    ) {
+        i += 1;
+    }
+
+    match format.get(i) {
+        Some(&conversion) if b"diouxXcsbfFeEgG".contains(&conversion) => Ok((
+            Spec {
+                flags,
+                width,
+                precision,
+                conversion,
+            },
+            i + 1,
+        )),
+        Some(_) => Err(format!(
+            "{}: invalid conversion specification",
+            String::from_utf8_lossy(&format[start..=i])
+        )),
+        None => Err(format!(
+            "{}: invalid conversion specification",
+            String::from_utf8_lossy(&format[start..])
+        )),
+    }
+}
+
+// Expands the backslash escape at `i`, appending it to `out`, and returns
+// the index after it. `echo_octal` selects the \0NNN form used by %b and
+// echo -e instead of printf's \NNN.
+pub fn unescape(text: &[u8], i: usize, out: &mut Vec<u8>, echo_octal: bool) -> usize {
+    let Some(&escape) = text.get(i + 1) else {
+        out.push(b'\\');
+        return i + 1;
+    };
+    let mut next = i + 2;
+    match escape {
+        b'\\' => out.push(b'\\'),
+        b'"' => out.push(b'"'),
+        b'a' => out.push(0x07),
+        b'b' => out.push(0x08),
+        b'e' => out.push(0x1b),
+        b'f' => out.push(0x0c),
+        b'n' => out.push(b'\n'),
+        b'r' => out.push(b'\r'),
+        b't' => out.push(b'\t'),
+        b'v' => out.push(0x0b),
+        b'x' => {
+            let digits = text[next..]
+                .iter()
+                .take(2)
+                .take_while(|b| b.is_ascii_hexdigit())
+                .count();
+            if digits == 0 {
+                out.extend_from_slice(b"\\x");
+            } else {
+                let hex = std::str::from_utf8(&text[next..next + digits]).unwrap_or("0");
+                out.push(u8::from_str_radix(hex, 16).unwrap_or(0));
+                next += digits;
+            }
+        }
+        b'0'..=b'7' => {
+            // printf reads \NNN; %b reads \0NNN, where the 0 does not count
+            let (mut value, limit) = if echo_octal && escape == b'0' {
+                (0u32, 3)
+            } else if echo_octal {
+                ((escape - b'0') as u32, 2)
+            } else {
+                next -= 1;
+                (0u32, 3)
+            };
+            let mut taken = 0;
+            while taken < limit && next < text.len() && (b'0'..=b'7').contains(&text[next]) {
+                value = value * 8 + (text[next] - b'0') as u32;
+                next += 1;
+                taken += 1;
+            }
+            out.push(value as u8);
+        }
+        other => {
+            out.push(b'\\');
+            out.push(other);
+        }
+    }
+    next
+}
+
+impl Spec {
+    pub fn is_integer(&self) -> bool {
+        b"diouxX".contains(&self.conversion)
+    }
+
+    pub fn is_float(&self) -> bool {
+        b"fFeEgG".contains(&self.conversion)
+    }
+
+    // Renders a value with the given width and precision (already taken
+    // from the arguments if they were `*`). A negative width from `*`
+    // means left alignment, as in C.
+    pub fn render(&self, width: i64, precision: Option<usize>, value: Value) -> Vec<u8> {
+        let mut flags = self.flags;
+        if width < 0 {
+            flags.left = true;
+        }
+        let width = width.unsigned_abs() as usize;
+
+        let (sign, prefix, body, zero_pad) = match value {
+            Value::Signed(n) => {
+                let sign = sign_for(n < 0, flags);
+                let (prefix, digits) = self.integer_digits(n.unsigned_abs(), precision, flags);
+                (sign, prefix, digits, precision.is_none())
+            }
+            Value::Unsigned(n) => {
+                let (prefix, digits) = self.integer_digits(n, precision, flags);
+                ("", prefix, digits, precision.is_none())
+            }
+            Value::Float(x) => {
+                let sign = sign_for(x.is_sign_negative(), flags);
+                let body = self.float_body(x.abs(), precision, flags);
+                (sign, "", body, x.is_finite())
+            }
+            Value::Bytes(bytes) => {
+                let bytes = match (self.conversion, precision) {
+                    (b'c', _) => &bytes[..bytes.len().min(1)],
+                    (_, Some(p)) => &bytes[..bytes.len().min(p)],
+                    _ => bytes,
+                };
+                return pad(Vec::new(), bytes.to_vec(), width, flags.left, false);
+            }
+        };
+
+        let mut head = sign.as_bytes().to_vec();
+        head.extend_from_slice(prefix.as_bytes());
+        pad(
+            head,
+            body.into_bytes(),
+            width,
+            flags.left,
+            flags.zero && zero_pad,
+        )
+    }
+
+    fn integer_digits(
+        &self,
+        n: u64,
+        precision: Option<usize>,
+        flags: Flags,
+    ) -> (&'static str, String) {
+        let mut digits = match self.conversion {
+            b'o' => format!("{:o}", n),
+            b'x' => format!("{:x}", n),
+            b'X' => format!("{:X}", n),
+            _ => n.to_string(),
+        };
+        // An explicit zero precision prints nothing for zero
+        if precision == Some(0) && n == 0 {
+            digits.clear();
+        }
+        if let Some(p) = precision {
+            if digits.len() < p {
+                digits = "0".repeat(p - digits.len()) + &digits;
+            }
+        }
+
+        let prefix = match self.conversion {
+            b'o' if flags.alternate && !digits.starts_with('0') => "0",
+            b'x' if flags.alternate && n != 0 => "0x",
+            b'X' if flags.alternate && n != 0 => "0X",
+            _ => "",
+        };
+        (prefix, digits)
+    }
+
+    fn float_body(&self, x: f64, precision: Option<usize>, flags: Flags) -> String {
+        let upper = self.conversion.is_ascii_uppercase();
+        if !x.is_finite() {
+            let text = if x.is_nan() { "nan" } else { "inf" };
+            return if upper {
+                text.to_uppercase()
+            } else {
+                text.to_string()
+            };
+        }
+
+        let precision = precision.unwrap_or(6);
+        let body = match self.conversion {
+            b'e' | b'E' => exponent_form(x, precision, flags.alternate),
+            b'g' | b'G' => general_form(x, precision, flags.alternate),
+            _ => {
+                let mut text = format!("{:.*}", precision, x);
+                if flags.alternate && precision == 0 {
+                    text.push('.');
+                }
+                text
+            }
+        };
+        if upper {
+            body.to_uppercase()
+        } else {
+            body
+        }
+    }
+}
+
+fn sign_for(negative: bool, flags: Flags) -> &'static str {
+    if negative {
+        "-"
+    } else if flags.plus {
+        "+"
+    } else if flags.space {
+        " "
+    } else {
+        ""
+    }
+}
+
+// Pads to `width`: spaces on the left or right, or zeros between the sign
+// or prefix and the digits
+fn pad(head: Vec<u8>, body: Vec<u8>, width: usize, left: bool, zeros: bool) -> Vec<u8> {
+    let len = head.len() + body.len();
+    let fill = width.saturating_sub(len);
+    let mut out = Vec::with_capacity(len + fill);
+    if left {
+        out.extend(head);
+        out.extend(body);
+        out.extend(std::iter::repeat_n(b' ', fill));
+    } else if zeros {
+        out.extend(head);
+        out.extend(std::iter::repeat_n(b'0', fill));
+        out.extend(body);
+    } else {
+        out.extend(std::iter::repeat_n(b' ', fill));
+        out.extend(head);
+        out.extend(body);
+    }
+    out
+}
+
+// %e: d.ddde±XX with at least two exponent digits
+fn exponent_form(x: f64, precision: usize, alternate: bool) -> String {
+    let text = format!("{:.*e}", precision, x);
+    let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let mut mantissa = mantissa.to_string();
+    if alternate && precision == 0 {
+        mantissa.push('.');
+    }
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exponent.unsigned_abs())
+}
+
+// %g: %e or %f depending on the exponent, without trailing zeros unless #
+fn general_form(x: f64, precision: usize, alternate: bool) -> String {
+    let precision = precision.max(1);
+    // The exponent after rounding to the requested significant digits
+    let rounded = format!("{:.*e}", precision - 1, x);
+    let exponent: i64 = rounded
+        .split_once('e')
+        .and_then(|(_, e)| e.parse().ok())
+        .unwrap_or(0);
+
+    let mut text = if exponent < -4 || exponent >= precision as i64 {
+        exponent_form(x, precision - 1, alternate)
+    } else {
+        let decimals = (precision as i64 - 1 - exponent) as usize;
+        let mut text = format!("{:.*}", decimals, x);
+        if alternate && decimals == 0 {
+            text.push('.');
+        }
+        text
+    };
+
+    if !alternate {
+        let (mantissa, exponent) = match text.find('e') {
+            Some(pos) => (text[..pos].to_string(), text[pos..].to_string()),
+            None => (text.clone(), String::new()),
+        };
+        if mantissa.contains('.') {
+            let trimmed = mantissa.trim_end_matches('0').trim_end_matches('.');
+            text = format!("{}{}", trimmed, exponent);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Renders a format holding one conversion with its inline width and
+    // precision
+    fn render(format: &str, value: Value) -> String {
+        let pieces = parse(format.as_bytes(), false).unwrap();
+        let [Piece::Conversion(spec)] = &pieces[..] else {
+            panic!("{} is not a single conversion", format);
+        };
+        let width = match spec.width {
+            Some(Count::Fixed(width)) => width as i64,
+            _ => 0,
+        };
+        let precision = match spec.precision {
+            Some(Count::Fixed(precision)) => Some(precision),
+            _ => None,
+        };
+        String::from_utf8(spec.render(width, precision, value)).unwrap()
+    }
+
+    #[test]
+    fn integers() {
+        for (format, value, expected) in [
+            ("%d", 42, "42"),
+            ("%5d", 42, "   42"),
+            ("%-5d", 42, "42   "),
+            ("%05d", -42, "-0042"),
+            ("%+d", 42, "+42"),
+            ("% d", 42, " 42"),
+            ("%.3d", 42, "042"),
+            ("%08.3d", 42, "     042"),
+            ("%.0d", 0, ""),
+            ("%x", 42, "2a"),
+            ("%#x", 42, "0x2a"),
+            ("%#x", 0, "0"),
+            ("%#X", 255, "0XFF"),
+            ("%o", 8, "10"),
+            ("%#o", 8, "010"),
+            ("%#o", 0, "0"),
+            ("%ld", i64::MIN, "-9223372036854775808"),
+        ] {
+            assert_eq!(render(format, Value::Signed(value)), expected, "{}", format);
+        }
+        assert_eq!(
+            render("%u", Value::Unsigned(u64::MAX)),
+            "18446744073709551615"
+        );
+    }
+
+    #[test]
+    fn floats() {
+        for (format, value, expected) in [
+            ("%f", 1234.5678, "1234.567800"),
+            ("%.2f", 1234.5678, "1234.57"),
+            ("%.0f", 2.5, "2"),
+            ("%#.0f", 1234.5678, "1235."),
+            ("%010.3f", 1234.5678, "001234.568"),
+            ("%+.1f", 1.0, "+1.0"),
+            ("%e", 1234.5678, "1.234568e+03"),
+            ("%E", 0.0000123, "1.230000E-05"),
+            ("%.0e", 5.0, "5e+00"),
+            ("%g", 1234.5678, "1234.57"),
+            ("%g", 0.0000123, "1.23e-05"),
+            ("%g", 100000.0, "100000"),
+            ("%g", 1000000.0, "1e+06"),
+            ("%.3g", 1234.5678, "1.23e+03"),
+            ("%#g", 0.0000123, "1.23000e-05"),
+            ("%G", 0.0000123, "1.23E-05"),
+            ("%f", f64::INFINITY, "inf"),
+            ("%5F", f64::NAN, "  NAN"),
+            ("%05f", -f64::INFINITY, " -inf"),
+        ] {
+            assert_eq!(render(format, Value::Float(value)), expected, "{}", format);
+        }
+    }
+
+    #[test]
+    fn strings() {
+        for (format, expected) in [
+            ("%s", "hello"),
+            ("%8s", "   hello"),
+            ("%-8s", "hello   "),
+            ("%.2s", "he"),
+            ("%c", "h"),
+            ("%3c", "  h"),
+        ] {
+            assert_eq!(
+                render(format, Value::Bytes(b"hello")),
+                expected,
+                "{}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn pieces() {
+        let pieces = parse(b"a%%b%-*.*dc\\n\\cd", true).unwrap();
+        assert_eq!(pieces.len(), 4);
+        assert!(matches!(&pieces[0], Piece::Text(text) if text == b"a%b"));
+        let Piece::Conversion(spec) = &pieces[1] else {
+            panic!("expected a conversion");
+        };
+        assert!(spec.flags.left && spec.is_integer() && !spec.is_float());
+        assert!(matches!(spec.width, Some(Count::Argument)));
+        assert!(matches!(spec.precision, Some(Count::Argument)));
+        assert!(matches!(&pieces[2], Piece::Text(text) if text == b"c\n"));
+        assert!(matches!(pieces[3], Piece::Stop));
+
+        assert!(parse(b"%lld", false).is_ok());
+        assert!(parse(b"%y", false).is_err());
+        assert!(parse(b"%5", false).is_err());
+    }
+
+    #[test]
+    fn escapes() {
+        for (text, echo_octal, expected) in [
+            (&b"\\n"[..], false, &b"\n"[..]),
+            (b"\\t", false, b"\t"),
+            (b"\\\\", false, b"\\"),
+            (b"\\x41", false, b"A"),
+            (b"\\x4", false, b"\x04"),
+            (b"\\101", false, b"A"),
+            (b"\\0101", true, b"A"),
+            (b"\\101", true, b"A"),
+            (b"\\q", false, b"\\q"),
+            (b"\\", false, b"\\"),
+        ] {
+            let mut out = Vec::new();
+            let next = unescape(text, 0, &mut out, echo_octal);
+            assert_eq!(out, expected, "{:?}", String::from_utf8_lossy(text));
+            assert_eq!(next, text.len(), "{:?}", String::from_utf8_lossy(text));
+        }
+
+        // A \x without digits is kept, and what follows is left alone
+        let mut out = Vec::new();
+        assert_eq!(unescape(b"\\xg", 0, &mut out, false), 2);
+        assert_eq!(out, b"\\x");
+    }
+}
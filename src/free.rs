@@ -0,0 +1,265 @@
+use cmds_lite::duration;
+use cmds_lite::error;
+use cmds_lite::platform::{self, MemoryInfo};
+use std::env;
+use std::io::{self, BufWriter, Write};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    // -b: bytes
+    Bytes,
+    // -k: kibibytes, the default
+    Kibi,
+    // -m: mebibytes
+    Mebi,
+    // -g: gibibytes
+    Gibi,
+    // -h: whatever unit keeps the figure short, with a suffix
+    Human,
+}
+
+pub struct FreeOptions {
+    pub unit: Unit,
+    // --si: powers of 1000 instead of 1024
+    pub si: bool,
+    // -w: buffers and cache in separate columns
+    pub wide: bool,
+    // -t: a line with memory and swap added up
+    pub total: bool,
+    // -s: repeat every this often
+    pub delay: Option<Duration>,
+    // -c: repeat this many times
+    pub count: Option<u64>,
+}
+
+impl Default for FreeOptions {
+    fn default() -> Self {
+        FreeOptions {
+            unit: Unit::Kibi,
+            si: false,
+            wide: false,
+            total: false,
+            delay: None,
+            count: None,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("free: {}", message);
+    std::process::exit(1);
+}
+
+struct FreeCommand<'a> {
+    options: &'a FreeOptions,
+}
+
+impl<'a> FreeCommand<'a> {
+    // A figure in KiB the way the chosen unit shows it
+    fn scale(&self, kib: u64) -> String {
+        let bytes = kib * 1024;
+        let base: u64 = if self.options.si { 1000 } else { 1024 };
+        let power = match self.options.unit {
+            Unit::Bytes => 0,
+            Unit::Kibi => 1,
+            Unit::Mebi => 2,
+            Unit::Gibi => 3,
+            Unit::Human => return self.human(bytes),
+        };
+        (bytes / base.pow(power)).to_string()
+    }
+
+    // Like procps: "0B", "8.8Mi", "587Mi", "5.9Gi"; --si drops the "i"
+    fn human(&self, bytes: u64) -> String {
+        let base = if self.options.si { 1000.0 } else { 1024.0 };
+        if (bytes as f64) < base {
+            return format!("{}B", bytes);
+        }
+        let mut value = bytes as f64;
+        for unit in ["K", "M", "G", "T", "P"] {
+            value /= base;
+            let suffix = if self.options.si { "" } else { "i" };
+            if value < 9.95 {
+                return format!("{:.1}{}{}", value, unit, suffix);
+            }
+            if value.round() < base {
+                return format!("{}{}{}", value.round(), unit, suffix);
+            }
+        }
+        format!(
+            "{}E{}",
+            (value / base).round(),
+            if self.options.si { "" } else { "i" }
+        )
+    }
+
+    fn row(&self, label: &str, figures: &[u64]) -> String {
+        let mut line = format!("{:<8}", label);
+        for &figure in figures {
+            line.push_str(&format!("{:>12}", self.scale(figure)));
+        }
+        line
+    }
+
+    fn report(&self, info: &MemoryInfo, out: &mut impl Write) -> io::Result<()> {
+        let used = info.total - info.available;
+        let swap_used = info.swap_total.saturating_sub(info.swap_free);
+        if self.options.wide {
+            writeln!(
+                out,
+                "{:<8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}",
+                "", "total", "used", "free", "shared", "buffers", "cache", "available"
+            )?;
+            let figures = [
+                info.total,
+                used,
+                info.free,
+                info.shared,
+                info.buffers,
+                info.cached,
+                info.available,
+            ];
+            writeln!(out, "{}", self.row("Mem:", &figures))?;
+        } else {
+            writeln!(
+                out,
+                "{:<8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}",
+                "", "total", "used", "free", "shared", "buff/cache", "available"
+            )?;
+            let figures = [
+                info.total,
+                used,
+                info.free,
+                info.shared,
+                info.buffers + info.cached,
+                info.available,
+            ];
+            writeln!(out, "{}", self.row("Mem:", &figures))?;
+        }
+        let swap = [info.swap_total, swap_used, info.swap_free];
+        writeln!(out, "{}", self.row("Swap:", &swap))?;
+        if self.options.total {
+            let totals = [
+                info.total + info.swap_total,
+                used + swap_used,
+                info.free + info.swap_free,
+            ];
+            writeln!(out, "{}", self.row("Total:", &totals))?;
+        }
+        Ok(())
+    }
+
+    // Reports once, or -c times every -s seconds with a blank line between
+    fn run(&self) -> io::Result<()> {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        let repeat = self.options.delay.is_some() || self.options.count.is_some();
+        let delay = self.options.delay.unwrap_or(Duration::from_secs(1));
+        let mut shown = 0u64;
+        loop {
+            let info = platform::memory_info().unwrap_or_else(|e| {
+                fail(&format!(
+                    "cannot read /proc/meminfo: {}",
+                    error::message(&e)
+                ))
+            });
+            self.report(&info, &mut out)?;
+            shown += 1;
+            if !repeat || self.options.count.is_some_and(|count| shown >= count) {
+                break;
+            }
+            writeln!(out)?;
+            out.flush()?;
+            thread::sleep(delay);
+        }
+        out.flush()
+    }
+}
+
+fn parse_delay(value: &str) -> Duration {
+    match duration::parse_duration(value) {
+        Some(delay) if value.parse::<f64>().is_ok() && !delay.is_zero() => delay,
+        _ => fail(&format!(
+            "seconds argument '{}' is not positive number",
+            value
+        )),
+    }
+}
+
+fn parse_count(value: &str) -> u64 {
+    match value.parse::<u64>() {
+        Ok(count) if count > 0 => count,
+        _ => fail(&format!("failed to parse count argument: '{}'", value)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = FreeOptions::default();
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        match arg.as_str() {
+            "--bytes" => options.unit = Unit::Bytes,
+            "--kibi" => options.unit = Unit::Kibi,
+            "--mebi" => options.unit = Unit::Mebi,
+            "--gibi" => options.unit = Unit::Gibi,
+            "--human" => options.unit = Unit::Human,
+            "--si" => options.si = true,
+            "--wide" => options.wide = true,
+            "--total" => options.total = true,
+            _ if arg.starts_with("--seconds=") => {
+                options.delay = Some(parse_delay(&arg["--seconds=".len()..]))
+            }
+            _ if arg.starts_with("--count=") => {
+                options.count = Some(parse_count(&arg["--count=".len()..]))
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'b' => options.unit = Unit::Bytes,
+                        'k' => options.unit = Unit::Kibi,
+                        'm' => options.unit = Unit::Mebi,
+                        'g' => options.unit = Unit::Gibi,
+                        'h' => options.unit = Unit::Human,
+                        'w' => options.wide = true,
+                        't' => options.total = true,
+                        's' | 'c' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            if flag == 's' {
+                                options.delay = Some(parse_delay(&value));
+                            } else {
+                                options.count = Some(parse_count(&value));
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+            _ => fail(&format!("extra operand '{}'", arg)),
+        }
+    }
+
+    let command = FreeCommand { options: &options };
+    if let Err(e) = command.run() {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            fail(&e.to_string());
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// Shell-style wildcard matching (fnmatch without FNM_PATHNAME): `*`, `?`,
+// bracket expressions such as `[a-z]`, `[!0-9]` or `[[:digit:]]`, and
+// backslash escapes.
+
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&pattern, &text, false)
+}
+
+pub fn matches_ignore_case(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&pattern, &text, true)
+}
+
+fn chars_equal(a: char, b: char, ignore_case: bool) -> bool {
+    if ignore_case {
+        a.to_lowercase().eq(b.to_lowercase())
+    } else {
+        a == b
+    }
+}
+
+// Iterative matcher that backtracks only to the most recent `*`, which
+// keeps patterns like `*a*a*a*b` linear instead of exponential.
+fn match_from(pattern: &[char], text: &[char], ignore_case: bool) -> bool {
+    let mut p = 0;
+    let mut t = 0;
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                '*' => {
+                    star = Some((p, t));
+                    p += 1;
+                    continue;
+                }
+                '?' => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                '[' => {
+                    if let Some((matched, next)) = match_bracket(pattern, p, text[t], ignore_case) {
+                        if matched {
+                            p = next;
+                            t += 1;
+                            continue;
+                        }
+                    } else if text[t] == '[' {
+                        // An unterminated bracket is a literal '['
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+                '\\' if p + 1 < pattern.len() => {
+                    if chars_equal(pattern[p + 1], text[t], ignore_case) {
+                        p += 2;
+                        t += 1;
+                        continue;
+                    }
+                }
+                c => {
+                    if chars_equal(c, text[t], ignore_case) {
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        match star {
+            Some((star_p, star_t)) => {
+                p = star_p + 1;
+                t = star_t + 1;
+                star = Some((star_p, star_t + 1));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// Matches `c` against the bracket expression starting at `start`. Returns
+// whether it matched and the index just past the closing ']', or None if
+// the expression is not terminated.
+fn match_bracket(pattern: &[char], start: usize, c: char, ignore_case: bool) -> Option<(bool, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let current = *pattern.get(i)?;
+        if current == ']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+        first = false;
+
+        // Character classes such as [:alpha:]
+        if current == '[' && pattern.get(i + 1) == Some(&':') {
+            if let Some(end) = (i + 2..pattern.len().saturating_sub(1))
+                .find(|&j| pattern[j] == ':' && pattern[j + 1] == ']')
+            {
+                let name: String = pattern[i + 2..end].iter().collect();
+                if class_matches(&name, c) {
+                    matched = true;
+                }
+                i = end + 2;
+                continue;
+            }
+        }
+
+        let low = if current == '\\' {
+            i += 1;
+            *pattern.get(i)?
+        } else {
+            current
+        };
+        i += 1;
+
+        if pattern.get(i) == Some(&'-') && pattern.get(i + 1).is_some_and(|c| *c != ']') {
+            let mut high = pattern[i + 1];
+            i += 2;
+            if high == '\\' {
+                high = *pattern.get(i)?;
+                i += 1;
+            }
+            let in_range = |ch: char| low <= ch && ch <= high;
+            if in_range(c)
+                || (ignore_case
+                    && (in_range(c.to_ascii_lowercase()) || in_range(c.to_ascii_uppercase())))
+            {
+                matched = true;
+            }
+        } else if chars_equal(low, c, ignore_case) {
+            matched = true;
+        }
+    }
+}
+
+pub fn class_matches(name: &str, c: char) -> bool {
+    match name {
+        "alpha" => c.is_alphabetic(),
+        "digit" => c.is_ascii_digit(),
+        "alnum" => c.is_alphanumeric(),
+        "upper" => c.is_uppercase(),
+        "lower" => c.is_lowercase(),
+        "space" => c.is_whitespace(),
+        "blank" => c == ' ' || c == '\t',
+        "punct" => c.is_ascii_punctuation(),
+        "print" => !c.is_control(),
+        "graph" => !c.is_control() && !c.is_whitespace(),
+        "cntrl" => c.is_control(),
+        "xdigit" => c.is_ascii_hexdigit(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        for (pattern, text, expected) in [
+            ("*", "", true),
+            ("*", "anything", true),
+            ("*.rs", "main.rs", true),
+            ("*.rs", "main.rs.bak", false),
+            ("a?c", "abc", true),
+            ("a?c", "ac", false),
+            ("*a*a*a*b", "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", false),
+            ("*a*b", "xaxxbyb", true),
+            ("[abc]x", "bx", true),
+            ("[!abc]x", "bx", false),
+            ("[^abc]x", "dx", true),
+            ("[a-c]", "d", false),
+            ("[]]", "]", true),
+            ("[a-]", "-", true),
+            ("[[:digit:]][[:upper:]]", "7Q", true),
+            ("[[:space:]]", "x", false),
+            ("\\*", "*", true),
+            ("\\*", "a", false),
+            ("[abc", "[abc", true),
+            ("ü?", "üß", true),
+        ] {
+            assert_eq!(matches(pattern, text), expected, "{} {}", pattern, text);
+        }
+    }
+
+    #[test]
+    fn ignoring_case() {
+        assert!(matches_ignore_case("*.TXT", "notes.txt"));
+        assert!(matches_ignore_case("[a-c]*", "Banana"));
+        assert!(matches_ignore_case("[A-C]*", "banana"));
+        assert!(!matches("*.TXT", "notes.txt"));
+    }
+}
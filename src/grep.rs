@@ -0,0 +1,991 @@
+use cmds_lite::color::{Palette, When};
+use cmds_lite::error;
+use cmds_lite::glob;
+use cmds_lite::platform;
+use cmds_lite::regex::{Regex, Syntax};
+use cmds_lite::walk::{WalkOptions, Walker};
+use std::collections::{BTreeMap, VecDeque};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+
+// Like GNU grep: 0 when a line was selected, 1 when none was, and 2 on
+// errors
+const EXIT_SELECTED: i32 = 0;
+const EXIT_NONE: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+const STDIN_LABEL: &str = "(standard input)";
+
+// GNU grep's colors: red matches, magenta file names, green line numbers
+// and cyan separators. $GREP_COLORS can change them.
+const DEFAULT_PALETTE: &str = "ms=01;31:mc=01;31:sl=:cx=:fn=35:ln=32:bn=32:se=36";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BinaryFiles {
+    // Search them, but only say whether they match
+    Binary,
+    // Search them like any other file
+    Text,
+    // Do not search them at all
+    WithoutMatch,
+}
+
+pub struct GrepOptions {
+    // -E, -G: how patterns are read
+    pub syntax: Syntax,
+    // -F: patterns are plain strings
+    pub fixed_strings: bool,
+    // -i: ignore case in patterns and input
+    pub ignore_case: bool,
+    // -v: select the lines that do not match
+    pub invert: bool,
+    // -w: matches must be whole words
+    pub word: bool,
+    // -x: matches must be whole lines
+    pub line: bool,
+    // -c: print the number of selected lines per file
+    pub count: bool,
+    // -l: print only the names of files with selected lines
+    pub files_with_matches: bool,
+    // -L: print only the names of files without them
+    pub files_without_match: bool,
+    // -m: stop reading a file after this many selected lines
+    pub max_count: Option<u64>,
+    // -n: put the line number before each line
+    pub line_number: bool,
+    // -A, -B, -C: lines of context to print after and before each
+    // selected line. Groups of lines are set apart with "--" whenever
+    // either is given, even as 0.
+    pub after_context: Option<u64>,
+    pub before_context: Option<u64>,
+    // --color: highlight matches, names and separators
+    pub color: When,
+    // -H, -h: whether to put the file name before each line; None leaves
+    // it to how many files there are
+    pub with_filename: Option<bool>,
+    // -o: print only the matching parts of lines
+    pub only_matching: bool,
+    // -q: print nothing and stop at the first selected line
+    pub quiet: bool,
+    // -s: leave out messages about files that cannot be read
+    pub no_messages: bool,
+    // -r, -R: search directories, following symbolic links within them
+    // only with -R
+    pub recursive: bool,
+    pub dereference_recursive: bool,
+    // --include, --exclude, --exclude-dir: base name globs choosing what
+    // is searched
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub exclude_dir: Vec<String>,
+    // --binary-files, -a, -I
+    pub binary_files: BinaryFiles,
+    // --label: the name standard input is shown with
+    pub label: String,
+    // --threads: number of files searched at once
+    pub threads: usize,
+}
+
+impl Default for GrepOptions {
+    fn default() -> Self {
+        GrepOptions {
+            syntax: Syntax::Basic,
+            fixed_strings: false,
+            ignore_case: false,
+            invert: false,
+            word: false,
+            line: false,
+            count: false,
+            files_with_matches: false,
+            files_without_match: false,
+            max_count: None,
+            line_number: false,
+            after_context: None,
+            before_context: None,
+            color: When::Never,
+            with_filename: None,
+            only_matching: false,
+            quiet: false,
+            no_messages: false,
+            recursive: false,
+            dereference_recursive: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            exclude_dir: Vec::new(),
+            binary_files: BinaryFiles::Binary,
+            label: String::from(STDIN_LABEL),
+            threads: 1,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("grep: {}", message);
+    std::process::exit(EXIT_ERROR);
+}
+
+fn is_word(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+enum Pattern {
+    Regex(Regex),
+    // With -i, stored in lower case and compared with lowered input
+    Fixed(Vec<u8>),
+}
+
+// The patterns together: a line matches when any of them does
+struct Matcher {
+    patterns: Vec<Pattern>,
+    ignore_case: bool,
+    word: bool,
+    line: bool,
+}
+
+impl Matcher {
+    fn new(patterns: &[String], options: &GrepOptions) -> Result<Matcher, String> {
+        let mut compiled = Vec::new();
+        for pattern in patterns {
+            compiled.push(if options.fixed_strings {
+                let mut bytes = pattern.as_bytes().to_vec();
+                if options.ignore_case {
+                    bytes.make_ascii_lowercase();
+                }
+                Pattern::Fixed(bytes)
+            } else {
+                Pattern::Regex(Regex::new(pattern, options.syntax, options.ignore_case)?)
+            });
+        }
+        Ok(Matcher {
+            patterns: compiled,
+            ignore_case: options.ignore_case,
+            word: options.word,
+            line: options.line,
+        })
+    }
+
+    fn find_pattern(
+        pattern: &Pattern,
+        text: &[u8],
+        folded: &[u8],
+        from: usize,
+    ) -> Option<(usize, usize)> {
+        match pattern {
+            Pattern::Regex(regex) => regex.find_at(text, from),
+            Pattern::Fixed(needle) => {
+                if from > folded.len() {
+                    return None;
+                }
+                if needle.is_empty() {
+                    return Some((from, from));
+                }
+                folded[from..]
+                    .windows(needle.len())
+                    .position(|window| window == &needle[..])
+                    .map(|offset| (from + offset, from + offset + needle.len()))
+            }
+        }
+    }
+
+    // A match of one pattern at or after `from` that obeys -w and -x. For
+    // -w, a match with a word character next to it is passed over for the
+    // next one.
+    fn find_one(
+        &self,
+        pattern: &Pattern,
+        text: &[u8],
+        folded: &[u8],
+        mut from: usize,
+    ) -> Option<(usize, usize)> {
+        if self.line {
+            return Matcher::find_pattern(pattern, text, folded, 0)
+                .filter(|&found| from == 0 && found == (0, text.len()));
+        }
+        loop {
+            let (start, end) = Matcher::find_pattern(pattern, text, folded, from)?;
+            if !self.word
+                || ((start == 0 || !is_word(text[start - 1]))
+                    && (end == text.len() || !is_word(text[end])))
+            {
+                return Some((start, end));
+            }
+            from = start + 1;
+        }
+    }
+
+    // The leftmost match of any pattern at or after `from`, the longest of
+    // those that start there
+    fn find_at(&self, text: &[u8], from: usize) -> Option<(usize, usize)> {
+        let folded;
+        let folded = if self.ignore_case
+            && self
+                .patterns
+                .iter()
+                .any(|pattern| matches!(pattern, Pattern::Fixed(_)))
+        {
+            folded = text.to_ascii_lowercase();
+            &folded[..]
+        } else {
+            text
+        };
+        self.patterns
+            .iter()
+            .filter_map(|pattern| self.find_one(pattern, text, folded, from))
+            .min_by_key(|&(start, end)| (start, usize::MAX - end))
+    }
+
+    fn is_match(&self, text: &[u8]) -> bool {
+        if self.word || self.line || self.ignore_case {
+            return self.find_at(text, 0).is_some();
+        }
+        self.patterns.iter().any(|pattern| match pattern {
+            Pattern::Regex(regex) => regex.is_match(text),
+            Pattern::Fixed(needle) => {
+                needle.is_empty() || text.windows(needle.len()).any(|window| window == needle)
+            }
+        })
+    }
+}
+
+// Something to search, as the walk found it
+enum Target {
+    File { path: PathBuf, name: String },
+    Stdin,
+}
+
+// What searching one target produced, held until the targets before it
+// have been printed
+#[derive(Default)]
+struct Report {
+    output: Vec<u8>,
+    // Messages for standard error, printed after the output
+    messages: Vec<String>,
+    selected: bool,
+    failed: bool,
+}
+
+// Where the lines around the selected ones stand in the input being read
+struct Context {
+    // The lines just read that were not printed, with their numbers, in
+    // case a selected line follows
+    before: VecDeque<(u64, Vec<u8>)>,
+    // How many more lines are still due after the last selected one
+    after: u64,
+    // The number of the last line printed, to tell where a group of lines
+    // ends
+    last_printed: Option<u64>,
+}
+
+struct GrepCommand<'a> {
+    options: &'a GrepOptions,
+    matcher: Matcher,
+    // The colors to use, or None for plain output
+    palette: Option<Palette>,
+    show_names: bool,
+    // Whether -r is searching the current directory because no file was
+    // given
+    implicit_root: bool,
+}
+
+impl<'a> GrepCommand<'a> {
+    fn error_report(&self, message: String) -> Report {
+        Report {
+            messages: if self.options.no_messages {
+                Vec::new()
+            } else {
+                vec![message]
+            },
+            failed: true,
+            ..Report::default()
+        }
+    }
+
+    // Writes text colored as `key`, if coloring and it has a color
+    fn colored(&self, out: &mut Vec<u8>, key: &str, text: &[u8]) {
+        match self.palette.as_ref().and_then(|palette| palette.get(key)) {
+            Some(sgr) if !sgr.is_empty() => {
+                out.extend_from_slice(format!("\x1b[{}m\x1b[K", sgr).as_bytes());
+                out.extend_from_slice(text);
+                out.extend_from_slice(b"\x1b[m\x1b[K");
+            }
+            _ => out.extend_from_slice(text),
+        }
+    }
+
+    // The file name that starts a line of -c or -l output
+    fn name(&self, out: &mut Vec<u8>, name: &str) {
+        self.colored(out, "fn", name.as_bytes());
+    }
+
+    // The name and number before a line, each followed by ':' for a
+    // selected line and '-' for one of context
+    fn prefix(&self, out: &mut Vec<u8>, name: &str, number: u64, separator: &[u8]) {
+        if self.show_names {
+            self.name(out, name);
+            self.colored(out, "se", separator);
+        }
+        if self.options.line_number {
+            self.colored(out, "ln", number.to_string().as_bytes());
+            self.colored(out, "se", separator);
+        }
+    }
+
+    // The line between groups of lines that are not next to each other
+    fn group_separator(&self, out: &mut Vec<u8>) {
+        self.colored(out, "se", b"--");
+        out.push(b'\n');
+    }
+
+    fn shows_context(&self) -> bool {
+        let options = self.options;
+        (options.after_context.is_some() || options.before_context.is_some())
+            && !(options.count
+                || options.files_with_matches
+                || options.files_without_match
+                || options.quiet)
+    }
+
+    // Searches one input line by line. Input with a NUL byte in its first
+    // buffer, or in a selected line, is binary: unless told otherwise,
+    // only the fact that it matches is reported.
+    fn search(&self, mut reader: impl BufRead, name: &str, report: &mut Report) {
+        let options = self.options;
+        let mut binary = options.binary_files != BinaryFiles::Text
+            && reader.fill_buf().is_ok_and(|buffer| buffer.contains(&0));
+        if binary && options.binary_files == BinaryFiles::WithoutMatch {
+            return;
+        }
+        let listing = options.count || options.files_with_matches || options.files_without_match;
+        let before = options.before_context.unwrap_or(0);
+        let mut context = Context {
+            before: VecDeque::new(),
+            after: 0,
+            last_printed: None,
+        };
+        let mut selected: u64 = 0;
+        let mut number: u64 = 0;
+        let mut line = Vec::new();
+
+        loop {
+            // Past -m only the context after the last selected line is
+            // still printed, whether those lines match or not
+            let done = options.max_count.is_some_and(|max| selected >= max);
+            if done && context.after == 0 {
+                break;
+            }
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    if !options.no_messages {
+                        report
+                            .messages
+                            .push(format!("grep: {}: {}", name, error::message(&e)));
+                    }
+                    report.failed = true;
+                    break;
+                }
+            }
+            number += 1;
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            if done || self.matcher.is_match(&line) == options.invert {
+                if context.after > 0 {
+                    context.after -= 1;
+                    self.print_line(&mut report.output, &mut context, &line, name, number, false);
+                } else if before > 0 && !listing {
+                    context.before.push_back((number, line.clone()));
+                    if context.before.len() as u64 > before {
+                        context.before.pop_front();
+                    }
+                }
+                continue;
+            }
+            selected += 1;
+            report.selected = true;
+            if options.quiet || (listing && !options.count) {
+                break;
+            }
+            if listing {
+                continue;
+            }
+            binary |= options.binary_files != BinaryFiles::Text && line.contains(&0);
+            if binary {
+                report
+                    .messages
+                    .push(format!("grep: {}: binary file matches", name));
+                break;
+            }
+            while let Some((before, text)) = context.before.pop_front() {
+                self.print_line(&mut report.output, &mut context, &text, name, before, false);
+            }
+            self.print_line(&mut report.output, &mut context, &line, name, number, true);
+            context.after = options.after_context.unwrap_or(0);
+        }
+
+        if options.count {
+            if self.show_names {
+                self.name(&mut report.output, name);
+                self.colored(&mut report.output, "se", b":");
+            }
+            report
+                .output
+                .extend_from_slice(format!("{}\n", selected).as_bytes());
+        }
+        if (options.files_with_matches && selected > 0)
+            || (options.files_without_match && selected == 0)
+        {
+            self.name(&mut report.output, name);
+            report.output.push(b'\n');
+        }
+    }
+
+    // A selected line or one of context, or with -o each non-empty match
+    // in a selected line. A separator goes first when the line does not
+    // follow the last one printed.
+    fn print_line(
+        &self,
+        out: &mut Vec<u8>,
+        context: &mut Context,
+        line: &[u8],
+        name: &str,
+        number: u64,
+        selected: bool,
+    ) {
+        if context
+            .last_printed
+            .is_some_and(|last| last + 1 != number && self.shows_context())
+        {
+            self.group_separator(out);
+        }
+        context.last_printed = Some(number);
+
+        let options = self.options;
+        let separator: &[u8] = if selected { b":" } else { b"-" };
+        // Matches are highlighted in the lines that match: the selected
+        // ones, or with -v the context
+        let matching = selected != options.invert;
+        let key = if selected { "ms" } else { "mc" };
+        if !options.only_matching {
+            self.prefix(out, name, number, separator);
+            if self.palette.is_some() && matching {
+                self.highlight(out, line, key);
+            } else {
+                out.extend_from_slice(line);
+            }
+            out.push(b'\n');
+            return;
+        }
+        if !selected || options.invert {
+            return;
+        }
+        self.each_match(line, |start, end| {
+            self.prefix(out, name, number, separator);
+            self.colored(out, key, &line[start..end]);
+            out.push(b'\n');
+        });
+    }
+
+    // Writes a line with its matches colored as `key`
+    fn highlight(&self, out: &mut Vec<u8>, line: &[u8], key: &str) {
+        let mut written = 0;
+        self.each_match(line, |start, end| {
+            out.extend_from_slice(&line[written..start]);
+            self.colored(out, key, &line[start..end]);
+            written = end;
+        });
+        out.extend_from_slice(&line[written..]);
+    }
+
+    // Calls `found` with the start and end of each non-empty match in a
+    // line, from left to right
+    fn each_match(&self, line: &[u8], mut found: impl FnMut(usize, usize)) {
+        let mut from = 0;
+        while let Some((start, end)) = self.matcher.find_at(line, from) {
+            if end > start {
+                found(start, end);
+            }
+            from = end.max(start + 1);
+            if from > line.len() {
+                break;
+            }
+        }
+    }
+
+    fn search_target(&self, target: &Target) -> Report {
+        let mut report = Report::default();
+        match target {
+            Target::Stdin => {
+                let stdin = io::stdin();
+                self.search(stdin.lock(), &self.options.label, &mut report);
+            }
+            Target::File { path, name } => {
+                let file = match File::open(path) {
+                    Ok(file) => file,
+                    Err(e) => {
+                        return self.error_report(format!("grep: {}: {}", name, error::message(&e)))
+                    }
+                };
+                if file.metadata().is_ok_and(|m| m.is_dir()) {
+                    return self.error_report(format!("grep: {}: Is a directory", name));
+                }
+                self.search(BufReader::new(file), name, &mut report);
+            }
+        }
+        report
+    }
+
+    fn is_skipped(&self, name: &str) -> bool {
+        let options = self.options;
+        options
+            .exclude
+            .iter()
+            .any(|pattern| glob::matches(pattern, name))
+            || (!options.include.is_empty()
+                && !options
+                    .include
+                    .iter()
+                    .any(|pattern| glob::matches(pattern, name)))
+    }
+
+    // Finds what to search and hands it out in order: each target, or
+    // error found on the way, gets the next index
+    fn collect(&self, operands: &[String], mut send: impl FnMut(Result<Target, String>)) {
+        let options = self.options;
+        let base_name = |path: &Path| {
+            path.file_name()
+                .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+                .to_string()
+        };
+        for operand in operands {
+            if operand == "-" {
+                send(Ok(Target::Stdin));
+                continue;
+            }
+            let root = Path::new(operand);
+            let is_dir = fs::metadata(root).is_ok_and(|m| m.is_dir());
+            if !options.recursive || !is_dir {
+                if !self.is_skipped(&base_name(root)) {
+                    send(Ok(Target::File {
+                        path: root.to_path_buf(),
+                        name: operand.clone(),
+                    }));
+                }
+                continue;
+            }
+
+            let walk_options = WalkOptions {
+                follow_links: options.dereference_recursive,
+                follow_root_links: true,
+                ..WalkOptions::default()
+            };
+            let mut walker = Walker::new(&[root], walk_options);
+            while let Some(item) = walker.next() {
+                let entry = match item {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        send(Err(format!(
+                            "grep: {}: {}",
+                            e.path.display(),
+                            error::message(&e.error)
+                        )));
+                        continue;
+                    }
+                };
+                let file_name = entry.file_name();
+                if entry.is_dir() {
+                    if entry.depth > 0
+                        && options
+                            .exclude_dir
+                            .iter()
+                            .any(|pattern| glob::matches(pattern, &file_name))
+                    {
+                        walker.skip_current_dir();
+                    }
+                    continue;
+                }
+                // Within directories only regular files are searched, and
+                // symbolic links only with -R
+                if !entry.metadata.is_file() || self.is_skipped(&file_name) {
+                    continue;
+                }
+                // With no operand the current directory is searched, and
+                // names are shown relative to it
+                let name = entry.path.to_string_lossy();
+                let name = if self.implicit_root {
+                    name.strip_prefix("./").unwrap_or(&name).to_string()
+                } else {
+                    name.to_string()
+                };
+                send(Ok(Target::File {
+                    path: entry.path,
+                    name,
+                }));
+            }
+        }
+    }
+
+    // Searches the targets with a pool of workers. A walker feeds them,
+    // and the reports are printed in the order the targets were found, so
+    // each file's lines stay together and the output is the same as a
+    // search of one file after another.
+    fn run(&self, operands: &[String]) -> i32 {
+        let threads = self.options.threads.max(1);
+        let (job_sender, job_receiver) = mpsc::channel::<(usize, Target)>();
+        let job_receiver = Mutex::new(job_receiver);
+        let (report_sender, report_receiver) = mpsc::channel::<(usize, Report)>();
+
+        let stdout = io::stdout();
+        let mut out = io::BufWriter::new(stdout.lock());
+        let mut selected = false;
+        let mut failed = false;
+        // Whether a group of lines with context has been printed, so that
+        // the next file's first group is set apart from it
+        let mut grouped = false;
+
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                let report_sender = report_sender.clone();
+                let job_receiver = &job_receiver;
+                scope.spawn(move || loop {
+                    let job = job_receiver
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .recv();
+                    let Ok((index, target)) = job else {
+                        break;
+                    };
+                    if report_sender
+                        .send((index, self.search_target(&target)))
+                        .is_err()
+                    {
+                        break;
+                    }
+                });
+            }
+            scope.spawn(move || {
+                let mut index = 0;
+                self.collect(operands, |item| {
+                    let _ = match item {
+                        Ok(target) => job_sender.send((index, target)).is_ok(),
+                        Err(message) => report_sender
+                            .send((index, self.error_report(message)))
+                            .is_ok(),
+                    };
+                    index += 1;
+                });
+            });
+
+            let mut pending = BTreeMap::new();
+            let mut next = 0;
+            for (index, report) in report_receiver {
+                // With -q the first selected line settles the exit status,
+                // whichever file it is in
+                if self.options.quiet && report.selected {
+                    std::process::exit(EXIT_SELECTED);
+                }
+                pending.insert(index, report);
+                while let Some(report) = pending.remove(&next) {
+                    next += 1;
+                    selected |= report.selected;
+                    failed |= report.failed;
+                    let mut separator = Vec::new();
+                    if self.shows_context() && !report.output.is_empty() {
+                        if grouped {
+                            self.group_separator(&mut separator);
+                        }
+                        grouped = true;
+                    }
+                    let mut written = out
+                        .write_all(&separator)
+                        .and_then(|_| out.write_all(&report.output));
+                    if !report.messages.is_empty() {
+                        written = written.and_then(|_| out.flush());
+                        for message in &report.messages {
+                            eprintln!("{}", message);
+                        }
+                    }
+                    if let Err(e) = written {
+                        if e.kind() == io::ErrorKind::BrokenPipe {
+                            std::process::exit(if selected { EXIT_SELECTED } else { EXIT_NONE });
+                        }
+                        fail(&format!("write error: {}", error::message(&e)));
+                    }
+                }
+            }
+        });
+
+        if let Err(e) = out.flush() {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                fail(&format!("write error: {}", error::message(&e)));
+            }
+        }
+        if failed && !(self.options.quiet && selected) {
+            EXIT_ERROR
+        } else if selected {
+            EXIT_SELECTED
+        } else {
+            EXIT_NONE
+        }
+    }
+}
+
+// Patterns are one per line, whether given with -e, -f or as the first
+// operand
+fn add_patterns(patterns: &mut Vec<String>, text: &str) {
+    patterns.extend(text.split('\n').map(String::from));
+}
+
+fn read_pattern_file(patterns: &mut Vec<String>, name: &str) {
+    let text = if name == "-" {
+        io::read_to_string(io::stdin())
+    } else {
+        fs::read_to_string(name)
+    };
+    match text {
+        Ok(text) => patterns.extend(text.lines().map(String::from)),
+        Err(e) => fail(&format!("{}: {}", name, error::message(&e))),
+    }
+}
+
+fn parse_count(text: &str) -> u64 {
+    text.parse()
+        .unwrap_or_else(|_| fail(&format!("invalid max count '{}'", text)))
+}
+
+fn parse_context(text: &str) -> u64 {
+    text.parse()
+        .unwrap_or_else(|_| fail(&format!("{}: invalid context length argument", text)))
+}
+
+// The colors from $GREP_COLORS over the defaults; mt sets the colors of
+// matches in selected and context lines at once
+fn grep_palette() -> Palette {
+    let mut palette = Palette::new(DEFAULT_PALETTE);
+    if let Ok(spec) = env::var("GREP_COLORS") {
+        palette.update(&spec);
+        if let Some(matches) = palette.get("mt").map(String::from) {
+            palette.update(&format!("ms={0}:mc={0}", matches));
+        }
+    }
+    palette
+}
+
+fn parse_binary_files(text: &str) -> BinaryFiles {
+    match text {
+        "binary" => BinaryFiles::Binary,
+        "text" => BinaryFiles::Text,
+        "without-match" => BinaryFiles::WithoutMatch,
+        _ => fail("unknown binary-files type"),
+    }
+}
+
+fn parse_threads(text: &str) -> usize {
+    match text.parse::<usize>() {
+        Ok(0) => platform::available_processors(),
+        Ok(threads) => threads,
+        Err(_) => fail(&format!("invalid thread count '{}'", text)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = GrepOptions {
+        threads: platform::available_processors(),
+        ..GrepOptions::default()
+    };
+    let mut patterns = Vec::new();
+    let mut have_patterns = false;
+    // -A and -B win over -C, whichever comes first
+    let mut after_context = None;
+    let mut before_context = None;
+    let mut context = None;
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--extended-regexp" => options.syntax = Syntax::Extended,
+            "--basic-regexp" => {
+                options.syntax = Syntax::Basic;
+                options.fixed_strings = false;
+            }
+            "--fixed-strings" => options.fixed_strings = true,
+            "--ignore-case" => options.ignore_case = true,
+            "--no-ignore-case" => options.ignore_case = false,
+            "--invert-match" => options.invert = true,
+            "--word-regexp" => options.word = true,
+            "--line-regexp" => options.line = true,
+            "--count" => options.count = true,
+            "--files-with-matches" => options.files_with_matches = true,
+            "--files-without-match" => options.files_without_match = true,
+            "--line-number" => options.line_number = true,
+            "--with-filename" => options.with_filename = Some(true),
+            "--no-filename" => options.with_filename = Some(false),
+            "--only-matching" => options.only_matching = true,
+            "--quiet" | "--silent" => options.quiet = true,
+            "--no-messages" => options.no_messages = true,
+            "--recursive" => options.recursive = true,
+            "--dereference-recursive" => {
+                options.recursive = true;
+                options.dereference_recursive = true;
+            }
+            "--text" => options.binary_files = BinaryFiles::Text,
+            "--color" | "--colour" => options.color = When::Auto,
+            _ if arg.starts_with("--color=") || arg.starts_with("--colour=") => {
+                let value = &arg[arg.find('=').unwrap_or(0) + 1..];
+                options.color = When::parse(value)
+                    .unwrap_or_else(|| fail(&format!("invalid argument '{}' for '--color'", value)))
+            }
+            _ if arg.starts_with("--after-context=") => {
+                after_context = Some(parse_context(&arg["--after-context=".len()..]))
+            }
+            _ if arg.starts_with("--before-context=") => {
+                before_context = Some(parse_context(&arg["--before-context=".len()..]))
+            }
+            _ if arg.starts_with("--context=") => {
+                context = Some(parse_context(&arg["--context=".len()..]))
+            }
+            _ if arg.starts_with("--regexp=") => {
+                add_patterns(&mut patterns, &arg["--regexp=".len()..]);
+                have_patterns = true;
+            }
+            _ if arg.starts_with("--file=") => {
+                read_pattern_file(&mut patterns, &arg["--file=".len()..]);
+                have_patterns = true;
+            }
+            _ if arg.starts_with("--max-count=") => {
+                options.max_count = Some(parse_count(&arg["--max-count=".len()..]))
+            }
+            _ if arg.starts_with("--include=") => {
+                options.include.push(arg["--include=".len()..].to_string())
+            }
+            _ if arg.starts_with("--exclude=") => {
+                options.exclude.push(arg["--exclude=".len()..].to_string())
+            }
+            _ if arg.starts_with("--exclude-dir=") => options
+                .exclude_dir
+                .push(arg["--exclude-dir=".len()..].to_string()),
+            _ if arg.starts_with("--binary-files=") => {
+                options.binary_files = parse_binary_files(&arg["--binary-files=".len()..])
+            }
+            _ if arg.starts_with("--label=") => options.label = arg["--label=".len()..].to_string(),
+            _ if arg.starts_with("--threads=") => {
+                options.threads = parse_threads(&arg["--threads=".len()..])
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            // -NUM is the same as -C NUM
+            _ if arg[1..].bytes().all(|b| b.is_ascii_digit()) => {
+                context = Some(parse_context(&arg[1..]))
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'E' => options.syntax = Syntax::Extended,
+                        'G' => {
+                            options.syntax = Syntax::Basic;
+                            options.fixed_strings = false;
+                        }
+                        'F' => options.fixed_strings = true,
+                        'i' | 'y' => options.ignore_case = true,
+                        'v' => options.invert = true,
+                        'w' => options.word = true,
+                        'x' => options.line = true,
+                        'c' => options.count = true,
+                        'l' => options.files_with_matches = true,
+                        'L' => options.files_without_match = true,
+                        'n' => options.line_number = true,
+                        'H' => options.with_filename = Some(true),
+                        'h' => options.with_filename = Some(false),
+                        'o' => options.only_matching = true,
+                        'q' => options.quiet = true,
+                        's' => options.no_messages = true,
+                        'r' => options.recursive = true,
+                        'R' => {
+                            options.recursive = true;
+                            options.dereference_recursive = true;
+                        }
+                        'a' => options.binary_files = BinaryFiles::Text,
+                        'I' => options.binary_files = BinaryFiles::WithoutMatch,
+                        'e' | 'f' | 'm' | 'A' | 'B' | 'C' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            match flag {
+                                'e' => {
+                                    add_patterns(&mut patterns, &value);
+                                    have_patterns = true;
+                                }
+                                'f' => {
+                                    read_pattern_file(&mut patterns, &value);
+                                    have_patterns = true;
+                                }
+                                'A' => after_context = Some(parse_context(&value)),
+                                'B' => before_context = Some(parse_context(&value)),
+                                'C' => context = Some(parse_context(&value)),
+                                _ => options.max_count = Some(parse_count(&value)),
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    options.after_context = after_context.or(context);
+    options.before_context = before_context.or(context);
+
+    if !have_patterns {
+        if operands.is_empty() {
+            eprintln!("Usage: grep [OPTION]... PATTERNS [FILE]...");
+            std::process::exit(EXIT_ERROR);
+        }
+        add_patterns(&mut patterns, &operands.remove(0));
+    }
+    let matcher = Matcher::new(&patterns, &options).unwrap_or_else(|message| fail(&message));
+
+    // Without files, -r searches the current directory and anything else
+    // standard input
+    let implicit_root = operands.is_empty() && options.recursive;
+    if operands.is_empty() {
+        operands.push(String::from(if options.recursive { "." } else { "-" }));
+    }
+    let show_names = options.with_filename.unwrap_or_else(|| {
+        operands.len() > 1
+            || (options.recursive && fs::metadata(&operands[0]).is_ok_and(|m| m.is_dir()))
+    });
+
+    let command = GrepCommand {
+        options: &options,
+        matcher,
+        palette: options.color.enabled().then(grep_palette),
+        show_names,
+        implicit_root,
+    };
+    std::process::exit(command.run(&operands));
+}
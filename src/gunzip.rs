@@ -0,0 +1,9 @@
+use cmds_lite::compress::{self, CompressOptions};
+
+fn main() {
+    let options = CompressOptions {
+        decompress: true,
+        ..CompressOptions::default()
+    };
+    compress::main("gunzip", options)
+}
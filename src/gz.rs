@@ -0,0 +1,268 @@
+// The gzip file format (RFC 1952): a header, DEFLATE data, and a trailer
+// holding the CRC-32 and length of the original data. Several members
+// may follow one another, and are read back as one stream.
+
+use crate::crc::{self, Crc};
+use crate::deflate;
+use std::io::{self, BufRead, Read, Write};
+
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+const METHOD_DEFLATE: u8 = 8;
+
+const FLAG_HEADER_CRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+
+// Operating system byte for Unix
+const OS_UNIX: u8 = 3;
+
+// The error for data after the last member, which gzip only warns about
+pub const TRAILING_GARBAGE: &str = "trailing garbage ignored";
+
+fn corrupt(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+pub struct Encoder<W: Write> {
+    deflate: deflate::Encoder<W>,
+    crc: Crc,
+    size: u64,
+}
+
+impl<W: Write> Encoder<W> {
+    // Writes the header at once; name and mtime describe the original
+    // file, and are left out for data that has none
+    pub fn new(mut inner: W, level: u32, name: Option<&str>, mtime: u32) -> io::Result<Self> {
+        let flags = if name.is_some() { FLAG_NAME } else { 0 };
+        // Extra flags: 2 for maximum compression, 4 for the fastest
+        let extra = match level {
+            9 => 2,
+            1 => 4,
+            _ => 0,
+        };
+        inner.write_all(&[MAGIC[0], MAGIC[1], METHOD_DEFLATE, flags])?;
+        inner.write_all(&mtime.to_le_bytes())?;
+        inner.write_all(&[extra, OS_UNIX])?;
+        if let Some(name) = name {
+            inner.write_all(name.as_bytes())?;
+            inner.write_all(&[0])?;
+        }
+        Ok(Encoder {
+            deflate: deflate::Encoder::new(inner, level),
+            crc: Crc::new(crc::Algorithm::Crc32),
+            size: 0,
+        })
+    }
+
+    // Writes the last of the data and the trailer, returning the inner
+    // writer
+    pub fn finish(self) -> io::Result<W> {
+        let mut inner = self.deflate.finish()?;
+        inner.write_all(&self.crc.finish().to_le_bytes())?;
+        inner.write_all(&(self.size as u32).to_le_bytes())?;
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let written = self.deflate.write(buffer)?;
+        self.crc.update(&buffer[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.deflate.flush()
+    }
+}
+
+// Reads a member header, leaving the reader at the compressed data
+fn read_header(input: &mut impl BufRead) -> io::Result<()> {
+    let mut fixed = [0u8; 10];
+    input.read_exact(&mut fixed).map_err(|e| {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            corrupt("not in gzip format")
+        } else {
+            e
+        }
+    })?;
+    if fixed[..2] != MAGIC {
+        return Err(corrupt("not in gzip format"));
+    }
+    if fixed[2] != METHOD_DEFLATE {
+        return Err(corrupt("unknown method"));
+    }
+    let flags = fixed[3];
+    if flags & FLAG_EXTRA != 0 {
+        let mut length = [0u8; 2];
+        input.read_exact(&mut length)?;
+        let length = u16::from_le_bytes(length) as u64;
+        io::copy(&mut input.take(length), &mut io::sink())?;
+    }
+    for flag in [FLAG_NAME, FLAG_COMMENT] {
+        if flags & flag != 0 {
+            input.read_until(0, &mut Vec::new())?;
+        }
+    }
+    if flags & FLAG_HEADER_CRC != 0 {
+        input.read_exact(&mut [0u8; 2])?;
+    }
+    Ok(())
+}
+
+pub struct Decoder<R: BufRead> {
+    deflate: deflate::Decoder<R>,
+    crc: Crc,
+    size: u64,
+    done: bool,
+}
+
+impl<R: BufRead> Decoder<R> {
+    // Fails at once if the input does not start with a gzip header
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        read_header(&mut inner)?;
+        Ok(Decoder {
+            deflate: deflate::Decoder::new(inner),
+            crc: Crc::new(crc::Algorithm::Crc32),
+            size: 0,
+            done: false,
+        })
+    }
+
+    // Checks the trailer of the member just read, then moves on to the
+    // next one if there is one
+    fn end_member(&mut self) -> io::Result<()> {
+        let input = self.deflate.get_mut();
+        let mut trailer = [0u8; 8];
+        input.read_exact(&mut trailer)?;
+        let crc = u32::from_le_bytes(trailer[..4].try_into().unwrap());
+        let size = u32::from_le_bytes(trailer[4..].try_into().unwrap());
+        if crc != self.crc.finish() {
+            return Err(corrupt("invalid compressed data--crc error"));
+        }
+        if size != self.size as u32 {
+            return Err(corrupt("invalid compressed data--length error"));
+        }
+
+        // Zeros after the last member are padding, as tape archives have
+        loop {
+            let available = input.fill_buf()?;
+            if available.is_empty() {
+                self.done = true;
+                return Ok(());
+            }
+            if available[0] == MAGIC[0] {
+                read_header(input)?;
+                break;
+            }
+            let zeros = available.iter().take_while(|&&b| b == 0).count();
+            if zeros < available.len() {
+                return Err(corrupt(TRAILING_GARBAGE));
+            }
+            input.consume(zeros);
+        }
+        self.deflate.reset();
+        self.crc = Crc::new(crc::Algorithm::Crc32);
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        while !self.done && !buffer.is_empty() {
+            let read = self.deflate.read(buffer)?;
+            if read > 0 {
+                self.crc.update(&buffer[..read]);
+                self.size += read as u64;
+                return Ok(read);
+            }
+            self.end_member()?;
+        }
+        Ok(0)
+    }
+}
+
+// True if the data starts like a gzip member
+pub fn is_gzip(start: &[u8]) -> bool {
+    start.starts_with(&MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "hello\n" as gzip -9n writes it
+    const HELLO: [u8; 26] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9,
+        0xe7, 0x02, 0x00, 0x20, 0x30, 0x3a, 0x36, 0x06, 0x00, 0x00, 0x00,
+    ];
+
+    fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        Decoder::new(data)?.read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    fn message(result: io::Result<Vec<u8>>) -> String {
+        result.unwrap_err().to_string()
+    }
+
+    #[test]
+    fn reads_gzip_output() {
+        assert!(is_gzip(&HELLO));
+        assert_eq!(decompress(&HELLO).unwrap(), b"hello\n");
+    }
+
+    #[test]
+    fn round_trips() {
+        let data = b"some text, some more text, and some text again\n".repeat(500);
+        let mut encoder = Encoder::new(Vec::new(), 6, Some("notes.txt"), 1_700_000_000).unwrap();
+        encoder.write_all(&data).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(compressed[3], FLAG_NAME);
+        assert_eq!(&compressed[10..20], b"notes.txt\0");
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn reads_members_as_one_stream() {
+        let mut data = HELLO.to_vec();
+        data.extend_from_slice(&HELLO);
+        // Zeros after the last member are padding
+        data.extend_from_slice(&[0; 100]);
+        assert_eq!(decompress(&data).unwrap(), b"hello\nhello\n");
+    }
+
+    #[test]
+    fn rejects_damaged_input() {
+        assert!(!is_gzip(b"hello"));
+        assert_eq!(message(decompress(b"hello there")), "not in gzip format");
+        assert_eq!(message(decompress(&HELLO[..5])), "not in gzip format");
+
+        let mut method = HELLO;
+        method[2] = 7;
+        assert_eq!(message(decompress(&method)), "unknown method");
+
+        let mut crc = HELLO;
+        crc[18] ^= 1;
+        assert_eq!(
+            message(decompress(&crc)),
+            "invalid compressed data--crc error"
+        );
+
+        let mut length = HELLO;
+        length[22] = 7;
+        assert_eq!(
+            message(decompress(&length)),
+            "invalid compressed data--length error"
+        );
+
+        let mut garbage = HELLO.to_vec();
+        garbage.extend_from_slice(b"junk");
+        assert_eq!(message(decompress(&garbage)), TRAILING_GARBAGE);
+    }
+}
@@ -0,0 +1,5 @@
+use cmds_lite::compress::{self, CompressOptions};
+
+fn main() {
+    compress::main("gzip", CompressOptions::default())
+}
@@ -0,0 +1,321 @@
+use cmds_lite::error;
+use cmds_lite::size;
+use cmds_lite::trailing::{self, LastBytes, LastLines};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::mem::ManuallyDrop;
+use std::os::fd::FromRawFd;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+pub struct HeadOptions {
+    // -n, -c: how many lines or bytes
+    pub count: u64,
+    // -c: count bytes instead of lines
+    pub bytes: bool,
+    // -n -K, -c -K: print all but the last K lines or bytes
+    pub all_but_last: bool,
+    // -q: never print headers
+    pub quiet: bool,
+    // -v: always print headers
+    pub verbose: bool,
+    // -z: lines end with NUL instead of newline
+    pub zero_terminated: bool,
+}
+
+impl Default for HeadOptions {
+    fn default() -> Self {
+        HeadOptions {
+            count: 10,
+            bytes: false,
+            all_but_last: false,
+            quiet: false,
+            verbose: false,
+            zero_terminated: false,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("head: {}", message);
+    std::process::exit(1);
+}
+
+// An error reading the input, as opposed to one writing the output
+struct ReadError(io::Error);
+
+struct HeadCommand<'a> {
+    options: &'a HeadOptions,
+}
+
+impl<'a> HeadCommand<'a> {
+    fn delimiter(&self) -> u8 {
+        if self.options.zero_terminated {
+            b'\0'
+        } else {
+            b'\n'
+        }
+    }
+
+    // The first lines. A regular file is left positioned just after them,
+    // so that whoever reads it next carries on from there.
+    fn first_lines(&self, mut file: &File, out: &mut impl Write) -> Result<(), ReadError> {
+        let mut buffer = vec![0; BUFFER_SIZE];
+        let mut remaining = self.options.count;
+        while remaining > 0 {
+            let read = file.read(&mut buffer).map_err(ReadError)?;
+            if read == 0 {
+                break;
+            }
+            let mut end = read;
+            for (index, &byte) in buffer[..read].iter().enumerate() {
+                if byte == self.delimiter() {
+                    remaining -= 1;
+                    if remaining == 0 {
+                        end = index + 1;
+                        break;
+                    }
+                }
+            }
+            write_output(out, &buffer[..end]);
+            if end < read {
+                let _ = file.seek(SeekFrom::Current(end as i64 - read as i64));
+            }
+        }
+        Ok(())
+    }
+
+    // Everything but the last lines or bytes. For a regular file, where
+    // they start is found from its end, and everything before is copied;
+    // anything else is read through, holding back the last ones read.
+    fn all_but_last(&self, file: &File, out: &mut impl Write) -> Result<(), ReadError> {
+        let count = self.options.count;
+        if let Some((start, end)) = trailing::remaining_extent(file) {
+            let stop = if self.options.bytes {
+                end.saturating_sub(count).max(start)
+            } else {
+                trailing::last_lines_offset(file, start, end, count, self.delimiter())
+                    .map_err(ReadError)?
+            };
+            return copy(&mut file.take(stop - start), out);
+        }
+
+        if self.options.bytes {
+            let mut last = LastBytes::new(count);
+            let mut buffer = vec![0; BUFFER_SIZE];
+            let mut reader = file;
+            loop {
+                let read = reader.read(&mut buffer).map_err(ReadError)?;
+                if read == 0 {
+                    return Ok(());
+                }
+                last.push(&buffer[..read], |data| write_output(out, data));
+            }
+        }
+
+        let mut last = LastLines::new(count);
+        let mut reader = BufReader::new(file);
+        loop {
+            let mut line = Vec::new();
+            if reader
+                .read_until(self.delimiter(), &mut line)
+                .map_err(ReadError)?
+                == 0
+            {
+                return Ok(());
+            }
+            if let Some(line) = last.push(line) {
+                write_output(out, &line);
+            }
+        }
+    }
+
+    fn head(&self, file: &File, out: &mut impl Write) -> Result<(), ReadError> {
+        match (self.options.all_but_last, self.options.bytes) {
+            (true, _) => self.all_but_last(file, out),
+            (false, true) => copy(&mut file.take(self.options.count), out),
+            (false, false) => self.first_lines(file, out),
+        }
+    }
+
+    fn run(&self, names: &[String]) -> bool {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        let show_headers = self.options.verbose || (names.len() > 1 && !self.options.quiet);
+        // Standard input is used in place, so that a regular file's size
+        // and offset can be looked at like any other file's
+        let stdin = ManuallyDrop::new(unsafe { File::from_raw_fd(0) });
+        let mut success = true;
+        let mut first = true;
+
+        for name in names {
+            let opened;
+            let file = if name == "-" {
+                &*stdin
+            } else {
+                match File::open(name) {
+                    Ok(file) => {
+                        opened = file;
+                        &opened
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "head: cannot open '{}' for reading: {}",
+                            name,
+                            error::message(&e)
+                        );
+                        success = false;
+                        continue;
+                    }
+                }
+            };
+            let shown = if name == "-" { "standard input" } else { name };
+            if show_headers {
+                let separator = if first { "" } else { "\n" };
+                write_output(
+                    &mut out,
+                    format!("{}==> {} <==\n", separator, shown).as_bytes(),
+                );
+            }
+            first = false;
+
+            if let Err(ReadError(e)) = self.head(file, &mut out) {
+                flush_output(&mut out);
+                eprintln!("head: error reading '{}': {}", shown, error::message(&e));
+                success = false;
+            }
+        }
+        flush_output(&mut out);
+        success
+    }
+}
+
+fn copy(reader: &mut impl Read, out: &mut impl Write) -> Result<(), ReadError> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(ReadError(e)),
+        };
+        write_output(out, &buffer[..read]);
+    }
+}
+
+// Output errors end head: quietly when the reader has gone away
+fn write_output(out: &mut impl Write, data: &[u8]) {
+    if let Err(e) = out.write_all(data) {
+        output_failed(e);
+    }
+}
+
+fn flush_output(out: &mut impl Write) {
+    if let Err(e) = out.flush() {
+        output_failed(e);
+    }
+}
+
+fn output_failed(e: io::Error) -> ! {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        std::process::exit(0);
+    }
+    fail(&format!(
+        "error writing 'standard output': {}",
+        error::message(&e)
+    ));
+}
+
+// A count for -n or -c, with the size suffixes dd takes; a leading '-'
+// asks for all but that many
+fn parse_count(options: &mut HeadOptions, text: &str, bytes: bool) {
+    let (all_but_last, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let what = if bytes { "bytes" } else { "lines" };
+    options.count = size::parse_size(digits)
+        .unwrap_or_else(|| fail(&format!("invalid number of {}: '{}'", what, text)));
+    options.all_but_last = all_but_last;
+    options.bytes = bytes;
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = HeadOptions::default();
+    let mut names = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            names.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--quiet" | "--silent" => {
+                options.quiet = true;
+                options.verbose = false;
+            }
+            "--verbose" => {
+                options.verbose = true;
+                options.quiet = false;
+            }
+            "--zero-terminated" => options.zero_terminated = true,
+            _ if arg.starts_with("--lines=") => {
+                parse_count(&mut options, &arg["--lines=".len()..], false)
+            }
+            _ if arg.starts_with("--bytes=") => {
+                parse_count(&mut options, &arg["--bytes=".len()..], true)
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            // The old form: -K for the first K lines
+            _ if arg[1..].bytes().all(|b| b.is_ascii_digit()) => {
+                parse_count(&mut options, &arg[1..], false)
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'q' => {
+                            options.quiet = true;
+                            options.verbose = false;
+                        }
+                        'v' => {
+                            options.verbose = true;
+                            options.quiet = false;
+                        }
+                        'z' => options.zero_terminated = true,
+                        'n' | 'c' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            parse_count(&mut options, &value, flag == 'c');
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if names.is_empty() {
+        names.push(String::from("-"));
+    }
+
+    let command = HeadCommand { options: &options };
+    if !command.run(&names) {
+        std::process::exit(1);
+    }
+}
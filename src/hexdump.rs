@@ -0,0 +1,204 @@
+use cmds_lite::dump::{self, Input};
+use cmds_lite::error;
+use std::env;
+use std::io::{self, BufWriter, Write};
+
+const BYTES_PER_LINE: usize = 16;
+
+#[derive(Default)]
+pub struct HexdumpOptions {
+    // -C: hex bytes in two groups of eight, then the text
+    pub canonical: bool,
+    // -s: bytes to skip first
+    pub skip: u64,
+    // -n: bytes to dump at most
+    pub length: Option<u64>,
+    // -v: print repeated lines instead of a '*'
+    pub verbose: bool,
+}
+
+struct HexdumpCommand<'a> {
+    options: &'a HexdumpOptions,
+}
+
+impl<'a> HexdumpCommand<'a> {
+    fn address(&self, offset: u64) -> String {
+        if self.options.canonical {
+            format!("{:08x}", offset)
+        } else {
+            format!("{:07x}", offset)
+        }
+    }
+
+    // Bytes past `read` are left blank; a unit that is only partly there
+    // is zero-filled
+    fn write_line(
+        &self,
+        offset: u64,
+        block: &[u8],
+        read: usize,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let mut line = self.address(offset);
+        if self.options.canonical {
+            for (i, byte) in block.iter().enumerate() {
+                line.push_str(if i % 8 == 0 { "  " } else { " " });
+                if i < read {
+                    line.push_str(&format!("{:02x}", byte));
+                } else {
+                    line.push_str("  ");
+                }
+            }
+            let text: String = block[..read].iter().map(|&b| dump::printable(b)).collect();
+            line.push_str(&format!("  |{}|", text));
+        } else {
+            for (i, unit) in block.chunks(2).enumerate() {
+                if i * 2 < read {
+                    line.push_str(&format!(" {:04x}", u16::from_le_bytes([unit[0], unit[1]])));
+                } else {
+                    line.push_str("     ");
+                }
+            }
+        }
+        line.push('\n');
+        out.write_all(line.as_bytes())
+    }
+
+    fn run(&self, input: &mut Input, out: &mut impl Write) -> io::Result<bool> {
+        let mut failed = 0;
+        let mut report = |input: &mut Input, out: &mut dyn Write| -> io::Result<()> {
+            for (name, e) in input.take_errors() {
+                out.flush()?;
+                eprintln!("hexdump: {}: {}", name, error::message(&e));
+                failed += 1;
+            }
+            Ok(())
+        };
+
+        let mut offset = input.skip(self.options.skip);
+        report(input, out)?;
+
+        let mut remaining = self.options.length.unwrap_or(u64::MAX);
+        let mut block = [0u8; BYTES_PER_LINE];
+        let mut previous: Option<[u8; BYTES_PER_LINE]> = None;
+        let mut squeezed = false;
+        let mut dumped = false;
+        while remaining > 0 {
+            let wanted = remaining.min(BYTES_PER_LINE as u64) as usize;
+            block.fill(0);
+            let read = input.read_block(&mut block[..wanted]);
+            report(input, out)?;
+            if read == 0 {
+                break;
+            }
+
+            // A full line like the one before is shown as a single '*'
+            if !self.options.verbose && read == BYTES_PER_LINE && previous == Some(block) {
+                if !squeezed {
+                    out.write_all(b"*\n")?;
+                    squeezed = true;
+                }
+            } else {
+                self.write_line(offset, &block, read, out)?;
+                squeezed = false;
+            }
+            previous = Some(block);
+            offset += read as u64;
+            remaining -= read as u64;
+            dumped = true;
+        }
+
+        // Empty input prints nothing at all
+        if dumped {
+            writeln!(out, "{}", self.address(offset))?;
+        }
+        out.flush()?;
+        Ok(failed == 0)
+    }
+}
+
+pub fn run(files: &[String], options: &HexdumpOptions) -> io::Result<bool> {
+    let command = HexdumpCommand { options };
+    let mut input = Input::new(files);
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    command.run(&mut input, &mut out)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("hexdump: {}", message);
+    std::process::exit(1);
+}
+
+fn parse_count(value: &str, what: &str) -> u64 {
+    dump::parse_count(value)
+        .unwrap_or_else(|| fail(&format!("failed to parse {}: '{}'", what, value)))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = HexdumpOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--canonical" => options.canonical = true,
+            "--no-squeezing" => options.verbose = true,
+            _ if arg.starts_with("--skip=") => {
+                options.skip = parse_count(&arg["--skip=".len()..], "offset")
+            }
+            _ if arg.starts_with("--length=") => {
+                options.length = Some(parse_count(&arg["--length=".len()..], "length"))
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'C' => options.canonical = true,
+                        'v' => options.verbose = true,
+                        's' | 'n' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            if flag == 's' {
+                                options.skip = parse_count(&value, "offset");
+                            } else {
+                                options.length = Some(parse_count(&value, "length"));
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    match run(&files, &options) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(1),
+        Err(e) => fail(&format!("write error: {}", error::message(&e))),
+    }
+}
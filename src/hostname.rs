@@ -0,0 +1,107 @@
+use cmds_lite::error;
+use cmds_lite::platform;
+use std::env;
+use std::io;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Show {
+    // The name as the kernel has it
+    Name,
+    // -s: the name up to the first dot
+    Short,
+    // -f: the fully qualified domain name
+    Fqdn,
+    // -d: the domain part of the fully qualified name
+    Domain,
+    // -i: the addresses the name resolves to
+    Addresses,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("hostname: {}", message);
+    std::process::exit(1);
+}
+
+// The canonical name the resolver gives for the host name
+fn fully_qualified(name: &str) -> String {
+    match platform::lookup_host(name) {
+        Ok((canonical, _)) => canonical.unwrap_or_else(|| name.to_string()),
+        Err(e) => fail(&format!("{}: {}", name, error::message(&e))),
+    }
+}
+
+fn run(show: Show) -> String {
+    let name = platform::hostname().unwrap_or_else(|e| fail(&e.to_string()));
+    match show {
+        Show::Name => name,
+        Show::Short => name.split('.').next().unwrap_or_default().to_string(),
+        Show::Fqdn => fully_qualified(&name),
+        Show::Domain => {
+            let fqdn = fully_qualified(&name);
+            fqdn.split_once('.')
+                .map(|(_, domain)| domain.to_string())
+                .unwrap_or_default()
+        }
+        Show::Addresses => match platform::lookup_host(&name) {
+            Ok((_, addresses)) => {
+                let addresses: Vec<String> = addresses.iter().map(|a| a.to_string()).collect();
+                addresses.join(" ")
+            }
+            Err(e) => fail(&format!("{}: {}", name, error::message(&e))),
+        },
+    }
+}
+
+fn set(name: &str) {
+    if name.is_empty() {
+        fail("the specified hostname is invalid");
+    }
+    if let Err(e) = platform::set_hostname(name) {
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => fail("you must be root to change the host name"),
+            io::ErrorKind::InvalidInput => fail("the specified hostname is invalid"),
+            _ => fail(&e.to_string()),
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut show = Show::Name;
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--short" => show = Show::Short,
+            "--fqdn" | "--long" => show = Show::Fqdn,
+            "--domain" => show = Show::Domain,
+            "--ip-address" => show = Show::Addresses,
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    show = match flag {
+                        's' => Show::Short,
+                        'f' => Show::Fqdn,
+                        'd' => Show::Domain,
+                        'i' => Show::Addresses,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    };
+                }
+            }
+        }
+    }
+
+    match operands.len() {
+        0 => println!("{}", run(show)),
+        1 if show == Show::Name => set(&operands[0]),
+        1 => fail("cannot set the host name while printing it"),
+        _ => fail(&format!("extra operand '{}'", operands[1])),
+    }
+}
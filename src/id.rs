@@ -0,0 +1,245 @@
+use cmds_lite::users;
+use std::env;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Only {
+    // The full "uid=... gid=... groups=..." line
+    All,
+    // -u: the user ID
+    User,
+    // -g: the group ID
+    Group,
+    // -G: all group IDs
+    Groups,
+}
+
+pub struct IdOptions {
+    pub only: Only,
+    // -n: names instead of numbers
+    pub names: bool,
+    // -r: real IDs instead of effective ones
+    pub real: bool,
+}
+
+impl Default for IdOptions {
+    fn default() -> Self {
+        IdOptions {
+            only: Only::All,
+            names: false,
+            real: false,
+        }
+    }
+}
+
+// The IDs to report: of this process, or of a user named on the command
+// line, whose real and effective IDs are the same
+struct Identity {
+    uid: u32,
+    euid: u32,
+    gid: u32,
+    egid: u32,
+    groups: Vec<u32>,
+}
+
+impl Identity {
+    fn current() -> Self {
+        let credentials = users::current();
+        let mut groups = vec![credentials.gid];
+        if credentials.egid != credentials.gid {
+            groups.push(credentials.egid);
+        }
+        for gid in credentials.groups {
+            if !groups.contains(&gid) {
+                groups.push(gid);
+            }
+        }
+        Identity {
+            uid: credentials.uid,
+            euid: credentials.euid,
+            gid: credentials.gid,
+            egid: credentials.egid,
+            groups,
+        }
+    }
+
+    fn of(user: &users::User) -> Self {
+        let mut groups = vec![user.gid];
+        for gid in users::groups_of(user) {
+            if !groups.contains(&gid) {
+                groups.push(gid);
+            }
+        }
+        Identity {
+            uid: user.uid,
+            euid: user.uid,
+            gid: user.gid,
+            egid: user.gid,
+            groups,
+        }
+    }
+}
+
+struct IdCommand<'a> {
+    options: &'a IdOptions,
+    ok: bool,
+}
+
+impl<'a> IdCommand<'a> {
+    // An ID as -n prints it: its name, or the number with a warning when
+    // it has none
+    fn user_name(&mut self, uid: u32) -> String {
+        users::user_by_id(uid)
+            .map(|user| user.name)
+            .unwrap_or_else(|| {
+                eprintln!("id: cannot find name for user ID {}", uid);
+                self.ok = false;
+                uid.to_string()
+            })
+    }
+
+    fn group_name(&mut self, gid: u32) -> String {
+        users::group_by_id(gid)
+            .map(|group| group.name)
+            .unwrap_or_else(|| {
+                eprintln!("id: cannot find name for group ID {}", gid);
+                self.ok = false;
+                gid.to_string()
+            })
+    }
+
+    fn only(&mut self, identity: &Identity) -> String {
+        let (uid, gid) = if self.options.real {
+            (identity.uid, identity.gid)
+        } else {
+            (identity.euid, identity.egid)
+        };
+        match (self.options.only, self.options.names) {
+            (Only::User, false) => uid.to_string(),
+            (Only::User, true) => self.user_name(uid),
+            (Only::Group, false) => gid.to_string(),
+            (Only::Group, true) => self.group_name(gid),
+            (_, names) => {
+                let groups: Vec<String> = identity
+                    .groups
+                    .iter()
+                    .map(|&gid| {
+                        if names {
+                            self.group_name(gid)
+                        } else {
+                            gid.to_string()
+                        }
+                    })
+                    .collect();
+                groups.join(" ")
+            }
+        }
+    }
+
+    // The default format, with names in parentheses where there are any
+    fn full(&self, identity: &Identity) -> String {
+        let user = |uid: u32| match users::user_by_id(uid) {
+            Some(user) => format!("{}({})", uid, user.name),
+            None => uid.to_string(),
+        };
+        let group = |gid: u32| match users::group_by_id(gid) {
+            Some(group) => format!("{}({})", gid, group.name),
+            None => gid.to_string(),
+        };
+
+        let mut line = format!("uid={} gid={}", user(identity.uid), group(identity.gid));
+        if identity.euid != identity.uid {
+            line.push_str(&format!(" euid={}", user(identity.euid)));
+        }
+        if identity.egid != identity.gid {
+            line.push_str(&format!(" egid={}", group(identity.egid)));
+        }
+        let groups: Vec<String> = identity.groups.iter().map(|&gid| group(gid)).collect();
+        line.push_str(&format!(" groups={}", groups.join(",")));
+        line
+    }
+
+    fn run(&mut self, user: Option<&str>) -> bool {
+        let identity = match user {
+            Some(name) => match users::parse_user(name) {
+                Some(user) if users::user_by_id(user.uid).is_some() => Identity::of(&user),
+                _ => {
+                    eprintln!("id: '{}': no such user", name);
+                    return false;
+                }
+            },
+            None => Identity::current(),
+        };
+        let line = match self.options.only {
+            Only::All => self.full(&identity),
+            _ => self.only(&identity),
+        };
+        println!("{}", line);
+        self.ok
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("id: {}", message);
+    std::process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = IdOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+    let mut choices = 0;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        let mut choose = |options: &mut IdOptions, only: Only| {
+            if options.only != only {
+                choices += 1;
+            }
+            options.only = only;
+        };
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--user" => choose(&mut options, Only::User),
+            "--group" => choose(&mut options, Only::Group),
+            "--groups" => choose(&mut options, Only::Groups),
+            "--name" => options.names = true,
+            "--real" => options.real = true,
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'u' => choose(&mut options, Only::User),
+                        'g' => choose(&mut options, Only::Group),
+                        'G' => choose(&mut options, Only::Groups),
+                        'n' => options.names = true,
+                        'r' => options.real = true,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if choices > 1 {
+        fail("cannot print \"only\" of more than one choice");
+    }
+    if options.only == Only::All && (options.names || options.real) {
+        fail("cannot print only names or real IDs in default format");
+    }
+    if operands.len() > 1 {
+        fail(&format!("extra operand '{}'", operands[1]));
+    }
+
+    let mut command = IdCommand {
+        options: &options,
+        ok: true,
+    };
+    if !command.run(operands.first().map(String::as_str)) {
+        std::process::exit(1);
+    }
+}
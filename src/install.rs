@@ -0,0 +1,439 @@
+use cmds_lite::datetime::Timestamp;
+use cmds_lite::error;
+use cmds_lite::mode::Mode;
+use cmds_lite::platform;
+use cmds_lite::users;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Files and directories get rwxr-xr-x unless -m says otherwise
+const DEFAULT_MODE: u32 = 0o755;
+
+pub struct InstallOptions {
+    // -d: the operands are directories to create
+    pub directories: bool,
+    // -D: create the missing directories leading to the destination
+    pub leading: bool,
+    // -m: the mode to set instead of rwxr-xr-x
+    pub mode: Option<Mode>,
+    // -o / -g: the owner and group to set
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    // -p: give the copies the access and modification times of the sources
+    pub preserve_timestamps: bool,
+    // -s: strip symbol tables from the copies
+    pub strip: bool,
+    // --strip-program: what -s runs
+    pub strip_program: String,
+    // -t: copy every source into this directory
+    pub target_directory: Option<String>,
+    // -T: the destination is a file, even when it names a directory
+    pub no_target_directory: bool,
+    // -v: report each file copied and directory created
+    pub verbose: bool,
+}
+
+impl Default for InstallOptions {
+    fn default() -> Self {
+        InstallOptions {
+            directories: false,
+            leading: false,
+            mode: None,
+            uid: None,
+            gid: None,
+            preserve_timestamps: false,
+            strip: false,
+            strip_program: String::from("strip"),
+            target_directory: None,
+            no_target_directory: false,
+            verbose: false,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("install: {}", message);
+    std::process::exit(1);
+}
+
+struct InstallCommand<'a> {
+    options: &'a InstallOptions,
+}
+
+impl<'a> InstallCommand<'a> {
+    // The mode -m asks for, worked out from nothing like GNU install does,
+    // so `u+x` alone gives --x------
+    fn mode(&self, is_dir: bool) -> u32 {
+        match &self.options.mode {
+            Some(mode) => mode.apply(0, is_dir, 0),
+            None => DEFAULT_MODE,
+        }
+    }
+
+    // Creates `path` and any of its missing ancestors with the default
+    // mode, reporting each with -v
+    fn create_directories(&self, path: &Path) -> io::Result<()> {
+        let mut missing: Vec<&Path> = Vec::new();
+        let mut current = Some(path);
+        while let Some(dir) = current {
+            if dir.as_os_str().is_empty() || dir.is_dir() {
+                break;
+            }
+            missing.push(dir);
+            current = dir.parent();
+        }
+        for dir in missing.into_iter().rev() {
+            match fs::DirBuilder::new().mode(DEFAULT_MODE).create(dir) {
+                Ok(()) => {
+                    if self.options.verbose {
+                        println!("install: creating directory '{}'", dir.display());
+                    }
+                }
+                // Another process may have created it in the meantime
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && dir.is_dir() => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    // Sets the owner first, since changing it clears the set-ID bits the
+    // mode may be asking for
+    fn set_attributes(&self, path: &Path, mode: u32) -> Result<(), String> {
+        if self.options.uid.is_some() || self.options.gid.is_some() {
+            platform::change_owner(path, self.options.uid, self.options.gid, true).map_err(
+                |e| {
+                    format!(
+                        "cannot change ownership of '{}': {}",
+                        path.display(),
+                        error::message(&e)
+                    )
+                },
+            )?;
+        }
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|e| {
+            format!(
+                "cannot change permissions of '{}': {}",
+                path.display(),
+                error::message(&e)
+            )
+        })
+    }
+
+    fn install_directory(&self, name: &str) -> Result<(), String> {
+        let path = Path::new(name);
+        self.create_directories(path)
+            .map_err(|e| format!("cannot create directory '{}': {}", name, error::message(&e)))?;
+        self.set_attributes(path, self.mode(true))
+    }
+
+    fn strip(&self, path: &Path) -> Result<(), String> {
+        let status = Command::new(&self.options.strip_program)
+            .arg(path)
+            .status()
+            .map_err(|e| {
+                format!(
+                    "cannot run '{}': {}",
+                    self.options.strip_program,
+                    error::message(&e)
+                )
+            })?;
+        if !status.success() {
+            return Err(String::from("strip process terminated abnormally"));
+        }
+        Ok(())
+    }
+
+    // Copies `source` to `dest`. An existing destination is removed first
+    // rather than written over, so a program that is running can be
+    // replaced, and the new file is private until its mode is set.
+    fn copy(&self, source: &str, dest: &Path) -> Result<(), String> {
+        let metadata = fs::metadata(source)
+            .map_err(|e| format!("cannot stat '{}': {}", source, error::message(&e)))?;
+        if metadata.is_dir() {
+            return Err(format!("omitting directory '{}'", source));
+        }
+        if let Ok(existing) = fs::metadata(dest) {
+            if existing.dev() == metadata.dev() && existing.ino() == metadata.ino() {
+                return Err(format!(
+                    "'{}' and '{}' are the same file",
+                    source,
+                    dest.display()
+                ));
+            }
+            if existing.is_dir() {
+                return Err(format!(
+                    "cannot overwrite directory '{}' with non-directory",
+                    dest.display()
+                ));
+            }
+        }
+
+        let mut input = File::open(source).map_err(|e| {
+            format!(
+                "cannot open '{}' for reading: {}",
+                source,
+                error::message(&e)
+            )
+        })?;
+        match fs::remove_file(dest) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                return Err(format!(
+                    "cannot remove '{}': {}",
+                    dest.display(),
+                    error::message(&e)
+                ))
+            }
+            _ => {}
+        }
+        let mut output = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(dest)
+            .map_err(|e| {
+                format!(
+                    "cannot create regular file '{}': {}",
+                    dest.display(),
+                    error::message(&e)
+                )
+            })?;
+        io::copy(&mut input, &mut output)
+            .map_err(|e| format!("error copying '{}': {}", source, error::message(&e)))?;
+        drop(output);
+
+        if self.options.strip {
+            if let Err(message) = self.strip(dest) {
+                let _ = fs::remove_file(dest);
+                return Err(message);
+            }
+        }
+        self.set_attributes(dest, self.mode(false))?;
+        if self.options.preserve_timestamps {
+            let atime = Timestamp::new(metadata.atime(), metadata.atime_nsec() as u32);
+            let mtime = Timestamp::new(metadata.mtime(), metadata.mtime_nsec() as u32);
+            platform::set_times(dest, atime, mtime, true).map_err(|e| {
+                format!(
+                    "failed to preserve times for '{}': {}",
+                    dest.display(),
+                    error::message(&e)
+                )
+            })?;
+        }
+        if self.options.verbose {
+            println!("'{}' -> '{}'", source, dest.display());
+        }
+        Ok(())
+    }
+
+    fn install_file(&self, source: &str, dest: &Path) -> Result<(), String> {
+        if self.options.leading {
+            if let Some(parent) = dest.parent() {
+                self.create_directories(parent).map_err(|e| {
+                    format!(
+                        "cannot create directory '{}': {}",
+                        parent.display(),
+                        error::message(&e)
+                    )
+                })?;
+            }
+        }
+        self.copy(source, dest)
+    }
+
+    fn install_into(&self, sources: &[String], dir: &str) -> bool {
+        if self.options.leading {
+            if let Err(e) = self.create_directories(Path::new(dir)) {
+                eprintln!(
+                    "install: cannot create directory '{}': {}",
+                    dir,
+                    error::message(&e)
+                );
+                return false;
+            }
+        }
+        match fs::metadata(dir) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => {
+                eprintln!("install: target '{}' is not a directory", dir);
+                return false;
+            }
+            Err(e) => {
+                eprintln!(
+                    "install: failed to access '{}': {}",
+                    dir,
+                    error::message(&e)
+                );
+                return false;
+            }
+        }
+
+        let mut ok = true;
+        for source in sources {
+            let name = Path::new(source)
+                .file_name()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(source));
+            if let Err(message) = self.copy(source, &Path::new(dir).join(name)) {
+                eprintln!("install: {}", message);
+                ok = false;
+            }
+        }
+        ok
+    }
+
+    fn run(&self, operands: &[String]) -> bool {
+        if self.options.directories {
+            let mut ok = true;
+            for dir in operands {
+                if let Err(message) = self.install_directory(dir) {
+                    eprintln!("install: {}", message);
+                    ok = false;
+                }
+            }
+            return ok;
+        }
+
+        if let Some(dir) = &self.options.target_directory {
+            return self.install_into(operands, dir);
+        }
+        let (dest, sources) = operands.split_last().unwrap_or_else(|| unreachable!());
+        if !self.options.no_target_directory && (sources.len() > 1 || Path::new(dest).is_dir()) {
+            return self.install_into(sources, dest);
+        }
+        match self.install_file(&sources[0], Path::new(dest)) {
+            Ok(()) => true,
+            Err(message) => {
+                eprintln!("install: {}", message);
+                false
+            }
+        }
+    }
+}
+
+// Stores the value of -m, -o, -g, -t or --strip-program
+fn set_value(options: &mut InstallOptions, flag: char, value: String) {
+    match flag {
+        'm' => options.mode = Some(Mode::parse(&value).unwrap_or_else(|e| fail(&e.to_string()))),
+        'o' => {
+            let user = users::parse_user(&value)
+                .unwrap_or_else(|| fail(&format!("invalid user '{}'", value)));
+            options.uid = Some(user.uid);
+        }
+        'g' => {
+            let gid = users::parse_group(&value)
+                .unwrap_or_else(|| fail(&format!("invalid group '{}'", value)));
+            options.gid = Some(gid);
+        }
+        't' => options.target_directory = Some(value),
+        _ => options.strip_program = value,
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = InstallOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--directory" => options.directories = true,
+            "--preserve-timestamps" => options.preserve_timestamps = true,
+            "--strip" => options.strip = true,
+            "--no-target-directory" => options.no_target_directory = true,
+            "--verbose" => options.verbose = true,
+            _ if arg.starts_with("--") => {
+                let (name, value) = match arg.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (arg.as_str(), None),
+                };
+                let flag = match name {
+                    "--mode" => 'm',
+                    "--owner" => 'o',
+                    "--group" => 'g',
+                    "--target-directory" => 't',
+                    "--strip-program" => 's',
+                    _ => fail(&format!("unrecognized option '{}'", arg)),
+                };
+                let value = match value {
+                    Some(value) => value,
+                    None if i < args.len() => {
+                        i += 1;
+                        args[i - 1].clone()
+                    }
+                    None => fail(&format!("option '{}' requires an argument", name)),
+                };
+                set_value(&mut options, flag, value);
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'd' => options.directories = true,
+                        'D' => options.leading = true,
+                        'p' => options.preserve_timestamps = true,
+                        's' => options.strip = true,
+                        'T' => options.no_target_directory = true,
+                        'v' => options.verbose = true,
+                        // Accepted for compatibility; copying is all install does
+                        'c' => {}
+                        'm' | 'o' | 'g' | 't' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            set_value(&mut options, flag, value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if options.directories && options.target_directory.is_some() {
+        fail("target directory not allowed when installing a directory");
+    }
+    if options.target_directory.is_some() && options.no_target_directory {
+        fail("cannot combine --target-directory (-t) and --no-target-directory (-T)");
+    }
+    if operands.is_empty() {
+        fail("missing file operand");
+    }
+    if !options.directories && options.target_directory.is_none() {
+        if operands.len() == 1 {
+            fail(&format!(
+                "missing destination file operand after '{}'",
+                operands[0]
+            ));
+        }
+        if options.no_target_directory && operands.len() > 2 {
+            fail(&format!("extra operand '{}'", operands[2]));
+        }
+    }
+
+    let command = InstallCommand { options: &options };
+    if !command.run(&operands) {
+        std::process::exit(1);
+    }
+}
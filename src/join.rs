@@ -0,0 +1,527 @@
+use cmds_lite::collate::{self, Collator};
+use cmds_lite::error;
+use std::cmp::Ordering;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    // Fields are separated by runs of blanks, ignoring leading ones, and
+    // output with a single space
+    Blanks,
+    // -t CHAR: every CHAR separates two fields
+    Char(u8),
+    // -t '': the whole line is one field
+    WholeLine,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OrderCheck {
+    // Complain about unsorted input once unpairable lines have been seen
+    Default,
+    // --check-order: unsorted input is fatal
+    Enabled,
+    // --nocheck-order
+    Disabled,
+}
+
+#[derive(Clone, Copy)]
+pub enum OutputField {
+    // 0: the join field
+    Join,
+    // FILE.FIELD, both counted from 0
+    Field(usize, usize),
+}
+
+pub enum Format {
+    // Each file's lines have as many fields as its first line
+    Auto,
+    Fields(Vec<OutputField>),
+}
+
+pub struct JoinOptions {
+    // -1, -2: the join field of each file, counted from 0
+    pub fields: [usize; 2],
+    pub separator: Separator,
+    // -a: also print the unpairable lines of these files
+    pub unpaired: [bool; 2],
+    // -v: print only unpairable lines
+    pub only_unpaired: bool,
+    // -e: replaces empty fields, and missing ones in -o output
+    pub empty: String,
+    pub format: Option<Format>,
+    pub ignore_case: bool,
+    // -z: lines end in NUL instead of newline
+    pub zero_terminated: bool,
+    pub order_check: OrderCheck,
+}
+
+impl Default for JoinOptions {
+    fn default() -> Self {
+        JoinOptions {
+            fields: [0, 0],
+            separator: Separator::Blanks,
+            unpaired: [false, false],
+            only_unpaired: false,
+            empty: String::new(),
+            format: None,
+            ignore_case: false,
+            zero_terminated: false,
+            order_check: OrderCheck::Default,
+        }
+    }
+}
+
+// Writing fails with the error itself, so a closed pipe can exit quietly
+pub enum Failure {
+    Write(io::Error),
+    Other(String),
+}
+
+// An input line and where each of its fields is
+struct Line {
+    text: Vec<u8>,
+    fields: Vec<(usize, usize)>,
+}
+
+impl Line {
+    fn new(text: Vec<u8>, separator: Separator) -> Self {
+        let mut fields = Vec::new();
+        match separator {
+            Separator::WholeLine => fields.push((0, text.len())),
+            Separator::Char(delimiter) => {
+                if !text.is_empty() {
+                    let mut start = 0;
+                    for (i, &byte) in text.iter().enumerate() {
+                        if byte == delimiter {
+                            fields.push((start, i));
+                            start = i + 1;
+                        }
+                    }
+                    fields.push((start, text.len()));
+                }
+            }
+            Separator::Blanks => {
+                // With -z a line can hold newlines, which separate fields too
+                let blank = |byte: u8| collate::is_blank(byte) || byte == b'\n';
+                let mut pos = 0;
+                while pos < text.len() && blank(text[pos]) {
+                    pos += 1;
+                }
+                // A separator at the end leaves an empty last field
+                while pos < text.len() {
+                    let start = pos;
+                    while pos < text.len() && !blank(text[pos]) {
+                        pos += 1;
+                    }
+                    fields.push((start, pos));
+                    if pos == text.len() {
+                        break;
+                    }
+                    while pos < text.len() && blank(text[pos]) {
+                        pos += 1;
+                    }
+                    if pos == text.len() {
+                        fields.push((pos, pos));
+                    }
+                }
+            }
+        }
+        Line { text, fields }
+    }
+
+    fn field(&self, index: usize) -> Option<&[u8]> {
+        self.fields
+            .get(index)
+            .map(|&(start, end)| &self.text[start..end])
+    }
+}
+
+struct Input {
+    name: String,
+    reader: Box<dyn BufRead>,
+    line_number: u64,
+    // The key of the line read last, to check the order against
+    previous: Option<Vec<u8>>,
+    warned: bool,
+}
+
+struct JoinCommand<'a> {
+    options: &'a JoinOptions,
+    collator: Collator,
+    inputs: [Input; 2],
+    seen_unpairable: bool,
+    // Field counts of the first lines, for -o auto
+    auto_counts: [usize; 2],
+}
+
+impl<'a> JoinCommand<'a> {
+    fn key<'l>(&self, line: &'l Line, file: usize) -> &'l [u8] {
+        line.field(self.options.fields[file]).unwrap_or(b"")
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        if self.options.ignore_case {
+            self.collator.compare_folded(a, b)
+        } else {
+            self.collator.compare(a, b)
+        }
+    }
+
+    // Reads the next line of a file, checking that it does not sort
+    // before the previous one
+    fn advance(&mut self, file: usize) -> Result<Option<Line>, Failure> {
+        let input = &mut self.inputs[file];
+        let terminator = if self.options.zero_terminated {
+            0
+        } else {
+            b'\n'
+        };
+        let mut text = Vec::new();
+        let read = input
+            .reader
+            .read_until(terminator, &mut text)
+            .map_err(|e| Failure::Other(format!("{}: {}", input.name, error::message(&e))))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if text.last() == Some(&terminator) {
+            text.pop();
+        }
+        input.line_number += 1;
+        let line = Line::new(text, self.options.separator);
+        let key = self.key(&line, file).to_vec();
+
+        let check = match self.options.order_check {
+            OrderCheck::Enabled => true,
+            OrderCheck::Default => self.seen_unpairable,
+            OrderCheck::Disabled => false,
+        };
+        let previous = self.inputs[file].previous.replace(key.clone());
+        if check && !self.inputs[file].warned {
+            if let Some(previous) = previous {
+                if self.compare(&previous, &key) == Ordering::Greater {
+                    let input = &mut self.inputs[file];
+                    let message = format!(
+                        "{}:{}: is not sorted: {}",
+                        input.name,
+                        input.line_number,
+                        String::from_utf8_lossy(&line.text)
+                    );
+                    if self.options.order_check == OrderCheck::Enabled {
+                        return Err(Failure::Other(message));
+                    }
+                    eprintln!("join: {}", message);
+                    input.warned = true;
+                }
+            }
+        }
+        Ok(Some(line))
+    }
+
+    fn write_line(
+        &self,
+        first: Option<&Line>,
+        second: Option<&Line>,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let separator: &[u8] = match self.options.separator {
+            Separator::Char(delimiter) => &[delimiter],
+            _ => b" ",
+        };
+        let lines = [first, second];
+        let join_field = first
+            .map(|line| self.key(line, 0))
+            .or_else(|| second.map(|line| self.key(line, 1)))
+            .unwrap_or(b"");
+
+        let output_field = |field: &OutputField| match *field {
+            OutputField::Join => join_field,
+            OutputField::Field(file, index) => lines[file]
+                .and_then(|line| line.field(index))
+                .unwrap_or(b""),
+        };
+
+        let items: Vec<&[u8]> = match &self.options.format {
+            Some(Format::Fields(fields)) => fields.iter().map(output_field).collect(),
+            Some(Format::Auto) => {
+                let mut fields = vec![OutputField::Join];
+                for file in 0..2 {
+                    for index in 0..self.auto_counts[file] {
+                        if index != self.options.fields[file] {
+                            fields.push(OutputField::Field(file, index));
+                        }
+                    }
+                }
+                fields.iter().map(output_field).collect()
+            }
+            // The join field, then the other fields of each line present
+            None => {
+                let mut items = vec![join_field];
+                for (file, line) in lines.iter().enumerate() {
+                    let Some(line) = line else { continue };
+                    for index in 0..line.fields.len() {
+                        if index != self.options.fields[file] {
+                            items.push(line.field(index).unwrap_or(b""));
+                        }
+                    }
+                }
+                items
+            }
+        };
+
+        let empty = self.options.empty.as_bytes();
+        let items: Vec<&[u8]> = items
+            .into_iter()
+            .map(|item| if item.is_empty() { empty } else { item })
+            .collect();
+        out.write_all(&items.join(separator))?;
+        out.write_all(if self.options.zero_terminated {
+            b"\0"
+        } else {
+            b"\n"
+        })
+    }
+
+    fn unpairable(&mut self, file: usize, line: &Line, out: &mut impl Write) -> io::Result<()> {
+        self.seen_unpairable = true;
+        if !self.options.unpaired[file] {
+            return Ok(());
+        }
+        if file == 0 {
+            self.write_line(Some(line), None, out)
+        } else {
+            self.write_line(None, Some(line), out)
+        }
+    }
+
+    // Reads the lines that follow `first` with the same key
+    fn group(&mut self, file: usize, first: Line) -> Result<(Vec<Line>, Option<Line>), Failure> {
+        let mut group = vec![first];
+        loop {
+            let next = self.advance(file)?;
+            match next {
+                Some(line)
+                    if self.compare(self.key(&line, file), self.key(&group[0], file))
+                        == Ordering::Equal =>
+                {
+                    group.push(line)
+                }
+                other => return Ok((group, other)),
+            }
+        }
+    }
+
+    fn run(&mut self, out: &mut impl Write) -> Result<(), Failure> {
+        let mut current = [self.advance(0)?, self.advance(1)?];
+        self.auto_counts = [0, 1].map(|file| current[file].as_ref().map_or(0, |l| l.fields.len()));
+
+        while let (Some(first), Some(second)) = (&current[0], &current[1]) {
+            let order = self.compare(self.key(first, 0), self.key(second, 1));
+            match order {
+                Ordering::Less | Ordering::Greater => {
+                    let file = if order == Ordering::Less { 0 } else { 1 };
+                    if let Some(line) = current[file].take() {
+                        self.unpairable(file, &line, out).map_err(Failure::Write)?;
+                    }
+                    current[file] = self.advance(file)?;
+                }
+                Ordering::Equal => {
+                    let (Some(first), Some(second)) = (current[0].take(), current[1].take()) else {
+                        break;
+                    };
+                    let (firsts, next_first) = self.group(0, first)?;
+                    let (seconds, next_second) = self.group(1, second)?;
+                    if !self.options.only_unpaired {
+                        for first in &firsts {
+                            for second in &seconds {
+                                self.write_line(Some(first), Some(second), out)
+                                    .map_err(Failure::Write)?;
+                            }
+                        }
+                    }
+                    current = [next_first, next_second];
+                }
+            }
+        }
+
+        // Whatever is left of either file has nothing to pair with
+        for (file, mut line) in current.into_iter().enumerate() {
+            while let Some(unpaired) = line {
+                self.unpairable(file, &unpaired, out)
+                    .map_err(Failure::Write)?;
+                line = self.advance(file)?;
+            }
+        }
+
+        out.flush().map_err(Failure::Write)?;
+        if self.inputs.iter().any(|input| input.warned) {
+            return Err(Failure::Other(String::from("input is not in sorted order")));
+        }
+        Ok(())
+    }
+}
+
+fn open(name: &str) -> Result<Input, String> {
+    let reader: Box<dyn BufRead> = if name == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        let file = File::open(name).map_err(|e| format!("{}: {}", name, error::message(&e)))?;
+        Box::new(BufReader::new(file))
+    };
+    Ok(Input {
+        name: name.to_string(),
+        reader,
+        line_number: 0,
+        previous: None,
+        warned: false,
+    })
+}
+
+pub fn run(first: &str, second: &str, options: &JoinOptions) -> Result<(), Failure> {
+    let mut command = JoinCommand {
+        options,
+        collator: Collator::from_environment(),
+        inputs: [
+            open(first).map_err(Failure::Other)?,
+            open(second).map_err(Failure::Other)?,
+        ],
+        seen_unpairable: false,
+        auto_counts: [0, 0],
+    };
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    command.run(&mut out)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("join: {}", message);
+    std::process::exit(1);
+}
+
+fn field_number(value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(n) if n >= 1 => n - 1,
+        _ => fail(&format!("invalid field number: '{}'", value)),
+    }
+}
+
+fn file_number(value: &str) -> usize {
+    match value {
+        "1" => 0,
+        "2" => 1,
+        _ => fail(&format!("invalid field number: '{}'", value)),
+    }
+}
+
+// -o: 0 or FILE.FIELD items separated by commas or blanks, or `auto`
+fn parse_format(list: &str, format: &mut Option<Format>) {
+    if list == "auto" {
+        *format = Some(Format::Auto);
+        return;
+    }
+    let mut fields = match format.take() {
+        Some(Format::Fields(fields)) => fields,
+        _ => Vec::new(),
+    };
+    for item in list.split([',', ' ', '\t']).filter(|item| !item.is_empty()) {
+        if item == "0" {
+            fields.push(OutputField::Join);
+            continue;
+        }
+        let (file, field) = match item.split_once('.') {
+            Some(("1", field)) => (0, field),
+            Some(("2", field)) => (1, field),
+            _ => fail(&format!("invalid file number in field spec: '{}'", item)),
+        };
+        fields.push(OutputField::Field(file, field_number(field)));
+    }
+    *format = Some(Format::Fields(fields));
+}
+
+fn set_option(options: &mut JoinOptions, option: char, value: &str) {
+    match option {
+        '1' => options.fields[0] = field_number(value),
+        '2' => options.fields[1] = field_number(value),
+        'j' => options.fields = [field_number(value); 2],
+        'a' => options.unpaired[file_number(value)] = true,
+        'v' => {
+            options.unpaired[file_number(value)] = true;
+            options.only_unpaired = true;
+        }
+        'e' => options.empty = value.to_string(),
+        'o' => parse_format(value, &mut options.format),
+        _ => {
+            options.separator = match value.as_bytes() {
+                [] => Separator::WholeLine,
+                b"\\0" => Separator::Char(0),
+                [delimiter] => Separator::Char(*delimiter),
+                _ => fail(&format!("multi-character tab '{}'", value)),
+            }
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = JoinOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--ignore-case" => options.ignore_case = true,
+            "--zero-terminated" => options.zero_terminated = true,
+            "--check-order" => options.order_check = OrderCheck::Enabled,
+            "--nocheck-order" => options.order_check = OrderCheck::Disabled,
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'i' => options.ignore_case = true,
+                        'z' => options.zero_terminated = true,
+                        '1' | '2' | 'j' | 'a' | 'v' | 'e' | 'o' | 't' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            set_option(&mut options, flag, &value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    match files.len() {
+        0 => fail("missing operand"),
+        1 => fail(&format!("missing operand after '{}'", files[0])),
+        2 => {}
+        _ => fail(&format!("extra operand '{}'", files[2])),
+    }
+
+    match run(&files[0], &files[1], &options) {
+        Ok(()) => {}
+        Err(Failure::Write(e)) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(1),
+        Err(Failure::Write(e)) => fail(&format!("write error: {}", error::message(&e))),
+        Err(Failure::Other(message)) => fail(&message),
+    }
+}
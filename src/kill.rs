@@ -0,0 +1,187 @@
+use cmds_lite::error;
+use cmds_lite::signal::{self, Signal};
+use std::env;
+use std::io::{self, BufWriter, Write};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    // Send the signal to the process IDs
+    Send,
+    // -l: print signal names, or convert the operands between names and
+    // numbers
+    List,
+    // -t: print a table of numbers, names and descriptions
+    Table,
+}
+
+pub struct KillOptions {
+    pub action: Action,
+    // -s: the signal to send; None is signal 0, which only checks that the
+    // processes exist
+    pub signal: Option<Signal>,
+}
+
+impl Default for KillOptions {
+    fn default() -> Self {
+        KillOptions {
+            action: Action::Send,
+            signal: Some(Signal::TERM),
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("kill: {}", message);
+    std::process::exit(1);
+}
+
+// A signal given by name or number; 0 is allowed and sends nothing
+fn parse_signal(text: &str) -> Option<Option<Signal>> {
+    if text == "0" || text.eq_ignore_ascii_case("EXIT") {
+        return Some(None);
+    }
+    Signal::from_name(text).map(Some)
+}
+
+fn signal_or_fail(text: &str) -> Option<Signal> {
+    parse_signal(text).unwrap_or_else(|| fail(&format!("'{}': invalid signal", text)))
+}
+
+// -l with operands: names become numbers and numbers names, where numbers
+// above 128 are taken as exit statuses of processes killed by the signal
+fn convert(operands: &[String]) -> bool {
+    let mut ok = true;
+    for operand in operands {
+        let converted = match operand.parse::<i32>() {
+            Ok(number) => {
+                let number = if number > 128 { number - 128 } else { number };
+                match number {
+                    0 => Some(String::from("EXIT")),
+                    _ => Signal::from_number(number).map(|signal| signal.name().to_string()),
+                }
+            }
+            Err(_) => {
+                parse_signal(operand).map(|signal| signal.map_or(0, Signal::number).to_string())
+            }
+        };
+        match converted {
+            Some(text) => println!("{}", text),
+            None => {
+                eprintln!("kill: '{}': invalid signal", operand);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+fn list(table: bool, out: &mut impl Write) -> io::Result<()> {
+    let width = Signal::all()
+        .map(|signal| signal.name().len())
+        .max()
+        .unwrap_or(0);
+    for signal in Signal::all() {
+        if table {
+            writeln!(
+                out,
+                "{:2} {:<width$} {}",
+                signal.number(),
+                signal.name(),
+                signal.description()
+            )?;
+        } else {
+            writeln!(out, "{}", signal.name())?;
+        }
+    }
+    out.flush()
+}
+
+// Sends the signal to each process ID, negative ones meaning process
+// groups; true if every one was signalled
+fn send(signal: Option<Signal>, pids: &[String]) -> bool {
+    let mut ok = true;
+    for text in pids {
+        let pid = match text.parse::<i32>() {
+            Ok(pid) => pid,
+            Err(_) => {
+                eprintln!("kill: '{}': invalid process id", text);
+                ok = false;
+                continue;
+            }
+        };
+        if let Err(e) = signal::send(pid, signal) {
+            eprintln!("kill: {}: {}", text, error::message(&e));
+            ok = false;
+        }
+    }
+    ok
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = KillOptions::default();
+    let mut operands = Vec::new();
+    let mut signal_given = false;
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        // Once a signal is chosen, "-123" is a process group, not an option
+        if !end_of_options && arg == "--" {
+            end_of_options = true;
+            continue;
+        }
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 || signal_given {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "-l" | "--list" => options.action = Action::List,
+            "-t" | "-L" | "--table" => options.action = Action::Table,
+            "-s" | "--signal" => {
+                if i == args.len() {
+                    fail(&format!("option requires an argument -- '{}'", &arg[1..]));
+                }
+                i += 1;
+                options.signal = signal_or_fail(&args[i - 1]);
+                signal_given = true;
+            }
+            _ if arg.starts_with("--signal=") => {
+                options.signal = signal_or_fail(&arg["--signal=".len()..]);
+                signal_given = true;
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ if arg.starts_with("-s") => {
+                options.signal = signal_or_fail(&arg[2..]);
+                signal_given = true;
+            }
+            // -9, -KILL, -SIGKILL
+            _ => {
+                options.signal = signal_or_fail(&arg[1..]);
+                signal_given = true;
+            }
+        }
+    }
+
+    let ok = match options.action {
+        Action::List | Action::Table if signal_given => fail("cannot combine signal with -l or -t"),
+        Action::List if !operands.is_empty() => convert(&operands),
+        Action::Table if !operands.is_empty() => convert(&operands),
+        Action::List | Action::Table => {
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            match list(options.action == Action::Table, &mut out) {
+                Err(e) if e.kind() != io::ErrorKind::BrokenPipe => fail(&e.to_string()),
+                _ => true,
+            }
+        }
+        Action::Send if operands.is_empty() => fail("no process ID specified"),
+        Action::Send => send(options.signal, &operands),
+    };
+    if !ok {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,37 @@
+pub mod archive;
+pub mod checksum;
+pub mod collate;
+pub mod color;
+pub mod compress;
+pub mod copy;
+pub mod crc;
+pub mod datetime;
+pub mod deflate;
+pub mod dump;
+pub mod duration;
+pub mod error;
+pub mod format;
+pub mod glob;
+pub mod gz;
+pub mod hash;
+pub mod mode;
+pub mod mounts;
+pub mod numbering;
+pub mod ownership;
+pub mod pathname;
+pub mod platform;
+pub mod proc;
+pub mod progress;
+pub mod random;
+pub mod regex;
+pub mod resolve;
+pub mod signal;
+pub mod size;
+pub mod tabs;
+pub mod trailing;
+pub mod tty;
+pub mod unicode;
+pub mod users;
+pub mod walk;
+pub mod wrap;
+pub mod xattr;
@@ -0,0 +1,44 @@
+use cmds_lite::error;
+use std::env;
+use std::fs;
+
+fn fail(message: &str) -> ! {
+    eprintln!("link: {}", message);
+    std::process::exit(1);
+}
+
+// A bare link(2): exactly two operands, no options, and the new name must
+// not exist. A symlink given as FILE is linked itself, not its target.
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            operands.push(arg.as_str());
+        } else if arg == "--" {
+            end_of_options = true;
+        } else if arg.starts_with("--") {
+            fail(&format!("unrecognized option '{}'", arg));
+        } else {
+            fail(&format!("invalid option -- '{}'", &arg[1..2]));
+        }
+    }
+
+    match operands.as_slice() {
+        [] => fail("missing operand"),
+        [file] => fail(&format!("missing operand after '{}'", file)),
+        [file, new_name] => {
+            if let Err(e) = fs::hard_link(file, new_name) {
+                fail(&format!(
+                    "cannot create link '{}' to '{}': {}",
+                    new_name,
+                    file,
+                    error::message(&e)
+                ));
+            }
+        }
+        [_, _, extra, ..] => fail(&format!("extra operand '{}'", extra)),
+    }
+}
@@ -0,0 +1,239 @@
+use cmds_lite::error;
+use cmds_lite::pathname;
+use cmds_lite::resolve::{self, Existence};
+use std::env;
+use std::fs;
+use std::io;
+use std::os::unix::fs::{symlink, MetadataExt};
+use std::path::{Path, PathBuf};
+
+#[derive(Default)]
+pub struct LnOptions {
+    pub symbolic: bool,
+    pub force: bool,
+    // -n: a destination that is a symlink to a directory is replaced, not
+    // linked into
+    pub no_dereference: bool,
+    pub relative: bool,
+    pub verbose: bool,
+}
+
+struct LnCommand<'a> {
+    options: &'a LnOptions,
+}
+
+impl<'a> LnCommand<'a> {
+    fn new(options: &'a LnOptions) -> Self {
+        LnCommand { options }
+    }
+
+    fn run(&self, operands: &[String]) -> bool {
+        let (targets, last) = operands.split_at(operands.len() - 1);
+        let last = &last[0];
+
+        // ln TARGET links into the current directory
+        if targets.is_empty() {
+            return self.link(last, &Path::new(".").join(pathname::base_name(last)));
+        }
+        if targets.len() == 1 && !self.is_directory(last) {
+            return self.link(&targets[0], Path::new(last));
+        }
+
+        if !self.is_directory(last) {
+            match fs::metadata(last) {
+                Ok(_) => eprintln!("ln: target '{}': Not a directory", last),
+                Err(e) => eprintln!("ln: target '{}': {}", last, error::message(&e)),
+            }
+            return false;
+        }
+
+        let mut ok = true;
+        for target in targets {
+            let destination = Path::new(last).join(pathname::base_name(target));
+            ok &= self.link(target, &destination);
+        }
+        ok
+    }
+
+    fn is_directory(&self, path: &str) -> bool {
+        let metadata = if self.options.no_dereference {
+            fs::symlink_metadata(path)
+        } else {
+            fs::metadata(path)
+        };
+        metadata.map(|m| m.is_dir()).unwrap_or(false)
+    }
+
+    fn link(&self, target: &str, destination: &Path) -> bool {
+        let source = if self.options.symbolic {
+            None
+        } else {
+            match fs::symlink_metadata(target) {
+                Ok(metadata) if metadata.is_dir() => {
+                    eprintln!("ln: {}: hard link not allowed for directory", target);
+                    return false;
+                }
+                Ok(metadata) => Some(metadata),
+                Err(e) => {
+                    eprintln!("ln: failed to access '{}': {}", target, error::message(&e));
+                    return false;
+                }
+            }
+        };
+
+        if self.options.force {
+            if let Ok(existing) = fs::symlink_metadata(destination) {
+                if existing.is_dir() {
+                    eprintln!("ln: {}: cannot overwrite directory", destination.display());
+                    return false;
+                }
+                if let Some(source) = &source {
+                    if source.dev() == existing.dev() && source.ino() == existing.ino() {
+                        eprintln!(
+                            "ln: '{}' and '{}' are the same file",
+                            target,
+                            destination.display()
+                        );
+                        return false;
+                    }
+                }
+                if let Err(e) = fs::remove_file(destination) {
+                    eprintln!(
+                        "ln: cannot remove '{}': {}",
+                        destination.display(),
+                        error::message(&e)
+                    );
+                    return false;
+                }
+            }
+        }
+
+        let value = if self.options.relative {
+            relative_target(target, destination)
+        } else {
+            PathBuf::from(target)
+        };
+
+        let result = if self.options.symbolic {
+            symlink(&value, destination)
+        } else {
+            fs::hard_link(target, destination)
+        };
+        if let Err(e) = result {
+            self.report_failure(target, destination, &e);
+            return false;
+        }
+
+        if self.options.verbose {
+            let arrow = if self.options.symbolic { "->" } else { "=>" };
+            println!(
+                "'{}' {} '{}'",
+                destination.display(),
+                arrow,
+                value.display()
+            );
+        }
+        true
+    }
+
+    fn report_failure(&self, target: &str, destination: &Path, e: &io::Error) {
+        let kind = if self.options.symbolic {
+            "symbolic"
+        } else {
+            "hard"
+        };
+        // Like GNU ln, name the target too when the error may be about it
+        let about_destination = self.options.symbolic
+            || matches!(
+                e.raw_os_error(),
+                Some(libc::EEXIST | libc::EDQUOT | libc::ENOSPC | libc::EROFS)
+            );
+        if about_destination {
+            eprintln!(
+                "ln: failed to create {} link '{}': {}",
+                kind,
+                destination.display(),
+                error::message(e)
+            );
+        } else {
+            eprintln!(
+                "ln: failed to create {} link '{}' => '{}': {}",
+                kind,
+                destination.display(),
+                target,
+                error::message(e)
+            );
+        }
+    }
+}
+
+// -r: the target as seen from the directory the link is created in
+fn relative_target(target: &str, destination: &Path) -> PathBuf {
+    let directory = pathname::dir_name(destination.to_str().unwrap_or("."));
+    let base = resolve::canonicalize(Path::new(directory), Existence::Nothing);
+    let target_path = resolve::canonicalize(Path::new(target), Existence::Nothing);
+    match (base, target_path) {
+        (Ok(base), Ok(target_path)) => resolve::relative_path(&base, &target_path),
+        _ => PathBuf::from(target),
+    }
+}
+
+pub fn run(operands: &[String], options: &LnOptions) -> bool {
+    let command = LnCommand::new(options);
+    command.run(operands)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = LnOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--symbolic" => options.symbolic = true,
+            "--force" => options.force = true,
+            "--no-dereference" => options.no_dereference = true,
+            "--relative" => options.relative = true,
+            "--verbose" => options.verbose = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("ln: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        's' => options.symbolic = true,
+                        'f' => options.force = true,
+                        'n' => options.no_dereference = true,
+                        'r' => options.relative = true,
+                        'v' => options.verbose = true,
+                        _ => {
+                            eprintln!("ln: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if operands.is_empty() {
+        eprintln!("ln: missing file operand");
+        std::process::exit(1);
+    }
+    if options.relative && !options.symbolic {
+        eprintln!("ln: cannot do --relative without --symbolic");
+        std::process::exit(1);
+    }
+
+    if !run(&operands, &options) {
+        std::process::exit(1);
+    }
+}
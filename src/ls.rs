@@ -2,21 +2,20 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::io;
 use std::time::SystemTime;
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
 use std::env;
+use cmds_lite::size::BlockSize;
+use cmds_lite::xattr;
 
+#[derive(Default)]
 pub struct LsOptions {
     pub show_hidden: bool,
     pub long_format: bool,
-}
-
-impl Default for LsOptions {
-    fn default() -> Self {
-        LsOptions {
-            show_hidden: false,
-            long_format: false,
-        }
-    }
+    // --extended: list the extended attributes of each entry in long format
+    pub extended: bool,
+    // --block-size: the unit sizes and the total are shown in; sizes are
+    // in bytes and the total in kilobytes when unset
+    pub block_size: Option<BlockSize>,
 }
 
 pub struct FileEntry {
@@ -45,6 +44,11 @@ impl FileEntry {
         self.metadata.len()
     }
 
+    // Bytes of disk actually allocated, which is what the total counts
+    fn allocated(&self) -> u64 {
+        self.metadata.blocks() * 512
+    }
+
     fn modified_timestamp(&self) -> u64 {
         self.metadata
             .modified()
@@ -57,6 +61,24 @@ impl FileEntry {
     fn permissions(&self) -> u32 {
         self.metadata.permissions().mode()
     }
+
+    fn attributes(&self) -> Vec<String> {
+        xattr::names(&self.path).unwrap_or_default()
+    }
+
+    // '+' for an entry with an ACL and '@' for one with other extended
+    // attributes. SELinux labels are on every file where SELinux is in
+    // use, so they do not count.
+    fn attribute_marker(&self) -> Option<char> {
+        let names = self.attributes();
+        if names.iter().any(|name| xattr::is_acl(name)) {
+            Some('+')
+        } else if names.iter().any(|name| name != "security.selinux") {
+            Some('@')
+        } else {
+            None
+        }
+    }
 }
 
 trait EntryFilter {
@@ -89,14 +111,20 @@ impl EntryFormatter for SimpleFormatter {
     }
 }
 
-struct LongFormatter;
+struct LongFormatter {
+    // Whether any entry listed has an attribute marker; the others then
+    // get a space in its place, so the columns stay aligned
+    markers: bool,
+    extended: bool,
+    block_size: Option<BlockSize>,
+}
 
 impl EntryFormatter for LongFormatter {
     fn format(&self, entry: &FileEntry) -> String {
         let mode = entry.permissions();
         let file_type = if entry.is_dir() { "d" } else { "-" };
         
-        let permissions = format!(
+        let mut permissions = format!(
             "{}{}{}{}{}{}{}{}{}{}",
             file_type,
             if (mode & 0o400) != 0 { "r" } else { "-" },
@@ -109,14 +137,30 @@ impl EntryFormatter for LongFormatter {
             if (mode & 0o002) != 0 { "w" } else { "-" },
             if (mode & 0o001) != 0 { "x" } else { "-" }
         );
+        if self.markers {
+            permissions.push(entry.attribute_marker().unwrap_or(' '));
+        }
         
-        format!(
+        let size = match &self.block_size {
+            Some(block_size) => block_size.format(entry.size()),
+            None => entry.size().to_string(),
+        };
+        let mut line = format!(
             "{} {:>8} {:>12} {}", 
             permissions, 
-            entry.size(), 
+            size, 
             entry.modified_timestamp(), 
             entry.name
-        )
+        );
+
+        // Each attribute on a line of its own, with the size of its value
+        if self.extended {
+            for name in entry.attributes() {
+                let size = xattr::value_size(&entry.path, &name).unwrap_or(0);
+                line.push_str(&format!("\n\t{}\t{:>4}", name, size));
+            }
+        }
+        line
     }
 }
 
@@ -174,13 +218,25 @@ pub fn run(dir_path: &str, options: &LsOptions) -> io::Result<()> {
     let entries = FileCollector::collect_entries(path)?;
     
     let formatter: Box<dyn EntryFormatter> = if options.long_format {
-        Box::new(LongFormatter)
+        let listed = entries
+            .iter()
+            .filter(|entry| options.show_hidden || !entry.is_hidden());
+        let markers = listed.clone().any(|entry| entry.attribute_marker().is_some());
+        let total: u64 = listed.map(FileEntry::allocated).sum();
+        let unit = options.block_size.clone().unwrap_or_default();
+        println!("total {}", unit.format(total));
+        Box::new(LongFormatter {
+            markers,
+            extended: options.extended,
+            block_size: options.block_size.clone(),
+        })
     } else {
         Box::new(SimpleFormatter)
     };
     
-    let mut filters: Vec<Box<dyn EntryFilter>> = Vec::new();
-    filters.push(Box::new(HiddenFilter { show_hidden: options.show_hidden }));
+    let filters: Vec<Box<dyn EntryFilter>> = vec![
+        Box::new(HiddenFilter { show_hidden: options.show_hidden }),
+    ];
     
     let processor = FileProcessor {
         formatter,
@@ -197,11 +253,23 @@ fn main() {
     let mut options = LsOptions::default();
     
     for arg in args.iter().skip(1) {
-        if arg.starts_with('-') {
+        if arg == "--extended" {
+            options.extended = true;
+        } else if let Some(value) = arg.strip_prefix("--block-size=") {
+            match BlockSize::parse(value) {
+                Some(block_size) => options.block_size = Some(block_size),
+                None => {
+                    eprintln!("ls: invalid --block-size argument '{}'", value);
+                    std::process::exit(2);
+                }
+            }
+        } else if arg.starts_with('-') {
             for flag in arg.chars().skip(1) {
                 match flag {
                     'a' => options.show_hidden = true,
                     'l' => options.long_format = true,
+                    '@' => options.extended = true,
+                    'k' => options.block_size = None,
                     _ => eprintln!("Unknown option: {}", flag),
                 }
             }
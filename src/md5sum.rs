@@ -0,0 +1,6 @@
+use cmds_lite::checksum;
+use cmds_lite::hash::Algorithm;
+
+fn main() {
+    checksum::main("md5sum", Algorithm::Md5)
+}
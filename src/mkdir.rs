@@ -0,0 +1,180 @@
+use cmds_lite::error;
+use cmds_lite::mode::{self, Mode};
+use std::env;
+use std::fs;
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+#[derive(Default)]
+pub struct MkdirOptions {
+    pub parents: bool,
+    pub verbose: bool,
+    pub mode: Option<Mode>,
+}
+
+struct MkdirCommand<'a> {
+    options: &'a MkdirOptions,
+}
+
+impl<'a> MkdirCommand<'a> {
+    fn new(options: &'a MkdirOptions) -> Self {
+        MkdirCommand { options }
+    }
+
+    fn run(&self, dirs: &[String]) -> bool {
+        let mut ok = true;
+        for dir in dirs {
+            let path = Path::new(dir);
+            let result = if self.options.parents {
+                self.create_with_parents(path)
+            } else {
+                self.create(path, self.final_mode())
+            };
+
+            if let Err(e) = result {
+                eprintln!("mkdir: cannot create directory '{}': {}", dir, error::message(&e));
+                ok = false;
+            }
+        }
+        ok
+    }
+
+    // The mode requested with -m, applied to a=rwx like GNU mkdir does
+    fn final_mode(&self) -> Option<u32> {
+        self.options
+            .mode
+            .as_ref()
+            .map(|mode| mode.apply(0o777, true, mode::current_umask()))
+    }
+
+    fn create(&self, path: &Path, mode: Option<u32>) -> io::Result<()> {
+        let mut builder = fs::DirBuilder::new();
+        if let Some(mode) = mode {
+            builder.mode(mode);
+        }
+        builder.create(path)?;
+
+        // The umask applies at creation time, so set an explicit -m mode
+        // afterwards to get exactly what was asked for
+        if let Some(mode) = mode {
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+        }
+
+        if self.options.verbose {
+            println!("mkdir: created directory '{}'", path.display());
+        }
+        Ok(())
+    }
+
+    fn create_with_parents(&self, path: &Path) -> io::Result<()> {
+        // Collect the missing ancestors so each creation can be reported
+        let mut missing: Vec<PathBuf> = Vec::new();
+        let mut current = Some(path);
+        while let Some(dir) = current {
+            if dir.as_os_str().is_empty() || dir.is_dir() {
+                break;
+            }
+            missing.push(dir.to_path_buf());
+            current = dir.parent();
+        }
+
+        // Only the final directory gets the -m mode; parents use the default
+        for (index, dir) in missing.iter().rev().enumerate() {
+            let mode = if index + 1 == missing.len() {
+                self.final_mode()
+            } else {
+                None
+            };
+            match self.create(dir, mode) {
+                Ok(()) => {}
+                // Another process may have created it in the meantime
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && dir.is_dir() => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn run(dirs: &[String], options: &MkdirOptions) -> bool {
+    let command = MkdirCommand::new(options);
+    command.run(dirs)
+}
+
+fn parse_mode(spec: &str) -> Mode {
+    match Mode::parse(spec) {
+        Ok(mode) => mode,
+        Err(e) => {
+            eprintln!("mkdir: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = MkdirOptions::default();
+    let mut dirs = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            dirs.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--parents" => options.parents = true,
+            "--verbose" => options.verbose = true,
+            _ if arg.starts_with("--mode=") => {
+                options.mode = Some(parse_mode(&arg["--mode=".len()..]));
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("mkdir: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'p' => options.parents = true,
+                        'v' => options.verbose = true,
+                        'm' => {
+                            // The mode is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let spec = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                eprintln!("mkdir: option requires an argument -- 'm'");
+                                std::process::exit(1);
+                            };
+                            options.mode = Some(parse_mode(&spec));
+                            break;
+                        }
+                        _ => {
+                            eprintln!("mkdir: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if dirs.is_empty() {
+        eprintln!("mkdir: missing operand");
+        std::process::exit(1);
+    }
+
+    if !run(&dirs, &options) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,245 @@
+use cmds_lite::error;
+use cmds_lite::random::Random;
+use std::env;
+use std::fs::{DirBuilder, OpenOptions};
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::Path;
+
+// Characters the X's of a template are replaced with
+const NAME_CHARACTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+// How many names are tried before giving up when they are all taken
+const ATTEMPTS: usize = 10_000;
+
+const DEFAULT_TEMPLATE: &str = "tmp.XXXXXXXXXX";
+
+#[derive(Default)]
+pub struct MktempOptions {
+    // -d: create a directory instead of a file
+    pub directory: bool,
+    // -u: only print a name that is free, creating nothing
+    pub dry_run: bool,
+    // -q: no messages when creation fails
+    pub quiet: bool,
+    // -p / --tmpdir: the directory the template is relative to; Some("")
+    // means $TMPDIR or /tmp
+    pub tmpdir: Option<String>,
+    // -t: the template is a name inside $TMPDIR, -p or /tmp
+    pub in_tmpdir: bool,
+    // --suffix: text after the X's
+    pub suffix: String,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("mktemp: {}", message);
+    std::process::exit(1);
+}
+
+struct MktempCommand<'a> {
+    options: &'a MktempOptions,
+    random: Random,
+}
+
+impl<'a> MktempCommand<'a> {
+    // The directory a template without one goes in: -p's, $TMPDIR's or /tmp
+    fn tmpdir(&self) -> String {
+        match self.options.tmpdir.as_deref() {
+            Some(dir) if !dir.is_empty() => dir.to_string(),
+            _ => env::var("TMPDIR")
+                .ok()
+                .filter(|dir| !dir.is_empty())
+                .unwrap_or_else(|| String::from("/tmp")),
+        }
+    }
+
+    // The full template, and where its X's start
+    fn template(&self, template: Option<&str>) -> Result<(String, usize), String> {
+        let given = template.is_some();
+        let mut template = template.unwrap_or(DEFAULT_TEMPLATE).to_string();
+        let trailing = template.len() - template.trim_end_matches('X').len();
+        if trailing < 3 {
+            return Err(format!("too few X's in template '{}'", template));
+        }
+        let start = template.len() - trailing;
+
+        let relative = !given || self.options.tmpdir.is_some() || self.options.in_tmpdir;
+        if !relative {
+            template.push_str(&self.options.suffix);
+            return Ok((template, start));
+        }
+        if given && (self.options.in_tmpdir || self.options.tmpdir.is_some()) {
+            if Path::new(&template).is_absolute() {
+                return Err(format!(
+                    "invalid template, '{}'; with --tmpdir, it may not be absolute",
+                    template
+                ));
+            }
+            if self.options.in_tmpdir && template.contains('/') {
+                return Err(format!(
+                    "invalid template, '{}', contains directory separator",
+                    template
+                ));
+            }
+        }
+        let dir = self.tmpdir();
+        let prefix = if dir.ends_with('/') {
+            dir
+        } else {
+            format!("{}/", dir)
+        };
+        Ok((
+            format!("{}{}{}", prefix, template, self.options.suffix),
+            prefix.len() + start,
+        ))
+    }
+
+    // The template with its X's replaced by random characters
+    fn candidate(&mut self, template: &str, start: usize) -> String {
+        let end = template.len() - self.options.suffix.len();
+        let mut name = template.as_bytes().to_vec();
+        for byte in &mut name[start..end] {
+            let pick = self.random.below(NAME_CHARACTERS.len() as u64) as usize;
+            *byte = NAME_CHARACTERS[pick];
+        }
+        String::from_utf8(name).unwrap_or_default()
+    }
+
+    // Creates the file, readable and writable only by its owner, or the
+    // directory, usable only by its owner. Failing because the name is
+    // taken is the only failure that is tried again.
+    fn create(&self, name: &str) -> io::Result<()> {
+        if self.options.dry_run {
+            return match Path::new(name).symlink_metadata() {
+                Ok(_) => Err(io::Error::from(io::ErrorKind::AlreadyExists)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(e),
+            };
+        }
+        if self.options.directory {
+            DirBuilder::new().mode(0o700).create(name)
+        } else {
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(name)
+                .map(|_| ())
+        }
+    }
+
+    fn run(&mut self, template: Option<&str>) -> Result<String, String> {
+        let (template, start) = self.template(template)?;
+        let mut last_error = io::Error::from(io::ErrorKind::AlreadyExists);
+        for _ in 0..ATTEMPTS {
+            let name = self.candidate(&template, start);
+            match self.create(&name) {
+                Ok(()) => return Ok(name),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => last_error = e,
+                Err(e) => {
+                    last_error = e;
+                    break;
+                }
+            }
+        }
+        let what = if self.options.directory {
+            "directory"
+        } else {
+            "file"
+        };
+        Err(format!(
+            "failed to create {} via template '{}': {}",
+            what,
+            template,
+            error::message(&last_error)
+        ))
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = MktempOptions::default();
+    let mut templates = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            templates.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--directory" => options.directory = true,
+            "--dry-run" => options.dry_run = true,
+            "--quiet" => options.quiet = true,
+            // --tmpdir takes its value only after =, so a template can follow
+            "--tmpdir" => options.tmpdir = Some(String::new()),
+            _ if arg.starts_with("--tmpdir=") => {
+                options.tmpdir = Some(arg["--tmpdir=".len()..].to_string())
+            }
+            _ if arg.starts_with("--suffix=") => {
+                options.suffix = arg["--suffix=".len()..].to_string()
+            }
+            "--suffix" if i < args.len() => {
+                i += 1;
+                options.suffix = args[i - 1].clone();
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'd' => options.directory = true,
+                        'u' => options.dry_run = true,
+                        'q' => options.quiet = true,
+                        't' => options.in_tmpdir = true,
+                        'p' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            options.tmpdir = Some(value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if templates.len() > 1 {
+        fail("too many templates");
+    }
+    if options.suffix.contains('/') {
+        fail(&format!(
+            "invalid suffix '{}', contains directory separator",
+            options.suffix
+        ));
+    }
+    let template = templates.first().map(String::as_str);
+    let random = Random::from_entropy().unwrap_or_else(|e| fail(&e.to_string()));
+    let mut command = MktempCommand {
+        options: &options,
+        random,
+    };
+    match command.run(template) {
+        Ok(name) => println!("{}", name),
+        Err(message) => {
+            if !options.quiet {
+                eprintln!("mktemp: {}", message);
+            }
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use std::fmt;
+
+// Permission classes as chmod(1) understands them, each including the
+// special bit that belongs to that class.
+const USER_BITS: u32 = 0o4700;
+const GROUP_BITS: u32 = 0o2070;
+const OTHER_BITS: u32 = 0o1007;
+const ALL_BITS: u32 = 0o7777;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseModeError {
+    spec: String,
+}
+
+impl fmt::Display for ParseModeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid mode: '{}'", self.spec)
+    }
+}
+
+impl std::error::Error for ParseModeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Remove,
+    Set,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Perms {
+    // Literal permission letters: rwx as 0o111 multiples, plus flags
+    Literal {
+        bits: u32,
+        conditional_exec: bool,
+    },
+    // Copy the permissions of another class, e.g. g=u
+    Copy(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Action {
+    op: Op,
+    perms: Perms,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Clause {
+    // None means no class was given, so the umask limits the change
+    who: Option<u32>,
+    actions: Vec<Action>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ModeKind {
+    // `mentioned` is the bits the digits speak for: fewer than five digits
+    // leave a directory's set-user-ID and set-group-ID bits alone unless
+    // they are being set
+    Octal { value: u32, mentioned: u32 },
+    Symbolic(Vec<Clause>),
+}
+
+// A parsed chmod-style mode: either an absolute octal value or a list of
+// symbolic clauses such as `u=rwx,g+rx`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mode {
+    kind: ModeKind,
+}
+
+impl Mode {
+    pub fn parse(spec: &str) -> Result<Mode, ParseModeError> {
+        let error = || ParseModeError {
+            spec: spec.to_string(),
+        };
+
+        if spec.is_empty() {
+            return Err(error());
+        }
+
+        if spec.chars().all(|c| c.is_digit(8)) {
+            let value = u32::from_str_radix(spec, 8).map_err(|_| error())?;
+            if value > ALL_BITS {
+                return Err(error());
+            }
+            let mentioned = if spec.len() < 5 {
+                (value & 0o6000) | 0o1777
+            } else {
+                ALL_BITS
+            };
+            return Ok(Mode {
+                kind: ModeKind::Octal { value, mentioned },
+            });
+        }
+
+        let mut clauses = Vec::new();
+        for text in spec.split(',') {
+            clauses.push(parse_clause(text).ok_or_else(error)?);
+        }
+        Ok(Mode {
+            kind: ModeKind::Symbolic(clauses),
+        })
+    }
+
+    // Computes the mode that results from applying this change to `current`.
+    // `umask` only matters for symbolic clauses that omit the class letters.
+    // As with GNU chmod, a directory keeps its set-user-ID and set-group-ID
+    // bits unless the mode names them.
+    pub fn apply(&self, current: u32, is_dir: bool, umask: u32) -> u32 {
+        let kept = |mentioned: u32| if is_dir { 0o6000 & !mentioned } else { 0 };
+        let clauses = match &self.kind {
+            ModeKind::Octal { value, mentioned } => {
+                return (current & kept(*mentioned)) | value;
+            }
+            ModeKind::Symbolic(clauses) => clauses,
+        };
+
+        let mut mode = current & ALL_BITS;
+        for clause in clauses {
+            let (affected, omit) = match clause.who {
+                Some(who) => (who, 0),
+                None => (ALL_BITS, umask & 0o777),
+            };
+
+            for action in &clause.actions {
+                let mentioned = match action.perms {
+                    Perms::Literal { bits, .. } => bits & affected,
+                    Perms::Copy(_) => 0,
+                };
+                let mut value = match action.perms {
+                    Perms::Copy(class) => {
+                        let shift = match class {
+                            USER_BITS => 6,
+                            GROUP_BITS => 3,
+                            _ => 0,
+                        };
+                        ((mode >> shift) & 0o7) * 0o111
+                    }
+                    Perms::Literal {
+                        bits,
+                        conditional_exec,
+                    } => {
+                        let mut bits = bits;
+                        if conditional_exec && (is_dir || mode & 0o111 != 0) {
+                            bits |= 0o111;
+                        }
+                        bits
+                    }
+                };
+                value &= affected & !omit;
+
+                mode = match action.op {
+                    Op::Add => mode | value,
+                    Op::Remove => mode & !value,
+                    Op::Set => (mode & (!affected | kept(mentioned))) | value,
+                };
+            }
+        }
+        mode
+    }
+}
+
+fn parse_clause(text: &str) -> Option<Clause> {
+    let mut chars = text.chars().peekable();
+
+    let mut who = 0;
+    let mut who_given = false;
+    while let Some(&c) = chars.peek() {
+        let bits = match c {
+            'u' => USER_BITS,
+            'g' => GROUP_BITS,
+            'o' => OTHER_BITS,
+            'a' => ALL_BITS,
+            _ => break,
+        };
+        who |= bits;
+        who_given = true;
+        chars.next();
+    }
+
+    let mut actions = Vec::new();
+    while let Some(c) = chars.next() {
+        let op = match c {
+            '+' => Op::Add,
+            '-' => Op::Remove,
+            '=' => Op::Set,
+            _ => return None,
+        };
+
+        let perms = match chars.peek() {
+            Some('u') | Some('g') | Some('o') => {
+                let class = match chars.next() {
+                    Some('u') => USER_BITS,
+                    Some('g') => GROUP_BITS,
+                    _ => OTHER_BITS,
+                };
+                Perms::Copy(class)
+            }
+            _ => {
+                let mut bits = 0;
+                let mut conditional_exec = false;
+                while let Some(&p) = chars.peek() {
+                    match p {
+                        'r' => bits |= 0o444,
+                        'w' => bits |= 0o222,
+                        'x' => bits |= 0o111,
+                        'X' => conditional_exec = true,
+                        's' => bits |= 0o6000,
+                        't' => bits |= 0o1000,
+                        _ => break,
+                    }
+                    chars.next();
+                }
+                Perms::Literal {
+                    bits,
+                    conditional_exec,
+                }
+            }
+        };
+        actions.push(Action { op, perms });
+    }
+
+    if actions.is_empty() {
+        return None;
+    }
+
+    Some(Clause {
+        who: if who_given { Some(who) } else { None },
+        actions,
+    })
+}
+
+// Reads the process umask. There is no way to query it without setting
+// it, so it is immediately restored.
+pub fn current_umask() -> u32 {
+    unsafe {
+        let mask = libc::umask(0o022);
+        libc::umask(mask);
+        mask as u32
+    }
+}
+
+// The nine permission characters ls -l shows, such as `rwxr-sr-t`, with
+// the set-id and sticky bits folded into the execute positions
+pub fn symbolic(mode: u32) -> String {
+    let mut text = String::with_capacity(9);
+    for (shift, special, set, unset) in [
+        (6, 0o4000, 's', 'S'),
+        (3, 0o2000, 's', 'S'),
+        (0, 0o1000, 't', 'T'),
+    ] {
+        let bits = (mode >> shift) & 0o7;
+        text.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        text.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => set,
+            (false, true) => unset,
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(spec: &str, current: u32, is_dir: bool, umask: u32) -> u32 {
+        Mode::parse(spec).unwrap().apply(current, is_dir, umask)
+    }
+
+    #[test]
+    fn octal_modes() {
+        for (spec, current, expected) in [
+            ("755", 0o644, 0o755),
+            ("0", 0o777, 0),
+            ("4755", 0o644, 0o4755),
+            ("7777", 0, 0o7777),
+        ] {
+            assert_eq!(apply(spec, current, false, 0o022), expected, "{}", spec);
+        }
+    }
+
+    #[test]
+    fn symbolic_modes() {
+        for (spec, current, is_dir, expected) in [
+            ("u+x", 0o644, false, 0o744),
+            ("go-w", 0o666, false, 0o644),
+            ("a=r", 0o755, false, 0o444),
+            ("u=rwx,g=rx,o=", 0o000, false, 0o750),
+            ("+x", 0o644, false, 0o755),
+            ("=rw", 0o777, false, 0o644),
+            ("a+X", 0o644, false, 0o644),
+            ("a+X", 0o744, false, 0o755),
+            ("a+X", 0o600, true, 0o711),
+            ("g=u", 0o740, false, 0o770),
+            ("o=g", 0o751, false, 0o755),
+            ("u+s", 0o755, false, 0o4755),
+            ("g+s,o+t", 0o755, true, 0o3755),
+            ("u-x+s", 0o755, false, 0o4655),
+        ] {
+            assert_eq!(apply(spec, current, is_dir, 0o022), expected, "{}", spec);
+        }
+    }
+
+    #[test]
+    fn directories_keep_set_id_bits_unless_named() {
+        for (spec, expected) in [
+            ("755", 0o2755),
+            ("0755", 0o2755),
+            ("00755", 0o755),
+            ("4755", 0o6755),
+            ("u=rwx,g=rx,o=rx", 0o2755),
+            ("g-s", 0o755),
+            ("g=rx", 0o2755),
+            ("g=rxs", 0o2755),
+            ("u=rwxs", 0o6755),
+        ] {
+            assert_eq!(apply(spec, 0o2755, true, 0), expected, "{}", spec);
+        }
+        assert_eq!(apply("755", 0o2755, false, 0), 0o755);
+        assert_eq!(apply("u=rwx,g=rx,o=rx", 0o2755, false, 0), 0o755);
+    }
+
+    #[test]
+    fn invalid_modes() {
+        for spec in ["", "8", "17777", "u+q", "x", "u", "u+x,", "rwx"] {
+            assert!(Mode::parse(spec).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn symbolic_permissions() {
+        assert_eq!(symbolic(0o755), "rwxr-xr-x");
+        assert_eq!(symbolic(0o4755), "rwsr-xr-x");
+        assert_eq!(symbolic(0o2640), "rw-r-S---");
+        assert_eq!(symbolic(0o1777), "rwxrwxrwt");
+        assert_eq!(symbolic(0o1776), "rwxrwxrwT");
+    }
+}
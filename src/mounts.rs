@@ -0,0 +1,95 @@
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+// Mount table and filesystem statistics, as used by df and stat -f.
+
+pub struct MountEntry {
+    pub device: String,
+    pub mount_point: String,
+    pub fs_type: String,
+    pub options: String,
+}
+
+pub struct FsStats {
+    pub block_size: u64,
+    pub fragment_size: u64,
+    pub blocks: u64,
+    pub blocks_free: u64,
+    pub blocks_available: u64,
+    pub files: u64,
+    pub files_free: u64,
+    pub files_available: u64,
+    pub fs_id: u64,
+    pub max_name_length: u64,
+}
+
+// Reads the kernel's view of mounted filesystems from /proc/mounts
+pub fn read_mounts() -> io::Result<Vec<MountEntry>> {
+    let contents = fs::read_to_string("/proc/mounts")?;
+    Ok(parse_mounts(&contents))
+}
+
+pub fn parse_mounts(contents: &str) -> Vec<MountEntry> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = unescape(fields.next()?);
+            let mount_point = unescape(fields.next()?);
+            let fs_type = unescape(fields.next()?);
+            let options = fields.next().unwrap_or("").to_string();
+            Some(MountEntry {
+                device,
+                mount_point,
+                fs_type,
+                options,
+            })
+        })
+        .collect()
+}
+
+// Undoes the octal escapes (\040 for space etc.) used in /proc/mounts
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 4 <= bytes.len() {
+            let digits = std::str::from_utf8(&bytes[i + 1..i + 4]).unwrap_or("");
+            if let Ok(value) = u8::from_str_radix(digits, 8) {
+                out.push(value);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+pub fn statvfs(path: &Path) -> io::Result<FsStats> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains NUL"))?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    #[allow(clippy::unnecessary_cast)]
+    Ok(FsStats {
+        block_size: stats.f_bsize as u64,
+        fragment_size: stats.f_frsize as u64,
+        blocks: stats.f_blocks as u64,
+        blocks_free: stats.f_bfree as u64,
+        blocks_available: stats.f_bavail as u64,
+        files: stats.f_files as u64,
+        files_free: stats.f_ffree as u64,
+        files_available: stats.f_favail as u64,
+        fs_id: stats.f_fsid as u64,
+        max_name_length: stats.f_namemax as u64,
+    })
+}
@@ -0,0 +1,580 @@
+use cmds_lite::copy::{self, Sparse};
+use cmds_lite::datetime::Timestamp;
+use cmds_lite::error;
+use cmds_lite::platform;
+use cmds_lite::progress::Progress;
+use cmds_lite::signal::{self, Signal};
+use cmds_lite::walk::{self, WalkOptions, Walker};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::CString;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{
+    self as unix_fs, DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt,
+};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Signals that stop a move across file systems. They are held back while
+// the copy runs and looked for between blocks, so that the partial copy
+// can be removed before the process ends the way the signal would have
+// ended it. Ones the caller ignores stay ignored.
+const INTERRUPTS: [Signal; 3] = [Signal::INT, Signal::TERM, Signal::HUP];
+
+#[derive(Default)]
+pub struct MvOptions {
+    // -f: overwrite existing destinations (the default)
+    pub force: bool,
+    // -n: leave existing destinations alone
+    pub no_clobber: bool,
+    // -v: report each move
+    pub verbose: bool,
+    // -t: move every source into this directory
+    pub target_directory: Option<String>,
+    // -T: the destination is a file, even when it names a directory
+    pub no_target_directory: bool,
+    // --progress: show how far a move across file systems has got
+    pub progress: bool,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("mv: {}", message);
+    std::process::exit(1);
+}
+
+// Why a move across file systems did not finish
+enum CopyFailure {
+    Error(String),
+    Interrupted(Signal),
+}
+
+// The state of one move across file systems
+struct Transfer {
+    interrupts: Vec<Signal>,
+    // Set when one of the interrupts has arrived
+    interrupted: Cell<Option<Signal>>,
+    progress: Option<Progress>,
+    // Bytes copied from the files already done
+    done: u64,
+}
+
+impl Transfer {
+    fn check(&self) -> Result<(), CopyFailure> {
+        match interruption(&self.interrupts, &self.interrupted) {
+            Some(signal) => Err(CopyFailure::Interrupted(signal)),
+            None => Ok(()),
+        }
+    }
+}
+
+// The interrupt that has arrived, if any, remembered once seen
+fn interruption(interrupts: &[Signal], interrupted: &Cell<Option<Signal>>) -> Option<Signal> {
+    if interrupted.get().is_none() {
+        if let Ok(Some(signal)) = signal::wait_timeout(interrupts, Duration::ZERO) {
+            interrupted.set(Some(signal));
+        }
+    }
+    interrupted.get()
+}
+
+struct MvCommand<'a> {
+    options: &'a MvOptions,
+}
+
+impl<'a> MvCommand<'a> {
+    // Gives the copy the owner, mode and timestamps of the original. The
+    // owner can only be given away by root, so failing to set it is not
+    // an error, as with GNU mv.
+    fn copy_attributes(&self, metadata: &fs::Metadata, dest: &Path) -> Result<(), String> {
+        let is_link = metadata.file_type().is_symlink();
+        let _ = platform::change_owner(dest, Some(metadata.uid()), Some(metadata.gid()), !is_link);
+        if !is_link {
+            fs::set_permissions(dest, fs::Permissions::from_mode(metadata.mode() & 0o7777))
+                .map_err(|e| {
+                    format!(
+                        "setting permissions for '{}': {}",
+                        dest.display(),
+                        error::message(&e)
+                    )
+                })?;
+        }
+        let atime = Timestamp::new(metadata.atime(), metadata.atime_nsec() as u32);
+        let mtime = Timestamp::new(metadata.mtime(), metadata.mtime_nsec() as u32);
+        platform::set_times(dest, atime, mtime, !is_link).map_err(|e| {
+            format!(
+                "preserving times for '{}': {}",
+                dest.display(),
+                error::message(&e)
+            )
+        })
+    }
+
+    // Copies a regular file and flushes it to disk, so that the original
+    // is only removed once the copy is sure to survive a crash
+    fn copy_file(
+        &self,
+        transfer: &mut Transfer,
+        source: &Path,
+        metadata: &fs::Metadata,
+        dest: &Path,
+    ) -> Result<(), CopyFailure> {
+        let error = |what: &str, path: &Path, e: &io::Error| {
+            CopyFailure::Error(format!(
+                "{} '{}': {}",
+                what,
+                path.display(),
+                error::message(e)
+            ))
+        };
+        let mut input = File::open(source).map_err(|e| error("cannot open", source, &e))?;
+        let mut output = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(dest)
+            .map_err(|e| error("cannot create regular file", dest, &e))?;
+
+        let done = transfer.done;
+        let progress = &mut transfer.progress;
+        let interrupts = &transfer.interrupts;
+        let interrupted = &transfer.interrupted;
+        let copied = copy::copy_data(&mut input, &mut output, Sparse::Auto, &mut |copied| {
+            if let Some(progress) = progress {
+                progress.update(done + copied);
+            }
+            match interruption(interrupts, interrupted) {
+                Some(_) => Err(io::Error::from(io::ErrorKind::Interrupted)),
+                None => Ok(()),
+            }
+        });
+        if let Some(signal) = transfer.interrupted.get() {
+            return Err(CopyFailure::Interrupted(signal));
+        }
+        let copied = copied.map_err(|e| error("error writing", dest, &e))?;
+        transfer.done += copied;
+        output
+            .sync_all()
+            .map_err(|e| error("error writing", dest, &e))?;
+        drop(output);
+        self.copy_attributes(metadata, dest)
+            .map_err(CopyFailure::Error)
+    }
+
+    // Recreates a symbolic link, FIFO, socket or device node
+    fn copy_special(
+        &self,
+        source: &Path,
+        metadata: &fs::Metadata,
+        dest: &Path,
+    ) -> Result<(), String> {
+        let created = if metadata.file_type().is_symlink() {
+            fs::read_link(source).and_then(|target| unix_fs::symlink(target, dest))
+        } else {
+            let path = CString::new(dest.as_os_str().as_bytes())
+                .map_err(|_| format!("cannot create '{}': path contains NUL", dest.display()))?;
+            let result = unsafe {
+                libc::mknod(
+                    path.as_ptr(),
+                    metadata.mode() as libc::mode_t,
+                    metadata.rdev() as libc::dev_t,
+                )
+            };
+            if result == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        };
+        created
+            .map_err(|e| format!("cannot create '{}': {}", dest.display(), error::message(&e)))?;
+        self.copy_attributes(metadata, dest)
+    }
+
+    // Copies `source` and everything under it to `dest`, which must not
+    // exist. Directories are kept writable while they are filled, and get
+    // their own mode and times once everything in them is done. Files
+    // linked more than once in the tree are copied once and linked again.
+    fn copy_tree(
+        &self,
+        transfer: &mut Transfer,
+        source: &Path,
+        dest: &Path,
+    ) -> Result<(), CopyFailure> {
+        let mut directories = Vec::new();
+        // (device, inode) of multiply-linked files already copied, and
+        // where to
+        let mut linked: HashMap<(u64, u64), PathBuf> = HashMap::new();
+        for item in Walker::new(&[source], WalkOptions::default()) {
+            transfer.check()?;
+            let entry = item.map_err(|e| CopyFailure::Error(format!("cannot access {}", e)))?;
+            let relative = entry.path.strip_prefix(source).unwrap_or(&entry.path);
+            let target = if relative.as_os_str().is_empty() {
+                dest.to_path_buf()
+            } else {
+                dest.join(relative)
+            };
+            let file_type = entry.metadata.file_type();
+            let id = (entry.metadata.dev(), entry.metadata.ino());
+            if !file_type.is_dir() && entry.metadata.nlink() > 1 {
+                if let Some(first) = linked.get(&id) {
+                    fs::hard_link(first, &target).map_err(|e| {
+                        CopyFailure::Error(format!(
+                            "cannot create hard link '{}' to '{}': {}",
+                            target.display(),
+                            first.display(),
+                            error::message(&e)
+                        ))
+                    })?;
+                    continue;
+                }
+                linked.insert(id, target.clone());
+            }
+            if file_type.is_dir() {
+                fs::DirBuilder::new()
+                    .mode(0o700)
+                    .create(&target)
+                    .map_err(|e| {
+                        CopyFailure::Error(format!(
+                            "cannot create directory '{}': {}",
+                            target.display(),
+                            error::message(&e)
+                        ))
+                    })?;
+                directories.push((target, entry.metadata));
+            } else if file_type.is_file() {
+                self.copy_file(transfer, &entry.path, &entry.metadata, &target)?;
+            } else {
+                self.copy_special(&entry.path, &entry.metadata, &target)
+                    .map_err(CopyFailure::Error)?;
+            }
+        }
+        for (path, metadata) in directories.iter().rev() {
+            self.copy_attributes(metadata, path)
+                .map_err(CopyFailure::Error)?;
+        }
+        // The new entries themselves are made durable with the directory
+        // that holds the copy
+        let parent = dest
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        if let Ok(parent) = File::open(parent) {
+            let _ = parent.sync_all();
+        }
+        Ok(())
+    }
+
+    // Bytes in the regular files under `path`, for the progress line. Hard
+    // links are only copied once, so only counted once.
+    fn total_size(&self, path: &Path) -> u64 {
+        let mut seen = HashSet::new();
+        Walker::new(&[path], WalkOptions::default())
+            .filter_map(Result::ok)
+            .filter(|entry| entry.metadata.is_file())
+            .filter(|entry| {
+                entry.metadata.nlink() == 1
+                    || seen.insert((entry.metadata.dev(), entry.metadata.ino()))
+            })
+            .map(|entry| entry.metadata.len())
+            .sum()
+    }
+
+    fn remove(path: &Path, is_dir: bool) -> io::Result<()> {
+        if is_dir {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+    }
+
+    // Moves `source` to another file system: copies it, then removes the
+    // original. Should the copy fail or be interrupted, what was copied so
+    // far is removed again, leaving only the original.
+    fn move_across(
+        &self,
+        source: &Path,
+        metadata: &fs::Metadata,
+        dest: &Path,
+    ) -> Result<(), String> {
+        if let Ok(existing) = fs::symlink_metadata(dest) {
+            let removed = if existing.is_dir() {
+                fs::remove_dir(dest)
+            } else {
+                fs::remove_file(dest)
+            };
+            removed.map_err(|e| {
+                format!(
+                    "inter-device move failed: '{}' to '{}'; unable to remove target: {}",
+                    source.display(),
+                    dest.display(),
+                    error::message(&e)
+                )
+            })?;
+        }
+
+        let interrupts: Vec<Signal> = INTERRUPTS
+            .into_iter()
+            .filter(|signal| !signal::is_ignored(*signal))
+            .collect();
+        let _ = signal::block(&interrupts);
+        let mut transfer = Transfer {
+            progress: self
+                .options
+                .progress
+                .then(|| Progress::new(self.total_size(source))),
+            interrupts,
+            interrupted: Cell::new(None),
+            done: 0,
+        };
+        let copied = if metadata.is_dir() {
+            self.copy_tree(&mut transfer, source, dest)
+        } else if metadata.is_file() {
+            self.copy_file(&mut transfer, source, metadata, dest)
+        } else {
+            self.copy_special(source, metadata, dest)
+                .map_err(CopyFailure::Error)
+        };
+        // A signal that came during the last steps still stops the move
+        // before the original is touched
+        let copied = copied.and_then(|()| transfer.check());
+        if let Some(progress) = &mut transfer.progress {
+            progress.finish();
+        }
+
+        if let Err(failure) = copied {
+            let _ = MvCommand::remove(dest, metadata.is_dir());
+            match failure {
+                CopyFailure::Error(message) => {
+                    let _ = signal::unblock(&transfer.interrupts);
+                    return Err(message);
+                }
+                CopyFailure::Interrupted(signal) => signal::exit_by(signal),
+            }
+        }
+        let _ = signal::unblock(&transfer.interrupts);
+
+        MvCommand::remove(source, metadata.is_dir()).map_err(|e| {
+            format!(
+                "cannot remove '{}': {}",
+                source.display(),
+                error::message(&e)
+            )
+        })
+    }
+
+    fn move_one(&self, source: &str, dest: &Path) -> Result<(), String> {
+        let path = Path::new(source);
+        let metadata = fs::symlink_metadata(path).map_err(|error| {
+            format!(
+                "cannot stat {}",
+                walk::WalkError {
+                    path: path.to_path_buf(),
+                    error
+                }
+            )
+        })?;
+
+        if let Ok(existing) = fs::symlink_metadata(dest) {
+            if existing.dev() == metadata.dev() && existing.ino() == metadata.ino() {
+                return Err(format!(
+                    "'{}' and '{}' are the same file",
+                    source,
+                    dest.display()
+                ));
+            }
+            if self.options.no_clobber {
+                return Ok(());
+            }
+            if existing.is_dir() && !metadata.is_dir() {
+                return Err(format!(
+                    "cannot overwrite directory '{}' with non-directory",
+                    dest.display()
+                ));
+            }
+            if !existing.is_dir() && metadata.is_dir() {
+                return Err(format!(
+                    "cannot overwrite non-directory '{}' with directory '{}'",
+                    dest.display(),
+                    source
+                ));
+            }
+        }
+        if metadata.is_dir() {
+            if let (Ok(from), Some(Ok(to))) = (
+                fs::canonicalize(path),
+                dest.parent().map(|parent| {
+                    fs::canonicalize(if parent.as_os_str().is_empty() {
+                        Path::new(".")
+                    } else {
+                        parent
+                    })
+                }),
+            ) {
+                if to.starts_with(&from) {
+                    return Err(format!(
+                        "cannot move '{}' to a subdirectory of itself, '{}'",
+                        source,
+                        dest.display()
+                    ));
+                }
+            }
+        }
+
+        match fs::rename(path, dest) {
+            Ok(()) => {}
+            Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+                self.move_across(path, &metadata, dest)?
+            }
+            Err(e) => {
+                return Err(format!(
+                    "cannot move '{}' to '{}': {}",
+                    source,
+                    dest.display(),
+                    error::message(&e)
+                ))
+            }
+        }
+        if self.options.verbose {
+            println!("renamed '{}' -> '{}'", source, dest.display());
+        }
+        Ok(())
+    }
+
+    fn report(&self, result: Result<(), String>) -> bool {
+        match result {
+            Ok(()) => true,
+            Err(message) => {
+                eprintln!("mv: {}", message);
+                false
+            }
+        }
+    }
+
+    fn move_into(&self, sources: &[String], dir: &str) -> bool {
+        match fs::metadata(dir) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => {
+                eprintln!("mv: target '{}' is not a directory", dir);
+                return false;
+            }
+            Err(e) => {
+                eprintln!("mv: failed to access '{}': {}", dir, error::message(&e));
+                return false;
+            }
+        }
+
+        let mut ok = true;
+        for source in sources {
+            let name = Path::new(source)
+                .file_name()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(source));
+            ok &= self.report(self.move_one(source, &Path::new(dir).join(name)));
+        }
+        ok
+    }
+
+    fn run(&self, operands: &[String]) -> bool {
+        if let Some(dir) = &self.options.target_directory {
+            return self.move_into(operands, dir);
+        }
+        let (dest, sources) = operands.split_last().unwrap_or_else(|| unreachable!());
+        if !self.options.no_target_directory && (sources.len() > 1 || Path::new(dest).is_dir()) {
+            return self.move_into(sources, dest);
+        }
+        self.report(self.move_one(&sources[0], Path::new(dest)))
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = MvOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--force" => {
+                options.force = true;
+                options.no_clobber = false;
+            }
+            "--no-clobber" => {
+                options.no_clobber = true;
+                options.force = false;
+            }
+            "--verbose" => options.verbose = true,
+            "--no-target-directory" => options.no_target_directory = true,
+            "--progress" => options.progress = true,
+            _ if arg.starts_with("--target-directory=") => {
+                options.target_directory = Some(arg["--target-directory=".len()..].to_string())
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'f' => {
+                            options.force = true;
+                            options.no_clobber = false;
+                        }
+                        'n' => {
+                            options.no_clobber = true;
+                            options.force = false;
+                        }
+                        'v' => options.verbose = true,
+                        'T' => options.no_target_directory = true,
+                        't' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail("option requires an argument -- 't'");
+                            };
+                            options.target_directory = Some(value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if options.target_directory.is_some() && options.no_target_directory {
+        fail("cannot combine --target-directory (-t) and --no-target-directory (-T)");
+    }
+    if operands.is_empty() {
+        fail("missing file operand");
+    }
+    if options.target_directory.is_none() {
+        if operands.len() == 1 {
+            fail(&format!(
+                "missing destination file operand after '{}'",
+                operands[0]
+            ));
+        }
+        if options.no_target_directory && operands.len() > 2 {
+            fail(&format!("extra operand '{}'", operands[2]));
+        }
+    }
+
+    let command = MvCommand { options: &options };
+    if !command.run(&operands) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,107 @@
+use cmds_lite::error;
+use cmds_lite::platform;
+use std::env;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+// Like GNU nice, failures of nice itself exit with 125, and a command
+// that cannot be run with 126 or 127
+const EXIT_FAILURE: i32 = 125;
+const EXIT_CANNOT_INVOKE: i32 = 126;
+const EXIT_NOT_FOUND: i32 = 127;
+
+// The range the kernel keeps niceness in
+const MOST_FAVORED: i32 = -20;
+const LEAST_FAVORED: i32 = 19;
+
+fn fail(message: &str) -> ! {
+    eprintln!("nice: {}", message);
+    std::process::exit(EXIT_FAILURE);
+}
+
+// An adjustment of any size is accepted; the result is clamped anyway
+fn parse_adjustment(text: &str) -> i32 {
+    match text.parse::<i64>() {
+        Ok(value) => value.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+        Err(_) => fail(&format!("invalid adjustment '{}'", text)),
+    }
+}
+
+// -N or --N, the forms of -n from before it existed
+fn is_old_adjustment(arg: &str) -> bool {
+    let digits = arg[1..].strip_prefix(['-', '+']).unwrap_or(&arg[1..]);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut adjustment = None;
+    let mut i = 1;
+
+    // Options end at the first argument that is not one, which starts the
+    // command
+    while i < args.len() {
+        let arg = &args[i];
+        if !arg.starts_with('-') || arg == "-" {
+            break;
+        }
+        i += 1;
+
+        match arg.as_str() {
+            "--" => break,
+            _ if arg.starts_with("--adjustment=") => {
+                adjustment = Some(parse_adjustment(&arg["--adjustment=".len()..]))
+            }
+            "--adjustment" if i < args.len() => {
+                i += 1;
+                adjustment = Some(parse_adjustment(&args[i - 1]));
+            }
+            // The old forms: -N raises the niceness by N and --N lowers it
+            _ if is_old_adjustment(arg) => adjustment = Some(parse_adjustment(&arg[1..])),
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ if arg.starts_with("-n") => {
+                // The value is either the rest of this argument or the next one
+                let value = if arg.len() > 2 {
+                    arg[2..].to_string()
+                } else if i < args.len() {
+                    i += 1;
+                    args[i - 1].clone()
+                } else {
+                    fail("option requires an argument -- 'n'");
+                };
+                adjustment = Some(parse_adjustment(&value));
+            }
+            _ => fail(&format!("invalid option -- '{}'", &arg[1..2])),
+        }
+    }
+
+    let current = platform::niceness()
+        .unwrap_or_else(|e| fail(&format!("cannot get niceness: {}", error::message(&e))));
+    let command = &args[i..];
+    if command.is_empty() {
+        if adjustment.is_some() {
+            fail("a command must be given with an adjustment");
+        }
+        println!("{}", current);
+        return;
+    }
+
+    // Like GNU nice, not being allowed to change the niceness is only
+    // worth a warning; the command still runs
+    let niceness = current
+        .saturating_add(adjustment.unwrap_or(10))
+        .clamp(MOST_FAVORED, LEAST_FAVORED);
+    if let Err(e) = platform::set_niceness(niceness) {
+        eprintln!("nice: cannot set niceness: {}", error::message(&e));
+    }
+
+    // exec only returns when it fails
+    let e = Command::new(&command[0]).args(&command[1..]).exec();
+    eprintln!("nice: '{}': {}", command[0], error::message(&e));
+    std::process::exit(if e.kind() == io::ErrorKind::NotFound {
+        EXIT_NOT_FOUND
+    } else {
+        EXIT_CANNOT_INVOKE
+    });
+}
@@ -0,0 +1,283 @@
+use cmds_lite::error;
+use cmds_lite::numbering::{LineNumbering, Style};
+use cmds_lite::regex::{Regex, Syntax};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+// The parts of a logical page, each numbered in its own style
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Header,
+    Body,
+    Footer,
+}
+
+pub struct NlOptions {
+    pub body: Style,
+    pub header: Style,
+    pub footer: Style,
+    // The two characters that make up section delimiter lines
+    pub delimiter: String,
+    pub increment: i64,
+    pub separator: String,
+    pub start: i64,
+    pub width: usize,
+}
+
+impl Default for NlOptions {
+    fn default() -> Self {
+        NlOptions {
+            body: Style::NonEmpty,
+            header: Style::Nothing,
+            footer: Style::Nothing,
+            delimiter: String::from("\\:"),
+            increment: 1,
+            separator: String::from("\t"),
+            start: 1,
+            width: 6,
+        }
+    }
+}
+
+// Reading a file fails only that file; writing fails everything
+enum Failure {
+    Read(io::Error),
+    Write(io::Error),
+}
+
+struct NlCommand<'a> {
+    options: &'a NlOptions,
+    numbering: LineNumbering,
+    section: Section,
+}
+
+impl<'a> NlCommand<'a> {
+    fn new(options: &'a NlOptions) -> Self {
+        NlCommand {
+            options,
+            numbering: LineNumbering::new(
+                options.start,
+                options.increment,
+                options.width,
+                &options.separator,
+            ),
+            section: Section::Body,
+        }
+    }
+
+    // A line made of nothing but the delimiter three, two or one times
+    // starts a header, body or footer
+    fn delimiter_section(&self, line: &[u8]) -> Option<Section> {
+        let delimiter = self.options.delimiter.as_bytes();
+        if delimiter.is_empty() || !line.len().is_multiple_of(delimiter.len()) {
+            return None;
+        }
+        if !line.chunks(delimiter.len()).all(|chunk| chunk == delimiter) {
+            return None;
+        }
+        match line.len() / delimiter.len() {
+            3 => Some(Section::Header),
+            2 => Some(Section::Body),
+            1 => Some(Section::Footer),
+            _ => None,
+        }
+    }
+
+    fn style(&self) -> &Style {
+        match self.section {
+            Section::Header => &self.options.header,
+            Section::Body => &self.options.body,
+            Section::Footer => &self.options.footer,
+        }
+    }
+
+    fn process<R: BufRead>(&mut self, mut reader: R, out: &mut impl Write) -> Result<(), Failure> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line).map_err(Failure::Read)? == 0 {
+                return Ok(());
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+
+            // Delimiter lines come out empty, and every section numbers
+            // from the start again
+            if let Some(section) = self.delimiter_section(&line) {
+                self.section = section;
+                self.numbering.reset();
+                out.write_all(b"\n").map_err(Failure::Write)?;
+                continue;
+            }
+
+            let prefix = if self.style().numbers(&line) {
+                self.numbering.number()
+            } else {
+                self.numbering.padding()
+            };
+            out.write_all(prefix.as_bytes())
+                .and_then(|_| out.write_all(&line))
+                .and_then(|_| out.write_all(b"\n"))
+                .map_err(Failure::Write)?;
+        }
+    }
+
+    // Files are numbered as one continuous input
+    fn run(&mut self, files: &[String]) -> io::Result<bool> {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        let mut ok = true;
+
+        for file in files {
+            let processed = if file == "-" {
+                self.process(io::stdin().lock(), &mut out)
+            } else {
+                File::open(file)
+                    .map_err(Failure::Read)
+                    .and_then(|f| self.process(BufReader::new(f), &mut out))
+            };
+            match processed {
+                Ok(()) => {}
+                Err(Failure::Read(e)) => {
+                    out.flush()?;
+                    eprintln!("nl: {}: {}", file, error::message(&e));
+                    ok = false;
+                }
+                Err(Failure::Write(e)) => return Err(e),
+            }
+        }
+        out.flush()?;
+        Ok(ok)
+    }
+}
+
+pub fn run(files: &[String], options: &NlOptions) -> io::Result<bool> {
+    let mut command = NlCommand::new(options);
+    command.run(files)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("nl: {}", message);
+    std::process::exit(1);
+}
+
+// a, t, n or pREGEX
+fn parse_style(value: &str, section: &str) -> Style {
+    match value {
+        "a" => Style::All,
+        "t" => Style::NonEmpty,
+        "n" => Style::Nothing,
+        _ if value.starts_with('p') => match Regex::new(&value[1..], Syntax::Basic, false) {
+            Ok(regex) => Style::Matching(regex),
+            Err(message) => fail(&message),
+        },
+        _ => fail(&format!("invalid {} numbering style: '{}'", section, value)),
+    }
+}
+
+fn set_option(options: &mut NlOptions, option: char, value: &str) {
+    match option {
+        'b' => options.body = parse_style(value, "body"),
+        'h' => options.header = parse_style(value, "header"),
+        'f' => options.footer = parse_style(value, "footer"),
+        // A lone delimiter character is completed with ':'
+        'd' => {
+            options.delimiter = if value.chars().count() == 1 {
+                format!("{}:", value)
+            } else {
+                value.to_string()
+            }
+        }
+        'i' => {
+            options.increment = value
+                .parse()
+                .unwrap_or_else(|_| fail(&format!("invalid line number increment: '{}'", value)))
+        }
+        's' => options.separator = value.to_string(),
+        'v' => {
+            options.start = value
+                .parse()
+                .unwrap_or_else(|_| fail(&format!("invalid starting line number: '{}'", value)))
+        }
+        _ => {
+            options.width = match value.parse::<usize>() {
+                Ok(width) if width > 0 && width <= i32::MAX as usize => width,
+                Ok(_) => fail(&format!(
+                    "invalid line number field width: '{}': Numerical result out of range",
+                    value
+                )),
+                Err(_) => fail(&format!("invalid line number field width: '{}'", value)),
+            }
+        }
+    }
+}
+
+const LONG_OPTIONS: [(&str, char); 8] = [
+    ("--body-numbering=", 'b'),
+    ("--header-numbering=", 'h'),
+    ("--footer-numbering=", 'f'),
+    ("--section-delimiter=", 'd'),
+    ("--line-increment=", 'i'),
+    ("--number-separator=", 's'),
+    ("--starting-line-number=", 'v'),
+    ("--number-width=", 'w'),
+];
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = NlOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+        if arg == "--" {
+            end_of_options = true;
+            continue;
+        }
+
+        if arg.starts_with("--") {
+            match LONG_OPTIONS.iter().find(|(name, _)| arg.starts_with(name)) {
+                Some((name, option)) => set_option(&mut options, *option, &arg[name.len()..]),
+                None => fail(&format!("unrecognized option '{}'", arg)),
+            }
+            continue;
+        }
+
+        // Every short option takes a value
+        let flag = arg.chars().nth(1).unwrap_or('-');
+        if !"bhfdisvw".contains(flag) {
+            fail(&format!("invalid option -- '{}'", flag));
+        }
+        // The value is either the rest of this argument or the next one
+        let rest = &arg[1 + flag.len_utf8()..];
+        let value = if !rest.is_empty() {
+            rest.to_string()
+        } else if i < args.len() {
+            i += 1;
+            args[i - 1].clone()
+        } else {
+            fail(&format!("option requires an argument -- '{}'", flag));
+        };
+        set_option(&mut options, flag, &value);
+    }
+
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    match run(&files, &options) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(1),
+        Err(e) => fail(&format!("write error: {}", error::message(&e))),
+    }
+}
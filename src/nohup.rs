@@ -0,0 +1,158 @@
+use cmds_lite::error;
+use cmds_lite::signal::{self, Signal};
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+// Like GNU nohup, failures of nohup itself exit with 125, and a command
+// that cannot be run with 126 or 127
+const EXIT_FAILURE: i32 = 125;
+const EXIT_CANNOT_INVOKE: i32 = 126;
+const EXIT_NOT_FOUND: i32 = 127;
+
+const OUTPUT_NAME: &str = "nohup.out";
+
+fn fail(message: &str) -> ! {
+    eprintln!("nohup: {}", message);
+    std::process::exit(EXIT_FAILURE);
+}
+
+fn is_terminal(fd: i32) -> bool {
+    unsafe { libc::isatty(fd) == 1 }
+}
+
+// Makes `fd` refer to the same file as `file`
+fn redirect(file: &impl AsRawFd, fd: i32) {
+    if unsafe { libc::dup2(file.as_raw_fd(), fd) } < 0 {
+        fail(&format!(
+            "failed to redirect output: {}",
+            error::message(&io::Error::last_os_error())
+        ));
+    }
+}
+
+// nohup.out in the current directory, or in $HOME if that cannot be
+// opened. Only the owner may read what the command writes there.
+fn open_output() -> (File, String) {
+    let open = |path: &PathBuf| {
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .mode(0o600)
+            .open(path)
+    };
+    let local = PathBuf::from(OUTPUT_NAME);
+    let local_error = match open(&local) {
+        Ok(file) => return (file, OUTPUT_NAME.to_string()),
+        Err(e) => e,
+    };
+    eprintln!(
+        "nohup: failed to open '{}': {}",
+        OUTPUT_NAME,
+        error::message(&local_error)
+    );
+    let Some(home) = env::var_os("HOME") else {
+        std::process::exit(EXIT_FAILURE);
+    };
+    let path = PathBuf::from(home).join(OUTPUT_NAME);
+    match open(&path) {
+        Ok(file) => (file, path.display().to_string()),
+        Err(e) => fail(&format!(
+            "failed to open '{}': {}",
+            path.display(),
+            error::message(&e)
+        )),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut start = 1;
+    match args.get(1).map(String::as_str) {
+        Some("--") => start = 2,
+        Some(arg) if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+        Some(arg) if arg.starts_with('-') && arg != "-" => {
+            fail(&format!("invalid option -- '{}'", &arg[1..2]))
+        }
+        _ => {}
+    }
+    let command = &args[start.min(args.len())..];
+    if command.is_empty() {
+        fail("missing operand");
+    }
+
+    // Only what is connected to the terminal is redirected; anything the
+    // caller already sent elsewhere is left alone
+    let ignoring_input = is_terminal(0);
+    let redirecting_stdout = is_terminal(1);
+    let redirecting_stderr = is_terminal(2);
+
+    if ignoring_input {
+        // Opened for writing, so that reading fails rather than seeing end
+        // of file
+        match OpenOptions::new().write(true).open("/dev/null") {
+            Ok(null) => redirect(&null, 0),
+            Err(e) => fail(&format!(
+                "failed to open '/dev/null': {}",
+                error::message(&e)
+            )),
+        }
+        if !redirecting_stdout && !redirecting_stderr {
+            eprintln!("nohup: ignoring input");
+        }
+    }
+
+    if redirecting_stdout {
+        let (file, name) = open_output();
+        redirect(&file, 1);
+        if ignoring_input {
+            eprintln!("nohup: ignoring input and appending output to '{}'", name);
+        } else {
+            eprintln!("nohup: appending output to '{}'", name);
+        }
+    }
+
+    // Errors running the command still go to the terminal, through a copy
+    // of standard error that the command does not inherit
+    let mut saved_stderr = None;
+    if redirecting_stderr {
+        let fd = unsafe { libc::fcntl(2, libc::F_DUPFD_CLOEXEC, 3) };
+        if fd >= 0 {
+            saved_stderr = Some(unsafe { File::from_raw_fd(fd) });
+        }
+        if !redirecting_stdout {
+            if ignoring_input {
+                eprintln!("nohup: ignoring input and redirecting stderr to stdout");
+            } else {
+                eprintln!("nohup: redirecting stderr to stdout");
+            }
+        }
+        redirect(&io::stdout(), 2);
+    }
+
+    if let Err(e) = signal::ignore(Signal::HUP) {
+        fail(&format!("cannot ignore hangups: {}", error::message(&e)));
+    }
+
+    // exec only returns when it fails
+    let e = Command::new(&command[0]).args(&command[1..]).exec();
+    let message = format!(
+        "nohup: failed to run command '{}': {}\n",
+        command[0],
+        error::message(&e)
+    );
+    let _ = match saved_stderr.as_mut() {
+        Some(file) => file.write_all(message.as_bytes()),
+        None => io::stderr().write_all(message.as_bytes()),
+    };
+    std::process::exit(if e.kind() == io::ErrorKind::NotFound {
+        EXIT_NOT_FOUND
+    } else {
+        EXIT_CANNOT_INVOKE
+    });
+}
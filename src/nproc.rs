@@ -0,0 +1,73 @@
+use cmds_lite::platform;
+use std::env;
+
+#[derive(Default)]
+pub struct NprocOptions {
+    // --all: count every installed processor, not just the usable ones
+    pub all: bool,
+    // --ignore: leave this many out, keeping at least one
+    pub ignore: usize,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("nproc: {}", message);
+    std::process::exit(1);
+}
+
+// A positive number from an OpenMP variable; OMP_NUM_THREADS may be a
+// list, of which the first entry counts
+fn omp_variable(name: &str) -> Option<usize> {
+    let value = env::var(name).ok()?;
+    let first = value.split(',').next()?.trim();
+    first.parse().ok().filter(|&count| count > 0)
+}
+
+pub fn count(options: &NprocOptions) -> usize {
+    let count = if options.all {
+        platform::all_processors()
+    } else {
+        // Like GNU nproc, OMP_NUM_THREADS replaces the count and
+        // OMP_THREAD_LIMIT caps it
+        let count = omp_variable("OMP_NUM_THREADS").unwrap_or_else(platform::available_processors);
+        count.min(omp_variable("OMP_THREAD_LIMIT").unwrap_or(usize::MAX))
+    };
+    count.saturating_sub(options.ignore).max(1)
+}
+
+fn parse_ignore(value: &str) -> usize {
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("invalid number: '{}'", value)))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = NprocOptions::default();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            fail(&format!("extra operand '{}'", arg));
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--all" => options.all = true,
+            _ if arg.starts_with("--ignore=") => {
+                options.ignore = parse_ignore(&arg["--ignore=".len()..])
+            }
+            "--ignore" if i < args.len() => {
+                i += 1;
+                options.ignore = parse_ignore(&args[i - 1]);
+            }
+            "--ignore" => fail("option '--ignore' requires an argument"),
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => fail(&format!("invalid option -- '{}'", &arg[1..2])),
+        }
+    }
+
+    println!("{}", count(&options));
+}
@@ -0,0 +1,69 @@
+use crate::regex::Regex;
+
+// Line numbering shared by cat -n/-b and nl: which lines get a number and
+// how the number is laid out in front of them.
+
+pub enum Style {
+    // Every line
+    All,
+    // Lines that are not empty
+    NonEmpty,
+    // No line at all
+    Nothing,
+    // Lines matching a basic regular expression
+    Matching(Regex),
+}
+
+impl Style {
+    pub fn numbers(&self, line: &[u8]) -> bool {
+        match self {
+            Style::All => true,
+            Style::NonEmpty => !line.is_empty(),
+            Style::Nothing => false,
+            Style::Matching(regex) => regex.is_match(line),
+        }
+    }
+}
+
+pub struct LineNumbering {
+    start: i64,
+    next: i64,
+    increment: i64,
+    width: usize,
+    separator: String,
+}
+
+impl LineNumbering {
+    pub fn new(start: i64, increment: i64, width: usize, separator: &str) -> Self {
+        LineNumbering {
+            start,
+            next: start,
+            increment,
+            width,
+            separator: separator.to_string(),
+        }
+    }
+
+    // The right-aligned number and separator for the next numbered line
+    pub fn number(&mut self) -> String {
+        let prefix = format!(
+            "{:>width$}{}",
+            self.next,
+            self.separator,
+            width = self.width
+        );
+        self.next = self.next.saturating_add(self.increment);
+        prefix
+    }
+
+    // Blanks as wide as a number and its separator, for lines that are not
+    // numbered
+    pub fn padding(&self) -> String {
+        " ".repeat(self.width + self.separator.len())
+    }
+
+    // Starts over from the first number
+    pub fn reset(&mut self) {
+        self.next = self.start;
+    }
+}
@@ -0,0 +1,527 @@
+use cmds_lite::dump::{self, Input};
+use cmds_lite::error;
+use std::env;
+use std::io::{self, BufWriter, Write};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Hex,
+    Octal,
+    Signed,
+    Unsigned,
+    // c: printable characters and backslash escapes
+    Char,
+    // a: named characters
+    Named,
+}
+
+// One -t type: every line of input is shown once per type
+#[derive(Clone, Copy)]
+pub struct Type {
+    pub kind: Kind,
+    pub size: usize,
+    // z: show the printable characters after the fields
+    pub trailer: bool,
+}
+
+impl Type {
+    fn new(kind: Kind, size: usize) -> Self {
+        Type {
+            kind,
+            size,
+            trailer: false,
+        }
+    }
+
+    // The widest value of this type
+    fn field_width(&self) -> usize {
+        match (self.kind, self.size) {
+            (Kind::Hex, size) => size * 2,
+            (Kind::Octal, size) => (size * 8).div_ceil(3),
+            (Kind::Signed, 1) => 4,
+            (Kind::Signed, 2) => 6,
+            (Kind::Signed, 4) => 11,
+            (Kind::Unsigned, 1) => 3,
+            (Kind::Unsigned, 2) => 5,
+            (Kind::Unsigned, 4) => 10,
+            (Kind::Signed | Kind::Unsigned, _) => 20,
+            (Kind::Char | Kind::Named, _) => 3,
+        }
+    }
+
+    fn format(&self, bytes: &[u8]) -> String {
+        let mut value = [0u8; 8];
+        value[..bytes.len()].copy_from_slice(bytes);
+        let value = u64::from_le_bytes(value);
+        let width = self.field_width();
+        match self.kind {
+            Kind::Hex => format!("{:0width$x}", value),
+            Kind::Octal => format!("{:0width$o}", value),
+            Kind::Unsigned => value.to_string(),
+            Kind::Signed => {
+                // Sign-extend from the type's size
+                let shift = 64 - 8 * self.size as u32;
+                (((value << shift) as i64) >> shift).to_string()
+            }
+            Kind::Char => char_name(bytes[0]),
+            Kind::Named => named_char(bytes[0]),
+        }
+    }
+}
+
+fn char_name(byte: u8) -> String {
+    match byte {
+        0 => String::from("\\0"),
+        0x07 => String::from("\\a"),
+        0x08 => String::from("\\b"),
+        0x0c => String::from("\\f"),
+        b'\n' => String::from("\\n"),
+        b'\r' => String::from("\\r"),
+        b'\t' => String::from("\\t"),
+        0x0b => String::from("\\v"),
+        0x20..=0x7e => (byte as char).to_string(),
+        _ => format!("{:03o}", byte),
+    }
+}
+
+const CONTROL_NAMES: [&str; 33] = [
+    "nul", "soh", "stx", "etx", "eot", "enq", "ack", "bel", "bs", "ht", "nl", "vt", "ff", "cr",
+    "so", "si", "dle", "dc1", "dc2", "dc3", "dc4", "nak", "syn", "etb", "can", "em", "sub", "esc",
+    "fs", "gs", "rs", "us", "sp",
+];
+
+// -t a ignores the high bit
+fn named_char(byte: u8) -> String {
+    let byte = byte & 0x7f;
+    match byte {
+        0x7f => String::from("del"),
+        _ if (byte as usize) < CONTROL_NAMES.len() => CONTROL_NAMES[byte as usize].to_string(),
+        _ => (byte as char).to_string(),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Octal,
+    Decimal,
+    Hex,
+    None,
+}
+
+pub struct OdOptions {
+    pub types: Vec<Type>,
+    pub radix: Radix,
+    // -j: bytes to skip first
+    pub skip: u64,
+    // -N: bytes to dump at most
+    pub limit: Option<u64>,
+    // -w: bytes per line
+    pub width: Option<usize>,
+    // -v: print repeated lines instead of a '*'
+    pub verbose: bool,
+}
+
+impl Default for OdOptions {
+    fn default() -> Self {
+        OdOptions {
+            types: Vec::new(),
+            radix: Radix::Octal,
+            skip: 0,
+            limit: None,
+            width: None,
+            verbose: false,
+        }
+    }
+}
+
+// How the fields of one type are spread over a line, so that the values
+// of every type line up under the same bytes
+struct Layout {
+    kind: Type,
+    fields: usize,
+    field_width: usize,
+    pad_width: usize,
+}
+
+struct OdCommand<'a> {
+    options: &'a OdOptions,
+    bytes_per_line: usize,
+    layouts: Vec<Layout>,
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl<'a> OdCommand<'a> {
+    fn new(options: &'a OdOptions, types: &[Type]) -> Self {
+        let multiple = types
+            .iter()
+            .fold(1, |lcm, kind| lcm * kind.size / gcd(lcm, kind.size));
+        let bytes_per_line = match options.width {
+            Some(width) if width % multiple == 0 => width,
+            Some(width) => {
+                eprintln!(
+                    "od: warning: invalid width {}; using {} instead",
+                    width, multiple
+                );
+                multiple
+            }
+            None => multiple * (16 / multiple).max(1),
+        };
+
+        // Each line is as wide as the widest type needs, with at least one
+        // space before every field; narrower types get the slack
+        let line_width = types
+            .iter()
+            .map(|kind| (kind.field_width() + 1) * (bytes_per_line / kind.size))
+            .max()
+            .unwrap_or(0);
+        let layouts = types
+            .iter()
+            .map(|&kind| {
+                let fields = bytes_per_line / kind.size;
+                Layout {
+                    kind,
+                    fields,
+                    field_width: kind.field_width(),
+                    pad_width: line_width - kind.field_width() * fields,
+                }
+            })
+            .collect();
+
+        OdCommand {
+            options,
+            bytes_per_line,
+            layouts,
+        }
+    }
+
+    fn address(&self, offset: u64) -> String {
+        match self.options.radix {
+            Radix::Octal => format!("{:07o}", offset),
+            Radix::Decimal => format!("{:07}", offset),
+            Radix::Hex => format!("{:06x}", offset),
+            Radix::None => String::new(),
+        }
+    }
+
+    // `block` is zero-filled past the `read` bytes that are really there
+    fn write_line(
+        &self,
+        offset: u64,
+        block: &[u8],
+        read: usize,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let address = self.address(offset);
+        for (i, layout) in self.layouts.iter().enumerate() {
+            if i == 0 {
+                out.write_all(address.as_bytes())?;
+            } else {
+                write!(out, "{:1$}", "", address.len())?;
+            }
+
+            let size = layout.kind.size;
+            let blank = (self.bytes_per_line - read) / size;
+            let mut pad_remaining = layout.pad_width;
+            for (n, bytes) in block.chunks(size).take(layout.fields - blank).enumerate() {
+                let next_pad = layout.pad_width * (layout.fields - n - 1) / layout.fields;
+                let width = pad_remaining - next_pad + layout.field_width;
+                write!(out, "{:>1$}", layout.kind.format(bytes), width)?;
+                pad_remaining = next_pad;
+            }
+
+            if layout.kind.trailer {
+                let padding = layout.field_width * blank + layout.pad_width * blank / layout.fields;
+                let text: String = block[..read].iter().map(|&b| dump::printable(b)).collect();
+                write!(out, "{:1$}  >{2}<", "", padding, text)?;
+            }
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn run(&self, input: &mut Input, out: &mut impl Write) -> io::Result<bool> {
+        let mut ok = true;
+        let mut report = |input: &mut Input, out: &mut dyn Write| -> io::Result<()> {
+            for (name, e) in input.take_errors() {
+                out.flush()?;
+                eprintln!("od: {}: {}", name, error::message(&e));
+                ok = false;
+            }
+            Ok(())
+        };
+
+        let skipped = input.skip(self.options.skip);
+        report(input, out)?;
+        if skipped < self.options.skip {
+            out.flush()?;
+            eprintln!("od: cannot skip past end of combined input");
+            return Ok(false);
+        }
+
+        let mut offset = self.options.skip;
+        let mut remaining = self.options.limit.unwrap_or(u64::MAX);
+        let mut block = vec![0u8; self.bytes_per_line];
+        let mut previous: Option<Vec<u8>> = None;
+        let mut squeezed = false;
+        while remaining > 0 {
+            let wanted = remaining.min(self.bytes_per_line as u64) as usize;
+            block.fill(0);
+            let read = input.read_block(&mut block[..wanted]);
+            report(input, out)?;
+            if read == 0 {
+                break;
+            }
+
+            // A full line like the one before is shown as a single '*'
+            if !self.options.verbose && read == self.bytes_per_line {
+                if previous.as_deref() == Some(&block[..]) {
+                    if !squeezed {
+                        out.write_all(b"*\n")?;
+                        squeezed = true;
+                    }
+                } else {
+                    self.write_line(offset, &block, read, out)?;
+                    squeezed = false;
+                }
+                previous = Some(block.clone());
+            } else {
+                self.write_line(offset, &block, read, out)?;
+            }
+            offset += read as u64;
+            remaining -= read as u64;
+        }
+
+        if self.options.radix != Radix::None {
+            writeln!(out, "{}", self.address(offset))?;
+        }
+        out.flush()?;
+        Ok(ok)
+    }
+}
+
+pub fn run(files: &[String], options: &OdOptions) -> io::Result<bool> {
+    let default = [Type::new(Kind::Octal, 2)];
+    let types = if options.types.is_empty() {
+        &default[..]
+    } else {
+        &options.types
+    };
+    let command = OdCommand::new(options, types);
+    let mut input = Input::new(files);
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    command.run(&mut input, &mut out)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("od: {}", message);
+    std::process::exit(1);
+}
+
+// -t: one or more types, each a letter with an optional size and z
+fn parse_types(text: &str) -> Result<Vec<Type>, String> {
+    let invalid = |c: char| format!("invalid character '{}' in type string '{}'", c, text);
+    let mut types = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let kind = match c {
+            'x' => Kind::Hex,
+            'o' => Kind::Octal,
+            'd' => Kind::Signed,
+            'u' => Kind::Unsigned,
+            'c' => Kind::Char,
+            'a' => Kind::Named,
+            _ => return Err(invalid(c)),
+        };
+        let mut size = 1;
+        if !matches!(kind, Kind::Char | Kind::Named) {
+            let letter = match chars.peek() {
+                Some('C') => Some(1),
+                Some('S') => Some(2),
+                Some('I') => Some(4),
+                Some('L') => Some(8),
+                _ => None,
+            };
+            let mut digits = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                digits.push(c);
+                chars.next();
+            }
+            size = if let Some(size) = letter {
+                chars.next();
+                size
+            } else if digits.is_empty() {
+                4
+            } else {
+                match digits.parse::<usize>() {
+                    Ok(size @ (1 | 2 | 4 | 8)) => size,
+                    _ => {
+                        return Err(format!(
+                            "invalid type string '{}';\nthis system doesn't provide a {}-byte integral type",
+                            text, digits
+                        ))
+                    }
+                }
+            };
+        }
+        let mut kind = Type::new(kind, size);
+        if chars.peek() == Some(&'z') {
+            chars.next();
+            kind.trailer = true;
+        }
+        types.push(kind);
+    }
+    Ok(types)
+}
+
+fn parse_width(text: &str) -> usize {
+    match text.parse::<usize>() {
+        Ok(width) if width > 0 => width,
+        _ => fail(&format!("invalid -w argument '{}'", text)),
+    }
+}
+
+fn set_option(options: &mut OdOptions, option: char, value: &str, ok: &mut bool) {
+    match option {
+        'A' => {
+            options.radix = match value {
+                "o" => Radix::Octal,
+                "d" => Radix::Decimal,
+                "x" => Radix::Hex,
+                "n" => Radix::None,
+                _ => fail(&format!(
+                    "invalid output address radix '{}'; it must be one character from [doxn]",
+                    value
+                )),
+            }
+        }
+        'j' => {
+            options.skip = dump::parse_count(value)
+                .unwrap_or_else(|| fail(&format!("invalid -j argument '{}'", value)))
+        }
+        'N' => {
+            options.limit = Some(
+                dump::parse_count(value)
+                    .unwrap_or_else(|| fail(&format!("invalid -N argument '{}'", value))),
+            )
+        }
+        _ => match parse_types(value) {
+            Ok(types) => options.types.extend(types),
+            Err(message) => {
+                eprintln!("od: {}", message);
+                *ok = false;
+            }
+        },
+    }
+}
+
+const LONG_OPTIONS: [(&str, char); 4] = [
+    ("--address-radix=", 'A'),
+    ("--skip-bytes=", 'j'),
+    ("--read-bytes=", 'N'),
+    ("--format=", 't'),
+];
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = OdOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut types_ok = true;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--output-duplicates" => options.verbose = true,
+            "--width" => options.width = Some(32),
+            _ if arg.starts_with("--width=") => {
+                options.width = Some(parse_width(&arg["--width=".len()..]))
+            }
+            _ if arg.starts_with("--") => {
+                match LONG_OPTIONS.iter().find(|(name, _)| arg.starts_with(name)) {
+                    Some((name, option)) => {
+                        set_option(&mut options, *option, &arg[name.len()..], &mut types_ok)
+                    }
+                    None => fail(&format!("unrecognized option '{}'", arg)),
+                }
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    let traditional = match flag {
+                        'a' => Some(Type::new(Kind::Named, 1)),
+                        'b' => Some(Type::new(Kind::Octal, 1)),
+                        'c' => Some(Type::new(Kind::Char, 1)),
+                        'd' => Some(Type::new(Kind::Unsigned, 2)),
+                        'i' => Some(Type::new(Kind::Signed, 4)),
+                        'l' => Some(Type::new(Kind::Signed, 8)),
+                        'o' => Some(Type::new(Kind::Octal, 2)),
+                        's' => Some(Type::new(Kind::Signed, 2)),
+                        'x' => Some(Type::new(Kind::Hex, 2)),
+                        _ => None,
+                    };
+                    if let Some(kind) = traditional {
+                        options.types.push(kind);
+                        continue;
+                    }
+                    match flag {
+                        'v' => options.verbose = true,
+                        // The width is optional, so it can only be attached
+                        'w' => {
+                            let rest = &arg[pos + 1..];
+                            options.width = Some(if rest.is_empty() {
+                                32
+                            } else {
+                                parse_width(rest)
+                            });
+                            break;
+                        }
+                        'A' | 'j' | 'N' | 't' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            set_option(&mut options, flag, &value, &mut types_ok);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if !types_ok {
+        std::process::exit(1);
+    }
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    match run(&files, &options) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(1),
+        Err(e) => fail(&format!("write error: {}", error::message(&e))),
+    }
+}
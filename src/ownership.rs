@@ -0,0 +1,282 @@
+use crate::error;
+use crate::platform;
+use crate::users;
+use crate::walk::{WalkOptions, Walker};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+// The shared core of chown and chgrp: which IDs to set, and walking the
+// operands to set them.
+
+// The requested owner. The texts are kept as given for -v and -c output;
+// chgrp has no user text at all, which switches its messages to "group".
+#[derive(Default)]
+pub struct Owner {
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub user_text: Option<String>,
+    pub group_text: Option<String>,
+}
+
+impl Owner {
+    // Parses chown's USER[:GROUP] form. `USER:` means the user's login
+    // group, and either part may be left empty.
+    pub fn parse(spec: &str) -> Result<Owner, String> {
+        let (user_part, group_part) = match spec.split_once(':') {
+            Some((user, group)) => (user, Some(group)),
+            None => (spec, None),
+        };
+
+        let mut owner = Owner {
+            user_text: Some(user_part.to_string()),
+            ..Owner::default()
+        };
+        let mut login_group = None;
+        if !user_part.is_empty() {
+            let user =
+                users::parse_user(user_part).ok_or_else(|| format!("invalid user: '{}'", spec))?;
+            owner.uid = Some(user.uid);
+            login_group = Some(user.gid);
+        }
+
+        match group_part {
+            Some("") => {
+                if let Some(gid) = login_group {
+                    owner.gid = Some(gid);
+                    owner.group_text = Some(users::group_name(gid));
+                }
+            }
+            Some(group) => {
+                let gid = users::parse_group(group)
+                    .ok_or_else(|| format!("invalid group: '{}'", spec))?;
+                owner.gid = Some(gid);
+                owner.group_text = Some(group.to_string());
+            }
+            None => {}
+        }
+        Ok(owner)
+    }
+
+    fn describe(&self, user: Option<String>, group: Option<String>) -> String {
+        match (user, group) {
+            (Some(user), Some(group)) => format!("{}:{}", user, group),
+            (Some(user), None) => user,
+            (None, Some(group)) => group,
+            (None, None) => String::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Reporting {
+    #[default]
+    Silent,
+    // -c: only files whose ownership actually changed
+    Changes,
+    // -v: every file processed
+    Verbose,
+}
+
+pub struct OwnershipOptions {
+    pub recursive: bool,
+    // Change what a symlink points to rather than the link (unset by -h)
+    pub dereference: bool,
+    pub reporting: Reporting,
+    // -f: suppress most error messages
+    pub quiet: bool,
+    // --from: only change files currently owned by these IDs
+    pub from_uid: Option<u32>,
+    pub from_gid: Option<u32>,
+}
+
+impl Default for OwnershipOptions {
+    fn default() -> Self {
+        OwnershipOptions {
+            recursive: false,
+            dereference: true,
+            reporting: Reporting::Silent,
+            quiet: false,
+            from_uid: None,
+            from_gid: None,
+        }
+    }
+}
+
+enum Status {
+    Changed,
+    Retained,
+    // Skipped because of --from
+    NotApplied,
+    // Neither a user nor a group was given
+    NothingRequested,
+    Failed,
+}
+
+pub struct OwnershipCommand<'a> {
+    program: &'a str,
+    owner: &'a Owner,
+    options: &'a OwnershipOptions,
+}
+
+impl<'a> OwnershipCommand<'a> {
+    pub fn new(program: &'a str, owner: &'a Owner, options: &'a OwnershipOptions) -> Self {
+        OwnershipCommand {
+            program,
+            owner,
+            options,
+        }
+    }
+
+    pub fn run(&self, files: &[String]) -> bool {
+        let mut ok = true;
+        for file in files {
+            ok &= if self.options.recursive {
+                self.change_tree(file)
+            } else {
+                self.change_operand(Path::new(file))
+            };
+        }
+        ok
+    }
+
+    fn change_operand(&self, path: &Path) -> bool {
+        let metadata = if self.options.dereference {
+            fs::metadata(path)
+        } else {
+            fs::symlink_metadata(path)
+        };
+        match metadata {
+            Ok(metadata) => self.change(path, &metadata, self.options.dereference),
+            Err(e) => {
+                self.report_error(&format!(
+                    "cannot access '{}': {}",
+                    path.display(),
+                    error::message(&e)
+                ));
+                self.report(path, None, Status::Failed);
+                false
+            }
+        }
+    }
+
+    // Like GNU chown -R, no symlink is followed, named or found. Directories
+    // are changed after their contents.
+    fn change_tree(&self, root: &str) -> bool {
+        let walk_options = WalkOptions {
+            contents_first: true,
+            ..WalkOptions::default()
+        };
+        let mut ok = true;
+        for item in Walker::new(&[root], walk_options) {
+            match item {
+                Ok(entry) => ok &= self.change(&entry.path, &entry.metadata, false),
+                Err(e) => {
+                    let message = if e.path == Path::new(root) {
+                        format!("cannot access '{}'", root)
+                    } else {
+                        format!("cannot read directory '{}'", e.path.display())
+                    };
+                    self.report_error(&format!("{}: {}", message, error::message(&e.error)));
+                    ok = false;
+                }
+            }
+        }
+        ok
+    }
+
+    fn change(&self, path: &Path, metadata: &fs::Metadata, follow: bool) -> bool {
+        let matches_from = self
+            .options
+            .from_uid
+            .is_none_or(|uid| uid == metadata.uid())
+            && self
+                .options
+                .from_gid
+                .is_none_or(|gid| gid == metadata.gid());
+        if !matches_from {
+            self.report(path, Some(metadata), Status::NotApplied);
+            return true;
+        }
+        if self.owner.uid.is_none() && self.owner.gid.is_none() {
+            self.report(path, Some(metadata), Status::NothingRequested);
+            return true;
+        }
+
+        // The call is made even when nothing changes, since chown also
+        // clears set-ID bits
+        if let Err(e) = platform::change_owner(path, self.owner.uid, self.owner.gid, follow) {
+            let what = if self.owner.uid.is_some() {
+                "ownership"
+            } else {
+                "group"
+            };
+            self.report_error(&format!(
+                "changing {} of '{}': {}",
+                what,
+                path.display(),
+                error::message(&e)
+            ));
+            self.report(path, Some(metadata), Status::Failed);
+            return false;
+        }
+
+        let unchanged = self.owner.uid.is_none_or(|uid| uid == metadata.uid())
+            && self.owner.gid.is_none_or(|gid| gid == metadata.gid());
+        let status = if unchanged {
+            Status::Retained
+        } else {
+            Status::Changed
+        };
+        self.report(path, Some(metadata), status);
+        true
+    }
+
+    fn report(&self, path: &Path, metadata: Option<&fs::Metadata>, status: Status) {
+        let wanted = match status {
+            Status::Changed => self.options.reporting != Reporting::Silent,
+            _ => self.options.reporting == Reporting::Verbose,
+        };
+        if !wanted {
+            return;
+        }
+
+        let what = if self.owner.user_text.is_some() {
+            "ownership"
+        } else {
+            "group"
+        };
+        let new = self
+            .owner
+            .describe(self.owner.user_text.clone(), self.owner.group_text.clone());
+        let old = metadata.map(|metadata| {
+            self.owner.describe(
+                self.owner
+                    .user_text
+                    .as_ref()
+                    .map(|_| users::user_name(metadata.uid())),
+                self.owner
+                    .group_text
+                    .as_ref()
+                    .map(|_| users::group_name(metadata.gid())),
+            )
+        });
+        let old = old.unwrap_or_default();
+        let path = path.display();
+
+        match status {
+            Status::Changed => println!("changed {} of '{}' from {} to {}", what, path, old, new),
+            Status::Retained | Status::NotApplied => {
+                println!("{} of '{}' retained as {}", what, path, old)
+            }
+            Status::NothingRequested => println!("ownership of '{}' retained", path),
+            Status::Failed => println!("failed to change {} of '{}' to {}", what, path, new),
+        }
+    }
+
+    fn report_error(&self, message: &str) {
+        if !self.options.quiet {
+            eprintln!("{}: {}", self.program, message);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use cmds_lite::error;
+use cmds_lite::regex::{Regex, Syntax};
+use cmds_lite::tty::{self, RawMode};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Read, StdoutLock, Write};
+
+#[derive(Default)]
+pub struct PagerOptions {
+    // -F: just print text that fits on one screen
+    pub quit_if_one_screen: bool,
+}
+
+const CONTROL_C: u8 = 0x03;
+const ESCAPE: u8 = 0x1b;
+
+// What a key asks for
+enum Key {
+    Byte(u8),
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("pager: {}", message);
+    std::process::exit(1);
+}
+
+// A line as it is shown: tabs expanded, control characters as ^X and
+// color sequences kept, cut into rows of the screen width. Color
+// sequences take up no room.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    let mut column = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&'[') {
+            row.push(c);
+            for c in chars.by_ref() {
+                row.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        let text = match c {
+            '\t' => " ".repeat(8 - column % 8),
+            _ if c.is_control() => format!("^{}", ((c as u8) ^ 0x40) as char),
+            _ => c.to_string(),
+        };
+        for c in text.chars() {
+            if column == width {
+                rows.push(std::mem::take(&mut row));
+                column = 0;
+            }
+            row.push(c);
+            column += 1;
+        }
+    }
+    rows.push(row);
+    rows
+}
+
+struct PagerCommand<'a> {
+    lines: &'a [String],
+    // The lines cut to the screen width, with the line each row is from
+    rows: Vec<(usize, String)>,
+    size: tty::Size,
+    // The first row on the screen
+    top: usize,
+    // The last search pattern, highlighted where it matches
+    pattern: Option<Regex>,
+    // A note for the prompt line, such as "Pattern not found"
+    message: Option<String>,
+    name: Option<String>,
+    terminal: File,
+    out: BufWriter<StdoutLock<'static>>,
+}
+
+impl<'a> PagerCommand<'a> {
+    fn layout(&mut self) {
+        let line = self.rows.get(self.top).map_or(0, |&(line, _)| line);
+        self.rows = self
+            .lines
+            .iter()
+            .enumerate()
+            .flat_map(|(i, text)| {
+                wrap(text, self.size.columns)
+                    .into_iter()
+                    .map(move |row| (i, row))
+            })
+            .collect();
+        // The line at the top stays at the top
+        self.top = self.rows.iter().position(|&(i, _)| i >= line).unwrap_or(0);
+    }
+
+    // Rows of text on a screen, leaving the last for the prompt
+    fn page(&self) -> usize {
+        self.size.rows.saturating_sub(1).max(1)
+    }
+
+    fn last_top(&self) -> usize {
+        self.rows.len().saturating_sub(self.page())
+    }
+
+    fn scroll_to(&mut self, top: usize) {
+        self.top = top.min(self.last_top());
+    }
+
+    fn highlight(&self, row: &str) -> String {
+        let Some(pattern) = &self.pattern else {
+            return row.to_string();
+        };
+        let bytes = row.as_bytes();
+        let mut text = String::new();
+        let mut from = 0;
+        while let Some((start, end)) = pattern.find_at(bytes, from) {
+            if end == start || !row.is_char_boundary(start) || !row.is_char_boundary(end) {
+                break;
+            }
+            text.push_str(&row[from..start]);
+            text.push_str(tty::REVERSE);
+            text.push_str(&row[start..end]);
+            text.push_str(tty::RESET);
+            from = end;
+        }
+        text.push_str(&row[from..]);
+        text
+    }
+
+    fn prompt(&self) -> String {
+        if let Some(message) = &self.message {
+            return message.clone();
+        }
+        let bottom = (self.top + self.page()).min(self.rows.len());
+        if bottom == self.rows.len() {
+            return String::from("(END)");
+        }
+        let percent = bottom * 100 / self.rows.len().max(1);
+        match &self.name {
+            Some(name) => format!("{} --More--({}%)", name, percent),
+            None => format!("--More--({}%)", percent),
+        }
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        let mut screen = String::from(tty::CLEAR);
+        let end = (self.top + self.page()).min(self.rows.len());
+        for (_, row) in &self.rows[self.top..end] {
+            screen.push_str(&self.highlight(row));
+            screen.push_str(tty::RESET);
+            screen.push('\n');
+        }
+        for _ in end - self.top..self.page() {
+            screen.push_str("~\n");
+        }
+        screen.push_str(tty::REVERSE);
+        screen.push_str(&self.prompt());
+        screen.push_str(tty::RESET);
+        self.out.write_all(screen.as_bytes())?;
+        self.out.flush()
+    }
+
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0];
+        if self.terminal.read(&mut byte)? == 0 {
+            return Ok(b'q');
+        }
+        Ok(byte[0])
+    }
+
+    // A key, with the escape sequences of arrow and paging keys decoded
+    fn read_key(&mut self) -> io::Result<Key> {
+        let byte = self.read_byte()?;
+        if byte != ESCAPE {
+            return Ok(Key::Byte(byte));
+        }
+        if self.read_byte()? != b'[' {
+            return Ok(Key::Byte(ESCAPE));
+        }
+        let key = match self.read_byte()? {
+            b'A' => Key::Up,
+            b'B' => Key::Down,
+            b'H' => Key::Home,
+            b'F' => Key::End,
+            code @ b'1'..=b'8' => {
+                // "ESC [ 5 ~" and the like
+                let _ = self.read_byte()?;
+                match code {
+                    b'5' => Key::PageUp,
+                    b'6' => Key::PageDown,
+                    b'1' | b'7' => Key::Home,
+                    b'4' | b'8' => Key::End,
+                    _ => Key::Byte(ESCAPE),
+                }
+            }
+            _ => Key::Byte(ESCAPE),
+        };
+        Ok(key)
+    }
+
+    // Reads a search pattern on the prompt line; None if cancelled
+    fn read_pattern(&mut self) -> io::Result<Option<String>> {
+        let mut pattern = Vec::new();
+        loop {
+            let text = String::from_utf8_lossy(&pattern).into_owned();
+            write!(self.out, "{}/{}", tty::CLEAR_LINE, text)?;
+            self.out.flush()?;
+            match self.read_byte()? {
+                b'\r' | b'\n' => return Ok(Some(text)),
+                ESCAPE | CONTROL_C => return Ok(None),
+                // Backspace over nothing cancels the search
+                0x7f | 0x08 if pattern.is_empty() => return Ok(None),
+                0x7f | 0x08 => {
+                    // A whole UTF-8 character goes
+                    while pattern.pop().is_some_and(|byte| byte & 0xc0 == 0x80) {}
+                }
+                byte if byte >= 0x20 => pattern.push(byte),
+                _ => {}
+            }
+        }
+    }
+
+    // Moves the next (or previous) row matching the pattern to the top
+    fn search(&mut self, forward: bool) {
+        let Some(pattern) = &self.pattern else {
+            self.message = Some(String::from("No previous search pattern"));
+            return;
+        };
+        let found = if forward {
+            (self.top + 1..self.rows.len()).find(|&i| pattern.is_match(self.rows[i].1.as_bytes()))
+        } else {
+            (0..self.top)
+                .rev()
+                .find(|&i| pattern.is_match(self.rows[i].1.as_bytes()))
+        };
+        match found {
+            Some(row) => self.scroll_to(row),
+            None => self.message = Some(String::from("Pattern not found")),
+        }
+    }
+
+    fn run(&mut self) -> io::Result<()> {
+        let _raw = RawMode::enable(&self.terminal)?;
+        loop {
+            if let Some(size) = tty::size() {
+                if size.columns != self.size.columns || size.rows != self.size.rows {
+                    self.size = size;
+                    self.layout();
+                    self.scroll_to(self.top);
+                }
+            }
+            self.draw()?;
+            self.message = None;
+
+            let page = self.page();
+            match self.read_key()? {
+                Key::Byte(b'q' | b'Q' | CONTROL_C) => return Ok(()),
+                Key::Byte(b' ' | b'f' | 0x06 | 0x16) | Key::PageDown => {
+                    self.scroll_to(self.top + page)
+                }
+                Key::Byte(b'b' | 0x02) | Key::PageUp => self.top = self.top.saturating_sub(page),
+                Key::Byte(b'\r' | b'\n' | b'j' | b'e') | Key::Down => self.scroll_to(self.top + 1),
+                Key::Byte(b'k' | b'y') | Key::Up => self.top = self.top.saturating_sub(1),
+                Key::Byte(b'd') => self.scroll_to(self.top + page / 2),
+                Key::Byte(b'u') => self.top = self.top.saturating_sub(page / 2),
+                Key::Byte(b'g' | b'<') | Key::Home => self.top = 0,
+                Key::Byte(b'G' | b'>') | Key::End => self.top = self.last_top(),
+                Key::Byte(b'/') => {
+                    if let Some(text) = self.read_pattern()? {
+                        // An empty pattern repeats the last search
+                        if !text.is_empty() {
+                            match Regex::new(&text, Syntax::Extended, false) {
+                                Ok(pattern) => self.pattern = Some(pattern),
+                                Err(e) => {
+                                    self.message = Some(e);
+                                    continue;
+                                }
+                            }
+                        }
+                        self.search(true);
+                    }
+                }
+                Key::Byte(b'n') => self.search(true),
+                Key::Byte(b'N') => self.search(false),
+                _ => {}
+            }
+        }
+    }
+}
+
+// Everything to page through, read in full: the files in turn, or
+// standard input
+fn read_input(files: &[String]) -> Vec<u8> {
+    let mut input = Vec::new();
+    if files.is_empty() {
+        if let Err(e) = io::stdin().lock().read_to_end(&mut input) {
+            fail(&format!("standard input: {}", error::message(&e)));
+        }
+    }
+    for file in files {
+        let result = File::open(file).and_then(|mut f| f.read_to_end(&mut input));
+        if let Err(e) = result {
+            fail(&format!("{}: {}", file, error::message(&e)));
+        }
+    }
+    input
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = PagerOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            files.push(arg.clone());
+            continue;
+        }
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--quit-if-one-screen" => options.quit_if_one_screen = true,
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'F' => options.quit_if_one_screen = true,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if files.iter().any(|file| file == "-") {
+        fail("reading standard input as a file operand is not supported");
+    }
+
+    let input = read_input(&files);
+    let stdout = io::stdout();
+
+    // Not on a terminal there is nothing to page: the text passes through
+    let size = tty::size().filter(|_| stdout.is_terminal());
+    let terminal = File::options().read(true).write(true).open("/dev/tty");
+    let (Some(size), Ok(terminal)) = (size, terminal) else {
+        match stdout.lock().write_all(&input) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => fail(&e.to_string()),
+            _ => return,
+        }
+    };
+
+    let text = String::from_utf8_lossy(&input);
+    let lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let mut command = PagerCommand {
+        lines: &lines,
+        rows: Vec::new(),
+        size,
+        top: 0,
+        pattern: None,
+        message: None,
+        name: (files.len() == 1).then(|| files[0].clone()),
+        terminal,
+        out: BufWriter::new(stdout.lock()),
+    };
+    command.layout();
+
+    if options.quit_if_one_screen && command.rows.len() <= command.page() {
+        let result = command
+            .out
+            .write_all(&input)
+            .and_then(|_| command.out.flush());
+        if let Err(e) = result {
+            fail(&e.to_string());
+        }
+        return;
+    }
+
+    let _ = write!(command.out, "{}", tty::ALTERNATE_SCREEN);
+    let result = command.run();
+    let _ = write!(command.out, "{}", tty::NORMAL_SCREEN);
+    let _ = command.out.flush();
+    if let Err(e) = result {
+        fail(&e.to_string());
+    }
+}
@@ -0,0 +1,269 @@
+use cmds_lite::error;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, StdinLock, Write};
+
+pub struct PasteOptions {
+    // Used in turn between columns; an empty one puts nothing there
+    pub delimiters: Vec<Vec<u8>>,
+    // -s: paste the lines of each file into one line instead
+    pub serial: bool,
+}
+
+impl Default for PasteOptions {
+    fn default() -> Self {
+        PasteOptions {
+            delimiters: vec![b"\t".to_vec()],
+            serial: false,
+        }
+    }
+}
+
+// Every - operand reads from the same standard input, taking turns
+enum Input {
+    Stdin,
+    File(BufReader<File>),
+}
+
+struct Source {
+    name: String,
+    input: Input,
+    finished: bool,
+}
+
+struct PasteCommand<'a> {
+    options: &'a PasteOptions,
+    stdin: StdinLock<'static>,
+    ok: bool,
+}
+
+impl<'a> PasteCommand<'a> {
+    fn new(options: &'a PasteOptions) -> Self {
+        PasteCommand {
+            options,
+            stdin: io::stdin().lock(),
+            ok: true,
+        }
+    }
+
+    // The next line of a source without its newline; None once it has
+    // run out. A read error ends the source as if it were empty.
+    fn read_line(&mut self, source: &mut Source, line: &mut Vec<u8>) -> bool {
+        if source.finished {
+            return false;
+        }
+        line.clear();
+        let read = match &mut source.input {
+            Input::Stdin => self.stdin.read_until(b'\n', line),
+            Input::File(reader) => reader.read_until(b'\n', line),
+        };
+        match read {
+            Ok(0) => {
+                source.finished = true;
+                false
+            }
+            Ok(_) => {
+                if line.last() == Some(&b'\n') {
+                    line.pop();
+                }
+                true
+            }
+            Err(e) => {
+                eprintln!("paste: {}: {}", source.name, error::message(&e));
+                self.ok = false;
+                source.finished = true;
+                false
+            }
+        }
+    }
+
+    fn delimiter(&self, index: usize) -> &[u8] {
+        &self.options.delimiters[index % self.options.delimiters.len()]
+    }
+
+    // One output line per input line, with a column for each file; files
+    // that have run out contribute empty columns
+    fn paste_parallel(&mut self, sources: &mut [Source], out: &mut impl Write) -> io::Result<()> {
+        let mut line = Vec::new();
+        let mut output = Vec::new();
+        loop {
+            output.clear();
+            let mut any = false;
+            for i in 0..sources.len() {
+                if self.read_line(&mut sources[i], &mut line) {
+                    output.extend_from_slice(&line);
+                    any = true;
+                }
+                if i + 1 < sources.len() {
+                    output.extend_from_slice(self.delimiter(i));
+                }
+            }
+            if !any {
+                return Ok(());
+            }
+            output.push(b'\n');
+            out.write_all(&output)?;
+        }
+    }
+
+    // -s: all lines of a file on one output line
+    fn paste_serial(&mut self, sources: &mut [Source], out: &mut impl Write) -> io::Result<()> {
+        let mut line = Vec::new();
+        for source in sources.iter_mut() {
+            let mut count = 0;
+            while self.read_line(source, &mut line) {
+                if count > 0 {
+                    out.write_all(self.delimiter(count - 1))?;
+                }
+                out.write_all(&line)?;
+                count += 1;
+            }
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn run(&mut self, files: &[String]) -> io::Result<bool> {
+        // Every file is opened before anything is written
+        let mut sources = Vec::new();
+        for name in files {
+            let input = if name == "-" {
+                Input::Stdin
+            } else {
+                match File::open(name) {
+                    Ok(file) => Input::File(BufReader::new(file)),
+                    Err(e) => {
+                        eprintln!("paste: {}: {}", name, error::message(&e));
+                        return Ok(false);
+                    }
+                }
+            };
+            sources.push(Source {
+                name: name.clone(),
+                input,
+                finished: false,
+            });
+        }
+
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        if self.options.serial {
+            self.paste_serial(&mut sources, &mut out)?;
+        } else {
+            self.paste_parallel(&mut sources, &mut out)?;
+        }
+        out.flush()?;
+        Ok(self.ok)
+    }
+}
+
+// The -d list: \n, \t, \\ and \0 (no delimiter) are escapes, and any
+// other escaped character stands for itself
+fn parse_delimiters(list: &str) -> Result<Vec<Vec<u8>>, String> {
+    let bytes = list.as_bytes();
+    let mut delimiters = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            delimiters.push(vec![bytes[i]]);
+            i += 1;
+            continue;
+        }
+        let Some(&escape) = bytes.get(i + 1) else {
+            return Err(format!(
+                "delimiter list ends with an unescaped backslash: {}",
+                list
+            ));
+        };
+        delimiters.push(match escape {
+            b'0' => Vec::new(),
+            b'n' => b"\n".to_vec(),
+            b't' => b"\t".to_vec(),
+            b'b' => b"\x08".to_vec(),
+            b'f' => b"\x0c".to_vec(),
+            b'r' => b"\r".to_vec(),
+            b'v' => b"\x0b".to_vec(),
+            other => vec![other],
+        });
+        i += 2;
+    }
+    // An empty list means no delimiter at all
+    if delimiters.is_empty() {
+        delimiters.push(Vec::new());
+    }
+    Ok(delimiters)
+}
+
+pub fn run(files: &[String], options: &PasteOptions) -> io::Result<bool> {
+    let mut command = PasteCommand::new(options);
+    command.run(files)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("paste: {}", message);
+    std::process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = PasteOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    let set_delimiters = |options: &mut PasteOptions, list: &str| match parse_delimiters(list) {
+        Ok(delimiters) => options.delimiters = delimiters,
+        Err(message) => fail(&message),
+    };
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--serial" => options.serial = true,
+            _ if arg.starts_with("--delimiters=") => {
+                set_delimiters(&mut options, &arg["--delimiters=".len()..]);
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        's' => options.serial = true,
+                        'd' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let list = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail("option requires an argument -- 'd'");
+                            };
+                            set_delimiters(&mut options, &list);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    match run(&files, &options) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(1),
+        Err(e) => fail(&format!("write error: {}", error::message(&e))),
+    }
+}
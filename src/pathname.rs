@@ -0,0 +1,75 @@
+// Splitting of path strings the way POSIX basename and dirname do it,
+// which differs from std::path for trailing slashes and the root: the
+// base name of "dir/" is "dir" and the directory of "/" is "/".
+
+fn trim_trailing_slashes(path: &str) -> &str {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() && !path.is_empty() {
+        // Only slashes: the root
+        "/"
+    } else {
+        trimmed
+    }
+}
+
+pub fn base_name(path: &str) -> &str {
+    let trimmed = trim_trailing_slashes(path);
+    if trimmed == "/" {
+        return trimmed;
+    }
+    match trimmed.rfind('/') {
+        Some(pos) => &trimmed[pos + 1..],
+        None => trimmed,
+    }
+}
+
+pub fn dir_name(path: &str) -> &str {
+    let trimmed = trim_trailing_slashes(path);
+    if trimmed == "/" {
+        return trimmed;
+    }
+    match trimmed.rfind('/') {
+        Some(pos) => {
+            let parent = trim_trailing_slashes(&trimmed[..pos]);
+            if parent.is_empty() {
+                "/"
+            } else {
+                parent
+            }
+        }
+        None => ".",
+    }
+}
+
+// Removes `suffix` from a base name, unless it is the whole name
+pub fn strip_suffix<'a>(name: &'a str, suffix: &str) -> &'a str {
+    match name.strip_suffix(suffix) {
+        Some(stripped) if !stripped.is_empty() => stripped,
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_like_posix() {
+        for (path, base, dir) in [
+            ("/usr/lib", "lib", "/usr"),
+            ("/usr/", "usr", "/"),
+            ("usr", "usr", "."),
+            ("dir/", "dir", "."),
+            ("/", "/", "/"),
+            ("//", "/", "/"),
+            ("a//b//", "b", "a"),
+            ("", "", "."),
+        ] {
+            assert_eq!(base_name(path), base, "{}", path);
+            assert_eq!(dir_name(path), dir, "{}", path);
+        }
+        assert_eq!(strip_suffix("notes.txt", ".txt"), "notes");
+        assert_eq!(strip_suffix(".txt", ".txt"), ".txt");
+        assert_eq!(strip_suffix("notes", ".txt"), "notes");
+    }
+}
@@ -0,0 +1,533 @@
+use crate::datetime::Timestamp;
+use std::env;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs as unix_fs;
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::ExitStatus;
+use std::time::Duration;
+
+// Operating system calls that have no portable equivalent in std, kept in
+// one place so the commands themselves stay free of per-platform code.
+
+// Changes the owner and/or group of a file; None leaves that ID alone.
+// With `follow` unset a symlink itself is changed, not its target.
+pub fn change_owner(
+    path: &Path,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    follow: bool,
+) -> io::Result<()> {
+    if follow {
+        unix_fs::chown(path, uid, gid)
+    } else {
+        unix_fs::lchown(path, uid, gid)
+    }
+}
+
+// The filesystem type magic number statfs reports, such as 0xEF53 for ext4
+pub fn filesystem_type(path: &Path) -> io::Result<u64> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains NUL"))?;
+    let mut stats: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stats.f_type as u64)
+}
+
+// Splits a device number into its major and minor parts
+pub fn device_numbers(device: u64) -> (u32, u32) {
+    #[allow(clippy::useless_conversion)]
+    let device = device.into();
+    (libc::major(device), libc::minor(device))
+}
+
+// Bytes available for arguments on one command line: ARG_MAX minus the
+// environment and some headroom, capped like the GNU find and xargs default
+pub fn argument_space() -> usize {
+    let arg_max = unsafe { libc::sysconf(libc::_SC_ARG_MAX) };
+    let arg_max = if arg_max > 0 { arg_max as usize } else { 128 * 1024 };
+    let environment: usize = env::vars_os()
+        .map(|(key, value)| key.len() + value.len() + 2 + std::mem::size_of::<usize>())
+        .sum();
+    arg_max
+        .saturating_sub(environment)
+        .saturating_sub(2048)
+        .clamp(4096, 128 * 1024)
+}
+
+// The width of the terminal standard output is on: $COLUMNS if it is set
+// to a number, otherwise what the terminal reports
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        if columns > 0 {
+            return Some(columns);
+        }
+    }
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } != 0
+        || size.ws_col == 0
+    {
+        return None;
+    }
+    Some(size.ws_col as usize)
+}
+
+// Sets the access and modification times of a file. With `follow` unset
+// a symlink itself is changed, not its target.
+pub fn set_times(path: &Path, atime: Timestamp, mtime: Timestamp, follow: bool) -> io::Result<()> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains NUL"))?;
+    let times = [atime, mtime].map(|stamp| libc::timespec {
+        tv_sec: stamp.secs as libc::time_t,
+        tv_nsec: stamp.nanos as libc::c_long,
+    });
+    let flags = if follow { 0 } else { libc::AT_SYMLINK_NOFOLLOW };
+    if unsafe { libc::utimensat(libc::AT_FDCWD, c_path.as_ptr(), times.as_ptr(), flags) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// The kinds of access can_access checks for
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+// Whether this process may access a file in the given way, judged with
+// its effective user and group IDs as an actual open or exec would be
+pub fn can_access(path: &Path, access: Access) -> bool {
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mode = match access {
+        Access::Read => libc::R_OK,
+        Access::Write => libc::W_OK,
+        Access::Execute => libc::X_OK,
+    };
+    unsafe { libc::faccessat(libc::AT_FDCWD, c_path.as_ptr(), mode, libc::AT_EACCESS) == 0 }
+}
+
+// The name of this machine as the kernel knows it
+pub fn hostname() -> io::Result<String> {
+    let mut buffer = [0u8; 256];
+    if unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    Ok(String::from_utf8_lossy(&buffer[..end]).into_owned())
+}
+
+pub fn set_hostname(name: &str) -> io::Result<()> {
+    if unsafe { libc::sethostname(name.as_ptr() as *const libc::c_char, name.len() as _) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// Looks a host name up through the resolver: its canonical name, if the
+// resolver gives one, and its addresses in the order returned, without
+// repeats
+pub fn lookup_host(name: &str) -> io::Result<(Option<String>, Vec<IpAddr>)> {
+    let c_name = CString::new(name)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "name contains NUL"))?;
+    let mut hints: libc::addrinfo = unsafe { std::mem::zeroed() };
+    hints.ai_family = libc::AF_UNSPEC;
+    hints.ai_socktype = libc::SOCK_STREAM;
+    hints.ai_flags = libc::AI_CANONNAME;
+    let mut list = std::ptr::null_mut();
+    let status = unsafe { libc::getaddrinfo(c_name.as_ptr(), std::ptr::null(), &hints, &mut list) };
+    if status != 0 {
+        let message = unsafe { CStr::from_ptr(libc::gai_strerror(status)) };
+        return Err(io::Error::other(message.to_string_lossy().into_owned()));
+    }
+
+    let mut canonical = None;
+    let mut addresses = Vec::new();
+    let mut entry = list;
+    while !entry.is_null() {
+        let info = unsafe { &*entry };
+        if canonical.is_none() && !info.ai_canonname.is_null() {
+            canonical = Some(
+                unsafe { CStr::from_ptr(info.ai_canonname) }
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
+        let address = match info.ai_family {
+            libc::AF_INET => {
+                let socket = unsafe { &*(info.ai_addr as *const libc::sockaddr_in) };
+                let address = Ipv4Addr::from(u32::from_be(socket.sin_addr.s_addr));
+                Some(IpAddr::V4(address))
+            }
+            libc::AF_INET6 => {
+                let socket = unsafe { &*(info.ai_addr as *const libc::sockaddr_in6) };
+                Some(IpAddr::V6(Ipv6Addr::from(socket.sin6_addr.s6_addr)))
+            }
+            _ => None,
+        };
+        if let Some(address) = address.filter(|address| !addresses.contains(address)) {
+            addresses.push(address);
+        }
+        entry = info.ai_next;
+    }
+    unsafe { libc::freeaddrinfo(list) };
+    Ok((canonical, addresses))
+}
+
+// What uname reports about the running system
+pub struct SystemInfo {
+    pub kernel_name: String,
+    pub node_name: String,
+    pub kernel_release: String,
+    pub kernel_version: String,
+    pub machine: String,
+}
+
+pub fn system_info() -> io::Result<SystemInfo> {
+    let mut names: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut names) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let field = |chars: &[libc::c_char]| {
+        unsafe { CStr::from_ptr(chars.as_ptr()) }
+            .to_string_lossy()
+            .into_owned()
+    };
+    Ok(SystemInfo {
+        kernel_name: field(&names.sysname),
+        node_name: field(&names.nodename),
+        kernel_release: field(&names.release),
+        kernel_version: field(&names.version),
+        machine: field(&names.machine),
+    })
+}
+
+// Seconds since the system booted
+#[cfg(not(target_os = "macos"))]
+pub fn uptime() -> io::Result<f64> {
+    let text = std::fs::read_to_string("/proc/uptime")?;
+    text.split_whitespace()
+        .next()
+        .and_then(|seconds| seconds.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed /proc/uptime"))
+}
+
+#[cfg(target_os = "macos")]
+pub fn uptime() -> io::Result<f64> {
+    let mut boot: libc::timeval = unsafe { std::mem::zeroed() };
+    let mut size = std::mem::size_of::<libc::timeval>();
+    let name = b"kern.boottime\0";
+    let status = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr() as *const libc::c_char,
+            &mut boot as *mut libc::timeval as *mut libc::c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if status != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let now = Timestamp::now();
+    let boot = boot.tv_sec as f64 + boot.tv_usec as f64 / 1e6;
+    Ok(now.secs as f64 + now.nanos as f64 / 1e9 - boot)
+}
+
+// The 1, 5 and 15 minute load averages
+#[cfg(not(target_os = "macos"))]
+pub fn load_average() -> io::Result<[f64; 3]> {
+    let text = std::fs::read_to_string("/proc/loadavg")?;
+    let mut fields = text.split_whitespace().map(|field| field.parse().ok());
+    match (fields.next(), fields.next(), fields.next()) {
+        (Some(Some(one)), Some(Some(five)), Some(Some(fifteen))) => Ok([one, five, fifteen]),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "malformed /proc/loadavg",
+        )),
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn load_average() -> io::Result<[f64; 3]> {
+    let mut averages = [0.0; 3];
+    if unsafe { libc::getloadavg(averages.as_mut_ptr(), 3) } != 3 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(averages)
+}
+
+// What a finished child process used, as wait4 reports it
+#[derive(Clone, Copy, Default)]
+pub struct ResourceUsage {
+    pub user: Duration,
+    pub system: Duration,
+    // The peak resident set size, in KiB
+    pub max_resident: u64,
+    pub major_faults: u64,
+    pub minor_faults: u64,
+    pub swaps: u64,
+    // Blocks read and written by the file system
+    pub inputs: u64,
+    pub outputs: u64,
+    pub voluntary_switches: u64,
+    pub involuntary_switches: u64,
+}
+
+// Waits for the child `pid` to end, returning how it ended and what it
+// used; std's Child::wait gives no way to get the latter
+pub fn wait_with_usage(pid: u32) -> io::Result<(ExitStatus, ResourceUsage)> {
+    let mut status = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        if unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, &mut usage) } >= 0 {
+            break;
+        }
+        let e = io::Error::last_os_error();
+        if e.kind() != io::ErrorKind::Interrupted {
+            return Err(e);
+        }
+    }
+    let duration =
+        |time: libc::timeval| Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000);
+    // macOS reports the resident size in bytes rather than KiB
+    let max_resident = if cfg!(target_os = "macos") {
+        usage.ru_maxrss as u64 / 1024
+    } else {
+        usage.ru_maxrss as u64
+    };
+    let usage = ResourceUsage {
+        user: duration(usage.ru_utime),
+        system: duration(usage.ru_stime),
+        max_resident,
+        major_faults: usage.ru_majflt as u64,
+        minor_faults: usage.ru_minflt as u64,
+        swaps: usage.ru_nswap as u64,
+        inputs: usage.ru_inblock as u64,
+        outputs: usage.ru_oublock as u64,
+        voluntary_switches: usage.ru_nvcsw as u64,
+        involuntary_switches: usage.ru_nivcsw as u64,
+    };
+    Ok((ExitStatus::from_raw(status), usage))
+}
+
+// The scheduling priority of this process, from -20 (most favored) to
+// 19 (least)
+pub fn niceness() -> io::Result<i32> {
+    // -1 is a valid niceness, so only errno tells a failure apart
+    unsafe {
+        #[cfg(target_os = "linux")]
+        {
+            *libc::__errno_location() = 0;
+        }
+        #[cfg(target_os = "macos")]
+        {
+            *libc::__error() = 0;
+        }
+    }
+    let value = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+    if value == -1 {
+        let e = io::Error::last_os_error();
+        if e.raw_os_error().is_some_and(|code| code != 0) {
+            return Err(e);
+        }
+    }
+    Ok(value)
+}
+
+// Sets the scheduling priority of this process; lowering it below the
+// current value needs privileges
+pub fn set_niceness(value: i32) -> io::Result<()> {
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, value) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// Sessions of logged-in users recorded in the utmp database
+pub fn logged_in_users() -> usize {
+    let mut count = 0;
+    unsafe {
+        libc::setutxent();
+        loop {
+            let entry = libc::getutxent();
+            if entry.is_null() {
+                break;
+            }
+            if (*entry).ut_type == libc::USER_PROCESS {
+                count += 1;
+            }
+        }
+        libc::endutxent();
+    }
+    count
+}
+
+// Memory and swap figures in KiB, as /proc/meminfo gives them
+#[derive(Clone, Copy, Default)]
+pub struct MemoryInfo {
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+    pub shared: u64,
+    pub buffers: u64,
+    // The page cache together with reclaimable kernel slabs
+    pub cached: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+}
+
+pub fn memory_info() -> io::Result<MemoryInfo> {
+    let text = std::fs::read_to_string("/proc/meminfo")?;
+    let mut info = MemoryInfo::default();
+    let mut available = None;
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(Ok(value)) = value.split_whitespace().next().map(str::parse::<u64>) else {
+            continue;
+        };
+        match key {
+            "MemTotal" => info.total = value,
+            "MemFree" => info.free = value,
+            "MemAvailable" => available = Some(value),
+            "Shmem" => info.shared = value,
+            "Buffers" => info.buffers = value,
+            "Cached" | "SReclaimable" => info.cached += value,
+            "SwapTotal" => info.swap_total = value,
+            "SwapFree" => info.swap_free = value,
+            _ => {}
+        }
+    }
+    if info.total == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "malformed /proc/meminfo",
+        ));
+    }
+    // Kernels before 3.14 have no estimate, so count what is free
+    info.available = available.unwrap_or(info.free).min(info.total);
+    Ok(info)
+}
+
+// Processors installed in the machine, online or not
+pub fn all_processors() -> usize {
+    let count = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) };
+    if count > 0 {
+        count as usize
+    } else {
+        1
+    }
+}
+
+// Processors this process may use: those in its CPU affinity mask, fewer
+// when a cgroup CPU quota allows less time than that, and at least one
+pub fn available_processors() -> usize {
+    let mut count = affinity_processors().unwrap_or_else(|| {
+        let online = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+        if online > 0 {
+            online as usize
+        } else {
+            all_processors()
+        }
+    });
+    if let Some(quota) = cgroup_processors() {
+        count = count.min(quota);
+    }
+    count.max(1)
+}
+
+#[cfg(target_os = "linux")]
+fn affinity_processors() -> Option<usize> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::cpu_set_t>();
+    if unsafe { libc::sched_getaffinity(0, size, &mut set) } != 0 {
+        return None;
+    }
+    Some(unsafe { libc::CPU_COUNT(&set) } as usize)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn affinity_processors() -> Option<usize> {
+    None
+}
+
+// The processors a quota of `quota` microseconds every `period` amounts
+// to, rounded up; None when there is no quota
+fn quota_processors(quota: i64, period: i64) -> Option<usize> {
+    if quota <= 0 || period <= 0 {
+        return None;
+    }
+    Some((quota as u64).div_ceil(period as u64).max(1) as usize)
+}
+
+// The tightest CPU quota on the cgroups this process is in, from its own
+// group up to the root. cgroup v2 keeps "QUOTA PERIOD" or "max PERIOD" in
+// cpu.max; v1 splits them over cpu.cfs_quota_us and cpu.cfs_period_us,
+// with -1 for no quota.
+#[cfg(target_os = "linux")]
+fn cgroup_processors() -> Option<usize> {
+    let read_number =
+        |path: &Path| -> Option<i64> { std::fs::read_to_string(path).ok()?.trim().parse().ok() };
+    let groups = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+    let mut limit: Option<usize> = None;
+    for line in groups.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(_), Some(controllers), Some(group)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let version2 = controllers.is_empty();
+        let root = if version2 {
+            std::path::PathBuf::from("/sys/fs/cgroup")
+        } else if controllers.split(',').any(|controller| controller == "cpu") {
+            Path::new("/sys/fs/cgroup").join(controllers)
+        } else {
+            continue;
+        };
+        let mut dir = root.join(group.trim_start_matches('/'));
+        loop {
+            let quota = if version2 {
+                std::fs::read_to_string(dir.join("cpu.max"))
+                    .ok()
+                    .and_then(|text| {
+                        let mut fields = text.split_whitespace();
+                        let quota = fields.next()?.parse().ok()?;
+                        let period = fields.next()?.parse().ok()?;
+                        quota_processors(quota, period)
+                    })
+            } else {
+                match (
+                    read_number(&dir.join("cpu.cfs_quota_us")),
+                    read_number(&dir.join("cpu.cfs_period_us")),
+                ) {
+                    (Some(quota), Some(period)) => quota_processors(quota, period),
+                    _ => None,
+                }
+            };
+            if let Some(quota) = quota {
+                limit = Some(limit.map_or(quota, |limit| limit.min(quota)));
+            }
+            if dir == root || !dir.pop() {
+                break;
+            }
+        }
+    }
+    limit
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cgroup_processors() -> Option<usize> {
+    None
+}
@@ -0,0 +1,110 @@
+use cmds_lite::error;
+use std::env;
+use std::io::{self, BufWriter, Write};
+use std::os::unix::ffi::OsStrExt;
+
+// Like GNU printenv, a variable that is not set exits with 1 and a usage
+// error with 2
+const EXIT_UNSET: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Default)]
+pub struct PrintenvOptions {
+    // -0: end each line with NUL instead of newline
+    pub null: bool,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("printenv: {}", message);
+    std::process::exit(EXIT_USAGE);
+}
+
+struct PrintenvCommand<'a> {
+    options: &'a PrintenvOptions,
+}
+
+impl<'a> PrintenvCommand<'a> {
+    fn terminator(&self) -> u8 {
+        if self.options.null {
+            b'\0'
+        } else {
+            b'\n'
+        }
+    }
+
+    // Every variable as NAME=VALUE
+    fn print_all(&self, out: &mut impl Write) -> io::Result<()> {
+        for (name, value) in env::vars_os() {
+            out.write_all(name.as_bytes())?;
+            out.write_all(b"=")?;
+            out.write_all(value.as_bytes())?;
+            out.write_all(&[self.terminator()])?;
+        }
+        Ok(())
+    }
+
+    // The values of the named variables, in the order asked for. Returns
+    // whether all of them were set; a name with = in it never is.
+    fn print_values(&self, names: &[String], out: &mut impl Write) -> io::Result<bool> {
+        let mut all_set = true;
+        for name in names {
+            let value = (!name.contains('=')).then(|| env::var_os(name)).flatten();
+            match value {
+                Some(value) => {
+                    out.write_all(value.as_bytes())?;
+                    out.write_all(&[self.terminator()])?;
+                }
+                None => all_set = false,
+            }
+        }
+        Ok(all_set)
+    }
+
+    fn run(&self, names: &[String]) -> io::Result<bool> {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        let all_set = if names.is_empty() {
+            self.print_all(&mut out).map(|_| true)?
+        } else {
+            self.print_values(names, &mut out)?
+        };
+        out.flush()?;
+        Ok(all_set)
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = PrintenvOptions::default();
+    let mut names = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            names.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--null" => options.null = true,
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        '0' => options.null = true,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    let command = PrintenvCommand { options: &options };
+    match command.run(&names) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(EXIT_UNSET),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(e) => fail(&format!("write error: {}", error::message(&e))),
+    }
+}
@@ -0,0 +1,307 @@
+use cmds_lite::error;
+use cmds_lite::format::{self, Count, Piece, Spec, Value};
+use std::env;
+use std::io::{self, Write};
+
+struct PrintfCommand {
+    pieces: Vec<Piece>,
+    ok: bool,
+}
+
+// Where the arguments stand while the format is being applied
+struct Arguments<'a> {
+    values: &'a [String],
+    next: usize,
+}
+
+impl<'a> Arguments<'a> {
+    fn take(&mut self) -> Option<&'a str> {
+        let value = self.values.get(self.next)?;
+        self.next += 1;
+        Some(value)
+    }
+
+    fn remaining(&self) -> bool {
+        self.next < self.values.len()
+    }
+}
+
+impl PrintfCommand {
+    fn new(format: &str) -> Result<Self, String> {
+        Ok(PrintfCommand {
+            pieces: format::parse(format.as_bytes(), true)?,
+            ok: true,
+        })
+    }
+
+    // Applies the format, reusing it while arguments are left over, as long
+    // as it consumes any
+    fn run(&mut self, values: &[String], output: &mut Vec<u8>) {
+        let mut arguments = Arguments { values, next: 0 };
+        let consumes = self
+            .pieces
+            .iter()
+            .any(|piece| matches!(piece, Piece::Conversion(_)));
+
+        loop {
+            if !self.apply(&mut arguments, output) {
+                return;
+            }
+            if !consumes || !arguments.remaining() {
+                break;
+            }
+        }
+
+        if !consumes && arguments.remaining() {
+            eprintln!(
+                "printf: warning: ignoring excess arguments, starting with '{}'",
+                values[arguments.next]
+            );
+        }
+    }
+
+    // One pass over the format; false when \c ended the output
+    fn apply(&mut self, arguments: &mut Arguments, output: &mut Vec<u8>) -> bool {
+        for index in 0..self.pieces.len() {
+            let spec = match &self.pieces[index] {
+                Piece::Text(text) => {
+                    output.extend_from_slice(text);
+                    continue;
+                }
+                Piece::Stop => return false,
+                Piece::Conversion(spec) => spec.clone(),
+            };
+
+            let width = match spec.width {
+                Some(Count::Argument) => self.integer(arguments.take()),
+                Some(Count::Fixed(width)) => width as i64,
+                None => 0,
+            };
+            let precision = match spec.precision {
+                // A negative precision from `*` counts as none
+                Some(Count::Argument) => usize::try_from(self.integer(arguments.take())).ok(),
+                Some(Count::Fixed(precision)) => Some(precision),
+                None => None,
+            };
+
+            let argument = arguments.take();
+            if spec.conversion == b'b' {
+                let mut expanded = Vec::new();
+                let stopped = expand_b(argument.unwrap_or("").as_bytes(), &mut expanded);
+                output.extend(spec.render(width, precision, Value::Bytes(&expanded)));
+                if stopped {
+                    return false;
+                }
+                continue;
+            }
+            output.extend(self.render(&spec, width, precision, argument));
+        }
+        true
+    }
+
+    fn render(
+        &mut self,
+        spec: &Spec,
+        width: i64,
+        precision: Option<usize>,
+        argument: Option<&str>,
+    ) -> Vec<u8> {
+        if spec.is_float() {
+            let value = self.float(argument);
+            spec.render(width, precision, Value::Float(value))
+        } else if spec.is_integer() {
+            if matches!(spec.conversion, b'd' | b'i') {
+                let value = self.integer(argument);
+                spec.render(width, precision, Value::Signed(value))
+            } else {
+                let value = self.unsigned(argument);
+                spec.render(width, precision, Value::Unsigned(value))
+            }
+        } else {
+            let bytes = argument.unwrap_or("").as_bytes();
+            spec.render(width, precision, Value::Bytes(bytes))
+        }
+    }
+
+    fn integer(&mut self, argument: Option<&str>) -> i64 {
+        let value = self.number(argument);
+        match i64::try_from(value) {
+            Ok(value) => value,
+            Err(_) => {
+                self.fail(format!(
+                    "'{}': Numerical result out of range",
+                    argument.unwrap_or("")
+                ));
+                if value < 0 {
+                    i64::MIN
+                } else {
+                    i64::MAX
+                }
+            }
+        }
+    }
+
+    fn unsigned(&mut self, argument: Option<&str>) -> u64 {
+        let value = self.number(argument);
+        if value < 0 {
+            // Negative numbers wrap around like strtoumax does
+            return match i64::try_from(value) {
+                Ok(value) => value as u64,
+                Err(_) => {
+                    self.fail(format!(
+                        "'{}': Numerical result out of range",
+                        argument.unwrap_or("")
+                    ));
+                    u64::MAX
+                }
+            };
+        }
+        match u64::try_from(value) {
+            Ok(value) => value,
+            Err(_) => {
+                self.fail(format!(
+                    "'{}': Numerical result out of range",
+                    argument.unwrap_or("")
+                ));
+                u64::MAX
+            }
+        }
+    }
+
+    // Numeric arguments may be decimal, 0x hex, 0 octal, or a quote
+    // followed by a character whose code is used
+    fn number(&mut self, argument: Option<&str>) -> i128 {
+        let Some(text) = argument else {
+            return 0;
+        };
+        if let Some(value) = character_value(text) {
+            return value as i128;
+        }
+
+        let trimmed = text.trim_start();
+        let (negative, digits) = match trimmed.as_bytes().first() {
+            Some(b'-') => (true, &trimmed[1..]),
+            Some(b'+') => (false, &trimmed[1..]),
+            _ => (false, trimmed),
+        };
+        let (radix, digits) = if let Some(hex) = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            (16, hex)
+        } else if digits.len() > 1 && digits.starts_with('0') {
+            (8, &digits[1..])
+        } else {
+            (10, digits)
+        };
+
+        let valid = digits.chars().take_while(|c| c.is_digit(radix)).count();
+        // Anything past u64 is out of range either way, so saturate there
+        let magnitude = u128::from_str_radix(&digits[..valid], radix)
+            .unwrap_or(0)
+            .min(u64::MAX as u128 + 1);
+        if valid == 0 {
+            self.fail(format!("'{}': expected a numeric value", text));
+        } else if valid < digits.len() {
+            self.fail(format!("'{}': value not completely converted", text));
+        }
+
+        if negative {
+            -(magnitude as i128)
+        } else {
+            magnitude as i128
+        }
+    }
+
+    fn float(&mut self, argument: Option<&str>) -> f64 {
+        let Some(text) = argument else {
+            return 0.0;
+        };
+        if let Some(value) = character_value(text) {
+            return value as f64;
+        }
+
+        let trimmed = text.trim_start();
+        if let Ok(value) = trimmed.parse::<f64>() {
+            return value;
+        }
+        // Use the longest prefix that is a number
+        let prefix = (1..trimmed.len())
+            .rev()
+            .filter(|end| trimmed.is_char_boundary(*end))
+            .find_map(|end| trimmed[..end].parse::<f64>().ok());
+        match prefix {
+            Some(value) => {
+                self.fail(format!("'{}': value not completely converted", text));
+                value
+            }
+            None => {
+                self.fail(format!("'{}': expected a numeric value", text));
+                0.0
+            }
+        }
+    }
+
+    // Bad numbers are reported but do not stop the output, like GNU printf
+    fn fail(&mut self, message: String) {
+        eprintln!("printf: {}", message);
+        self.ok = false;
+    }
+}
+
+// 'c or "c stands for the code of c
+fn character_value(text: &str) -> Option<i64> {
+    let rest = text.strip_prefix('\'').or_else(|| text.strip_prefix('"'))?;
+    Some(rest.chars().next().map(|c| c as i64).unwrap_or(0))
+}
+
+// Expands %b's argument like echo -e; returns true if \c was found
+fn expand_b(text: &[u8], out: &mut Vec<u8>) -> bool {
+    let mut i = 0;
+    while i < text.len() {
+        if text[i] != b'\\' {
+            out.push(text[i]);
+            i += 1;
+        } else if text.get(i + 1) == Some(&b'c') {
+            return true;
+        } else {
+            i = format::unescape(text, i, out, true);
+        }
+    }
+    false
+}
+
+pub fn run(format: &str, arguments: &[String]) -> Result<bool, String> {
+    let mut command = PrintfCommand::new(format)?;
+    let mut output = Vec::new();
+    command.run(arguments, &mut output);
+
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(&output)
+        .and_then(|_| stdout.flush())
+        .map_err(|e| format!("write error: {}", error::message(&e)))?;
+    Ok(command.ok)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut operands = &args[1..];
+    if operands.first().map(|s| s.as_str()) == Some("--") {
+        operands = &operands[1..];
+    }
+
+    let Some(format) = operands.first() else {
+        eprintln!("printf: missing operand");
+        std::process::exit(1);
+    };
+
+    match run(format, &operands[1..]) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(message) => {
+            eprintln!("printf: {}", message);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs;
+use std::io;
+
+// Processes as /proc describes them, as used by ps and kill.
+
+pub struct Process {
+    pub pid: u32,
+    pub ppid: u32,
+    // The executable name the kernel keeps, at most 15 bytes
+    pub name: String,
+    // R running, S sleeping, D waiting on I/O, Z zombie, T stopped, ...
+    pub state: char,
+    // The effective user ID
+    pub uid: u32,
+    // The controlling terminal's device number, 0 for none
+    pub tty: u64,
+    // User plus system CPU time, in clock ticks
+    pub cpu_ticks: u64,
+    pub nice: i64,
+    // Virtual and resident memory, in KiB
+    pub vsize: u64,
+    pub rss: u64,
+    // The arguments it was started with; empty for kernel threads
+    pub cmdline: Vec<String>,
+}
+
+impl Process {
+    // The command line as ps shows it, with the name in brackets when
+    // there are no arguments to show and control characters as "?"
+    pub fn command_line(&self) -> String {
+        if self.cmdline.is_empty() {
+            return format!("[{}]", self.name);
+        }
+        self.cmdline
+            .join(" ")
+            .chars()
+            .map(|c| if c.is_control() { '?' } else { c })
+            .collect()
+    }
+}
+
+// The IDs of all processes, lowest first
+pub fn pids() -> io::Result<Vec<u32>> {
+    let mut pids: Vec<u32> = fs::read_dir("/proc")?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+    pids.sort_unstable();
+    Ok(pids)
+}
+
+// Every process that still exists by the time it is read
+pub fn all() -> io::Result<Vec<Process>> {
+    Ok(pids()?
+        .into_iter()
+        .filter_map(|pid| read(pid).ok())
+        .collect())
+}
+
+fn malformed(pid: u32) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("malformed /proc/{}/stat", pid),
+    )
+}
+
+pub fn read(pid: u32) -> io::Result<Process> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid))?;
+    // The name is in parentheses and may itself contain them, so it runs
+    // to the last closing one
+    let (open, close) = match (stat.find('('), stat.rfind(')')) {
+        (Some(open), Some(close)) if open < close => (open, close),
+        _ => return Err(malformed(pid)),
+    };
+    let name = stat[open + 1..close].to_string();
+    let fields: Vec<&str> = stat[close + 1..].split_whitespace().collect();
+    if fields.len() < 22 {
+        return Err(malformed(pid));
+    }
+    let number = |index: usize| fields[index].parse::<u64>().unwrap_or(0);
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as u64;
+
+    let status = fs::read_to_string(format!("/proc/{}/status", pid))?;
+    let uid = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|ids| ids.split_whitespace().nth(1)?.parse().ok())
+        .ok_or_else(|| malformed(pid))?;
+
+    // Arguments are NUL-terminated; a kernel thread has none
+    let cmdline = fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+    let cmdline = cmdline
+        .split(|&byte| byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+
+    Ok(Process {
+        pid,
+        ppid: number(1) as u32,
+        name,
+        state: fields[0].chars().next().unwrap_or('?'),
+        uid,
+        tty: number(4),
+        cpu_ticks: number(11) + number(12),
+        nice: fields[16].parse().unwrap_or(0),
+        vsize: number(20) / 1024,
+        rss: number(21) * page_size / 1024,
+        cmdline,
+    })
+}
+
+// Clock ticks per second, the unit of CPU times
+pub fn clock_ticks() -> u64 {
+    unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as u64
+}
+
+// The terminal name for a device number, as ps shows it: "pts/0", "tty1"
+pub fn tty_name(device: u64) -> Option<String> {
+    if device == 0 {
+        return None;
+    }
+    let major = (device >> 8) & 0xfff;
+    let minor = (device & 0xff) | ((device >> 12) & 0xfff00);
+    match major {
+        136..=143 => Some(format!("pts/{}", (major - 136) * 256 + minor)),
+        4 if minor < 64 => Some(format!("tty{}", minor)),
+        4 => Some(format!("ttyS{}", minor - 64)),
+        5 if minor == 0 => Some(String::from("tty")),
+        5 if minor == 1 => Some(String::from("console")),
+        _ => Some(format!("{}:{}", major, minor)),
+    }
+}
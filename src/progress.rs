@@ -0,0 +1,67 @@
+use crate::size;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+// A line on standard error showing how far a long transfer has got, such
+// as "1.2G / 4.0G (30%), 118M/s". It is redrawn in place at most once a
+// second, and only once the transfer has taken a second, so quick ones
+// show nothing.
+
+const INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct Progress {
+    // Bytes the whole transfer is expected to be
+    total: u64,
+    started: Instant,
+    // When the line is next due
+    next: Instant,
+    // Whether a line is showing, to be ended before anything else is
+    // written to standard error
+    shown: bool,
+}
+
+impl Progress {
+    pub fn new(total: u64) -> Self {
+        let started = Instant::now();
+        Progress {
+            total,
+            started,
+            next: started + INTERVAL,
+            shown: false,
+        }
+    }
+
+    // Notes that `done` bytes have been transferred, redrawing the line if
+    // it is due
+    pub fn update(&mut self, done: u64) {
+        let now = Instant::now();
+        if now < self.next {
+            return;
+        }
+        self.next = now + INTERVAL;
+        self.shown = true;
+
+        let seconds = now.duration_since(self.started).as_secs_f64();
+        let rate = (done as f64 / seconds) as u64;
+        let percent = (done.min(self.total) * 100)
+            .checked_div(self.total)
+            .unwrap_or(100);
+        let mut stderr = io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r{} / {} ({}%), {}/s\x1b[K",
+            size::human_readable(done),
+            size::human_readable(self.total),
+            percent,
+            size::human_readable(rate)
+        );
+        let _ = stderr.flush();
+    }
+
+    // Ends the line, if one is showing
+    pub fn finish(&mut self) {
+        if std::mem::take(&mut self.shown) {
+            let _ = writeln!(io::stderr());
+        }
+    }
+}
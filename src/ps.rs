@@ -0,0 +1,334 @@
+use cmds_lite::proc::{self, Process};
+use cmds_lite::users;
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, BufWriter, Write};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Pid,
+    Ppid,
+    User,
+    Uid,
+    State,
+    Nice,
+    Rss,
+    Vsz,
+    Tty,
+    Time,
+    // The executable name alone
+    Comm,
+    // The full command line
+    Args,
+}
+
+impl Field {
+    // The field for an -o key and the header it has by default
+    fn parse(key: &str) -> Option<(Field, &'static str)> {
+        let field = match key {
+            "pid" => (Field::Pid, "PID"),
+            "ppid" => (Field::Ppid, "PPID"),
+            "user" | "euser" | "uname" => (Field::User, "USER"),
+            "uid" | "euid" => (Field::Uid, "UID"),
+            "s" | "state" => (Field::State, "S"),
+            "ni" | "nice" => (Field::Nice, "NI"),
+            "rss" | "rssize" => (Field::Rss, "RSS"),
+            "vsz" | "vsize" => (Field::Vsz, "VSZ"),
+            "tty" | "tname" | "tt" => (Field::Tty, "TTY"),
+            "time" | "cputime" => (Field::Time, "TIME"),
+            "comm" | "ucomm" => (Field::Comm, "COMMAND"),
+            "args" | "command" => (Field::Args, "COMMAND"),
+            "cmd" => (Field::Args, "CMD"),
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    // Numbers line up on the right, text on the left
+    fn right_aligned(self) -> bool {
+        matches!(
+            self,
+            Field::Pid
+                | Field::Ppid
+                | Field::Uid
+                | Field::Nice
+                | Field::Rss
+                | Field::Vsz
+                | Field::Time
+        )
+    }
+}
+
+pub struct Column {
+    pub field: Field,
+    pub header: String,
+}
+
+pub struct PsOptions {
+    // -e: every process
+    pub all: bool,
+    // -u: processes whose effective user is one of these
+    pub users: Vec<u32>,
+    // -p: processes with one of these IDs
+    pub pids: Vec<u32>,
+    // -o: the columns to show
+    pub columns: Vec<Column>,
+}
+
+impl Default for PsOptions {
+    fn default() -> Self {
+        PsOptions {
+            all: false,
+            users: Vec::new(),
+            pids: Vec::new(),
+            columns: parse_format("pid,user,s,rss,time,args"),
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("ps: {}", message);
+    std::process::exit(1);
+}
+
+// CPU time as [DD-]HH:MM:SS
+fn cpu_time(ticks: u64) -> String {
+    let seconds = ticks / proc::clock_ticks();
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{}-{:02}:{:02}:{:02}", days, hours, minutes, seconds % 60)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds % 60)
+    }
+}
+
+struct PsCommand<'a> {
+    options: &'a PsOptions,
+    // User names looked up so far
+    names: HashMap<u32, String>,
+}
+
+impl<'a> PsCommand<'a> {
+    // Without -e, -u or -p, ps shows the processes of this user on this
+    // terminal
+    fn selected(&self, process: &Process, own: &Process) -> bool {
+        let options = self.options;
+        if options.all {
+            return true;
+        }
+        if options.users.is_empty() && options.pids.is_empty() {
+            return process.uid == own.uid && process.tty == own.tty;
+        }
+        options.users.contains(&process.uid) || options.pids.contains(&process.pid)
+    }
+
+    fn value(&mut self, field: Field, process: &Process) -> String {
+        match field {
+            Field::Pid => process.pid.to_string(),
+            Field::Ppid => process.ppid.to_string(),
+            Field::User => self
+                .names
+                .entry(process.uid)
+                .or_insert_with(|| users::user_name(process.uid))
+                .clone(),
+            Field::Uid => process.uid.to_string(),
+            Field::State => process.state.to_string(),
+            Field::Nice => process.nice.to_string(),
+            Field::Rss => process.rss.to_string(),
+            Field::Vsz => process.vsize.to_string(),
+            Field::Tty => proc::tty_name(process.tty).unwrap_or_else(|| String::from("?")),
+            Field::Time => cpu_time(process.cpu_ticks),
+            Field::Comm => process.name.clone(),
+            Field::Args => process.command_line(),
+        }
+    }
+
+    fn run(&mut self, out: &mut impl Write) -> io::Result<bool> {
+        let own = proc::read(std::process::id())?;
+        let columns = &self.options.columns;
+        let mut rows = Vec::new();
+        for process in proc::all()? {
+            if self.selected(&process, &own) {
+                let row: Vec<String> = columns
+                    .iter()
+                    .map(|column| self.value(column.field, &process))
+                    .collect();
+                rows.push(row);
+            }
+        }
+
+        // The header is left out when every column's header is empty
+        let headers: Vec<String> = columns.iter().map(|column| column.header.clone()).collect();
+        let show_headers = headers.iter().any(|header| !header.is_empty());
+        let widths: Vec<usize> = (0..columns.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain(show_headers.then(|| headers[i].chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let show_headers = show_headers.then_some(&headers);
+        for row in show_headers.into_iter().chain(&rows) {
+            let mut line = String::new();
+            for (i, cell) in row.iter().enumerate() {
+                if i > 0 {
+                    line.push(' ');
+                }
+                let width = widths[i];
+                if columns[i].field.right_aligned() {
+                    line.push_str(&format!("{:>width$}", cell));
+                } else if i + 1 < row.len() {
+                    line.push_str(&format!("{:<width$}", cell));
+                } else {
+                    line.push_str(cell);
+                }
+            }
+            writeln!(out, "{}", line)?;
+        }
+        // Like procps, ps fails when nothing was selected
+        Ok(!rows.is_empty())
+    }
+}
+
+// An -o list: keys separated by commas or spaces, each optionally with
+// "=HEADER"; a header runs to the end of the list
+fn parse_format(text: &str) -> Vec<Column> {
+    let mut columns = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = rest.find([',', ' ', '=']).unwrap_or(rest.len());
+        let key = &rest[..end];
+        let header = if rest[end..].starts_with('=') {
+            let header = rest[end + 1..].to_string();
+            rest = "";
+            Some(header)
+        } else {
+            rest = rest[end..].trim_start_matches([',', ' ']);
+            None
+        };
+        if key.is_empty() {
+            continue;
+        }
+        match Field::parse(key) {
+            Some((field, default)) => columns.push(Column {
+                field,
+                header: header.unwrap_or_else(|| default.to_string()),
+            }),
+            None => fail(&format!(
+                "unknown user-defined format specifier \"{}\"",
+                key
+            )),
+        }
+    }
+    columns
+}
+
+// A -u list of user names or IDs
+fn parse_users(text: &str) -> Vec<u32> {
+    text.split([',', ' '])
+        .filter(|name| !name.is_empty())
+        .map(|name| match users::parse_user(name) {
+            Some(user) => user.uid,
+            None => fail(&format!("user name does not exist: {}", name)),
+        })
+        .collect()
+}
+
+// A -p list of process IDs
+fn parse_pids(text: &str) -> Vec<u32> {
+    text.split([',', ' '])
+        .filter(|pid| !pid.is_empty())
+        .map(|pid| match pid.parse::<u32>() {
+            Ok(pid) if pid > 0 => pid,
+            _ => fail(&format!("process ID list syntax error: {}", pid)),
+        })
+        .collect()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = PsOptions::default();
+    let mut format: Option<Vec<Column>> = None;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        let (key, inline) = match arg.split_once('=') {
+            Some((key, value)) if arg.starts_with("--") => (key, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        match key {
+            "--user" | "--pid" | "--format" => {
+                let value = match inline {
+                    Some(value) => value,
+                    None if i < args.len() => {
+                        i += 1;
+                        args[i - 1].clone()
+                    }
+                    None => fail(&format!("option '{}' requires an argument", arg)),
+                };
+                match key {
+                    "--user" => options.users.extend(parse_users(&value)),
+                    "--pid" => options.pids.extend(parse_pids(&value)),
+                    _ => format
+                        .get_or_insert_with(Vec::new)
+                        .extend(parse_format(&value)),
+                }
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'e' | 'A' => options.all = true,
+                        'u' | 'p' | 'o' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            match flag {
+                                'u' => options.users.extend(parse_users(&value)),
+                                'p' => options.pids.extend(parse_pids(&value)),
+                                _ => format
+                                    .get_or_insert_with(Vec::new)
+                                    .extend(parse_format(&value)),
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+            _ => fail(&format!("unsupported operand '{}'", arg)),
+        }
+    }
+    if let Some(columns) = format {
+        options.columns = columns;
+    }
+
+    let mut command = PsCommand {
+        options: &options,
+        names: HashMap::new(),
+    };
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    match command
+        .run(&mut out)
+        .and_then(|found| out.flush().map(|_| found))
+    {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(e) => fail(&e.to_string()),
+    }
+}
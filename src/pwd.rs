@@ -0,0 +1,109 @@
+use cmds_lite::error;
+use std::env;
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    // The path the shell used to get here, from $PWD, symlinks included
+    Logical,
+    // The path with every symlink resolved
+    Physical,
+}
+
+pub struct PwdOptions {
+    pub resolution: Resolution,
+}
+
+impl Default for PwdOptions {
+    fn default() -> Self {
+        // Like GNU pwd, -P is the default
+        PwdOptions {
+            resolution: Resolution::Physical,
+        }
+    }
+}
+
+// $PWD is only trusted when it is absolute, has no . or .. components and
+// names the current directory
+fn logical_directory() -> Option<PathBuf> {
+    let pwd = PathBuf::from(env::var_os("PWD")?);
+    if !pwd.is_absolute()
+        || pwd
+            .components()
+            .any(|c| matches!(c, Component::CurDir | Component::ParentDir))
+    {
+        return None;
+    }
+
+    let claimed = fs::metadata(&pwd).ok()?;
+    let actual = fs::metadata(".").ok()?;
+    if claimed.dev() == actual.dev() && claimed.ino() == actual.ino() {
+        Some(pwd)
+    } else {
+        None
+    }
+}
+
+fn physical_directory() -> io::Result<PathBuf> {
+    let current = env::current_dir()?;
+    fs::canonicalize(&current).or(Ok(current))
+}
+
+pub fn run(options: &PwdOptions) -> io::Result<PathBuf> {
+    if options.resolution == Resolution::Logical {
+        if let Some(path) = logical_directory() {
+            return Ok(path);
+        }
+    }
+    physical_directory()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = PwdOptions::default();
+    let mut operands = false;
+
+    for arg in args.iter().skip(1) {
+        match arg.as_str() {
+            "--logical" => options.resolution = Resolution::Logical,
+            "--physical" => options.resolution = Resolution::Physical,
+            "--" => break,
+            _ if arg.starts_with("--") => {
+                eprintln!("pwd: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                // The last of -L and -P wins
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'L' => options.resolution = Resolution::Logical,
+                        'P' => options.resolution = Resolution::Physical,
+                        _ => {
+                            eprintln!("pwd: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            _ => operands = true,
+        }
+    }
+
+    if operands {
+        eprintln!("pwd: ignoring non-option arguments");
+    }
+
+    match run(&options) {
+        Ok(path) => println!("{}", path.display()),
+        Err(e) => {
+            eprintln!(
+                "pwd: error retrieving current directory: {}",
+                error::message(&e)
+            );
+            std::process::exit(1);
+        }
+    }
+}
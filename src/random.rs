@@ -0,0 +1,85 @@
+use std::io::{self, Read};
+
+// Random numbers for commands that shuffle or pick, such as shuf. The
+// generator is xoshiro256**, seeded from the kernel, from a number, or
+// from the bytes of a file so that runs can be repeated.
+
+pub struct Random {
+    state: [u64; 4],
+}
+
+// Spreads a 64-bit seed over the generator's state, as its authors
+// recommend, so that similar seeds give unrelated sequences
+fn split_mix(seed: &mut u64) -> u64 {
+    *seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *seed;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Random {
+    pub fn from_seed(mut seed: u64) -> Random {
+        Random {
+            state: [
+                split_mix(&mut seed),
+                split_mix(&mut seed),
+                split_mix(&mut seed),
+                split_mix(&mut seed),
+            ],
+        }
+    }
+
+    // Seeded with random bytes from the kernel
+    pub fn from_entropy() -> io::Result<Random> {
+        let mut bytes = [0u8; 8];
+        let read = unsafe { libc::getrandom(bytes.as_mut_ptr().cast(), bytes.len(), 0) };
+        if read != bytes.len() as isize {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Random::from_seed(u64::from_ne_bytes(bytes)))
+    }
+
+    // Seeded with the first bytes of `source`; the same bytes always give
+    // the same numbers
+    pub fn from_source(source: &mut impl Read) -> io::Result<Random> {
+        let mut bytes = Vec::new();
+        source.take(32).read_to_end(&mut bytes)?;
+        if bytes.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "end of file"));
+        }
+        let mut seed = 0;
+        for chunk in bytes.chunks(8) {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            seed = split_mix(&mut seed) ^ u64::from_le_bytes(word);
+        }
+        Ok(Random::from_seed(seed))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+        result
+    }
+
+    // A number from 0 up to but not including `bound`, with every one
+    // equally likely: draws that would favor the low numbers are thrown
+    // away
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0);
+        let zone = u64::MAX - (u64::MAX - bound + 1) % bound;
+        loop {
+            let value = self.next_u64();
+            if value <= zone {
+                return value % bound;
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use cmds_lite::error;
+use cmds_lite::resolve::{self, Existence};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(Default)]
+pub struct ReadlinkOptions {
+    // Canonicalize instead of reading a single link (-f, -e or -m)
+    pub canonicalize: Option<Existence>,
+    pub no_newline: bool,
+    pub verbose: bool,
+    pub zero: bool,
+}
+
+struct ReadlinkCommand<'a> {
+    options: &'a ReadlinkOptions,
+}
+
+impl<'a> ReadlinkCommand<'a> {
+    fn new(options: &'a ReadlinkOptions) -> Self {
+        ReadlinkCommand { options }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        // -n only makes sense for a single name, as in GNU readlink
+        let no_newline = self.options.no_newline && files.len() == 1;
+        if self.options.no_newline && !no_newline {
+            eprintln!("readlink: ignoring --no-newline with multiple arguments");
+        }
+        let mut stdout = io::stdout().lock();
+        let mut ok = true;
+
+        for file in files {
+            let path = Path::new(file);
+            let result = match self.options.canonicalize {
+                Some(existence) => resolve::canonicalize(path, existence),
+                None => fs::read_link(path),
+            };
+            match result {
+                Ok(target) => {
+                    let mut written = stdout.write_all(target.as_os_str().as_encoded_bytes());
+                    if !no_newline {
+                        let terminator = if self.options.zero { b'\0' } else { b'\n' };
+                        written = written.and_then(|_| stdout.write_all(&[terminator]));
+                    }
+                    if let Err(e) = written {
+                        eprintln!("readlink: write error: {}", error::message(&e));
+                        return false;
+                    }
+                }
+                // Failures are silent unless -v is given
+                Err(e) => {
+                    if self.options.verbose {
+                        eprintln!("readlink: {}: {}", file, error::message(&e));
+                    }
+                    ok = false;
+                }
+            }
+        }
+        ok
+    }
+}
+
+pub fn run(files: &[String], options: &ReadlinkOptions) -> bool {
+    let command = ReadlinkCommand::new(options);
+    command.run(files)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = ReadlinkOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--canonicalize" => options.canonicalize = Some(Existence::AllButLast),
+            "--canonicalize-existing" => options.canonicalize = Some(Existence::All),
+            "--canonicalize-missing" => options.canonicalize = Some(Existence::Nothing),
+            "--no-newline" => options.no_newline = true,
+            "--quiet" | "--silent" => options.verbose = false,
+            "--verbose" => options.verbose = true,
+            "--zero" => options.zero = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("readlink: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'f' => options.canonicalize = Some(Existence::AllButLast),
+                        'e' => options.canonicalize = Some(Existence::All),
+                        'm' => options.canonicalize = Some(Existence::Nothing),
+                        'n' => options.no_newline = true,
+                        'q' | 's' => options.verbose = false,
+                        'v' => options.verbose = true,
+                        'z' => options.zero = true,
+                        _ => {
+                            eprintln!("readlink: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        eprintln!("readlink: missing operand");
+        std::process::exit(1);
+    }
+
+    if !run(&files, &options) {
+        std::process::exit(1);
+    }
+}
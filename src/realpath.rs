@@ -0,0 +1,144 @@
+use cmds_lite::error;
+use cmds_lite::resolve::{self, Existence};
+use std::env;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub struct RealpathOptions {
+    pub existence: Existence,
+    // -s: only tidy the path up, without resolving symlinks
+    pub no_symlinks: bool,
+    pub relative_to: Option<String>,
+    pub quiet: bool,
+    pub zero: bool,
+}
+
+impl Default for RealpathOptions {
+    fn default() -> Self {
+        RealpathOptions {
+            existence: Existence::AllButLast,
+            no_symlinks: false,
+            relative_to: None,
+            quiet: false,
+            zero: false,
+        }
+    }
+}
+
+struct RealpathCommand<'a> {
+    options: &'a RealpathOptions,
+}
+
+impl<'a> RealpathCommand<'a> {
+    fn new(options: &'a RealpathOptions) -> Self {
+        RealpathCommand { options }
+    }
+
+    fn resolve(&self, path: &Path) -> io::Result<PathBuf> {
+        if self.options.no_symlinks {
+            resolve::normalize(path)
+        } else {
+            resolve::canonicalize(path, self.options.existence)
+        }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let base = match &self.options.relative_to {
+            Some(dir) => match self.resolve(Path::new(dir)) {
+                Ok(base) => Some(base),
+                Err(e) => {
+                    eprintln!("realpath: {}: {}", dir, error::message(&e));
+                    return false;
+                }
+            },
+            None => None,
+        };
+
+        let terminator = if self.options.zero { b'\0' } else { b'\n' };
+        let mut stdout = io::stdout().lock();
+        let mut ok = true;
+        for file in files {
+            match self.resolve(Path::new(file)) {
+                Ok(path) => {
+                    let path = match &base {
+                        Some(base) => resolve::relative_path(base, &path),
+                        None => path,
+                    };
+                    let written = stdout
+                        .write_all(path.as_os_str().as_encoded_bytes())
+                        .and_then(|_| stdout.write_all(&[terminator]));
+                    if let Err(e) = written {
+                        eprintln!("realpath: write error: {}", error::message(&e));
+                        return false;
+                    }
+                }
+                Err(e) => {
+                    if !self.options.quiet {
+                        eprintln!("realpath: {}: {}", file, error::message(&e));
+                    }
+                    ok = false;
+                }
+            }
+        }
+        ok
+    }
+}
+
+pub fn run(files: &[String], options: &RealpathOptions) -> bool {
+    let command = RealpathCommand::new(options);
+    command.run(files)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = RealpathOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--canonicalize-existing" => options.existence = Existence::All,
+            "--canonicalize-missing" => options.existence = Existence::Nothing,
+            "--strip" | "--no-symlinks" => options.no_symlinks = true,
+            "--quiet" => options.quiet = true,
+            "--zero" => options.zero = true,
+            _ if arg.starts_with("--relative-to=") => {
+                options.relative_to = Some(arg["--relative-to=".len()..].to_string());
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("realpath: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'e' => options.existence = Existence::All,
+                        'm' => options.existence = Existence::Nothing,
+                        's' => options.no_symlinks = true,
+                        'q' => options.quiet = true,
+                        'z' => options.zero = true,
+                        _ => {
+                            eprintln!("realpath: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        eprintln!("realpath: missing operand");
+        std::process::exit(1);
+    }
+
+    if !run(&files, &options) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,906 @@
+// POSIX regular expressions in basic (BRE) and extended (ERE) syntax, with
+// the GNU extensions: \+ \? \| in BRE and \w \W \s \S \b \B \< \> \` \' in
+// both. Matching works on bytes and finds the leftmost-longest match, as
+// POSIX requires. Patterns are compiled to a small instruction set that is
+// run as a Pike VM, so matching time stays linear in the text; only
+// patterns with back-references, and callers that want the text of each
+// group, fall back to backtracking.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Basic,
+    Extended,
+}
+
+// Same limit as glibc's RE_DUP_MAX
+const MAX_REPEAT: u32 = 0x7fff;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Assertion {
+    LineStart,
+    LineEnd,
+    WordBoundary,
+    NotWordBoundary,
+    WordStart,
+    WordEnd,
+}
+
+#[derive(Clone)]
+enum Node {
+    Empty,
+    Byte(u8),
+    Any,
+    Set(Box<[bool; 256]>),
+    Assert(Assertion),
+    Group(Box<Node>, usize),
+    BackReference(usize),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat(Box<Node>, u32, Option<u32>),
+}
+
+enum Inst {
+    Byte(u8),
+    Any,
+    Set(Box<[bool; 256]>),
+    Assert(Assertion),
+    Split(usize, usize),
+    Jump(usize),
+    Save(usize),
+    BackReference(usize),
+    Match,
+}
+
+pub struct Regex {
+    program: Vec<Inst>,
+    groups: usize,
+    back_references: bool,
+}
+
+fn is_word(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+fn class_members(name: &[u8]) -> Option<fn(u8) -> bool> {
+    let test: fn(u8) -> bool = match name {
+        b"alpha" => |b| b.is_ascii_alphabetic(),
+        b"digit" => |b| b.is_ascii_digit(),
+        b"alnum" => |b| b.is_ascii_alphanumeric(),
+        b"upper" => |b| b.is_ascii_uppercase(),
+        b"lower" => |b| b.is_ascii_lowercase(),
+        b"space" => |b| b" \t\n\r\x0b\x0c".contains(&b),
+        b"blank" => |b| b == b' ' || b == b'\t',
+        b"punct" => |b| b.is_ascii_punctuation(),
+        b"print" => |b| (0x20..0x7f).contains(&b),
+        b"graph" => |b| b.is_ascii_graphic(),
+        b"cntrl" => |b| b.is_ascii_control(),
+        b"xdigit" => |b| b.is_ascii_hexdigit(),
+        _ => return None,
+    };
+    Some(test)
+}
+
+fn set_of(test: impl Fn(u8) -> bool) -> Box<[bool; 256]> {
+    let mut set = Box::new([false; 256]);
+    for byte in 0..=255u8 {
+        set[byte as usize] = test(byte);
+    }
+    set
+}
+
+struct Parser<'a> {
+    pattern: &'a [u8],
+    pos: usize,
+    syntax: Syntax,
+    ignore_case: bool,
+    depth: usize,
+    groups: usize,
+    back_references: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.pattern.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.pattern.get(self.pos + offset).copied()
+    }
+
+    fn extended(&self) -> bool {
+        self.syntax == Syntax::Extended
+    }
+
+    // Whether the next token ends the current branch: the end of the
+    // pattern, an alternation or the end of a group
+    fn at_branch_end(&self) -> bool {
+        match (self.syntax, self.peek(), self.peek_at(1)) {
+            (_, None, _) => true,
+            (Syntax::Basic, Some(b'\\'), Some(b'|' | b')')) => true,
+            (Syntax::Extended, Some(b'|'), _) => true,
+            (Syntax::Extended, Some(b')'), _) => self.depth > 0,
+            _ => false,
+        }
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.parse_branch()?];
+        loop {
+            match (self.syntax, self.peek(), self.peek_at(1)) {
+                (Syntax::Basic, Some(b'\\'), Some(b'|')) => self.pos += 2,
+                (Syntax::Extended, Some(b'|'), _) => self.pos += 1,
+                _ => break,
+            }
+            branches.push(self.parse_branch()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap_or(Node::Empty)
+        } else {
+            Node::Alternate(branches)
+        })
+    }
+
+    fn parse_branch(&mut self) -> Result<Node, String> {
+        let mut items: Vec<Node> = Vec::new();
+        // Whether a repetition operator has something to apply to
+        let mut repeatable = false;
+
+        while !self.at_branch_end() {
+            let byte = self.pattern[self.pos];
+
+            if let Some((min, max)) = self.repetition(repeatable)? {
+                let last = items.pop().unwrap_or(Node::Empty);
+                items.push(Node::Repeat(Box::new(last), min, max));
+                continue;
+            }
+            // A leading * is ignored in ERE, like GNU grep -E does
+            if self.extended() && !repeatable && matches!(byte, b'*' | b'+' | b'?') {
+                self.pos += 1;
+                continue;
+            }
+
+            match byte {
+                b'^' if self.extended() || items.is_empty() => {
+                    self.pos += 1;
+                    items.push(Node::Assert(Assertion::LineStart));
+                    repeatable = false;
+                }
+                b'$' if self.extended() || self.dollar_is_anchor() => {
+                    self.pos += 1;
+                    items.push(Node::Assert(Assertion::LineEnd));
+                    repeatable = false;
+                }
+                _ => {
+                    let atom = self.parse_atom()?;
+                    repeatable = !matches!(atom, Node::Assert(_));
+                    items.push(atom);
+                }
+            }
+        }
+
+        Ok(match items.len() {
+            0 => Node::Empty,
+            1 => items.pop().unwrap_or(Node::Empty),
+            _ => Node::Concat(items),
+        })
+    }
+
+    // In BRE, $ is an anchor only at the end of a branch
+    fn dollar_is_anchor(&self) -> bool {
+        matches!(
+            (self.peek_at(1), self.peek_at(2)),
+            (None, _) | (Some(b'\\'), Some(b')' | b'|'))
+        )
+    }
+
+    // Parses *, +, ?, or an interval if one comes next and there is
+    // something to repeat; BRE's operators are literal otherwise
+    fn repetition(&mut self, repeatable: bool) -> Result<Option<(u32, Option<u32>)>, String> {
+        if !repeatable {
+            return Ok(None);
+        }
+        let (operator, length) = match (self.syntax, self.peek(), self.peek_at(1)) {
+            (_, Some(b'*'), _) => (b'*', 1),
+            (Syntax::Extended, Some(op @ (b'+' | b'?' | b'{')), _) => (op, 1),
+            (Syntax::Basic, Some(b'\\'), Some(op @ (b'+' | b'?' | b'{'))) => (op, 2),
+            _ => return Ok(None),
+        };
+        let start = self.pos;
+        self.pos += length;
+        Ok(Some(match operator {
+            b'*' => (0, None),
+            b'+' => (1, None),
+            b'?' => (0, Some(1)),
+            _ => match self.parse_interval()? {
+                Some(bounds) => bounds,
+                None => {
+                    // An ERE { that does not start an interval is literal
+                    self.pos = start;
+                    return Ok(None);
+                }
+            },
+        }))
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let digits = self.pattern[self.pos..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if digits == 0 {
+            return None;
+        }
+        let text = std::str::from_utf8(&self.pattern[self.pos..self.pos + digits]).ok()?;
+        self.pos += digits;
+        Some(text.parse().unwrap_or(u32::MAX))
+    }
+
+    // {m}, {m,}, {,n} or {m,n} after the opening brace; None when an ERE
+    // brace turns out not to start an interval
+    fn parse_interval(&mut self) -> Result<Option<(u32, Option<u32>)>, String> {
+        let invalid = |parser: &Self| {
+            if parser.extended() {
+                Ok(None)
+            } else if parser.pos >= parser.pattern.len() {
+                Err(String::from("Unmatched \\{"))
+            } else {
+                Err(String::from("Invalid content of \\{\\}"))
+            }
+        };
+
+        let min = self.number();
+        let max = if self.peek() == Some(b',') {
+            self.pos += 1;
+            self.number()
+        } else {
+            match min {
+                Some(min) => Some(min),
+                None => return invalid(self),
+            }
+        };
+        let closed = match self.syntax {
+            Syntax::Basic => self.peek() == Some(b'\\') && self.peek_at(1) == Some(b'}'),
+            Syntax::Extended => self.peek() == Some(b'}'),
+        };
+        if !closed {
+            return invalid(self);
+        }
+        self.pos += if self.extended() { 1 } else { 2 };
+
+        let min = min.unwrap_or(0);
+        if max.is_some_and(|max| max < min) {
+            return Err(String::from("Invalid content of \\{\\}"));
+        }
+        if min > MAX_REPEAT || max.is_some_and(|max| max > MAX_REPEAT) {
+            return Err(String::from("Regular expression too big"));
+        }
+        Ok(Some((min, max)))
+    }
+
+    fn literal(&self, byte: u8) -> Node {
+        if self.ignore_case && byte.is_ascii_alphabetic() {
+            Node::Set(set_of(|b| b.eq_ignore_ascii_case(&byte)))
+        } else {
+            Node::Byte(byte)
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        let byte = self.pattern[self.pos];
+        self.pos += 1;
+        match byte {
+            b'.' => Ok(Node::Any),
+            b'[' => self.parse_bracket(),
+            b'(' if self.extended() => self.parse_group(),
+            b'\\' => self.parse_escape(),
+            _ => Ok(self.literal(byte)),
+        }
+    }
+
+    fn parse_group(&mut self) -> Result<Node, String> {
+        self.groups += 1;
+        let index = self.groups;
+        self.depth += 1;
+        let inner = self.parse_alternation()?;
+        self.depth -= 1;
+
+        let closed = match self.syntax {
+            Syntax::Basic => self.peek() == Some(b'\\') && self.peek_at(1) == Some(b')'),
+            Syntax::Extended => self.peek() == Some(b')'),
+        };
+        if !closed {
+            return Err(String::from("Unmatched ( or \\("));
+        }
+        self.pos += if self.extended() { 1 } else { 2 };
+        Ok(Node::Group(Box::new(inner), index))
+    }
+
+    fn parse_escape(&mut self) -> Result<Node, String> {
+        let Some(byte) = self.peek() else {
+            return Err(String::from("Trailing backslash"));
+        };
+        self.pos += 1;
+        Ok(match byte {
+            b'(' if !self.extended() => return self.parse_group(),
+            b'1'..=b'9' => {
+                let index = (byte - b'0') as usize;
+                if index > self.groups {
+                    return Err(String::from("Invalid back reference"));
+                }
+                self.back_references = true;
+                Node::BackReference(index)
+            }
+            b'w' => Node::Set(set_of(is_word)),
+            b'W' => Node::Set(set_of(|b| !is_word(b))),
+            b's' => Node::Set(set_of(|b| b" \t\n\r\x0b\x0c".contains(&b))),
+            b'S' => Node::Set(set_of(|b| !b" \t\n\r\x0b\x0c".contains(&b))),
+            b'b' => Node::Assert(Assertion::WordBoundary),
+            b'B' => Node::Assert(Assertion::NotWordBoundary),
+            b'<' => Node::Assert(Assertion::WordStart),
+            b'>' => Node::Assert(Assertion::WordEnd),
+            b'`' => Node::Assert(Assertion::LineStart),
+            b'\'' => Node::Assert(Assertion::LineEnd),
+            _ => self.literal(byte),
+        })
+    }
+
+    // A bracket expression, after its opening [
+    fn parse_bracket(&mut self) -> Result<Node, String> {
+        let unmatched = || String::from("Unmatched [, [^, [:, [., or [=");
+        let mut set = Box::new([false; 256]);
+        let negated = self.peek() == Some(b'^');
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut first = true;
+        loop {
+            let Some(byte) = self.peek() else {
+                return Err(unmatched());
+            };
+            if byte == b']' && !first {
+                self.pos += 1;
+                break;
+            }
+            first = false;
+
+            let low = match (byte, self.peek_at(1)) {
+                (b'[', Some(b':')) => {
+                    let name = self.bracket_word(b':').ok_or_else(unmatched)?;
+                    let test = class_members(&name).ok_or("Invalid character class name")?;
+                    for b in 0..=255u8 {
+                        if test(b) {
+                            set[b as usize] = true;
+                        }
+                    }
+                    continue;
+                }
+                (b'[', Some(delimiter @ (b'.' | b'='))) => {
+                    let word = self.bracket_word(delimiter).ok_or_else(unmatched)?;
+                    match word[..] {
+                        [single] => single,
+                        _ => return Err(String::from("Invalid collation character")),
+                    }
+                }
+                _ => {
+                    self.pos += 1;
+                    byte
+                }
+            };
+
+            // A - that is not last makes a range
+            if self.peek() == Some(b'-') && self.peek_at(1).is_some_and(|b| b != b']') {
+                self.pos += 1;
+                let high = match (self.peek(), self.peek_at(1)) {
+                    (Some(b'['), Some(delimiter @ (b'.' | b'='))) => {
+                        let word = self.bracket_word(delimiter).ok_or_else(unmatched)?;
+                        match word[..] {
+                            [single] => single,
+                            _ => return Err(String::from("Invalid collation character")),
+                        }
+                    }
+                    (Some(high), _) => {
+                        self.pos += 1;
+                        high
+                    }
+                    (None, _) => return Err(unmatched()),
+                };
+                if high < low {
+                    return Err(String::from("Invalid range end"));
+                }
+                for b in low..=high {
+                    set[b as usize] = true;
+                }
+            } else {
+                set[low as usize] = true;
+            }
+        }
+
+        if self.ignore_case {
+            for b in 0..=255u8 {
+                if b.is_ascii_alphabetic() && set[b as usize] {
+                    set[b.to_ascii_lowercase() as usize] = true;
+                    set[b.to_ascii_uppercase() as usize] = true;
+                }
+            }
+        }
+        if negated {
+            for member in set.iter_mut() {
+                *member = !*member;
+            }
+            // A negated set never matches a newline, as with REG_NEWLINE
+            set[b'\n' as usize] = false;
+        }
+        Ok(Node::Set(set))
+    }
+
+    // The word inside [:word:], [.word.] or [=word=], at the opening [
+    fn bracket_word(&mut self, delimiter: u8) -> Option<Vec<u8>> {
+        let start = self.pos + 2;
+        let length = self.pattern[start..]
+            .windows(2)
+            .position(|pair| pair == [delimiter, b']'])?;
+        self.pos = start + length + 2;
+        Some(self.pattern[start..start + length].to_vec())
+    }
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn emit(&mut self, node: &Node) {
+        match node {
+            Node::Empty => {}
+            Node::Byte(byte) => self.program.push(Inst::Byte(*byte)),
+            Node::Any => self.program.push(Inst::Any),
+            Node::Set(set) => self.program.push(Inst::Set(set.clone())),
+            Node::Assert(assertion) => self.program.push(Inst::Assert(*assertion)),
+            Node::BackReference(index) => self.program.push(Inst::BackReference(*index)),
+            Node::Group(inner, index) => {
+                self.program.push(Inst::Save(index * 2));
+                self.emit(inner);
+                self.program.push(Inst::Save(index * 2 + 1));
+            }
+            Node::Concat(items) => {
+                for item in items {
+                    self.emit(item);
+                }
+            }
+            Node::Alternate(branches) => {
+                let mut jumps = Vec::new();
+                for (i, branch) in branches.iter().enumerate() {
+                    if i + 1 < branches.len() {
+                        let split = self.placeholder();
+                        self.emit(branch);
+                        jumps.push(self.placeholder());
+                        let next = self.program.len();
+                        self.program[split] = Inst::Split(split + 1, next);
+                    } else {
+                        self.emit(branch);
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat(inner, min, max) => {
+                for _ in 0..*min {
+                    self.emit(inner);
+                }
+                match max {
+                    None => {
+                        let split = self.placeholder();
+                        self.emit(inner);
+                        self.program.push(Inst::Jump(split));
+                        let end = self.program.len();
+                        self.program[split] = Inst::Split(split + 1, end);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.placeholder());
+                            self.emit(inner);
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = Inst::Split(split + 1, end);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn placeholder(&mut self) -> usize {
+        self.program.push(Inst::Match);
+        self.program.len() - 1
+    }
+}
+
+// The threads of the Pike VM: a sparse set of program counters, each with
+// the position its match started at, kept in order of priority
+struct Threads {
+    dense: Vec<(usize, usize)>,
+    sparse: Vec<usize>,
+}
+
+impl Threads {
+    fn new(size: usize) -> Self {
+        Threads {
+            dense: Vec::with_capacity(size),
+            sparse: vec![0; size],
+        }
+    }
+
+    fn contains(&self, pc: usize) -> bool {
+        let index = self.sparse[pc];
+        index < self.dense.len() && self.dense[index].0 == pc
+    }
+
+    fn insert(&mut self, pc: usize, start: usize) {
+        self.sparse[pc] = self.dense.len();
+        self.dense.push((pc, start));
+    }
+}
+
+impl Regex {
+    pub fn new(pattern: &str, syntax: Syntax, ignore_case: bool) -> Result<Regex, String> {
+        let mut parser = Parser {
+            pattern: pattern.as_bytes(),
+            pos: 0,
+            syntax,
+            ignore_case,
+            depth: 0,
+            groups: 0,
+            back_references: false,
+        };
+        let node = parser.parse_alternation()?;
+        if parser.pos < parser.pattern.len() {
+            // Only a \) without its \( stops the top level early
+            return Err(String::from("Unmatched ) or \\)"));
+        }
+
+        let mut compiler = Compiler {
+            program: Vec::new(),
+        };
+        compiler.emit(&node);
+        compiler.program.push(Inst::Match);
+        Ok(Regex {
+            program: compiler.program,
+            groups: parser.groups,
+            back_references: parser.back_references,
+        })
+    }
+
+    pub fn is_match(&self, text: &[u8]) -> bool {
+        self.search(text, 0, true).is_some()
+    }
+
+    // The leftmost-longest match starting at or after `from`, as start and
+    // end offsets. Anchors and word boundaries still look at the whole text.
+    pub fn find_at(&self, text: &[u8], from: usize) -> Option<(usize, usize)> {
+        self.search(text, from, false)
+    }
+
+    // How many \( \) or ( ) groups the pattern has
+    pub fn groups(&self) -> usize {
+        self.groups
+    }
+
+    // Like find_at, but also gives where each group matched: entry 0 is the
+    // whole match, and entry N the last text group N matched, if any
+    pub fn captures_at(&self, text: &[u8], from: usize) -> Option<Vec<Option<(usize, usize)>>> {
+        if from > text.len() {
+            return None;
+        }
+        for start in from..=text.len() {
+            let mut saves = vec![None; (self.groups + 1) * 2];
+            let mut visits = vec![usize::MAX; self.program.len()];
+            let mut longest = None;
+            self.backtrack(text, 0, start, &mut saves, &mut visits, &mut longest);
+            if let Some((end, saves)) = longest {
+                let mut groups = vec![Some((start, end))];
+                for index in 1..=self.groups {
+                    groups.push(match (saves[index * 2], saves[index * 2 + 1]) {
+                        (Some(start), Some(end)) => Some((start, end)),
+                        _ => None,
+                    });
+                }
+                return Some(groups);
+            }
+        }
+        None
+    }
+
+    fn holds(assertion: Assertion, text: &[u8], pos: usize) -> bool {
+        let before = pos > 0 && is_word(text[pos - 1]);
+        let after = pos < text.len() && is_word(text[pos]);
+        match assertion {
+            Assertion::LineStart => pos == 0,
+            Assertion::LineEnd => pos == text.len(),
+            Assertion::WordBoundary => before != after,
+            Assertion::NotWordBoundary => before == after,
+            Assertion::WordStart => !before && after,
+            Assertion::WordEnd => before && !after,
+        }
+    }
+
+    // Adds the thread at `pc` and everything it reaches without consuming
+    // input
+    fn add(&self, threads: &mut Threads, pc: usize, start: usize, text: &[u8], pos: usize) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if threads.contains(pc) {
+                continue;
+            }
+            threads.insert(pc, start);
+            match &self.program[pc] {
+                Inst::Jump(target) => stack.push(*target),
+                Inst::Split(first, second) => {
+                    stack.push(*second);
+                    stack.push(*first);
+                }
+                Inst::Save(_) => stack.push(pc + 1),
+                Inst::Assert(assertion) if Self::holds(*assertion, text, pos) => {
+                    stack.push(pc + 1);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn search(&self, text: &[u8], from: usize, any: bool) -> Option<(usize, usize)> {
+        if from > text.len() {
+            return None;
+        }
+        if self.back_references {
+            return self.backtrack_search(text, from);
+        }
+
+        let size = self.program.len();
+        let mut current = Threads::new(size);
+        let mut next = Threads::new(size);
+        let mut best: Option<(usize, usize)> = None;
+        let mut pos = from;
+
+        loop {
+            // Later starts only matter while nothing has matched yet
+            if best.is_none() {
+                self.add(&mut current, 0, pos, text, pos);
+            }
+
+            for i in 0..current.dense.len() {
+                let (pc, start) = current.dense[i];
+                if best.is_some_and(|(best_start, _)| start > best_start) {
+                    continue;
+                }
+                let consumes = match &self.program[pc] {
+                    Inst::Match => {
+                        if best.is_none_or(|(s, e)| start < s || (start == s && pos > e)) {
+                            best = Some((start, pos));
+                        }
+                        if any {
+                            return best;
+                        }
+                        false
+                    }
+                    Inst::Byte(byte) => text.get(pos) == Some(byte),
+                    Inst::Any => pos < text.len() && text[pos] != b'\n',
+                    Inst::Set(set) => pos < text.len() && set[text[pos] as usize],
+                    _ => false,
+                };
+                if consumes {
+                    self.add(&mut next, pc + 1, start, text, pos + 1);
+                }
+            }
+
+            if pos >= text.len() || (best.is_some() && next.dense.is_empty()) {
+                break;
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.dense.clear();
+            pos += 1;
+        }
+        best
+    }
+
+    // Exhaustive backtracking, for patterns whose back-references the VM
+    // cannot follow
+    fn backtrack_search(&self, text: &[u8], from: usize) -> Option<(usize, usize)> {
+        for start in from..=text.len() {
+            let mut saves = vec![None; (self.groups + 1) * 2];
+            let mut visits = vec![usize::MAX; self.program.len()];
+            let mut longest = None;
+            self.backtrack(text, 0, start, &mut saves, &mut visits, &mut longest);
+            if let Some((end, _)) = longest {
+                return Some((start, end));
+            }
+        }
+        None
+    }
+
+    fn backtrack(
+        &self,
+        text: &[u8],
+        mut pc: usize,
+        mut pos: usize,
+        saves: &mut [Option<usize>],
+        visits: &mut [usize],
+        longest: &mut Option<(usize, Vec<Option<usize>>)>,
+    ) {
+        loop {
+            match &self.program[pc] {
+                Inst::Match => {
+                    // The first way found of reaching the longest end is
+                    // the one whose groups are kept
+                    if longest.as_ref().is_none_or(|(end, _)| pos > *end) {
+                        *longest = Some((pos, saves.to_vec()));
+                    }
+                    return;
+                }
+                Inst::Byte(byte) if text.get(pos) == Some(byte) => pos += 1,
+                Inst::Any if pos < text.len() && text[pos] != b'\n' => pos += 1,
+                Inst::Set(set) if pos < text.len() && set[text[pos] as usize] => pos += 1,
+                Inst::Assert(assertion) if Self::holds(*assertion, text, pos) => {}
+                Inst::Jump(target) => {
+                    pc = *target;
+                    continue;
+                }
+                Inst::Split(first, second) => {
+                    // Coming back to a split without consuming anything
+                    // means an empty loop iteration, which would never end
+                    if visits[pc] == pos {
+                        return;
+                    }
+                    let previous = visits[pc];
+                    visits[pc] = pos;
+                    let mut copy = saves.to_vec();
+                    self.backtrack(text, *first, pos, &mut copy, visits, longest);
+                    self.backtrack(text, *second, pos, saves, visits, longest);
+                    visits[pc] = previous;
+                    return;
+                }
+                Inst::Save(slot) => {
+                    let previous = saves[*slot];
+                    saves[*slot] = Some(pos);
+                    self.backtrack(text, pc + 1, pos, saves, visits, longest);
+                    saves[*slot] = previous;
+                    return;
+                }
+                Inst::BackReference(index) => {
+                    let (Some(start), Some(end)) = (saves[index * 2], saves[index * 2 + 1]) else {
+                        return;
+                    };
+                    let captured = &text[start..end];
+                    if !text[pos..].starts_with(captured) {
+                        return;
+                    }
+                    pos += captured.len();
+                }
+                _ => return,
+            }
+            pc += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(syntax: Syntax, pattern: &str, text: &str) -> Option<(usize, usize)> {
+        Regex::new(pattern, syntax, false)
+            .unwrap()
+            .find_at(text.as_bytes(), 0)
+    }
+
+    #[test]
+    fn basic_syntax() {
+        for (pattern, text, expected) in [
+            ("abc", "xxabcxx", Some((2, 5))),
+            ("a.c", "abc", Some((0, 3))),
+            ("^ab", "xab", None),
+            ("ab$", "abab", Some((2, 4))),
+            ("a*", "baaa", Some((0, 0))),
+            ("ba*", "baaa", Some((0, 4))),
+            ("a\\+", "baaa", Some((1, 4))),
+            ("colou\\?r", "color", Some((0, 5))),
+            ("a\\{2,3\\}", "aaaa", Some((0, 3))),
+            ("a\\{2\\}", "a", None),
+            ("cat\\|dog", "hotdog", Some((3, 6))),
+            ("\\(ab\\)*c", "ababc", Some((0, 5))),
+            ("\\(a\\)\\1", "xaa", Some((1, 3))),
+            ("a+b", "a+b", Some((0, 3))),
+            ("a{2}", "a{2}", Some((0, 4))),
+            ("*a", "*a", Some((0, 2))),
+            ("[[:digit:]]\\+", "ab123c", Some((2, 5))),
+            ("[^a-c]", "abcd", Some((3, 4))),
+            ("[]a]", "]", Some((0, 1))),
+            ("\\<is\\>", "this is", Some((5, 7))),
+            ("\\bx", "ax x", Some((3, 4))),
+            ("\\w\\+", "  foo_1 ", Some((2, 7))),
+            ("\\s", "a b", Some((1, 2))),
+        ] {
+            assert_eq!(find(Syntax::Basic, pattern, text), expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn extended_syntax() {
+        for (pattern, text, expected) in [
+            ("a+", "baaa", Some((1, 4))),
+            ("colou?r", "colour", Some((0, 6))),
+            ("a{2,}", "aaaaa", Some((0, 5))),
+            ("(cat|dog)s", "dogs", Some((0, 4))),
+            // Leftmost, then longest, whatever the order of alternatives
+            ("a|ab|abc", "abcd", Some((0, 3))),
+            ("(a|ab)(c|bcd)", "abcd", Some((0, 4))),
+            ("x*", "", Some((0, 0))),
+            ("^$", "", Some((0, 0))),
+            ("(a)(b)?\\2", "ab", None),
+            (
+                "[a-z]+@[a-z]+\\.com",
+                "mail bob@example.com now",
+                Some((5, 20)),
+            ),
+            ("\\.", "a.b", Some((1, 2))),
+        ] {
+            assert_eq!(
+                find(Syntax::Extended, pattern, text),
+                expected,
+                "{}",
+                pattern
+            );
+        }
+    }
+
+    #[test]
+    fn ignoring_case() {
+        let regex = Regex::new("hello [a-c]", Syntax::Extended, true).unwrap();
+        assert!(regex.is_match(b"HeLLo B"));
+        assert!(!regex.is_match(b"hello d"));
+    }
+
+    #[test]
+    fn matches_from_an_offset() {
+        let regex = Regex::new("\\<o", Syntax::Extended, false).unwrap();
+        assert_eq!(regex.find_at(b"one two", 1), None);
+        let regex = Regex::new("o", Syntax::Extended, false).unwrap();
+        assert_eq!(regex.find_at(b"one two", 1), Some((6, 7)));
+        assert_eq!(regex.find_at(b"one", 4), None);
+    }
+
+    #[test]
+    fn captures() {
+        let regex = Regex::new("(a+)(b)?(c)", Syntax::Extended, false).unwrap();
+        assert_eq!(regex.groups(), 3);
+        assert_eq!(
+            regex.captures_at(b"xaac", 0),
+            Some(vec![Some((1, 4)), Some((1, 3)), None, Some((3, 4))])
+        );
+        let regex = Regex::new("\\(x*\\)*", Syntax::Basic, false).unwrap();
+        assert_eq!(
+            regex.captures_at(b"", 0).map(|groups| groups[0]),
+            Some(Some((0, 0)))
+        );
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        for (pattern, syntax) in [
+            ("\\(a", Syntax::Basic),
+            ("a\\)", Syntax::Basic),
+            ("(a", Syntax::Extended),
+            ("[a", Syntax::Extended),
+            ("a{3,1}", Syntax::Extended),
+            ("a{99999}", Syntax::Extended),
+            ("\\1", Syntax::Basic),
+            ("[[:nope:]]", Syntax::Basic),
+        ] {
+            assert!(Regex::new(pattern, syntax, false).is_err(), "{}", pattern);
+        }
+    }
+}
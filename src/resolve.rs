@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+// Path canonicalization for realpath, readlink -f and ln -r. Unlike
+// fs::canonicalize, components are resolved one at a time, so paths whose
+// tail does not exist yet can still be canonicalized.
+
+// Which components of the path have to exist
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Existence {
+    // Every component (realpath -e, readlink -e)
+    All,
+    // Every component but the last (the default, readlink -f)
+    AllButLast,
+    // None at all (-m)
+    Nothing,
+}
+
+// Same limit as Linux's MAXSYMLINKS; following more links than this means
+// the links form a loop
+const MAX_SYMLINKS: usize = 40;
+
+fn absolute(path: &Path) -> io::Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        Ok(env::current_dir()?.join(path))
+    }
+}
+
+pub fn canonicalize(path: &Path, existence: Existence) -> io::Result<PathBuf> {
+    if path.as_os_str().is_empty() {
+        return Err(io::Error::from(io::ErrorKind::NotFound));
+    }
+
+    let mut pending: VecDeque<OsString> = VecDeque::new();
+    push_components(&mut pending, &absolute(path)?);
+    let mut resolved = PathBuf::from("/");
+    let mut links_followed = 0;
+
+    while let Some(name) = pending.pop_front() {
+        if name == "." {
+            continue;
+        }
+        if name == ".." {
+            resolved.pop();
+            continue;
+        }
+
+        let candidate = resolved.join(&name);
+        let is_last = pending.is_empty();
+        match fs::symlink_metadata(&candidate) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                links_followed += 1;
+                if links_followed > MAX_SYMLINKS {
+                    // -m gives up on the links and keeps the path as written
+                    if existence == Existence::Nothing {
+                        return normalize(path);
+                    }
+                    return Err(io::Error::from_raw_os_error(libc::ELOOP));
+                }
+                let target = fs::read_link(&candidate)?;
+                if target.is_absolute() {
+                    resolved = PathBuf::from("/");
+                }
+                // The target's components are resolved before what remains
+                let mut rest = VecDeque::new();
+                push_components(&mut rest, &target);
+                rest.append(&mut pending);
+                pending = rest;
+            }
+            Ok(metadata) => {
+                if !is_last && !metadata.is_dir() && existence != Existence::Nothing {
+                    return Err(io::Error::from_raw_os_error(libc::ENOTDIR));
+                }
+                resolved = candidate;
+            }
+            Err(e) => {
+                let allowed = match existence {
+                    Existence::All => false,
+                    Existence::AllButLast => is_last && e.kind() == io::ErrorKind::NotFound,
+                    Existence::Nothing => true,
+                };
+                if !allowed {
+                    return Err(e);
+                }
+                resolved = candidate;
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+fn push_components(queue: &mut VecDeque<OsString>, path: &Path) {
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => queue.push_back(name.to_os_string()),
+            Component::CurDir => queue.push_back(OsString::from(".")),
+            Component::ParentDir => queue.push_back(OsString::from("..")),
+            Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+}
+
+// Makes a path absolute and removes . and .. without looking at the
+// filesystem, so symlinks are left alone (realpath -s)
+pub fn normalize(path: &Path) -> io::Result<PathBuf> {
+    let mut normalized = PathBuf::from("/");
+    for component in absolute(path)?.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => {}
+        }
+    }
+    Ok(normalized)
+}
+
+// The path that leads from directory `base` to `target`, both absolute
+// and canonical
+pub fn relative_path(base: &Path, target: &Path) -> PathBuf {
+    let base: Vec<Component> = base.components().collect();
+    let target: Vec<Component> = target.components().collect();
+    let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &target[common..] {
+        relative.push(component.as_os_str());
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
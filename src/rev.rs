@@ -0,0 +1,125 @@
+use cmds_lite::error;
+use cmds_lite::unicode;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+#[derive(Default)]
+pub struct RevOptions {
+    // -0: lines end with NUL instead of newline
+    pub zero: bool,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("rev: {}", message);
+    std::process::exit(1);
+}
+
+// The line reversed by grapheme cluster, so combining marks stay on their
+// letters and emoji sequences stay whole. Bytes that are not UTF-8 are
+// kept, each as a cluster of its own.
+fn reverse(line: &[u8]) -> Vec<u8> {
+    let mut clusters: Vec<&[u8]> = Vec::new();
+    for chunk in line.utf8_chunks() {
+        clusters.extend(unicode::graphemes(chunk.valid()).map(str::as_bytes));
+        clusters.extend(chunk.invalid().chunks(1));
+    }
+    clusters.into_iter().rev().flatten().copied().collect()
+}
+
+struct RevCommand<'a> {
+    options: &'a RevOptions,
+}
+
+impl<'a> RevCommand<'a> {
+    fn rev<R: BufRead, W: Write>(&self, mut input: R, output: &mut W) -> io::Result<()> {
+        let terminator = if self.options.zero { b'\0' } else { b'\n' };
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if input.read_until(terminator, &mut line)? == 0 {
+                return Ok(());
+            }
+            let ended = line.last() == Some(&terminator);
+            if ended {
+                line.pop();
+            }
+            output.write_all(&reverse(&line))?;
+            if ended {
+                output.write_all(&[terminator])?;
+            }
+        }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let stdout = io::stdout();
+        let mut output = BufWriter::new(stdout.lock());
+        let mut ok = true;
+
+        for file in files {
+            let result = if file == "-" {
+                self.rev(io::stdin().lock(), &mut output)
+            } else {
+                match File::open(file) {
+                    Ok(f) => self.rev(BufReader::new(f), &mut output),
+                    Err(e) => {
+                        eprintln!("rev: cannot open {}: {}", file, error::message(&e));
+                        ok = false;
+                        continue;
+                    }
+                }
+            };
+            match result {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return ok,
+                Err(e) => {
+                    eprintln!("rev: {}: {}", file, error::message(&e));
+                    ok = false;
+                }
+            }
+        }
+
+        match output.flush() {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                eprintln!("rev: write error: {}", error::message(&e));
+                false
+            }
+            _ => ok,
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = RevOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--zero" => options.zero = true,
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        '0' => options.zero = true,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    let command = RevCommand { options: &options };
+    if !command.run(&files) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,550 @@
+use cmds_lite::datetime::{self, Timestamp};
+use cmds_lite::error;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interactive {
+    #[default]
+    Never,
+    Once,
+    Always,
+}
+
+pub struct RmOptions {
+    pub recursive: bool,
+    pub force: bool,
+    pub interactive: Interactive,
+    pub preserve_root: bool,
+    pub trash: bool,
+    pub verbose: bool,
+}
+
+impl Default for RmOptions {
+    fn default() -> Self {
+        RmOptions {
+            recursive: false,
+            force: false,
+            interactive: Interactive::Never,
+            preserve_root: true,
+            trash: false,
+            verbose: false,
+        }
+    }
+}
+
+trait Remover {
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+    // Whether directories have to be emptied before remove_dir is called
+    fn needs_descent(&self) -> bool;
+}
+
+trait Prompter {
+    fn confirm(&self, question: &str) -> bool;
+}
+
+struct UnlinkRemover;
+
+impl Remover for UnlinkRemover {
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(path)
+    }
+
+    fn needs_descent(&self) -> bool {
+        true
+    }
+}
+
+// Moves entries into the XDG trash ($XDG_DATA_HOME/Trash) instead of
+// unlinking them, writing the matching .trashinfo record for restore tools.
+// An entry on another file system goes to the trash at the top of that
+// file system instead, since it cannot be renamed into the home one.
+struct TrashRemover {
+    home: Trash,
+    // Device the home trash is on
+    home_device: u64,
+}
+
+// One trash directory, with its files and info subdirectories
+struct Trash {
+    files_dir: PathBuf,
+    info_dir: PathBuf,
+    // For a trash at the top of a file system, that directory; the paths
+    // recorded in it are relative to it
+    top: Option<PathBuf>,
+}
+
+impl Trash {
+    fn create(dir: &Path, top: Option<PathBuf>) -> io::Result<Self> {
+        let files_dir = dir.join("files");
+        let info_dir = dir.join("info");
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+        Ok(Trash {
+            files_dir,
+            info_dir,
+            top,
+        })
+    }
+
+    // The trash for a file system mounted at `top`: the shared .Trash
+    // directory's subdirectory for this user when an administrator has set
+    // one up, or else the user's own .Trash-UID
+    fn at_top(top: &Path) -> io::Result<Self> {
+        let uid = unsafe { libc::getuid() };
+        let shared = top.join(".Trash");
+        if let Ok(metadata) = shared.symlink_metadata() {
+            if metadata.is_dir() && metadata.permissions().mode() & 0o1000 != 0 {
+                let dir = shared.join(uid.to_string());
+                if let Ok(trash) = Trash::create(&dir, Some(top.to_path_buf())) {
+                    return Ok(trash);
+                }
+            }
+        }
+
+        let dir = top.join(format!(".Trash-{}", uid));
+        match fs::DirBuilder::new().mode(0o700).create(&dir) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+        if !dir.symlink_metadata()?.is_dir() {
+            return Err(io::Error::from_raw_os_error(libc::ENOTDIR));
+        }
+        Trash::create(&dir, Some(top.to_path_buf()))
+    }
+
+    fn put(&self, path: &Path, absolute: &Path) -> io::Result<()> {
+        let base = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("unnamed"));
+        let recorded = match &self.top {
+            Some(top) => absolute.strip_prefix(top).unwrap_or(absolute),
+            None => absolute,
+        };
+
+        // Reserve a unique name by creating the info file exclusively
+        let mut counter = 1;
+        loop {
+            let name = if counter == 1 {
+                base.clone()
+            } else {
+                format!("{}.{}", base, counter)
+            };
+            let info_path = self.info_dir.join(format!("{}.trashinfo", name));
+            let target = self.files_dir.join(&name);
+            if target.symlink_metadata().is_ok() {
+                counter += 1;
+                continue;
+            }
+
+            match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(mut info) => {
+                    write!(
+                        info,
+                        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                        percent_encode(&recorded.to_string_lossy()),
+                        deletion_date()
+                    )?;
+                    if let Err(e) = fs::rename(path, &target) {
+                        let _ = fs::remove_file(&info_path);
+                        return Err(e);
+                    }
+                    return Ok(());
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => counter += 1,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl TrashRemover {
+    fn new() -> io::Result<Self> {
+        let data_home = match env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => {
+                let home = env::var_os("HOME").ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, "HOME is not set")
+                })?;
+                PathBuf::from(home).join(".local/share")
+            }
+        };
+        let home = Trash::create(&data_home.join("Trash"), None)?;
+        let home_device = fs::metadata(&home.files_dir)?.dev();
+        Ok(TrashRemover { home, home_device })
+    }
+
+    fn trash(&self, path: &Path) -> io::Result<()> {
+        // The directory holding the entry is resolved, so that the recorded
+        // path and the file system it is on are those of the entry itself
+        // rather than of any link to its directory
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let absolute = fs::canonicalize(parent)?.join(name);
+
+        let device = path.symlink_metadata()?.dev();
+        if device == self.home_device {
+            return self.home.put(path, &absolute);
+        }
+        Trash::at_top(&mount_point(&absolute, device)?)?.put(path, &absolute)
+    }
+}
+
+// The top directory of the file system `path` is on, found by climbing
+// until the device changes
+fn mount_point(path: &Path, device: u64) -> io::Result<PathBuf> {
+    let mut top = path.parent().unwrap_or(path);
+    while let Some(parent) = top.parent() {
+        if fs::symlink_metadata(parent)?.dev() != device {
+            break;
+        }
+        top = parent;
+    }
+    Ok(top.to_path_buf())
+}
+
+impl Remover for TrashRemover {
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.trash(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        self.trash(path)
+    }
+
+    fn needs_descent(&self) -> bool {
+        false
+    }
+}
+
+fn percent_encode(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// The current local time, as the trash spec wants it
+fn deletion_date() -> String {
+    let now = Timestamp::now();
+    match datetime::local_time(now.secs) {
+        Some(time) => format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            time.year, time.month, time.day, time.hour, time.minute, time.second
+        ),
+        None => String::new(),
+    }
+}
+
+struct StdinPrompter;
+
+impl Prompter for StdinPrompter {
+    fn confirm(&self, question: &str) -> bool {
+        eprint!("rm: {}? ", question);
+        let _ = io::stderr().flush();
+        let mut answer = String::new();
+        if io::stdin().lock().read_line(&mut answer).is_err() {
+            return false;
+        }
+        matches!(answer.trim_start().chars().next(), Some('y') | Some('Y'))
+    }
+}
+
+fn describe(metadata: &fs::Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_symlink() {
+        "symbolic link"
+    } else if metadata.len() == 0 {
+        "regular empty file"
+    } else {
+        "regular file"
+    }
+}
+
+struct RmCommand<'a> {
+    remover: Box<dyn Remover>,
+    prompter: Box<dyn Prompter>,
+    options: &'a RmOptions,
+}
+
+impl<'a> RmCommand<'a> {
+    fn new(options: &'a RmOptions) -> io::Result<Self> {
+        let remover: Box<dyn Remover> = if options.trash {
+            Box::new(TrashRemover::new()?)
+        } else {
+            Box::new(UnlinkRemover)
+        };
+        Ok(RmCommand {
+            remover,
+            prompter: Box::new(StdinPrompter),
+            options,
+        })
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let options = self.options;
+
+        if options.interactive == Interactive::Once
+            && (files.len() > 3 || options.recursive)
+            && !files.is_empty()
+        {
+            let noun = if files.len() == 1 { "argument" } else { "arguments" };
+            let question = if options.recursive {
+                format!("remove {} {} recursively", files.len(), noun)
+            } else {
+                format!("remove {} {}", files.len(), noun)
+            };
+            if !self.prompter.confirm(&question) {
+                return true;
+            }
+        }
+
+        let mut ok = true;
+        for file in files {
+            ok &= self.remove_operand(file);
+        }
+        ok
+    }
+
+    fn remove_operand(&self, file: &str) -> bool {
+        let path = Path::new(file);
+
+        if is_dot_or_dotdot(file) {
+            eprintln!(
+                "rm: refusing to remove '.' or '..' directory: skipping '{}'",
+                file
+            );
+            return false;
+        }
+
+        if self.options.preserve_root && is_root(path) {
+            if self.options.recursive || self.options.trash {
+                eprintln!("rm: it is dangerous to operate recursively on '{}'", file);
+                eprintln!("rm: use --no-preserve-root to override this failsafe");
+            } else {
+                eprintln!("rm: cannot remove '{}': Is a directory", file);
+            }
+            return false;
+        }
+
+        let metadata = match path.symlink_metadata() {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                if self.options.force && e.kind() == io::ErrorKind::NotFound {
+                    return true;
+                }
+                eprintln!("rm: cannot remove '{}': {}", file, error::message(&e));
+                return false;
+            }
+        };
+
+        self.remove_path(path, &metadata)
+    }
+
+    fn remove_path(&self, path: &Path, metadata: &fs::Metadata) -> bool {
+        let display = path.display();
+        let interactive = self.options.interactive == Interactive::Always;
+
+        if !metadata.is_dir() {
+            if interactive
+                && !self
+                    .prompter
+                    .confirm(&format!("remove {} '{}'", describe(metadata), display))
+            {
+                return true;
+            }
+            return self.report(path, false, self.remover.remove_file(path));
+        }
+
+        if !self.options.recursive {
+            eprintln!("rm: cannot remove '{}': Is a directory", display);
+            return false;
+        }
+
+        let mut ok = true;
+        if self.remover.needs_descent() {
+            let entries = match fs::read_dir(path) {
+                Ok(entries) => entries.collect::<Result<Vec<_>, _>>(),
+                Err(e) => Err(e),
+            };
+            let mut entries = match entries {
+                Ok(entries) => entries,
+                Err(e) => {
+                    eprintln!("rm: cannot remove '{}': {}", display, error::message(&e));
+                    return false;
+                }
+            };
+
+            if !entries.is_empty()
+                && interactive
+                && !self
+                    .prompter
+                    .confirm(&format!("descend into directory '{}'", display))
+            {
+                return true;
+            }
+
+            entries.sort_by_key(|entry| entry.file_name());
+            for entry in entries {
+                let child = entry.path();
+                match child.symlink_metadata() {
+                    Ok(child_metadata) => ok &= self.remove_path(&child, &child_metadata),
+                    Err(e) => {
+                        eprintln!(
+                            "rm: cannot remove '{}': {}",
+                            child.display(),
+                            error::message(&e)
+                        );
+                        ok = false;
+                    }
+                }
+            }
+        }
+
+        if interactive
+            && !self
+                .prompter
+                .confirm(&format!("remove directory '{}'", display))
+        {
+            return ok;
+        }
+        ok && self.report(path, true, self.remover.remove_dir(path))
+    }
+
+    fn report(&self, path: &Path, is_dir: bool, result: io::Result<()>) -> bool {
+        match result {
+            Ok(()) => {
+                if self.options.verbose {
+                    if self.options.trash {
+                        println!("trashed '{}'", path.display());
+                    } else if is_dir {
+                        println!("removed directory '{}'", path.display());
+                    } else {
+                        println!("removed '{}'", path.display());
+                    }
+                }
+                true
+            }
+            Err(e) => {
+                eprintln!(
+                    "rm: cannot remove '{}': {}",
+                    path.display(),
+                    error::message(&e)
+                );
+                false
+            }
+        }
+    }
+}
+
+fn is_dot_or_dotdot(file: &str) -> bool {
+    let trimmed = file.trim_end_matches('/');
+    let last = trimmed.rsplit('/').next().unwrap_or(trimmed);
+    !trimmed.is_empty() && (last == "." || last == "..")
+}
+
+fn is_root(path: &Path) -> bool {
+    match fs::canonicalize(path) {
+        Ok(resolved) => resolved == Path::new("/"),
+        Err(_) => false,
+    }
+}
+
+pub fn run(files: &[String], options: &RmOptions) -> io::Result<bool> {
+    let command = RmCommand::new(options)?;
+    Ok(command.run(files))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = RmOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--recursive" => options.recursive = true,
+            "--force" => {
+                options.force = true;
+                options.interactive = Interactive::Never;
+            }
+            "--verbose" => options.verbose = true,
+            "--trash" => options.trash = true,
+            "--preserve-root" => options.preserve_root = true,
+            "--no-preserve-root" => options.preserve_root = false,
+            _ if arg.starts_with("--") => {
+                eprintln!("rm: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'r' | 'R' => options.recursive = true,
+                        // The last of -f, -i and -I wins
+                        'f' => {
+                            options.force = true;
+                            options.interactive = Interactive::Never;
+                        }
+                        'i' => {
+                            options.force = false;
+                            options.interactive = Interactive::Always;
+                        }
+                        'I' => {
+                            options.force = false;
+                            options.interactive = Interactive::Once;
+                        }
+                        'v' => options.verbose = true,
+                        _ => {
+                            eprintln!("rm: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        if options.force {
+            return;
+        }
+        eprintln!("rm: missing operand");
+        std::process::exit(1);
+    }
+
+    match run(&files, &options) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("rm: cannot use the trash: {}", error::message(&e));
+            std::process::exit(1);
+        }
+    }
+}
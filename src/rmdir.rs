@@ -0,0 +1,143 @@
+use cmds_lite::error;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Default)]
+pub struct RmdirOptions {
+    pub parents: bool,
+    pub ignore_non_empty: bool,
+    pub verbose: bool,
+}
+
+enum Outcome {
+    Removed,
+    // Not empty, but --ignore-fail-on-non-empty was given
+    Ignored,
+    Failed,
+}
+
+struct RmdirCommand<'a> {
+    options: &'a RmdirOptions,
+}
+
+impl<'a> RmdirCommand<'a> {
+    fn new(options: &'a RmdirOptions) -> Self {
+        RmdirCommand { options }
+    }
+
+    fn run(&self, dirs: &[String]) -> bool {
+        let mut ok = true;
+        for dir in dirs {
+            ok &= self.remove_operand(dir);
+        }
+        ok
+    }
+
+    fn remove_operand(&self, dir: &str) -> bool {
+        match self.remove(dir) {
+            Outcome::Removed => {}
+            Outcome::Ignored => return true,
+            Outcome::Failed => return false,
+        }
+        if !self.options.parents {
+            return true;
+        }
+
+        // Walk up the operand itself, not the resolved path, like GNU rmdir -p
+        let mut current = trim_trailing_slashes(dir);
+        while let Some(parent) = Path::new(current).parent() {
+            let parent = trim_trailing_slashes(parent.to_str().unwrap_or(""));
+            if parent.is_empty() || parent == "/" {
+                break;
+            }
+            match self.remove(parent) {
+                Outcome::Removed => {}
+                Outcome::Ignored => return true,
+                Outcome::Failed => return false,
+            }
+            current = parent;
+        }
+        true
+    }
+
+    fn remove(&self, dir: &str) -> Outcome {
+        if self.options.verbose {
+            println!("rmdir: removing directory, '{}'", dir);
+        }
+
+        match fs::remove_dir(dir) {
+            Ok(()) => Outcome::Removed,
+            Err(e) if self.options.ignore_non_empty && is_non_empty_error(&e) => Outcome::Ignored,
+            Err(e) => {
+                eprintln!("rmdir: failed to remove '{}': {}", dir, error::message(&e));
+                Outcome::Failed
+            }
+        }
+    }
+}
+
+fn trim_trailing_slashes(path: &str) -> &str {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() && path.starts_with('/') {
+        "/"
+    } else {
+        trimmed
+    }
+}
+
+fn is_non_empty_error(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::DirectoryNotEmpty || e.raw_os_error() == Some(libc::EEXIST)
+}
+
+pub fn run(dirs: &[String], options: &RmdirOptions) -> bool {
+    let command = RmdirCommand::new(options);
+    command.run(dirs)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = RmdirOptions::default();
+    let mut dirs = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            dirs.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--parents" => options.parents = true,
+            "--verbose" => options.verbose = true,
+            "--ignore-fail-on-non-empty" => options.ignore_non_empty = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("rmdir: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'p' => options.parents = true,
+                        'v' => options.verbose = true,
+                        _ => {
+                            eprintln!("rmdir: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if dirs.is_empty() {
+        eprintln!("rmdir: missing operand");
+        std::process::exit(1);
+    }
+
+    if !run(&dirs, &options) {
+        std::process::exit(1);
+    }
+}
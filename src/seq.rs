@@ -0,0 +1,423 @@
+use cmds_lite::error;
+use cmds_lite::format::{self, Count, Piece, Spec, Value};
+use std::env;
+use std::io::{self, BufWriter, Write};
+
+// An operand as an exact decimal: `digits` scaled down by 10^`scale`
+#[derive(Clone, Copy)]
+struct Decimal {
+    digits: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    // Accepts [-+]DIGITS[.DIGITS][e[-+]N]; anything else (hex, inf, nan)
+    // is left to floating point
+    fn parse(text: &str) -> Option<Decimal> {
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(pos) => (&text[..pos], text[pos + 1..].parse::<i32>().ok()?),
+            None => (text, 0),
+        };
+        let (negative, unsigned) = match mantissa.as_bytes().first()? {
+            b'-' => (true, &mantissa[1..]),
+            b'+' => (false, &mantissa[1..]),
+            _ => (false, mantissa),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if whole.is_empty() && fraction.is_empty()
+            || !whole
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let mut digits: i128 = format!("{}{}", whole, fraction).parse().ok()?;
+        let mut scale = fraction.len() as i32 - exponent;
+        if scale < 0 {
+            digits = digits.checked_mul(10i128.checked_pow(scale.unsigned_abs())?)?;
+            scale = 0;
+        }
+        Some(Decimal {
+            digits: if negative { -digits } else { digits },
+            scale: scale as u32,
+        })
+    }
+
+    fn rescale(self, scale: u32) -> Option<i128> {
+        self.digits
+            .checked_mul(10i128.checked_pow(scale - self.scale)?)
+    }
+}
+
+// Operands are kept both exactly and as floats; the exact form drives the
+// stepping whenever every operand has one
+pub struct Operand {
+    text: String,
+    float: f64,
+    decimal: Option<Decimal>,
+}
+
+fn parse_operand(text: &str) -> Option<Operand> {
+    let decimal = Decimal::parse(text);
+    let float = match decimal {
+        Some(_) => text.parse().ok()?,
+        None => parse_float(text)?,
+    };
+    Some(Operand {
+        text: text.to_string(),
+        float,
+        decimal,
+    })
+}
+
+// Hexadecimal integers, inf and nan
+fn parse_float(text: &str) -> Option<f64> {
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let hex = unsigned
+        .strip_prefix("0x")
+        .or_else(|| unsigned.strip_prefix("0X"));
+    let value = match hex {
+        Some(digits) => u64::from_str_radix(digits, 16).ok()? as f64,
+        None => match unsigned.to_ascii_lowercase().as_str() {
+            "inf" | "infinity" => f64::INFINITY,
+            "nan" => f64::NAN,
+            _ => return None,
+        },
+    };
+    Some(if negative { -value } else { value })
+}
+
+#[derive(Default)]
+pub struct SeqOptions {
+    pub separator: Option<String>,
+    pub equal_width: bool,
+    pub format: Option<String>,
+}
+
+// How each number is printed: a -f format, or fixed decimals
+enum Style {
+    Format(Vec<Piece>),
+    Fixed { precision: u32, width: usize },
+}
+
+struct SeqCommand<'a> {
+    options: &'a SeqOptions,
+    style: Style,
+}
+
+impl<'a> SeqCommand<'a> {
+    fn new(options: &'a SeqOptions, operands: &[Operand; 3]) -> Result<Self, String> {
+        let style = match &options.format {
+            Some(text) => Style::Format(parse_format(text)?),
+            None => {
+                // Like GNU seq, the decimals shown are those of FIRST and
+                // INCREMENT; LAST only matters for -w
+                let precision = operands[..2]
+                    .iter()
+                    .map(|o| o.decimal.map_or(0, |d| d.scale))
+                    .max()
+                    .unwrap_or(0);
+                let width = if options.equal_width {
+                    [&operands[0], &operands[2]]
+                        .iter()
+                        .map(|o| fixed(o.float, precision, 0).len())
+                        .max()
+                        .unwrap_or(0)
+                } else {
+                    0
+                };
+                Style::Fixed { precision, width }
+            }
+        };
+        Ok(SeqCommand { options, style })
+    }
+
+    fn render(&self, value: f64, exact: Option<(i128, u32)>) -> Vec<u8> {
+        match &self.style {
+            Style::Format(pieces) => {
+                let mut out = Vec::new();
+                for piece in pieces {
+                    match piece {
+                        Piece::Text(text) => out.extend_from_slice(text),
+                        Piece::Conversion(spec) => out.extend(render_spec(spec, value)),
+                        Piece::Stop => {}
+                    }
+                }
+                out
+            }
+            Style::Fixed { precision, width } => match exact {
+                Some((digits, scale)) => exact_fixed(digits, scale, *precision, *width),
+                None => fixed(value, *precision, *width),
+            },
+        }
+    }
+
+    fn run<W: Write>(&self, operands: &[Operand; 3], output: &mut W) -> io::Result<()> {
+        let separator = self.options.separator.as_deref().unwrap_or("\n");
+        let mut first = true;
+        let mut emit = |text: &[u8], output: &mut W| -> io::Result<()> {
+            if !first {
+                output.write_all(separator.as_bytes())?;
+            }
+            first = false;
+            output.write_all(text)
+        };
+
+        let exact = exact_operands(operands);
+        if let Some(((start, step, last), scale)) = exact {
+            // Integer stepping: no rounding error can build up
+            let mut value = start;
+            while if step > 0 {
+                value <= last
+            } else {
+                value >= last
+            } {
+                let float = value as f64 / 10f64.powi(scale as i32);
+                emit(&self.render(float, Some((value, scale))), output)?;
+                value = match value.checked_add(step) {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+        } else {
+            // Each term is computed from FIRST rather than by adding up
+            // increments, so errors do not accumulate
+            let (start, step, last) = (operands[0].float, operands[1].float, operands[2].float);
+            let past = |x: f64| if step > 0.0 { x > last } else { x < last };
+            let mut previous: Option<Vec<u8>> = None;
+            let mut i = 0u64;
+            loop {
+                let value = start + i as f64 * step;
+                let text = self.render(value, None);
+                if past(value) {
+                    // A value just past LAST that prints as LAST still counts
+                    if text == self.render(last, None) && previous.as_ref() != Some(&text) {
+                        emit(&text, output)?;
+                    }
+                    break;
+                }
+                emit(&text, output)?;
+                previous = Some(text);
+                i += 1;
+            }
+        }
+
+        if !first {
+            output.write_all(b"\n")?;
+        }
+        output.flush()
+    }
+}
+
+// FIRST, INCREMENT and LAST as integers at a common scale, when they are
+// all decimals small enough
+fn exact_operands(operands: &[Operand; 3]) -> Option<((i128, i128, i128), u32)> {
+    let decimals = [
+        operands[0].decimal?,
+        operands[1].decimal?,
+        operands[2].decimal?,
+    ];
+    let scale = decimals.iter().map(|d| d.scale).max()?;
+    let values = (
+        decimals[0].rescale(scale)?,
+        decimals[1].rescale(scale)?,
+        decimals[2].rescale(scale)?,
+    );
+    Some((values, scale))
+}
+
+// A -f format must hold exactly one floating point conversion
+fn parse_format(text: &str) -> Result<Vec<Piece>, String> {
+    let pieces = format::parse(text.as_bytes(), false)
+        .map_err(|_| format!("format '{}' has unknown % directive", text))?;
+    let conversions: Vec<&Spec> = pieces
+        .iter()
+        .filter_map(|piece| match piece {
+            Piece::Conversion(spec) => Some(spec),
+            _ => None,
+        })
+        .collect();
+    match conversions.as_slice() {
+        [] => Err(format!("format '{}' has no % directive", text)),
+        [spec] if spec.is_float() => Ok(pieces),
+        [spec] => Err(format!(
+            "format '{}' has unknown %{} directive",
+            text, spec.conversion as char
+        )),
+        _ => Err(format!("format '{}' has too many % directives", text)),
+    }
+}
+
+fn render_spec(spec: &Spec, value: f64) -> Vec<u8> {
+    let width = match spec.width {
+        Some(Count::Fixed(width)) => width as i64,
+        _ => 0,
+    };
+    let precision = match spec.precision {
+        Some(Count::Fixed(precision)) => Some(precision),
+        _ => None,
+    };
+    spec.render(width, precision, Value::Float(value))
+}
+
+// %.Nf of a float, zero-padded to `width`
+fn fixed(value: f64, precision: u32, width: usize) -> Vec<u8> {
+    if !value.is_finite() {
+        return format!("{}", value).to_lowercase().into_bytes();
+    }
+    pad_zeros(format!("{:.*}", precision as usize, value), width)
+}
+
+// The same for an exact decimal, where `precision` never exceeds `scale`
+fn exact_fixed(digits: i128, scale: u32, precision: u32, width: usize) -> Vec<u8> {
+    let shown = digits / 10i128.pow(scale - precision);
+    let text = shown.unsigned_abs().to_string();
+    let text = if precision == 0 {
+        text
+    } else {
+        let precision = precision as usize;
+        let text = format!("{:0>width$}", text, width = precision + 1);
+        let (whole, fraction) = text.split_at(text.len() - precision);
+        format!("{}.{}", whole, fraction)
+    };
+    let sign = if shown < 0 { "-" } else { "" };
+    pad_zeros(format!("{}{}", sign, text), width)
+}
+
+fn pad_zeros(text: String, width: usize) -> Vec<u8> {
+    if text.len() >= width {
+        return text.into_bytes();
+    }
+    let (sign, digits) = match text.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", text.as_str()),
+    };
+    let zeros = "0".repeat(width - text.len());
+    format!("{}{}{}", sign, zeros, digits).into_bytes()
+}
+
+pub fn run(operands: &[Operand; 3], options: &SeqOptions) -> Result<(), String> {
+    let command = SeqCommand::new(options, operands)?;
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    match command.run(operands, &mut stdout) {
+        Ok(()) => Ok(()),
+        // A closed pipe just ends the sequence
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        Err(e) => Err(format!("write error: {}", error::message(&e))),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("seq: {}", message);
+    std::process::exit(1);
+}
+
+// Negative numbers are operands, not options
+fn is_number(arg: &str) -> bool {
+    arg.len() > 1 && arg[1..].starts_with(|c: char| c.is_ascii_digit() || c == '.')
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = SeqOptions::default();
+    let mut texts = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 || is_number(arg) {
+            texts.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--equal-width" => options.equal_width = true,
+            _ if arg.starts_with("--separator=") => {
+                options.separator = Some(arg["--separator=".len()..].to_string());
+            }
+            _ if arg.starts_with("--format=") => {
+                options.format = Some(arg["--format=".len()..].to_string());
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'w' => options.equal_width = true,
+                        's' | 'f' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            if flag == 's' {
+                                options.separator = Some(value);
+                            } else {
+                                options.format = Some(value);
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    match texts.len() {
+        0 => fail("missing operand"),
+        1..=3 => {}
+        _ => fail(&format!("extra operand '{}'", texts[3])),
+    }
+    if options.format.is_some() && options.equal_width {
+        fail("format string may not be specified when printing equal width strings");
+    }
+
+    let mut operands: Vec<Operand> = Vec::new();
+    for text in &texts {
+        match parse_operand(text) {
+            // No value compares past NaN, so it would never end
+            Some(operand) if operand.float.is_nan() => {
+                fail(&format!("invalid 'not-a-number' argument: '{}'", text))
+            }
+            Some(operand) => operands.push(operand),
+            None => fail(&format!("invalid floating point argument: '{}'", text)),
+        }
+    }
+    let one = || parse_operand("1").unwrap();
+    let operands: [Operand; 3] = match operands.len() {
+        1 => [one(), one(), operands.remove(0)],
+        2 => {
+            let last = operands.remove(1);
+            [operands.remove(0), one(), last]
+        }
+        _ => {
+            let last = operands.remove(2);
+            let step = operands.remove(1);
+            [operands.remove(0), step, last]
+        }
+    };
+    if operands[1].float == 0.0 {
+        fail(&format!(
+            "invalid Zero increment value: '{}'",
+            operands[1].text
+        ));
+    }
+
+    if let Err(message) = run(&operands, &options) {
+        fail(&message);
+    }
+}
@@ -0,0 +1,6 @@
+use cmds_lite::checksum;
+use cmds_lite::hash::Algorithm;
+
+fn main() {
+    checksum::main("sha256sum", Algorithm::Sha256)
+}
@@ -0,0 +1,315 @@
+use cmds_lite::error;
+use cmds_lite::random::Random;
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+#[derive(Default)]
+pub struct ShufOptions {
+    // -n: output at most this many lines
+    pub count: Option<u64>,
+    // -e: the operands are the lines to shuffle
+    pub echo: bool,
+    // -i: shuffle the numbers from LO to HI instead of lines
+    pub range: Option<(u64, u64)>,
+    // -r: pick lines again and again, with repeats
+    pub repeat: bool,
+    // -z: lines end with NUL instead of newline
+    pub zero: bool,
+    // --random-source: take randomness from this file
+    pub random_source: Option<String>,
+    // --random-seed: take randomness from this number
+    pub seed: Option<u64>,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("shuf: {}", message);
+    std::process::exit(1);
+}
+
+struct ShufCommand<'a> {
+    options: &'a ShufOptions,
+    random: Random,
+}
+
+impl<'a> ShufCommand<'a> {
+    fn terminator(&self) -> u8 {
+        if self.options.zero {
+            b'\0'
+        } else {
+            b'\n'
+        }
+    }
+
+    fn read_lines(&self, input: &mut impl BufRead, lines: &mut Vec<Vec<u8>>) -> io::Result<()> {
+        let terminator = self.terminator();
+        loop {
+            let mut line = Vec::new();
+            if input.read_until(terminator, &mut line)? == 0 {
+                return Ok(());
+            }
+            if line.last() == Some(&terminator) {
+                line.pop();
+            }
+            lines.push(line);
+        }
+    }
+
+    // Keeps a uniform sample of `count` lines while reading, so only that
+    // many are held however long the input is
+    fn sample_lines(&mut self, input: &mut impl BufRead, count: u64) -> io::Result<Vec<Vec<u8>>> {
+        let terminator = self.terminator();
+        let mut reservoir: Vec<Vec<u8>> = Vec::new();
+        let mut seen: u64 = 0;
+        loop {
+            let mut line = Vec::new();
+            if input.read_until(terminator, &mut line)? == 0 {
+                return Ok(reservoir);
+            }
+            if line.last() == Some(&terminator) {
+                line.pop();
+            }
+            seen += 1;
+            if (reservoir.len() as u64) < count {
+                reservoir.push(line);
+            } else {
+                // The new line replaces a kept one with probability
+                // count / seen
+                let slot = self.random.below(seen);
+                if slot < count {
+                    reservoir[slot as usize] = line;
+                }
+            }
+        }
+    }
+
+    // Puts the first `count` items in random order (Fisher–Yates, stopped
+    // early) and drops the rest
+    fn shuffle<T>(&mut self, items: &mut Vec<T>, count: u64) {
+        let count = (count as usize).min(items.len());
+        for i in 0..count {
+            let j = i + self.random.below((items.len() - i) as u64) as usize;
+            items.swap(i, j);
+        }
+        items.truncate(count);
+    }
+
+    fn write_line(&self, out: &mut impl Write, line: &[u8]) -> io::Result<()> {
+        out.write_all(line)?;
+        out.write_all(&[self.terminator()])
+    }
+
+    fn write_lines(&mut self, mut lines: Vec<Vec<u8>>, out: &mut impl Write) -> io::Result<()> {
+        let count = self.options.count.unwrap_or(u64::MAX);
+        if self.options.repeat {
+            if lines.is_empty() {
+                if count == 0 {
+                    return Ok(());
+                }
+                fail("no lines to repeat");
+            }
+            for _ in 0..count {
+                let pick = self.random.below(lines.len() as u64) as usize;
+                self.write_line(out, &lines[pick])?;
+            }
+            return Ok(());
+        }
+        self.shuffle(&mut lines, count);
+        for line in &lines {
+            self.write_line(out, line)?;
+        }
+        Ok(())
+    }
+
+    // The numbers from `low` to `high` in random order. Only the numbers
+    // moved by the shuffle are remembered, so a large range with a small
+    // -n takes little memory.
+    fn write_range(&mut self, low: u64, high: u64, out: &mut impl Write) -> io::Result<()> {
+        let size = high + 1 - low;
+        let count = self.options.count.unwrap_or(u64::MAX);
+        if self.options.repeat {
+            if size == 0 && count > 0 {
+                fail("no lines to repeat");
+            }
+            for _ in 0..count {
+                let number = low + self.random.below(size);
+                self.write_line(out, number.to_string().as_bytes())?;
+            }
+            return Ok(());
+        }
+        let mut moved: HashMap<u64, u64> = HashMap::new();
+        for i in 0..count.min(size) {
+            let j = i + self.random.below(size - i);
+            let at_j = moved.get(&j).copied().unwrap_or(j);
+            let at_i = moved.remove(&i).unwrap_or(i);
+            if j != i {
+                moved.insert(j, at_i);
+            }
+            self.write_line(out, (low + at_j).to_string().as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn run(&mut self, operands: &[String]) -> io::Result<()> {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+
+        if let Some((low, high)) = self.options.range {
+            self.write_range(low, high, &mut out)?;
+            return out.flush();
+        }
+        if self.options.echo {
+            let lines = operands.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+            self.write_lines(lines, &mut out)?;
+            return out.flush();
+        }
+
+        let name = operands.first().map(String::as_str).unwrap_or("-");
+        let mut input: Box<dyn BufRead> = if name == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            match File::open(name) {
+                Ok(file) => Box::new(BufReader::new(file)),
+                Err(e) => fail(&format!("{}: {}", name, error::message(&e))),
+            }
+        };
+        let lines = match self.options.count {
+            Some(count) if !self.options.repeat => self.sample_lines(&mut input, count),
+            _ => {
+                let mut lines = Vec::new();
+                self.read_lines(&mut input, &mut lines).map(|_| lines)
+            }
+        };
+        let lines = lines.unwrap_or_else(|e| fail(&format!("{}: {}", name, error::message(&e))));
+        self.write_lines(lines, &mut out)?;
+        out.flush()
+    }
+}
+
+fn parse_count(value: &str) -> u64 {
+    value
+        .parse::<u64>()
+        .unwrap_or_else(|_| fail(&format!("invalid line count: '{}'", value)))
+}
+
+fn parse_range(value: &str) -> (u64, u64) {
+    let range = value.split_once('-').and_then(|(low, high)| {
+        let low = low.parse::<u64>().ok()?;
+        let high = high.parse::<u64>().ok()?;
+        // An empty range is allowed: LO one past HI
+        (low <= high.saturating_add(1) && high < u64::MAX).then_some((low, high))
+    });
+    range.unwrap_or_else(|| fail(&format!("invalid input range: '{}'", value)))
+}
+
+fn parse_seed(value: &str) -> u64 {
+    value
+        .parse::<u64>()
+        .unwrap_or_else(|_| fail(&format!("invalid random seed: '{}'", value)))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = ShufOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--echo" => options.echo = true,
+            "--repeat" => options.repeat = true,
+            "--zero-terminated" => options.zero = true,
+            _ if arg.starts_with("--head-count=") => {
+                options.count = Some(parse_count(&arg["--head-count=".len()..]))
+            }
+            _ if arg.starts_with("--input-range=") => {
+                options.range = Some(parse_range(&arg["--input-range=".len()..]))
+            }
+            _ if arg.starts_with("--random-source=") => {
+                options.random_source = Some(arg["--random-source=".len()..].to_string())
+            }
+            _ if arg.starts_with("--random-seed=") => {
+                options.seed = Some(parse_seed(&arg["--random-seed=".len()..]))
+            }
+            "--head-count" | "--input-range" | "--random-source" | "--random-seed"
+                if i < args.len() =>
+            {
+                i += 1;
+                let value = &args[i - 1];
+                match arg.as_str() {
+                    "--head-count" => options.count = Some(parse_count(value)),
+                    "--input-range" => options.range = Some(parse_range(value)),
+                    "--random-source" => options.random_source = Some(value.clone()),
+                    _ => options.seed = Some(parse_seed(value)),
+                }
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'e' => options.echo = true,
+                        'r' => options.repeat = true,
+                        'z' => options.zero = true,
+                        'n' | 'i' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            if flag == 'n' {
+                                options.count = Some(parse_count(&value));
+                            } else {
+                                options.range = Some(parse_range(&value));
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if options.echo && options.range.is_some() {
+        fail("cannot combine -e and -i options");
+    }
+    if options.range.is_some() && !operands.is_empty() {
+        fail(&format!("extra operand '{}'", operands[0]));
+    }
+    if !options.echo && operands.len() > 1 {
+        fail(&format!("extra operand '{}'", operands[1]));
+    }
+
+    let random = match (&options.random_source, options.seed) {
+        (Some(_), Some(_)) => fail("cannot combine --random-source and --random-seed"),
+        (Some(source), None) => File::open(source)
+            .and_then(|mut file| Random::from_source(&mut file))
+            .unwrap_or_else(|e| fail(&format!("{}: {}", source, error::message(&e)))),
+        (None, Some(seed)) => Random::from_seed(seed),
+        (None, None) => Random::from_entropy().unwrap_or_else(|e| fail(&e.to_string())),
+    };
+    let mut command = ShufCommand {
+        options: &options,
+        random,
+    };
+    if let Err(e) = command.run(&operands) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            fail(&format!("write error: {}", error::message(&e)));
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::ffi::CStr;
+use std::fmt;
+use std::io;
+use std::time::{Duration, Instant};
+
+// Signal names and dispositions shared by tee, sleep, kill, dd, timeout
+// and nohup.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signal(i32);
+
+// Standard signals in number order, named without the SIG prefix
+const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("STKFLT", libc::SIGSTKFLT),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("POLL", libc::SIGPOLL),
+    ("PWR", libc::SIGPWR),
+    ("SYS", libc::SIGSYS),
+];
+
+impl Signal {
+    pub const CHLD: Signal = Signal(libc::SIGCHLD);
+    pub const CONT: Signal = Signal(libc::SIGCONT);
+    pub const HUP: Signal = Signal(libc::SIGHUP);
+    pub const INT: Signal = Signal(libc::SIGINT);
+    pub const KILL: Signal = Signal(libc::SIGKILL);
+    pub const PIPE: Signal = Signal(libc::SIGPIPE);
+    pub const TERM: Signal = Signal(libc::SIGTERM);
+    pub const USR1: Signal = Signal(libc::SIGUSR1);
+
+    pub fn from_number(number: i32) -> Option<Signal> {
+        SIGNALS
+            .iter()
+            .find(|(_, n)| *n == number)
+            .map(|(_, n)| Signal(*n))
+    }
+
+    // Accepts names with or without the SIG prefix, in any case, as well
+    // as plain numbers
+    pub fn from_name(name: &str) -> Option<Signal> {
+        if let Ok(number) = name.parse::<i32>() {
+            return Signal::from_number(number);
+        }
+        let upper = name.to_ascii_uppercase();
+        let short = upper.strip_prefix("SIG").unwrap_or(&upper);
+        SIGNALS
+            .iter()
+            .find(|(n, _)| *n == short)
+            .map(|(_, n)| Signal(*n))
+    }
+
+    pub fn all() -> impl Iterator<Item = Signal> {
+        SIGNALS.iter().map(|(_, n)| Signal(*n))
+    }
+
+    pub fn number(self) -> i32 {
+        self.0
+    }
+
+    pub fn name(self) -> &'static str {
+        SIGNALS
+            .iter()
+            .find(|(_, n)| *n == self.0)
+            .map(|(name, _)| *name)
+            .unwrap_or("UNKNOWN")
+    }
+
+    // What the C library calls the signal, e.g. "Hangup"
+    pub fn description(self) -> String {
+        let text = unsafe { libc::strsignal(self.0) };
+        if text.is_null() {
+            return format!("Unknown signal {}", self.0);
+        }
+        unsafe { CStr::from_ptr(text) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SIG{}", self.name())
+    }
+}
+
+// Sends the signal to a process, or to a process group when the ID is
+// negative. Without a signal nothing is sent, but the checks that the
+// process exists and may be signalled are still made.
+pub fn send(pid: i32, signal: Option<Signal>) -> io::Result<()> {
+    let number = signal.map_or(0, |signal| signal.0);
+    if unsafe { libc::kill(pid, number) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn set_disposition(signal: Signal, handler: libc::sighandler_t) -> io::Result<()> {
+    if unsafe { libc::signal(signal.0, handler) } == libc::SIG_ERR {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn is_ignored(signal: Signal) -> bool {
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        libc::sigaction(signal.0, std::ptr::null(), &mut action) == 0
+            && action.sa_sigaction == libc::SIG_IGN
+    }
+}
+
+// Makes the process immune to the signal
+pub fn ignore(signal: Signal) -> io::Result<()> {
+    set_disposition(signal, libc::SIG_IGN)
+}
+
+// Restores the default action, e.g. SIGPIPE, which the Rust runtime ignores
+pub fn set_default(signal: Signal) -> io::Result<()> {
+    set_disposition(signal, libc::SIG_DFL)
+}
+
+fn signal_set(signals: &[Signal]) -> libc::sigset_t {
+    unsafe {
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        for signal in signals {
+            libc::sigaddset(&mut set, signal.0);
+        }
+        set
+    }
+}
+
+// Holds the signals back so they stay pending until waited for. Linux
+// queues blocked signals even when they are ignored, so callers should
+// leave those out.
+pub fn block(signals: &[Signal]) -> io::Result<()> {
+    let set = signal_set(signals);
+    match unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut()) } {
+        0 => Ok(()),
+        error => Err(io::Error::from_raw_os_error(error)),
+    }
+}
+
+pub fn unblock(signals: &[Signal]) -> io::Result<()> {
+    let set = signal_set(signals);
+    match unsafe { libc::pthread_sigmask(libc::SIG_UNBLOCK, &set, std::ptr::null_mut()) } {
+        0 => Ok(()),
+        error => Err(io::Error::from_raw_os_error(error)),
+    }
+}
+
+// Waits until one of the (blocked) signals arrives or the time is up,
+// returning the signal, or None on timeout
+pub fn wait_timeout(signals: &[Signal], timeout: Duration) -> io::Result<Option<Signal>> {
+    let set = signal_set(signals);
+    let deadline = Instant::now().checked_add(timeout);
+    loop {
+        let remaining = match deadline {
+            Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+            None => timeout,
+        };
+        // Far-off deadlines are clamped; the loop simply waits again
+        let secs = remaining.as_secs().min(i32::MAX as u64);
+        let timespec = libc::timespec {
+            tv_sec: secs as libc::time_t,
+            tv_nsec: remaining.subsec_nanos() as libc::c_long,
+        };
+        let number = unsafe { libc::sigtimedwait(&set, std::ptr::null_mut(), &timespec) };
+        if number > 0 {
+            return Ok(Some(Signal(number)));
+        }
+
+        let error = io::Error::last_os_error();
+        match error.raw_os_error() {
+            Some(libc::EINTR) => continue,
+            Some(libc::EAGAIN) if remaining.as_secs() > secs => continue,
+            Some(libc::EAGAIN) => return Ok(None),
+            _ => return Err(error),
+        }
+    }
+}
+
+// Ends the process the way the signal would have, so the parent sees it
+// in the exit status (e.g. 130 for SIGINT in a shell)
+pub fn exit_by(signal: Signal) -> ! {
+    let _ = set_default(signal);
+    let _ = unblock(&[signal]);
+    unsafe {
+        libc::raise(signal.0);
+    }
+    std::process::exit(128 + signal.0);
+}
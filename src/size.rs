@@ -0,0 +1,268 @@
+// Size formatting and parsing shared by commands that report or take byte
+// counts.
+
+const UNITS: [char; 8] = ['K', 'M', 'G', 'T', 'P', 'E', 'Z', 'Y'];
+const SI_UNITS: [char; 8] = ['k', 'M', 'G', 'T', 'P', 'E', 'Z', 'Y'];
+
+// Formats a byte count like `ls -h` and `du -h`: powers of 1024, one
+// decimal below ten ("4.0K", "1.5M"), rounding up so sizes are never
+// understated.
+pub fn human_readable(bytes: u64) -> String {
+    scaled(bytes, 1024, &UNITS)
+}
+
+// The same with powers of 1000, as --si shows sizes ("1.5k", "2.0M")
+pub fn human_readable_si(bytes: u64) -> String {
+    scaled(bytes, 1000, &SI_UNITS)
+}
+
+fn scaled(bytes: u64, base: u64, units: &[char; 8]) -> String {
+    if bytes < base {
+        return bytes.to_string();
+    }
+
+    let base = base as f64;
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    value /= base;
+    while value >= base && unit + 1 < units.len() {
+        value /= base;
+        unit += 1;
+    }
+
+    if value < 10.0 {
+        let tenths = (value * 10.0).ceil();
+        if tenths < 100.0 {
+            return format!("{:.1}{}", tenths / 10.0, units[unit]);
+        }
+        value = tenths / 10.0;
+    }
+
+    let whole = value.ceil();
+    if whole >= base && unit + 1 < units.len() {
+        return format!("1.0{}", units[unit + 1]);
+    }
+    format!("{}{}", whole as u64, units[unit])
+}
+
+// Number of `block_size` units needed to hold `bytes`, rounding up
+pub fn blocks(bytes: u64, block_size: u64) -> u64 {
+    bytes.div_ceil(block_size)
+}
+
+// Parses a size such as `10K` or `1MB` the way GNU tools take them: b is
+// 512 bytes, K, M, G... (or KiB, MiB...) are powers of 1024 and KB, MB...
+// powers of 1000. None when the text is malformed or the size too large.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let digits = text.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let number: u64 = text[..digits].parse().ok()?;
+    let suffix = &text[digits..];
+    if suffix.is_empty() {
+        return Some(number);
+    }
+    if suffix == "b" {
+        return number.checked_mul(512);
+    }
+
+    let mut chars = suffix.chars();
+    let unit = chars.next()?.to_ascii_uppercase();
+    let exponent = UNITS.iter().position(|&u| u == unit)? as u32 + 1;
+    let base: u64 = match chars.as_str() {
+        "" | "iB" => 1024,
+        "B" => 1000,
+        _ => return None,
+    };
+    number.checked_mul(base.checked_pow(exponent)?)
+}
+
+// A unit suffix the way it is shown after sizes: the letter in upper case,
+// except for the SI kilo ("kB")
+fn unit_suffix(text: &str) -> String {
+    let letter = text[..1].to_ascii_uppercase();
+    let rest = &text[1..];
+    if letter == "K" && rest == "B" {
+        String::from("kB")
+    } else {
+        letter + rest
+    }
+}
+
+// The unit sizes are shown in, as --block-size takes it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockSize {
+    // Whole units of this many bytes, rounded up. A unit given by its
+    // suffix alone, such as `M` rather than `1M`, is shown after each
+    // number.
+    Units { bytes: u64, suffix: Option<String> },
+    // -h: powers of 1024 with a unit letter
+    Human,
+    // --si: powers of 1000 with a unit letter
+    Si,
+}
+
+impl Default for BlockSize {
+    fn default() -> Self {
+        BlockSize::Units {
+            bytes: 1024,
+            suffix: None,
+        }
+    }
+}
+
+impl BlockSize {
+    // Parses a --block-size argument: `human-readable`, `si`, or a size
+    // such as `4K`, `1MB` or `M`. A leading ' asks for thousands
+    // separators, which the C locale does not have, so it is accepted and
+    // ignored. None when the text is malformed or the size is zero.
+    pub fn parse(text: &str) -> Option<BlockSize> {
+        match text {
+            "human-readable" => return Some(BlockSize::Human),
+            "si" => return Some(BlockSize::Si),
+            _ => {}
+        }
+        let text = text.strip_prefix('\'').unwrap_or(text);
+        let starts_with_digit = text.bytes().next().is_some_and(|b| b.is_ascii_digit());
+        let (bytes, suffix) = if starts_with_digit {
+            (parse_size(text)?, None)
+        } else if text.is_empty() || text == "b" {
+            return None;
+        } else {
+            (parse_size(&format!("1{}", text))?, Some(unit_suffix(text)))
+        };
+        if bytes == 0 {
+            return None;
+        }
+        Some(BlockSize::Units { bytes, suffix })
+    }
+
+    pub fn format(&self, bytes: u64) -> String {
+        match self {
+            BlockSize::Units {
+                bytes: unit,
+                suffix,
+            } => {
+                format!(
+                    "{}{}",
+                    blocks(bytes, *unit),
+                    suffix.as_deref().unwrap_or("")
+                )
+            }
+            BlockSize::Human => human_readable(bytes),
+            BlockSize::Si => human_readable_si(bytes),
+        }
+    }
+
+    // A column heading such as df's "1K-blocks" or "512B-blocks"
+    pub fn heading(&self) -> String {
+        let BlockSize::Units { bytes, suffix } = self else {
+            return String::from("Size");
+        };
+        if let Some(suffix) = suffix {
+            return format!("1{}-blocks", suffix);
+        }
+        let mut count = *bytes;
+        let mut unit = None;
+        for letter in UNITS {
+            if count % 1024 != 0 {
+                break;
+            }
+            count /= 1024;
+            unit = Some(letter);
+        }
+        match unit {
+            Some(letter) => format!("{}{}-blocks", count, letter),
+            None => format!("{}B-blocks", bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn human_readable_sizes() {
+        for (bytes, expected) in [
+            (0, "0"),
+            (1023, "1023"),
+            (1024, "1.0K"),
+            (1025, "1.1K"),
+            (10 * 1024, "10K"),
+            (10 * 1024 + 1, "11K"),
+            (1024 * 1024 - 1, "1.0M"),
+            (1536 * 1024, "1.5M"),
+            (u64::MAX, "16E"),
+        ] {
+            assert_eq!(human_readable(bytes), expected, "{}", bytes);
+        }
+        assert_eq!(human_readable_si(999), "999");
+        assert_eq!(human_readable_si(1000), "1.0k");
+        assert_eq!(human_readable_si(1_500_000), "1.5M");
+    }
+
+    #[test]
+    fn parsed_sizes() {
+        for (text, expected) in [
+            ("0", Some(0)),
+            ("123", Some(123)),
+            ("2b", Some(1024)),
+            ("10K", Some(10240)),
+            ("10k", Some(10240)),
+            ("1KiB", Some(1024)),
+            ("1KB", Some(1000)),
+            ("3M", Some(3 << 20)),
+            ("1G", Some(1 << 30)),
+            ("16E", None),
+            ("", None),
+            ("K", None),
+            ("1X", None),
+            ("1Kb", None),
+            ("-1", None),
+        ] {
+            assert_eq!(parse_size(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn block_sizes() {
+        let units = |bytes, suffix: Option<&str>| BlockSize::Units {
+            bytes,
+            suffix: suffix.map(String::from),
+        };
+        for (text, expected) in [
+            ("human-readable", Some(BlockSize::Human)),
+            ("si", Some(BlockSize::Si)),
+            ("4K", Some(units(4096, None))),
+            ("'1M", Some(units(1 << 20, None))),
+            ("M", Some(units(1 << 20, Some("M")))),
+            ("kB", Some(units(1000, Some("kB")))),
+            ("KB", Some(units(1000, Some("kB")))),
+            ("0", None),
+            ("b", None),
+            ("", None),
+            ("x", None),
+        ] {
+            assert_eq!(BlockSize::parse(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn block_size_output() {
+        let default = BlockSize::default();
+        assert_eq!(default.format(0), "0");
+        assert_eq!(default.format(1), "1");
+        assert_eq!(default.format(4097), "5");
+        assert_eq!(default.heading(), "1K-blocks");
+
+        let megabytes = BlockSize::parse("M").unwrap();
+        assert_eq!(megabytes.format(3 << 20), "3M");
+        assert_eq!(megabytes.heading(), "1M-blocks");
+
+        assert_eq!(BlockSize::parse("512").unwrap().heading(), "512B-blocks");
+        assert_eq!(BlockSize::parse("8K").unwrap().heading(), "8K-blocks");
+        assert_eq!(BlockSize::Human.heading(), "Size");
+    }
+}
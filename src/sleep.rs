@@ -0,0 +1,69 @@
+use cmds_lite::duration;
+use cmds_lite::error;
+use cmds_lite::signal::{self, Signal};
+use std::env;
+use std::io;
+use std::time::Duration;
+
+// Signals that end the sleep early. They are waited for rather than left
+// to kill the process, and then re-raised so the exit status still shows
+// the signal. Ones the caller ignores (e.g. under nohup) stay ignored.
+const INTERRUPTS: [Signal; 3] = [Signal::INT, Signal::TERM, Signal::HUP];
+
+pub fn run(duration: Duration) -> io::Result<()> {
+    let interrupts: Vec<Signal> = INTERRUPTS
+        .into_iter()
+        .filter(|signal| !signal::is_ignored(*signal))
+        .collect();
+    signal::block(&interrupts)?;
+    if let Some(signal) = signal::wait_timeout(&interrupts, duration)? {
+        signal::exit_by(signal);
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut total = Duration::ZERO;
+    let mut operands = 0;
+    let mut valid = true;
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if !end_of_options && arg == "--" {
+            end_of_options = true;
+            continue;
+        }
+        if !end_of_options && arg.starts_with("--") {
+            eprintln!("sleep: unrecognized option '{}'", arg);
+            std::process::exit(1);
+        }
+        if !end_of_options && arg.starts_with('-') && arg.len() > 1 {
+            eprintln!("sleep: invalid option -- '{}'", &arg[1..2]);
+            std::process::exit(1);
+        }
+
+        // Every operand is checked before failing, like GNU sleep
+        operands += 1;
+        match duration::parse_duration(arg) {
+            Some(duration) => total = total.saturating_add(duration),
+            None => {
+                eprintln!("sleep: invalid time interval '{}'", arg);
+                valid = false;
+            }
+        }
+    }
+
+    if operands == 0 {
+        eprintln!("sleep: missing operand");
+        std::process::exit(1);
+    }
+    if !valid {
+        std::process::exit(1);
+    }
+
+    if let Err(e) = run(total) {
+        eprintln!("sleep: cannot sleep: {}", error::message(&e));
+        std::process::exit(1);
+    }
+}
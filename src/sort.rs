@@ -0,0 +1,792 @@
+use cmds_lite::collate::{self, Collator};
+use cmds_lite::error;
+use cmds_lite::platform;
+use std::cmp::Ordering;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::thread;
+
+// At most this many runs are merged at once, like GNU sort's --batch-size
+// default, which keeps the number of open files bounded
+const MERGE_FAN_IN: usize = 16;
+
+// Chunks smaller than this are not worth splitting across threads
+const PARALLEL_THRESHOLD: usize = 16 * 1024;
+
+// Bookkeeping cost of each line on top of its bytes
+const LINE_OVERHEAD: usize = std::mem::size_of::<Vec<u8>>();
+
+// How one key is compared; set per key with -k modifiers or globally
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyOrdering {
+    pub numeric: bool,
+    pub reverse: bool,
+    pub ignore_case: bool,
+}
+
+// A -k key: from START_FIELD.START_CHAR to END_FIELD.END_CHAR, all
+// 1-based. An end character of 0 means the end of the field and no end
+// means the end of the line.
+#[derive(Clone)]
+pub struct KeySpec {
+    pub start_field: usize,
+    pub start_char: usize,
+    pub end: Option<(usize, usize)>,
+    pub skip_start_blanks: bool,
+    pub skip_end_blanks: bool,
+    pub ordering: KeyOrdering,
+}
+
+impl KeySpec {
+    // The whole line, used when no -k is given
+    fn whole_line() -> Self {
+        KeySpec {
+            start_field: 1,
+            start_char: 1,
+            end: None,
+            skip_start_blanks: false,
+            skip_end_blanks: false,
+            ordering: KeyOrdering::default(),
+        }
+    }
+
+    // Parses POS1[,POS2] where POS is F[.C][OPTS] and OPTS are b, f, n, r
+    pub fn parse(text: &str) -> Option<KeySpec> {
+        let (start, end) = match text.split_once(',') {
+            Some((start, end)) => (start, Some(end)),
+            None => (text, None),
+        };
+
+        let mut key = KeySpec::whole_line();
+        let (field, char, flags) = parse_position(start)?;
+        if field == 0 || char == Some(0) {
+            return None;
+        }
+        key.start_field = field;
+        key.start_char = char.unwrap_or(1);
+        key.skip_start_blanks = key.apply_flags(flags)?;
+
+        if let Some(end) = end {
+            let (field, char, flags) = parse_position(end)?;
+            if field == 0 {
+                return None;
+            }
+            key.end = Some((field, char.unwrap_or(0)));
+            key.skip_end_blanks = key.apply_flags(flags)?;
+        }
+        Some(key)
+    }
+
+    // Records ordering flags on the key and reports whether 'b' was given
+    fn apply_flags(&mut self, flags: &str) -> Option<bool> {
+        let mut blanks = false;
+        for flag in flags.chars() {
+            match flag {
+                'b' => blanks = true,
+                'n' => self.ordering.numeric = true,
+                'r' => self.ordering.reverse = true,
+                'f' => self.ordering.ignore_case = true,
+                _ => return None,
+            }
+        }
+        Some(blanks)
+    }
+
+    fn has_modifiers(&self) -> bool {
+        self.ordering != KeyOrdering::default() || self.skip_start_blanks || self.skip_end_blanks
+    }
+
+    // The bytes of `line` this key covers. Without a separator a field is a
+    // run of blanks followed by non-blanks, so the leading blanks belong to
+    // the field unless 'b' skips them, as in GNU sort.
+    fn extract<'l>(&self, line: &'l [u8], separator: Option<u8>) -> &'l [u8] {
+        let start = self.start(line, separator);
+        let end = self.end(line, separator);
+        if start < end {
+            &line[start..end]
+        } else {
+            &[]
+        }
+    }
+
+    fn start(&self, line: &[u8], separator: Option<u8>) -> usize {
+        let mut pos = skip_fields(line, 0, self.start_field - 1, separator, true);
+        if self.skip_start_blanks {
+            pos = skip_blanks(line, pos);
+        }
+        (pos + self.start_char - 1).min(line.len())
+    }
+
+    fn end(&self, line: &[u8], separator: Option<u8>) -> usize {
+        let Some((field, char)) = self.end else {
+            return line.len();
+        };
+        if char == 0 {
+            // Up to the end of the field, not including its separator
+            return skip_fields(line, 0, field, separator, false);
+        }
+        let mut pos = skip_fields(line, 0, field - 1, separator, true);
+        if self.skip_end_blanks {
+            pos = skip_blanks(line, pos);
+        }
+        (pos + char).min(line.len())
+    }
+}
+
+// Splits POS into its field number, optional character number and flags
+fn parse_position(text: &str) -> Option<(usize, Option<usize>, &str)> {
+    let digits_end = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let split = digits_end(text);
+    let field = text[..split].parse().ok()?;
+    let rest = &text[split..];
+
+    match rest.strip_prefix('.') {
+        Some(rest) => {
+            let split = digits_end(rest);
+            let char = rest[..split].parse().ok()?;
+            Some((field, Some(char), &rest[split..]))
+        }
+        None => Some((field, None, rest)),
+    }
+}
+
+// Moves past `count` fields starting at `pos`. With `past_separator` the
+// separator after the last field is skipped too, landing on the next field.
+fn skip_fields(
+    line: &[u8],
+    mut pos: usize,
+    count: usize,
+    separator: Option<u8>,
+    past_separator: bool,
+) -> usize {
+    for remaining in (0..count).rev() {
+        match separator {
+            Some(separator) => {
+                while pos < line.len() && line[pos] != separator {
+                    pos += 1;
+                }
+                if pos < line.len() && (past_separator || remaining > 0) {
+                    pos += 1;
+                }
+            }
+            None => {
+                pos = skip_blanks(line, pos);
+                while pos < line.len() && !collate::is_blank(line[pos]) {
+                    pos += 1;
+                }
+            }
+        }
+    }
+    pos
+}
+
+fn skip_blanks(line: &[u8], mut pos: usize) -> usize {
+    while pos < line.len() && collate::is_blank(line[pos]) {
+        pos += 1;
+    }
+    pos
+}
+
+pub struct SortOptions {
+    pub numeric: bool,
+    pub reverse: bool,
+    pub unique: bool,
+    pub ignore_case: bool,
+    pub ignore_blanks: bool,
+    pub stable: bool,
+    pub keys: Vec<KeySpec>,
+    // Field separator for -k; fields are blank-separated when unset
+    pub separator: Option<u8>,
+    pub output: Option<String>,
+    // Memory budget for lines held at once; bigger inputs are sorted in
+    // runs that are spilled to temporary files and merged
+    pub buffer_size: usize,
+    pub temporary_directory: PathBuf,
+    // Number of threads sorting each in-memory chunk
+    pub threads: usize,
+}
+
+impl Default for SortOptions {
+    fn default() -> Self {
+        SortOptions {
+            numeric: false,
+            reverse: false,
+            unique: false,
+            ignore_case: false,
+            ignore_blanks: false,
+            stable: false,
+            keys: Vec::new(),
+            separator: None,
+            output: None,
+            buffer_size: 64 * 1024 * 1024,
+            temporary_directory: env::var_os("TMPDIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("/tmp")),
+            threads: default_threads(),
+        }
+    }
+}
+
+struct Comparator<'a> {
+    collator: Collator,
+    keys: Vec<KeySpec>,
+    options: &'a SortOptions,
+}
+
+impl<'a> Comparator<'a> {
+    fn new(options: &'a SortOptions) -> Self {
+        // Keys without modifiers of their own take the global ones, and
+        // without any -k the whole line is the key
+        let global = KeyOrdering {
+            numeric: options.numeric,
+            reverse: options.reverse,
+            ignore_case: options.ignore_case,
+        };
+        let mut keys = options.keys.clone();
+        if keys.is_empty() {
+            keys.push(KeySpec::whole_line());
+        }
+        for key in keys.iter_mut() {
+            if !key.has_modifiers() {
+                key.ordering = global;
+                key.skip_start_blanks = options.ignore_blanks;
+                key.skip_end_blanks = options.ignore_blanks;
+            }
+        }
+
+        Comparator {
+            collator: Collator::from_environment(),
+            keys,
+            options,
+        }
+    }
+
+    // Ordering according to the keys only; lines that compare equal here
+    // are duplicates for -u
+    fn compare_keys(&self, a: &[u8], b: &[u8]) -> Ordering {
+        for key in &self.keys {
+            let a = key.extract(a, self.options.separator);
+            let b = key.extract(b, self.options.separator);
+            let ordering = if key.ordering.numeric {
+                collate::compare_numeric(a, b)
+            } else if key.ordering.ignore_case {
+                self.collator.compare_folded(a, b)
+            } else {
+                self.collator.compare(a, b)
+            };
+            let ordering = if key.ordering.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+
+    // Full ordering: like GNU sort, lines with equal keys fall back to a
+    // whole-line comparison unless -s or -u ask for input order instead
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        let ordering = self.compare_keys(a, b);
+        if ordering != Ordering::Equal || self.options.stable || self.options.unique {
+            return ordering;
+        }
+        if self.options.reverse {
+            b.cmp(a)
+        } else {
+            a.cmp(b)
+        }
+    }
+}
+
+// A sorted sequence of lines taking part in a merge
+trait Run {
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>>;
+}
+
+struct MemoryRun {
+    lines: std::vec::IntoIter<Vec<u8>>,
+}
+
+impl Run for MemoryRun {
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.lines.next())
+    }
+}
+
+struct FileRun {
+    reader: BufReader<File>,
+    // Kept so the file is removed once the run has been merged
+    _file: TempFile,
+}
+
+impl Run for FileRun {
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        read_line(&mut self.reader)
+    }
+}
+
+// A spilled run on disk, deleted when dropped
+struct TempFile {
+    path: PathBuf,
+}
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl TempFile {
+    fn create(directory: &Path) -> io::Result<(TempFile, File)> {
+        loop {
+            let name = format!(
+                "sort{}-{}",
+                std::process::id(),
+                TEMP_COUNTER.fetch_add(1, AtomicOrdering::Relaxed)
+            );
+            let path = directory.join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((TempFile { path }, file)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn open(self) -> io::Result<FileRun> {
+        let reader = BufReader::with_capacity(64 * 1024, File::open(&self.path)?);
+        Ok(FileRun {
+            reader,
+            _file: self,
+        })
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Writes merged lines, dropping repeats when -u is given
+struct Output<'c, W: Write> {
+    writer: BufWriter<W>,
+    last: Option<Vec<u8>>,
+    unique: Option<&'c Comparator<'c>>,
+}
+
+impl<'c, W: Write> Output<'c, W> {
+    fn new(writer: W, unique: Option<&'c Comparator<'c>>) -> Self {
+        Output {
+            writer: BufWriter::new(writer),
+            last: None,
+            unique,
+        }
+    }
+
+    fn push(&mut self, line: Vec<u8>) -> io::Result<()> {
+        if let (Some(comparator), Some(last)) = (self.unique, &self.last) {
+            if comparator.compare_keys(last, &line) == Ordering::Equal {
+                return Ok(());
+            }
+        }
+        self.writer.write_all(&line)?;
+        self.writer.write_all(b"\n")?;
+        if self.unique.is_some() {
+            self.last = Some(line);
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+struct SortCommand<'a> {
+    comparator: Comparator<'a>,
+    options: &'a SortOptions,
+}
+
+impl<'a> SortCommand<'a> {
+    fn new(options: &'a SortOptions) -> Self {
+        SortCommand {
+            comparator: Comparator::new(options),
+            options,
+        }
+    }
+
+    fn run(&self, files: &[String]) -> Result<(), String> {
+        // Runs are kept in input order so ties between them resolve to the
+        // earlier input, which keeps -s and -u stable across spills
+        let mut runs = Vec::new();
+        let mut chunk = Vec::new();
+        let mut chunk_bytes = 0;
+
+        for file in files {
+            let read_error =
+                |e: io::Error| format!("cannot read: {}: {}", file, error::message(&e));
+            let mut reader: Box<dyn BufRead> = if file == "-" {
+                Box::new(io::stdin().lock())
+            } else {
+                let file = File::open(file).map_err(read_error)?;
+                Box::new(BufReader::with_capacity(64 * 1024, file))
+            };
+
+            while let Some(line) = read_line(&mut reader).map_err(read_error)? {
+                chunk_bytes += line.len() + LINE_OVERHEAD;
+                chunk.push(line);
+                if chunk_bytes >= self.options.buffer_size {
+                    self.sort_lines(&mut chunk);
+                    runs.push(self.spill(std::mem::take(&mut chunk))?);
+                    chunk_bytes = 0;
+                }
+            }
+        }
+        self.sort_lines(&mut chunk);
+
+        // Merge passes until the remaining runs and the last chunk can be
+        // merged straight into the output
+        while runs.len() >= MERGE_FAN_IN {
+            let mut merged = Vec::new();
+            let mut pending = runs.into_iter().peekable();
+            while pending.peek().is_some() {
+                let group: Vec<TempFile> = pending.by_ref().take(MERGE_FAN_IN).collect();
+                merged.push(self.merge_to_file(group)?);
+            }
+            runs = merged;
+        }
+
+        let mut sources = self.open_runs(runs)?;
+        sources.push(Box::new(MemoryRun {
+            lines: chunk.into_iter(),
+        }));
+
+        // The output file is only opened after all input has been read, so
+        // `sort -o file file` works
+        let unique = self.options.unique.then_some(&self.comparator);
+        match &self.options.output {
+            Some(path) => {
+                let file = File::create(path)
+                    .map_err(|e| format!("open failed: {}: {}", path, error::message(&e)))?;
+                let mut output = Output::new(file, unique);
+                self.merge(sources, |line| output.push(line))
+                    .and_then(|_| output.finish())
+                    .map_err(|e| format!("write failed: {}: {}", path, error::message(&e)))
+            }
+            None => {
+                let mut output = Output::new(io::stdout().lock(), unique);
+                self.merge(sources, |line| output.push(line))
+                    .and_then(|_| output.finish())
+                    .map_err(|e| format!("write failed: standard output: {}", error::message(&e)))
+            }
+        }
+    }
+
+    // Sorts a chunk, splitting it across threads when it is big enough and
+    // merging the sorted parts back together
+    fn sort_lines(&self, lines: &mut Vec<Vec<u8>>) {
+        let comparator = &self.comparator;
+        let threads = self.options.threads.max(1);
+        if threads == 1 || lines.len() < PARALLEL_THRESHOLD {
+            // sort_by is a stable merge sort, so equal lines keep input order
+            lines.sort_by(|a, b| comparator.compare(a, b));
+            return;
+        }
+
+        let part_size = lines.len().div_ceil(threads);
+        let mut parts = Vec::new();
+        while lines.len() > part_size {
+            parts.push(lines.split_off(lines.len() - part_size));
+        }
+        parts.push(std::mem::take(lines));
+        parts.reverse();
+
+        thread::scope(|scope| {
+            for part in parts.iter_mut() {
+                scope.spawn(move || part.sort_by(|a, b| comparator.compare(a, b)));
+            }
+        });
+
+        let sources = parts
+            .into_iter()
+            .map(|part| {
+                Box::new(MemoryRun {
+                    lines: part.into_iter(),
+                }) as Box<dyn Run>
+            })
+            .collect();
+        // Merging memory runs cannot fail
+        let _ = self.merge(sources, |line| {
+            lines.push(line);
+            Ok(())
+        });
+    }
+
+    fn spill(&self, lines: Vec<Vec<u8>>) -> Result<TempFile, String> {
+        let (temp, mut writer) = self.create_run()?;
+        lines
+            .iter()
+            .try_for_each(|line| write_line(&mut writer, line))
+            .and_then(|_| writer.flush())
+            .map_err(|e| temp_error(self.options, &e))?;
+        Ok(temp)
+    }
+
+    fn merge_to_file(&self, group: Vec<TempFile>) -> Result<TempFile, String> {
+        let sources = self.open_runs(group)?;
+        let (temp, mut writer) = self.create_run()?;
+        self.merge(sources, |line| write_line(&mut writer, &line))
+            .and_then(|_| writer.flush())
+            .map_err(|e| temp_error(self.options, &e))?;
+        Ok(temp)
+    }
+
+    fn create_run(&self) -> Result<(TempFile, BufWriter<File>), String> {
+        let (temp, file) = TempFile::create(&self.options.temporary_directory)
+            .map_err(|e| temp_error(self.options, &e))?;
+        Ok((temp, BufWriter::with_capacity(64 * 1024, file)))
+    }
+
+    fn open_runs(&self, runs: Vec<TempFile>) -> Result<Vec<Box<dyn Run>>, String> {
+        let mut sources: Vec<Box<dyn Run>> = Vec::new();
+        for run in runs {
+            let run = run.open().map_err(|e| temp_error(self.options, &e))?;
+            sources.push(Box::new(run));
+        }
+        Ok(sources)
+    }
+
+    // k-way merge of sorted runs. The fan-in is small, so the smallest head
+    // is found with a linear scan; taking the first of equal heads keeps
+    // the merge stable.
+    fn merge<F>(&self, mut sources: Vec<Box<dyn Run>>, mut sink: F) -> io::Result<()>
+    where
+        F: FnMut(Vec<u8>) -> io::Result<()>,
+    {
+        let mut heads = Vec::with_capacity(sources.len());
+        for source in sources.iter_mut() {
+            heads.push(source.next_line()?);
+        }
+
+        loop {
+            let mut smallest: Option<usize> = None;
+            for (index, head) in heads.iter().enumerate() {
+                let Some(line) = head else { continue };
+                let better = match smallest.and_then(|current| heads[current].as_ref()) {
+                    None => true,
+                    Some(current) => self.comparator.compare(line, current) == Ordering::Less,
+                };
+                if better {
+                    smallest = Some(index);
+                }
+            }
+
+            let Some(index) = smallest else {
+                return Ok(());
+            };
+            let next = sources[index].next_line()?;
+            if let Some(line) = std::mem::replace(&mut heads[index], next) {
+                sink(line)?;
+            }
+        }
+    }
+}
+
+fn read_line<R: BufRead + ?Sized>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    // A missing newline at the end of the input still ends a line
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+    Ok(Some(line))
+}
+
+fn write_line<W: Write>(writer: &mut W, line: &[u8]) -> io::Result<()> {
+    writer.write_all(line)?;
+    writer.write_all(b"\n")
+}
+
+fn temp_error(options: &SortOptions, e: &io::Error) -> String {
+    format!(
+        "cannot create temporary file in '{}': {}",
+        options.temporary_directory.display(),
+        error::message(e)
+    )
+}
+
+// GNU sort uses at most eight threads by default
+fn default_threads() -> usize {
+    platform::available_processors().min(8)
+}
+
+// Parses a -S size: a number with an optional b, K, M, G or T suffix
+// (kilobytes when there is none) or a percentage of physical memory
+fn parse_buffer_size(text: &str) -> Option<usize> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (digits, suffix) = text.split_at(split);
+    let value: usize = digits.parse().ok()?;
+
+    if suffix == "%" {
+        let pages = unsafe { libc::sysconf(libc::_SC_PHYS_PAGES) };
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if pages <= 0 || page_size <= 0 {
+            return None;
+        }
+        let memory = pages as usize * page_size as usize;
+        return Some(memory / 100 * value.min(100));
+    }
+
+    let multiplier: usize = match suffix {
+        "b" => 1,
+        "" | "K" | "k" => 1 << 10,
+        "M" | "m" => 1 << 20,
+        "G" | "g" => 1 << 30,
+        "T" | "t" => 1 << 40,
+        _ => return None,
+    };
+    value.checked_mul(multiplier)
+}
+
+pub fn run(files: &[String], options: &SortOptions) -> Result<(), String> {
+    let command = SortCommand::new(options);
+    command.run(files)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = SortOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    let buffer_size = |text: &str| match parse_buffer_size(text) {
+        Some(size) => size,
+        None => {
+            eprintln!("sort: invalid -S argument '{}'", text);
+            std::process::exit(2);
+        }
+    };
+
+    let key_spec = |text: &str| match KeySpec::parse(text) {
+        Some(key) => key,
+        None => {
+            eprintln!("sort: invalid field specification '{}'", text);
+            std::process::exit(2);
+        }
+    };
+    let separator = |text: &str| match text.as_bytes() {
+        [byte] => *byte,
+        [] => {
+            eprintln!("sort: empty tab");
+            std::process::exit(2);
+        }
+        _ => {
+            eprintln!("sort: multi-character tab '{}'", text);
+            std::process::exit(2);
+        }
+    };
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--numeric-sort" => options.numeric = true,
+            "--reverse" => options.reverse = true,
+            "--unique" => options.unique = true,
+            "--ignore-case" => options.ignore_case = true,
+            "--ignore-leading-blanks" => options.ignore_blanks = true,
+            "--stable" => options.stable = true,
+            _ if arg.starts_with("--key=") => {
+                options.keys.push(key_spec(&arg["--key=".len()..]));
+            }
+            _ if arg.starts_with("--field-separator=") => {
+                options.separator = Some(separator(&arg["--field-separator=".len()..]));
+            }
+            _ if arg.starts_with("--output=") => {
+                options.output = Some(arg["--output=".len()..].to_string());
+            }
+            _ if arg.starts_with("--buffer-size=") => {
+                options.buffer_size = buffer_size(&arg["--buffer-size=".len()..]);
+            }
+            _ if arg.starts_with("--temporary-directory=") => {
+                options.temporary_directory = PathBuf::from(&arg["--temporary-directory=".len()..]);
+            }
+            _ if arg.starts_with("--parallel=") => {
+                let value = &arg["--parallel=".len()..];
+                options.threads = match value.parse::<usize>() {
+                    Ok(threads) if threads > 0 => threads,
+                    _ => {
+                        eprintln!("sort: invalid number of threads: '{}'", value);
+                        std::process::exit(2);
+                    }
+                };
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("sort: unrecognized option '{}'", arg);
+                std::process::exit(2);
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'n' => options.numeric = true,
+                        'r' => options.reverse = true,
+                        'u' => options.unique = true,
+                        'f' => options.ignore_case = true,
+                        'b' => options.ignore_blanks = true,
+                        's' => options.stable = true,
+                        'o' | 'S' | 'T' | 'k' | 't' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + flag.len_utf8()..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                eprintln!("sort: option requires an argument -- '{}'", flag);
+                                std::process::exit(2);
+                            };
+                            match flag {
+                                'o' => options.output = Some(value),
+                                'S' => options.buffer_size = buffer_size(&value),
+                                'k' => options.keys.push(key_spec(&value)),
+                                't' => options.separator = Some(separator(&value)),
+                                _ => options.temporary_directory = PathBuf::from(value),
+                            }
+                            break;
+                        }
+                        _ => {
+                            eprintln!("sort: invalid option -- '{}'", flag);
+                            std::process::exit(2);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    // Like GNU sort, failures exit with status 2
+    if let Err(message) = run(&files, &options) {
+        eprintln!("sort: {}", message);
+        std::process::exit(2);
+    }
+}
@@ -0,0 +1,491 @@
+use cmds_lite::error;
+use cmds_lite::size;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::os::unix::io::FromRawFd;
+
+#[derive(Clone, Copy)]
+pub enum Mode {
+    // -l: this many lines per file
+    Lines(u64),
+    // -b: this many bytes per file
+    Bytes(u64),
+    // -n: a fixed number of files of about the same size; with `lines` no
+    // line is split between two of them, and with `only` just that chunk
+    // is written, to standard output
+    Chunks {
+        count: u64,
+        only: Option<u64>,
+        lines: bool,
+    },
+}
+
+pub struct SplitOptions {
+    pub mode: Mode,
+    // -d: suffixes made of digits instead of letters
+    pub numeric: bool,
+    // -a: suffix length; None lets it grow as files are added
+    pub suffix_length: Option<usize>,
+    pub verbose: bool,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        SplitOptions {
+            mode: Mode::Lines(1000),
+            numeric: false,
+            suffix_length: None,
+            verbose: false,
+        }
+    }
+}
+
+// Reading fails with the input's name in the message; everything else
+// carries its own message
+enum Failure {
+    Read(io::Error),
+    Other(String),
+}
+
+const ALPHABETIC: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const NUMERIC: &[u8] = b"0123456789";
+
+// Output file names: the prefix followed by a suffix that counts up
+// through the alphabet (xaa, xab, ...)
+struct Names {
+    prefix: String,
+    alphabet: &'static [u8],
+    digits: Vec<usize>,
+    // When the length is not fixed, the suffix widens like GNU split's
+    // before it runs out: after xyz comes xzaaa, after x89 comes x9000
+    widen: bool,
+    started: bool,
+}
+
+impl Names {
+    fn new(prefix: &str, alphabet: &'static [u8], length: usize, widen: bool) -> Self {
+        Names {
+            prefix: prefix.to_string(),
+            alphabet,
+            digits: vec![0; length],
+            widen,
+            started: false,
+        }
+    }
+
+    fn next(&mut self) -> Result<String, Failure> {
+        if self.started {
+            self.advance()?;
+        }
+        self.started = true;
+        let mut name = self.prefix.clone();
+        name.extend(self.digits.iter().map(|&d| self.alphabet[d] as char));
+        Ok(name)
+    }
+
+    fn advance(&mut self) -> Result<(), Failure> {
+        let last = self.alphabet.len() - 1;
+        for i in (0..self.digits.len()).rev() {
+            self.digits[i] += 1;
+            if self.widen && i == 0 && self.digits[0] == last {
+                self.prefix.push(self.alphabet[last] as char);
+                self.digits = vec![0; self.digits.len() + 1];
+                return Ok(());
+            }
+            if self.digits[i] <= last {
+                return Ok(());
+            }
+            self.digits[i] = 0;
+        }
+        Err(Failure::Other(String::from(
+            "output file suffixes exhausted",
+        )))
+    }
+}
+
+// Where chunk `index` (counting from 1) of `count` starts, for input of
+// `size` bytes; every chunk but the last holds at least one byte
+fn chunk_start(index: u64, count: u64, size: u64) -> u64 {
+    let chunk_size = (size / count).max(1);
+    ((index - 1) * chunk_size).min(size)
+}
+
+fn chunk_end(index: u64, count: u64, size: u64) -> u64 {
+    if index == count {
+        size
+    } else {
+        chunk_start(index + 1, count, size)
+    }
+}
+
+struct SplitCommand<'a> {
+    options: &'a SplitOptions,
+    names: Names,
+}
+
+impl<'a> SplitCommand<'a> {
+    fn new(prefix: &str, options: &'a SplitOptions) -> Self {
+        let alphabet = if options.numeric { NUMERIC } else { ALPHABETIC };
+        let (length, widen) = match (options.suffix_length, options.mode) {
+            (Some(length), _) => (length, false),
+            // The suffix is made long enough for every chunk up front
+            (None, Mode::Chunks { count, .. }) => {
+                let mut length = 2;
+                while (alphabet.len() as u64).saturating_pow(length as u32) < count {
+                    length += 1;
+                }
+                (length, false)
+            }
+            (None, _) => (2, true),
+        };
+        SplitCommand {
+            options,
+            names: Names::new(prefix, alphabet, length, widen),
+        }
+    }
+
+    fn create(&mut self) -> Result<BufWriter<File>, Failure> {
+        let name = self.names.next()?;
+        if self.options.verbose {
+            println!("creating file '{}'", name);
+        }
+        File::create(&name)
+            .map(BufWriter::new)
+            .map_err(|e| Failure::Other(format!("{}: {}", name, error::message(&e))))
+    }
+
+    fn split_lines(&mut self, input: &mut impl BufRead, lines: u64) -> Result<(), Failure> {
+        let mut out: Option<BufWriter<File>> = None;
+        let mut written = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line).map_err(Failure::Read)? == 0 {
+                break;
+            }
+            if out.is_none() || written == lines {
+                finish(out.take())?;
+                out = Some(self.create()?);
+                written = 0;
+            }
+            if let Some(file) = out.as_mut() {
+                file.write_all(&line).map_err(write_error)?;
+            }
+            written += 1;
+        }
+        finish(out)
+    }
+
+    fn split_bytes(&mut self, input: &mut impl Read, bytes: u64) -> Result<(), Failure> {
+        let mut out: Option<BufWriter<File>> = None;
+        let mut room = 0;
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let read = match input.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Failure::Read(e)),
+            };
+            let mut data = &buffer[..read];
+            while !data.is_empty() {
+                if out.is_none() || room == 0 {
+                    finish(out.take())?;
+                    out = Some(self.create()?);
+                    room = bytes;
+                }
+                let take = data.len().min(usize::try_from(room).unwrap_or(usize::MAX));
+                if let Some(file) = out.as_mut() {
+                    file.write_all(&data[..take]).map_err(write_error)?;
+                }
+                room -= take as u64;
+                data = &data[take..];
+            }
+        }
+        finish(out)
+    }
+
+    // -n N and -n K/N: byte ranges of the input
+    fn split_chunks(
+        &mut self,
+        input: &mut impl Read,
+        size: u64,
+        count: u64,
+        only: Option<u64>,
+    ) -> Result<(), Failure> {
+        if let Some(index) = only {
+            let start = chunk_start(index, count, size);
+            let length = chunk_end(index, count, size) - start;
+            io::copy(&mut input.take(start), &mut io::sink()).map_err(Failure::Read)?;
+            let stdout = io::stdout();
+            let mut out = stdout.lock();
+            return copy_exactly(input, &mut out, length);
+        }
+
+        for index in 1..=count {
+            let length = chunk_end(index, count, size) - chunk_start(index, count, size);
+            let mut out = self.create()?;
+            copy_exactly(input, &mut out, length)?;
+            finish(Some(out))?;
+        }
+        Ok(())
+    }
+
+    // -n l/N and -n l/K/N: each chunk runs to the end of the line that
+    // reaches its last byte, so later chunks may be left empty by long
+    // lines
+    fn split_line_chunks(
+        &mut self,
+        input: &mut impl BufRead,
+        size: u64,
+        count: u64,
+        only: Option<u64>,
+    ) -> Result<(), Failure> {
+        let stdout = io::stdout();
+        let mut out: Box<dyn Write> = match only {
+            Some(_) => Box::new(stdout.lock()),
+            None => Box::new(self.create()?),
+        };
+        let mut index = 1;
+        let mut offset = 0;
+        let mut line = Vec::new();
+
+        loop {
+            line.clear();
+            let read = input.read_until(b'\n', &mut line).map_err(Failure::Read)? as u64;
+            if read == 0 {
+                break;
+            }
+            if only.is_none_or(|only| only == index) {
+                out.write_all(&line).map_err(write_error)?;
+            }
+            offset += read;
+
+            // Move on once the line has reached the last byte of the chunk,
+            // skipping the chunks it covered entirely
+            while index < count && offset >= chunk_end(index, count, size) {
+                index += 1;
+                if only.is_some_and(|only| index > only) {
+                    return out.flush().map_err(write_error);
+                }
+                if only.is_none() {
+                    out.flush().map_err(write_error)?;
+                    out = Box::new(self.create()?);
+                }
+            }
+        }
+
+        // Chunks the input did not reach are still created, empty
+        if only.is_none() {
+            while index < count {
+                out.flush().map_err(write_error)?;
+                out = Box::new(self.create()?);
+                index += 1;
+            }
+        }
+        out.flush().map_err(write_error)
+    }
+
+    fn run(&mut self, input_name: &str) -> Result<(), String> {
+        // Standard input is used through a file too, so that its size can
+        // be asked for when it is redirected from one
+        let file = if input_name == "-" {
+            match unsafe { libc::dup(0) } {
+                -1 => return Err(format!("-: {}", io::Error::last_os_error())),
+                fd => unsafe { File::from_raw_fd(fd) },
+            }
+        } else {
+            File::open(input_name).map_err(|e| {
+                format!(
+                    "cannot open '{}' for reading: {}",
+                    input_name,
+                    error::message(&e)
+                )
+            })?
+        };
+        let size = match self.options.mode {
+            Mode::Chunks { .. } => match file.metadata() {
+                Ok(metadata) if metadata.is_file() => metadata.len(),
+                _ => return Err(format!("{}: cannot determine file size", input_name)),
+            },
+            _ => 0,
+        };
+
+        let mut input = BufReader::with_capacity(64 * 1024, file);
+        let result = match self.options.mode {
+            Mode::Lines(lines) => self.split_lines(&mut input, lines),
+            Mode::Bytes(bytes) => self.split_bytes(&mut input, bytes),
+            Mode::Chunks {
+                count,
+                only,
+                lines: false,
+            } => self.split_chunks(&mut input, size, count, only),
+            Mode::Chunks {
+                count,
+                only,
+                lines: true,
+            } => self.split_line_chunks(&mut input, size, count, only),
+        };
+        result.map_err(|failure| match failure {
+            Failure::Read(e) => format!("{}: {}", input_name, error::message(&e)),
+            Failure::Other(message) => message,
+        })
+    }
+}
+
+// Copies `length` bytes, or as many as the input still has
+fn copy_exactly(input: &mut impl Read, out: &mut impl Write, length: u64) -> Result<(), Failure> {
+    let mut limited = input.take(length);
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        match limited.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(read) => out.write_all(&buffer[..read]).map_err(write_error)?,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Failure::Read(e)),
+        }
+    }
+}
+
+fn finish(out: Option<BufWriter<File>>) -> Result<(), Failure> {
+    match out {
+        Some(mut file) => file.flush().map_err(write_error),
+        None => Ok(()),
+    }
+}
+
+fn write_error(e: io::Error) -> Failure {
+    Failure::Other(format!("write error: {}", error::message(&e)))
+}
+
+pub fn run(input: &str, prefix: &str, options: &SplitOptions) -> Result<(), String> {
+    let mut command = SplitCommand::new(prefix, options);
+    command.run(input)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("split: {}", message);
+    std::process::exit(1);
+}
+
+fn positive(value: &str, what: &str, parse: fn(&str) -> Option<u64>) -> u64 {
+    match parse(value) {
+        Some(0) => fail(&format!(
+            "invalid {}: '{}': Numerical result out of range",
+            what, value
+        )),
+        Some(n) => n,
+        None => fail(&format!("invalid {}: '{}'", what, value)),
+    }
+}
+
+// N, K/N, l/N or l/K/N
+fn parse_chunks(value: &str) -> Mode {
+    let (lines, rest) = match value.strip_prefix("l/") {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let (only, count) = match rest.split_once('/') {
+        Some((only, count)) => (Some(only), count),
+        None => (None, rest),
+    };
+    let count = positive(count, "number of chunks", |text| text.parse().ok());
+    let only = only.map(|only| match only.parse::<u64>() {
+        Ok(index) if index >= 1 && index <= count => index,
+        Ok(_) => fail(&format!(
+            "invalid chunk number: '{}': Numerical result out of range",
+            only
+        )),
+        Err(_) => fail(&format!("invalid number of chunks: '{}'", rest)),
+    });
+    Mode::Chunks { count, only, lines }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = SplitOptions::default();
+    let mut operands = Vec::new();
+    let mut modes = 0;
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    let mut set_option = |options: &mut SplitOptions, option: char, value: &str| match option {
+        'l' => {
+            modes += 1;
+            options.mode =
+                Mode::Lines(positive(value, "number of lines", |text| text.parse().ok()));
+        }
+        'b' => {
+            modes += 1;
+            options.mode = Mode::Bytes(positive(value, "number of bytes", size::parse_size));
+        }
+        'n' => {
+            modes += 1;
+            options.mode = parse_chunks(value);
+        }
+        _ => {
+            // A length of 0 means the default
+            options.suffix_length = match value.parse::<usize>() {
+                Ok(0) => None,
+                Ok(length) => Some(length),
+                Err(_) => fail(&format!("invalid suffix length: '{}'", value)),
+            }
+        }
+    };
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--numeric-suffixes" => options.numeric = true,
+            "--verbose" => options.verbose = true,
+            _ if arg.starts_with("--lines=") => set_option(&mut options, 'l', &arg[8..]),
+            _ if arg.starts_with("--bytes=") => set_option(&mut options, 'b', &arg[8..]),
+            _ if arg.starts_with("--number=") => set_option(&mut options, 'n', &arg[9..]),
+            _ if arg.starts_with("--suffix-length=") => set_option(&mut options, 'a', &arg[16..]),
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'd' => options.numeric = true,
+                        'l' | 'b' | 'n' | 'a' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            set_option(&mut options, flag, &value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if modes > 1 {
+        fail("cannot split in more than one way");
+    }
+    if operands.len() > 2 {
+        fail(&format!("extra operand '{}'", operands[2]));
+    }
+    let input = operands.first().map(String::as_str).unwrap_or("-");
+    let prefix = operands.get(1).map(String::as_str).unwrap_or("x");
+
+    if let Err(message) = run(input, prefix, &options) {
+        fail(&message);
+    }
+}
@@ -0,0 +1,635 @@
+use cmds_lite::datetime::{self, Timestamp};
+use cmds_lite::error;
+use cmds_lite::format::{self, Count, Flags, Spec, Value};
+use cmds_lite::mode;
+use cmds_lite::mounts;
+use cmds_lite::platform;
+use cmds_lite::users;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+
+#[derive(Default)]
+pub struct StatOptions {
+    // -L: report on what symlinks point to
+    pub dereference: bool,
+    // -f: report on the filesystem holding the file
+    pub filesystem: bool,
+    // -c or --printf; the built-in reports are used without one
+    pub format: Option<Template>,
+}
+
+// The built-in reports, written as templates like GNU stat's
+const FILE_FORMAT: &str = "  File: %N\n  Size: %-10s\tBlocks: %-10b IO Block: %-6o %F\n\
+    Device: %Hd,%Ld\tInode: %-10i  Links: %h\n";
+const DEVICE_FORMAT: &str = "  File: %N\n  Size: %-10s\tBlocks: %-10b IO Block: %-6o %F\n\
+    Device: %Hd,%Ld\tInode: %-10i  Links: %-5h Device type: %Hr,%Lr\n";
+const OWNER_AND_TIMES_FORMAT: &str = "Access: (%04a/%10.10A)  Uid: (%5u/%8U)   Gid: (%5g/%8G)\n\
+    Access: %x\nModify: %y\nChange: %z\n Birth: %w\n";
+const FILESYSTEM_FORMAT: &str = "  File: \"%n\"\n    ID: %-8i Namelen: %-7l Type: %T\n\
+    Block size: %-10s Fundamental block size: %S\n\
+    Blocks: Total: %-10b Free: %-10f Available: %a\n\
+    Inodes: Total: %-10c Free: %d\n";
+
+// Names for the statfs magic numbers of common filesystems, following
+// GNU stat's spelling
+const FILESYSTEM_TYPES: &[(u64, &str)] = &[
+    (0x0000_9fa0, "proc"),
+    (0x0000_ef53, "ext2/ext3"),
+    (0x0000_4d44, "msdos"),
+    (0x0000_6969, "nfs"),
+    (0x0000_1cd1, "devpts"),
+    (0x0000_9660, "isofs"),
+    (0x0102_1994, "tmpfs"),
+    (0x2fc1_2fc1, "zfs"),
+    (0x5846_5342, "xfs"),
+    (0x6265_6572, "sysfs"),
+    (0x6367_7270, "cgroup2fs"),
+    (0x6573_5546, "fuseblk"),
+    (0x6573_5543, "fuse"),
+    (0x7371_7368, "squashfs"),
+    (0x794c_7630, "overlayfs"),
+    (0x8584_58f6, "ramfs"),
+    (0x9123_683e, "btrfs"),
+    (0xf15f, "ecryptfs"),
+    (0xff53_4d42, "cifs"),
+];
+
+fn filesystem_type_name(magic: u64) -> String {
+    FILESYSTEM_TYPES
+        .iter()
+        .find(|(number, _)| *number == magic)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("UNKNOWN (0x{:x})", magic))
+}
+
+fn type_description(metadata: &fs::Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_symlink() {
+        "symbolic link"
+    } else if file_type.is_char_device() {
+        "character special file"
+    } else if file_type.is_block_device() {
+        "block special file"
+    } else if file_type.is_fifo() {
+        "fifo"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if metadata.len() == 0 {
+        "regular empty file"
+    } else {
+        "regular file"
+    }
+}
+
+// The type letter ls -l shows in front of the permissions
+fn type_letter(metadata: &fs::Metadata) -> char {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_char_device() {
+        'c'
+    } else if file_type.is_block_device() {
+        'b'
+    } else if file_type.is_fifo() {
+        'p'
+    } else if file_type.is_socket() {
+        's'
+    } else {
+        '-'
+    }
+}
+
+enum Piece {
+    Text(Vec<u8>),
+    // A %-sequence; `modifier` is the H or L of %Hd, %Lr and the like
+    Directive {
+        spec: Spec,
+        modifier: Option<u8>,
+        letter: u8,
+    },
+}
+
+// A parsed -c or --printf format
+pub struct Template {
+    pieces: Vec<Piece>,
+    // %N quotes names, except in the built-in reports
+    quote_names: bool,
+}
+
+impl Template {
+    // With `escapes`, backslash sequences are expanded as --printf does
+    pub fn parse(text: &str, escapes: bool) -> Template {
+        let text = text.as_bytes();
+        let mut pieces = Vec::new();
+        let mut literal = Vec::new();
+        let mut i = 0;
+
+        while i < text.len() {
+            match text[i] {
+                b'\\' if escapes => i = format::unescape(text, i, &mut literal, false),
+                b'%' => {
+                    let (piece, next) = parse_directive(text, i);
+                    match piece {
+                        Piece::Text(bytes) => literal.extend(bytes),
+                        directive => {
+                            if !literal.is_empty() {
+                                pieces.push(Piece::Text(std::mem::take(&mut literal)));
+                            }
+                            pieces.push(directive);
+                        }
+                    }
+                    i = next;
+                }
+                byte => {
+                    literal.push(byte);
+                    i += 1;
+                }
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Text(literal));
+        }
+        Template {
+            pieces,
+            quote_names: true,
+        }
+    }
+
+    // -c prints a newline after each file; --printf leaves that to the format
+    pub fn with_newline(mut self) -> Template {
+        self.pieces.push(Piece::Text(b"\n".to_vec()));
+        self
+    }
+
+    fn built_in(text: &str) -> Template {
+        Template {
+            quote_names: false,
+            ..Template::parse(text, false)
+        }
+    }
+}
+
+// Parses %[flags][width][.precision][H|L]letter at `start`. A format that
+// ends early is kept as literal text.
+fn parse_directive(text: &[u8], start: usize) -> (Piece, usize) {
+    let mut i = start + 1;
+    let mut flags = Flags::default();
+    while let Some(byte) = text.get(i) {
+        match byte {
+            b'-' => flags.left = true,
+            b'+' => flags.plus = true,
+            b' ' => flags.space = true,
+            b'#' => flags.alternate = true,
+            b'0' => flags.zero = true,
+            b'\'' => {}
+            _ => break,
+        }
+        i += 1;
+    }
+
+    let number = |i: &mut usize| {
+        let digits = text[*i..].iter().take_while(|b| b.is_ascii_digit()).count();
+        let value = std::str::from_utf8(&text[*i..*i + digits])
+            .ok()
+            .and_then(|digits| digits.parse().ok());
+        *i += digits;
+        value
+    };
+    let width = number(&mut i);
+    let precision = if text.get(i) == Some(&b'.') {
+        i += 1;
+        Some(number(&mut i).unwrap_or(0))
+    } else {
+        None
+    };
+
+    let mut modifier = None;
+    if matches!(text.get(i), Some(b'H' | b'L')) && matches!(text.get(i + 1), Some(b'd' | b'r')) {
+        modifier = Some(text[i]);
+        i += 1;
+    }
+
+    match text.get(i) {
+        None => (Piece::Text(text[start..].to_vec()), i),
+        Some(b'%') => (Piece::Text(b"%".to_vec()), i + 1),
+        Some(&letter) => (
+            Piece::Directive {
+                spec: Spec {
+                    flags,
+                    width: width.map(Count::Fixed),
+                    precision: precision.map(Count::Fixed),
+                    conversion: letter,
+                },
+                modifier,
+                letter,
+            },
+            i + 1,
+        ),
+    }
+}
+
+// A directive's value before printf-style formatting
+enum Field {
+    Decimal(i64),
+    Octal(u64),
+    Hex(u64),
+    Text(String),
+    // Seconds since the epoch; a precision adds that many fraction digits
+    Epoch(Timestamp),
+}
+
+impl Field {
+    fn render(self, spec: &Spec) -> Vec<u8> {
+        let width = match spec.width {
+            Some(Count::Fixed(width)) => width as i64,
+            _ => 0,
+        };
+        let precision = match spec.precision {
+            Some(Count::Fixed(precision)) => Some(precision),
+            _ => None,
+        };
+        let with = |conversion: u8| Spec {
+            conversion,
+            ..spec.clone()
+        };
+
+        match self {
+            Field::Decimal(n) => with(b'd').render(width, precision, Value::Signed(n)),
+            Field::Octal(n) => with(b'o').render(width, precision, Value::Unsigned(n)),
+            Field::Hex(n) => with(b'x').render(width, precision, Value::Unsigned(n)),
+            Field::Text(text) => with(b's').render(width, precision, Value::Bytes(text.as_bytes())),
+            Field::Epoch(stamp) => {
+                let mut text = stamp.secs.to_string();
+                if let Some(digits) = precision.filter(|digits| *digits > 0) {
+                    let mut fraction = format!("{:09}", stamp.nanos);
+                    while fraction.len() < digits {
+                        fraction.push('0');
+                    }
+                    fraction.truncate(digits);
+                    text = format!("{}.{}", text, fraction);
+                }
+                with(b's').render(width, None, Value::Bytes(text.as_bytes()))
+            }
+        }
+    }
+}
+
+// Quotes a name for %N the way GNU's default shell-escape-always style does
+fn quote(name: &str) -> String {
+    format!("'{}'", name.replace('\'', "'\\''"))
+}
+
+// YYYY-MM-DD HH:MM:SS.NNNNNNNNN +ZZZZ in local time
+fn format_time(stamp: Timestamp) -> String {
+    match datetime::local_time(stamp.secs) {
+        Some(local) => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:09} {}",
+            local.year,
+            local.month,
+            local.day,
+            local.hour,
+            local.minute,
+            local.second,
+            stamp.nanos,
+            datetime::format_offset(local.utc_offset)
+        ),
+        None => stamp.secs.to_string(),
+    }
+}
+
+// The directory a file's filesystem is mounted on: the highest ancestor
+// still on the same device
+fn mount_point(path: &Path, device: u64) -> String {
+    let Ok(mut current) = fs::canonicalize(path) else {
+        return String::from("?");
+    };
+    while let Some(parent) = current.parent() {
+        match fs::metadata(parent) {
+            Ok(metadata) if metadata.dev() == device => current = parent.to_path_buf(),
+            _ => break,
+        }
+    }
+    current.display().to_string()
+}
+
+struct FileSubject<'a> {
+    name: &'a str,
+    metadata: fs::Metadata,
+    quote_names: bool,
+}
+
+impl FileSubject<'_> {
+    fn field(&self, modifier: Option<u8>, letter: u8) -> Field {
+        let metadata = &self.metadata;
+        let time = |secs: i64, nanos: i64| Timestamp::new(secs, nanos as u32);
+        let birth = metadata.created().ok().map(Timestamp::from_system_time);
+        let (device_major, device_minor) = platform::device_numbers(metadata.dev());
+        let (rdev_major, rdev_minor) = platform::device_numbers(metadata.rdev());
+
+        match (modifier, letter) {
+            (Some(b'H'), b'd') => Field::Decimal(device_major as i64),
+            (Some(_), b'd') => Field::Decimal(device_minor as i64),
+            (Some(b'H'), b'r') => Field::Decimal(rdev_major as i64),
+            (Some(_), b'r') => Field::Decimal(rdev_minor as i64),
+            (_, b'a') => Field::Octal((metadata.mode() & 0o7777) as u64),
+            (_, b'A') => Field::Text(format!(
+                "{}{}",
+                type_letter(metadata),
+                mode::symbolic(metadata.mode())
+            )),
+            (_, b'b') => Field::Decimal(metadata.blocks() as i64),
+            (_, b'B') => Field::Decimal(512),
+            (_, b'd') => Field::Decimal(metadata.dev() as i64),
+            (_, b'D') => Field::Hex(metadata.dev()),
+            (_, b'f') => Field::Hex(metadata.mode() as u64),
+            (_, b'F') => Field::Text(type_description(metadata).to_string()),
+            (_, b'g') => Field::Decimal(metadata.gid() as i64),
+            (_, b'G') => Field::Text(users::group_name(metadata.gid())),
+            (_, b'h') => Field::Decimal(metadata.nlink() as i64),
+            (_, b'i') => Field::Decimal(metadata.ino() as i64),
+            (_, b'm') => Field::Text(mount_point(Path::new(self.name), metadata.dev())),
+            (_, b'n') => Field::Text(self.name.to_string()),
+            (_, b'N') => Field::Text(self.quoted_name()),
+            (_, b'o') => Field::Decimal(metadata.blksize() as i64),
+            (_, b'r') => Field::Decimal(metadata.rdev() as i64),
+            (_, b'R') => Field::Hex(metadata.rdev()),
+            (_, b's') => Field::Decimal(metadata.size() as i64),
+            (_, b't') => Field::Hex(rdev_major as u64),
+            (_, b'T') => Field::Hex(rdev_minor as u64),
+            (_, b'u') => Field::Decimal(metadata.uid() as i64),
+            (_, b'U') => Field::Text(users::user_name(metadata.uid())),
+            (_, b'w') => Field::Text(birth.map(format_time).unwrap_or_else(|| "-".to_string())),
+            (_, b'W') => birth.map(Field::Epoch).unwrap_or(Field::Decimal(0)),
+            (_, b'x') => Field::Text(format_time(time(metadata.atime(), metadata.atime_nsec()))),
+            (_, b'X') => Field::Epoch(time(metadata.atime(), metadata.atime_nsec())),
+            (_, b'y') => Field::Text(format_time(time(metadata.mtime(), metadata.mtime_nsec()))),
+            (_, b'Y') => Field::Epoch(time(metadata.mtime(), metadata.mtime_nsec())),
+            (_, b'z') => Field::Text(format_time(time(metadata.ctime(), metadata.ctime_nsec()))),
+            (_, b'Z') => Field::Epoch(time(metadata.ctime(), metadata.ctime_nsec())),
+            // No security contexts are supported, nor unknown directives
+            _ => Field::Text(String::from("?")),
+        }
+    }
+
+    // The name, followed by the target for symlinks
+    fn quoted_name(&self) -> String {
+        let quote = |name: &str| {
+            if self.quote_names {
+                quote(name)
+            } else {
+                name.to_string()
+            }
+        };
+        let mut text = quote(self.name);
+        if self.metadata.file_type().is_symlink() {
+            if let Ok(target) = fs::read_link(self.name) {
+                text = format!("{} -> {}", text, quote(&target.to_string_lossy()));
+            }
+        }
+        text
+    }
+}
+
+struct FilesystemSubject<'a> {
+    name: &'a str,
+    stats: mounts::FsStats,
+    magic: u64,
+}
+
+impl FilesystemSubject<'_> {
+    fn field(&self, letter: u8) -> Field {
+        let stats = &self.stats;
+        match letter {
+            b'a' => Field::Decimal(stats.blocks_available as i64),
+            b'b' => Field::Decimal(stats.blocks as i64),
+            b'c' => Field::Decimal(stats.files as i64),
+            b'd' => Field::Decimal(stats.files_free as i64),
+            b'f' => Field::Decimal(stats.blocks_free as i64),
+            // GNU prints the two halves of the ID low word first
+            b'i' => Field::Text(format!(
+                "{:x}{:08x}",
+                stats.fs_id & 0xffff_ffff,
+                stats.fs_id >> 32
+            )),
+            b'l' => Field::Decimal(stats.max_name_length as i64),
+            b'n' => Field::Text(self.name.to_string()),
+            b's' => Field::Decimal(stats.block_size as i64),
+            b'S' => Field::Decimal(stats.fragment_size as i64),
+            b't' => Field::Hex(self.magic),
+            b'T' => Field::Text(filesystem_type_name(self.magic)),
+            _ => Field::Text(String::from("?")),
+        }
+    }
+}
+
+fn render<F: Fn(Option<u8>, u8) -> Field>(template: &Template, field: F) -> Vec<u8> {
+    let mut out = Vec::new();
+    for piece in &template.pieces {
+        match piece {
+            Piece::Text(text) => out.extend_from_slice(text),
+            Piece::Directive {
+                spec,
+                modifier,
+                letter,
+            } => out.extend(field(*modifier, *letter).render(spec)),
+        }
+    }
+    out
+}
+
+struct StatCommand<'a> {
+    options: &'a StatOptions,
+}
+
+impl<'a> StatCommand<'a> {
+    fn new(options: &'a StatOptions) -> Self {
+        StatCommand { options }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let mut stdout = io::stdout().lock();
+        let mut ok = true;
+        for file in files {
+            let report = if self.options.filesystem {
+                self.filesystem_report(file)
+            } else {
+                self.file_report(file)
+            };
+            match report {
+                Some(text) => {
+                    if let Err(e) = stdout.write_all(&text) {
+                        eprintln!("stat: write error: {}", error::message(&e));
+                        return false;
+                    }
+                }
+                None => ok = false,
+            }
+        }
+        ok
+    }
+
+    fn file_report(&self, name: &str) -> Option<Vec<u8>> {
+        let path = Path::new(name);
+        let metadata = if self.options.dereference {
+            fs::metadata(path)
+        } else {
+            fs::symlink_metadata(path)
+        };
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                eprintln!("stat: cannot statx '{}': {}", name, error::message(&e));
+                return None;
+            }
+        };
+
+        let built_in;
+        let template = match &self.options.format {
+            Some(template) => template,
+            None => {
+                let file_type = metadata.file_type();
+                let first = if file_type.is_char_device() || file_type.is_block_device() {
+                    DEVICE_FORMAT
+                } else {
+                    FILE_FORMAT
+                };
+                built_in = Template::built_in(&format!("{}{}", first, OWNER_AND_TIMES_FORMAT));
+                &built_in
+            }
+        };
+
+        let subject = FileSubject {
+            name,
+            metadata,
+            quote_names: template.quote_names,
+        };
+        Some(render(template, |modifier, letter| {
+            subject.field(modifier, letter)
+        }))
+    }
+
+    fn filesystem_report(&self, name: &str) -> Option<Vec<u8>> {
+        let path = Path::new(name);
+        let stats = mounts::statvfs(path);
+        let magic = platform::filesystem_type(path);
+        let (stats, magic) = match (stats, magic) {
+            (Ok(stats), Ok(magic)) => (stats, magic),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!(
+                    "stat: cannot read file system information for '{}': {}",
+                    name,
+                    error::message(&e)
+                );
+                return None;
+            }
+        };
+
+        let built_in;
+        let template = match &self.options.format {
+            Some(template) => template,
+            None => {
+                built_in = Template::built_in(FILESYSTEM_FORMAT);
+                &built_in
+            }
+        };
+        let subject = FilesystemSubject { name, stats, magic };
+        Some(render(template, |_, letter| subject.field(letter)))
+    }
+}
+
+pub fn run(files: &[String], options: &StatOptions) -> bool {
+    let command = StatCommand::new(options);
+    command.run(files)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = StatOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--dereference" => options.dereference = true,
+            "--file-system" => options.filesystem = true,
+            // Without "=" the template is the next argument
+            "--format" | "--printf" => {
+                let Some(value) = args.get(i) else {
+                    eprintln!("stat: option '{}' requires an argument", arg);
+                    std::process::exit(1);
+                };
+                i += 1;
+                options.format = Some(if arg == "--format" {
+                    Template::parse(value, false).with_newline()
+                } else {
+                    Template::parse(value, true)
+                });
+            }
+            _ if arg.starts_with("--format=") => {
+                options.format =
+                    Some(Template::parse(&arg["--format=".len()..], false).with_newline());
+            }
+            _ if arg.starts_with("--printf=") => {
+                options.format = Some(Template::parse(&arg["--printf=".len()..], true));
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("stat: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'L' => options.dereference = true,
+                        'f' => options.filesystem = true,
+                        'c' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                eprintln!("stat: option requires an argument -- 'c'");
+                                std::process::exit(1);
+                            };
+                            options.format = Some(Template::parse(&value, false).with_newline());
+                            break;
+                        }
+                        _ => {
+                            eprintln!("stat: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        eprintln!("stat: missing operand");
+        std::process::exit(1);
+    }
+
+    if !run(&files, &options) {
+        std::process::exit(1);
+    }
+}
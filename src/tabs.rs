@@ -0,0 +1,144 @@
+// Tab stops shared by expand and unexpand, given as -t lists such as "4",
+// "2,6,10" or "4,8,/8". Columns are counted from zero.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TabStops {
+    // Stops given one by one, in increasing order
+    stops: Vec<usize>,
+    // After the last stop given: "/N" puts stops at every multiple of N
+    // and "+N" every N columns further on. Without either there are no
+    // more stops.
+    repeat: Option<usize>,
+    offset: bool,
+}
+
+impl Default for TabStops {
+    fn default() -> Self {
+        TabStops::every(8)
+    }
+}
+
+impl TabStops {
+    // A stop every `width` columns
+    pub fn every(width: usize) -> Self {
+        TabStops {
+            stops: Vec::new(),
+            repeat: Some(width),
+            offset: false,
+        }
+    }
+
+    // Parses a list of stops separated by commas or blanks. A single
+    // number is the distance between stops rather than one stop.
+    pub fn parse(text: &str) -> Result<TabStops, String> {
+        let mut stops: Vec<usize> = Vec::new();
+        let mut repeat = None;
+        let mut offset = false;
+        let items: Vec<&str> = text
+            .split([',', ' ', '\t'])
+            .filter(|item| !item.is_empty())
+            .collect();
+        for (index, item) in items.iter().enumerate() {
+            let (digits, kind) = match item.strip_prefix('/') {
+                Some(digits) => (digits, Some(false)),
+                None => match item.strip_prefix('+') {
+                    Some(digits) => (digits, Some(true)),
+                    None => (*item, None),
+                },
+            };
+            let value = digits
+                .parse::<usize>()
+                .map_err(|_| format!("tab size contains invalid character(s): '{}'", item))?;
+            if value == 0 {
+                return Err(String::from("tab size cannot be 0"));
+            }
+            match kind {
+                Some(_) if index + 1 != items.len() => {
+                    return Err(format!(
+                        "'{}' specifier only allowed with the last value",
+                        &item[..1]
+                    ))
+                }
+                Some(is_offset) => {
+                    repeat = Some(value);
+                    offset = is_offset;
+                }
+                None => {
+                    if stops.last().is_some_and(|&last| value <= last) {
+                        return Err(String::from("tab sizes must be ascending"));
+                    }
+                    stops.push(value);
+                }
+            }
+        }
+        if stops.len() == 1 && repeat.is_none() {
+            return Ok(TabStops::every(stops[0]));
+        }
+        if stops.is_empty() && repeat.is_none() {
+            return Ok(TabStops::default());
+        }
+        Ok(TabStops {
+            stops,
+            repeat,
+            offset,
+        })
+    }
+
+    // The first stop after `column`, or None past the last one
+    pub fn next(&self, column: usize) -> Option<usize> {
+        if let Some(&stop) = self.stops.iter().find(|&&stop| stop > column) {
+            return Some(stop);
+        }
+        let width = self.repeat?;
+        let last = self.stops.last().copied().unwrap_or(0);
+        if self.offset {
+            Some(last + ((column - last) / width + 1) * width)
+        } else {
+            Some((column / width + 1) * width)
+        }
+    }
+
+    // Whether there is a stop at `column`
+    pub fn is_stop(&self, column: usize) -> bool {
+        column > 0 && self.next(column - 1) == Some(column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stops(text: &str, columns: &[usize]) -> Vec<Option<usize>> {
+        let stops = TabStops::parse(text).unwrap();
+        columns.iter().map(|&column| stops.next(column)).collect()
+    }
+
+    #[test]
+    fn stop_lists() {
+        assert_eq!(
+            stops("4", &[0, 3, 4, 9]),
+            [Some(4), Some(4), Some(8), Some(12)]
+        );
+        assert_eq!(
+            stops("2,6", &[0, 2, 5, 6]),
+            [Some(2), Some(6), Some(6), None]
+        );
+        assert_eq!(stops("2 5,/4", &[4, 5, 8]), [Some(5), Some(8), Some(12)]);
+        assert_eq!(stops("2,5,+4", &[4, 5, 8]), [Some(5), Some(9), Some(9)]);
+        assert_eq!(stops("", &[0]), [Some(8)]);
+        assert!(TabStops::parse("4").unwrap().is_stop(8));
+        assert!(!TabStops::parse("4").unwrap().is_stop(0));
+    }
+
+    #[test]
+    fn invalid_lists() {
+        for (text, message) in [
+            ("0", "tab size cannot be 0"),
+            ("4,2", "tab sizes must be ascending"),
+            ("x", "tab size contains invalid character(s): 'x'"),
+            ("/4,8", "'/' specifier only allowed with the last value"),
+        ] {
+            assert_eq!(TabStops::parse(text).unwrap_err(), message, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use cmds_lite::error;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+
+const BLOCK_SIZE: usize = 64 * 1024;
+
+pub struct TacOptions {
+    // -b: the separator starts a record instead of ending it
+    pub before: bool,
+    // -s: what records are separated by
+    pub separator: Vec<u8>,
+}
+
+impl Default for TacOptions {
+    fn default() -> Self {
+        TacOptions {
+            before: false,
+            separator: b"\n".to_vec(),
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("tac: {}", message);
+    std::process::exit(1);
+}
+
+// The start of the last occurrence of `needle` in `haystack`
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.len() > haystack.len() {
+        return None;
+    }
+    (0..=haystack.len() - needle.len())
+        .rev()
+        .find(|&i| haystack[i..].starts_with(needle))
+}
+
+struct TacCommand<'a> {
+    options: &'a TacOptions,
+}
+
+impl<'a> TacCommand<'a> {
+    // Writes the records at the end of `buffer[..end]` that are known to
+    // be whole, last first, and returns where the unwritten text now
+    // ends. `searched` is how far down the text is known to hold no
+    // separator, so text is only searched once. At the start of the
+    // input whatever is left is a record too.
+    fn write_records(
+        &self,
+        buffer: &[u8],
+        mut end: usize,
+        searched: &mut usize,
+        at_start: bool,
+        out: &mut impl Write,
+    ) -> io::Result<usize> {
+        let separator = &self.options.separator[..];
+        loop {
+            // With the separator ending records, one that ends the text
+            // belongs to the last record rather than bounding it
+            let limit = if self.options.before {
+                end
+            } else {
+                end.saturating_sub(1)
+            };
+            let window = limit.min(*searched + separator.len() - 1);
+            let boundary = rfind(&buffer[..window], separator).map(|start| {
+                if self.options.before {
+                    start
+                } else {
+                    start + separator.len()
+                }
+            });
+            match boundary {
+                Some(boundary) if boundary < end => {
+                    out.write_all(&buffer[boundary..end])?;
+                    end = boundary;
+                    *searched = boundary;
+                }
+                _ => {
+                    *searched = 0;
+                    if at_start {
+                        out.write_all(&buffer[..end])?;
+                        return Ok(0);
+                    }
+                    return Ok(end);
+                }
+            }
+        }
+    }
+
+    // A file that can be read from the end: blocks are read backwards and
+    // only the text not yet written is held
+    fn reverse_file(&self, file: &mut File, out: &mut impl Write) -> io::Result<()> {
+        let mut position = file.seek(SeekFrom::End(0))?;
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut end = 0;
+        let mut searched = 0;
+        while position > 0 {
+            // A long record is read in blocks as large as what is held of
+            // it, so it is copied a bounded number of times
+            let size = (position as usize).min(BLOCK_SIZE.max(end));
+            position -= size as u64;
+            let mut block = vec![0; size];
+            file.seek(SeekFrom::Start(position))?;
+            file.read_exact(&mut block)?;
+
+            buffer.truncate(end);
+            block.extend_from_slice(&buffer);
+            buffer = block;
+            end += size;
+            searched = if end == size { end } else { searched + size };
+            end = self.write_records(&buffer, end, &mut searched, position == 0, out)?;
+        }
+        Ok(())
+    }
+
+    // Input that cannot be read backwards, such as a pipe, is read whole
+    fn reverse_stream(&self, input: &mut impl Read, out: &mut impl Write) -> io::Result<()> {
+        let mut buffer = Vec::new();
+        input.read_to_end(&mut buffer)?;
+        let mut searched = buffer.len();
+        self.write_records(&buffer, buffer.len(), &mut searched, true, out)?;
+        Ok(())
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        let mut ok = true;
+        for name in files {
+            let result = if name == "-" {
+                self.reverse_stream(&mut io::stdin().lock(), &mut out)
+            } else {
+                let mut file = match File::open(name) {
+                    Ok(file) => file,
+                    Err(e) => {
+                        eprintln!(
+                            "tac: failed to open '{}' for reading: {}",
+                            name,
+                            error::message(&e)
+                        );
+                        ok = false;
+                        continue;
+                    }
+                };
+                let seekable = file.metadata().is_ok_and(|metadata| metadata.is_file());
+                if seekable {
+                    self.reverse_file(&mut file, &mut out)
+                } else {
+                    self.reverse_stream(&mut file, &mut out)
+                }
+            };
+            match result {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return ok,
+                Err(e) => {
+                    eprintln!("tac: {}: read error: {}", name, error::message(&e));
+                    ok = false;
+                }
+            }
+        }
+        match out.flush() {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => fail(&error::message(&e)),
+            _ => ok,
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = TacOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--before" => options.before = true,
+            _ if arg.starts_with("--separator=") => {
+                options.separator = arg.as_bytes()["--separator=".len()..].to_vec()
+            }
+            "--separator" if i < args.len() => {
+                i += 1;
+                options.separator = args[i - 1].as_bytes().to_vec();
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'b' => options.before = true,
+                        's' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            options.separator = value.into_bytes();
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if options.separator.is_empty() {
+        fail("separator cannot be empty");
+    }
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    let command = TacCommand { options: &options };
+    if !command.run(&files) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,333 @@
+use cmds_lite::error;
+use cmds_lite::size;
+use cmds_lite::trailing::{self, LastBytes, LastLines};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::mem::ManuallyDrop;
+use std::os::fd::FromRawFd;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+pub struct TailOptions {
+    // -n, -c: how many lines or bytes
+    pub count: u64,
+    // -c: count bytes instead of lines
+    pub bytes: bool,
+    // -n +K, -c +K: print from line or byte K on instead of the last ones
+    pub from_start: bool,
+    // -q: never print tailers
+    pub quiet: bool,
+    // -v: always print tailers
+    pub verbose: bool,
+    // -z: lines end with NUL instead of newline
+    pub zero_terminated: bool,
+}
+
+impl Default for TailOptions {
+    fn default() -> Self {
+        TailOptions {
+            count: 10,
+            bytes: false,
+            from_start: false,
+            quiet: false,
+            verbose: false,
+            zero_terminated: false,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("tail: {}", message);
+    std::process::exit(1);
+}
+
+// An error reading the input, as opposed to one writing the output
+struct ReadError(io::Error);
+
+struct TailCommand<'a> {
+    options: &'a TailOptions,
+}
+
+impl<'a> TailCommand<'a> {
+    fn delimiter(&self) -> u8 {
+        if self.options.zero_terminated {
+            b'\0'
+        } else {
+            b'\n'
+        }
+    }
+
+    // Everything from line or byte K on, K counting from 1. Bytes are
+    // skipped with a seek where the input allows it.
+    fn starting_at(&self, mut file: &File, out: &mut impl Write) -> Result<(), ReadError> {
+        let skip = self.options.count.saturating_sub(1);
+        if self.options.bytes {
+            if trailing::remaining_extent(file).is_some() {
+                file.seek(SeekFrom::Current(skip.min(i64::MAX as u64) as i64))
+                    .map_err(ReadError)?;
+            } else {
+                io::copy(&mut file.take(skip), &mut io::sink()).map_err(ReadError)?;
+            }
+            return copy(&mut file, out);
+        }
+
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        for _ in 0..skip {
+            line.clear();
+            if reader
+                .read_until(self.delimiter(), &mut line)
+                .map_err(ReadError)?
+                == 0
+            {
+                return Ok(());
+            }
+        }
+        write_output(out, reader.buffer());
+        reader.consume(reader.buffer().len());
+        copy(&mut reader, out)
+    }
+
+    // The last lines or bytes. For a regular file, where they start is
+    // found from its end, and only they are read; anything else is read
+    // through, keeping the last ones read.
+    fn last(&self, file: &File, out: &mut impl Write) -> Result<(), ReadError> {
+        let count = self.options.count;
+        if let Some((start, end)) = trailing::remaining_extent(file) {
+            let from = if self.options.bytes {
+                end.saturating_sub(count).max(start)
+            } else {
+                trailing::last_lines_offset(file, start, end, count, self.delimiter())
+                    .map_err(ReadError)?
+            };
+            let mut reader = file;
+            reader.seek(SeekFrom::Start(from)).map_err(ReadError)?;
+            return copy(&mut reader, out);
+        }
+
+        if self.options.bytes {
+            let mut last = LastBytes::new(count);
+            let mut buffer = vec![0; BUFFER_SIZE];
+            let mut reader = file;
+            loop {
+                let read = reader.read(&mut buffer).map_err(ReadError)?;
+                if read == 0 {
+                    break;
+                }
+                last.push(&buffer[..read], |_| {});
+            }
+            let (front, back) = last.as_slices();
+            write_output(out, front);
+            write_output(out, back);
+            return Ok(());
+        }
+
+        let mut last = LastLines::new(count);
+        let mut reader = BufReader::new(file);
+        loop {
+            let mut line = Vec::new();
+            if reader
+                .read_until(self.delimiter(), &mut line)
+                .map_err(ReadError)?
+                == 0
+            {
+                break;
+            }
+            last.push(line);
+        }
+        for line in last.into_lines() {
+            write_output(out, &line);
+        }
+        Ok(())
+    }
+
+    fn tail(&self, file: &File, out: &mut impl Write) -> Result<(), ReadError> {
+        if self.options.from_start {
+            self.starting_at(file, out)
+        } else {
+            self.last(file, out)
+        }
+    }
+
+    fn run(&self, names: &[String]) -> bool {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        let show_tailers = self.options.verbose || (names.len() > 1 && !self.options.quiet);
+        // Standard input is used in place, so that a regular file's size
+        // and offset can be looked at like any other file's
+        let stdin = ManuallyDrop::new(unsafe { File::from_raw_fd(0) });
+        let mut success = true;
+        let mut first = true;
+
+        for name in names {
+            let opened;
+            let file = if name == "-" {
+                &*stdin
+            } else {
+                match File::open(name) {
+                    Ok(file) => {
+                        opened = file;
+                        &opened
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "tail: cannot open '{}' for reading: {}",
+                            name,
+                            error::message(&e)
+                        );
+                        success = false;
+                        continue;
+                    }
+                }
+            };
+            let shown = if name == "-" { "standard input" } else { name };
+            if show_tailers {
+                let separator = if first { "" } else { "\n" };
+                write_output(
+                    &mut out,
+                    format!("{}==> {} <==\n", separator, shown).as_bytes(),
+                );
+            }
+            first = false;
+
+            if let Err(ReadError(e)) = self.tail(file, &mut out) {
+                flush_output(&mut out);
+                eprintln!("tail: error reading '{}': {}", shown, error::message(&e));
+                success = false;
+            }
+        }
+        flush_output(&mut out);
+        success
+    }
+}
+
+fn copy(reader: &mut impl Read, out: &mut impl Write) -> Result<(), ReadError> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(ReadError(e)),
+        };
+        write_output(out, &buffer[..read]);
+    }
+}
+
+// Output errors end tail: quietly when the reader has gone away
+fn write_output(out: &mut impl Write, data: &[u8]) {
+    if let Err(e) = out.write_all(data) {
+        output_failed(e);
+    }
+}
+
+fn flush_output(out: &mut impl Write) {
+    if let Err(e) = out.flush() {
+        output_failed(e);
+    }
+}
+
+fn output_failed(e: io::Error) -> ! {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        std::process::exit(0);
+    }
+    fail(&format!(
+        "error writing 'standard output': {}",
+        error::message(&e)
+    ));
+}
+
+// A count for -n or -c, with the size suffixes dd takes; a leading '+'
+// counts from the start instead of the end
+fn parse_count(options: &mut TailOptions, text: &str, bytes: bool) {
+    let trimmed = text.trim_start();
+    let (from_start, digits) = match trimmed.strip_prefix('+') {
+        Some(digits) => (true, digits),
+        None => (false, trimmed.strip_prefix('-').unwrap_or(trimmed)),
+    };
+    let what = if bytes { "bytes" } else { "lines" };
+    options.count = size::parse_size(digits)
+        .unwrap_or_else(|| fail(&format!("invalid number of {}: '{}'", what, text)));
+    options.from_start = from_start;
+    options.bytes = bytes;
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = TailOptions::default();
+    let mut names = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            names.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--quiet" | "--silent" => {
+                options.quiet = true;
+                options.verbose = false;
+            }
+            "--verbose" => {
+                options.verbose = true;
+                options.quiet = false;
+            }
+            "--zero-terminated" => options.zero_terminated = true,
+            _ if arg.starts_with("--lines=") => {
+                parse_count(&mut options, &arg["--lines=".len()..], false)
+            }
+            _ if arg.starts_with("--bytes=") => {
+                parse_count(&mut options, &arg["--bytes=".len()..], true)
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            // The old form: -K for the last K lines
+            _ if arg[1..].bytes().all(|b| b.is_ascii_digit()) => {
+                parse_count(&mut options, &arg[1..], false)
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'q' => {
+                            options.quiet = true;
+                            options.verbose = false;
+                        }
+                        'v' => {
+                            options.verbose = true;
+                            options.quiet = false;
+                        }
+                        'z' => options.zero_terminated = true,
+                        'n' | 'c' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            parse_count(&mut options, &value, flag == 'c');
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if names.is_empty() {
+        names.push(String::from("-"));
+    }
+
+    let command = TailCommand { options: &options };
+    if !command.run(&names) {
+        std::process::exit(1);
+    }
+}
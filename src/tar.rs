@@ -0,0 +1,699 @@
+use cmds_lite::archive::{Header, Kind, Reader, Writer};
+use cmds_lite::datetime::{self, Timestamp};
+use cmds_lite::error;
+use cmds_lite::gz;
+use cmds_lite::mode;
+use cmds_lite::platform;
+use cmds_lite::users;
+use cmds_lite::walk::{WalkOptions, Walker};
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::os::unix::fs::{
+    self as unix_fs, FileTypeExt, MetadataExt, OpenOptionsExt, PermissionsExt,
+};
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Create,
+    Extract,
+    List,
+}
+
+#[derive(Default)]
+pub struct TarOptions {
+    pub operation: Option<Operation>,
+    // -f: the archive; standard input or output when unset or "-"
+    pub archive: Option<String>,
+    // -C: change to this directory before adding or extracting members
+    pub directory: Option<String>,
+    pub verbose: bool,
+    // -z: compress the archive with gzip; compressed archives are
+    // recognised when reading whether or not it is given
+    pub gzip: bool,
+}
+
+// Where a new archive goes, compressed or not
+enum Output {
+    Plain(BufWriter<Box<dyn Write>>),
+    Gzip(gz::Encoder<BufWriter<Box<dyn Write>>>),
+}
+
+impl Output {
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Plain(mut out) => out.flush(),
+            Output::Gzip(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Plain(out) => out.write(buffer),
+            Output::Gzip(encoder) => encoder.write(buffer),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Plain(out) => out.flush(),
+            Output::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+struct TarCommand<'a> {
+    options: &'a TarOptions,
+    // Something went wrong but the command carried on
+    failed: bool,
+    warned_absolute: bool,
+}
+
+impl<'a> TarCommand<'a> {
+    fn report(&mut self, message: &str) {
+        eprintln!("tar: {}", message);
+        self.failed = true;
+    }
+
+    fn change_directory(&self) -> Result<(), String> {
+        match &self.options.directory {
+            Some(dir) => env::set_current_dir(dir)
+                .map_err(|e| format!("{}: Cannot open: {}", dir, error::message(&e))),
+            None => Ok(()),
+        }
+    }
+
+    fn archive_name(&self) -> Option<&str> {
+        self.options.archive.as_deref().filter(|&name| name != "-")
+    }
+
+    // Verbose names go to standard error when the archive is on standard
+    // output, so they do not end up inside it
+    fn announce(&mut self, line: &str) {
+        if self.options.operation == Some(Operation::Create) && self.archive_name().is_none() {
+            eprintln!("{}", line);
+        } else if let Err(e) = writeln!(io::stdout(), "{}", line) {
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                std::process::exit(2);
+            }
+            self.report(&format!("write error: {}", error::message(&e)));
+        }
+    }
+
+    // Member names are relative: leading slashes are dropped, once with a
+    // warning
+    fn member_name(&mut self, path: &str) -> String {
+        let trimmed = path.trim_start_matches('/');
+        if trimmed.len() != path.len() && !self.warned_absolute {
+            eprintln!("tar: Removing leading `/' from member names");
+            self.warned_absolute = true;
+        }
+        if trimmed.is_empty() {
+            String::from(".")
+        } else {
+            trimmed.to_string()
+        }
+    }
+
+    fn create(&mut self, operands: &[String]) -> Result<(), String> {
+        if operands.is_empty() {
+            return Err(String::from("Cowardly refusing to create an empty archive"));
+        }
+        let (output, archive_id): (Box<dyn Write>, _) = match self.archive_name() {
+            Some(name) => {
+                let file = File::create(name)
+                    .map_err(|e| format!("{}: Cannot open: {}", name, error::message(&e)))?;
+                let id = file.metadata().ok().map(|m| (m.dev(), m.ino()));
+                (Box::new(file), id)
+            }
+            None => (Box::new(io::stdout().lock()), None),
+        };
+        self.change_directory()?;
+
+        let output = BufWriter::new(output);
+        let output = if self.options.gzip {
+            let encoder = gz::Encoder::new(output, 6, None, 0)
+                .map_err(|e| format!("write error: {}", error::message(&e)))?;
+            Output::Gzip(encoder)
+        } else {
+            Output::Plain(output)
+        };
+        let mut writer = Writer::new(output);
+        // The first name each multiply-linked file was archived under
+        let mut links: HashMap<(u64, u64), String> = HashMap::new();
+        let walk_options = WalkOptions {
+            sort: true,
+            ..WalkOptions::default()
+        };
+
+        for operand in operands {
+            for item in Walker::new(&[operand], walk_options.clone()) {
+                let entry = match item {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        self.report(&format!(
+                            "{}: Cannot stat: {}",
+                            e.path.display(),
+                            error::message(&e.error)
+                        ));
+                        continue;
+                    }
+                };
+                let metadata = &entry.metadata;
+                let path = entry.path.to_string_lossy().to_string();
+                let id = (metadata.dev(), metadata.ino());
+                if Some(id) == archive_id {
+                    eprintln!("tar: {}: file is the archive; not dumped", path);
+                    continue;
+                }
+
+                let mut name = self.member_name(&path);
+                let file_type = metadata.file_type();
+                let kind = if file_type.is_dir() {
+                    if !name.ends_with('/') {
+                        name.push('/');
+                    }
+                    Kind::Directory
+                } else if file_type.is_symlink() {
+                    Kind::Symlink
+                } else if file_type.is_char_device() {
+                    Kind::CharDevice
+                } else if file_type.is_block_device() {
+                    Kind::BlockDevice
+                } else if file_type.is_fifo() {
+                    Kind::Fifo
+                } else if file_type.is_socket() {
+                    eprintln!("tar: {}: socket ignored", path);
+                    continue;
+                } else {
+                    Kind::File
+                };
+
+                let mut header = Header::new(&name, kind);
+                header.mode = metadata.mode() & 0o7777;
+                header.uid = metadata.uid();
+                header.gid = metadata.gid();
+                header.mtime = metadata.mtime();
+                header.user_name = users::user_by_id(header.uid)
+                    .map(|user| user.name)
+                    .unwrap_or_default();
+                header.group_name = users::group_by_id(header.gid)
+                    .map(|group| group.name)
+                    .unwrap_or_default();
+                if matches!(kind, Kind::CharDevice | Kind::BlockDevice) {
+                    (header.device_major, header.device_minor) =
+                        platform::device_numbers(metadata.rdev());
+                }
+
+                if kind == Kind::Symlink {
+                    match fs::read_link(&entry.path) {
+                        Ok(target) => header.link_name = target.to_string_lossy().to_string(),
+                        Err(e) => {
+                            self.report(&format!(
+                                "{}: Cannot readlink: {}",
+                                path,
+                                error::message(&e)
+                            ));
+                            continue;
+                        }
+                    }
+                } else if kind != Kind::Directory && metadata.nlink() > 1 {
+                    match links.get(&id) {
+                        Some(first) => {
+                            header.kind = Kind::HardLink;
+                            header.link_name = first.clone();
+                        }
+                        None => {
+                            links.insert(id, name.clone());
+                        }
+                    }
+                }
+
+                let mut data: Box<dyn Read> = if header.kind == Kind::File {
+                    match File::open(&entry.path) {
+                        Ok(file) => {
+                            header.size = metadata.len();
+                            Box::new(file)
+                        }
+                        Err(e) => {
+                            self.report(&format!("{}: Cannot open: {}", path, error::message(&e)));
+                            continue;
+                        }
+                    }
+                } else {
+                    Box::new(io::empty())
+                };
+                // Once part of a member is written the archive cannot be
+                // put right
+                writer
+                    .append(&header, &mut data)
+                    .map_err(|e| format!("{}: {}", path, error::message(&e)))?;
+                if self.options.verbose {
+                    self.announce(&name);
+                }
+            }
+        }
+
+        writer
+            .finish()
+            .and_then(|out| out.finish())
+            .map_err(|e| format!("write error: {}", error::message(&e)))
+    }
+
+    fn open_archive(&self) -> Result<Reader<Box<dyn Read>>, String> {
+        let input: Box<dyn Read> = match self.archive_name() {
+            Some(name) => Box::new(
+                File::open(name)
+                    .map_err(|e| format!("{}: Cannot open: {}", name, error::message(&e)))?,
+            ),
+            None => Box::new(io::stdin()),
+        };
+        let mut input = BufReader::new(input);
+        let start = input
+            .fill_buf()
+            .map_err(|e| format!("read error: {}", error::message(&e)))?;
+        if self.options.gzip || gz::is_gzip(start) {
+            let decoder = gz::Decoder::new(input).map_err(|e| error::message(&e))?;
+            Ok(Reader::new(Box::new(decoder)))
+        } else {
+            Ok(Reader::new(Box::new(input)))
+        }
+    }
+
+    fn list(&mut self, operands: &[String]) -> Result<(), String> {
+        let mut reader = self.open_archive()?;
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        let mut matched = vec![false; operands.len()];
+        while let Some(header) = next_header(&mut reader)? {
+            if !selected(&header.name, operands, &mut matched) {
+                continue;
+            }
+            let line = if self.options.verbose {
+                long_listing(&header)
+            } else {
+                header.name
+            };
+            if let Err(e) = writeln!(out, "{}", line) {
+                if e.kind() == io::ErrorKind::BrokenPipe {
+                    std::process::exit(2);
+                }
+                return Err(format!("write error: {}", error::message(&e)));
+            }
+        }
+        out.flush()
+            .map_err(|e| format!("write error: {}", error::message(&e)))?;
+        drain(reader)?;
+        self.report_unmatched(operands, &matched);
+        Ok(())
+    }
+
+    fn extract(&mut self, operands: &[String]) -> Result<(), String> {
+        let mut reader = self.open_archive()?;
+        self.change_directory()?;
+        let mut matched = vec![false; operands.len()];
+        // Directory modes and times are set last, as extracting into a
+        // directory changes its mtime and it may not be writable
+        let mut directories = Vec::new();
+
+        while let Some(header) = next_header(&mut reader)? {
+            if !selected(&header.name, operands, &mut matched) {
+                continue;
+            }
+            let Some(path) = self.safe_path(&header.name) else {
+                continue;
+            };
+            if self.options.verbose {
+                self.announce(&header.name);
+            }
+            if let Err(e) = self.extract_member(&header, &path, &mut reader, &mut directories) {
+                if e.kind() == io::ErrorKind::UnexpectedEof {
+                    return Err(error::message(&e));
+                }
+                self.report(&format!(
+                    "{}: Cannot extract: {}",
+                    header.name,
+                    error::message(&e)
+                ));
+            }
+        }
+        drain(reader)?;
+
+        for (path, header) in directories.iter().rev() {
+            let mtime = Timestamp::new(header.mtime, 0);
+            let result = fs::set_permissions(path, fs::Permissions::from_mode(header.mode))
+                .and_then(|_| platform::set_times(path, mtime, mtime, true));
+            if let Err(e) = result {
+                self.report(&format!(
+                    "{}: Cannot change mode: {}",
+                    header.name,
+                    error::message(&e)
+                ));
+            }
+        }
+        self.report_unmatched(operands, &matched);
+        Ok(())
+    }
+
+    // Where a member goes, relative to the current directory; None for
+    // names that would climb out of it
+    fn safe_path(&mut self, name: &str) -> Option<PathBuf> {
+        let relative = self.member_name(name);
+        let path = Path::new(&relative);
+        if path.components().any(|c| c == Component::ParentDir) {
+            self.report(&format!("{}: Member name contains '..'", name));
+            return None;
+        }
+        // Rebuilt from its components so a trailing slash, which would
+        // make lstat follow a symbolic link, is dropped
+        Some(path.components().collect())
+    }
+
+    fn extract_member(
+        &mut self,
+        header: &Header,
+        path: &Path,
+        data: &mut impl Read,
+        directories: &mut Vec<(PathBuf, Header)>,
+    ) -> io::Result<()> {
+        make_parents(path)?;
+        if header.kind == Kind::Directory {
+            // A symbolic link in the way is replaced, not followed
+            match fs::symlink_metadata(path) {
+                Ok(existing) if existing.is_dir() => {}
+                Ok(_) => {
+                    fs::remove_file(path)?;
+                    fs::create_dir(path)?;
+                }
+                Err(_) => fs::create_dir(path)?,
+            }
+            // Owner access is kept until the end so members can go inside
+            fs::set_permissions(path, fs::Permissions::from_mode(header.mode | 0o700))?;
+            directories.push((path.to_path_buf(), header.clone()));
+            return Ok(());
+        }
+
+        // An existing file is replaced, not written through
+        match fs::symlink_metadata(path) {
+            Ok(existing) if !existing.is_dir() => fs::remove_file(path)?,
+            _ => {}
+        }
+        let mtime = Timestamp::new(header.mtime, 0);
+        match header.kind {
+            Kind::File => {
+                let mut file = OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .mode(0o600)
+                    .open(path)?;
+                io::copy(data, &mut file)?;
+                file.set_permissions(fs::Permissions::from_mode(header.mode))?;
+                platform::set_times(path, mtime, mtime, true)
+            }
+            Kind::Symlink => {
+                unix_fs::symlink(&header.link_name, path)?;
+                platform::set_times(path, mtime, mtime, false)
+            }
+            Kind::HardLink => {
+                let Some(target) = self.safe_path(&header.link_name) else {
+                    return Ok(());
+                };
+                check_parents(&target)?;
+                fs::hard_link(target, path)
+            }
+            _ => {
+                self.report(&format!("{}: Cannot extract special file", header.name));
+                Ok(())
+            }
+        }
+    }
+
+    fn report_unmatched(&mut self, operands: &[String], matched: &[bool]) {
+        for (operand, found) in operands.iter().zip(matched) {
+            if !found {
+                self.report(&format!("{}: Not found in archive", operand));
+            }
+        }
+    }
+
+    fn run(&mut self, operands: &[String]) -> Result<bool, String> {
+        match self.options.operation {
+            Some(Operation::Create) => self.create(operands)?,
+            Some(Operation::Extract) => self.extract(operands)?,
+            Some(Operation::List) => self.list(operands)?,
+            None => return Err(String::from("You must specify one of the '-ctx' options")),
+        }
+        Ok(!self.failed)
+    }
+}
+
+// Creates the directories leading to a member. Each one that already
+// exists must be a real directory: a symbolic link extracted from the
+// archive could otherwise carry later members outside the current
+// directory
+fn make_parents(path: &Path) -> io::Result<()> {
+    let mut parent = PathBuf::new();
+    for component in path.parent().into_iter().flat_map(Path::components) {
+        parent.push(component);
+        if component == Component::RootDir || component == Component::CurDir {
+            continue;
+        }
+        match fs::symlink_metadata(&parent) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => return Err(io::Error::from_raw_os_error(libc::ENOTDIR)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => fs::create_dir(&parent)?,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+// Like make_parents, for a path that must already exist
+fn check_parents(path: &Path) -> io::Result<()> {
+    let mut parent = PathBuf::new();
+    for component in path.parent().into_iter().flat_map(Path::components) {
+        parent.push(component);
+        if !fs::symlink_metadata(&parent)?.is_dir() {
+            return Err(io::Error::from_raw_os_error(libc::ENOTDIR));
+        }
+    }
+    Ok(())
+}
+
+fn next_header(reader: &mut Reader<Box<dyn Read>>) -> Result<Option<Header>, String> {
+    reader.next_header().map_err(|e| error::message(&e))
+}
+
+// Reads the archive to its end past the end-of-archive blocks, so that a
+// writer on the other end of a pipe is not cut off and a compressed
+// archive has its trailer checked
+fn drain(reader: Reader<Box<dyn Read>>) -> Result<(), String> {
+    io::copy(&mut reader.into_inner(), &mut io::sink())
+        .map(|_| ())
+        .map_err(|e| error::message(&e))
+}
+
+// True if the member is named on the command line, or is inside a
+// directory that is; everything is selected when nothing is named
+fn selected(name: &str, operands: &[String], matched: &mut [bool]) -> bool {
+    if operands.is_empty() {
+        return true;
+    }
+    let name = name.trim_end_matches('/');
+    let mut any = false;
+    for (operand, found) in operands.iter().zip(matched.iter_mut()) {
+        let operand = operand.trim_end_matches('/');
+        let inside = name
+            .strip_prefix(operand)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+        if inside {
+            *found = true;
+            any = true;
+        }
+    }
+    any
+}
+
+// One line of tar -tv: type and mode, owner, size, date and name
+fn long_listing(header: &Header) -> String {
+    let type_char = match header.kind {
+        Kind::Directory => 'd',
+        Kind::Symlink => 'l',
+        Kind::HardLink => 'h',
+        Kind::CharDevice => 'c',
+        Kind::BlockDevice => 'b',
+        Kind::Fifo => 'p',
+        _ => '-',
+    };
+    let user = if header.user_name.is_empty() {
+        header.uid.to_string()
+    } else {
+        header.user_name.clone()
+    };
+    let group = if header.group_name.is_empty() {
+        header.gid.to_string()
+    } else {
+        header.group_name.clone()
+    };
+    let owner = format!("{}/{}", user, group);
+    let size = match header.kind {
+        Kind::CharDevice | Kind::BlockDevice => {
+            format!("{},{}", header.device_major, header.device_minor)
+        }
+        _ => header.size.to_string(),
+    };
+    // Owner and size share one right-aligned column, as in GNU tar
+    let width = 19usize.saturating_sub(owner.len()).max(size.len() + 1);
+    let date = match datetime::local_time(header.mtime) {
+        Some(t) => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            t.year, t.month, t.day, t.hour, t.minute
+        ),
+        None => header.mtime.to_string(),
+    };
+    let mut line = format!(
+        "{}{} {}{:>width$} {} {}",
+        type_char,
+        mode::symbolic(header.mode),
+        owner,
+        size,
+        date,
+        header.name,
+        width = width
+    );
+    match header.kind {
+        Kind::Symlink => line.push_str(&format!(" -> {}", header.link_name)),
+        Kind::HardLink => line.push_str(&format!(" link to {}", header.link_name)),
+        _ => {}
+    }
+    line
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("tar: {}", message);
+    std::process::exit(2);
+}
+
+fn set_operation(options: &mut TarOptions, operation: Operation) {
+    if options
+        .operation
+        .is_some_and(|current| current != operation)
+    {
+        fail("You may not specify more than one '-ctx' option");
+    }
+    options.operation = Some(operation);
+}
+
+// Applies one short flag; flags that take a value get it from `value`
+fn short_flag(options: &mut TarOptions, flag: char, value: &mut dyn FnMut() -> String) {
+    match flag {
+        'c' => set_operation(options, Operation::Create),
+        'x' => set_operation(options, Operation::Extract),
+        't' => set_operation(options, Operation::List),
+        'v' => options.verbose = true,
+        'z' => options.gzip = true,
+        'f' => options.archive = Some(value()),
+        'C' => options.directory = Some(value()),
+        _ => fail(&format!("invalid option -- '{}'", flag)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = TarOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    // The first argument may be a bundle of flags without a dash, whose
+    // values follow as separate arguments: tar cvf out.tar dir
+    if args.len() > 1 && !args[1].starts_with('-') {
+        i = 2;
+        for flag in args[1].chars() {
+            let mut value = || {
+                if i >= args.len() {
+                    fail(&format!("option requires an argument -- '{}'", flag));
+                }
+                i += 1;
+                args[i - 1].clone()
+            };
+            short_flag(&mut options, flag, &mut value);
+        }
+    }
+
+    while i < args.len() {
+        let arg = args[i].clone();
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            operands.push(arg);
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--create" => set_operation(&mut options, Operation::Create),
+            "--extract" | "--get" => set_operation(&mut options, Operation::Extract),
+            "--list" => set_operation(&mut options, Operation::List),
+            "--verbose" => options.verbose = true,
+            "--gzip" | "--gunzip" | "--ungzip" => options.gzip = true,
+            _ if arg.starts_with("--file=") => {
+                options.archive = Some(arg["--file=".len()..].to_string())
+            }
+            _ if arg.starts_with("--directory=") => {
+                options.directory = Some(arg["--directory=".len()..].to_string())
+            }
+            "--file" | "--directory" => {
+                if i >= args.len() {
+                    fail(&format!("option '{}' requires an argument", arg));
+                }
+                let value = args[i].clone();
+                i += 1;
+                if arg == "--file" {
+                    options.archive = Some(value);
+                } else {
+                    options.directory = Some(value);
+                }
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    if flag == 'f' || flag == 'C' {
+                        // The value is either the rest of this argument or the next one
+                        let rest = &arg[pos + 1..];
+                        let mut value = || {
+                            if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            }
+                        };
+                        short_flag(&mut options, flag, &mut value);
+                        break;
+                    }
+                    short_flag(&mut options, flag, &mut String::new);
+                }
+            }
+        }
+    }
+
+    let mut command = TarCommand {
+        options: &options,
+        failed: false,
+        warned_absolute: false,
+    };
+    match command.run(&operands) {
+        Ok(true) => {}
+        Ok(false) => {
+            eprintln!("tar: Exiting with failure status due to previous errors");
+            std::process::exit(2);
+        }
+        Err(message) => fail(&message),
+    }
+}
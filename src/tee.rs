@@ -0,0 +1,141 @@
+use cmds_lite::error;
+use cmds_lite::signal::{self, Signal};
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+
+#[derive(Default)]
+pub struct TeeOptions {
+    pub append: bool,
+    pub ignore_interrupts: bool,
+}
+
+struct Output {
+    name: String,
+    writer: Box<dyn Write>,
+}
+
+struct TeeCommand<'a> {
+    options: &'a TeeOptions,
+}
+
+impl<'a> TeeCommand<'a> {
+    fn new(options: &'a TeeOptions) -> Self {
+        TeeCommand { options }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let mut ok = true;
+        let mut outputs = vec![Output {
+            name: String::from("standard output"),
+            writer: Box::new(io::stdout()),
+        }];
+
+        for file in files {
+            match self.open(file) {
+                Ok(writer) => outputs.push(Output {
+                    name: file.clone(),
+                    writer: Box::new(writer),
+                }),
+                Err(e) => {
+                    eprintln!("tee: {}: {}", file, error::message(&e));
+                    ok = false;
+                }
+            }
+        }
+
+        let mut stdin = io::stdin().lock();
+        let mut buffer = vec![0; 64 * 1024];
+        while !outputs.is_empty() {
+            let count = match stdin.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(count) => count,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    eprintln!("tee: read error: {}", error::message(&e));
+                    ok = false;
+                    break;
+                }
+            };
+
+            // Every block is written and flushed right away so readers at the
+            // other end of a pipe see output as soon as it arrives. Outputs
+            // that fail are dropped and the rest keep receiving data.
+            outputs.retain_mut(|output| {
+                let result = output
+                    .writer
+                    .write_all(&buffer[..count])
+                    .and_then(|_| output.writer.flush());
+                match result {
+                    Ok(()) => true,
+                    // A reader that went away is not an error worth reporting
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => false,
+                    Err(e) => {
+                        eprintln!("tee: {}: {}", output.name, error::message(&e));
+                        ok = false;
+                        false
+                    }
+                }
+            });
+        }
+        ok
+    }
+
+    fn open(&self, file: &str) -> io::Result<File> {
+        if self.options.append {
+            OpenOptions::new().append(true).create(true).open(file)
+        } else {
+            File::create(file)
+        }
+    }
+}
+
+pub fn run(files: &[String], options: &TeeOptions) -> bool {
+    if options.ignore_interrupts {
+        if let Err(e) = signal::ignore(Signal::INT) {
+            eprintln!("tee: cannot ignore {}: {}", Signal::INT, error::message(&e));
+        }
+    }
+    let command = TeeCommand::new(options);
+    command.run(files)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = TeeOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--append" => options.append = true,
+            "--ignore-interrupts" => options.ignore_interrupts = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("tee: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'a' => options.append = true,
+                        'i' => options.ignore_interrupts = true,
+                        _ => {
+                            eprintln!("tee: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if !run(&files, &options) {
+        std::process::exit(1);
+    }
+}
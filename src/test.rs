@@ -0,0 +1,374 @@
+use cmds_lite::platform::{self, Access};
+use std::cmp::Ordering;
+use std::env;
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::Path;
+
+// Like POSIX test, 0 is true, 1 is false and 2 is an error
+const EXIT_TRUE: i32 = 0;
+const EXIT_FALSE: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+// Operators that take one operand, such as -f FILE
+fn is_unary_operator(arg: &str) -> bool {
+    matches!(
+        arg,
+        "-a" | "-b"
+            | "-c"
+            | "-d"
+            | "-e"
+            | "-f"
+            | "-g"
+            | "-h"
+            | "-k"
+            | "-n"
+            | "-p"
+            | "-r"
+            | "-s"
+            | "-t"
+            | "-u"
+            | "-w"
+            | "-o"
+            | "-x"
+            | "-z"
+            | "-G"
+            | "-L"
+            | "-O"
+            | "-S"
+    )
+}
+
+// Operators that go between two operands, such as A = B
+fn is_binary_operator(arg: &str) -> bool {
+    matches!(
+        arg,
+        "=" | "=="
+            | "!="
+            | "<"
+            | ">"
+            | "-eq"
+            | "-ne"
+            | "-lt"
+            | "-le"
+            | "-gt"
+            | "-ge"
+            | "-nt"
+            | "-ot"
+            | "-ef"
+    )
+}
+
+// An integer operand: optional blanks, an optional sign, digits and
+// optional blanks. Returned as whether it is negative and its digits
+// without leading zeros, so numbers of any length compare correctly.
+fn parse_integer(text: &str) -> Result<(bool, &str), String> {
+    let trimmed = text.trim_matches([' ', '\t']);
+    let (negative, digits) = match trimmed.as_bytes().first() {
+        Some(b'-') => (true, &trimmed[1..]),
+        Some(b'+') => (false, &trimmed[1..]),
+        _ => (false, trimmed),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("invalid integer '{}'", text));
+    }
+    let digits = digits.trim_start_matches('0');
+    Ok((negative && !digits.is_empty(), digits))
+}
+
+fn compare_integers(left: &str, right: &str) -> Result<Ordering, String> {
+    let (left_negative, left) = parse_integer(left)?;
+    let (right_negative, right) = parse_integer(right)?;
+    let magnitude = left.len().cmp(&right.len()).then_with(|| left.cmp(right));
+    Ok(match (left_negative, right_negative) {
+        (false, false) => magnitude,
+        (true, true) => magnitude.reverse(),
+        (false, true) => Ordering::Greater,
+        (true, false) => Ordering::Less,
+    })
+}
+
+fn unary_test(operator: &str, operand: &str) -> Result<bool, String> {
+    let path = Path::new(operand);
+    let metadata = || fs::metadata(path).ok();
+    let mode_bit = |bit: u32| metadata().is_some_and(|m| m.permissions().mode() & bit != 0);
+    Ok(match operator {
+        "-n" => !operand.is_empty(),
+        "-z" => operand.is_empty(),
+        "-e" => metadata().is_some(),
+        "-f" => metadata().is_some_and(|m| m.is_file()),
+        "-d" => metadata().is_some_and(|m| m.is_dir()),
+        "-b" => metadata().is_some_and(|m| m.file_type().is_block_device()),
+        "-c" => metadata().is_some_and(|m| m.file_type().is_char_device()),
+        "-p" => metadata().is_some_and(|m| m.file_type().is_fifo()),
+        "-S" => metadata().is_some_and(|m| m.file_type().is_socket()),
+        "-h" | "-L" => fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink()),
+        "-s" => metadata().is_some_and(|m| m.len() > 0),
+        "-u" => mode_bit(0o4000),
+        "-g" => mode_bit(0o2000),
+        "-k" => mode_bit(0o1000),
+        "-r" => platform::can_access(path, Access::Read),
+        "-w" => platform::can_access(path, Access::Write),
+        "-x" => platform::can_access(path, Access::Execute),
+        "-O" => metadata().is_some_and(|m| m.uid() == unsafe { libc::geteuid() }),
+        "-G" => metadata().is_some_and(|m| m.gid() == unsafe { libc::getegid() }),
+        _ => {
+            let (negative, digits) = parse_integer(operand)?;
+            let fd = if negative {
+                None
+            } else {
+                digits.parse::<i32>().ok()
+            };
+            fd.is_some_and(|fd| unsafe { libc::isatty(fd) } == 1)
+        }
+    })
+}
+
+fn binary_test(left: &str, operator: &str, right: &str) -> Result<bool, String> {
+    let modified = |name: &str| fs::metadata(name).ok().map(|m| (m.mtime(), m.mtime_nsec()));
+    Ok(match operator {
+        "=" | "==" => left == right,
+        "!=" => left != right,
+        "<" => left < right,
+        ">" => left > right,
+        "-eq" => compare_integers(left, right)? == Ordering::Equal,
+        "-ne" => compare_integers(left, right)? != Ordering::Equal,
+        "-lt" => compare_integers(left, right)? == Ordering::Less,
+        "-le" => compare_integers(left, right)? != Ordering::Greater,
+        "-gt" => compare_integers(left, right)? == Ordering::Greater,
+        "-ge" => compare_integers(left, right)? != Ordering::Less,
+        // A file that exists is newer than one that does not
+        "-nt" => match (modified(left), modified(right)) {
+            (Some(left), Some(right)) => left > right,
+            (left, _) => left.is_some(),
+        },
+        "-ot" => match (modified(left), modified(right)) {
+            (Some(left), Some(right)) => left < right,
+            (_, right) => right.is_some(),
+        },
+        _ => match (fs::metadata(left), fs::metadata(right)) {
+            (Ok(left), Ok(right)) => left.dev() == right.dev() && left.ino() == right.ino(),
+            _ => false,
+        },
+    })
+}
+
+// Evaluates the arguments the way GNU test does: up to four arguments
+// follow the POSIX rules, which decide by how many there are, and longer
+// expressions are parsed with ! binding tightest, then -a, then -o.
+struct TestCommand<'a> {
+    args: &'a [String],
+    pos: usize,
+}
+
+impl<'a> TestCommand<'a> {
+    fn arg(&self, offset: usize) -> &'a str {
+        self.args
+            .get(self.pos + offset)
+            .map(String::as_str)
+            .unwrap_or("")
+    }
+
+    fn remaining(&self) -> usize {
+        self.args.len().saturating_sub(self.pos)
+    }
+
+    fn beyond(&self) -> String {
+        format!(
+            "missing argument after '{}'",
+            self.args.last().map(String::as_str).unwrap_or("")
+        )
+    }
+
+    // Moves past one argument; `need_more` makes running out an error
+    fn advance(&mut self, need_more: bool) -> Result<(), String> {
+        self.pos += 1;
+        if need_more && self.pos >= self.args.len() {
+            return Err(self.beyond());
+        }
+        Ok(())
+    }
+
+    fn one_argument(&mut self) -> bool {
+        let value = !self.arg(0).is_empty();
+        self.pos += 1;
+        value
+    }
+
+    fn unary_operator(&mut self) -> Result<bool, String> {
+        let operator = self.arg(0);
+        // -a and -o are reserved as operators, so they are refused rather
+        // than taken as strings
+        if operator == "-a" || operator == "-o" {
+            return Err(format!("'{}': unary operator expected", operator));
+        }
+        self.advance(true)?;
+        let operand = self.arg(0);
+        self.pos += 1;
+        unary_test(operator, operand)
+    }
+
+    fn binary_operator(&mut self) -> Result<bool, String> {
+        let (left, operator, right) = (self.arg(0), self.arg(1), self.arg(2));
+        self.pos += 3;
+        binary_test(left, operator, right)
+    }
+
+    fn two_arguments(&mut self) -> Result<bool, String> {
+        let first = self.arg(0);
+        if first == "!" {
+            self.advance(false)?;
+            Ok(!self.one_argument())
+        } else if first.len() == 2 && first.starts_with('-') {
+            if !is_unary_operator(first) {
+                return Err(format!("'{}': unary operator expected", first));
+            }
+            self.unary_operator()
+        } else {
+            Err(self.beyond())
+        }
+    }
+
+    fn three_arguments(&mut self) -> Result<bool, String> {
+        if is_binary_operator(self.arg(1)) {
+            self.binary_operator()
+        } else if self.arg(0) == "!" {
+            self.advance(true)?;
+            Ok(!self.two_arguments()?)
+        } else if self.arg(0) == "(" && self.arg(2) == ")" {
+            self.advance(false)?;
+            let value = self.one_argument();
+            self.advance(false)?;
+            Ok(value)
+        } else if self.arg(1) == "-a" || self.arg(1) == "-o" {
+            self.expression()
+        } else {
+            Err(format!("'{}': binary operator expected", self.arg(1)))
+        }
+    }
+
+    fn posix_test(&mut self, count: usize) -> Result<bool, String> {
+        match count {
+            1 => Ok(self.one_argument()),
+            2 => self.two_arguments(),
+            3 => self.three_arguments(),
+            4 if self.arg(0) == "!" => {
+                self.advance(true)?;
+                Ok(!self.three_arguments()?)
+            }
+            4 if self.arg(0) == "(" && self.arg(3) == ")" => {
+                self.advance(false)?;
+                let value = self.two_arguments()?;
+                self.advance(false)?;
+                Ok(value)
+            }
+            _ => self.expression(),
+        }
+    }
+
+    fn expression(&mut self) -> Result<bool, String> {
+        if self.pos >= self.args.len() {
+            return Err(self.beyond());
+        }
+        let mut value = false;
+        loop {
+            value |= self.and()?;
+            if self.arg(0) != "-o" || self.remaining() == 0 {
+                return Ok(value);
+            }
+            self.advance(false)?;
+        }
+    }
+
+    fn and(&mut self) -> Result<bool, String> {
+        let mut value = true;
+        loop {
+            value &= self.term()?;
+            if self.arg(0) != "-a" || self.remaining() == 0 {
+                return Ok(value);
+            }
+            self.advance(false)?;
+        }
+    }
+
+    fn term(&mut self) -> Result<bool, String> {
+        let mut negated = false;
+        while self.remaining() > 0 && self.arg(0) == "!" {
+            self.advance(true)?;
+            negated = !negated;
+        }
+        if self.remaining() == 0 {
+            return Err(self.beyond());
+        }
+
+        let value = if self.arg(0) == "(" {
+            self.advance(true)?;
+            // What is inside the parentheses follows the POSIX rules too,
+            // counting up to the closing one
+            let mut count = 1;
+            while self.pos + count < self.args.len() && self.arg(count) != ")" {
+                if count == 4 {
+                    count = self.remaining();
+                    break;
+                }
+                count += 1;
+            }
+            let value = self.posix_test(count)?;
+            if self.remaining() == 0 {
+                return Err(String::from("')' expected"));
+            }
+            if self.arg(0) != ")" {
+                return Err(format!("')' expected, found '{}'", self.arg(0)));
+            }
+            self.advance(false)?;
+            value
+        } else if self.remaining() >= 3 && is_binary_operator(self.arg(1)) {
+            self.binary_operator()?
+        } else if self.arg(0).len() == 2 && self.arg(0).starts_with('-') {
+            if is_unary_operator(self.arg(0)) {
+                self.unary_operator()?
+            } else {
+                self.pos += 1;
+                true
+            }
+        } else {
+            self.one_argument()
+        };
+        Ok(negated ^ value)
+    }
+
+    fn run(&mut self) -> Result<bool, String> {
+        if self.args.is_empty() {
+            return Ok(false);
+        }
+        let value = self.posix_test(self.args.len())?;
+        if self.pos != self.args.len() {
+            return Err(format!("extra argument '{}'", self.arg(0)));
+        }
+        Ok(value)
+    }
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    // Run as [, the expression must end with a ] that is not part of it
+    let program = if args[0].ends_with('[') { "[" } else { "test" };
+    let mut operands = args.split_off(1);
+    if program == "[" && operands.pop().is_none_or(|last| last != "]") {
+        eprintln!("[: missing ']'");
+        std::process::exit(EXIT_ERROR);
+    }
+
+    let mut command = TestCommand {
+        args: &operands,
+        pos: 0,
+    };
+    match command.run() {
+        Ok(true) => std::process::exit(EXIT_TRUE),
+        Ok(false) => std::process::exit(EXIT_FALSE),
+        Err(message) => {
+            eprintln!("{}: {}", program, message);
+            std::process::exit(EXIT_ERROR);
+        }
+    }
+}
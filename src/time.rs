@@ -0,0 +1,214 @@
+use cmds_lite::error;
+use cmds_lite::platform::{self, ResourceUsage};
+use cmds_lite::signal::{self, Signal};
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, ExitStatus};
+use std::time::{Duration, Instant};
+
+// Like GNU time, failures of time itself exit with 125, and a command
+// that cannot be run with 126 or 127
+const EXIT_FAILURE: i32 = 125;
+const EXIT_CANNOT_INVOKE: i32 = 126;
+const EXIT_NOT_FOUND: i32 = 127;
+
+#[derive(Default)]
+pub struct TimeOptions {
+    // -p: the POSIX format, one "real", "user" and "sys" line each
+    pub portable: bool,
+    // -o: write the report to this file instead of standard error
+    pub output: Option<String>,
+    // -a: with -o, add to the file instead of replacing it
+    pub append: bool,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("time: {}", message);
+    std::process::exit(EXIT_FAILURE);
+}
+
+// Elapsed time as GNU time's %E shows it: hours:minutes:seconds, or
+// minutes:seconds.hundredths below an hour
+fn clock(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    } else {
+        format!(
+            "{}:{:02}.{:02}",
+            seconds / 60,
+            seconds % 60,
+            elapsed.subsec_millis() / 10
+        )
+    }
+}
+
+struct TimeCommand<'a> {
+    options: &'a TimeOptions,
+}
+
+impl<'a> TimeCommand<'a> {
+    // The report, in GNU time's default format or with -p the POSIX one,
+    // after a line saying how the command ended if it did not succeed
+    fn report(&self, status: ExitStatus, elapsed: Duration, usage: &ResourceUsage) -> String {
+        let mut text = String::new();
+        if let Some(number) = status.signal() {
+            text.push_str(&format!("Command terminated by signal {}\n", number));
+        } else if let Some(code) = status.code().filter(|&code| code != 0) {
+            text.push_str(&format!("Command exited with non-zero status {}\n", code));
+        }
+
+        let seconds = |duration: Duration| duration.as_secs_f64();
+        if self.options.portable {
+            text.push_str(&format!(
+                "real {:.2}\nuser {:.2}\nsys {:.2}\n",
+                seconds(elapsed),
+                seconds(usage.user),
+                seconds(usage.system)
+            ));
+            return text;
+        }
+        let cpu = if elapsed.is_zero() {
+            String::from("?")
+        } else {
+            let percent = seconds(usage.user + usage.system) / seconds(elapsed) * 100.0;
+            format!("{}", percent as u64)
+        };
+        text.push_str(&format!(
+            "{:.2}user {:.2}system {}elapsed {}%CPU (0avgtext+0avgdata {}maxresident)k\n",
+            seconds(usage.user),
+            seconds(usage.system),
+            clock(elapsed),
+            cpu,
+            usage.max_resident
+        ));
+        text.push_str(&format!(
+            "{}inputs+{}outputs ({}major+{}minor)pagefaults {}swaps\n",
+            usage.inputs, usage.outputs, usage.major_faults, usage.minor_faults, usage.swaps
+        ));
+        text
+    }
+
+    // Runs the command and returns the status time should exit with
+    fn run(&self, command: &[String], output: Option<File>) -> i32 {
+        let start = Instant::now();
+        let child = match Command::new(&command[0]).args(&command[1..]).spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let status = if e.kind() == io::ErrorKind::NotFound {
+                    EXIT_NOT_FOUND
+                } else {
+                    EXIT_CANNOT_INVOKE
+                };
+                eprintln!("time: cannot run {}: {}", command[0], error::message(&e));
+                return status;
+            }
+        };
+        // An interrupt from the terminal is for the command; time stays to
+        // report on it
+        let _ = signal::ignore(Signal::INT);
+
+        let (status, usage) = match platform::wait_with_usage(child.id()) {
+            Ok(result) => result,
+            Err(e) => fail(&format!(
+                "error waiting for child process: {}",
+                error::message(&e)
+            )),
+        };
+        let elapsed = start.elapsed();
+
+        let report = self.report(status, elapsed, &usage);
+        let written = match output {
+            Some(mut file) => file.write_all(report.as_bytes()),
+            None => io::stderr().write_all(report.as_bytes()),
+        };
+        if let Err(e) = written {
+            fail(&format!("write error: {}", error::message(&e)));
+        }
+
+        match (status.code(), status.signal()) {
+            (Some(code), _) => code,
+            (None, Some(number)) => 128 + number,
+            (None, None) => EXIT_FAILURE,
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = TimeOptions::default();
+    let mut i = 1;
+
+    // Options end at the first argument that is not one, which starts the
+    // command
+    while i < args.len() {
+        let arg = &args[i];
+        if !arg.starts_with('-') || arg == "-" {
+            break;
+        }
+        i += 1;
+
+        match arg.as_str() {
+            "--" => break,
+            "--portability" => options.portable = true,
+            "--append" => options.append = true,
+            _ if arg.starts_with("--output=") => {
+                options.output = Some(arg["--output=".len()..].to_string())
+            }
+            "--output" if i < args.len() => {
+                i += 1;
+                options.output = Some(args[i - 1].clone());
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'p' => options.portable = true,
+                        'a' => options.append = true,
+                        'o' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            options.output = Some(value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    let command = &args[i..];
+    if command.is_empty() {
+        fail("missing program to run");
+    }
+    // The file is opened before the command runs, so a bad name is found
+    // without running it
+    let output = options.output.as_ref().map(|name| {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(options.append)
+            .truncate(!options.append)
+            .open(name)
+            .unwrap_or_else(|e| fail(&format!("cannot open {}: {}", name, error::message(&e))))
+    });
+
+    let time = TimeCommand { options: &options };
+    std::process::exit(time.run(command, output));
+}
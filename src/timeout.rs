@@ -0,0 +1,282 @@
+use cmds_lite::duration;
+use cmds_lite::error;
+use cmds_lite::signal::{self, Signal};
+use std::env;
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, Command, ExitStatus};
+use std::time::{Duration, Instant};
+
+// Like GNU timeout: 124 when the command timed out, 125 when timeout
+// itself failed, and 126 or 127 when the command could not be run
+const EXIT_TIMED_OUT: i32 = 124;
+const EXIT_FAILURE: i32 = 125;
+const EXIT_CANNOT_INVOKE: i32 = 126;
+const EXIT_NOT_FOUND: i32 = 127;
+
+// Signals sent to timeout that are passed on to the command
+const FORWARDED: [Signal; 3] = [Signal::INT, Signal::TERM, Signal::HUP];
+
+pub struct TimeoutOptions {
+    // -s: what to send when the time is up
+    pub signal: Signal,
+    // -k: send KILL if the command is still running this long after
+    pub kill_after: Option<Duration>,
+    // --preserve-status: exit with the command's status even on timeout
+    pub preserve_status: bool,
+    // --foreground: leave the command in the terminal's process group, so
+    // it can read from the terminal, and signal only the command itself
+    pub foreground: bool,
+    // -v: report each signal sent
+    pub verbose: bool,
+}
+
+impl Default for TimeoutOptions {
+    fn default() -> Self {
+        TimeoutOptions {
+            signal: Signal::TERM,
+            kill_after: None,
+            preserve_status: false,
+            foreground: false,
+            verbose: false,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("timeout: {}", message);
+    std::process::exit(EXIT_FAILURE);
+}
+
+fn parse_duration(text: &str) -> Duration {
+    duration::parse_duration(text)
+        .unwrap_or_else(|| fail(&format!("invalid time interval '{}'", text)))
+}
+
+fn parse_signal(text: &str) -> Signal {
+    Signal::from_name(text).unwrap_or_else(|| fail(&format!("'{}': invalid signal", text)))
+}
+
+struct TimeoutCommand<'a> {
+    options: &'a TimeoutOptions,
+    // The signals being waited for: the child ending, and the ones to pass on
+    watched: Vec<Signal>,
+}
+
+impl<'a> TimeoutCommand<'a> {
+    // Sends the signal to the command and, unless --foreground, to the
+    // whole process group it runs in, so that its own children get it too.
+    // timeout is in that group, so it stops listening for the signal first.
+    // A stopped command is continued so it can act on the signal.
+    fn send(&mut self, child: &Child, name: &str, sig: Signal) {
+        if self.options.verbose {
+            eprintln!(
+                "timeout: sending signal {} to command '{}'",
+                sig.name(),
+                name
+            );
+        }
+        let _ = signal::send(child.id() as i32, Some(sig));
+        if self.options.foreground {
+            return;
+        }
+        if sig != Signal::KILL && sig != Signal::CHLD {
+            let _ = signal::ignore(sig);
+            let _ = signal::unblock(&[sig]);
+            self.watched.retain(|watched| *watched != sig);
+        }
+        let _ = signal::send(0, Some(sig));
+        if sig != Signal::KILL && sig != Signal::CONT {
+            let _ = signal::send(child.id() as i32, Some(Signal::CONT));
+            let _ = signal::send(0, Some(Signal::CONT));
+        }
+    }
+
+    // Waits for the command, sending the -s signal when the time is up and
+    // KILL once the -k time after that is up too. Returns how the command
+    // ended and whether it timed out.
+    fn wait(
+        &mut self,
+        child: &mut Child,
+        name: &str,
+        duration: Duration,
+    ) -> io::Result<(ExitStatus, bool)> {
+        let mut deadline = (!duration.is_zero())
+            .then(|| Instant::now().checked_add(duration))
+            .flatten();
+        let mut next_signal = self.options.signal;
+        let mut kill_after = self.options.kill_after;
+        let mut timed_out = false;
+        loop {
+            // Checked before every wait, since the child may have ended
+            // before SIGCHLD was blocked
+            if let Some(status) = child.try_wait()? {
+                return Ok((status, timed_out));
+            }
+            let remaining = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => Duration::MAX,
+            };
+            let sig = match signal::wait_timeout(&self.watched, remaining)? {
+                Some(Signal::CHLD) => continue,
+                Some(received) => received,
+                None => {
+                    timed_out = true;
+                    next_signal
+                }
+            };
+            // After the first signal, only KILL is left to send, and only
+            // with -k
+            deadline = kill_after
+                .take()
+                .and_then(|after| Instant::now().checked_add(after));
+            next_signal = Signal::KILL;
+            self.send(child, name, sig);
+        }
+    }
+
+    // Runs the command and returns the status timeout should exit with
+    fn run(&mut self, duration: Duration, command: &[String]) -> i32 {
+        // A process group of its own, so that a timeout reaches everything
+        // the command starts
+        if !self.options.foreground {
+            unsafe {
+                libc::setpgid(0, 0);
+            }
+        }
+        let mut child = match Command::new(&command[0]).args(&command[1..]).spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let status = if e.kind() == io::ErrorKind::NotFound {
+                    EXIT_NOT_FOUND
+                } else {
+                    EXIT_CANNOT_INVOKE
+                };
+                eprintln!(
+                    "timeout: failed to run command '{}': {}",
+                    command[0],
+                    error::message(&e)
+                );
+                return status;
+            }
+        };
+
+        self.watched = vec![Signal::CHLD];
+        for sig in FORWARDED.into_iter().chain([self.options.signal]) {
+            if sig != Signal::KILL && !signal::is_ignored(sig) && !self.watched.contains(&sig) {
+                self.watched.push(sig);
+            }
+        }
+        if let Err(e) = signal::block(&self.watched) {
+            fail(&format!("cannot block signals: {}", error::message(&e)));
+        }
+
+        let (status, timed_out) = match self.wait(&mut child, &command[0], duration) {
+            Ok(result) => result,
+            Err(e) => fail(&format!(
+                "error waiting for command: {}",
+                error::message(&e)
+            )),
+        };
+        // A command that had to be killed shows it in the status, so it
+        // can be told apart from one that ended on the -s signal
+        let killed = status.signal() == Some(Signal::KILL.number());
+        let status = match (status.code(), status.signal()) {
+            (Some(code), _) => code,
+            (None, Some(number)) => {
+                // Die the same way the command did, unless it was timeout
+                // that killed it
+                if !timed_out {
+                    if let Some(sig) = Signal::from_number(number) {
+                        signal::exit_by(sig);
+                    }
+                }
+                128 + number
+            }
+            (None, None) => EXIT_FAILURE,
+        };
+        if timed_out && !self.options.preserve_status && !killed {
+            EXIT_TIMED_OUT
+        } else {
+            status
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = TimeoutOptions::default();
+    let mut i = 1;
+
+    // Options end at the duration, so the command's own are left alone
+    while i < args.len() {
+        let arg = &args[i];
+        if !arg.starts_with('-') || arg == "-" {
+            break;
+        }
+        i += 1;
+
+        match arg.as_str() {
+            "--" => break,
+            "--preserve-status" => options.preserve_status = true,
+            "--foreground" => options.foreground = true,
+            "--verbose" => options.verbose = true,
+            _ if arg.starts_with("--signal=") => {
+                options.signal = parse_signal(&arg["--signal=".len()..])
+            }
+            _ if arg.starts_with("--kill-after=") => {
+                options.kill_after = Some(parse_duration(&arg["--kill-after=".len()..]))
+            }
+            "--signal" if i < args.len() => {
+                i += 1;
+                options.signal = parse_signal(&args[i - 1]);
+            }
+            "--kill-after" if i < args.len() => {
+                i += 1;
+                options.kill_after = Some(parse_duration(&args[i - 1]));
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'v' => options.verbose = true,
+                        's' | 'k' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            if flag == 's' {
+                                options.signal = parse_signal(&value);
+                            } else {
+                                options.kill_after = Some(parse_duration(&value));
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if i >= args.len() {
+        fail("missing operand");
+    }
+    let duration = parse_duration(&args[i]);
+    let command = &args[i + 1..];
+    if command.is_empty() {
+        fail(&format!("missing operand after '{}'", args[i]));
+    }
+
+    let mut timeout = TimeoutCommand {
+        options: &options,
+        watched: Vec::new(),
+    };
+    std::process::exit(timeout.run(duration, command));
+}
@@ -0,0 +1,248 @@
+use cmds_lite::datetime::{self, Timestamp};
+use cmds_lite::error;
+use std::env;
+use std::ffi::CString;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+#[derive(Clone, Copy)]
+pub enum TimeSource {
+    Now,
+    Explicit { atime: Timestamp, mtime: Timestamp },
+}
+
+pub struct TouchOptions {
+    pub no_create: bool,
+    pub access: bool,
+    pub modification: bool,
+    pub source: TimeSource,
+}
+
+impl Default for TouchOptions {
+    fn default() -> Self {
+        TouchOptions {
+            no_create: false,
+            access: false,
+            modification: false,
+            source: TimeSource::Now,
+        }
+    }
+}
+
+trait TimeSetter {
+    fn set_times(&self, path: &Path, atime: Option<TimeSpec>, mtime: Option<TimeSpec>)
+        -> io::Result<()>;
+}
+
+#[derive(Clone, Copy)]
+enum TimeSpec {
+    Now,
+    At(Timestamp),
+}
+
+// Uses utimensat so read-only files owned by the caller can still be
+// touched, and so one of the two times can be left alone with UTIME_OMIT.
+struct UtimensatSetter;
+
+impl TimeSetter for UtimensatSetter {
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<TimeSpec>,
+        mtime: Option<TimeSpec>,
+    ) -> io::Result<()> {
+        let to_timespec = |spec: Option<TimeSpec>| match spec {
+            None => libc::timespec {
+                tv_sec: 0,
+                tv_nsec: libc::UTIME_OMIT,
+            },
+            Some(TimeSpec::Now) => libc::timespec {
+                tv_sec: 0,
+                tv_nsec: libc::UTIME_NOW,
+            },
+            Some(TimeSpec::At(stamp)) => libc::timespec {
+                tv_sec: stamp.secs as libc::time_t,
+                tv_nsec: stamp.nanos as libc::c_long,
+            },
+        };
+        let times = [to_timespec(atime), to_timespec(mtime)];
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains NUL"))?;
+
+        let result = unsafe { libc::utimensat(libc::AT_FDCWD, c_path.as_ptr(), times.as_ptr(), 0) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+struct TouchCommand<'a> {
+    setter: Box<dyn TimeSetter>,
+    options: &'a TouchOptions,
+}
+
+impl<'a> TouchCommand<'a> {
+    fn new(options: &'a TouchOptions) -> Self {
+        TouchCommand {
+            setter: Box::new(UtimensatSetter),
+            options,
+        }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let mut ok = true;
+        for file in files {
+            ok &= self.touch(file);
+        }
+        ok
+    }
+
+    fn touch(&self, file: &str) -> bool {
+        let path = Path::new(file);
+
+        if path.symlink_metadata().is_err() {
+            if self.options.no_create {
+                return true;
+            }
+            if let Err(e) = OpenOptions::new().write(true).create(true).truncate(false).open(path) {
+                eprintln!("touch: cannot touch '{}': {}", file, error::message(&e));
+                return false;
+            }
+            // A new file already has the current time
+            if matches!(self.options.source, TimeSource::Now) {
+                return true;
+            }
+        }
+
+        // Neither -a nor -m means both
+        let both = !self.options.access && !self.options.modification;
+        let (atime, mtime) = match self.options.source {
+            TimeSource::Now => (TimeSpec::Now, TimeSpec::Now),
+            TimeSource::Explicit { atime, mtime } => (TimeSpec::At(atime), TimeSpec::At(mtime)),
+        };
+        let atime = if both || self.options.access { Some(atime) } else { None };
+        let mtime = if both || self.options.modification { Some(mtime) } else { None };
+
+        if let Err(e) = self.setter.set_times(path, atime, mtime) {
+            eprintln!("touch: setting times of '{}': {}", file, error::message(&e));
+            return false;
+        }
+        true
+    }
+}
+
+pub fn run(files: &[String], options: &TouchOptions) -> bool {
+    let command = TouchCommand::new(options);
+    command.run(files)
+}
+
+fn reference_times(file: &str) -> TimeSource {
+    match fs::metadata(file) {
+        Ok(metadata) => TimeSource::Explicit {
+            atime: Timestamp::new(metadata.atime(), metadata.atime_nsec() as u32),
+            mtime: Timestamp::new(metadata.mtime(), metadata.mtime_nsec() as u32),
+        },
+        Err(e) => {
+            eprintln!(
+                "touch: failed to get attributes of '{}': {}",
+                file,
+                error::message(&e)
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parsed_time(result: Result<Timestamp, datetime::ParseDateError>) -> TimeSource {
+    match result {
+        Ok(stamp) => TimeSource::Explicit {
+            atime: stamp,
+            mtime: stamp,
+        },
+        Err(e) => {
+            eprintln!("touch: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = TouchOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--no-create" => options.no_create = true,
+            _ if arg.starts_with("--reference=") => {
+                options.source = reference_times(&arg["--reference=".len()..]);
+            }
+            _ if arg.starts_with("--date=") => {
+                options.source = parsed_time(datetime::parse_date(&arg["--date=".len()..]));
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("touch: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'c' => options.no_create = true,
+                        'a' => options.access = true,
+                        'm' => options.modification = true,
+                        // Ignored for compatibility, like GNU touch
+                        'f' => {}
+                        'r' | 'd' | 't' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + flag.len_utf8()..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                eprintln!("touch: option requires an argument -- '{}'", flag);
+                                std::process::exit(1);
+                            };
+                            options.source = match flag {
+                                'r' => reference_times(&value),
+                                'd' => parsed_time(datetime::parse_date(&value)),
+                                _ => parsed_time(datetime::parse_touch_stamp(&value)),
+                            };
+                            break;
+                        }
+                        _ => {
+                            eprintln!("touch: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        eprintln!("touch: missing file operand");
+        std::process::exit(1);
+    }
+
+    if !run(&files, &options) {
+        std::process::exit(1);
+    }
+}
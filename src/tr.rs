@@ -0,0 +1,410 @@
+use cmds_lite::error;
+use cmds_lite::glob;
+use std::env;
+use std::io::{self, Read, Write};
+
+#[derive(Default)]
+pub struct TrOptions {
+    pub delete: bool,
+    pub squeeze: bool,
+    pub complement: bool,
+}
+
+// One element of a parsed SET before expansion
+enum SetItem {
+    Byte(u8),
+    Range(u8, u8),
+    Class(String),
+    // [c*n], or [c*] to fill SET2 up to the length of SET1
+    Repeat(u8, Option<usize>),
+}
+
+fn parse_set(text: &str) -> Result<Vec<SetItem>, String> {
+    let bytes = text.as_bytes();
+    let mut items = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'[' && bytes.get(i + 1) == Some(&b':') {
+            if let Some(end) = find(bytes, i + 2, b":]") {
+                let name = String::from_utf8_lossy(&bytes[i + 2..end]).to_string();
+                if !is_class(&name) {
+                    return Err(format!("invalid character class '{}'", name));
+                }
+                items.push(SetItem::Class(name));
+                i = end + 2;
+                continue;
+            }
+        }
+
+        if bytes[i] == b'[' {
+            if let Some((c, after)) = parse_char(bytes, i + 1) {
+                if bytes.get(after) == Some(&b'*') {
+                    if let Some(end) = find(bytes, after + 1, b"]") {
+                        let count = std::str::from_utf8(&bytes[after + 1..end]).unwrap_or("");
+                        let count = if count.is_empty() {
+                            None
+                        } else {
+                            // A leading zero means octal, as in GNU tr
+                            let radix = if count.starts_with('0') { 8 } else { 10 };
+                            let n = usize::from_str_radix(count, radix).map_err(|_| {
+                                format!("invalid repeat count '{}' in [c*n] construct", count)
+                            })?;
+                            if n == 0 {
+                                None
+                            } else {
+                                Some(n)
+                            }
+                        };
+                        items.push(SetItem::Repeat(c, count));
+                        i = end + 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        let (c, next) = parse_char(bytes, i).unwrap_or((bytes[i], i + 1));
+        if bytes.get(next) == Some(&b'-') && next + 1 < bytes.len() {
+            let (high, after) = parse_char(bytes, next + 1).unwrap_or((bytes[next + 1], next + 2));
+            if high < c {
+                return Err(format!(
+                    "range-endpoints of '{}-{}' are in reverse collating sequence order",
+                    escape(c),
+                    escape(high)
+                ));
+            }
+            items.push(SetItem::Range(c, high));
+            i = after;
+        } else {
+            items.push(SetItem::Byte(c));
+            i = next;
+        }
+    }
+    Ok(items)
+}
+
+fn is_class(name: &str) -> bool {
+    matches!(
+        name,
+        "alpha"
+            | "digit"
+            | "alnum"
+            | "upper"
+            | "lower"
+            | "space"
+            | "blank"
+            | "punct"
+            | "print"
+            | "graph"
+            | "cntrl"
+            | "xdigit"
+    )
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    (from..bytes.len().saturating_sub(needle.len() - 1)).find(|&j| bytes[j..].starts_with(needle))
+}
+
+// Reads one possibly backslash-escaped character, returning it and the
+// index after it
+fn parse_char(bytes: &[u8], i: usize) -> Option<(u8, usize)> {
+    let c = *bytes.get(i)?;
+    if c != b'\\' {
+        return Some((c, i + 1));
+    }
+    let Some(&next) = bytes.get(i + 1) else {
+        // A trailing backslash stands for itself
+        return Some((b'\\', i + 1));
+    };
+    let escaped = match next {
+        b'n' => b'\n',
+        b't' => b'\t',
+        b'r' => b'\r',
+        b'a' => 0x07,
+        b'b' => 0x08,
+        b'f' => 0x0c,
+        b'v' => 0x0b,
+        b'0'..=b'7' => {
+            // Up to three octal digits
+            let mut value: u32 = 0;
+            let mut j = i + 1;
+            while j < bytes.len() && j < i + 4 && (b'0'..=b'7').contains(&bytes[j]) {
+                value = value * 8 + (bytes[j] - b'0') as u32;
+                j += 1;
+            }
+            if value > 0xff {
+                j -= 1;
+                value /= 8;
+            }
+            return Some((value as u8, j));
+        }
+        other => other,
+    };
+    Some((escaped, i + 2))
+}
+
+fn escape(c: u8) -> String {
+    if c.is_ascii_graphic() {
+        (c as char).to_string()
+    } else {
+        format!("\\{:03o}", c)
+    }
+}
+
+fn class_bytes(name: &str) -> Vec<u8> {
+    (0..=127u8)
+        .filter(|b| glob::class_matches(name, *b as char))
+        .collect()
+}
+
+// Expands SET1 into the bytes it lists, in order
+fn expand(items: &[SetItem]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for item in items {
+        match item {
+            SetItem::Byte(c) => out.push(*c),
+            SetItem::Range(low, high) => out.extend(*low..=*high),
+            SetItem::Class(name) => out.extend(class_bytes(name)),
+            SetItem::Repeat(..) => {
+                return Err(String::from(
+                    "the [c*] repeat construct may not appear in string1",
+                ))
+            }
+        }
+    }
+    Ok(out)
+}
+
+// Expands SET2, where a [c*] fills it up to the length of SET1
+fn expand_to(items: &[SetItem], target: usize) -> Result<Vec<u8>, String> {
+    let fixed: usize = items
+        .iter()
+        .map(|item| match item {
+            SetItem::Byte(_) => 1,
+            SetItem::Range(low, high) => (*high - *low) as usize + 1,
+            SetItem::Class(name) => class_bytes(name).len(),
+            SetItem::Repeat(_, Some(n)) => *n,
+            SetItem::Repeat(_, None) => 0,
+        })
+        .sum();
+
+    let mut out = Vec::new();
+    let mut filled = false;
+    for item in items {
+        match item {
+            SetItem::Repeat(c, Some(n)) => out.extend(std::iter::repeat_n(*c, *n)),
+            SetItem::Repeat(c, None) => {
+                if filled {
+                    return Err(String::from(
+                        "only one [c*] repeat construct may appear in string2",
+                    ));
+                }
+                filled = true;
+                out.extend(std::iter::repeat_n(*c, target.saturating_sub(fixed)));
+            }
+            SetItem::Byte(c) => out.push(*c),
+            SetItem::Range(low, high) => out.extend(*low..=*high),
+            SetItem::Class(name) => {
+                if name != "upper" && name != "lower" {
+                    return Err(String::from(
+                        "when translating, the only character classes that may appear in string2 are 'upper' and 'lower'",
+                    ));
+                }
+                out.extend(class_bytes(name));
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn complement(set: &[u8]) -> Vec<u8> {
+    let mut present = [false; 256];
+    for c in set {
+        present[*c as usize] = true;
+    }
+    (0..=255u8).filter(|c| !present[*c as usize]).collect()
+}
+
+// Byte tables built once from the sets, so each input byte costs a lookup
+struct Translator {
+    map: [u8; 256],
+    delete: [bool; 256],
+    squeeze: [bool; 256],
+    // Last byte written, kept across reads so squeezing spans buffers
+    last: Option<u8>,
+}
+
+impl Translator {
+    fn new(options: &TrOptions, set1: &str, set2: Option<&str>) -> Result<Self, String> {
+        let mut set1_bytes = expand(&parse_set(set1)?)?;
+        if options.complement {
+            set1_bytes = complement(&set1_bytes);
+        }
+
+        let mut translator = Translator {
+            map: std::array::from_fn(|i| i as u8),
+            delete: [false; 256],
+            squeeze: [false; 256],
+            last: None,
+        };
+
+        let translating = !options.delete && set2.is_some();
+        if options.delete {
+            for c in &set1_bytes {
+                translator.delete[*c as usize] = true;
+            }
+        }
+
+        if translating {
+            let set2_bytes = expand_to(&parse_set(set2.unwrap_or(""))?, set1_bytes.len())?;
+            let Some(&fill) = set2_bytes.last() else {
+                return Err(String::from(
+                    "when not truncating set1, string2 must be non-empty",
+                ));
+            };
+            // A shorter SET2 is padded with its last character
+            for (index, c) in set1_bytes.iter().enumerate() {
+                translator.map[*c as usize] = *set2_bytes.get(index).unwrap_or(&fill);
+            }
+        }
+
+        if options.squeeze {
+            // Squeezing uses SET2 when one is given, otherwise SET1
+            let squeezed = match set2 {
+                Some(set2) if translating => expand_to(&parse_set(set2)?, set1_bytes.len())?,
+                Some(set2) => expand(&parse_set(set2)?)?,
+                None => set1_bytes,
+            };
+            for c in squeezed {
+                translator.squeeze[c as usize] = true;
+            }
+        }
+        Ok(translator)
+    }
+
+    fn process(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        for byte in input {
+            if self.delete[*byte as usize] {
+                continue;
+            }
+            let c = self.map[*byte as usize];
+            if self.squeeze[c as usize] && self.last == Some(c) {
+                continue;
+            }
+            output.push(c);
+            self.last = Some(c);
+        }
+    }
+}
+
+struct TrCommand {
+    translator: Translator,
+}
+
+impl TrCommand {
+    fn new(translator: Translator) -> Self {
+        TrCommand { translator }
+    }
+
+    fn run<R: Read, W: Write>(&mut self, mut input: R, mut output: W) -> io::Result<()> {
+        let mut buffer = vec![0; 64 * 1024];
+        let mut translated = Vec::with_capacity(buffer.len());
+        loop {
+            let count = match input.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(count) => count,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            translated.clear();
+            self.translator.process(&buffer[..count], &mut translated);
+            output.write_all(&translated)?;
+        }
+        output.flush()
+    }
+}
+
+pub fn run(options: &TrOptions, set1: &str, set2: Option<&str>) -> Result<(), String> {
+    let translator = Translator::new(options, set1, set2)?;
+    let mut command = TrCommand::new(translator);
+    command
+        .run(io::stdin().lock(), io::stdout().lock())
+        .map_err(|e| format!("write error: {}", error::message(&e)))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = TrOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--delete" => options.delete = true,
+            "--squeeze-repeats" => options.squeeze = true,
+            "--complement" => options.complement = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("tr: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'd' => options.delete = true,
+                        's' => options.squeeze = true,
+                        'c' | 'C' => options.complement = true,
+                        _ => {
+                            eprintln!("tr: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Translating and deleting-then-squeezing need both sets; deleting
+    // alone needs only SET1
+    let needed = match (options.delete, options.squeeze) {
+        (true, true) | (false, false) => 2,
+        (true, false) => 1,
+        // -s alone squeezes SET1, or translates and then squeezes SET2
+        (false, true) => operands.len().clamp(1, 2),
+    };
+
+    if operands.is_empty() {
+        eprintln!("tr: missing operand");
+        std::process::exit(1);
+    }
+    if operands.len() < needed {
+        eprintln!(
+            "tr: missing operand after '{}'",
+            operands[operands.len() - 1]
+        );
+        if options.delete {
+            eprintln!("Two strings must be given when both deleting and squeezing repeats.");
+        } else {
+            eprintln!("Two strings must be given when translating.");
+        }
+        std::process::exit(1);
+    }
+    if operands.len() > needed {
+        eprintln!("tr: extra operand '{}'", operands[needed]);
+        if options.delete && !options.squeeze {
+            eprintln!("Only one string may be given when deleting without squeezing repeats.");
+        }
+        std::process::exit(1);
+    }
+
+    if let Err(message) = run(&options, &operands[0], operands.get(1).map(|s| s.as_str())) {
+        eprintln!("tr: {}", message);
+        std::process::exit(1);
+    }
+}
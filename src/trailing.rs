@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Seek};
+use std::os::unix::fs::FileExt;
+
+// The last lines or bytes of an input, shared by head, which leaves them
+// out, and tail, which prints them. A regular file is searched backwards
+// from its end; anything else has to be read through, holding back only
+// what may still turn out to be at the end.
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+// The part of a regular file still to be read, as its current offset and
+// its size. None for pipes, terminals and the like, and for files that
+// claim to be empty, as those in /proc do.
+pub fn remaining_extent(mut file: &File) -> Option<(u64, u64)> {
+    let metadata = file
+        .metadata()
+        .ok()
+        .filter(|m| m.is_file() && m.len() > 0)?;
+    let position = file.stream_position().ok()?;
+    Some((position.min(metadata.len()), metadata.len()))
+}
+
+// The offset at which the last `count` lines between `start` and `end`
+// begin, or `start` if there are not that many. A last line without a
+// delimiter still counts as a line.
+pub fn last_lines_offset(
+    file: &File,
+    start: u64,
+    end: u64,
+    count: u64,
+    delimiter: u8,
+) -> io::Result<u64> {
+    if count == 0 {
+        return Ok(end);
+    }
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut position = end;
+    let mut remaining = count;
+    while position > start {
+        let size = (position - start).min(BUFFER_SIZE as u64) as usize;
+        position -= size as u64;
+        file.read_exact_at(&mut buffer[..size], position)?;
+        for index in (0..size).rev() {
+            let offset = position + index as u64;
+            // The delimiter at the very end only ends the last line
+            if buffer[index] != delimiter || offset + 1 == end {
+                continue;
+            }
+            remaining -= 1;
+            if remaining == 0 {
+                return Ok(offset + 1);
+            }
+        }
+    }
+    Ok(start)
+}
+
+// The last `capacity` lines read so far. Each line pushed past that
+// pushes out the oldest, which is handed back.
+pub struct LastLines {
+    lines: VecDeque<Vec<u8>>,
+    capacity: u64,
+}
+
+impl LastLines {
+    pub fn new(capacity: u64) -> Self {
+        LastLines {
+            lines: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, line: Vec<u8>) -> Option<Vec<u8>> {
+        if self.capacity == 0 {
+            return Some(line);
+        }
+        self.lines.push_back(line);
+        if self.lines.len() as u64 > self.capacity {
+            return self.lines.pop_front();
+        }
+        None
+    }
+
+    pub fn into_lines(self) -> impl Iterator<Item = Vec<u8>> {
+        self.lines.into_iter()
+    }
+}
+
+// The last `capacity` bytes read so far, kept in a ring
+pub struct LastBytes {
+    bytes: VecDeque<u8>,
+    capacity: u64,
+}
+
+impl LastBytes {
+    pub fn new(capacity: u64) -> Self {
+        LastBytes {
+            bytes: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    // Adds `data`, passing whatever no longer fits to `overflow`, oldest
+    // first
+    pub fn push(&mut self, data: &[u8], mut overflow: impl FnMut(&[u8])) {
+        self.bytes.extend(data);
+        let excess = (self.bytes.len() as u64).saturating_sub(self.capacity) as usize;
+        if excess == 0 {
+            return;
+        }
+        let (front, back) = self.bytes.as_slices();
+        let from_front = excess.min(front.len());
+        overflow(&front[..from_front]);
+        overflow(&back[..excess - from_front]);
+        self.bytes.drain(..excess);
+    }
+
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        self.bytes.as_slices()
+    }
+}
@@ -0,0 +1,315 @@
+use cmds_lite::color::{self, Palette};
+use cmds_lite::tty;
+use cmds_lite::walk::{WalkOptions, Walker};
+use std::env;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+pub struct TreeOptions {
+    // -a: include names starting with a dot
+    pub all: bool,
+    // -d: list directories only
+    pub dirs_only: bool,
+    // -L: descend at most this many levels
+    pub level: Option<usize>,
+    // --filelimit: do not open directories with more entries than this
+    pub file_limit: Option<usize>,
+    // -C / -n: color names the way ls does
+    pub color: color::When,
+    // --noreport: leave out the count at the end
+    pub no_report: bool,
+    // --paginate: page output that is longer than the screen
+    pub paginate: bool,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        TreeOptions {
+            all: false,
+            dirs_only: false,
+            level: None,
+            file_limit: None,
+            color: color::When::Auto,
+            no_report: false,
+            paginate: false,
+        }
+    }
+}
+
+// One line of the drawing, before it is known whether the entry is the
+// last in its directory
+struct Line {
+    depth: usize,
+    name: String,
+    // A note after the name, such as "[error opening dir]"
+    note: Option<String>,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("tree: {}", message);
+    std::process::exit(1);
+}
+
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+struct TreeCommand<'a> {
+    options: &'a TreeOptions,
+    palette: Option<Palette>,
+    directories: usize,
+    files: usize,
+}
+
+impl<'a> TreeCommand<'a> {
+    // The name as shown: colored, and with the target for symbolic links
+    fn display(&self, name: &str, path: &Path, metadata: &fs::Metadata) -> String {
+        let mut text = match &self.palette {
+            Some(palette) => {
+                let key = palette.file_key(name, path, metadata);
+                match palette.start(&key) {
+                    start if start.is_empty() => name.to_string(),
+                    start => format!("{}{}{}", start, name, palette.end()),
+                }
+            }
+            None => name.to_string(),
+        };
+        if metadata.file_type().is_symlink() {
+            if let Ok(target) = fs::read_link(path) {
+                text.push_str(&format!(" -> {}", target.display()));
+            }
+        }
+        text
+    }
+
+    // The entries a directory would show, to hold against --filelimit
+    fn count_entries(&self, path: &Path) -> usize {
+        let Ok(entries) = fs::read_dir(path) else {
+            return 0;
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| self.options.all || !is_hidden(&entry.file_name().to_string_lossy()))
+            .filter(|entry| {
+                !self.options.dirs_only || entry.file_type().is_ok_and(|kind| kind.is_dir())
+            })
+            .count()
+    }
+
+    // Walks one root, returning its lines in order
+    fn collect(&mut self, root: &str) -> Vec<Line> {
+        let walk_options = WalkOptions {
+            max_depth: self.options.level,
+            sort: true,
+            ..WalkOptions::default()
+        };
+        let mut walker = Walker::new(&[root], walk_options);
+        let mut lines: Vec<Line> = Vec::new();
+
+        while let Some(item) = walker.next() {
+            let entry = match item {
+                Ok(entry) => entry,
+                Err(_) => {
+                    // An unreadable directory was listed just before its
+                    // error; a missing root has no line yet
+                    match lines.last_mut() {
+                        Some(line) => line.note = Some(String::from("[error opening dir]")),
+                        None => lines.push(Line {
+                            depth: 0,
+                            name: root.to_string(),
+                            note: Some(String::from("[error opening dir]")),
+                        }),
+                    }
+                    continue;
+                }
+            };
+
+            let name = if entry.depth == 0 {
+                root.to_string()
+            } else {
+                entry.file_name()
+            };
+            if entry.depth > 0 {
+                if !self.options.all && is_hidden(&name) {
+                    walker.skip_current_dir();
+                    continue;
+                }
+                if self.options.dirs_only && !entry.is_dir() {
+                    continue;
+                }
+                if entry.is_dir() {
+                    self.directories += 1;
+                } else {
+                    self.files += 1;
+                }
+            }
+
+            let mut note = None;
+            if entry.depth > 0 && entry.is_dir() {
+                if let Some(limit) = self.options.file_limit {
+                    let count = self.count_entries(&entry.path);
+                    if count > limit {
+                        walker.skip_current_dir();
+                        note = Some(format!(
+                            "[{} entries exceeds filelimit, not opening dir]",
+                            count
+                        ));
+                    }
+                }
+            }
+            lines.push(Line {
+                depth: entry.depth,
+                name: self.display(&name, &entry.path, &entry.metadata),
+                note,
+            });
+        }
+        lines
+    }
+
+    // Draws the lines with box characters; an entry is the last in its
+    // directory when nothing at its depth follows before the walk climbs
+    // back out
+    fn draw(&self, lines: &[Line], out: &mut impl Write) -> io::Result<()> {
+        // Whether each open directory above the current line was the last
+        // of its own siblings
+        let mut last_at: Vec<bool> = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let mut text = String::new();
+            if line.depth > 0 {
+                let last = lines[i + 1..]
+                    .iter()
+                    .find(|next| next.depth <= line.depth)
+                    .is_none_or(|next| next.depth < line.depth);
+                last_at.truncate(line.depth - 1);
+                for &ancestor_last in &last_at {
+                    text.push_str(if ancestor_last { "    " } else { "│   " });
+                }
+                text.push_str(if last { "└── " } else { "├── " });
+                last_at.push(last);
+            }
+            text.push_str(&line.name);
+            if let Some(note) = &line.note {
+                text.push_str("  ");
+                text.push_str(note);
+            }
+            writeln!(out, "{}", text)?;
+        }
+        Ok(())
+    }
+
+    fn report(&self, out: &mut impl Write) -> io::Result<()> {
+        let directories = format!(
+            "{} director{}",
+            self.directories,
+            if self.directories == 1 { "y" } else { "ies" }
+        );
+        if self.options.dirs_only {
+            writeln!(out, "\n{}", directories)
+        } else {
+            writeln!(
+                out,
+                "\n{}, {} file{}",
+                directories,
+                self.files,
+                if self.files == 1 { "" } else { "s" }
+            )
+        }
+    }
+
+    fn run(&mut self, roots: &[String]) -> io::Result<()> {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        for root in roots {
+            let lines = self.collect(root);
+            self.draw(&lines, &mut out)?;
+        }
+        if !self.options.no_report {
+            self.report(&mut out)?;
+        }
+        out.flush()
+    }
+}
+
+fn parse_number(value: &str, what: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(number) if number > 0 => number,
+        _ => fail(&format!("invalid {} '{}'", what, value)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = TreeOptions::default();
+    let mut roots = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            roots.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--noreport" => options.no_report = true,
+            "--paginate" => options.paginate = true,
+            "--filelimit" if i < args.len() => {
+                i += 1;
+                options.file_limit = Some(parse_number(&args[i - 1], "file limit"));
+            }
+            _ if arg.starts_with("--filelimit=") => {
+                options.file_limit = Some(parse_number(&arg["--filelimit=".len()..], "file limit"))
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'a' => options.all = true,
+                        'd' => options.dirs_only = true,
+                        'C' => options.color = color::When::Always,
+                        'n' => options.color = color::When::Never,
+                        'L' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail("missing argument to -L option");
+                            };
+                            options.level = Some(parse_number(&value, "level"));
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if roots.is_empty() {
+        roots.push(String::from("."));
+    }
+
+    // Colors are settled while standard output is still the terminal
+    let colored = options.color.enabled();
+    if options.paginate {
+        tty::paginate();
+    }
+    let mut command = TreeCommand {
+        options: &options,
+        palette: colored.then(color::ls_palette),
+        directories: 0,
+        files: 0,
+    };
+    if let Err(e) = command.run(&roots) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            fail(&e.to_string());
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use cmds_lite::error;
+use cmds_lite::size;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::os::unix::fs::MetadataExt;
+
+// How a -s size applies to the size a file already has
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Adjust {
+    // SIZE: set it
+    Set,
+    // +SIZE: extend by
+    Extend,
+    // -SIZE: reduce by
+    Reduce,
+    // <SIZE: at most
+    AtMost,
+    // >SIZE: at least
+    AtLeast,
+    // /SIZE: round down to a multiple of
+    RoundDown,
+    // %SIZE: round up to a multiple of
+    RoundUp,
+}
+
+#[derive(Default)]
+pub struct TruncateOptions {
+    // -s: the size to set, or how to change the size
+    pub size: Option<(Adjust, u64)>,
+    // -r: start from this file's size
+    pub reference: Option<String>,
+    // -c: do not create files that do not exist
+    pub no_create: bool,
+    // -o: the size is in I/O blocks rather than bytes
+    pub io_blocks: bool,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("truncate: {}", message);
+    std::process::exit(1);
+}
+
+// Parses a -s value: a size with a K, M, G... suffix, optionally after
+// one of + - < > / %
+fn parse_size(text: &str) -> (Adjust, u64) {
+    let (adjust, rest) = match text.chars().next() {
+        Some('+') => (Adjust::Extend, &text[1..]),
+        Some('-') => (Adjust::Reduce, &text[1..]),
+        Some('<') => (Adjust::AtMost, &text[1..]),
+        Some('>') => (Adjust::AtLeast, &text[1..]),
+        Some('/') => (Adjust::RoundDown, &text[1..]),
+        Some('%') => (Adjust::RoundUp, &text[1..]),
+        _ => (Adjust::Set, text),
+    };
+    let value = size::parse_size(rest.trim_start())
+        .unwrap_or_else(|| fail(&format!("invalid number: '{}'", text)));
+    if value == 0 && matches!(adjust, Adjust::RoundDown | Adjust::RoundUp) {
+        fail("division by zero");
+    }
+    (adjust, value)
+}
+
+// The new size for a file of `current` bytes
+fn new_size(current: u64, adjust: Adjust, value: u64) -> Option<u64> {
+    match adjust {
+        Adjust::Set => Some(value),
+        Adjust::Extend => current.checked_add(value),
+        Adjust::Reduce => Some(current.saturating_sub(value)),
+        Adjust::AtMost => Some(current.min(value)),
+        Adjust::AtLeast => Some(current.max(value)),
+        Adjust::RoundDown => Some(current / value * value),
+        Adjust::RoundUp => current.div_ceil(value).checked_mul(value),
+    }
+}
+
+struct TruncateCommand<'a> {
+    options: &'a TruncateOptions,
+    // The size of the -r file
+    reference_size: Option<u64>,
+}
+
+impl<'a> TruncateCommand<'a> {
+    fn truncate(&self, name: &str) -> Result<(), String> {
+        let file = match OpenOptions::new()
+            .write(true)
+            .create(!self.options.no_create)
+            .truncate(false)
+            .open(name)
+        {
+            Ok(file) => file,
+            // With -c a missing file is left missing, quietly
+            Err(e) if e.kind() == io::ErrorKind::NotFound && self.options.no_create => {
+                return Ok(())
+            }
+            Err(e) => {
+                return Err(format!(
+                    "cannot open '{}' for writing: {}",
+                    name,
+                    error::message(&e)
+                ))
+            }
+        };
+        let metadata = file
+            .metadata()
+            .map_err(|e| format!("cannot fstat '{}': {}", name, error::message(&e)))?;
+
+        let size = match self.options.size {
+            Some((adjust, value)) => {
+                let value = if self.options.io_blocks {
+                    value
+                        .checked_mul(metadata.blksize().max(1))
+                        .ok_or_else(|| format!("overflow in {} * {}", value, metadata.blksize()))?
+                } else {
+                    value
+                };
+                // Relative sizes start from the reference file when there
+                // is one
+                let current = self.reference_size.unwrap_or(metadata.len());
+                new_size(current, adjust, value)
+                    .ok_or_else(|| format!("overflow extending size of file '{}'", name))?
+            }
+            None => self.reference_size.unwrap_or(metadata.len()),
+        };
+        if size > i64::MAX as u64 {
+            return Err(format!("overflow extending size of file '{}'", name));
+        }
+        file.set_len(size).map_err(|e| {
+            format!(
+                "failed to truncate '{}' at {} bytes: {}",
+                name,
+                size,
+                error::message(&e)
+            )
+        })
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let mut ok = true;
+        for name in files {
+            if let Err(message) = self.truncate(name) {
+                eprintln!("truncate: {}", message);
+                ok = false;
+            }
+        }
+        ok
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = TruncateOptions::default();
+    let mut files = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--no-create" => options.no_create = true,
+            "--io-blocks" => options.io_blocks = true,
+            _ if arg.starts_with("--size=") => {
+                options.size = Some(parse_size(&arg["--size=".len()..]))
+            }
+            _ if arg.starts_with("--reference=") => {
+                options.reference = Some(arg["--reference=".len()..].to_string())
+            }
+            "--size" if i < args.len() => {
+                i += 1;
+                options.size = Some(parse_size(&args[i - 1]));
+            }
+            "--reference" if i < args.len() => {
+                i += 1;
+                options.reference = Some(args[i - 1].clone());
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'c' => options.no_create = true,
+                        'o' => options.io_blocks = true,
+                        's' | 'r' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            if flag == 's' {
+                                options.size = Some(parse_size(&value));
+                            } else {
+                                options.reference = Some(value);
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    if options.size.is_none() && options.reference.is_none() {
+        fail("you must specify either '--size' or '--reference'");
+    }
+    if options.io_blocks && options.size.is_none() {
+        fail("'--io-blocks' was specified but '--size' was not");
+    }
+    if options
+        .size
+        .is_some_and(|(adjust, _)| adjust == Adjust::Set && options.reference.is_some())
+    {
+        fail("you must specify a relative '--size' with '--reference'");
+    }
+    if files.is_empty() {
+        fail("missing file operand");
+    }
+
+    let reference_size = options.reference.as_ref().map(|reference| {
+        fs::metadata(reference)
+            .map(|metadata| metadata.len())
+            .unwrap_or_else(|e| {
+                fail(&format!(
+                    "cannot stat '{}': {}",
+                    reference,
+                    error::message(&e)
+                ))
+            })
+    });
+    let command = TruncateCommand {
+        options: &options,
+        reference_size,
+    };
+    if !command.run(&files) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,145 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+// Terminal control for commands that take over the screen, such as watch
+// and pager: the escape sequences they draw with, the size of the
+// terminal, raw mode for reading single keys, and sending output through
+// a pager.
+
+// Switches to the alternate screen, so the shell's screen comes back
+// untouched afterwards, and back
+pub const ALTERNATE_SCREEN: &str = "\x1b[?1049h";
+pub const NORMAL_SCREEN: &str = "\x1b[?1049l";
+
+// Moves to the top left corner and clears the screen
+pub const CLEAR: &str = "\x1b[H\x1b[2J";
+
+// Returns to the start of the line and clears it
+pub const CLEAR_LINE: &str = "\r\x1b[K";
+
+// Reverse video on, and all attributes off
+pub const REVERSE: &str = "\x1b[7m";
+pub const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy)]
+pub struct Size {
+    pub rows: usize,
+    pub columns: usize,
+}
+
+// The size of the terminal standard output is on, with $LINES and
+// $COLUMNS taking precedence as they do for curses programs
+pub fn size() -> Option<Size> {
+    let variable = |name: &str| {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .filter(|&value| value > 0)
+    };
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let reported = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    let rows = variable("LINES").or((reported && size.ws_row > 0).then_some(size.ws_row as usize));
+    let columns =
+        variable("COLUMNS").or((reported && size.ws_col > 0).then_some(size.ws_col as usize));
+    Some(Size {
+        rows: rows?,
+        columns: columns?,
+    })
+}
+
+// A terminal in raw mode: keys arrive one at a time, unechoed, and
+// control characters such as ^C are read rather than acted on. The old
+// settings come back when this is dropped.
+pub struct RawMode {
+    fd: RawFd,
+    saved: libc::termios,
+}
+
+impl RawMode {
+    pub fn enable(terminal: &impl AsRawFd) -> io::Result<RawMode> {
+        let fd = terminal.as_raw_fd();
+        let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut saved) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = saved;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        // Output is left alone, so "\n" still starts a new line
+        raw.c_oflag = saved.c_oflag;
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(fd, libc::TCSAFLUSH, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(RawMode { fd, saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(self.fd, libc::TCSAFLUSH, &self.saved) };
+    }
+}
+
+// The pager standard output goes to, waited for when the process exits
+static PAGER: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn wait_for_pager() {
+    let pid = PAGER.swap(0, Ordering::SeqCst);
+    if pid > 0 {
+        // Closing the pipe is the end of the pager's input
+        unsafe {
+            libc::close(libc::STDOUT_FILENO);
+            libc::waitpid(pid, std::ptr::null_mut(), 0);
+        }
+    }
+}
+
+// The pager to use: $PAGER through the shell, with less told to quit when
+// the output fits on one screen as git does, or else cmd-pager from the
+// same directory as this program
+fn pager_command() -> Command {
+    if let Ok(pager) = env::var("PAGER") {
+        if !pager.is_empty() {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(pager);
+            if env::var_os("LESS").is_none() {
+                command.env("LESS", "FRX");
+            }
+            return command;
+        }
+    }
+    let program = env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join("cmd-pager")))
+        .filter(|program| program.exists())
+        .unwrap_or_else(|| PathBuf::from("cmd-pager"));
+    let mut command = Command::new(program);
+    command.arg("-F");
+    command
+}
+
+// Sends the rest of standard output through a pager when it is a
+// terminal. Output that fits on the screen is shown as it is. Nothing
+// changes if the pager cannot be started.
+pub fn paginate() {
+    if !io::stdout().is_terminal() {
+        return;
+    }
+    let Ok(mut child) = pager_command().stdin(Stdio::piped()).spawn() else {
+        return;
+    };
+    let Some(input) = child.stdin.take() else {
+        return;
+    };
+    if unsafe { libc::dup2(input.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+        return;
+    }
+    drop(input);
+    PAGER.store(child.id() as i32, Ordering::SeqCst);
+    unsafe { libc::atexit(wait_for_pager) };
+}
@@ -0,0 +1,116 @@
+use cmds_lite::platform;
+use std::env;
+
+#[derive(Default)]
+pub struct UnameOptions {
+    // -s: kernel name
+    pub kernel_name: bool,
+    // -n: network node host name
+    pub node_name: bool,
+    // -r: kernel release
+    pub kernel_release: bool,
+    // -v: kernel version
+    pub kernel_version: bool,
+    // -m: machine hardware name
+    pub machine: bool,
+    // -p: processor type, unknown on Linux
+    pub processor: bool,
+    // -i: hardware platform, unknown on Linux
+    pub platform: bool,
+    // -o: operating system
+    pub operating_system: bool,
+    // -a: everything, leaving out -p and -i when unknown
+    pub all: bool,
+}
+
+// The operating system name GNU uname prints for -o
+fn operating_system(kernel_name: &str) -> String {
+    match kernel_name {
+        "Linux" => String::from("GNU/Linux"),
+        name => name.to_string(),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("uname: {}", message);
+    std::process::exit(1);
+}
+
+fn run(options: &UnameOptions) -> String {
+    let info = platform::system_info().unwrap_or_else(|e| fail(&e.to_string()));
+    let any = options.kernel_name
+        || options.node_name
+        || options.kernel_release
+        || options.kernel_version
+        || options.machine
+        || options.processor
+        || options.platform
+        || options.operating_system;
+
+    let mut fields = Vec::new();
+    if options.all || options.kernel_name || !any {
+        fields.push(info.kernel_name.clone());
+    }
+    if options.all || options.node_name {
+        fields.push(info.node_name);
+    }
+    if options.all || options.kernel_release {
+        fields.push(info.kernel_release);
+    }
+    if options.all || options.kernel_version {
+        fields.push(info.kernel_version);
+    }
+    if options.all || options.machine {
+        fields.push(info.machine);
+    }
+    // -a leaves out unknown fields even when asked for them
+    if options.processor && !options.all {
+        fields.push(String::from("unknown"));
+    }
+    if options.platform && !options.all {
+        fields.push(String::from("unknown"));
+    }
+    if options.all || options.operating_system {
+        fields.push(operating_system(&info.kernel_name));
+    }
+    fields.join(" ")
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = UnameOptions::default();
+
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "--all" => options.all = true,
+            "--kernel-name" => options.kernel_name = true,
+            "--nodename" => options.node_name = true,
+            "--kernel-release" => options.kernel_release = true,
+            "--kernel-version" => options.kernel_version = true,
+            "--machine" => options.machine = true,
+            "--processor" => options.processor = true,
+            "--hardware-platform" => options.platform = true,
+            "--operating-system" => options.operating_system = true,
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'a' => options.all = true,
+                        's' => options.kernel_name = true,
+                        'n' => options.node_name = true,
+                        'r' => options.kernel_release = true,
+                        'v' => options.kernel_version = true,
+                        'm' => options.machine = true,
+                        'p' => options.processor = true,
+                        'i' => options.platform = true,
+                        'o' => options.operating_system = true,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+            _ => fail(&format!("extra operand '{}'", arg)),
+        }
+    }
+
+    println!("{}", run(&options));
+}
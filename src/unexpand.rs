@@ -0,0 +1,200 @@
+use cmds_lite::error;
+use cmds_lite::tabs::TabStops;
+use cmds_lite::wrap::{self, Count};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+#[derive(Default)]
+pub struct UnexpandOptions {
+    // -t: where the tab stops are
+    pub tabs: TabStops,
+    // -a: convert blanks anywhere in the line, not only at its start
+    pub all: bool,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("unexpand: {}", message);
+    std::process::exit(1);
+}
+
+struct UnexpandCommand<'a> {
+    options: &'a UnexpandOptions,
+}
+
+impl<'a> UnexpandCommand<'a> {
+    // Blanks that reach a tab stop become a tab, apart from a single
+    // space with no other blank next to it. Columns are counted by
+    // grapheme cluster, so characters of several bytes, wide characters
+    // and combining marks all line up.
+    fn unexpand_line(&self, line: &[u8], output: &mut Vec<u8>) {
+        let mut column = 0;
+        let mut leading = true;
+        // Blanks since the last stop or the last other character
+        let mut pending: Vec<u8> = Vec::new();
+        // Whether the pending blanks are one space that reached a stop
+        let mut space_at_stop = false;
+        let mut previous_blank = false;
+        for unit in wrap::units(line, Count::Columns) {
+            let blank = unit == b" " || unit == b"\t";
+            let after_blank = std::mem::replace(&mut previous_blank, blank);
+            if blank && (leading || self.options.all) {
+                if space_at_stop {
+                    output.push(b'\t');
+                    pending.clear();
+                    space_at_stop = false;
+                }
+                let next = if unit == b"\t" {
+                    self.options.tabs.next(column)
+                } else {
+                    Some(column + 1)
+                };
+                // Past the last stop tabs are left as they are
+                let Some(next) = next else {
+                    output.append(&mut pending);
+                    space_at_stop = false;
+                    output.extend_from_slice(unit);
+                    column += 1;
+                    continue;
+                };
+                pending.extend_from_slice(unit);
+                column = next;
+                if self.options.tabs.is_stop(column) {
+                    if pending == b" " && !after_blank {
+                        space_at_stop = true;
+                    } else {
+                        output.push(b'\t');
+                        pending.clear();
+                    }
+                }
+                continue;
+            }
+            output.append(&mut pending);
+            space_at_stop = false;
+            if !blank {
+                leading = false;
+            }
+            output.extend_from_slice(unit);
+            column = wrap::advance(column, unit, Count::Columns);
+        }
+        output.append(&mut pending);
+    }
+
+    fn unexpand<R: BufRead, W: Write>(&self, mut input: R, output: &mut W) -> io::Result<()> {
+        let mut line = Vec::new();
+        let mut converted = Vec::new();
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+            converted.clear();
+            self.unexpand_line(&line, &mut converted);
+            output.write_all(&converted)?;
+        }
+    }
+
+    fn run(&self, files: &[String]) -> bool {
+        let stdout = io::stdout();
+        let mut output = BufWriter::new(stdout.lock());
+        let mut ok = true;
+
+        for file in files {
+            let result = if file == "-" {
+                self.unexpand(io::stdin().lock(), &mut output)
+            } else {
+                File::open(file).and_then(|f| self.unexpand(BufReader::new(f), &mut output))
+            };
+            match result {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return ok,
+                Err(e) => {
+                    eprintln!("unexpand: {}: {}", file, error::message(&e));
+                    ok = false;
+                }
+            }
+        }
+
+        match output.flush() {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                eprintln!("unexpand: write error: {}", error::message(&e));
+                false
+            }
+            _ => ok,
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = UnexpandOptions::default();
+    let mut files = Vec::new();
+    // Every -t adds to one list
+    let mut tab_list: Vec<String> = Vec::new();
+    let mut first_only = false;
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--all" => options.all = true,
+            "--first-only" => first_only = true,
+            _ if arg.starts_with("--tabs=") => tab_list.push(arg["--tabs=".len()..].to_string()),
+            "--tabs" if i < args.len() => {
+                i += 1;
+                tab_list.push(args[i - 1].clone());
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            // The old form of -t, such as -4 or -2,6
+            _ if arg[1..].bytes().all(|b| b.is_ascii_digit() || b == b',') => {
+                tab_list.push(arg[1..].to_string())
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'a' => options.all = true,
+                        't' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            tab_list.push(value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if !tab_list.is_empty() {
+        options.tabs = TabStops::parse(&tab_list.join(",")).unwrap_or_else(|e| fail(&e));
+        // Giving tab stops converts all blanks, unless --first-only says not to
+        options.all = true;
+    }
+    if first_only {
+        options.all = false;
+    }
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    let command = UnexpandCommand { options: &options };
+    if !command.run(&files) {
+        std::process::exit(1);
+    }
+}
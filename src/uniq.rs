@@ -0,0 +1,208 @@
+use cmds_lite::collate;
+use cmds_lite::error;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+#[derive(Default)]
+pub struct UniqOptions {
+    pub count: bool,
+    // Only print lines that are repeated (-d) or never repeated (-u)
+    pub repeated: bool,
+    pub unique: bool,
+    pub ignore_case: bool,
+    pub skip_fields: usize,
+    pub skip_chars: usize,
+}
+
+struct UniqCommand<'a> {
+    options: &'a UniqOptions,
+}
+
+impl<'a> UniqCommand<'a> {
+    fn new(options: &'a UniqOptions) -> Self {
+        UniqCommand { options }
+    }
+
+    fn run<R: BufRead, W: Write>(&self, mut input: R, output: W) -> io::Result<()> {
+        let mut output = BufWriter::new(output);
+        // The first line of the current group of equal lines and its size
+        let mut group: Option<(Vec<u8>, usize)> = None;
+
+        loop {
+            let mut line = Vec::new();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+
+            match &mut group {
+                Some((first, count)) if self.same(first, &line) => *count += 1,
+                _ => {
+                    if let Some((first, count)) = group.take() {
+                        self.print(&mut output, &first, count)?;
+                    }
+                    group = Some((line, 1));
+                }
+            }
+        }
+
+        if let Some((first, count)) = group {
+            self.print(&mut output, &first, count)?;
+        }
+        output.flush()
+    }
+
+    fn same(&self, a: &[u8], b: &[u8]) -> bool {
+        let a = self.compared_part(a);
+        let b = self.compared_part(b);
+        if self.options.ignore_case {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    }
+
+    // The part of a line left after skipping -f fields and -s characters.
+    // A field is a run of blanks followed by non-blanks.
+    fn compared_part<'l>(&self, line: &'l [u8]) -> &'l [u8] {
+        let mut pos = 0;
+        for _ in 0..self.options.skip_fields {
+            while pos < line.len() && collate::is_blank(line[pos]) {
+                pos += 1;
+            }
+            while pos < line.len() && !collate::is_blank(line[pos]) {
+                pos += 1;
+            }
+        }
+        pos = (pos + self.options.skip_chars).min(line.len());
+        &line[pos..]
+    }
+
+    fn print<W: Write>(&self, output: &mut W, line: &[u8], count: usize) -> io::Result<()> {
+        if (self.options.repeated && count == 1) || (self.options.unique && count > 1) {
+            return Ok(());
+        }
+        if self.options.count {
+            write!(output, "{:>7} ", count)?;
+        }
+        output.write_all(line)?;
+        output.write_all(b"\n")
+    }
+}
+
+pub fn run(input: Option<&str>, output: Option<&str>, options: &UniqOptions) -> Result<(), String> {
+    let command = UniqCommand::new(options);
+    let reader: Box<dyn BufRead> = match input {
+        Some(path) if path != "-" => {
+            let file = File::open(path).map_err(|e| format!("{}: {}", path, error::message(&e)))?;
+            Box::new(BufReader::new(file))
+        }
+        _ => Box::new(io::stdin().lock()),
+    };
+
+    match output {
+        Some(path) if path != "-" => {
+            let file =
+                File::create(path).map_err(|e| format!("{}: {}", path, error::message(&e)))?;
+            command
+                .run(reader, file)
+                .map_err(|e| format!("{}: {}", input.unwrap_or("-"), error::message(&e)))
+        }
+        _ => command
+            .run(reader, io::stdout().lock())
+            .map_err(|e| format!("{}: {}", input.unwrap_or("-"), error::message(&e))),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = UniqOptions::default();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+    let mut i = 1;
+
+    let skip_count = |flag: char, text: &str| match text.parse::<usize>() {
+        Ok(count) => count,
+        Err(_) => {
+            let what = if flag == 'f' { "fields" } else { "bytes" };
+            eprintln!("uniq: {}: invalid number of {} to skip", text, what);
+            std::process::exit(1);
+        }
+    };
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            operands.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--count" => options.count = true,
+            "--repeated" => options.repeated = true,
+            "--unique" => options.unique = true,
+            "--ignore-case" => options.ignore_case = true,
+            _ if arg.starts_with("--skip-fields=") => {
+                options.skip_fields = skip_count('f', &arg["--skip-fields=".len()..]);
+            }
+            _ if arg.starts_with("--skip-chars=") => {
+                options.skip_chars = skip_count('s', &arg["--skip-chars=".len()..]);
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("uniq: unrecognized option '{}'", arg);
+                std::process::exit(1);
+            }
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'c' => options.count = true,
+                        'd' => options.repeated = true,
+                        'u' => options.unique = true,
+                        'i' => options.ignore_case = true,
+                        'f' | 's' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + flag.len_utf8()..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                eprintln!("uniq: option requires an argument -- '{}'", flag);
+                                std::process::exit(1);
+                            };
+                            if flag == 'f' {
+                                options.skip_fields = skip_count(flag, &value);
+                            } else {
+                                options.skip_chars = skip_count(flag, &value);
+                            }
+                            break;
+                        }
+                        _ => {
+                            eprintln!("uniq: invalid option -- '{}'", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if operands.len() > 2 {
+        eprintln!("uniq: extra operand '{}'", operands[2]);
+        std::process::exit(1);
+    }
+
+    let input = operands.first().map(|s| s.as_str());
+    let output = operands.get(1).map(|s| s.as_str());
+    if let Err(message) = run(input, output, &options) {
+        eprintln!("uniq: {}", message);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,38 @@
+use cmds_lite::error;
+use std::env;
+use std::fs;
+
+fn fail(message: &str) -> ! {
+    eprintln!("unlink: {}", message);
+    std::process::exit(1);
+}
+
+// A bare unlink(2): exactly one operand, no options, no prompting. A
+// directory is refused by the kernel rather than removed.
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut operands = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in &args[1..] {
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            operands.push(arg.as_str());
+        } else if arg == "--" {
+            end_of_options = true;
+        } else if arg.starts_with("--") {
+            fail(&format!("unrecognized option '{}'", arg));
+        } else {
+            fail(&format!("invalid option -- '{}'", &arg[1..2]));
+        }
+    }
+
+    match operands.as_slice() {
+        [] => fail("missing operand"),
+        [file] => {
+            if let Err(e) = fs::remove_file(file) {
+                fail(&format!("cannot unlink '{}': {}", file, error::message(&e)));
+            }
+        }
+        [_, extra, ..] => fail(&format!("extra operand '{}'", extra)),
+    }
+}
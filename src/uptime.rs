@@ -0,0 +1,131 @@
+use cmds_lite::datetime::{self, Timestamp};
+use cmds_lite::error;
+use cmds_lite::platform;
+use std::env;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    // The time, how long the system has been up, users and load
+    Default,
+    // -p: only how long the system has been up, in words
+    Pretty,
+    // -s: when the system booted
+    Since,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("uptime: {}", message);
+    std::process::exit(1);
+}
+
+fn plural(count: u64, unit: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
+// "up 2 weeks, 3 days, 4 hours, 5 minutes", leaving out what is zero
+fn pretty(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    let units = [
+        (minutes / (60 * 24 * 365), "year"),
+        (minutes / (60 * 24 * 7) % 52, "week"),
+        (minutes / (60 * 24) % 7, "day"),
+        (minutes / 60 % 24, "hour"),
+        (minutes % 60, "minute"),
+    ];
+    let parts: Vec<String> = units
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|&(count, unit)| plural(count, unit))
+        .collect();
+    if parts.is_empty() {
+        return String::from("up 0 minutes");
+    }
+    format!("up {}", parts.join(", "))
+}
+
+// The line procps prints: " 14:05:01 up 3 days,  4:05,  2 users,  load
+// average: 0.00, 0.01, 0.05"
+fn standard(now: &datetime::LocalTime, seconds: u64) -> String {
+    let mut line = format!(" {:02}:{:02}:{:02} up ", now.hour, now.minute, now.second);
+    let days = seconds / 86400;
+    if days > 0 {
+        line.push_str(&format!("{}, ", plural(days, "day")));
+    }
+    let (hours, minutes) = (seconds / 3600 % 24, seconds / 60 % 60);
+    if hours > 0 {
+        line.push_str(&format!("{:2}:{:02}, ", hours, minutes));
+    } else {
+        line.push_str(&format!("{} min, ", minutes));
+    }
+    let users = platform::logged_in_users();
+    line.push_str(&format!(
+        "{:2} user{}, ",
+        users,
+        if users == 1 { "" } else { "s" }
+    ));
+    match platform::load_average() {
+        Ok([one, five, fifteen]) => line.push_str(&format!(
+            " load average: {:.2}, {:.2}, {:.2}",
+            one, five, fifteen
+        )),
+        Err(e) => fail(&format!(
+            "cannot read the load average: {}",
+            error::message(&e)
+        )),
+    }
+    line
+}
+
+fn run(style: Style) -> String {
+    let uptime = platform::uptime().unwrap_or_else(|e| {
+        fail(&format!(
+            "cannot read the time since boot: {}",
+            error::message(&e)
+        ))
+    });
+    let seconds = uptime as u64;
+    let now = Timestamp::now();
+    match style {
+        Style::Pretty => pretty(seconds),
+        Style::Since => {
+            let boot = Timestamp::new(now.secs - seconds as i64, 0);
+            let time = datetime::local_time(boot.secs)
+                .unwrap_or_else(|| fail("cannot determine the boot time"));
+            datetime::format_time("%Y-%m-%d %H:%M:%S", boot, &time)
+        }
+        Style::Default => {
+            let time = datetime::local_time(now.secs)
+                .unwrap_or_else(|| fail("cannot determine the current time"));
+            standard(&time, seconds)
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut style = Style::Default;
+
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "--pretty" => style = Style::Pretty,
+            "--since" => style = Style::Since,
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for flag in arg.chars().skip(1) {
+                    style = match flag {
+                        'p' => Style::Pretty,
+                        's' => Style::Since,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    };
+                }
+            }
+            _ => fail(&format!("extra operand '{}'", arg)),
+        }
+    }
+
+    println!("{}", run(style));
+}
@@ -0,0 +1,196 @@
+use std::ffi::{CStr, CString};
+
+// User and group database lookups through the C library, so that NSS
+// sources beyond /etc/passwd and /etc/group are honoured.
+
+// Big enough for typical entries; lookups retry with more room on ERANGE
+const INITIAL_BUFFER: usize = 1024;
+
+pub struct User {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+pub struct Group {
+    pub name: String,
+    pub gid: u32,
+}
+
+// Calls a reentrant getpw*_r/getgr*_r function, growing its string
+// buffer until the entry fits
+fn lookup<T, F>(mut call: F) -> Option<T>
+where
+    F: FnMut(&mut Vec<libc::c_char>) -> Result<Option<T>, i32>,
+{
+    let mut buffer = vec![0 as libc::c_char; INITIAL_BUFFER];
+    loop {
+        match call(&mut buffer) {
+            Ok(entry) => return entry,
+            Err(libc::ERANGE) => {
+                let size = buffer.len() * 2;
+                buffer.resize(size, 0);
+            }
+            Err(_) => return None,
+        }
+    }
+}
+
+fn user_lookup<F>(call: F) -> Option<User>
+where
+    F: Fn(&mut libc::passwd, &mut Vec<libc::c_char>, &mut *mut libc::passwd) -> i32,
+{
+    lookup(|buffer| {
+        let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        match call(&mut entry, buffer, &mut result) {
+            0 if result.is_null() => Ok(None),
+            0 => Ok(Some(User {
+                name: unsafe { CStr::from_ptr(entry.pw_name) }
+                    .to_string_lossy()
+                    .into_owned(),
+                uid: entry.pw_uid,
+                gid: entry.pw_gid,
+            })),
+            error => Err(error),
+        }
+    })
+}
+
+fn group_lookup<F>(call: F) -> Option<Group>
+where
+    F: Fn(&mut libc::group, &mut Vec<libc::c_char>, &mut *mut libc::group) -> i32,
+{
+    lookup(|buffer| {
+        let mut entry: libc::group = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        match call(&mut entry, buffer, &mut result) {
+            0 if result.is_null() => Ok(None),
+            0 => Ok(Some(Group {
+                name: unsafe { CStr::from_ptr(entry.gr_name) }
+                    .to_string_lossy()
+                    .into_owned(),
+                gid: entry.gr_gid,
+            })),
+            error => Err(error),
+        }
+    })
+}
+
+pub fn user_by_name(name: &str) -> Option<User> {
+    let name = CString::new(name).ok()?;
+    user_lookup(|entry, buffer, result| unsafe {
+        libc::getpwnam_r(
+            name.as_ptr(),
+            entry,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            result,
+        )
+    })
+}
+
+pub fn user_by_id(uid: u32) -> Option<User> {
+    user_lookup(|entry, buffer, result| unsafe {
+        libc::getpwuid_r(uid, entry, buffer.as_mut_ptr(), buffer.len(), result)
+    })
+}
+
+pub fn group_by_name(name: &str) -> Option<Group> {
+    let name = CString::new(name).ok()?;
+    group_lookup(|entry, buffer, result| unsafe {
+        libc::getgrnam_r(
+            name.as_ptr(),
+            entry,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            result,
+        )
+    })
+}
+
+pub fn group_by_id(gid: u32) -> Option<Group> {
+    group_lookup(|entry, buffer, result| unsafe {
+        libc::getgrgid_r(gid, entry, buffer.as_mut_ptr(), buffer.len(), result)
+    })
+}
+
+// The name of a user, or the number itself when it has no entry
+pub fn user_name(uid: u32) -> String {
+    user_by_id(uid)
+        .map(|user| user.name)
+        .unwrap_or_else(|| uid.to_string())
+}
+
+pub fn group_name(gid: u32) -> String {
+    group_by_id(gid)
+        .map(|group| group.name)
+        .unwrap_or_else(|| gid.to_string())
+}
+
+// Resolves a user given by name or number, as chown does: a name wins
+// over a number when both would match
+pub fn parse_user(text: &str) -> Option<User> {
+    if let Some(user) = user_by_name(text) {
+        return Some(user);
+    }
+    let uid = text.parse().ok()?;
+    Some(user_by_id(uid).unwrap_or(User {
+        name: text.to_string(),
+        uid,
+        gid: uid,
+    }))
+}
+
+pub fn parse_group(text: &str) -> Option<u32> {
+    if let Some(group) = group_by_name(text) {
+        return Some(group.gid);
+    }
+    text.parse().ok()
+}
+
+// The real and effective IDs of this process and its supplementary groups
+pub struct Credentials {
+    pub uid: u32,
+    pub euid: u32,
+    pub gid: u32,
+    pub egid: u32,
+    pub groups: Vec<u32>,
+}
+
+pub fn current() -> Credentials {
+    let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    let mut groups = vec![0 as libc::gid_t; count.max(0) as usize];
+    let count = unsafe { libc::getgroups(groups.len() as libc::c_int, groups.as_mut_ptr()) };
+    groups.truncate(count.max(0) as usize);
+    unsafe {
+        Credentials {
+            uid: libc::getuid(),
+            euid: libc::geteuid(),
+            gid: libc::getgid(),
+            egid: libc::getegid(),
+            groups,
+        }
+    }
+}
+
+// The groups a user belongs to: their primary group, then those that list
+// them as a member
+pub fn groups_of(user: &User) -> Vec<u32> {
+    let Ok(name) = CString::new(user.name.as_str()) else {
+        return vec![user.gid];
+    };
+    let mut groups = vec![0 as libc::gid_t; 64];
+    loop {
+        let mut count = groups.len() as libc::c_int;
+        let result =
+            unsafe { libc::getgrouplist(name.as_ptr(), user.gid, groups.as_mut_ptr(), &mut count) };
+        if result >= 0 {
+            groups.truncate(count as usize);
+            return groups;
+        }
+        // Too small: count now says how many there are
+        let size = (count as usize).max(groups.len() * 2);
+        groups.resize(size, 0);
+    }
+}
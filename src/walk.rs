@@ -0,0 +1,231 @@
+use crate::error;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+// Shared directory traversal used by commands that work on whole trees.
+// Directories are read fully when entered, so the walk never holds more
+// than one open directory handle at a time.
+
+#[derive(Clone, Default)]
+pub struct WalkOptions {
+    // Follow symbolic links to directories (find -L)
+    pub follow_links: bool,
+    // Follow symbolic links given as roots only (find -H)
+    pub follow_root_links: bool,
+    // Do not descend below this depth; roots are depth 0
+    pub max_depth: Option<usize>,
+    // Yield a directory after its contents instead of before
+    pub contents_first: bool,
+    // Do not cross into other filesystems (du -x, find -xdev)
+    pub same_filesystem: bool,
+    // Visit directory entries in name order instead of readdir order
+    pub sort: bool,
+}
+
+pub struct Entry {
+    pub path: PathBuf,
+    pub depth: usize,
+    pub metadata: fs::Metadata,
+}
+
+impl Entry {
+    pub fn file_name(&self) -> String {
+        match self.path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            // Roots such as "/" or ".." have no final component
+            None => self.path.to_string_lossy().to_string(),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.metadata.is_dir()
+    }
+}
+
+#[derive(Debug)]
+pub struct WalkError {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+impl fmt::Display for WalkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "'{}': {}",
+            self.path.display(),
+            error::message(&self.error)
+        )
+    }
+}
+
+impl std::error::Error for WalkError {}
+
+struct Frame {
+    children: std::vec::IntoIter<PathBuf>,
+    depth: usize,
+    // The directory itself, held back until its contents are done when
+    // walking contents-first
+    pending: Option<Entry>,
+    // (device, inode) of the directory, for symlink loop detection
+    id: (u64, u64),
+}
+
+pub struct Walker {
+    options: WalkOptions,
+    roots: VecDeque<PathBuf>,
+    stack: Vec<Frame>,
+    root_device: Option<u64>,
+    deferred_error: Option<WalkError>,
+    // Whether the last entry returned is a directory whose contents are
+    // still to come
+    entered: bool,
+}
+
+impl Walker {
+    pub fn new<P: AsRef<Path>>(roots: &[P], options: WalkOptions) -> Self {
+        Walker {
+            options,
+            roots: roots.iter().map(|root| root.as_ref().to_path_buf()).collect(),
+            stack: Vec::new(),
+            root_device: None,
+            deferred_error: None,
+            entered: false,
+        }
+    }
+
+    // Leaves out the contents of the directory just returned, as find's
+    // -prune does. Only possible when directories come before their
+    // contents.
+    pub fn skip_current_dir(&mut self) {
+        if self.entered {
+            self.entered = false;
+            self.stack.pop();
+        }
+    }
+
+    fn metadata(&self, path: &Path, is_root: bool) -> io::Result<fs::Metadata> {
+        if self.options.follow_links || (is_root && self.options.follow_root_links) {
+            // Dangling links are still reported, as the links themselves
+            fs::metadata(path).or_else(|_| fs::symlink_metadata(path))
+        } else {
+            fs::symlink_metadata(path)
+        }
+    }
+
+    fn read_children(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut children = Vec::new();
+        for entry in fs::read_dir(path)? {
+            children.push(entry?.path());
+        }
+        if self.options.sort {
+            children.sort();
+        }
+        Ok(children)
+    }
+
+    // Turns a freshly stat'ed entry into the next item, descending into it
+    // if it is a directory the options allow entering.
+    fn visit(&mut self, entry: Entry) -> Option<Result<Entry, WalkError>> {
+        let descend = entry.is_dir()
+            && self.options.max_depth.is_none_or(|max| entry.depth < max)
+            && !(self.options.same_filesystem
+                && self.root_device.is_some_and(|dev| dev != entry.metadata.dev()));
+
+        if !descend {
+            return Some(Ok(entry));
+        }
+
+        let id = (entry.metadata.dev(), entry.metadata.ino());
+        if self.options.follow_links && self.stack.iter().any(|frame| frame.id == id) {
+            return Some(Err(WalkError {
+                path: entry.path,
+                error: io::Error::other("File system loop detected"),
+            }));
+        }
+
+        let children = match self.read_children(&entry.path) {
+            Ok(children) => children,
+            Err(error) => {
+                // The directory is still reported, followed by the error
+                self.deferred_error = Some(WalkError {
+                    path: entry.path.clone(),
+                    error,
+                });
+                return Some(Ok(entry));
+            }
+        };
+
+        if self.options.contents_first {
+            self.stack.push(Frame {
+                children: children.into_iter(),
+                depth: entry.depth,
+                pending: Some(entry),
+                id,
+            });
+            None
+        } else {
+            self.stack.push(Frame {
+                children: children.into_iter(),
+                depth: entry.depth,
+                pending: None,
+                id,
+            });
+            self.entered = true;
+            Some(Ok(entry))
+        }
+    }
+}
+
+impl Iterator for Walker {
+    type Item = Result<Entry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entered = false;
+        loop {
+            if let Some(error) = self.deferred_error.take() {
+                return Some(Err(error));
+            }
+
+            if let Some(frame) = self.stack.last_mut() {
+                match frame.children.next() {
+                    Some(path) => {
+                        let depth = frame.depth + 1;
+                        let metadata = match self.metadata(&path, false) {
+                            Ok(metadata) => metadata,
+                            Err(error) => return Some(Err(WalkError { path, error })),
+                        };
+                        if let Some(item) = self.visit(Entry { path, depth, metadata }) {
+                            return Some(item);
+                        }
+                    }
+                    None => {
+                        let frame = self.stack.pop()?;
+                        if let Some(entry) = frame.pending {
+                            return Some(Ok(entry));
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let root = self.roots.pop_front()?;
+            let metadata = match self.metadata(&root, true) {
+                Ok(metadata) => metadata,
+                Err(error) => return Some(Err(WalkError { path: root, error })),
+            };
+            self.root_device = Some(metadata.dev());
+            if let Some(item) = self.visit(Entry {
+                path: root,
+                depth: 0,
+                metadata,
+            }) {
+                return Some(item);
+            }
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use cmds_lite::datetime::{self, Timestamp};
+use cmds_lite::error;
+use cmds_lite::platform;
+use cmds_lite::signal::{self, Signal};
+use cmds_lite::tty;
+use std::env;
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+// Signals that end watch; they are waited for between runs so the screen
+// can be put back first
+const INTERRUPTS: [Signal; 3] = [Signal::INT, Signal::TERM, Signal::HUP];
+
+pub struct WatchOptions {
+    // -n: how long to wait between runs
+    pub interval: Duration,
+    // -d: highlight what changed since the last run
+    pub differences: bool,
+    // -e: stop when the command fails
+    pub errexit: bool,
+    // -g: stop when the output changes
+    pub chgexit: bool,
+    // -t: no header line
+    pub no_title: bool,
+    // -x: run the command directly instead of through sh -c
+    pub exec: bool,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            interval: Duration::from_secs(2),
+            differences: false,
+            errexit: false,
+            chgexit: false,
+            no_title: false,
+            exec: false,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("watch: {}", message);
+    std::process::exit(1);
+}
+
+// The output laid out on the screen: tabs expanded, long lines wrapped
+// and whatever does not fit below the last row dropped
+fn layout(output: &[u8], size: tty::Size, rows: usize) -> Vec<Vec<char>> {
+    let text = String::from_utf8_lossy(output);
+    let mut screen = Vec::new();
+    for line in text.lines() {
+        let mut row = Vec::new();
+        for c in line.chars() {
+            if row.len() == size.columns {
+                screen.push(std::mem::take(&mut row));
+            }
+            match c {
+                '\t' => {
+                    let stop = ((row.len() / 8 + 1) * 8).min(size.columns);
+                    row.resize(stop, ' ');
+                }
+                _ if c.is_control() => {}
+                _ => row.push(c),
+            }
+        }
+        screen.push(row);
+        if screen.len() >= rows {
+            break;
+        }
+    }
+    screen.truncate(rows);
+    screen
+}
+
+struct WatchCommand<'a> {
+    options: &'a WatchOptions,
+    command: &'a [String],
+    // The screen after the last run, for -d and -g
+    previous: Option<Vec<Vec<char>>>,
+    // Whether the alternate screen is in use
+    full_screen: bool,
+}
+
+impl<'a> WatchCommand<'a> {
+    // Runs the command once, collecting what it writes to standard output
+    // and standard error together
+    fn execute(&self) -> io::Result<(Vec<u8>, ExitStatus)> {
+        let mut command = if self.options.exec {
+            let mut command = Command::new(&self.command[0]);
+            command.args(&self.command[1..]);
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(self.command.join(" "));
+            command
+        };
+        let (mut reader, writer) = io::pipe()?;
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(writer.try_clone()?)
+            .stderr(writer)
+            .spawn()?;
+        // Both ends given to the child are gone once the Command is, so
+        // reading stops when the child closes them
+        drop(command);
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output)?;
+        Ok((output, child.wait()?))
+    }
+
+    fn header(&self, columns: usize) -> String {
+        let command = self.command.join(" ");
+        let left = format!(
+            "Every {:.1}s: {}",
+            self.options.interval.as_secs_f64(),
+            command
+        );
+        let now = Timestamp::now();
+        let date = datetime::local_time(now.secs)
+            .map(|time| datetime::format_time("%a %b %e %H:%M:%S %Y", now, &time))
+            .unwrap_or_default();
+        let right = match platform::hostname() {
+            Ok(host) => format!("{}: {}", host, date),
+            Err(_) => date,
+        };
+        // The command is cut short to keep the host and date in view
+        let room = columns.saturating_sub(right.chars().count() + 1);
+        let left: String = left.chars().take(room).collect();
+        let padding = columns.saturating_sub(left.chars().count() + right.chars().count());
+        format!("{}{}{}", left, " ".repeat(padding.max(1)), right)
+    }
+
+    fn draw(&self, screen: &[Vec<char>], columns: usize, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "{}", tty::CLEAR)?;
+        if !self.options.no_title {
+            write!(out, "{}\n\n", self.header(columns))?;
+        }
+        let previous = self.previous.as_ref().filter(|_| self.options.differences);
+        for (i, row) in screen.iter().enumerate() {
+            let before = previous.and_then(|previous| previous.get(i));
+            let mut highlighted = false;
+            let mut line = String::new();
+            for (j, &c) in row.iter().enumerate() {
+                let changed = before.is_some_and(|before| before.get(j) != Some(&c));
+                if changed != highlighted {
+                    line.push_str(if changed { tty::REVERSE } else { tty::RESET });
+                    highlighted = changed;
+                }
+                line.push(c);
+            }
+            if highlighted {
+                line.push_str(tty::RESET);
+            }
+            if i + 1 < screen.len() {
+                line.push('\n');
+            }
+            write!(out, "{}", line)?;
+        }
+        out.flush()
+    }
+
+    // Puts the shell's screen back
+    fn restore(&self) {
+        if self.full_screen {
+            print!("{}", tty::NORMAL_SCREEN);
+            let _ = io::stdout().flush();
+        }
+    }
+
+    fn run(&mut self) -> io::Result<i32> {
+        let interrupts: Vec<Signal> = INTERRUPTS
+            .into_iter()
+            .filter(|signal| !signal::is_ignored(*signal))
+            .collect();
+        signal::block(&interrupts)?;
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        if self.full_screen {
+            write!(out, "{}", tty::ALTERNATE_SCREEN)?;
+        }
+
+        loop {
+            let started = Instant::now();
+            let size = tty::size().unwrap_or(tty::Size {
+                rows: 24,
+                columns: 80,
+            });
+            let (output, status) = match self.execute() {
+                Ok(result) => result,
+                Err(e) => {
+                    drop(out);
+                    self.restore();
+                    fail(&format!(
+                        "cannot run '{}': {}",
+                        self.command[0],
+                        error::message(&e)
+                    ));
+                }
+            };
+            let rows = size
+                .rows
+                .saturating_sub(if self.options.no_title { 0 } else { 2 });
+            let screen = layout(&output, size, rows);
+            self.draw(&screen, size.columns, &mut out)?;
+
+            if self.options.errexit && !status.success() {
+                drop(out);
+                self.restore();
+                // What failed stays in view once the screen is put back
+                if self.full_screen {
+                    io::stdout().write_all(&output)?;
+                }
+                eprintln!("watch: command exit with a non-zero status");
+                return Ok(status.code().unwrap_or(1));
+            }
+            let changed = self
+                .previous
+                .as_ref()
+                .is_some_and(|previous| *previous != screen);
+            if self.options.chgexit && changed {
+                drop(out);
+                self.restore();
+                return Ok(0);
+            }
+            self.previous = Some(screen);
+
+            // The interval runs from the start of each run, so a slow
+            // command does not stretch it
+            let wait = self.options.interval.saturating_sub(started.elapsed());
+            if let Some(signal) = signal::wait_timeout(&interrupts, wait)? {
+                drop(out);
+                self.restore();
+                signal::exit_by(signal);
+            }
+        }
+    }
+}
+
+fn parse_interval(value: &str) -> Duration {
+    // A decimal comma is accepted, as in locales that write 0,5
+    let seconds = value
+        .replace(',', ".")
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .unwrap_or_else(|| fail(&format!("failed to parse argument: '{}'", value)));
+    // Like procps, nothing shorter than a tenth of a second
+    Duration::from_secs_f64(seconds.clamp(0.1, u32::MAX as f64))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = WatchOptions::default();
+    let mut i = 1;
+
+    // Options come first; the command starts at the first operand
+    while i < args.len() {
+        let arg = &args[i];
+        if !arg.starts_with('-') || arg.len() == 1 {
+            break;
+        }
+        i += 1;
+
+        match arg.as_str() {
+            "--" => break,
+            "--differences" => options.differences = true,
+            "--errexit" => options.errexit = true,
+            "--chgexit" => options.chgexit = true,
+            "--no-title" => options.no_title = true,
+            "--exec" => options.exec = true,
+            _ if arg.starts_with("--interval=") => {
+                options.interval = parse_interval(&arg["--interval=".len()..])
+            }
+            "--interval" if i < args.len() => {
+                i += 1;
+                options.interval = parse_interval(&args[i - 1]);
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        'd' => options.differences = true,
+                        'e' => options.errexit = true,
+                        'g' => options.chgexit = true,
+                        't' => options.no_title = true,
+                        'x' => options.exec = true,
+                        'n' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            options.interval = parse_interval(&value);
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if i == args.len() {
+        fail("no command given");
+    }
+
+    let mut command = WatchCommand {
+        options: &options,
+        command: &args[i..],
+        previous: None,
+        full_screen: io::stdout().is_terminal(),
+    };
+    match command.run() {
+        Ok(status) => std::process::exit(status),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            command.restore();
+            fail(&e.to_string());
+        }
+    }
+}
@@ -0,0 +1,484 @@
+use cmds_lite::error;
+use cmds_lite::platform;
+use cmds_lite::unicode;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, Write};
+use std::mem::ManuallyDrop;
+use std::os::fd::FromRawFd;
+use std::os::unix::fs::FileExt;
+use std::thread;
+
+// Size of each read
+const BUFFER_SIZE: usize = 128 * 1024;
+
+// Files smaller than this are not worth splitting across threads
+const PARALLEL_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Total {
+    Auto,
+    Always,
+    Only,
+    Never,
+}
+
+pub struct WcOptions {
+    // -l: count newlines
+    pub lines: bool,
+    // -w: count words, runs of characters other than white space
+    pub words: bool,
+    // -m: count characters
+    pub chars: bool,
+    // -c: count bytes
+    pub bytes: bool,
+    // -L: show the width of the widest line
+    pub max_line_length: bool,
+    // --total: when to print the line of totals
+    pub total: Total,
+    // Number of threads counting the lines of one large file
+    pub threads: usize,
+}
+
+impl Default for WcOptions {
+    fn default() -> Self {
+        WcOptions {
+            lines: false,
+            words: false,
+            chars: false,
+            bytes: false,
+            max_line_length: false,
+            total: Total::Auto,
+            threads: 1,
+        }
+    }
+}
+
+impl WcOptions {
+    // Lines and bytes need no decoding, so they are counted without looking
+    // at characters at all
+    fn bytes_only(&self) -> bool {
+        !self.words && !self.chars && !self.max_line_length
+    }
+
+    fn selected(&self) -> usize {
+        [
+            self.lines,
+            self.words,
+            self.chars,
+            self.bytes,
+            self.max_line_length,
+        ]
+        .into_iter()
+        .filter(|&selected| selected)
+        .count()
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Counts {
+    lines: u64,
+    words: u64,
+    chars: u64,
+    bytes: u64,
+    max_line_length: u64,
+}
+
+impl Counts {
+    fn add(&mut self, other: &Counts) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.chars += other.chars;
+        self.bytes += other.bytes;
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("wc: {}", message);
+    std::process::exit(1);
+}
+
+// Counts the newlines in `data` eight bytes at a time. In each word the
+// bytes equal to '\n' are turned to zero, and zero bytes are found without
+// carries between bytes, so the loop compiles to vector instructions.
+fn count_newlines(data: &[u8]) -> u64 {
+    const NEWLINES: u64 = u64::from_ne_bytes([b'\n'; 8]);
+    const LOW_BITS: u64 = u64::from_ne_bytes([0x7f; 8]);
+    let words = data.chunks_exact(8);
+    let tail = words.remainder();
+    let mut count = 0;
+    for word in words {
+        let word = u64::from_ne_bytes(word.try_into().unwrap()) ^ NEWLINES;
+        // The top bit of each byte is set unless the byte is zero
+        let nonzero = ((word & LOW_BITS) + LOW_BITS) | word;
+        count += (!nonzero & !LOW_BITS).count_ones() as u64;
+    }
+    count + tail.iter().filter(|&&b| b == b'\n').count() as u64
+}
+
+// Counts words, characters and line widths, decoding the input as UTF-8.
+// A character split between two reads is kept until the rest arrives;
+// bytes that are not UTF-8 count as bytes only.
+#[derive(Default)]
+struct TextCounter {
+    counts: Counts,
+    in_word: bool,
+    line_width: u64,
+    pending: Vec<u8>,
+}
+
+impl TextCounter {
+    fn feed(&mut self, data: &[u8]) {
+        self.counts.bytes += data.len() as u64;
+        let mut joined = std::mem::take(&mut self.pending);
+        let mut input = if joined.is_empty() {
+            data
+        } else {
+            joined.extend_from_slice(data);
+            &joined[..]
+        };
+        loop {
+            match std::str::from_utf8(input) {
+                Ok(text) => {
+                    self.text(text);
+                    return;
+                }
+                Err(e) => {
+                    let (valid, rest) = input.split_at(e.valid_up_to());
+                    self.text(std::str::from_utf8(valid).unwrap_or_default());
+                    match e.error_len() {
+                        Some(length) => input = &rest[length..],
+                        None => {
+                            self.pending = rest.to_vec();
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        let counts = &mut self.counts;
+        for c in text.chars() {
+            counts.chars += 1;
+            match c {
+                '\n' | '\r' | '\x0c' => {
+                    counts.lines += (c == '\n') as u64;
+                    counts.max_line_length = counts.max_line_length.max(self.line_width);
+                    self.line_width = 0;
+                }
+                '\t' => self.line_width += 8 - self.line_width % 8,
+                _ => self.line_width += unicode::width(c) as u64,
+            }
+            if c.is_whitespace() {
+                self.in_word = false;
+            } else if !self.in_word {
+                counts.words += 1;
+                self.in_word = true;
+            }
+        }
+    }
+
+    fn finish(mut self) -> Counts {
+        self.counts.max_line_length = self.counts.max_line_length.max(self.line_width);
+        self.counts
+    }
+}
+
+// What is being counted and how it is named in the output
+struct Input {
+    name: Option<String>,
+    file: Option<ManuallyDrop<File>>,
+}
+
+struct WcCommand<'a> {
+    options: &'a WcOptions,
+}
+
+impl<'a> WcCommand<'a> {
+    // Counts the lines and bytes of a large regular file with one thread per
+    // part of it, reading each part at its own offset
+    fn count_parallel(&self, file: &File, start: u64, end: u64) -> io::Result<Counts> {
+        let threads = self.options.threads as u64;
+        let part_size = (end - start).div_ceil(threads);
+        let parts: Vec<io::Result<Counts>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|part| {
+                    let from = start + part * part_size;
+                    let to = (from + part_size).min(end);
+                    scope.spawn(move || {
+                        let mut counts = Counts::default();
+                        let mut buffer = vec![0; BUFFER_SIZE];
+                        let mut offset = from;
+                        while offset < to {
+                            let wanted = (to - offset).min(BUFFER_SIZE as u64) as usize;
+                            let read = match file.read_at(&mut buffer[..wanted], offset) {
+                                Ok(0) => break,
+                                Ok(read) => read,
+                                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                                Err(e) => return Err(e),
+                            };
+                            counts.lines += count_newlines(&buffer[..read]);
+                            counts.bytes += read as u64;
+                            offset += read as u64;
+                        }
+                        Ok(counts)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        let mut counts = Counts::default();
+        for part in parts {
+            counts.add(&part?);
+        }
+        Ok(counts)
+    }
+
+    // The counts for one input, together with the error that stopped
+    // reading it, if any
+    fn count(&self, mut file: &File) -> (Counts, Option<io::Error>) {
+        let mut counts = Counts::default();
+        if self.options.bytes_only() {
+            let metadata = file.metadata().ok().filter(|m| m.is_file());
+            let position = metadata.as_ref().and_then(|_| file.stream_position().ok());
+            if let (Some(metadata), Some(position)) = (metadata, position) {
+                let end = metadata.len();
+                // The size of a regular file is the byte count, but files
+                // in /proc and the like claim to be empty, so those are read
+                if !self.options.lines && end > 0 {
+                    counts.bytes = end.saturating_sub(position);
+                    return (counts, None);
+                }
+                if self.options.threads > 1 && end.saturating_sub(position) >= PARALLEL_THRESHOLD {
+                    return match self.count_parallel(file, position, end) {
+                        Ok(counts) => (counts, None),
+                        Err(e) => (counts, Some(e)),
+                    };
+                }
+            }
+        }
+
+        let mut text = TextCounter::default();
+        let mut buffer = vec![0; BUFFER_SIZE];
+        let error = loop {
+            let read = match file.read(&mut buffer) {
+                Ok(0) => break None,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => break Some(e),
+            };
+            if self.options.bytes_only() {
+                counts.lines += count_newlines(&buffer[..read]);
+                counts.bytes += read as u64;
+            } else {
+                text.feed(&buffer[..read]);
+            }
+        };
+        if !self.options.bytes_only() {
+            counts = text.finish();
+        }
+        (counts, error)
+    }
+
+    fn format(&self, counts: &Counts, width: usize, name: Option<&str>) -> String {
+        let options = self.options;
+        let fields = [
+            (options.lines, counts.lines),
+            (options.words, counts.words),
+            (options.chars, counts.chars),
+            (options.bytes, counts.bytes),
+            (options.max_line_length, counts.max_line_length),
+        ];
+        let mut line = fields
+            .into_iter()
+            .filter(|&(selected, _)| selected)
+            .map(|(_, count)| format!("{:>width$}", count, width = width))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some(name) = name {
+            line.push(' ');
+            line.push_str(name);
+        }
+        line
+    }
+
+    // Like GNU wc, the columns are as wide as the total size of the files
+    // needs, and at least 7 when some input is not a regular file. A single
+    // count of a single input is not padded at all.
+    fn width(&self, inputs: &[Input]) -> usize {
+        if inputs.len() == 1 && self.options.selected() == 1 {
+            return 1;
+        }
+        let mut minimum = 1;
+        let mut total_size: u64 = 0;
+        for input in inputs {
+            let metadata = match (&input.name, &input.file) {
+                (_, Some(file)) => file.metadata(),
+                (Some(name), None) => fs::metadata(name),
+                (None, None) => continue,
+            };
+            match metadata {
+                Ok(metadata) if metadata.is_file() => {
+                    total_size = total_size.saturating_add(metadata.len())
+                }
+                Ok(_) => minimum = 7,
+                Err(_) => {}
+            }
+        }
+        total_size.to_string().len().max(minimum)
+    }
+
+    fn run(&self, inputs: &[Input]) -> bool {
+        let width = self.width(inputs);
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        let mut total = Counts::default();
+        let mut success = true;
+
+        for input in inputs {
+            let name = input.name.as_deref();
+            let opened;
+            let file: &File = match &input.file {
+                Some(file) => file,
+                None => {
+                    let name = name.unwrap_or("-");
+                    match File::open(name) {
+                        Ok(file) => {
+                            opened = file;
+                            &opened
+                        }
+                        Err(e) => {
+                            eprintln!("wc: {}: {}", name, error::message(&e));
+                            success = false;
+                            continue;
+                        }
+                    }
+                }
+            };
+            let (counts, error) = self.count(file);
+            if let Some(e) = error {
+                eprintln!("wc: {}: {}", name.unwrap_or("-"), error::message(&e));
+                success = false;
+            }
+            total.add(&counts);
+            if self.options.total != Total::Only {
+                let line = self.format(&counts, width, name);
+                if let Err(e) = writeln!(out, "{}", line) {
+                    if e.kind() == io::ErrorKind::BrokenPipe {
+                        return success;
+                    }
+                    fail(&format!("write error: {}", error::message(&e)));
+                }
+            }
+        }
+
+        let show_total = match self.options.total {
+            Total::Auto => inputs.len() > 1,
+            Total::Always | Total::Only => true,
+            Total::Never => false,
+        };
+        if show_total {
+            let line = if self.options.total == Total::Only {
+                self.format(&total, 1, None)
+            } else {
+                self.format(&total, width, Some("total"))
+            };
+            if let Err(e) = writeln!(out, "{}", line) {
+                if e.kind() != io::ErrorKind::BrokenPipe {
+                    fail(&format!("write error: {}", error::message(&e)));
+                }
+            }
+        }
+        success
+    }
+}
+
+fn parse_threads(text: &str) -> usize {
+    match text.parse::<usize>() {
+        Ok(0) => platform::available_processors(),
+        Ok(threads) => threads,
+        Err(_) => fail(&format!("invalid thread count '{}'", text)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = WcOptions::default();
+    let mut names = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg == "-" {
+            names.push(arg.clone());
+            continue;
+        }
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--lines" => options.lines = true,
+            "--words" => options.words = true,
+            "--chars" => options.chars = true,
+            "--bytes" => options.bytes = true,
+            "--max-line-length" => options.max_line_length = true,
+            "--parallel" => options.threads = platform::available_processors(),
+            _ if arg.starts_with("--threads=") => {
+                options.threads = parse_threads(&arg["--threads=".len()..])
+            }
+            _ if arg.starts_with("--total=") => {
+                options.total = match &arg["--total=".len()..] {
+                    "auto" => Total::Auto,
+                    "always" => Total::Always,
+                    "only" => Total::Only,
+                    "never" => Total::Never,
+                    value => fail(&format!("invalid argument '{}' for '--total'", value)),
+                }
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'l' => options.lines = true,
+                        'w' => options.words = true,
+                        'm' => options.chars = true,
+                        'c' => options.bytes = true,
+                        'L' => options.max_line_length = true,
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+    if options.selected() == 0 {
+        options.lines = true;
+        options.words = true;
+        options.bytes = true;
+    }
+
+    // Standard input is used in place, so that a regular file's size and
+    // offset can be looked at like any other file's
+    let stdin = || Some(ManuallyDrop::new(unsafe { File::from_raw_fd(0) }));
+    let inputs: Vec<Input> = if names.is_empty() {
+        vec![Input {
+            name: None,
+            file: stdin(),
+        }]
+    } else {
+        names
+            .into_iter()
+            .map(|name| Input {
+                file: if name == "-" { stdin() } else { None },
+                name: Some(name),
+            })
+            .collect()
+    };
+
+    let command = WcCommand { options: &options };
+    if !command.run(&inputs) {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,152 @@
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+// cmds-lite installs each command as cmd-NAME
+const COMMAND_PREFIX: &str = "cmd-";
+
+#[derive(Default)]
+pub struct WhichOptions {
+    // -a: every match, not just the first
+    pub all: bool,
+}
+
+// A regular file the current user may execute
+fn is_executable(path: &Path) -> bool {
+    let is_file = fs::metadata(path).map(|m| m.is_file()).unwrap_or(false);
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    is_file && unsafe { libc::access(c_path.as_ptr(), libc::X_OK) } == 0
+}
+
+struct WhichCommand<'a> {
+    options: &'a WhichOptions,
+    search_path: Vec<PathBuf>,
+    // Where this cmds-lite installation lives, found through which itself
+    own_directory: Option<PathBuf>,
+}
+
+impl<'a> WhichCommand<'a> {
+    fn new(options: &'a WhichOptions) -> Self {
+        // An empty PATH entry means the current directory
+        let search_path = env::var_os("PATH")
+            .map(|path| {
+                env::split_paths(&path)
+                    .map(|dir| {
+                        if dir.as_os_str().is_empty() {
+                            PathBuf::from(".")
+                        } else {
+                            dir
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let own_directory = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+        WhichCommand {
+            options,
+            search_path,
+            own_directory,
+        }
+    }
+
+    fn matches(&self, name: &str) -> Vec<PathBuf> {
+        if name.contains('/') {
+            let path = PathBuf::from(name);
+            return if is_executable(&path) {
+                vec![path]
+            } else {
+                Vec::new()
+            };
+        }
+
+        let mut found: Vec<PathBuf> = self
+            .search_path
+            .iter()
+            .map(|dir| dir.join(name))
+            .filter(|path| is_executable(path))
+            .collect();
+
+        // cmds-lite's own version of the command, e.g. cmd-ls for ls, even
+        // when its directory is not on PATH
+        if let Some(dir) = &self.own_directory {
+            let own_name = if name.starts_with(COMMAND_PREFIX) {
+                name.to_string()
+            } else {
+                format!("{}{}", COMMAND_PREFIX, name)
+            };
+            let own = dir.join(own_name);
+            let canonical = |path: &Path| fs::canonicalize(path).ok();
+            let listed = found.iter().any(|path| canonical(path) == canonical(&own));
+            if !listed && is_executable(&own) {
+                found.push(own);
+            }
+        }
+        found
+    }
+
+    fn run(&self, names: &[String]) -> bool {
+        let mut all_found = true;
+        for name in names {
+            let found = self.matches(name);
+            if found.is_empty() {
+                all_found = false;
+            }
+            let shown = if self.options.all { found.len() } else { 1 };
+            for path in found.iter().take(shown) {
+                println!("{}", path.display());
+            }
+        }
+        all_found
+    }
+}
+
+pub fn run(names: &[String], options: &WhichOptions) -> bool {
+    let command = WhichCommand::new(options);
+    command.run(names)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = WhichOptions::default();
+    let mut names = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            names.push(arg.clone());
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => end_of_options = true,
+            "--all" => options.all = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("which: unrecognized option '{}'", arg);
+                std::process::exit(2);
+            }
+            _ => {
+                for flag in arg.chars().skip(1) {
+                    match flag {
+                        'a' => options.all = true,
+                        _ => {
+                            eprintln!("which: invalid option -- '{}'", flag);
+                            std::process::exit(2);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Like debianutils which: exit 1 when nothing was asked for or any
+    // name was not found
+    if names.is_empty() || !run(&names, &options) {
+        std::process::exit(1);
+    }
+}
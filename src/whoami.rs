@@ -0,0 +1,28 @@
+use cmds_lite::users;
+use std::env;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    for arg in &args[1..] {
+        if arg == "--" {
+            continue;
+        }
+        if arg.starts_with("--") {
+            eprintln!("whoami: unrecognized option '{}'", arg);
+        } else if arg.starts_with('-') && arg.len() > 1 {
+            eprintln!("whoami: invalid option -- '{}'", &arg[1..2]);
+        } else {
+            eprintln!("whoami: extra operand '{}'", arg);
+        }
+        std::process::exit(1);
+    }
+
+    let euid = users::current().euid;
+    match users::user_by_id(euid) {
+        Some(user) => println!("{}", user.name),
+        None => {
+            eprintln!("whoami: cannot find name for user ID {}", euid);
+            std::process::exit(1);
+        }
+    }
+}
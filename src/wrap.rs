@@ -0,0 +1,130 @@
+use crate::unicode;
+
+// Line wrapping shared by fold and fmt: breaking a line into pieces that
+// fit a width, and filling words into lines. Widths are counted in
+// terminal columns, with wide characters taking two and combining marks
+// none, or else in bytes.
+
+pub const TAB_WIDTH: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Count {
+    // Columns on a terminal: tabs go to the next multiple of eight, a
+    // backspace goes back one and a carriage return goes back to the start
+    Columns,
+    // Every byte counts as one
+    Bytes,
+}
+
+// The column after `unit` when it starts at `column`
+pub fn advance(column: usize, unit: &[u8], count: Count) -> usize {
+    if count == Count::Bytes {
+        return column + unit.len();
+    }
+    match unit {
+        b"\t" => (column / TAB_WIDTH + 1) * TAB_WIDTH,
+        b"\x08" => column.saturating_sub(1),
+        b"\r" => 0,
+        _ => match std::str::from_utf8(unit) {
+            Ok(text) => column + unicode::cluster_width(text),
+            // A byte that is not UTF-8 is shown as one replacement
+            Err(_) => column + 1,
+        },
+    }
+}
+
+// The text split into what is never broken apart: bytes when counting
+// bytes, and otherwise grapheme clusters, with each byte that is not
+// UTF-8 on its own
+pub fn units(text: &[u8], count: Count) -> Vec<&[u8]> {
+    if count == Count::Bytes {
+        return text.chunks(1).collect();
+    }
+    let mut units = Vec::new();
+    for chunk in text.utf8_chunks() {
+        units.extend(unicode::graphemes(chunk.valid()).map(str::as_bytes));
+        units.extend(chunk.invalid().chunks(1));
+    }
+    units
+}
+
+// The width of the text from the start of a line
+pub fn width(text: &[u8], count: Count) -> usize {
+    units(text, count)
+        .into_iter()
+        .fold(0, |column, unit| advance(column, unit, count))
+}
+
+fn is_blank(unit: &[u8]) -> bool {
+    unit == b" " || unit == b"\t"
+}
+
+// Breaks a line, without its newline, into pieces no wider than `width`.
+// With `at_spaces` a piece ends after its last blank when it has one, so
+// words are kept whole. A single unit wider than `width` is a piece on
+// its own.
+pub fn fold(line: &[u8], width: usize, at_spaces: bool, count: Count) -> Vec<&[u8]> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    let mut column = 0;
+    // Where the current piece could end after a blank
+    let mut after_blank = None;
+
+    for unit in units(line, count) {
+        let mut next = advance(column, unit, count);
+        if next > width && offset > start {
+            let end = match after_blank {
+                Some(end) if at_spaces => end,
+                _ => offset,
+            };
+            pieces.push(&line[start..end]);
+            start = end;
+            after_blank = None;
+            column = self::width(&line[start..offset], count);
+            next = advance(column, unit, count);
+        }
+        offset += unit.len();
+        column = next;
+        if is_blank(unit) {
+            after_blank = Some(offset);
+        }
+    }
+    pieces.push(&line[start..]);
+    pieces
+}
+
+// A word to fill into lines, with the spaces to put after it when another
+// word follows on the same line
+pub struct Word<'a> {
+    pub text: &'a str,
+    pub space: usize,
+}
+
+// Fills the words into as few lines as greedily fit in `width` columns.
+// A word too long for any line is a line on its own.
+pub fn fill(words: &[Word], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut column = 0;
+    let mut space = 0;
+
+    for word in words {
+        let size = self::width(word.text.as_bytes(), Count::Columns);
+        if !line.is_empty() && column + space + size > width {
+            lines.push(std::mem::take(&mut line));
+            column = 0;
+        }
+        if !line.is_empty() {
+            line.extend(std::iter::repeat_n(' ', space));
+            column += space;
+        }
+        line.push_str(word.text);
+        column += size;
+        space = word.space;
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,510 @@
+use cmds_lite::error;
+use cmds_lite::platform;
+use std::env;
+use std::ffi::OsString;
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, Command, ExitStatus, Stdio};
+
+// Exit statuses, as documented for GNU xargs
+const EXIT_FAILURE: i32 = 1;
+const EXIT_COMMAND_FAILED: i32 = 123;
+const EXIT_COMMAND_ABORTED: i32 = 124;
+const EXIT_COMMAND_KILLED: i32 = 125;
+const EXIT_CANNOT_INVOKE: i32 = 126;
+const EXIT_NOT_FOUND: i32 = 127;
+
+pub struct XargsOptions {
+    // -0: items end with NUL and quotes and backslashes are not special
+    pub null: bool,
+    // -n: at most this many items per command line
+    pub max_args: Option<usize>,
+    // -I: run the command once per line, with this string in the initial
+    // arguments replaced by the line
+    pub replace: Option<String>,
+    // -P: commands run at the same time; 0 means no limit
+    pub max_procs: usize,
+    // -r: do not run the command at all when there is no input
+    pub no_run_if_empty: bool,
+}
+
+impl Default for XargsOptions {
+    fn default() -> Self {
+        XargsOptions {
+            null: false,
+            max_args: None,
+            replace: None,
+            max_procs: 1,
+            no_run_if_empty: false,
+        }
+    }
+}
+
+// Splits the input into items. By default blanks and newlines separate
+// items, and quotes and backslashes protect them; with `lines` (for -I)
+// only newlines do and leading blanks are dropped.
+struct Items<R: BufRead> {
+    input: R,
+    null: bool,
+    lines: bool,
+}
+
+impl<R: BufRead> Items<R> {
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8];
+        loop {
+            match self.input.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn next_item(&mut self) -> Result<Option<Vec<u8>>, String> {
+        let read_error = |e: io::Error| format!("read error: {}", error::message(&e));
+        if self.null {
+            let mut item = Vec::new();
+            if self
+                .input
+                .read_until(b'\0', &mut item)
+                .map_err(read_error)?
+                == 0
+            {
+                return Ok(None);
+            }
+            if item.last() == Some(&b'\0') {
+                item.pop();
+            }
+            return Ok(Some(item));
+        }
+
+        let mut item = Vec::new();
+        let mut started = false;
+        let mut quote: Option<u8> = None;
+        loop {
+            let Some(byte) = self.next_byte().map_err(read_error)? else {
+                if let Some(quote) = quote {
+                    return Err(unmatched_quote(quote));
+                }
+                return Ok(if started { Some(item) } else { None });
+            };
+
+            if let Some(open) = quote {
+                match byte {
+                    b'\n' => return Err(unmatched_quote(open)),
+                    _ if byte == open => quote = None,
+                    _ => item.push(byte),
+                }
+                continue;
+            }
+
+            match byte {
+                b'\n' if started => return Ok(Some(item)),
+                b'\n' => {}
+                b' ' | b'\t' if !started => {}
+                b' ' | b'\t' if !self.lines => return Ok(Some(item)),
+                b'\'' | b'"' => {
+                    quote = Some(byte);
+                    started = true;
+                }
+                b'\\' => {
+                    if let Some(escaped) = self.next_byte().map_err(read_error)? {
+                        item.push(escaped);
+                    }
+                    started = true;
+                }
+                _ => {
+                    item.push(byte);
+                    started = true;
+                }
+            }
+        }
+    }
+}
+
+fn unmatched_quote(quote: u8) -> String {
+    let name = if quote == b'\'' { "single" } else { "double" };
+    format!(
+        "unmatched {} quote; by default quotes are special to xargs unless you use the -0 option",
+        name
+    )
+}
+
+// Why xargs has to stop early, with the status to exit with
+struct Abort(i32);
+
+// The commands that have been started and not yet waited for
+struct Jobs {
+    // The command as given, for messages about it
+    name: String,
+    running: Vec<Child>,
+    max_procs: usize,
+    status: i32,
+}
+
+impl Jobs {
+    fn new(name: String, max_procs: usize) -> Self {
+        Jobs {
+            name,
+            running: Vec::new(),
+            max_procs,
+            status: 0,
+        }
+    }
+
+    fn start(&mut self, program: &OsString, args: &[OsString]) -> Result<(), Abort> {
+        if self.max_procs != 0 && self.running.len() >= self.max_procs {
+            self.wait_one()?;
+        }
+
+        // The command must not read the items that xargs is reading
+        let spawned = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(child) => {
+                self.running.push(child);
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!(
+                    "xargs: {}: {}",
+                    program.to_string_lossy(),
+                    error::message(&e)
+                );
+                let status = if e.kind() == io::ErrorKind::NotFound {
+                    EXIT_NOT_FOUND
+                } else {
+                    EXIT_CANNOT_INVOKE
+                };
+                Err(Abort(status))
+            }
+        }
+    }
+
+    // Waits for whichever command finishes first. waitid with WNOWAIT
+    // tells which one it is without reaping it, so that Child::wait still
+    // can.
+    fn wait_one(&mut self) -> Result<(), Abort> {
+        let pid = loop {
+            let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+            let flags = libc::WEXITED | libc::WNOWAIT;
+            if unsafe { libc::waitid(libc::P_ALL, 0, &mut info, flags) } == 0 {
+                break unsafe { info.si_pid() };
+            }
+            if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                self.running.clear();
+                return Ok(());
+            }
+        };
+        let Some(index) = self.running.iter().position(|c| c.id() as i32 == pid) else {
+            self.running.clear();
+            return Ok(());
+        };
+        let mut child = self.running.remove(index);
+        match child.wait() {
+            Ok(status) => self.check(status),
+            Err(e) => {
+                eprintln!("xargs: {}", error::message(&e));
+                Err(Abort(EXIT_FAILURE))
+            }
+        }
+    }
+
+    fn check(&mut self, status: ExitStatus) -> Result<(), Abort> {
+        let program = &self.name;
+        match (status.code(), status.signal()) {
+            (Some(0), _) => Ok(()),
+            (Some(255), _) => {
+                eprintln!("xargs: {}: exited with status 255; aborting", program);
+                Err(Abort(EXIT_COMMAND_ABORTED))
+            }
+            (Some(_), _) => {
+                self.status = EXIT_COMMAND_FAILED;
+                Ok(())
+            }
+            (None, signal) => {
+                eprintln!(
+                    "xargs: {}: terminated by signal {}",
+                    program,
+                    signal.unwrap_or(0)
+                );
+                Err(Abort(EXIT_COMMAND_KILLED))
+            }
+        }
+    }
+
+    // Waits for everything still running; an abort still lets the other
+    // commands finish, as GNU xargs does
+    fn finish(&mut self) -> Result<(), Abort> {
+        let mut result = Ok(());
+        while !self.running.is_empty() {
+            if let Err(abort) = self.wait_one() {
+                result = result.and(Err(abort));
+            }
+        }
+        result
+    }
+}
+
+struct XargsCommand<'a> {
+    options: &'a XargsOptions,
+    program: OsString,
+    initial_args: Vec<OsString>,
+    jobs: Jobs,
+    // Bytes of the command line taken by the command and its initial
+    // arguments
+    base_bytes: usize,
+    limit: usize,
+}
+
+// Each argument costs its bytes and a terminating NUL, as GNU xargs counts
+// them; the 128 KiB cap leaves far more room than the pointers need
+fn argument_cost(arg: &[u8]) -> usize {
+    arg.len() + 1
+}
+
+impl<'a> XargsCommand<'a> {
+    fn new(command: &[String], options: &'a XargsOptions) -> Self {
+        // Like GNU xargs, the default command is echo
+        let mut command: Vec<OsString> = command.iter().map(OsString::from).collect();
+        if command.is_empty() {
+            command.push(OsString::from("echo"));
+        }
+        let program = command.remove(0);
+        let base_bytes = argument_cost(program.as_bytes())
+            + command
+                .iter()
+                .map(|arg| argument_cost(arg.as_bytes()))
+                .sum::<usize>();
+        let name = program.to_string_lossy().into_owned();
+        XargsCommand {
+            options,
+            program,
+            initial_args: command,
+            jobs: Jobs::new(name, options.max_procs),
+            base_bytes,
+            limit: platform::argument_space(),
+        }
+    }
+
+    // Collects items into command lines as large as -n and ARG_MAX allow
+    fn run_batched<R: BufRead>(&mut self, items: &mut Items<R>) -> Result<(), Abort> {
+        let mut pending: Vec<OsString> = Vec::new();
+        let mut pending_bytes = 0;
+        let mut started = false;
+
+        loop {
+            let item = match items.next_item() {
+                Ok(Some(item)) => item,
+                Ok(None) => break,
+                Err(message) => {
+                    // What was read before the bad input still runs
+                    eprintln!("xargs: {}", message);
+                    if !pending.is_empty() {
+                        self.start(&pending)?;
+                    }
+                    return Err(Abort(EXIT_FAILURE));
+                }
+            };
+
+            let cost = argument_cost(&item);
+            if self.base_bytes + cost > self.limit {
+                eprintln!("xargs: argument line too long");
+                if !pending.is_empty() {
+                    self.start(&pending)?;
+                }
+                return Err(Abort(EXIT_FAILURE));
+            }
+            let full = self.options.max_args == Some(pending.len());
+            if full || self.base_bytes + pending_bytes + cost > self.limit {
+                self.start(&pending)?;
+                started = true;
+                pending.clear();
+                pending_bytes = 0;
+            }
+            pending.push(OsString::from_vec(item));
+            pending_bytes += cost;
+        }
+
+        // Without -r the command runs once even when there was no input
+        if !pending.is_empty() || (!started && !self.options.no_run_if_empty) {
+            self.start(&pending)?;
+        }
+        Ok(())
+    }
+
+    // -I: one command per item, with the item put in place of the
+    // replacement string
+    fn run_replacing<R: BufRead>(
+        &mut self,
+        items: &mut Items<R>,
+        replace: &str,
+    ) -> Result<(), Abort> {
+        loop {
+            let item = match items.next_item() {
+                Ok(Some(item)) => item,
+                Ok(None) => return Ok(()),
+                Err(message) => {
+                    eprintln!("xargs: {}", message);
+                    return Err(Abort(EXIT_FAILURE));
+                }
+            };
+
+            let program = substitute(self.program.as_bytes(), replace.as_bytes(), &item);
+            let args: Vec<OsString> = self
+                .initial_args
+                .iter()
+                .map(|arg| substitute(arg.as_bytes(), replace.as_bytes(), &item))
+                .collect();
+            self.jobs.start(&program, &args)?;
+        }
+    }
+
+    fn start(&mut self, items: &[OsString]) -> Result<(), Abort> {
+        let mut args = self.initial_args.clone();
+        args.extend_from_slice(items);
+        self.jobs.start(&self.program, &args)
+    }
+
+    fn run<R: BufRead>(&mut self, input: R) -> i32 {
+        let mut items = Items {
+            input,
+            null: self.options.null,
+            lines: self.options.replace.is_some(),
+        };
+        let result = match self.options.replace.clone() {
+            Some(replace) => self.run_replacing(&mut items, &replace),
+            None => self.run_batched(&mut items),
+        };
+        let finished = self.jobs.finish();
+        match result.and(finished) {
+            Ok(()) => self.jobs.status,
+            Err(Abort(status)) => status,
+        }
+    }
+}
+
+// Replaces every occurrence of `pattern` in `arg` with `item`
+fn substitute(arg: &[u8], pattern: &[u8], item: &[u8]) -> OsString {
+    if pattern.is_empty() {
+        return OsString::from_vec(arg.to_vec());
+    }
+    let mut out = Vec::with_capacity(arg.len());
+    let mut i = 0;
+    while i < arg.len() {
+        if arg[i..].starts_with(pattern) {
+            out.extend_from_slice(item);
+            i += pattern.len();
+        } else {
+            out.push(arg[i]);
+            i += 1;
+        }
+    }
+    OsString::from_vec(out)
+}
+
+pub fn run(command: &[String], options: &XargsOptions) -> i32 {
+    let mut xargs = XargsCommand::new(command, options);
+    let stdin = io::stdin();
+    xargs.run(BufReader::new(stdin.lock()))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("xargs: {}", message);
+    std::process::exit(EXIT_FAILURE);
+}
+
+fn number(value: &str, option: char) -> usize {
+    match value.parse() {
+        Ok(n) => n,
+        Err(_) => fail(&format!(
+            "invalid number \"{}\" for -{} option",
+            value, option
+        )),
+    }
+}
+
+fn max_args(value: &str) -> usize {
+    let n = number(value, 'n');
+    if n == 0 {
+        fail("value 0 for -n option should be >= 1");
+    }
+    n
+}
+
+// -I takes over from an earlier -n
+fn set_replace(options: &mut XargsOptions, replace: String) {
+    if options.max_args.take().is_some() {
+        eprintln!("xargs: warning: options --max-args and --replace/-I/-i are mutually exclusive, ignoring previous --max-args value");
+    }
+    options.replace = Some(replace);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut options = XargsOptions::default();
+    let mut i = 1;
+
+    // Options come first; the command and its initial arguments follow
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--" {
+            i += 1;
+            break;
+        }
+        if !arg.starts_with('-') || arg.len() == 1 {
+            break;
+        }
+        i += 1;
+
+        match arg.as_str() {
+            "--null" => options.null = true,
+            "--no-run-if-empty" => options.no_run_if_empty = true,
+            "--replace" => set_replace(&mut options, String::from("{}")),
+            _ if arg.starts_with("--replace=") => {
+                set_replace(&mut options, arg["--replace=".len()..].to_string());
+            }
+            _ if arg.starts_with("--max-args=") => {
+                options.max_args = Some(max_args(&arg["--max-args=".len()..]));
+            }
+            _ if arg.starts_with("--max-procs=") => {
+                options.max_procs = number(&arg["--max-procs=".len()..], 'P');
+            }
+            _ if arg.starts_with("--") => fail(&format!("unrecognized option '{}'", arg)),
+            _ => {
+                for (pos, flag) in arg.char_indices().skip(1) {
+                    match flag {
+                        '0' => options.null = true,
+                        'r' => options.no_run_if_empty = true,
+                        'n' | 'I' | 'P' => {
+                            // The value is either the rest of this argument or the next one
+                            let rest = &arg[pos + 1..];
+                            let value = if !rest.is_empty() {
+                                rest.to_string()
+                            } else if i < args.len() {
+                                i += 1;
+                                args[i - 1].clone()
+                            } else {
+                                fail(&format!("option requires an argument -- '{}'", flag));
+                            };
+                            match flag {
+                                'n' => options.max_args = Some(max_args(&value)),
+                                'I' => set_replace(&mut options, value),
+                                _ => options.max_procs = number(&value, 'P'),
+                            }
+                            break;
+                        }
+                        _ => fail(&format!("invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+        }
+    }
+
+    std::process::exit(run(&args[i..], &options));
+}
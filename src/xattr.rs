@@ -0,0 +1,127 @@
+use std::ffi::CString;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+// Extended attributes of files, read without following symbolic links.
+// On Linux, POSIX ACLs are kept as attributes too, under names of their
+// own, so the same calls tell whether a file has an ACL.
+
+// The attributes holding a file's access and default ACLs
+const ACL_NAMES: [&str; 2] = ["system.posix_acl_access", "system.posix_acl_default"];
+
+fn c_path(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains a NUL byte"))
+}
+
+// A size returned by one of the calls, or the error it set
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn size_result(size: isize) -> io::Result<usize> {
+    if size < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(size as usize)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn list_raw(path: &CString, buffer: &mut [u8]) -> io::Result<usize> {
+    size_result(unsafe {
+        libc::llistxattr(
+            path.as_ptr(),
+            buffer.as_mut_ptr() as *mut libc::c_char,
+            buffer.len(),
+        )
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn list_raw(path: &CString, buffer: &mut [u8]) -> io::Result<usize> {
+    size_result(unsafe {
+        libc::listxattr(
+            path.as_ptr(),
+            buffer.as_mut_ptr() as *mut libc::c_char,
+            buffer.len(),
+            libc::XATTR_NOFOLLOW,
+        )
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn value_size_raw(path: &CString, name: &CString) -> io::Result<usize> {
+    size_result(unsafe { libc::lgetxattr(path.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0) })
+}
+
+#[cfg(target_os = "macos")]
+fn value_size_raw(path: &CString, name: &CString) -> io::Result<usize> {
+    size_result(unsafe {
+        libc::getxattr(
+            path.as_ptr(),
+            name.as_ptr(),
+            std::ptr::null_mut(),
+            0,
+            0,
+            libc::XATTR_NOFOLLOW,
+        )
+    })
+}
+
+// Elsewhere the calls differ or are missing, so files are treated as
+// having no attributes
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn list_raw(_path: &CString, _buffer: &mut [u8]) -> io::Result<usize> {
+    Err(io::Error::from_raw_os_error(libc::ENOTSUP))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn value_size_raw(_path: &CString, _name: &CString) -> io::Result<usize> {
+    Err(io::Error::from_raw_os_error(libc::ENOTSUP))
+}
+
+// Whether the error only means the file system has no attributes
+fn unsupported(e: &io::Error) -> bool {
+    e.raw_os_error() == Some(libc::ENOTSUP)
+}
+
+// The names of the file's attributes. A file system without attributes
+// gives none rather than an error.
+pub fn names(path: &Path) -> io::Result<Vec<String>> {
+    let path = c_path(path)?;
+    loop {
+        let size = match list_raw(&path, &mut []) {
+            Ok(size) => size,
+            Err(e) if unsupported(&e) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        let mut buffer = vec![0; size];
+        let size = match list_raw(&path, &mut buffer) {
+            Ok(size) => size,
+            // The list grew between the two calls
+            Err(e) if e.raw_os_error() == Some(libc::ERANGE) => continue,
+            Err(e) => return Err(e),
+        };
+        buffer.truncate(size);
+        return Ok(buffer
+            .split(|&b| b == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| String::from_utf8_lossy(name).to_string())
+            .collect());
+    }
+}
+
+// The size in bytes of the value of attribute `name`
+pub fn value_size(path: &Path, name: &str) -> io::Result<usize> {
+    let path = c_path(path)?;
+    let name = CString::new(name)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "name contains a NUL byte"))?;
+    value_size_raw(&path, &name)
+}
+
+// Whether attribute `name` holds an ACL rather than data of its own
+pub fn is_acl(name: &str) -> bool {
+    ACL_NAMES.contains(&name)
+}
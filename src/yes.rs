@@ -0,0 +1,82 @@
+use cmds_lite::error;
+use std::env;
+use std::fs::File;
+use std::io::{self, IoSlice, Write};
+use std::mem::ManuallyDrop;
+use std::os::unix::io::FromRawFd;
+
+// The line is copied into a buffer of about this size, and several views
+// of it go out in each write call, so the pipe is kept full with very few
+// system calls
+const BUFFER_SIZE: usize = 128 * 1024;
+const SLICES_PER_WRITE: usize = 8;
+
+struct YesCommand {
+    buffer: Vec<u8>,
+}
+
+impl YesCommand {
+    fn new(words: &[String]) -> Self {
+        let mut line = if words.is_empty() {
+            b"y".to_vec()
+        } else {
+            words.join(" ").into_bytes()
+        };
+        line.push(b'\n');
+
+        // Whole lines only, so any position in the output maps to an offset
+        // into the buffer
+        let copies = (BUFFER_SIZE / line.len()).max(1);
+        YesCommand {
+            buffer: line.repeat(copies),
+        }
+    }
+
+    fn run<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        let mut offset = 0;
+        loop {
+            let mut slices = [IoSlice::new(&self.buffer); SLICES_PER_WRITE];
+            slices[0] = IoSlice::new(&self.buffer[offset..]);
+            let written = output.write_vectored(&slices)?;
+            if written == 0 {
+                return Err(io::Error::from(io::ErrorKind::WriteZero));
+            }
+            offset = (offset + written) % self.buffer.len();
+        }
+    }
+}
+
+pub fn run(words: &[String]) -> io::Result<()> {
+    let command = YesCommand::new(words);
+    // Write to the descriptor directly; std's stdout would scan every
+    // buffer for newlines
+    let mut stdout = ManuallyDrop::new(unsafe { File::from_raw_fd(1) });
+    command.run(&mut *stdout)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut words = Vec::new();
+    let mut end_of_options = false;
+
+    for arg in args.iter().skip(1) {
+        if end_of_options || !arg.starts_with('-') || arg.len() == 1 {
+            words.push(arg.clone());
+        } else if arg == "--" {
+            end_of_options = true;
+        } else if arg.starts_with("--") {
+            eprintln!("yes: unrecognized option '{}'", arg);
+            std::process::exit(1);
+        } else {
+            eprintln!("yes: invalid option -- '{}'", &arg[1..2]);
+            std::process::exit(1);
+        }
+    }
+
+    if let Err(e) = run(&words) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("yes: standard output: {}", error::message(&e));
+        }
+        std::process::exit(1);
+    }
+}
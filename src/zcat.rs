@@ -0,0 +1,10 @@
+use cmds_lite::compress::{self, CompressOptions};
+
+fn main() {
+    let options = CompressOptions {
+        decompress: true,
+        stdout: true,
+        ..CompressOptions::default()
+    };
+    compress::main("zcat", options)
+}